@@ -0,0 +1,37 @@
+// Puzzle 132 of Professor Layton and the Curious Village, solved with both searches and the
+// solution played back in the terminal: `cargo run --release --example layton1_puzzle_132`.
+use std::time::Duration;
+
+use astar_helper::{astar_state::AStarState, puzzles::sliding_block::{Piece, SlidingBlocks, Target}, traced::{frames::play, traced_astar::traced_astar}, untraced::untraced_astar::untraced_astar};
+
+fn puzzle() -> SlidingBlocks {
+    let piece = |label, size, position| Piece { label, size, position };
+    let pieces = [
+        piece('A', (2, 1), (0, 0)),
+        piece('B', (2, 2), (0, 1)),
+        piece('C', (2, 1), (0, 3)),
+        piece('D', (1, 1), (2, 0)),
+        piece('E', (1, 1), (2, 1)),
+        piece('F', (1, 1), (2, 2)),
+        piece('G', (1, 1), (2, 3)),
+        piece('H', (1, 2), (3, 0)),
+        piece('I', (1, 2), (3, 2)),
+        piece('J', (1, 1), (4, 0)),
+        piece('K', (1, 1), (4, 3))
+    ];
+
+    // The 2x2 block has to reach the right edge.
+    SlidingBlocks::new(5, 4, &pieces, Target { piece: 'B', position: (3, 1) }).unwrap()
+}
+
+fn main() {
+    let initial = puzzle();
+    let traced = traced_astar(initial.clone()).expect("the puzzle has a solution");
+
+    let render = |board: &SlidingBlocks| board.to_string();
+    play(&traced.frames_diff(initial.clone(), render), Duration::from_millis(150));
+    println!("traced_astar: {} moves, {} expansions", traced.cost, traced.iterations);
+
+    let untraced = untraced_astar(initial).expect("the puzzle has a solution");
+    println!("untraced_astar: {} moves, {} expansions", untraced.final_state.g(), untraced.iterations);
+}
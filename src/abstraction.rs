@@ -0,0 +1,293 @@
+use std::{cell::{Cell, RefCell}, collections::{HashMap, HashSet}, hash::Hash, marker::PhantomData};
+
+use crate::{open_list::OpenList, priority_queue::Insertion, traced::to_goal::Heuristic, untraced::state::UntracedState};
+
+// Heuristic for a concrete state space taken from the exact costs of an abstract one: a concrete
+// state is mapped to an abstract state by `abstraction`, and its estimate is the abstract cost
+// from there to an abstract goal. Admissible (and consistent) when every concrete path maps to an
+// abstract path that costs no more, e.g. grid cells mapped to the blocks of a coarser grid.
+//
+// Abstract costs are searched for on demand and memoized per abstract key: an abstract state not
+// solved yet triggers an abstract A* from it at query time, and every state on the path it finds
+// gets its exact cost. A search that runs out of its expansion bound returns the least f of its
+// open list instead, which is still a lower bound, but remembers nothing. Abstract states with no
+// path to a goal estimate usize::MAX.
+pub struct RefinementHeuristic<A, AK, F>
+where
+    AK: Clone + Eq + Hash
+{
+    abstraction: F,
+    max_expansions: usize,
+    costs: RefCell<HashMap<AK, usize>>,
+    searches: Cell<usize>,
+    expansions: Cell<usize>,
+    _marker: PhantomData<fn() -> A>
+}
+
+impl<A, AK, F> RefinementHeuristic<A, AK, F>
+where
+    A: UntracedState<AK>,
+    AK: Clone + Eq + Hash
+{
+    // Solves the abstract problem from `abstract_initial`, usually the image of the concrete
+    // initial state. The concrete searches then mostly hit the states on its path.
+    pub fn build(abstract_initial: A, abstraction: F) -> Self {
+        Self::build_bounded(abstract_initial, abstraction, usize::MAX)
+    }
+
+    // build with every abstract search, this first one included, bounded to `max_expansions`.
+    pub fn build_bounded(abstract_initial: A, abstraction: F, max_expansions: usize) -> Self {
+        let heuristic = RefinementHeuristic {
+            abstraction,
+            max_expansions,
+            costs: RefCell::new(HashMap::new()),
+            searches: Cell::new(0),
+            expansions: Cell::new(0),
+            _marker: PhantomData
+        };
+        heuristic.cost_to_go(abstract_initial);
+        heuristic
+    }
+
+    // Abstract keys with a memoized exact cost.
+    pub fn solved(&self) -> usize {
+        self.costs.borrow().len()
+    }
+
+    // Abstract searches run so far, and their expansions.
+    pub fn searches(&self) -> usize {
+        self.searches.get()
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions.get()
+    }
+
+    // Exact or lower bound abstract cost from `state` to a goal.
+    pub fn cost_to_go(&self, state: A) -> usize {
+        if let Some(&cost) = self.costs.borrow().get(&state.key()) {
+            return cost;
+        }
+        self.solve(state)
+    }
+
+    // Abstract A* from `start`. g is measured from the start, whatever the start's own g.
+    fn solve(&self, start: A) -> usize {
+        let start_key = start.key();
+        let start_g = start.g();
+        let mut open_list: OpenList<AK, A> = OpenList::new();
+        let mut closed: HashSet<AK> = HashSet::new();
+        // Parent and g of every key that made it into the open list.
+        let mut reached: HashMap<AK, (Option<AK>, usize)> = HashMap::new();
+        let mut expansions = 0;
+
+        reached.insert(start_key.clone(), (None, start_g));
+        open_list.insert(start_key.clone(), start);
+        self.searches.set(self.searches.get() + 1);
+
+        let cost = loop {
+            let Some(current) = open_list.extract_min() else {
+                self.costs.borrow_mut().insert(start_key, usize::MAX);
+                break usize::MAX;
+            };
+
+            if current.is_goal() {
+                let goal_g = current.g();
+                let mut costs = self.costs.borrow_mut();
+                let mut key = Some(current.key());
+                while let Some(on_path) = key {
+                    let (parent, g) = reached.remove(&on_path).expect("keys on the path were reached");
+                    costs.insert(on_path, goal_g - g);
+                    key = parent;
+                }
+                break goal_g - start_g;
+            }
+
+            if expansions == self.max_expansions {
+                break current.f().saturating_sub(start_g);
+            }
+            expansions += 1;
+
+            let current_key = current.key();
+            let successors = current.generate_successors();
+            closed.insert(current_key.clone());
+
+            for successor in successors {
+                let successor_key = successor.key();
+                if closed.contains(&successor_key) {
+                    continue;
+                }
+
+                let g = successor.g();
+                if !matches!(open_list.insert(successor_key.clone(), successor), Insertion::Rejected(_)) {
+                    reached.insert(successor_key, (Some(current_key.clone()), g));
+                }
+            }
+        };
+
+        self.expansions.set(self.expansions.get() + expansions);
+        cost
+    }
+}
+
+impl<S, A, AK, F> Heuristic<S> for RefinementHeuristic<A, AK, F>
+where
+    A: UntracedState<AK>,
+    AK: Clone + Eq + Hash,
+    F: Fn(&S) -> A
+{
+    fn estimate(&self, state: &S) -> usize {
+        self.cost_to_go((self.abstraction)(state))
+    }
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, grid::{heuristics::manhattan, map::GridMap, state::GridState}, traced::to_goal::traced_astar_to_goal};
+
+    // A cup open towards the start, with the goal behind its bottom. Manhattan distance leads the
+    // search into the cup, the coarse grid knows it's a dead end.
+    const TRAP: &str = "\
+        ................................\n\
+        ................................\n\
+        ................................\n\
+        ................................\n\
+        ....#######################.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ..........................#.....\n\
+        ....#######################.....\n\
+        ................................\n\
+        ................................\n\
+        ................................\n\
+        ................................";
+
+    const START: (usize, usize) = (2, 10);
+    const GOAL: (usize, usize) = (28, 10);
+
+    // The map coarsened into 2x2 blocks, four-way moves of cost 1 between neighboring blocks that
+    // share a free pair of cells. A concrete move changes the block by at most one step.
+    #[derive(Clone)]
+    struct Block<'a> {
+        map: &'a GridMap,
+        block: (usize, usize),
+        g: usize
+    }
+
+    fn block_of(cell: (usize, usize)) -> (usize, usize) {
+        (cell.0 / 2, cell.1 / 2)
+    }
+
+    impl AStarState<(usize, usize)> for Block<'_> {
+        fn key(&self) -> (usize, usize) {
+            self.block
+        }
+
+        fn h(&self) -> usize {
+            let goal = block_of(GOAL);
+            manhattan(self.block.0.abs_diff(goal.0), self.block.1.abs_diff(goal.1))
+        }
+
+        fn f(&self) -> usize {
+            self.g + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.block == block_of(GOAL)
+        }
+    }
+
+    impl UntracedState<(usize, usize)> for Block<'_> {
+        fn generate_successors(&self) -> Vec<Self> {
+            let (bx, by) = self.block;
+            let cells = (bx * 2..bx * 2 + 2).flat_map(|x| (by * 2..by * 2 + 2).map(move |y| (x, y)));
+            let mut blocks: Vec<(usize, usize)> = cells
+                .filter(|&(x, y)| self.map.in_bounds(x, y) && !self.map.is_blocked(x, y))
+                .flat_map(|(x, y)| self.map.neighbors(x, y))
+                .map(block_of)
+                .filter(|&block| block != self.block)
+                .collect();
+            blocks.sort_unstable();
+            blocks.dedup();
+
+            blocks.into_iter()
+                .map(|block| Block { map: self.map, block, g: self.g + 1 })
+                .collect()
+        }
+    }
+
+    fn expansions(map: &GridMap, heuristic: impl Fn(&GridState) -> usize) -> (usize, usize) {
+        let result = traced_astar_to_goal(
+            GridState::new(map, START),
+            GOAL,
+            |state: &GridState, _: &(usize, usize)| heuristic(state),
+            |state: &GridState, goal: &(usize, usize)| state.position() == *goal
+        ).unwrap();
+        (result.final_state.g(), result.iterations)
+    }
+
+    fn refinement<'a>(map: &'a GridMap) -> RefinementHeuristic<Block<'a>, (usize, usize), impl Fn(&GridState) -> Block<'a>> {
+        RefinementHeuristic::build(
+            Block { map, block: block_of(START), g: 0 },
+            move |state: &GridState| Block { map, block: block_of(state.position()), g: 0 }
+        )
+    }
+
+    #[test]
+    fn test_coarse_grid_beats_manhattan() {
+        let map = GridMap::from_ascii(TRAP);
+        let distance_to_goal = |state: &GridState| {
+            let (x, y) = state.position();
+            manhattan(x.abs_diff(GOAL.0), y.abs_diff(GOAL.1))
+        };
+        let heuristic = refinement(&map);
+
+        let (cost, plain) = expansions(&map, distance_to_goal);
+        let (refined_cost, refined) = expansions(&map, |state| distance_to_goal(state).max(heuristic.estimate(state)));
+
+        assert_eq!(cost, 38);
+        assert_eq!(refined_cost, cost);
+        assert!(refined * 3 < plain * 2, "{} vs {}", refined, plain);
+    }
+
+    #[test]
+    fn test_abstract_costs_are_memoized() {
+        let map = GridMap::from_ascii(TRAP);
+        let heuristic = refinement(&map);
+        let after_build = heuristic.searches();
+
+        // A cell on the abstract path, and then the cells of one block inside the cup, off the path.
+        assert_eq!(heuristic.estimate(&GridState::new(&map, START)), 19);
+        assert_eq!(heuristic.searches(), after_build);
+        for cell in [(8, 8), (9, 8), (8, 9), (9, 9)] {
+            heuristic.estimate(&GridState::new(&map, cell));
+        }
+        assert_eq!(heuristic.searches(), after_build + 1);
+    }
+
+    #[test]
+    fn test_bounded_search_stays_a_lower_bound() {
+        let map = GridMap::from_ascii(TRAP);
+        let bounded = RefinementHeuristic::build_bounded(
+            Block { map: &map, block: block_of(START), g: 0 },
+            |state: &GridState| Block { map: &map, block: block_of(state.position()), g: 0 },
+            3
+        );
+
+        let estimate = bounded.estimate(&GridState::new(&map, START));
+        assert!(estimate <= 19);
+        assert_eq!(bounded.solved(), 0);
+    }
+}
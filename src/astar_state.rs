@@ -1,4 +1,4 @@
-use std::hash::Hash;
+use std::{hash::Hash, rc::Rc, sync::Arc};
 
 pub trait AStarState<K>
 where
@@ -11,3 +11,139 @@ where
     fn g(&self) -> usize;
     fn is_goal(&self) -> bool;
 }
+
+// References and smart pointers to a state are states too. Only the owning pointers also forward
+// successor generation, since that needs somewhere to put the new states: see UntracedState.
+
+impl<K, T> AStarState<K> for &T
+where
+    K: Clone + Eq + Hash,
+    T: AStarState<K>
+{
+    fn key(&self) -> K {
+        (**self).key()
+    }
+
+    fn h(&self) -> usize {
+        (**self).h()
+    }
+
+    fn f(&self) -> usize {
+        (**self).f()
+    }
+
+    fn g(&self) -> usize {
+        (**self).g()
+    }
+
+    fn is_goal(&self) -> bool {
+        (**self).is_goal()
+    }
+}
+
+impl<K, T> AStarState<K> for Box<T>
+where
+    K: Clone + Eq + Hash,
+    T: AStarState<K>
+{
+    fn key(&self) -> K {
+        (**self).key()
+    }
+
+    fn h(&self) -> usize {
+        (**self).h()
+    }
+
+    fn f(&self) -> usize {
+        (**self).f()
+    }
+
+    fn g(&self) -> usize {
+        (**self).g()
+    }
+
+    fn is_goal(&self) -> bool {
+        (**self).is_goal()
+    }
+}
+
+impl<K, T> AStarState<K> for Rc<T>
+where
+    K: Clone + Eq + Hash,
+    T: AStarState<K>
+{
+    fn key(&self) -> K {
+        (**self).key()
+    }
+
+    fn h(&self) -> usize {
+        (**self).h()
+    }
+
+    fn f(&self) -> usize {
+        (**self).f()
+    }
+
+    fn g(&self) -> usize {
+        (**self).g()
+    }
+
+    fn is_goal(&self) -> bool {
+        (**self).is_goal()
+    }
+}
+
+impl<K, T> AStarState<K> for Arc<T>
+where
+    K: Clone + Eq + Hash,
+    T: AStarState<K>
+{
+    fn key(&self) -> K {
+        (**self).key()
+    }
+
+    fn h(&self) -> usize {
+        (**self).h()
+    }
+
+    fn f(&self) -> usize {
+        (**self).f()
+    }
+
+    fn g(&self) -> usize {
+        (**self).g()
+    }
+
+    fn is_goal(&self) -> bool {
+        (**self).is_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H};
+
+    fn assert_forwards<S: AStarState<usize>>(state: S, expected: &GraphState) {
+        assert_eq!(state.key(), expected.key());
+        assert_eq!(state.h(), expected.h());
+        assert_eq!(state.f(), expected.f());
+        assert_eq!(state.g(), expected.g());
+        assert_eq!(state.is_goal(), expected.is_goal());
+    }
+
+    #[test]
+    fn test_pointers_forward_every_method() {
+        for goal in [3, 7] {
+            let state = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 3, goal);
+
+            assert_forwards(&state, &state);
+            assert_forwards(Box::new(state.clone()), &state);
+            assert_forwards(Arc::new(state.clone()), &state);
+
+            let shared = Rc::new(state.clone());
+            assert_forwards(&shared, &state);
+            assert_forwards(shared, &state);
+        }
+    }
+}
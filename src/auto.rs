@@ -0,0 +1,324 @@
+use std::{hash::Hash, marker::PhantomData};
+
+use crate::{astar_state::AStarState, estimate::probe, expansion_limit::{LimitSpec, SharedBudget}, search_options::SearchOptions, traced::{beam::{iterative_beam_search_with, IterativeBeam, WidthGrowth}, result::TracedResult, state::TracedState, traced_astar::traced_astar_with_options}, untraced::state::UntracedState};
+
+// Random walks the size of the search space is predicted from, and their length. Their expansions
+// don't count against the budget.
+pub const PROBES: usize = 8;
+pub const PROBE_DEPTH: usize = 32;
+pub const PROBE_SEED: u64 = 0;
+// Weight of the first search of Quality::Optimal on spaces predicted too big for the budget.
+pub const ESCALATION_WEIGHT: f64 = 2.0;
+// Width the beam fallback of Quality::Any starts at, doubled on each retry.
+pub const BEAM_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub max_expansions: usize
+}
+
+impl Budget {
+    pub fn expansions(max_expansions: usize) -> Self {
+        Budget { max_expansions }
+    }
+
+    pub fn unlimited() -> Self {
+        Budget { max_expansions: usize::MAX }
+    }
+
+    // The budget as a pool for the searches of a batch, see `SearchOptions::with_shared_budget`.
+    pub fn shared(self) -> SharedBudget {
+        SharedBudget::new(self.max_expansions)
+    }
+}
+
+impl From<Budget> for SharedBudget {
+    fn from(budget: Budget) -> Self {
+        budget.shared()
+    }
+}
+
+impl From<Budget> for LimitSpec {
+    fn from(budget: Budget) -> Self {
+        LimitSpec::Absolute(budget.max_expansions)
+    }
+}
+
+// Only an absolute limit is a budget, a relative one is handed back.
+impl TryFrom<LimitSpec> for Budget {
+    type Error = LimitSpec;
+
+    fn try_from(limit: LimitSpec) -> Result<Self, Self::Error> {
+        match limit {
+            LimitSpec::Absolute(max_expansions) => Ok(Budget { max_expansions }),
+            relative => Err(relative)
+        }
+    }
+}
+
+// What the result of solve_auto has to be, for an admissible h (and a consistent one for Bounded).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Quality {
+    Optimal,
+    // Costs at most the factor times the optimum. Factors below 1 count as 1.
+    Bounded(f64),
+    // Any path to a goal.
+    Any
+}
+
+// The decision solve_auto took, in the order of the searches it ran.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Strategy {
+    // The space was predicted to fit the budget, so A* went first whatever the quality.
+    AStar,
+    // Weighted A* with the factor of Quality::Bounded as weight.
+    WeightedAStar { weight: f64 },
+    // Weighted A* for a first path, then A* with that path's cost as f bound, when budget remained.
+    WeightedThenOptimal { weight: f64 },
+    // Greedy best-first search on half the budget.
+    Greedy,
+    // Greedy ran out of its half, iterative beam search got the rest.
+    GreedyThenBeam
+}
+
+pub struct AutoOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // Always of the requested quality. None when the budget ran out first or no goal is reachable.
+    pub result: Option<TracedResult<S, K, C>>,
+    pub strategy_used: Strategy,
+    // Spent by the searches, the probe excluded.
+    pub expansions: usize,
+    // Nodes the probe predicted the space to hold, see `estimate::ProbeReport::predicted_nodes`.
+    pub predicted_nodes: f64
+}
+
+// Picks a search for the budget and quality: the probe predicts the nodes an A* would expand, and
+// spaces that fit the budget get plain A*. Bigger ones get weighted A* for Bounded, weighted A*
+// then a bounded A* for Optimal, and greedy with an iterative beam fallback for Any.
+// `strategy_used` says which.
+pub fn solve_auto<S, K, C>(initial_state: S, budget: Budget, quality: Quality) -> AutoOutcome<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let predicted_nodes = predicted_nodes(&initial_state);
+    let limit = budget.max_expansions;
+    let outcome = |result, strategy_used, expansions| AutoOutcome { result, strategy_used, expansions, predicted_nodes };
+
+    if predicted_nodes <= limit as f64 {
+        let (result, expansions) = capped(initial_state, SearchOptions::new(), limit);
+        return outcome(result, Strategy::AStar, expansions);
+    }
+
+    match quality {
+        Quality::Bounded(factor) => {
+            let weight = factor.max(1.0);
+            let (result, expansions) = capped(initial_state, SearchOptions::new().with_weight(weight), limit);
+            outcome(result, Strategy::WeightedAStar { weight }, expansions)
+        }
+        Quality::Optimal => {
+            let weight = ESCALATION_WEIGHT;
+            let strategy = Strategy::WeightedThenOptimal { weight };
+            let (incumbent, spent) = capped(initial_state.clone(), SearchOptions::new().with_weight(weight), limit);
+            let Some(incumbent) = incumbent else {
+                return outcome(None, strategy, spent);
+            };
+
+            // Nothing cheaper than the incumbent is pruned, so the bounded A* still finds the optimum.
+            let bound = SearchOptions::new().with_f_bound(incumbent.final_state.g());
+            let (result, expansions) = capped(initial_state, bound, limit - spent);
+            outcome(result, strategy, spent + expansions)
+        }
+        Quality::Any => {
+            let greedy_limit = limit / 2;
+            let greedy = traced_astar_with_options(initial_state.clone(), SearchOptions::new().with_greedy(true).with_max_expansions(greedy_limit));
+            let spent = greedy.stats.expansions;
+            if !greedy.budget_exhausted {
+                return outcome(greedy.result, Strategy::Greedy, spent);
+            }
+
+            let beam = iterative_beam_search_with(initial_state, &IterativeBeam::new(BEAM_WIDTH, WidthGrowth::Double).with_budget(limit - spent));
+            let expansions = beam.total_expansions();
+            outcome(beam.result, Strategy::GreedyThenBeam, spent + expansions)
+        }
+    }
+}
+
+// Nodes the probe solve_auto runs predicts a search from `state` to expand, e.g. for
+// `SearchOptions::with_node_estimator` with a `LimitSpec::Relative`.
+pub fn predicted_nodes<S, K, C>(state: &S) -> f64
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash
+{
+    probe(&Probed::new(state.clone()), PROBES, PROBE_DEPTH, PROBE_SEED).predicted_nodes
+}
+
+// traced_astar_with_options on at most `limit` expansions, and the expansions it took.
+fn capped<S, K, C>(initial_state: S, options: SearchOptions<S, K, C>, limit: usize) -> (Option<TracedResult<S, K, C>>, usize)
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let outcome = traced_astar_with_options(initial_state, options.with_max_expansions(limit));
+    (outcome.result, outcome.stats.expansions)
+}
+
+// A traced state seen as an untraced one, for the probe.
+struct Probed<S, C> {
+    state: S,
+    _marker: PhantomData<fn() -> C>
+}
+
+impl<S: Clone, C> Clone for Probed<S, C> {
+    fn clone(&self) -> Self {
+        Probed::new(self.state.clone())
+    }
+}
+
+impl<S, C> Probed<S, C> {
+    fn new(state: S) -> Self {
+        Probed { state, _marker: PhantomData }
+    }
+}
+
+impl<S, K, C> AStarState<K> for Probed<S, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.state.h()
+    }
+
+    fn f(&self) -> usize {
+        self.state.f()
+    }
+
+    fn g(&self) -> usize {
+        self.state.g()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.state.is_goal()
+    }
+}
+
+impl<S, K, C> UntracedState<K> for Probed<S, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn generate_successors(&self) -> Vec<Self> {
+        self.state.generate_traced_successors()
+            .into_iter()
+            .map(|(state, _)| Probed::new(state))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expansion_limit::ResolvedLimit, test_support::{Direction, SlidingPuzzle}, traced::replay::replay};
+
+    // 28 moves from solved, and the probe predicts far more nodes than any budget below.
+    fn hard() -> SlidingPuzzle {
+        SlidingPuzzle::scrambled(2, 60)
+    }
+
+    // Cost of the result after checking its path leads from `puzzle` to the goal.
+    fn checked_cost(puzzle: &SlidingPuzzle, outcome: AutoOutcome<SlidingPuzzle, [u8; 9], Direction>, budget: usize) -> Option<usize> {
+        assert!(outcome.expansions <= budget);
+        let result = outcome.result?;
+        let end = replay(puzzle, result.path.to_vec()).unwrap();
+        assert!(end.is_goal());
+        assert_eq!(end.g(), result.final_state.g());
+        Some(result.final_state.g())
+    }
+
+    #[test]
+    fn test_small_space_goes_straight_to_astar() {
+        // 10 moves from solved, predicted at under a thousand nodes.
+        let puzzle = SlidingPuzzle::scrambled(3, 20);
+        let outcome = solve_auto(puzzle.clone(), Budget::expansions(1000), Quality::Any);
+
+        assert!(outcome.predicted_nodes <= 1000.0);
+        assert_eq!(outcome.strategy_used, Strategy::AStar);
+        assert_eq!(checked_cost(&puzzle, outcome, 1000), Some(10));
+
+        let unlimited = solve_auto(hard(), Budget::unlimited(), Quality::Optimal);
+        assert_eq!(unlimited.strategy_used, Strategy::AStar);
+        assert_eq!(checked_cost(&hard(), unlimited, usize::MAX), Some(28));
+    }
+
+    #[test]
+    fn test_bounded_quality_uses_weighted_astar() {
+        let outcome = solve_auto(hard(), Budget::expansions(100_000), Quality::Bounded(1.5));
+
+        assert!(outcome.predicted_nodes > 100_000.0);
+        assert_eq!(outcome.strategy_used, Strategy::WeightedAStar { weight: 1.5 });
+        assert!(checked_cost(&hard(), outcome, 100_000).unwrap() * 2 <= 28 * 3);
+    }
+
+    #[test]
+    fn test_optimal_quality_escalates_while_budget_remains() {
+        let outcome = solve_auto(hard(), Budget::expansions(100_000), Quality::Optimal);
+        assert_eq!(outcome.strategy_used, Strategy::WeightedThenOptimal { weight: ESCALATION_WEIGHT });
+        assert_eq!(checked_cost(&hard(), outcome, 100_000), Some(28));
+
+        // Weighted A* fits in 500 expansions, proving the optimum doesn't: no result beats a
+        // suboptimal one.
+        let short = solve_auto(hard(), Budget::expansions(500), Quality::Optimal);
+        assert_eq!(short.strategy_used, Strategy::WeightedThenOptimal { weight: ESCALATION_WEIGHT });
+        assert_eq!(checked_cost(&hard(), short, 500), None);
+    }
+
+    #[test]
+    fn test_any_quality_falls_back_from_greedy_to_beam() {
+        let outcome = solve_auto(hard(), Budget::expansions(100_000), Quality::Any);
+        assert_eq!(outcome.strategy_used, Strategy::Greedy);
+        assert!(checked_cost(&hard(), outcome, 100_000).is_some());
+
+        // Greedy needs more than its 400 expansions here, the beam finds a path in the other 400.
+        let puzzle = SlidingPuzzle::scrambled(11, 60);
+        let outcome = solve_auto(puzzle.clone(), Budget::expansions(800), Quality::Any);
+        assert_eq!(outcome.strategy_used, Strategy::GreedyThenBeam);
+        assert!(checked_cost(&puzzle, outcome, 800).is_some());
+    }
+
+    #[test]
+    fn test_relative_limit_from_the_probe() {
+        let puzzle = SlidingPuzzle::scrambled(3, 20);
+        let predicted = predicted_nodes(&puzzle);
+        let options = SearchOptions::new().with_expansion_limit(LimitSpec::Relative { factor: 0.5 }).with_node_estimator(predicted_nodes);
+        let outcome = traced_astar_with_options(puzzle, options);
+
+        assert_eq!(outcome.stats.expansion_limit, Some(ResolvedLimit { limit: (predicted * 0.5).ceil() as usize, estimate: Some(predicted) }));
+        assert!(outcome.result.is_some());
+    }
+
+    #[test]
+    fn test_budget_conversions() {
+        let budget = Budget::expansions(300);
+        assert_eq!(LimitSpec::from(budget), LimitSpec::Absolute(300));
+        assert_eq!(Budget::try_from(LimitSpec::Absolute(300)), Ok(budget));
+        assert_eq!(Budget::try_from(LimitSpec::Relative { factor: 2.0 }), Err(LimitSpec::Relative { factor: 2.0 }));
+        assert_eq!(SharedBudget::from(budget).remaining(), 300);
+
+        let outcome = traced_astar_with_options(hard(), SearchOptions::new().with_expansion_limit(budget));
+        assert!(outcome.budget_exhausted);
+        assert_eq!(outcome.stats.expansions, 300);
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::{BTreeMap, HashMap}, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, priority_queue::{Insertion, PriorityQueue}};
+
+const DEFAULT_BAND_WIDTH: usize = 1;
+
+// Open list split into f-bands: entries with f in [i * width, (i + 1) * width) go to band i, each an
+// indexed heap of its own. Extractions only touch the lowest band, small enough to stay in cache
+// when the frontier is huge, instead of a heap over the whole frontier. Bands are created on the
+// first insert into them and dropped once empty. An improved entry moves to the band of its new f.
+pub struct BandedOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    band_width: usize,
+    bands: BTreeMap<usize, OpenList<K, V>>,
+    // Band of every queued key.
+    band_of_key: HashMap<K, usize>
+}
+
+impl<K, V> BandedOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    pub fn new() -> Self {
+        BandedOpenList {
+            band_width: DEFAULT_BAND_WIDTH,
+            bands: BTreeMap::new(),
+            band_of_key: HashMap::new()
+        }
+    }
+
+    // Range of f values per band, at least 1. Only meant to be set before the first insert.
+    pub fn with_band_width(mut self, width: usize) -> Self {
+        self.band_width = width.max(1);
+        self
+    }
+
+    pub fn band_width(&self) -> usize {
+        self.band_width
+    }
+
+    // Bands currently holding entries.
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.band_of_key.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.band_of_key.len()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.band_of_key.contains_key(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let band = self.band_of_key.get(key)?;
+        self.bands.get(band)?.get(key)
+    }
+
+    pub fn min(&self) -> Option<&V> {
+        self.bands.values().next()?.min()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        let band = value.f() / self.band_width;
+
+        let Some(&current_band) = self.band_of_key.get(&key) else {
+            self.band_of_key.insert(key.clone(), band);
+            self.bands.entry(band).or_default().insert(key, value);
+            return Insertion::Added;
+        };
+
+        if current_band == band {
+            return self.bands.get_mut(&band).expect("queued keys have a band").insert(key, value);
+        }
+
+        // Out of the current band, so the f differs: lower moves the entry, higher is rejected.
+        if band > current_band {
+            return Insertion::Rejected(value);
+        }
+
+        let previous = self.remove_from_band(current_band, &key);
+        self.band_of_key.insert(key.clone(), band);
+        self.bands.entry(band).or_default().insert(key, value);
+        Insertion::Improved(previous)
+    }
+
+    pub fn extract_min(&mut self) -> Option<V> {
+        let mut lowest = self.bands.first_entry()?;
+        let value = lowest.get_mut().extract_min().expect("empty bands are dropped");
+        if lowest.get().is_empty() {
+            lowest.remove();
+        }
+
+        self.band_of_key.remove(&value.key());
+        Some(value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let band = self.band_of_key.remove(key)?;
+        self.remove_from_band(band, key)
+    }
+
+    fn remove_from_band(&mut self, band: usize, key: &K) -> Option<V> {
+        let open_list = self.bands.get_mut(&band)?;
+        let value = open_list.remove(key);
+        if open_list.is_empty() {
+            self.bands.remove(&band);
+        }
+        value
+    }
+}
+
+impl<K, V> Default for BandedOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> PriorityQueue<K, V> for BandedOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        BandedOpenList::insert(self, key, value)
+    }
+
+    fn extract_min(&mut self) -> Option<V> {
+        BandedOpenList::extract_min(self)
+    }
+
+    fn len(&self) -> usize {
+        BandedOpenList::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mirrored_queue::MirroredQueue, rng::SplitMix64, test_support::{GraphState, SlidingPuzzle, TestState, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::{traced_astar, traced_astar_with_queue}, untraced::untraced_astar::untraced_astar_with_queue};
+
+    #[test]
+    fn test_extracts_across_bands_in_f_order() {
+        let mut open_list = BandedOpenList::new().with_band_width(10);
+
+        for (id, g_cost) in [(1, 35), (2, 4), (3, 12), (4, 9), (5, 30)] {
+            assert_eq!(open_list.insert(id, TestState { id, g_cost, h_cost: 1 }), Insertion::Added);
+        }
+
+        // Bands 0, 1 and 3 only.
+        assert_eq!(open_list.band_count(), 3);
+        assert_eq!(open_list.min().map(|state| state.id), Some(2));
+
+        let order: Vec<i32> = std::iter::from_fn(|| open_list.extract_min()).map(|state| state.id).collect();
+        assert_eq!(order, vec![2, 4, 3, 5, 1]);
+        assert_eq!(open_list.band_count(), 0);
+        assert!(open_list.is_empty());
+    }
+
+    #[test]
+    fn test_improve_moves_the_entry_to_its_new_band() {
+        let mut open_list = BandedOpenList::new().with_band_width(10);
+        let original = TestState { id: 1, g_cost: 24, h_cost: 1 }; // band 2
+        let same_band = TestState { id: 1, g_cost: 20, h_cost: 1 };
+        let lower_band = TestState { id: 1, g_cost: 3, h_cost: 1 }; // band 0
+
+        open_list.insert(1, original.clone());
+        assert_eq!(open_list.insert(1, same_band.clone()), Insertion::Improved(Some(original)));
+        assert_eq!(open_list.insert(1, lower_band.clone()), Insertion::Improved(Some(same_band)));
+        assert_eq!(open_list.band_count(), 1);
+
+        let worse = TestState { id: 1, g_cost: 50, h_cost: 1 };
+        assert_eq!(open_list.insert(1, worse.clone()), Insertion::Rejected(worse));
+        let tie = TestState { id: 1, g_cost: 3, h_cost: 1 };
+        assert_eq!(open_list.insert(1, tie.clone()), Insertion::Rejected(tie));
+
+        assert_eq!(open_list.len(), 1);
+        assert_eq!(open_list.get(&1), Some(&lower_band));
+        assert_eq!(open_list.extract_min(), Some(lower_band));
+        assert_eq!(open_list.extract_min(), None);
+    }
+
+    #[test]
+    fn test_remove_drops_empty_bands() {
+        let mut open_list = BandedOpenList::new();
+        open_list.insert(1, TestState { id: 1, g_cost: 5, h_cost: 0 });
+        open_list.insert(2, TestState { id: 2, g_cost: 7, h_cost: 0 });
+
+        assert_eq!(open_list.remove(&1).map(|state| state.id), Some(1));
+        assert_eq!(open_list.remove(&1), None);
+        assert!(!open_list.contains_key(&1));
+        assert_eq!(open_list.band_count(), 1);
+    }
+
+    #[test]
+    fn test_agrees_with_the_indexed_heap() {
+        let mut rng = SplitMix64::new(7);
+
+        for band_width in [1, 300, 20_000] {
+            let mut queue = MirroredQueue::new(OpenList::new(), BandedOpenList::new().with_band_width(band_width));
+
+            for step in 0..5_000 {
+                if rng.next_below(3) == 0 {
+                    queue.extract_min();
+                }
+                else {
+                    // Distinct f per key, so that both extract the same key.
+                    let id = rng.next_below(300) as i32;
+                    let g_cost = (step / 10 + rng.next_below(200)) * 300 + id as usize;
+                    queue.insert(id, TestState { id, g_cost, h_cost: 0 });
+                }
+            }
+            while queue.extract_min().is_some() {}
+        }
+    }
+
+    #[test]
+    fn test_as_search_backend() {
+        let untraced = untraced_astar_with_queue(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), BandedOpenList::new()).unwrap();
+        assert_eq!(untraced.final_state.g(), 9);
+
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 100);
+            let banded = traced_astar_with_queue(start.clone(), BandedOpenList::new().with_band_width(2)).unwrap();
+            assert_eq!(banded.final_state.g(), traced_astar(start).unwrap().final_state.g());
+        }
+    }
+}
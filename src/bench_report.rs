@@ -0,0 +1,230 @@
+use std::{fmt::Write, hash::Hash, time::{Duration, Instant}};
+
+use crate::traced::{outcome::SearchOutcome, result::TracedResult, state::TracedState};
+
+// Solves of each instance by each configuration in `run`. The report keeps their median and
+// fastest time.
+pub const REPETITIONS: usize = 3;
+
+// Columns of `Report::to_csv`, in order. Durations are in nanoseconds, and a column with nothing
+// to report is left empty.
+pub const CSV_HEADER: &str = "instance,config,cost,expansions,peak_open,median_ns,min_ns,repetitions";
+
+// What one solve reports. `peak_open`, the most states the open list held at once, stands for the
+// memory of the search, and is only known when the configuration returns a `SearchOutcome`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    // None when no goal was found.
+    pub cost: Option<usize>,
+    pub expansions: usize,
+    pub peak_open: Option<usize>
+}
+
+impl<S, K, C> From<Option<TracedResult<S, K, C>>> for Measurement
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn from(result: Option<TracedResult<S, K, C>>) -> Self {
+        Measurement {
+            cost: result.as_ref().map(|result| result.cost),
+            expansions: result.map_or(0, |result| result.iterations),
+            peak_open: None
+        }
+    }
+}
+
+impl<S, K, C> From<SearchOutcome<S, K, C>> for Measurement
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn from(outcome: SearchOutcome<S, K, C>) -> Self {
+        Measurement {
+            cost: outcome.result.map(|result| result.cost),
+            expansions: outcome.stats.expansions,
+            peak_open: Some(outcome.stats.peak_open)
+        }
+    }
+}
+
+// A named way of solving an instance: any search, with any options or backend, whose result
+// converts into a `Measurement`.
+pub struct BenchConfig<'a, S> {
+    name: String,
+    solve: Box<dyn Fn(S) -> Measurement + 'a>
+}
+
+impl<'a, S> BenchConfig<'a, S> {
+    // e.g. `BenchConfig::new("astar", |state| traced_astar(state).into())`.
+    pub fn new(name: impl Into<String>, solve: impl Fn(S) -> Measurement + 'a) -> Self {
+        BenchConfig { name: name.into(), solve: Box::new(solve) }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// One instance solved by one configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub instance: String,
+    pub config: String,
+    // Of the last repetition. Searches are deterministic, so every repetition reports the same.
+    pub measurement: Measurement,
+    pub median: Duration,
+    pub min: Duration,
+    pub repetitions: usize
+}
+
+// One row per instance and configuration, by instance and then by configuration in the order
+// they were given.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Report {
+    instances: Vec<String>,
+    configs: Vec<String>,
+    rows: Vec<Row>
+}
+
+impl Report {
+    pub fn instances(&self) -> &[String] {
+        &self.instances
+    }
+
+    pub fn configs(&self) -> &[String] {
+        &self.configs
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn get(&self, instance: &str, config: &str) -> Option<&Row> {
+        self.rows.iter().find(|row| row.instance == instance && row.config == config)
+    }
+
+    // A GitHub flavored markdown table, with a dash for a cost or peak that isn't known.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("| Instance | Config | Cost | Expansions | Peak open | Median | Min |\n");
+        markdown.push_str("|---|---|---:|---:|---:|---:|---:|\n");
+        for row in &self.rows {
+            let _ = writeln!(
+                markdown,
+                "| {} | {} | {} | {} | {} | {:?} | {:?} |",
+                row.instance.replace('|', "\\|"),
+                row.config.replace('|', "\\|"),
+                or_dash(row.measurement.cost),
+                row.measurement.expansions,
+                or_dash(row.measurement.peak_open),
+                row.median,
+                row.min
+            );
+        }
+        markdown
+    }
+
+    // CSV_HEADER and a line per row. Names are quoted when they hold a comma, quote or newline.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{CSV_HEADER}\n");
+        for row in &self.rows {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{}",
+                csv_field(&row.instance),
+                csv_field(&row.config),
+                or_empty(row.measurement.cost),
+                row.measurement.expansions,
+                or_empty(row.measurement.peak_open),
+                row.median.as_nanos(),
+                row.min.as_nanos(),
+                row.repetitions
+            );
+        }
+        csv
+    }
+}
+
+fn or_dash(value: Option<usize>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+fn or_empty(value: Option<usize>) -> String {
+    value.map_or_else(String::new, |value| value.to_string())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+// Solves every named instance with every configuration REPETITIONS times.
+pub fn run<S>(instances: &[(&str, S)], configs: &[BenchConfig<S>]) -> Report
+where
+    S: Clone
+{
+    run_with_repetitions(instances, configs, REPETITIONS)
+}
+
+// `run` with another number of repetitions, at least one. The instance is cloned before the clock
+// starts, so only the search is timed.
+pub fn run_with_repetitions<S>(instances: &[(&str, S)], configs: &[BenchConfig<S>], repetitions: usize) -> Report
+where
+    S: Clone
+{
+    let repetitions = repetitions.max(1);
+    let mut report = Report {
+        instances: instances.iter().map(|(name, _)| name.to_string()).collect(),
+        configs: configs.iter().map(|config| config.name.clone()).collect(),
+        rows: Vec::new()
+    };
+
+    for (instance, state) in instances {
+        for config in configs {
+            let mut times = Vec::with_capacity(repetitions);
+            let mut measurement = None;
+            for _ in 0..repetitions {
+                let state = state.clone();
+                let started = Instant::now();
+                measurement = Some((config.solve)(state));
+                times.push(started.elapsed());
+            }
+
+            times.sort();
+            report.rows.push(Row {
+                instance: instance.to_string(),
+                config: config.name.clone(),
+                measurement: measurement.expect("at least one repetition"),
+                median: times[times.len() / 2],
+                min: times[0],
+                repetitions
+            });
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::traced_astar};
+
+    #[test]
+    fn test_names_are_escaped() {
+        let instances = [("a,b", GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7))];
+        let configs = [BenchConfig::new("say \"a*\" | b", |state: GraphState| traced_astar(state).into())];
+        let report = run_with_repetitions(&instances, &configs, 0);
+        assert_eq!(report.rows()[0].repetitions, 1);
+
+        let csv = report.to_csv();
+        let line = csv.lines().nth(1).unwrap();
+        assert!(line.starts_with("\"a,b\",\"say \"\"a*\"\" | b\",9,"), "{line}");
+        assert!(line.ends_with(",1"));
+        assert!(report.to_markdown().contains("| a,b | say \"a*\" \\| b | 9 |"));
+    }
+}
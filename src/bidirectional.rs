@@ -0,0 +1,564 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}}};
+
+pub trait BidirectionalState<K, C>: TracedState<K, C>
+where
+    K: Clone + Eq + Hash
+{
+    // States that reach `self` in one move. Each predecessor's g() must be self.g() plus the cost
+    // of that move (so in the backward search g is the cost to the goal), and the change is the
+    // forward one, the change that turns the predecessor into `self`.
+    fn generate_predecessors(&self) -> Vec<(Self, C)>;
+
+    // Admissible and consistent estimate of the cost from `start` to `self`, the heuristic of the
+    // backward search. The forward search keeps using h().
+    fn reverse_h(&self, start: &Self) -> usize;
+}
+
+// How the frontiers are prioritized and when the search may stop with the incumbent U.
+// All three return optimal costs when both heuristics are admissible and consistent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BidirectionalStrategy {
+    // Both searches use the averaged potentials p = (h_own - h_other) / 2 and stop when
+    // top_fwd + top_bwd >= U, the bidirectional Dijkstra criterion on the reduced costs.
+    Classic,
+    // Both searches use f = g + h and stop when max(top_fwd, top_bwd) >= U.
+    MaxTops,
+    // MM's priority max(f, 2g), stopping when min(top_fwd, top_bwd) >= U.
+    MeetInMiddle
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BidirectionalStats {
+    pub forward_expansions: usize,
+    pub backward_expansions: usize,
+    // Closed states moved back to the open list because a cheaper path to them was found.
+    pub reopened: usize,
+    // Every expansion in order, with the direction that made it and the g of the state.
+    pub expansion_log: Vec<Expansion>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expansion {
+    pub direction: SearchDirection,
+    pub g: usize
+}
+
+pub struct BidirectionalOutcome<S, K, C>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>
+{
+    // The path runs from the start to the goal, and its cost and step costs are those of the
+    // stitched path. `final_state` is the goal state passed in, so its g() is that of the backward
+    // search root.
+    pub result: Option<TracedResult<S, K, C>>,
+    pub cost: Option<usize>,
+    pub stats: BidirectionalStats
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirection {
+    // From the start, over generate_traced_successors.
+    Forward,
+    // From the goal, over generate_predecessors.
+    Backward
+}
+
+type Node<S, K, C> = TracedStateWrapper<S, K, C>;
+
+struct Frontier<S, K, C>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>
+{
+    open: OpenList<K, Prioritized<Node<S, K, C>>>,
+    closed: HashMap<K, Node<S, K, C>>,
+    expansions: usize
+}
+
+impl<S, K, C> Frontier<S, K, C>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>
+{
+    fn new() -> Self {
+        Frontier {
+            open: OpenList::new(),
+            closed: HashMap::new(),
+            expansions: 0
+        }
+    }
+
+    fn top(&self) -> Option<usize> {
+        self.open.min().map(|node| node.f())
+    }
+
+    fn node(&self, key: &K) -> Option<&Node<S, K, C>> {
+        self.open.get(key)
+            .map(|node| &node.state)
+            .or_else(|| self.closed.get(key))
+    }
+
+    fn g(&self, key: &K) -> Option<usize> {
+        self.node(key).map(|node| node.state.g())
+    }
+
+    // Changes from the root of this frontier to `key` and the cost of each, root first.
+    fn path_to(&self, key: &K) -> (Vec<C>, Vec<usize>)
+    where
+        C: Clone
+    {
+        let mut path = Vec::new();
+        let mut step_costs = Vec::new();
+        let mut current = self.node(key);
+
+        while let Some(node) = current {
+            let prev = node.prev_key.as_ref().and_then(|prev_key| self.node(prev_key));
+            if let (Some(change), Some(prev)) = (&node.change, prev) {
+                path.push(change.clone());
+                step_costs.push(node.state.g() - prev.state.g());
+            }
+            current = prev;
+        }
+
+        path.reverse();
+        step_costs.reverse();
+        (path, step_costs)
+    }
+}
+
+pub fn bidirectional_astar<S, K, C>(start: S, goal: S) -> BidirectionalOutcome<S, K, C>
+where
+    S: BidirectionalState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    bidirectional_astar_with_strategy(start, goal, BidirectionalStrategy::Classic)
+}
+
+// MM: bidirectional_astar_with_strategy with BidirectionalStrategy::MeetInMiddle. Each direction
+// only expands states with max(f, 2g) below the optimal cost, so with consistent heuristics it
+// never expands a state with g above half the optimal cost, and the frontiers meet in the middle.
+pub fn mm_search<S, K, C>(start: S, goal: S) -> BidirectionalOutcome<S, K, C>
+where
+    S: BidirectionalState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    bidirectional_astar_with_strategy(start, goal, BidirectionalStrategy::MeetInMiddle)
+}
+
+// Searches forward from `start` and backward from `goal` until the strategy's termination test
+// proves the best meeting found so far optimal. The starting state's g() and the goal's g() are
+// both expected to be 0.
+pub fn bidirectional_astar_with_strategy<S, K, C>(start: S, goal: S, strategy: BidirectionalStrategy) -> BidirectionalOutcome<S, K, C>
+where
+    S: BidirectionalState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut forward: Frontier<S, K, C> = Frontier::new();
+    let mut backward: Frontier<S, K, C> = Frontier::new();
+    let mut stats = BidirectionalStats::default();
+
+    let start_key = start.key();
+    let goal_key = goal.key();
+    let priority = |state: &S, direction: SearchDirection| -> usize {
+        let g = state.g();
+        let (h_own, h_other) = match direction {
+            SearchDirection::Forward => (state.h(), state.reverse_h(&start)),
+            SearchDirection::Backward => (state.reverse_h(&start), state.h())
+        };
+
+        match strategy {
+            BidirectionalStrategy::Classic => (2 * g + h_own).saturating_sub(h_other),
+            BidirectionalStrategy::MaxTops => g + h_own,
+            BidirectionalStrategy::MeetInMiddle => (g + h_own).max(2 * g)
+        }
+    };
+
+    let start_priority = priority(&start, SearchDirection::Forward);
+    let goal_priority = priority(&goal, SearchDirection::Backward);
+    forward.open.insert(start_key.clone(), Prioritized::new(TracedStateWrapper::new(start.clone()), start_priority));
+    backward.open.insert(goal_key.clone(), Prioritized::new(TracedStateWrapper::new(goal), goal_priority));
+
+    let mut incumbent: Option<(usize, K)> = if start_key == goal_key {
+        Some((0, start_key))
+    }
+    else {
+        None
+    };
+
+    while let (Some(top_forward), Some(top_backward)) = (forward.top(), backward.top()) {
+        if let Some((best_cost, _)) = &incumbent {
+            let done = match strategy {
+                BidirectionalStrategy::Classic => top_forward + top_backward >= 2 * best_cost,
+                BidirectionalStrategy::MaxTops => top_forward.max(top_backward) >= *best_cost,
+                BidirectionalStrategy::MeetInMiddle => top_forward.min(top_backward) >= *best_cost
+            };
+
+            if done {
+                break;
+            }
+        }
+
+        let direction = if top_forward <= top_backward {
+            SearchDirection::Forward
+        }
+        else {
+            SearchDirection::Backward
+        };
+        let (own, other) = match direction {
+            SearchDirection::Forward => (&mut forward, &backward),
+            SearchDirection::Backward => (&mut backward, &forward)
+        };
+
+        let current = own.open.extract_min().expect("frontier top was checked").state;
+        let current_key = current.key();
+        own.expansions += 1;
+        stats.expansion_log.push(Expansion { direction, g: current.state.g() });
+
+        let neighbors = match direction {
+            SearchDirection::Forward => current.state.generate_traced_successors(),
+            SearchDirection::Backward => current.state.generate_predecessors()
+        };
+        let depth = current.depth + 1;
+
+        own.closed.insert(current_key.clone(), current);
+
+        for (neighbor, change) in neighbors {
+            let neighbor_key = neighbor.key();
+            let neighbor_g = neighbor.g();
+
+            if let Some(closed) = own.closed.get(&neighbor_key) {
+                if closed.state.g() <= neighbor_g {
+                    continue;
+                }
+                own.closed.remove(&neighbor_key);
+                stats.reopened += 1;
+            }
+
+            let neighbor_priority = priority(&neighbor, direction);
+            let node = TracedStateWrapper {
+                state: neighbor,
+                prev_key: Some(current_key.clone()),
+                change: Some(change),
+                depth,
+                g_override: None
+            };
+            own.open.insert(neighbor_key.clone(), Prioritized::new(node, neighbor_priority));
+
+            if let (Some(own_g), Some(other_g)) = (own.g(&neighbor_key), other.g(&neighbor_key)) {
+                let cost = own_g + other_g;
+                if incumbent.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                    incumbent = Some((cost, neighbor_key));
+                }
+            }
+        }
+    }
+
+    stats.forward_expansions = forward.expansions;
+    stats.backward_expansions = backward.expansions;
+
+    let Some((cost, meeting_key)) = incumbent else {
+        return BidirectionalOutcome {
+            result: None,
+            cost: None,
+            stats
+        };
+    };
+
+    // The backward half is walked from the goal towards the meeting point, so its changes and
+    // their costs come out in goal-to-meeting order and need reversing.
+    let (mut path, mut step_costs) = forward.path_to(&meeting_key);
+    let (mut backward_half, mut backward_costs) = backward.path_to(&meeting_key);
+    backward_half.reverse();
+    backward_costs.reverse();
+    path.extend(backward_half);
+    step_costs.extend(backward_costs);
+
+    let final_state = match backward.open.remove(&goal_key) {
+        Some(node) => node.state.state,
+        None => backward.closed.remove(&goal_key).expect("the goal is always in the backward frontier").state
+    };
+
+    BidirectionalOutcome {
+        result: Some(TracedResult::new(path, stats.forward_expansions + stats.backward_expansions, final_state).with_costs(cost, step_costs)),
+        cost: Some(cost),
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{Direction, GraphState, SlidingPuzzle, EIGHT_NODES, ZERO_H}, traced::{replay::replay as replay_changes, traced_astar::traced_astar}};
+    #[cfg(feature = "grid")]
+    use crate::{grid::{map::GridMap, movement::MovementModel, region::GoalRegion, state::GridState}, rng::SplitMix64, test_support::{random_free_cell, random_map}};
+
+    const STRATEGIES: [BidirectionalStrategy; 3] = [
+        BidirectionalStrategy::Classic,
+        BidirectionalStrategy::MaxTops,
+        BidirectionalStrategy::MeetInMiddle
+    ];
+
+    impl BidirectionalState<usize, usize> for GraphState {
+        fn generate_predecessors(&self) -> Vec<(Self, usize)> {
+            let mut predecessors = Vec::new();
+
+            for (source, edges) in self.edges.iter().enumerate() {
+                for &(target, cost) in edges.iter() {
+                    if target == self.node {
+                        let predecessor = GraphState {
+                            node: source,
+                            g_cost: self.g_cost + cost,
+                            ..self.clone()
+                        };
+                        predecessors.push((predecessor, self.node));
+                    }
+                }
+            }
+
+            predecessors
+        }
+
+        fn reverse_h(&self, _start: &Self) -> usize {
+            0
+        }
+    }
+
+    // Moves are invertible: a predecessor is a successor, reached by the opposite move.
+    impl BidirectionalState<[u8; 9], Direction> for SlidingPuzzle {
+        fn generate_predecessors(&self) -> Vec<(Self, Direction)> {
+            self.generate_traced_successors()
+                .into_iter()
+                .map(|(predecessor, direction)| (predecessor, direction.opposite()))
+                .collect()
+        }
+
+        fn reverse_h(&self, start: &Self) -> usize {
+            (1..9u8)
+                .map(|tile| {
+                    let here = self.tiles.iter().position(|&cell| cell == tile).unwrap();
+                    let there = start.tiles.iter().position(|&cell| cell == tile).unwrap();
+                    (here % 3).abs_diff(there % 3) + (here / 3).abs_diff(there / 3)
+                })
+                .sum()
+        }
+    }
+
+    const SPINE: usize = 20;
+    const TOOTH: usize = 20;
+
+    // A path from (0, 0) to (SPINE, 0) with a dead-end tooth of TOOTH cells hanging off every node
+    // of it, unit costs and h = 0 both ways. The states within g of an end grow with the square of
+    // g, so searching to half the cost from both ends visits about half as many as one search.
+    #[derive(Clone, Debug)]
+    struct Comb {
+        node: (usize, usize),
+        g: usize,
+        goal: (usize, usize)
+    }
+
+    impl Comb {
+        fn new(node: (usize, usize), goal: (usize, usize)) -> Self {
+            Comb { node, g: 0, goal }
+        }
+    }
+
+    impl AStarState<(usize, usize)> for Comb {
+        fn key(&self) -> (usize, usize) {
+            self.node
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.g
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.node == self.goal
+        }
+    }
+
+    impl TracedState<(usize, usize), (usize, usize)> for Comb {
+        fn generate_traced_successors(&self) -> Vec<(Self, (usize, usize))> {
+            let (spine, tooth) = self.node;
+            let mut nodes = vec![(spine, tooth + 1)];
+            if tooth > 0 {
+                nodes.push((spine, tooth - 1));
+            }
+            else {
+                nodes.extend([(spine.wrapping_sub(1), 0), (spine + 1, 0)]);
+            }
+
+            nodes.into_iter()
+                .filter(|&(spine, tooth)| spine <= SPINE && tooth <= TOOTH)
+                .map(|node| (Comb { node, g: self.g + 1, goal: self.goal }, node))
+                .collect()
+        }
+    }
+
+    impl BidirectionalState<(usize, usize), (usize, usize)> for Comb {
+        // Edges are undirected, and the change of a predecessor is the node it moves to: `self`.
+        fn generate_predecessors(&self) -> Vec<(Self, (usize, usize))> {
+            self.generate_traced_successors()
+                .into_iter()
+                .map(|(predecessor, _)| (predecessor, self.node))
+                .collect()
+        }
+
+        fn reverse_h(&self, _start: &Self) -> usize {
+            0
+        }
+    }
+
+    #[cfg(feature = "grid")]
+    fn replay(map: &GridMap, start: (usize, usize), path: &[Direction]) -> (usize, usize) {
+        path.iter().fold(start, |(x, y), direction| {
+            let next = direction.step(x, y).unwrap();
+            assert!(!map.is_blocked(next.0, next.1));
+            next
+        })
+    }
+
+    #[test]
+    fn test_graph_with_known_answer() {
+        for strategy in STRATEGIES {
+            let outcome = bidirectional_astar_with_strategy(
+                GraphState::new(EIGHT_NODES, ZERO_H, 0, 7),
+                GraphState::new(EIGHT_NODES, ZERO_H, 7, 7),
+                strategy
+            );
+
+            assert_eq!(outcome.cost, Some(9));
+            let result = outcome.result.unwrap();
+            assert_eq!(result.cost, 9);
+            let mut edge_costs = Vec::new();
+            let end = result.path.iter().fold(0, |node, next| {
+                let &(_, edge_cost) = EIGHT_NODES[node].iter().find(|(target, _)| *target == next).unwrap();
+                edge_costs.push(edge_cost);
+                next
+            });
+            assert_eq!(end, 7);
+            assert_eq!(result.step_costs, Some(edge_costs));
+        }
+    }
+
+    #[test]
+    fn test_sliding_puzzle_stitches_inverted_moves() {
+        let goal = SlidingPuzzle { tiles: SlidingPuzzle::SOLVED, g_cost: 0 };
+
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 200);
+            let optimal = traced_astar(start.clone()).unwrap().final_state.g();
+
+            for strategy in STRATEGIES {
+                let outcome = bidirectional_astar_with_strategy(start.clone(), goal.clone(), strategy);
+
+                assert_eq!(outcome.cost, Some(optimal));
+                assert!(outcome.stats.backward_expansions > 0);
+                let result = outcome.result.unwrap();
+                assert_eq!(result.cost, optimal);
+                assert_eq!(result.step_costs, Some(vec![1; optimal]));
+                let path = result.path.to_vec();
+                assert_eq!(path.len(), optimal);
+                assert!(replay_changes(&start, path).unwrap().is_goal());
+            }
+        }
+    }
+
+    #[test]
+    fn test_mm_meets_in_the_middle_of_a_comb() {
+        let goal = (SPINE, 0);
+        let unidirectional = traced_astar(Comb::new((0, 0), goal)).unwrap();
+        let outcome = mm_search(Comb::new((0, 0), goal), Comb::new(goal, goal));
+
+        assert_eq!(outcome.cost, Some(SPINE));
+        assert_eq!(unidirectional.final_state.g(), SPINE);
+        let result = outcome.result.unwrap();
+        assert_eq!(result.cost, SPINE);
+        let path = result.path.to_vec();
+        assert_eq!(path, (1..=SPINE).map(|spine| (spine, 0)).collect::<Vec<_>>());
+
+        let stats = outcome.stats;
+        assert_eq!(stats.expansion_log.len(), stats.forward_expansions + stats.backward_expansions);
+        for direction in [SearchDirection::Forward, SearchDirection::Backward] {
+            assert!(stats.expansion_log.iter().any(|expansion| expansion.direction == direction));
+        }
+        assert!(stats.expansion_log.iter().all(|expansion| 2 * expansion.g <= SPINE));
+        assert!(stats.expansion_log.len() * 3 < unidirectional.iterations * 2, "{} vs {}", stats.expansion_log.len(), unidirectional.iterations);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[(3, 1)], &[]];
+
+        for strategy in STRATEGIES {
+            let outcome = bidirectional_astar_with_strategy(
+                GraphState::new(SPLIT, &[0; 4], 0, 3),
+                GraphState::new(SPLIT, &[0; 4], 3, 3),
+                strategy
+            );
+
+            assert!(outcome.result.is_none());
+            assert_eq!(outcome.cost, None);
+        }
+    }
+
+    #[test]
+    fn test_start_is_goal() {
+        let outcome = bidirectional_astar(
+            GraphState::new(EIGHT_NODES, ZERO_H, 4, 4),
+            GraphState::new(EIGHT_NODES, ZERO_H, 4, 4)
+        );
+
+        assert_eq!(outcome.cost, Some(0));
+        let result = outcome.result.unwrap();
+        assert!(result.path.is_empty());
+        assert_eq!((result.cost, result.step_costs), (0, Some(Vec::new())));
+    }
+
+    #[test]
+    #[cfg(feature = "grid")]
+    fn test_strategies_match_unidirectional_on_random_grids() {
+        let mut rng = SplitMix64::new(2024);
+
+        for _ in 0..40 {
+            let map = random_map(&mut rng, 14, 11, 28, MovementModel::FourWay);
+            let start = random_free_cell(&mut rng, &map);
+            let goal = random_free_cell(&mut rng, &map);
+            let region = GoalRegion::Cell(goal.0, goal.1);
+
+            let expected = map.astar_to_region(start, &region).map(|result| result.final_state.g());
+            let reference = traced_astar(GridState::with_region(&map, start, &region)).map(|result| result.path.len());
+            assert_eq!(expected, reference);
+
+            for strategy in STRATEGIES {
+                let outcome = bidirectional_astar_with_strategy(
+                    GridState::with_region(&map, start, &region),
+                    GridState::with_region(&map, goal, &region),
+                    strategy
+                );
+
+                assert_eq!(outcome.cost, expected);
+                if let Some(result) = outcome.result {
+                    assert_eq!(Some(result.cost), expected);
+                    assert_eq!(Some(result.path.len()), expected);
+                    assert_eq!(replay(&map, start, &result.path.to_vec()), goal);
+                    assert_eq!(result.final_state.position(), goal);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{collections::BTreeMap, hash::Hash, mem};
+
+use crate::{astar_state::AStarState, closed_store::btree_bytes, priority_queue::{Insertion, PriorityQueue}};
+
+// Indexed binary heap like OpenList, with the index of each key in a BTreeMap instead of a hash
+// map: O(log n) lookups, and memory that grows with the entries. Keys are compared, never
+// hashed. They only need Hash for AStarState.
+pub struct BTreeOpenList<K, V> {
+    heap: Vec<(K, V)>,
+    index: BTreeMap<K, usize>
+}
+
+impl<K, V> BTreeOpenList<K, V> {
+    pub fn new() -> Self {
+        BTreeOpenList { heap: Vec::new(), index: BTreeMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn min(&self) -> Option<&V> {
+        self.heap.first().map(|(_, value)| value)
+    }
+
+    // Heap memory of the heap and the index, see ClosedStore::heap_bytes.
+    pub fn heap_bytes(&self) -> usize {
+        self.heap.capacity() * mem::size_of::<(K, V)>() + btree_bytes(self.index.len(), mem::size_of::<K>(), mem::size_of::<usize>())
+    }
+}
+
+impl<K, V> BTreeOpenList<K, V>
+where
+    K: Clone + Ord + Hash,
+    V: AStarState<K>
+{
+    pub fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        if let Some(&index) = self.index.get(&key) {
+            if value.f() < self.heap[index].1.f() {
+                let previous = mem::replace(&mut self.heap[index].1, value);
+                self.bubble_up(index);
+                Insertion::Improved(Some(previous))
+            }
+            else {
+                Insertion::Rejected(value)
+            }
+        }
+        else {
+            let index = self.heap.len();
+            self.index.insert(key.clone(), index);
+            self.heap.push((key, value));
+            self.bubble_up(index);
+            Insertion::Added
+        }
+    }
+
+    pub fn extract_min(&mut self) -> Option<V> {
+        if self.heap.is_empty() {
+            return None;
+        }
+
+        let last_index = self.heap.len() - 1;
+        self.swap(0, last_index);
+        let (key, value) = self.heap.pop()?;
+        self.index.remove(&key);
+
+        if !self.heap.is_empty() {
+            self.bubble_down(0);
+        }
+
+        Some(value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index.contains_key(key)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.heap.swap(i, j);
+
+        for index in [i, j] {
+            if let Some(slot) = self.index.get_mut(&self.heap[index].0) {
+                *slot = index;
+            }
+        }
+    }
+
+    fn bubble_up(&mut self, mut current: usize) {
+        while current > 0 {
+            let parent = (current - 1) / 2;
+            if self.heap[current].1.f() >= self.heap[parent].1.f() {
+                break;
+            }
+
+            self.swap(current, parent);
+            current = parent;
+        }
+    }
+
+    fn bubble_down(&mut self, mut current: usize) {
+        let len = self.heap.len();
+
+        loop {
+            let mut smallest = current;
+            for child in [2 * current + 1, 2 * current + 2] {
+                if child < len && self.heap[child].1.f() < self.heap[smallest].1.f() {
+                    smallest = child;
+                }
+            }
+
+            if smallest == current {
+                break;
+            }
+
+            self.swap(current, smallest);
+            current = smallest;
+        }
+    }
+}
+
+impl<K, V> Default for BTreeOpenList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> PriorityQueue<K, V> for BTreeOpenList<K, V>
+where
+    K: Clone + Ord + Hash,
+    V: AStarState<K>
+{
+    fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        BTreeOpenList::insert(self, key, value)
+    }
+
+    fn extract_min(&mut self) -> Option<V> {
+        BTreeOpenList::extract_min(self)
+    }
+
+    fn len(&self) -> usize {
+        BTreeOpenList::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mirrored_queue::MirroredQueue, open_list::OpenList, rng::SplitMix64, test_support::SlidingPuzzle, traced::traced_astar::{traced_astar, traced_astar_with_queue}};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Entry {
+        id: u32,
+        f: usize
+    }
+
+    impl AStarState<u32> for Entry {
+        fn key(&self) -> u32 {
+            self.id
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.f
+        }
+
+        fn g(&self) -> usize {
+            self.f
+        }
+
+        fn is_goal(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_the_indexed_heap() {
+        let mut rng = SplitMix64::new(7);
+        let mut queue = MirroredQueue::new(OpenList::new(), BTreeOpenList::new());
+
+        for step in 0..5_000 {
+            if rng.next_below(3) == 0 {
+                queue.extract_min();
+            }
+            else {
+                // Distinct f per key, so that both extract the same key.
+                let id = rng.next_below(300) as u32;
+                let f = (step / 10 + rng.next_below(200)) * 300 + id as usize;
+                queue.insert(id, Entry { id, f });
+            }
+        }
+        while queue.extract_min().is_some() {}
+    }
+
+    #[test]
+    fn test_as_search_backend() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let result = traced_astar_with_queue(start.clone(), BTreeOpenList::new()).unwrap();
+            assert_eq!(result.cost, traced_astar(start).unwrap().cost);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Projects how many nodes a search will store from its first expansions, so the open and closed
+// lists can be reserved once instead of growing step by step.
+
+// Expansions observed before projecting.
+pub(crate) const SAMPLE_EXPANSIONS: usize = 256;
+// Most entries reserved for either list, there being no memory budget to go by. Searches that get
+// past it grow as usual.
+pub(crate) const MAX_RESERVED: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Projection {
+    pub(crate) expansions: usize,
+    pub(crate) generated: usize
+}
+
+impl Projection {
+    // Open entries at the end of the search, at most.
+    pub(crate) fn frontier(&self) -> usize {
+        self.generated - self.expansions
+    }
+}
+
+// Nodes of a uniform tree with the given branching factor, not counting the root. Stops counting
+// past MAX_RESERVED, which also bounds the levels visited since each one adds a node at least.
+fn tree_size(branching: f64, depth: usize) -> f64 {
+    let mut total = 0.0;
+    let mut level = 1.0;
+
+    for _ in 0..depth.min(MAX_RESERVED) {
+        level *= branching;
+        total += level;
+        if total >= MAX_RESERVED as f64 {
+            break;
+        }
+    }
+
+    total
+}
+
+// The b for which b + b^2 + ... + b^depth = nodes.
+pub(crate) fn effective_branching_factor(nodes: usize, depth: usize) -> f64 {
+    if depth == 0 || nodes <= depth {
+        return 1.0;
+    }
+
+    let (mut low, mut high) = (1.0, nodes as f64);
+    for _ in 0..64 {
+        let middle = (low + high) / 2.0;
+        if tree_size(middle, depth) < nodes as f64 {
+            low = middle;
+        }
+        else {
+            high = middle;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+// Depth of the goal: the depth reached so far plus h in average edge costs so far, taken as 1
+// before any cost was paid.
+pub(crate) fn projected_depth(depth: usize, g: usize, h: usize) -> usize {
+    if g == 0 {
+        depth.saturating_add(h)
+    }
+    else {
+        depth.saturating_add(h.saturating_mul(depth).div_ceil(g))
+    }
+}
+
+// Extends the tree the search has grown so far to the projected depth of the goal, from the
+// counters after the sample and the depth, g and h of the node expanded last.
+pub(crate) fn project(expansions: usize, generated: usize, depth: usize, g: usize, h: usize) -> Projection {
+    let branching = effective_branching_factor(generated, depth.max(1));
+    let goal_depth = projected_depth(depth, g, h).max(depth);
+    let projected_generated = (tree_size(branching, goal_depth).min(MAX_RESERVED as f64) as usize).max(generated);
+
+    // Expansions keep the share of the generated nodes they had in the sample.
+    let share = expansions as f64 / generated.max(1) as f64;
+    let projected_expansions = ((projected_generated as f64 * share) as usize).clamp(expansions.min(projected_generated), projected_generated);
+
+    Projection {
+        expansions: projected_expansions,
+        generated: projected_generated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branching_factor_of_synthetic_trees() {
+        // 3 + 9 + 27 + 81 nodes down to depth 4.
+        assert!((effective_branching_factor(120, 4) - 3.0).abs() < 1e-6);
+        assert!((effective_branching_factor(2046, 10) - 2.0).abs() < 1e-6);
+
+        // A chain grows by one node per level, and anything thinner can't be told from it.
+        assert_eq!(effective_branching_factor(50, 50), 1.0);
+        assert_eq!(effective_branching_factor(10, 50), 1.0);
+        assert_eq!(effective_branching_factor(10, 0), 1.0);
+    }
+
+    #[test]
+    fn test_projected_depth() {
+        // 4 levels cost 8 so far, so h = 10 is 5 more levels.
+        assert_eq!(projected_depth(4, 8, 10), 9);
+        assert_eq!(projected_depth(4, 8, 0), 4);
+        assert_eq!(projected_depth(0, 0, 7), 7);
+        assert_eq!(projected_depth(3, 1, usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn test_projection_extends_the_observed_growth() {
+        // Binary tree explored to depth 4 with 30 nodes generated and 15 expanded; the goal is
+        // 4 more unit levels down.
+        let projection = project(15, 30, 4, 4, 4);
+        assert_eq!(projection.generated, 510);
+        assert_eq!(projection.expansions, 255);
+        assert_eq!(projection.frontier(), 255);
+
+        // Two nodes per level, halfway to the goal. A straight line would end at 400, the slight
+        // branching that fits the sample compounds to about 900.
+        let projection = project(100, 200, 100, 100, 100);
+        assert!((400..1000).contains(&projection.generated), "{:?}", projection);
+
+        // Nothing left to go keeps what was seen.
+        assert_eq!(project(15, 30, 4, 4, 0), Projection { expansions: 15, generated: 30 });
+    }
+
+    #[test]
+    fn test_projection_is_capped() {
+        let projection = project(256, 256 * 30, 3, 3, 1000);
+        assert_eq!(projection.generated, MAX_RESERVED);
+        assert!(projection.expansions <= MAX_RESERVED);
+
+        // However far the goal seems, the projection stops at the cap.
+        assert_eq!(project(256, 512, 100, 100, usize::MAX).generated, MAX_RESERVED);
+    }
+}
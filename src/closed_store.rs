@@ -0,0 +1,206 @@
+use std::{collections::{BTreeMap, HashMap}, hash::Hash, mem};
+
+// Entries a leaf of a std BTreeMap holds on average. Nodes have room for 11, and inserts in
+// random order leave them about 70% full.
+const BTREE_ENTRIES_PER_LEAF: usize = 8;
+const BTREE_NODE_CAPACITY: usize = 11;
+
+// Which map a search keeps its states in. `Hash` is the default. `BTree` trades O(log n)
+// lookups for a footprint without the load factor slack of a hash table, and needs `K: Ord`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MapImpl {
+    #[default]
+    Hash,
+    BTree
+}
+
+// The closed list of a search: expanded states by key. The bounds on the key come with each
+// implementation, so a BTree store asks for Ord and never for Hash.
+pub trait ClosedStore<K, V> {
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+    fn get(&self, key: &K) -> Option<&V>;
+    fn remove(&mut self, key: &K) -> Option<V>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    // Estimate of the heap memory held for the entries, their slots and the map's own
+    // bookkeeping, not counting anything the keys and values own.
+    fn heap_bytes(&self) -> usize;
+}
+
+// Lends a store to a search, so its footprint can be read once the search is done.
+impl<K, V, S> ClosedStore<K, V> for &mut S
+where
+    S: ClosedStore<K, V> + ?Sized
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        (**self).insert(key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        (**self).get(key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        (**self).remove(key)
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn heap_bytes(&self) -> usize {
+        (**self).heap_bytes()
+    }
+}
+
+pub struct HashClosedStore<K, V> {
+    map: HashMap<K, V>
+}
+
+impl<K, V> HashClosedStore<K, V> {
+    pub fn new() -> Self {
+        HashClosedStore { map: HashMap::new() }
+    }
+}
+
+impl<K, V> Default for HashClosedStore<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> ClosedStore<K, V> for HashClosedStore<K, V>
+where
+    K: Eq + Hash
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn heap_bytes(&self) -> usize {
+        hash_table_bytes(self.map.capacity(), mem::size_of::<(K, V)>())
+    }
+}
+
+pub struct BTreeClosedStore<K, V> {
+    map: BTreeMap<K, V>
+}
+
+impl<K, V> BTreeClosedStore<K, V> {
+    pub fn new() -> Self {
+        BTreeClosedStore { map: BTreeMap::new() }
+    }
+}
+
+impl<K, V> Default for BTreeClosedStore<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> ClosedStore<K, V> for BTreeClosedStore<K, V>
+where
+    K: Ord
+{
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn heap_bytes(&self) -> usize {
+        btree_bytes(self.map.len(), mem::size_of::<K>(), mem::size_of::<V>())
+    }
+}
+
+// Heap memory of a std hash table with room for `capacity` entries of `entry_size` bytes: a slot
+// and a control byte per bucket, with buckets a power of two kept at most 7/8 full.
+pub(crate) fn hash_table_bytes(capacity: usize, entry_size: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+
+    let buckets = if capacity < 8 { (capacity + 1).next_power_of_two() } else { (capacity * 8 / 7).next_power_of_two() };
+    buckets * (entry_size + 1)
+}
+
+// Estimated heap memory of a std BTreeMap of `len` entries. Every node has room for the keys and
+// values of a full one plus a parent link and lengths, and inner nodes have edges on top, one
+// inner node for every few leaves.
+pub(crate) fn btree_bytes(len: usize, key_size: usize, value_size: usize) -> usize {
+    let leaves = len.div_ceil(BTREE_ENTRIES_PER_LEAF);
+    let inner = leaves.saturating_sub(1).div_ceil(BTREE_ENTRIES_PER_LEAF);
+    let leaf_bytes = BTREE_NODE_CAPACITY * (key_size + value_size) + mem::size_of::<usize>() + 4;
+    let inner_bytes = leaf_bytes + (BTREE_NODE_CAPACITY + 1) * mem::size_of::<usize>();
+    leaves * leaf_bytes + inner * inner_bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(store: &mut dyn ClosedStore<u64, [u64; 4]>, count: u64) {
+        for key in 0..count {
+            store.insert(key.wrapping_mul(0x9e37_79b9_7f4a_7c15), [key; 4]);
+        }
+    }
+
+    #[test]
+    fn test_stores_agree() {
+        let (mut hash, mut btree) = (HashClosedStore::new(), BTreeClosedStore::new());
+        for store in [&mut hash as &mut dyn ClosedStore<u64, [u64; 4]>, &mut btree] {
+            fill(store, 100);
+            assert_eq!(store.insert(0, [7; 4]), Some([0; 4]));
+            assert_eq!(store.remove(&0x9e37_79b9_7f4a_7c15), Some([1; 4]));
+            assert!(!store.contains_key(&0x9e37_79b9_7f4a_7c15));
+            assert_eq!(store.get(&0), Some(&[7; 4]));
+            assert_eq!(store.len(), 99);
+        }
+    }
+
+    #[test]
+    fn test_footprints() {
+        let (mut hash, mut btree) = (HashClosedStore::new(), BTreeClosedStore::new());
+        assert_eq!((hash.heap_bytes(), btree.heap_bytes()), (0, 0));
+
+        // One entry past a full table doubles the buckets, leaving the hash map a bit over half
+        // full, while the B-tree grows with its entries.
+        fill(&mut hash, 1793);
+        fill(&mut btree, 1793);
+        let entry = mem::size_of::<(u64, [u64; 4])>();
+        assert_eq!(hash.heap_bytes(), 4096 * (entry + 1));
+        assert!(btree.heap_bytes() < hash.heap_bytes() * 3 / 4, "{} vs {}", btree.heap_bytes(), hash.heap_bytes());
+        assert!(btree.heap_bytes() > 1793 * entry);
+    }
+}
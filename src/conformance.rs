@@ -0,0 +1,247 @@
+use std::{fmt::Debug, hash::Hash};
+
+use crate::{banded_open_list::BandedOpenList, btree_open_list::BTreeOpenList, closed_store::{BTreeClosedStore, MapImpl}, open_list::OpenList, lazy_open_list::LazyOpenList, search_options::SearchOptions, traced::{replay::replay, result::TracedResult, state::TracedState, traced_astar::{traced_astar, traced_astar_with_map_impl, traced_astar_with_options, traced_astar_with_queue, traced_astar_with_stores}, uniform_cost::uniform_cost_search}};
+#[cfg(feature = "bidirectional")]
+use crate::bidirectional::{bidirectional_astar_with_strategy, BidirectionalState, BidirectionalStrategy};
+#[cfg(feature = "ida")]
+use crate::{traced::{ida_star::{ida_star, ida_star_with_table}, rbfs::rbfs}, transposition::{ReplacementPolicy, TranspositionTable}};
+#[cfg(feature = "dfbb")]
+use crate::traced::dfbb::{dfbb, dfbb_with_table};
+#[cfg(feature = "fringe")]
+use crate::traced::fringe::fringe_search;
+
+// Runs every optimal search over each instance and panics unless all of them return the given
+// optimal cost with a path that replays to a goal of that cost. Bounded searches must find the
+// optimum when the bound allows it and return nothing when it doesn't, and satisficing ones must
+// return valid paths no cheaper than the optimum. Heuristics must be admissible and consistent.
+pub fn run_all<S, K, C>(instances: Vec<S>, optimal_costs: Vec<usize>)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq + Debug
+{
+    assert_eq!(instances.len(), optimal_costs.len(), "one optimal cost per instance");
+
+    for (index, (instance, optimal_cost)) in instances.into_iter().zip(optimal_costs).enumerate() {
+        let check = |algorithm: &str, result: Option<TracedResult<S, K, C>>| {
+            let result = result.unwrap_or_else(|| panic!("{} found no solution for instance {}", algorithm, index));
+            assert_eq!(result.cost, optimal_cost, "{} returned a suboptimal cost for instance {}", algorithm, index);
+            check_replay(algorithm, index, &instance, &result);
+        };
+
+        check("traced_astar", traced_astar(instance.clone()));
+        check("traced_astar with LazyOpenList", traced_astar_with_queue(instance.clone(), LazyOpenList::new()));
+        check("traced_astar with BandedOpenList", traced_astar_with_queue(instance.clone(), BandedOpenList::new().with_band_width(4)));
+        let verified = traced_astar_with_options(instance.clone(), SearchOptions::new().with_cost_verification(true));
+        assert_eq!(verified.error, None, "traced_astar_with_options failed its own cost verification on instance {}", index);
+        check("traced_astar_with_options", verified.result);
+
+        check("uniform_cost_search", uniform_cost_search(instance.clone()));
+        #[cfg(feature = "fringe")]
+        check("fringe_search", fringe_search(instance.clone()));
+
+        let bounded = traced_astar_with_options(instance.clone(), SearchOptions::new().with_f_bound(optimal_cost));
+        check("traced_astar_with_options at the optimal f bound", bounded.result);
+
+        if optimal_cost > 0 {
+            let below = traced_astar_with_options(instance.clone(), SearchOptions::new().with_f_bound(optimal_cost - 1));
+            assert!(below.result.is_none(), "f bound below the optimum still found a solution for instance {}", index);
+        }
+
+        let greedy = traced_astar_with_options(instance.clone(), SearchOptions::new().with_greedy(true)).result
+            .unwrap_or_else(|| panic!("greedy search found no solution for instance {}", index));
+        assert!(greedy.cost >= optimal_cost, "greedy search beat the optimum of instance {}", index);
+        check_replay("greedy search", index, &instance, &greedy);
+    }
+}
+
+// run_all for the open and closed lists that compare keys instead of hashing them, alone and
+// together, which needs keys that are Ord.
+pub fn run_btree_backends<S, K, C>(instances: Vec<S>, optimal_costs: Vec<usize>)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Ord + Hash,
+    C: Clone + PartialEq + Debug
+{
+    assert_eq!(instances.len(), optimal_costs.len(), "one optimal cost per instance");
+
+    for (index, (instance, optimal_cost)) in instances.into_iter().zip(optimal_costs).enumerate() {
+        let check = |algorithm: &str, result: Option<TracedResult<S, K, C>>| {
+            let result = result.unwrap_or_else(|| panic!("{} found no solution for instance {}", algorithm, index));
+            assert_eq!(result.cost, optimal_cost, "{} returned a suboptimal cost for instance {}", algorithm, index);
+            check_replay(algorithm, index, &instance, &result);
+        };
+
+        check("traced_astar with BTreeOpenList", traced_astar_with_queue(instance.clone(), BTreeOpenList::new()));
+        check("traced_astar with BTreeClosedStore", traced_astar_with_stores(instance.clone(), OpenList::new(), BTreeClosedStore::new()));
+        check("traced_astar_with_map_impl (BTree)", traced_astar_with_map_impl(instance.clone(), MapImpl::BTree));
+    }
+}
+
+// run_all for the depth-first searches. They re-expand states along every path, so keep the
+// instances small enough for that (the sliding puzzle is fine, the Layton puzzle is not). dfbb
+// starts from an incumbent just above the optimum, as a known solution would set it.
+#[cfg(feature = "ida")]
+pub fn run_depth_first<S, K, C>(instances: Vec<S>, optimal_costs: Vec<usize>)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq + Debug
+{
+    assert_eq!(instances.len(), optimal_costs.len(), "one optimal cost per instance");
+
+    for (index, (instance, optimal_cost)) in instances.into_iter().zip(optimal_costs).enumerate() {
+        let check = |algorithm: &str, result: Option<TracedResult<S, K, C>>| {
+            let result = result.unwrap_or_else(|| panic!("{} found no solution for instance {}", algorithm, index));
+            assert_eq!(result.cost, optimal_cost, "{} returned a suboptimal cost for instance {}", algorithm, index);
+            check_replay(algorithm, index, &instance, &result);
+        };
+
+        check("ida_star", ida_star(instance.clone()));
+        check("rbfs", rbfs(instance.clone()));
+        #[cfg(feature = "dfbb")]
+        check("dfbb", dfbb(instance.clone(), Some(optimal_cost + 1)));
+        for policy in [ReplacementPolicy::AlwaysReplace, ReplacementPolicy::DepthPreferred] {
+            let mut table = TranspositionTable::new(1024, policy);
+            check("ida_star_with_table", ida_star_with_table(instance.clone(), &mut table).result);
+            #[cfg(feature = "dfbb")]
+            {
+                let mut table = TranspositionTable::new(1024, policy);
+                check("dfbb_with_table", dfbb_with_table(instance.clone(), Some(optimal_cost + 1), &mut table));
+            }
+        }
+    }
+}
+
+// run_all for the bidirectional searches, which need the goal state of every instance.
+#[cfg(feature = "bidirectional")]
+pub fn run_bidirectional<S, K, C>(instances: Vec<(S, S)>, optimal_costs: Vec<usize>)
+where
+    S: BidirectionalState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq + Debug
+{
+    assert_eq!(instances.len(), optimal_costs.len(), "one optimal cost per instance");
+
+    for (index, ((start, goal), optimal_cost)) in instances.into_iter().zip(optimal_costs).enumerate() {
+        for strategy in [BidirectionalStrategy::Classic, BidirectionalStrategy::MaxTops, BidirectionalStrategy::MeetInMiddle] {
+            let algorithm = format!("bidirectional_astar ({:?})", strategy);
+            let outcome = bidirectional_astar_with_strategy(start.clone(), goal.clone(), strategy);
+
+            assert_eq!(outcome.cost, Some(optimal_cost), "{} returned a wrong cost for instance {}", algorithm, index);
+            let result = outcome.result.unwrap();
+            assert_eq!(result.cost, optimal_cost, "{} returned a result of the wrong cost for instance {}", algorithm, index);
+            let reached = replay(&start, &result.path).unwrap_or_else(|error| {
+                panic!("{} returned a path that doesn't replay for instance {}: {:?}", algorithm, index, error)
+            });
+            assert_eq!(reached.g(), optimal_cost, "{} returned a path of the wrong cost for instance {}", algorithm, index);
+        }
+    }
+}
+
+fn check_replay<S, K, C>(algorithm: &str, index: usize, instance: &S, result: &TracedResult<S, K, C>)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq + Debug
+{
+    match replay(instance, &result.path) {
+        Ok(reached) => {
+            assert!(reached.is_goal(), "{} returned a path that doesn't reach a goal for instance {}", algorithm, index);
+            assert_eq!(reached.g(), result.cost, "{} returned a path whose cost doesn't match its result for instance {}", algorithm, index);
+        }
+        Err(error) => panic!("{} returned a path that doesn't replay for instance {}: {:?}", algorithm, index, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H}, traced::replay::ReplayError};
+
+    #[test]
+    fn test_graph_instances() {
+        run_all(
+            vec![
+                GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7),
+                GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 7)
+            ],
+            vec![9, 0]
+        );
+        run_btree_backends(vec![GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)], vec![9]);
+        #[cfg(feature = "ida")]
+        run_depth_first(vec![GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)], vec![9]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "grid", feature = "bidirectional", feature = "ida"))]
+    fn test_grid_instances() {
+        use crate::{grid::{map::GridMap, region::GoalRegion, state::GridState}, rng::SplitMix64};
+
+        let map = GridMap::from_ascii("
+            ..........
+            .####.###.
+            .#......#.
+            .#.####.#.
+            ...#......
+        ");
+        let goals = [(9, 4), (4, 2), (0, 4), (9, 0)];
+        let regions: Vec<GoalRegion> = goals.iter().map(|&(x, y)| GoalRegion::Cell(x, y)).collect();
+
+        let mut rng = SplitMix64::new(5);
+        let mut pairs = Vec::new();
+        let mut costs = Vec::new();
+        for (region, &goal) in regions.iter().zip(goals.iter()) {
+            let start = loop {
+                let cell = (rng.next_below(map.width()), rng.next_below(map.height()));
+                if !map.is_blocked(cell.0, cell.1) && map.astar_to_region(cell, region).is_some() {
+                    break cell;
+                }
+            };
+
+            costs.push(map.astar_to_region(start, region).unwrap().final_state.g());
+            pairs.push((GridState::with_region(&map, start, region), GridState::with_region(&map, goal, region)));
+        }
+
+        run_all(pairs.iter().map(|(start, _)| start.clone()).collect(), costs.clone());
+        run_depth_first(pairs.iter().map(|(start, _)| start.clone()).collect(), costs.clone());
+        run_bidirectional(pairs, costs);
+    }
+
+    #[test]
+    fn test_sliding_puzzle_instances() {
+        let instances: Vec<SlidingPuzzle> = (0..3).map(|seed| SlidingPuzzle::scrambled(seed, 30)).collect();
+        let costs: Vec<usize> = instances.iter().map(|instance| traced_astar(instance.clone()).unwrap().final_state.g()).collect();
+
+        run_all(instances.clone(), costs.clone());
+        run_btree_backends(instances.clone(), costs.clone());
+        #[cfg(feature = "ida")]
+        run_depth_first(instances, costs);
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_paths() {
+        let start = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let optimal = traced_astar(start.clone()).unwrap();
+
+        assert!(replay(&start, &optimal.path).is_ok());
+        assert_eq!(replay(&start, vec![7]).err(), Some(ReplayError::InvalidChange { step: 0 }));
+        assert_eq!(replay(&start, optimal.path.to_vec().into_iter().take(1)).err(), Some(ReplayError::NotAGoal));
+    }
+
+    #[test]
+    #[cfg(feature = "grid")]
+    fn test_replay_accepts_grid_moves() {
+        use crate::grid::{map::GridMap, region::GoalRegion, state::{Direction, GridState}};
+
+        let map = GridMap::from_ascii("..");
+        let region = GoalRegion::Cell(1, 0);
+        assert!(replay(&GridState::with_region(&map, (0, 0), &region), vec![Direction::Right]).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "suboptimal")]
+    fn test_wrong_optimum_is_reported() {
+        run_all(vec![GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)], vec![8]);
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::{Debug, Display};
+
+// Expansions after which a search where every edge cost 0 so far is taken to have forgotten its
+// edge costs.
+pub const FORGOTTEN_COSTS_EXPANSIONS: usize = 32;
+
+// Inclusive range of edge costs a domain declares, see SearchOptions::with_edge_cost_bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeCostBounds {
+    pub min: usize,
+    pub max: usize
+}
+
+// Edge costs are taken as the g of a successor minus the g of its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EdgeCostError<K, C> {
+    OutOfBounds { parent: K, change: C, cost: usize, bounds: EdgeCostBounds },
+    // The successor has a lower g than its parent.
+    Negative { parent: K, change: C, parent_g: usize, successor_g: usize },
+    // Every edge of the first `expansions` expansions cost 0.
+    ForgottenCosts { expansions: usize, edges: usize }
+}
+
+impl<K, C> Display for EdgeCostError<K, C>
+where
+    K: Debug,
+    C: Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeCostError::OutOfBounds { parent, change, cost, bounds } => {
+                write!(f, "edge {:?} from {:?} costs {}, outside {}..={}", change, parent, cost, bounds.min, bounds.max)
+            }
+            EdgeCostError::Negative { parent, change, parent_g, successor_g } => {
+                write!(f, "edge {:?} from {:?} lowers g from {} to {}", change, parent, parent_g, successor_g)
+            }
+            EdgeCostError::ForgottenCosts { expansions, edges } => {
+                write!(f, "all {} edges of the first {} expansions cost 0, do the successors add their edge cost to g?", edges, expansions)
+            }
+        }
+    }
+}
+
+impl<K, C> std::error::Error for EdgeCostError<K, C>
+where
+    K: Debug,
+    C: Debug
+{}
+
+// Checks the edges of every expansion of a search against the declared bounds.
+pub(crate) struct EdgeCostValidator {
+    bounds: EdgeCostBounds,
+    edges: usize,
+    paid_edges: usize
+}
+
+impl EdgeCostValidator {
+    pub(crate) fn new(bounds: EdgeCostBounds) -> Self {
+        EdgeCostValidator {
+            bounds,
+            edges: 0,
+            paid_edges: 0
+        }
+    }
+
+    // Returns the number of zero cost edges among the successors, which are only an error when
+    // the bounds exclude them. `expansions` counts this one.
+    pub(crate) fn check<'c, K, C>(&mut self, parent: &K, parent_g: usize, successors: impl IntoIterator<Item = (usize, &'c C)>, expansions: usize) -> Result<usize, EdgeCostError<K, C>>
+    where
+        K: Clone,
+        C: Clone + 'c
+    {
+        let mut zero_cost_edges = 0;
+
+        for (successor_g, change) in successors {
+            self.edges += 1;
+
+            let Some(cost) = successor_g.checked_sub(parent_g) else {
+                return Err(EdgeCostError::Negative { parent: parent.clone(), change: change.clone(), parent_g, successor_g });
+            };
+
+            if cost == 0 {
+                zero_cost_edges += 1;
+            }
+            else {
+                self.paid_edges += 1;
+            }
+
+            if cost < self.bounds.min || cost > self.bounds.max {
+                // Without a single paid edge so far, the costs were most likely never added.
+                if self.paid_edges == 0 {
+                    return Err(EdgeCostError::ForgottenCosts { expansions, edges: self.edges });
+                }
+                return Err(EdgeCostError::OutOfBounds { parent: parent.clone(), change: change.clone(), cost, bounds: self.bounds });
+            }
+        }
+
+        if expansions == FORGOTTEN_COSTS_EXPANSIONS && self.paid_edges == 0 && self.edges > 0 {
+            return Err(EdgeCostError::ForgottenCosts { expansions, edges: self.edges });
+        }
+
+        Ok(zero_cost_edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, search_options::SearchOptions, traced::{state::TracedState, traced_astar::traced_astar_with_options}};
+
+    const LENGTH: usize = 100;
+
+    // Walk along a line up to LENGTH. The step from a position with g so far leads to next_g(position, g).
+    #[derive(Clone)]
+    struct Walk {
+        position: usize,
+        g: usize,
+        next_g: fn(usize, usize) -> usize
+    }
+
+    impl AStarState<usize> for Walk {
+        fn key(&self) -> usize {
+            self.position
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.g
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.position == LENGTH
+        }
+    }
+
+    impl TracedState<usize, usize> for Walk {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            let next = Walk {
+                position: self.position + 1,
+                g: (self.next_g)(self.position, self.g),
+                next_g: self.next_g
+            };
+            vec![(next, self.position + 1)]
+        }
+    }
+
+    fn walk(next_g: fn(usize, usize) -> usize, min: usize, max: usize) -> (Option<usize>, usize, Option<EdgeCostError<usize, usize>>) {
+        let options = SearchOptions::new().with_edge_cost_bounds(min, max);
+        let outcome = traced_astar_with_options(Walk { position: 0, g: 0, next_g }, options);
+        (outcome.result.map(|result| result.final_state.g()), outcome.stats.zero_cost_edges, outcome.edge_cost_error)
+    }
+
+    #[test]
+    fn test_zero_cost_edges_are_counted() {
+        assert_eq!(walk(|position, g| g + position % 2, 0, 1), (Some(LENGTH / 2), LENGTH / 2, None));
+
+        // Without bounds nothing is checked or counted.
+        let outcome = traced_astar_with_options(Walk { position: 0, g: 0, next_g: |position, g| g + position % 2 }, SearchOptions::new());
+        assert_eq!(outcome.stats.zero_cost_edges, 0);
+    }
+
+    #[test]
+    fn test_out_of_bounds_edge() {
+        let (result, _, error) = walk(|position, g| g + if position == 7 { 50 } else { 1 }, 1, 10);
+        assert_eq!(result, None);
+
+        let bounds = EdgeCostBounds { min: 1, max: 10 };
+        assert_eq!(error, Some(EdgeCostError::OutOfBounds { parent: 7, change: 8, cost: 50, bounds }));
+        assert_eq!(error.unwrap().to_string(), "edge 8 from 7 costs 50, outside 1..=10");
+    }
+
+    #[test]
+    fn test_negative_edge() {
+        let (_, _, error) = walk(|position, g| if position == 5 { g - 1 } else { g + 1 }, 0, 10);
+        assert_eq!(error, Some(EdgeCostError::Negative { parent: 5, change: 6, parent_g: 5, successor_g: 4 }));
+    }
+
+    #[test]
+    fn test_forgotten_costs() {
+        // Zero costs are allowed, so it takes FORGOTTEN_COSTS_EXPANSIONS expansions to tell.
+        let (_, zero_cost_edges, error) = walk(|_, g| g, 0, 10);
+        assert_eq!(error, Some(EdgeCostError::ForgottenCosts { expansions: FORGOTTEN_COSTS_EXPANSIONS, edges: FORGOTTEN_COSTS_EXPANSIONS }));
+        assert_eq!(zero_cost_edges, FORGOTTEN_COSTS_EXPANSIONS - 1);
+
+        // When they aren't, the first edge does.
+        let (_, _, error) = walk(|_, g| g, 1, 10);
+        assert_eq!(error, Some(EdgeCostError::ForgottenCosts { expansions: 1, edges: 1 }));
+
+        // A single paid edge before the first zero one makes it an ordinary bounds error.
+        let (_, _, error) = walk(|position, g| g + usize::from(position == 0), 1, 10);
+        assert!(matches!(error, Some(EdgeCostError::OutOfBounds { parent: 1, cost: 0, .. })));
+    }
+}
@@ -0,0 +1,140 @@
+use std::{collections::BTreeMap, hash::Hash};
+
+use crate::{rng::SplitMix64, untraced::state::UntracedState};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeReport {
+    pub probes: usize,
+    pub depth: usize,
+    // How many times each h value was seen along the walks, start state excluded.
+    pub h_distribution: BTreeMap<usize, usize>,
+    pub min_h: Option<usize>,
+    pub goals_reached: usize,
+    pub dead_ends: usize,
+    // Average number of successors of the expanded states.
+    pub branching_factor: f64,
+    // 1 + b + b^2 + ... + b^d, using the initial h as the solution depth d.
+    pub predicted_nodes: f64
+}
+
+// Runs `probes` random walks of up to `depth` expansions from the initial state. A walk that hits a
+// dead end restarts from the initial state without getting extra expansions, and a walk stops
+// early when it reaches a goal. The same seed always produces the same report.
+pub fn probe<S, K>(initial_state: &S, probes: usize, depth: usize, seed: u64) -> ProbeReport
+where
+    S: UntracedState<K> + Clone,
+    K: Clone + Eq + Hash
+{
+    let mut rng = SplitMix64::new(seed);
+    let mut h_distribution = BTreeMap::new();
+    let mut min_h: Option<usize> = None;
+    let mut goals_reached = 0;
+    let mut dead_ends = 0;
+    let mut expansions = 0;
+    let mut generated = 0;
+
+    for _ in 0..probes {
+        let mut current = initial_state.clone();
+
+        for _ in 0..depth {
+            if current.is_goal() {
+                break;
+            }
+
+            let mut successors = current.generate_successors();
+            expansions += 1;
+            generated += successors.len();
+
+            if successors.is_empty() {
+                dead_ends += 1;
+                current = initial_state.clone();
+                continue;
+            }
+
+            let chosen = rng.next_below(successors.len());
+            current = successors.swap_remove(chosen);
+
+            let h = current.h();
+            *h_distribution.entry(h).or_insert(0) += 1;
+            min_h = Some(min_h.map_or(h, |min| min.min(h)));
+        }
+
+        if current.is_goal() {
+            goals_reached += 1;
+        }
+    }
+
+    let branching_factor = if expansions == 0 {
+        0.0
+    }
+    else {
+        generated as f64 / expansions as f64
+    };
+
+    ProbeReport {
+        probes,
+        depth,
+        h_distribution,
+        min_h,
+        goals_reached,
+        dead_ends,
+        branching_factor,
+        predicted_nodes: predicted_nodes(branching_factor, initial_state.h())
+    }
+}
+
+fn predicted_nodes(branching_factor: f64, solution_depth: usize) -> f64 {
+    if (branching_factor - 1.0).abs() < f64::EPSILON {
+        return (solution_depth + 1) as f64;
+    }
+
+    (branching_factor.powi(solution_depth as i32 + 1) - 1.0) / (branching_factor - 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H};
+
+    #[test]
+    fn test_same_seed_same_report() {
+        let start = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+
+        let first = probe(&start, 20, 6, 42);
+        let second = probe(&start, 20, 6, 42);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_solvable_instance_reaches_zero_h() {
+        let start = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let report = probe(&start, 20, 6, 7);
+
+        assert_eq!(report.min_h, Some(0));
+        assert!(report.goals_reached > 0);
+        assert!(report.h_distribution.contains_key(&0));
+        assert!(report.branching_factor >= 1.0);
+    }
+
+    #[test]
+    fn test_dead_ends_restart_the_walk() {
+        // 0 -> 1 is a dead end, 0 -> 2 leads to the goal 3.
+        const FORK: &[&[(usize, usize)]] = &[&[(1, 1), (2, 1)], &[], &[(3, 1)], &[]];
+        let start = GraphState::new(FORK, &[2, 1, 1, 0], 0, 3);
+        let report = probe(&start, 10, 20, 3);
+
+        assert!(report.dead_ends > 0);
+        assert!(report.goals_reached > 0);
+        // Every step into 1 ends in a dead end, every successful walk is 0 -> 2 -> 3.
+        assert_eq!(
+            report.h_distribution.values().sum::<usize>(),
+            report.dead_ends + 2 * report.goals_reached
+        );
+    }
+
+    #[test]
+    fn test_predicted_nodes() {
+        assert_eq!(predicted_nodes(1.0, 4), 5.0);
+        assert_eq!(predicted_nodes(2.0, 3), 15.0);
+    }
+}
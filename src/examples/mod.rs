@@ -1,2 +0,0 @@
-pub mod layton1_puzzle_132_rc_keys;
-pub mod layton1_puzzle_132_cloning_keys;
@@ -0,0 +1,145 @@
+use std::cell::Cell;
+
+pub(crate) type NodeEstimator<'a, S> = Box<dyn Fn(&S) -> f64 + 'a>;
+
+// How many expansions a search may do, see `SearchOptions::with_expansion_limit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum LimitSpec {
+    Absolute(usize),
+    // `factor` times the nodes the instance is estimated to need, rounded up. The estimate comes
+    // from `SearchOptions::with_node_estimator`, run on the initial state when the search starts.
+    // A search without one runs unlimited and warns with `SearchWarning::UnresolvedLimit`.
+    Relative { factor: f64 }
+}
+
+impl LimitSpec {
+    // The absolute limit for an instance estimated at `estimate` nodes. A relative limit without
+    // an estimate is no limit. Negative and NaN products count as 0.
+    pub fn resolve(self, estimate: Option<f64>) -> Option<usize> {
+        match self {
+            LimitSpec::Absolute(limit) => Some(limit),
+            LimitSpec::Relative { factor } => estimate.map(|estimate| (estimate * factor).ceil() as usize)
+        }
+    }
+}
+
+impl From<usize> for LimitSpec {
+    fn from(limit: usize) -> Self {
+        LimitSpec::Absolute(limit)
+    }
+}
+
+// The expansion limit a search ran with, in `SearchStats::expansion_limit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedLimit {
+    pub limit: usize,
+    // Nodes the instance was estimated to need, when the limit is relative.
+    pub estimate: Option<f64>
+}
+
+// Estimates are never NaN, see `SearchOptions::with_node_estimator`.
+impl Eq for ResolvedLimit {}
+
+// A pool of expansions shared by the searches of a batch, see `SearchOptions::with_shared_budget`.
+// Each search may take what is left when it starts, and what it spends is taken out when it ends.
+#[derive(Debug, Default)]
+pub struct SharedBudget {
+    remaining: Cell<usize>,
+    spent: Cell<usize>
+}
+
+impl SharedBudget {
+    pub fn new(expansions: usize) -> Self {
+        SharedBudget { remaining: Cell::new(expansions), spent: Cell::new(0) }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.remaining.get()
+    }
+
+    pub fn spent(&self) -> usize {
+        self.spent.get()
+    }
+
+    pub(crate) fn charge(&self, expansions: usize) {
+        self.remaining.set(self.remaining.get().saturating_sub(expansions));
+        self.spent.set(self.spent.get() + expansions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search_options::SearchOptions, search_stats::SearchWarning, test_support::SlidingPuzzle, traced::traced_astar::traced_astar_with_options};
+
+    #[test]
+    fn test_relative_limit_is_the_estimate_times_the_factor() {
+        let options = SearchOptions::new().with_expansion_limit(LimitSpec::Relative { factor: 1.5 }).with_node_estimator(|_| 250.0);
+        let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(3, 20), options);
+        assert_eq!(outcome.stats.expansion_limit, Some(ResolvedLimit { limit: 375, estimate: Some(250.0) }));
+
+        let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(3, 20), SearchOptions::new().with_max_expansions(40));
+        assert_eq!(outcome.stats.expansion_limit, Some(ResolvedLimit { limit: 40, estimate: None }));
+
+        assert!(outcome.stats.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_relative_limit_without_an_estimate_warns() {
+        let relative = || SearchOptions::new().with_expansion_limit(LimitSpec::Relative { factor: 1.5 });
+        let unlimited = traced_astar_with_options(SlidingPuzzle::scrambled(3, 20), SearchOptions::new());
+
+        // No estimator, and an estimator that gives up.
+        for options in [relative(), relative().with_node_estimator(|_| f64::NAN)] {
+            let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(3, 20), options);
+            assert_eq!(outcome.stats.expansion_limit, None);
+            assert_eq!(outcome.stats.warnings, vec![SearchWarning::UnresolvedLimit]);
+            assert_eq!(outcome.stats.expansions, unlimited.stats.expansions);
+        }
+
+        // A shared budget still caps the search, the warning says the relative part was lost.
+        let budget = SharedBudget::new(10);
+        let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(3, 20), relative().with_shared_budget(&budget));
+        assert_eq!(outcome.stats.expansion_limit, Some(ResolvedLimit { limit: 10, estimate: None }));
+        assert_eq!(outcome.stats.warnings, vec![SearchWarning::UnresolvedLimit]);
+    }
+
+    #[test]
+    #[cfg(feature = "grid")]
+    fn test_adversarial_instance_hits_its_limit_in_a_batch() {
+        use crate::{astar_state::AStarState, grid::{map::GridMap, region::GoalRegion, state::GridState}};
+
+        // A wall down the middle with a gap at the bottom. From (0, 0) to (29, 0) the search has
+        // to fill most of the left half before going round, 681 expansions against the 240 its
+        // estimate allows. The others take 83, 107 and 268.
+        let mut map = GridMap::new(30, 30);
+        for y in 0..29 {
+            map.set_blocked(15, y, true);
+        }
+        let batch = [((0, 0), (10, 10)), ((0, 0), (29, 0)), ((20, 5), (29, 25)), ((14, 0), (0, 29))];
+        let run = |budget: &SharedBudget| -> Vec<(bool, usize)> {
+            batch.iter().map(|&(start, (x, y))| {
+                let goal = GoalRegion::Cell(x, y);
+                // A path's length in nodes, and 8 times that as limit.
+                let options = SearchOptions::new()
+                    .with_expansion_limit(LimitSpec::Relative { factor: 8.0 })
+                    .with_node_estimator(|state: &GridState| (state.h() + 1) as f64)
+                    .with_shared_budget(budget);
+                let outcome = traced_astar_with_options(GridState::with_region(&map, start, &goal), options);
+                assert_eq!(outcome.result.is_none(), outcome.budget_exhausted);
+                (outcome.result.is_some(), outcome.stats.expansions)
+            }).collect()
+        };
+
+        let budget = SharedBudget::new(1000);
+        assert_eq!(run(&budget), vec![(true, 83), (false, 240), (true, 107), (true, 268)]);
+        assert_eq!((budget.spent(), budget.remaining()), (698, 302));
+
+        // With less in the pool, the trap gets the 217 the first search left, and it spends them
+        // all.
+        let budget = SharedBudget::new(300);
+        assert_eq!(run(&budget), vec![(true, 83), (false, 217), (false, 0), (false, 0)]);
+        assert_eq!((budget.spent(), budget.remaining()), (300, 0));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use crate::{astar_state::AStarState, open_list::OpenList};
+
+// Callbacks over node ids owned by the host. Successors come with the cost of the edge to them.
+pub type SuccessorFn = Box<dyn Fn(u64) -> Vec<(u64, usize)> + Send>;
+pub type HeuristicFn = Box<dyn Fn(u64) -> usize + Send>;
+pub type GoalFn = Box<dyn Fn(u64) -> bool + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SearchStatus {
+    Running,
+    Solved { cost: usize },
+    Exhausted
+}
+
+// Object safe view of a search that advances in bounded steps, so a host can keep a
+// `Box<dyn IncrementalSearch>` without knowing anything about the domain.
+pub trait IncrementalSearch: Send {
+    // Expands at most `expansions` nodes and returns the status afterwards.
+    fn step(&mut self, expansions: usize) -> SearchStatus;
+    fn status(&self) -> SearchStatus;
+    // Node ids from the start to the goal, empty unless the search is solved.
+    fn solution_ids(&self) -> Vec<u64>;
+    fn expansions(&self) -> usize;
+}
+
+struct Node {
+    id: u64,
+    parent: Option<u64>,
+    g: usize,
+    f: usize
+}
+
+impl AStarState<u64> for Node {
+    fn key(&self) -> u64 {
+        self.id
+    }
+
+    fn h(&self) -> usize {
+        self.f - self.g
+    }
+
+    fn f(&self) -> usize {
+        self.f
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        false
+    }
+}
+
+// Traced A* over opaque u64 node ids, driven entirely by boxed closures. There are no type
+// parameters, so a single instantiation serves every host domain, and no global state, so any
+// number of contexts can run side by side (each one on any thread).
+pub struct OpaqueSearch {
+    successors: SuccessorFn,
+    heuristic: HeuristicFn,
+    goal: GoalFn,
+    open_list: OpenList<u64, Node>,
+    closed_list: HashMap<u64, Node>,
+    status: SearchStatus,
+    solution: Vec<u64>
+}
+
+impl OpaqueSearch {
+    pub fn new(start: u64, successors: SuccessorFn, heuristic: HeuristicFn, goal: GoalFn) -> Self {
+        let mut open_list = OpenList::new();
+        let h = heuristic(start);
+        open_list.insert(start, Node { id: start, parent: None, g: 0, f: h });
+
+        OpaqueSearch {
+            successors,
+            heuristic,
+            goal,
+            open_list,
+            closed_list: HashMap::new(),
+            status: SearchStatus::Running,
+            solution: Vec::new()
+        }
+    }
+
+    fn expand_next(&mut self) {
+        let Some(current) = self.open_list.extract_min() else {
+            self.status = SearchStatus::Exhausted;
+            return;
+        };
+
+        if (self.goal)(current.id) {
+            self.status = SearchStatus::Solved { cost: current.g };
+            self.solution = self.trace_back(current);
+            return;
+        }
+
+        for (successor, edge_cost) in (self.successors)(current.id) {
+            if self.closed_list.contains_key(&successor) {
+                continue;
+            }
+
+            let g = current.g + edge_cost;
+            let f = g + (self.heuristic)(successor);
+            self.open_list.insert(successor, Node { id: successor, parent: Some(current.id), g, f });
+        }
+
+        self.closed_list.insert(current.id, current);
+    }
+
+    fn trace_back(&self, goal: Node) -> Vec<u64> {
+        let mut ids = vec![goal.id];
+        let mut parent = goal.parent;
+
+        while let Some(id) = parent {
+            ids.push(id);
+            parent = self.closed_list.get(&id).and_then(|node| node.parent);
+        }
+
+        ids.reverse();
+        ids
+    }
+}
+
+impl IncrementalSearch for OpaqueSearch {
+    fn step(&mut self, expansions: usize) -> SearchStatus {
+        for _ in 0..expansions {
+            if self.status != SearchStatus::Running {
+                break;
+            }
+            self.expand_next();
+        }
+
+        self.status
+    }
+
+    fn status(&self) -> SearchStatus {
+        self.status
+    }
+
+    fn solution_ids(&self) -> Vec<u64> {
+        self.solution.clone()
+    }
+
+    fn expansions(&self) -> usize {
+        self.closed_list.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::traced_astar::traced_astar};
+
+    fn graph_search(start: u64, goal: u64, h_values: &'static [usize]) -> OpaqueSearch {
+        OpaqueSearch::new(
+            start,
+            Box::new(|id| EIGHT_NODES[id as usize].iter().map(|&(target, cost)| (target as u64, cost)).collect()),
+            Box::new(move |id| h_values[id as usize]),
+            Box::new(move |id| id == goal)
+        )
+    }
+
+    #[test]
+    fn test_parity_with_traced_astar() {
+        for goal in 0..EIGHT_NODES.len() {
+            let h_values = if goal == 7 { EIGHT_NODES_H } else { ZERO_H };
+            let generic = traced_astar(GraphState::new(EIGHT_NODES, h_values, 0, goal));
+
+            let mut search: Box<dyn IncrementalSearch> = Box::new(graph_search(0, goal as u64, h_values));
+            let status = search.step(usize::MAX);
+
+            match generic {
+                Some(result) => {
+                    assert_eq!(status, SearchStatus::Solved { cost: result.final_state.g_cost });
+                    let mut expected = vec![0];
+                    expected.extend(result.path.iter().map(|node| node as u64));
+                    assert_eq!(search.solution_ids(), expected);
+                    assert_eq!(search.expansions(), result.iterations);
+                }
+                None => assert_eq!(status, SearchStatus::Exhausted)
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounded_steps() {
+        let mut search = graph_search(0, 7, EIGHT_NODES_H);
+
+        assert_eq!(search.step(0), SearchStatus::Running);
+        assert_eq!(search.step(1), SearchStatus::Running);
+        assert_eq!(search.expansions(), 1);
+        assert!(search.solution_ids().is_empty());
+
+        while search.step(1) == SearchStatus::Running {}
+        assert_eq!(search.status(), SearchStatus::Solved { cost: 9 });
+        assert_eq!(search.step(10), SearchStatus::Solved { cost: 9 });
+    }
+
+    #[test]
+    fn test_unreachable_and_threads() {
+        // Node 4 only leads to 7, so nothing reaches 3 from it.
+        let mut search = graph_search(4, 3, ZERO_H);
+        let status = std::thread::spawn(move || search.step(100)).join().unwrap();
+        assert_eq!(status, SearchStatus::Exhausted);
+    }
+}
@@ -0,0 +1,145 @@
+use std::hash::{Hash, Hasher};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// 64-bit FNV-1a. Unlike the std hashers its output is fixed: integers are fed as little endian
+// bytes and usize and isize as 64 bits, so a value hashes the same on every platform and crate
+// version as long as its Hash impl feeds the same data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    pub fn new() -> Self {
+        StableHasher(FNV_OFFSET)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_i16(&mut self, value: i16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_i128(&mut self, value: i128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.write_i64(value as i64);
+    }
+}
+
+// Running hash of a search's expansion order: key, g and h of every expanded state, then the key
+// of the goal. Two runs with the same fingerprint expanded the same states in the same order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceFingerprint(StableHasher);
+
+impl TraceFingerprint {
+    pub fn new() -> Self {
+        TraceFingerprint(StableHasher::new())
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.finish()
+    }
+
+    pub(crate) fn expanded<K: Hash>(&mut self, key: &K, g: usize, h: usize) {
+        self.0.write_u8(0);
+        key.hash(&mut self.0);
+        self.0.write_usize(g);
+        self.0.write_usize(h);
+    }
+
+    pub(crate) fn goal<K: Hash>(&mut self, key: &K) {
+        self.0.write_u8(1);
+        key.hash(&mut self.0);
+    }
+}
+
+impl Default for TraceFingerprint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference values of 64-bit FNV-1a.
+    #[test]
+    fn test_fnv_1a_vectors() {
+        for (input, expected) in [("", 0xcbf2_9ce4_8422_2325), ("a", 0xaf63_dc4c_8601_ec8c), ("foobar", 0x8594_4171_f739_67e8)] {
+            let mut hasher = StableHasher::new();
+            hasher.write(input.as_bytes());
+            assert_eq!(hasher.finish(), expected);
+        }
+    }
+
+    #[test]
+    fn test_usize_hashes_as_u64() {
+        let mut narrow = StableHasher::new();
+        let mut wide = StableHasher::new();
+        7usize.hash(&mut narrow);
+        7u64.hash(&mut wide);
+
+        assert_eq!(narrow.finish(), wide.finish());
+    }
+
+    #[test]
+    fn test_order_changes_the_fingerprint() {
+        let mut forward = TraceFingerprint::new();
+        forward.expanded(&1u8, 0, 2);
+        forward.expanded(&2u8, 1, 1);
+        let mut backward = TraceFingerprint::new();
+        backward.expanded(&2u8, 1, 1);
+        backward.expanded(&1u8, 0, 2);
+
+        assert_ne!(forward.get(), backward.get());
+    }
+}
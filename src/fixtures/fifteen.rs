@@ -0,0 +1,92 @@
+use crate::{astar_state::AStarState, grid::state::Direction, traced::state::TracedState, untraced::state::UntracedState};
+
+// 4x4 sliding tile puzzle. Tiles are 1..15 and 0 is the blank, row by row, solved with the tiles
+// in order and the blank in the last cell. h is the Manhattan distance of the tiles and the change
+// of a move is the direction the blank moves in.
+#[derive(Clone, Debug)]
+pub struct FifteenPuzzle {
+    tiles: [u8; 16],
+    blank: usize,
+    g: usize
+}
+
+impl FifteenPuzzle {
+    pub const SOLVED: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0];
+
+    // Panics unless `tiles` holds each of 0..16 once.
+    pub fn new(tiles: [u8; 16]) -> Self {
+        let mut sorted = tiles;
+        sorted.sort_unstable();
+        assert!(sorted.iter().enumerate().all(|(index, &tile)| tile as usize == index), "not a 15-puzzle board: {:?}", tiles);
+
+        FifteenPuzzle {
+            tiles,
+            blank: tiles.iter().position(|&tile| tile == 0).unwrap(),
+            g: 0
+        }
+    }
+
+    pub fn tiles(&self) -> [u8; 16] {
+        self.tiles
+    }
+}
+
+impl AStarState<[u8; 16]> for FifteenPuzzle {
+    fn key(&self) -> [u8; 16] {
+        self.tiles
+    }
+
+    fn h(&self) -> usize {
+        self.tiles.iter()
+            .enumerate()
+            .filter(|&(_, &tile)| tile != 0)
+            .map(|(index, &tile)| {
+                let target = tile as usize - 1;
+                (index % 4).abs_diff(target % 4) + (index / 4).abs_diff(target / 4)
+            })
+            .sum()
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.tiles == Self::SOLVED
+    }
+}
+
+impl TracedState<[u8; 16], Direction> for FifteenPuzzle {
+    fn generate_traced_successors(&self) -> Vec<(Self, Direction)> {
+        let (x, y) = (self.blank % 4, self.blank / 4);
+        let mut successors = Vec::with_capacity(4);
+
+        for (direction, possible, target) in [
+            (Direction::Up, y > 0, self.blank.wrapping_sub(4)),
+            (Direction::Down, y < 3, self.blank + 4),
+            (Direction::Left, x > 0, self.blank.wrapping_sub(1)),
+            (Direction::Right, x < 3, self.blank + 1)
+        ] {
+            if possible {
+                let mut tiles = self.tiles;
+                tiles.swap(self.blank, target);
+                successors.push((FifteenPuzzle { tiles, blank: target, g: self.g + 1 }, direction));
+            }
+        }
+
+        successors
+    }
+}
+
+impl UntracedState<[u8; 16]> for FifteenPuzzle {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.generate_traced_successors()
+            .into_iter()
+            .map(|(successor, _)| successor)
+            .collect()
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{astar_state::AStarState, traced::state::TracedState, untraced::state::UntracedState};
+
+// Nodes on a plane joined by undirected edges, each costing at least the euclidean length of the
+// edge rounded up. h is the euclidean distance to the goal rounded down, which is then admissible
+// and consistent. The change of a move is the node it moves to.
+#[derive(Clone, Debug)]
+pub struct WeightedGraph {
+    points: &'static [(i32, i32)],
+    // (node, node, cost)
+    edges: &'static [(usize, usize, usize)],
+    node: usize,
+    goal: usize,
+    g: usize
+}
+
+impl WeightedGraph {
+    pub fn new(points: &'static [(i32, i32)], edges: &'static [(usize, usize, usize)], start: usize, goal: usize) -> Self {
+        WeightedGraph { points, edges, node: start, goal, g: 0 }
+    }
+
+    pub fn node(&self) -> usize {
+        self.node
+    }
+
+    pub fn distance(&self, from: usize, to: usize) -> f64 {
+        let ((from_x, from_y), (to_x, to_y)) = (self.points[from], self.points[to]);
+        f64::from(from_x - to_x).hypot(f64::from(from_y - to_y))
+    }
+}
+
+impl AStarState<usize> for WeightedGraph {
+    fn key(&self) -> usize {
+        self.node
+    }
+
+    fn h(&self) -> usize {
+        self.distance(self.node, self.goal).floor() as usize
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.node == self.goal
+    }
+}
+
+impl TracedState<usize, usize> for WeightedGraph {
+    fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+        self.edges.iter()
+            .filter_map(|&(a, b, cost)| {
+                let target = if a == self.node { b } else if b == self.node { a } else { return None };
+                Some((WeightedGraph { node: target, g: self.g + cost, ..self.clone() }, target))
+            })
+            .collect()
+    }
+}
+
+impl UntracedState<usize> for WeightedGraph {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.generate_traced_successors()
+            .into_iter()
+            .map(|(successor, _)| successor)
+            .collect()
+    }
+}
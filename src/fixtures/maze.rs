@@ -0,0 +1,114 @@
+use std::sync::OnceLock;
+
+use crate::{astar_state::AStarState, grid::{map::GridMap, state::Direction}, traced::state::TracedState, untraced::state::UntracedState};
+
+// A grid map with a start and a goal cell, parsed once from text where 'S' and 'G' mark them.
+#[derive(Debug)]
+pub struct Maze {
+    map: GridMap,
+    start: (usize, usize),
+    goal: (usize, usize)
+}
+
+impl Maze {
+    // Panics unless the text has one 'S' and one 'G'.
+    pub fn from_ascii(text: &str) -> Self {
+        let find = |marker: char| -> (usize, usize) {
+            let mut cells = text.lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .enumerate()
+                .flat_map(|(y, line)| line.chars().enumerate().filter(move |&(_, cell)| cell == marker).map(move |(x, _)| (x, y)));
+            let cell = cells.next().unwrap_or_else(|| panic!("no '{}' in the maze", marker));
+            assert!(cells.next().is_none(), "more than one '{}' in the maze", marker);
+            cell
+        };
+
+        Maze {
+            map: GridMap::from_ascii(text),
+            start: find('S'),
+            goal: find('G')
+        }
+    }
+
+    pub fn map(&self) -> &GridMap {
+        &self.map
+    }
+
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    pub fn goal(&self) -> (usize, usize) {
+        self.goal
+    }
+
+    // State at the start of a maze that lives for the rest of the program, such as a fixture's.
+    pub fn state(&'static self) -> MazeState {
+        MazeState { maze: self, position: self.start, g: 0 }
+    }
+}
+
+// Parses a maze the first time it's asked for and keeps it for good.
+pub(crate) fn cached(cell: &'static OnceLock<Maze>, text: &str) -> &'static Maze {
+    cell.get_or_init(|| Maze::from_ascii(text))
+}
+
+// Position in a maze, moving between free cells with the movement model of its map. h is the
+// Manhattan distance to the goal, so the map must be 4-connected for it to be admissible.
+#[derive(Clone, Debug)]
+pub struct MazeState {
+    maze: &'static Maze,
+    position: (usize, usize),
+    g: usize
+}
+
+impl MazeState {
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+}
+
+impl AStarState<(usize, usize)> for MazeState {
+    fn key(&self) -> (usize, usize) {
+        self.position
+    }
+
+    fn h(&self) -> usize {
+        let (x, y) = self.position;
+        x.abs_diff(self.maze.goal.0) + y.abs_diff(self.maze.goal.1)
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.position == self.maze.goal
+    }
+}
+
+impl TracedState<(usize, usize), Direction> for MazeState {
+    fn generate_traced_successors(&self) -> Vec<(Self, Direction)> {
+        let (x, y) = self.position;
+        let movement = self.maze.map.movement();
+
+        self.maze.map.moves(x, y)
+            .into_iter()
+            .map(|(position, direction)| (MazeState { maze: self.maze, position, g: self.g + movement.cost(direction) }, direction))
+            .collect()
+    }
+}
+
+impl UntracedState<(usize, usize)> for MazeState {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.generate_traced_successors()
+            .into_iter()
+            .map(|(successor, _)| successor)
+            .collect()
+    }
+}
@@ -0,0 +1,198 @@
+// Benchmark instances with known optimal costs, for conformance tests and benchmarks. Every
+// constructor returns the initial state and the optimal cost, which the tests below confirm by
+// solving each instance with `traced_astar`.
+pub mod fifteen;
+pub mod maze;
+pub mod graph;
+
+use std::sync::OnceLock;
+
+use crate::{fixtures::{fifteen::FifteenPuzzle, graph::WeightedGraph, maze::{Maze, MazeState}}, puzzles::sliding_block::{SlidingBlocks, Target}};
+
+// The embedded boards are valid.
+fn board(text: &str, piece: char, position: (usize, usize)) -> SlidingBlocks {
+    SlidingBlocks::from_ascii(text, Target { piece, position }).unwrap()
+}
+
+// Puzzle 132 of Professor Layton and the Curious Village: the 2x2 block to the right edge.
+pub fn layton_132() -> (SlidingBlocks, usize) {
+    (board(include_str!("data/layton_132.txt"), 'B', (3, 1)), 64)
+}
+
+// Klotski (Huarong Dao): the 2x2 block down to the exit in the middle of the bottom edge. Costs
+// count single cell moves, not whole slides of a piece.
+pub fn klotski() -> (SlidingBlocks, usize) {
+    (board(include_str!("data/klotski.txt"), 'A', (1, 3)), 116)
+}
+
+// A 4x4 board with two blanks in the top right corner: the 2x2 block from the top left corner
+// to the bottom right one.
+pub fn corner() -> (SlidingBlocks, usize) {
+    (board(include_str!("data/corner.txt"), 'A', (2, 2)), 26)
+}
+
+// 15-puzzle boards of increasing solution depth, as (tiles, optimal cost).
+const FIFTEEN_PUZZLES: [([u8; 16], usize); 5] = [
+    ([1, 2, 3, 4, 5, 6, 11, 7, 9, 10, 12, 0, 13, 14, 8, 15], 9),
+    ([5, 1, 2, 3, 7, 10, 12, 4, 9, 6, 8, 0, 13, 14, 11, 15], 17),
+    ([2, 3, 4, 7, 9, 1, 6, 8, 13, 14, 5, 10, 15, 0, 11, 12], 26),
+    ([5, 2, 8, 3, 6, 9, 14, 12, 13, 10, 1, 15, 7, 0, 4, 11], 34),
+    ([1, 2, 3, 10, 5, 7, 8, 15, 13, 11, 12, 14, 0, 9, 4, 6], 39)
+];
+
+// The `index`th of the 15-puzzle boards, from 0 (shallowest) to 4 (deepest).
+pub fn fifteen_puzzle(index: usize) -> (FifteenPuzzle, usize) {
+    let (tiles, optimal_cost) = FIFTEEN_PUZZLES[index];
+    (FifteenPuzzle::new(tiles), optimal_cost)
+}
+
+pub fn fifteen_puzzles() -> Vec<(FifteenPuzzle, usize)> {
+    (0..FIFTEEN_PUZZLES.len()).map(fifteen_puzzle).collect()
+}
+
+static MAZE_SMALL: OnceLock<Maze> = OnceLock::new();
+static MAZE_LARGE: OnceLock<Maze> = OnceLock::new();
+
+// 21x13 maze with a few loops, from the top left corner to the bottom right one.
+pub fn maze_small() -> (MazeState, usize) {
+    (maze::cached(&MAZE_SMALL, include_str!("data/maze_small.txt")).state(), 40)
+}
+
+// 61x31 maze with more loops, from the top left corner to the bottom right one.
+pub fn maze_large() -> (MazeState, usize) {
+    (maze::cached(&MAZE_LARGE, include_str!("data/maze_large.txt")).state(), 134)
+}
+
+const GRAPH_POINTS: [(i32, i32); 16] = [
+    (2, 3), (22, 25), (33, 0), (29, 24), (15, 20), (3, 28), (10, 3), (23, 15),
+    (15, 12), (34, 3), (36, 7), (0, 23), (13, 13), (17, 5), (24, 5), (37, 26)
+];
+
+const GRAPH_EDGES: [(usize, usize, usize); 32] = [
+    (0, 6, 12), (0, 12, 19), (0, 13, 19), (1, 3, 9), (1, 4, 10), (1, 7, 11), (1, 15, 16), (2, 9, 5),
+    (2, 10, 9), (2, 14, 12), (3, 7, 12), (3, 15, 11), (4, 5, 17), (4, 7, 11), (4, 8, 12), (4, 11, 21),
+    (4, 12, 13), (5, 11, 7), (5, 12, 20), (6, 8, 16), (6, 13, 9), (7, 8, 12), (7, 14, 13), (7, 15, 18),
+    (8, 12, 5), (8, 13, 11), (9, 10, 6), (9, 14, 12), (10, 14, 15), (11, 12, 17), (12, 13, 11), (13, 14, 9)
+];
+
+// 16 nodes and 32 edges with costs up to 5 above their length, from node 0 to node 15.
+pub fn weighted_graph() -> (WeightedGraph, usize) {
+    (WeightedGraph::new(&GRAPH_POINTS, &GRAPH_EDGES, 0, 15), 54)
+}
+
+// Initial state of a fixture, one variant per state type.
+#[derive(Clone, Debug)]
+pub enum Instance {
+    SlidingBlocks(SlidingBlocks),
+    Fifteen(FifteenPuzzle),
+    Maze(MazeState),
+    Graph(WeightedGraph)
+}
+
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub instance: Instance,
+    pub optimal_cost: usize
+}
+
+impl Fixture {
+    fn new(name: &'static str, (instance, optimal_cost): (impl Into<Instance>, usize)) -> Self {
+        Fixture { name, instance: instance.into(), optimal_cost }
+    }
+}
+
+impl From<SlidingBlocks> for Instance {
+    fn from(state: SlidingBlocks) -> Self {
+        Instance::SlidingBlocks(state)
+    }
+}
+
+impl From<FifteenPuzzle> for Instance {
+    fn from(state: FifteenPuzzle) -> Self {
+        Instance::Fifteen(state)
+    }
+}
+
+impl From<MazeState> for Instance {
+    fn from(state: MazeState) -> Self {
+        Instance::Maze(state)
+    }
+}
+
+impl From<WeightedGraph> for Instance {
+    fn from(state: WeightedGraph) -> Self {
+        Instance::Graph(state)
+    }
+}
+
+// Every fixture, roughly from the quickest to solve to the slowest.
+pub fn all() -> Vec<Fixture> {
+    let mut fixtures = vec![
+        Fixture::new("weighted_graph", weighted_graph()),
+        Fixture::new("maze_small", maze_small()),
+        Fixture::new("maze_large", maze_large()),
+        Fixture::new("corner", corner()),
+        Fixture::new("layton_132", layton_132()),
+        Fixture::new("klotski", klotski())
+    ];
+
+    const FIFTEEN_NAMES: [&str; 5] = ["fifteen_0", "fifteen_1", "fifteen_2", "fifteen_3", "fifteen_4"];
+    for (index, name) in FIFTEEN_NAMES.into_iter().enumerate() {
+        fixtures.push(Fixture::new(name, fifteen_puzzle(index)));
+    }
+
+    fixtures
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fmt::Debug, hash::Hash};
+
+    use super::*;
+    use crate::{astar_state::AStarState, traced::{replay::replay, state::TracedState, traced_astar::traced_astar}};
+
+    fn solve<S, K, C>(name: &str, instance: &S, optimal_cost: usize)
+    where
+        S: TracedState<K, C> + Clone,
+        K: Clone + Eq + Hash,
+        C: Clone + PartialEq + Debug
+    {
+        let result = traced_astar(instance.clone()).unwrap_or_else(|| panic!("{} has no solution", name));
+        assert_eq!(result.cost, optimal_cost, "{}", name);
+        assert_eq!(replay(instance, result.path.to_vec()).unwrap().g(), optimal_cost, "{}", name);
+    }
+
+    #[test]
+    fn test_recorded_optima() {
+        for fixture in all() {
+            match &fixture.instance {
+                Instance::SlidingBlocks(state) => solve(fixture.name, state, fixture.optimal_cost),
+                Instance::Fifteen(state) => solve(fixture.name, state, fixture.optimal_cost),
+                Instance::Maze(state) => solve(fixture.name, state, fixture.optimal_cost),
+                Instance::Graph(state) => solve(fixture.name, state, fixture.optimal_cost)
+            }
+        }
+    }
+
+    #[test]
+    fn test_fifteen_puzzles_get_deeper() {
+        let costs: Vec<usize> = fifteen_puzzles().into_iter().map(|(_, optimal_cost)| optimal_cost).collect();
+        assert!(costs.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", costs);
+    }
+
+    #[test]
+    fn test_graph_heuristic_is_consistent() {
+        let (graph, _) = weighted_graph();
+        for &(a, b, cost) in &GRAPH_EDGES {
+            assert!(graph.distance(a, b).ceil() as usize <= cost, "edge {}-{} is shorter than its length", a, b);
+        }
+
+        for node in 0..GRAPH_POINTS.len() {
+            let state = WeightedGraph::new(&GRAPH_POINTS, &GRAPH_EDGES, node, 15);
+            for (successor, _) in state.generate_traced_successors() {
+                assert!(state.h() <= successor.g() + successor.h(), "h drops by more than the edge from {}", node);
+            }
+        }
+    }
+}
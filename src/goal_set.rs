@@ -0,0 +1,168 @@
+use std::hash::Hash;
+
+use crate::{astar_state::AStarState, traced::{generation::GenContext, result::TracedResult, state::TracedState, traced_astar::traced_astar}, untraced::{result::UntracedResult, state::UntracedState, untraced_astar::untraced_astar}};
+
+// Several goals, told apart by their index. A state that satisfies more than one is reported
+// with the lowest index.
+pub trait GoalSet<S> {
+    fn satisfied_by(&self, state: &S) -> Option<usize>;
+}
+
+impl<S, F> GoalSet<S> for [F]
+where
+    F: Fn(&S) -> bool
+{
+    fn satisfied_by(&self, state: &S) -> Option<usize> {
+        self.iter().position(|is_goal| is_goal(state))
+    }
+}
+
+impl<S, F, const N: usize> GoalSet<S> for [F; N]
+where
+    F: Fn(&S) -> bool
+{
+    fn satisfied_by(&self, state: &S) -> Option<usize> {
+        self[..].satisfied_by(state)
+    }
+}
+
+// A search result along with the goal its final state satisfies.
+#[derive(Debug, Clone)]
+pub struct GoalReached<R> {
+    pub result: R,
+    pub goal: usize
+}
+
+// A state whose goal test is the goal set. Everything else is the state's own.
+struct AnyGoal<'g, S, G>
+where
+    G: ?Sized
+{
+    state: S,
+    goals: &'g G
+}
+
+impl<K, S, G> AStarState<K> for AnyGoal<'_, S, G>
+where
+    K: Clone + Eq + Hash,
+    S: AStarState<K>,
+    G: GoalSet<S> + ?Sized
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.state.h()
+    }
+
+    fn f(&self) -> usize {
+        self.state.f()
+    }
+
+    fn g(&self) -> usize {
+        self.state.g()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.goals.satisfied_by(&self.state).is_some()
+    }
+}
+
+impl<K, C, S, G> TracedState<K, C> for AnyGoal<'_, S, G>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>,
+    G: GoalSet<S> + ?Sized
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, C)> {
+        self.state.generate_traced_successors()
+            .into_iter()
+            .map(|(state, change)| (AnyGoal { state, goals: self.goals }, change))
+            .collect()
+    }
+
+    fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, C)> {
+        self.state.generate_traced_successors_with(ctx)
+            .into_iter()
+            .map(|(state, change)| (AnyGoal { state, goals: self.goals }, change))
+            .collect()
+    }
+}
+
+impl<K, S, G> UntracedState<K> for AnyGoal<'_, S, G>
+where
+    K: Clone + Eq + Hash,
+    S: UntracedState<K>,
+    G: GoalSet<S> + ?Sized
+{
+    fn generate_successors(&self) -> Vec<Self> {
+        self.state.generate_successors()
+            .into_iter()
+            .map(|state| AnyGoal { state, goals: self.goals })
+            .collect()
+    }
+}
+
+// traced_astar to the cheapest state satisfying any of the goals, in place of the state's own
+// is_goal(). The state's h() must then estimate the cost to the nearest of them, e.g. the least
+// of its estimates to each, to stay admissible.
+pub fn traced_astar_to_any<S, K, C, G>(initial_state: S, goals: &G) -> Option<GoalReached<TracedResult<S, K, C>>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    G: GoalSet<S> + ?Sized
+{
+    let result = traced_astar(AnyGoal { state: initial_state, goals })?;
+    let result = result.map_state(|bound| bound.state);
+    let goal = goals.satisfied_by(&result.final_state).expect("the search ends at a goal");
+    Some(GoalReached { result, goal })
+}
+
+// untraced_astar to any of the goals, with the same requirement on h() as traced_astar_to_any.
+pub fn untraced_astar_to_any<S, K, G>(initial_state: S, goals: &G) -> Option<GoalReached<UntracedResult<S, K>>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash,
+    G: GoalSet<S> + ?Sized
+{
+    let result = untraced_astar(AnyGoal { state: initial_state, goals })?;
+    let goal = goals.satisfied_by(&result.final_state.state).expect("the search ends at a goal");
+    Some(GoalReached { result: UntracedResult::new(result.iterations, result.final_state.state), goal })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, ZERO_H};
+
+    type Goal = dyn Fn(&GraphState) -> bool;
+
+    fn start() -> GraphState {
+        // The graph's own goal, 6, is never tested.
+        GraphState::new(EIGHT_NODES, ZERO_H, 0, 6)
+    }
+
+    #[test]
+    fn test_closer_goal_wins() {
+        // 7 costs 9 from 0, 4 costs 6.
+        let goals: [&Goal; 2] = [&|state| state.node == 7, &|state| state.node == 4];
+
+        let traced = traced_astar_to_any(start(), &goals).unwrap();
+        assert_eq!((traced.goal, traced.result.cost, traced.result.path.to_vec()), (1, 6, vec![1, 4]));
+
+        let untraced = untraced_astar_to_any(start(), &goals).unwrap();
+        assert_eq!((untraced.goal, untraced.result.final_state.node), (1, 4));
+    }
+
+    #[test]
+    fn test_first_listed_goal_on_a_tie() {
+        // 5 is the cheapest of 5 to 7, at 5.
+        let goals: [&Goal; 2] = [&|state| state.node >= 5, &|state| state.node == 5];
+        let reached = traced_astar_to_any(start(), &goals).unwrap();
+        assert_eq!((reached.goal, reached.result.final_state.node), (0, 5));
+
+        let none: [&Goal; 1] = [&|state| state.node > 7];
+        assert!(traced_astar_to_any(start(), &none[..]).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{astar_state::AStarState, grid::heuristics::GridHeuristic, traced::state::TracedState, untraced::state::UntracedState};
+
+type Cell = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum GraphKind {
+    // Each edge is an arc from its first node to its second.
+    #[default]
+    Directed,
+    // Each edge is inserted, replaced and removed as both arcs at once.
+    Undirected
+}
+
+// Graph over grid cells with weighted edges. Between two nodes there is at most one arc in each
+// direction: of parallel edges, only the cheapest is kept, and the others are counted in
+// `dropped_parallel_edges`.
+#[derive(Debug, Clone, Default)]
+pub struct ExplicitGraph {
+    kind: GraphKind,
+    positions: Vec<Cell>,
+    edges: Vec<Vec<(usize, usize)>>,
+    dropped_parallel_edges: usize
+}
+
+impl ExplicitGraph {
+    // A directed graph.
+    pub fn new() -> Self {
+        ExplicitGraph::default()
+    }
+
+    pub fn with_kind(kind: GraphKind) -> Self {
+        ExplicitGraph { kind, ..ExplicitGraph::default() }
+    }
+
+    // A graph with a node at each position, in order, and the (from, to, cost) edges between them.
+    pub fn from_edges(kind: GraphKind, positions: impl IntoIterator<Item = Cell>, edges: impl IntoIterator<Item = (usize, usize, usize)>) -> Self {
+        let mut graph = ExplicitGraph::with_kind(kind);
+        for position in positions {
+            graph.add_node(position);
+        }
+        for (from, to, cost) in edges {
+            graph.add_edge(from, to, cost);
+        }
+        graph
+    }
+
+    pub fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    pub fn add_node(&mut self, position: Cell) -> usize {
+        self.positions.push(position);
+        self.edges.push(Vec::new());
+        self.positions.len() - 1
+    }
+
+    // Adds the edge, both ways in an undirected graph. An edge parallel to one already there
+    // replaces it if cheaper, and either way one of the two is dropped.
+    pub fn add_edge(&mut self, from: usize, to: usize, cost: usize) {
+        self.add_arc(from, to, cost);
+        if self.kind == GraphKind::Undirected && from != to {
+            self.add_arc(to, from, cost);
+        }
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, cost: usize) {
+        let arcs = &mut self.edges[from];
+        match arcs.iter_mut().find(|(target, _)| *target == to) {
+            Some(arc) => {
+                arc.1 = arc.1.min(cost);
+                self.dropped_parallel_edges += 1;
+            }
+            None => arcs.push((to, cost))
+        }
+    }
+
+    // Removes the edge, both ways in an undirected graph, and returns its cost. To change a cost,
+    // remove the edge and add it again: adding alone only ever lowers it.
+    pub fn remove_edge(&mut self, from: usize, to: usize) -> Option<usize> {
+        let cost = self.remove_arc(from, to)?;
+        if self.kind == GraphKind::Undirected && from != to {
+            self.remove_arc(to, from);
+        }
+        Some(cost)
+    }
+
+    fn remove_arc(&mut self, from: usize, to: usize) -> Option<usize> {
+        let arcs = &mut self.edges[from];
+        let index = arcs.iter().position(|(target, _)| *target == to)?;
+        Some(arcs.swap_remove(index).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // Arcs, so an undirected edge between two nodes counts twice.
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    // Edges left out for a cheaper parallel one since the graph was built, both arcs of an
+    // undirected edge counted.
+    pub fn dropped_parallel_edges(&self) -> usize {
+        self.dropped_parallel_edges
+    }
+
+    pub fn position(&self, node: usize) -> Cell {
+        self.positions[node]
+    }
+
+    // (target, cost) of every edge leaving the node.
+    pub fn edges(&self, node: usize) -> &[(usize, usize)] {
+        &self.edges[node]
+    }
+
+    // The nodes an edge leads to from this one.
+    pub fn neighbors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges[node].iter().map(|&(target, _)| target)
+    }
+}
+
+// A search over the graph towards a goal node. The change of a move is the node it moves to. h is
+// the heuristic's distance between the positions of the node and the goal, in cells for Manhattan
+// and Chebyshev and in OCTILE_STRAIGHT per cell for the other two. It is admissible as long as no
+// edge costs less than that distance between its own ends: an edit that lowers a cost below it
+// can make later searches return worse than optimal paths, while removed edges and raised costs
+// keep h admissible and consistent. The state borrows the graph, so edits go between searches.
+#[derive(Debug, Clone)]
+pub struct ExplicitGraphState<'a> {
+    graph: &'a ExplicitGraph,
+    heuristic: GridHeuristic,
+    node: usize,
+    goal: usize,
+    g: usize
+}
+
+impl<'a> ExplicitGraphState<'a> {
+    pub fn new(graph: &'a ExplicitGraph, start: usize, goal: usize, heuristic: GridHeuristic) -> Self {
+        ExplicitGraphState { graph, heuristic, node: start, goal, g: 0 }
+    }
+
+    pub fn node(&self) -> usize {
+        self.node
+    }
+}
+
+impl AStarState<usize> for ExplicitGraphState<'_> {
+    fn key(&self) -> usize {
+        self.node
+    }
+
+    fn h(&self) -> usize {
+        let ((x, y), (goal_x, goal_y)) = (self.graph.position(self.node), self.graph.position(self.goal));
+        self.heuristic.distance(x.abs_diff(goal_x), y.abs_diff(goal_y))
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.node == self.goal
+    }
+}
+
+impl TracedState<usize, usize> for ExplicitGraphState<'_> {
+    fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+        self.graph.edges(self.node)
+            .iter()
+            .map(|&(node, cost)| (ExplicitGraphState { node, g: self.g + cost, ..self.clone() }, node))
+            .collect()
+    }
+}
+
+impl UntracedState<usize> for ExplicitGraphState<'_> {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.generate_traced_successors()
+            .into_iter()
+            .map(|(successor, _)| successor)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traced::traced_astar::traced_astar;
+
+    // Four towns on a line and a bypass, as (from, to, cost) rows in either direction, with the
+    // road from 1 to 2 listed twice.
+    const TOWNS: [Cell; 5] = [(0, 0), (10, 0), (20, 0), (30, 0), (15, 10)];
+    const ROADS: [(usize, usize, usize); 7] = [(0, 1, 10), (2, 1, 10), (1, 2, 14), (3, 2, 10), (0, 4, 19), (4, 3, 19), (4, 2, 12)];
+
+    fn route(graph: &ExplicitGraph, start: usize, goal: usize) -> Option<(usize, Vec<usize>)> {
+        traced_astar(ExplicitGraphState::new(graph, start, goal, GridHeuristic::Chebyshev)).map(|result| (result.cost, result.path.to_vec()))
+    }
+
+    #[test]
+    fn test_undirected_roads_work_both_ways() {
+        let graph = ExplicitGraph::from_edges(GraphKind::Undirected, TOWNS, ROADS);
+        assert_eq!((graph.edge_count(), graph.dropped_parallel_edges()), (12, 2));
+        assert_eq!(graph.edges(1), &[(0, 10), (2, 10)]);
+        assert_eq!(route(&graph, 0, 3), Some((30, vec![1, 2, 3])));
+        assert_eq!(route(&graph, 3, 0), Some((30, vec![2, 1, 0])));
+
+        let directed = ExplicitGraph::from_edges(GraphKind::Directed, TOWNS, ROADS);
+        assert_eq!((directed.edge_count(), directed.dropped_parallel_edges()), (7, 0));
+        assert_eq!(route(&directed, 3, 0), None);
+    }
+
+    #[test]
+    fn test_edits_between_searches_change_the_route() {
+        let mut graph = ExplicitGraph::from_edges(GraphKind::Undirected, TOWNS, ROADS);
+        assert_eq!(route(&graph, 0, 3), Some((30, vec![1, 2, 3])));
+
+        // Roadworks between 1 and 2: adding a dearer road alone keeps the cheaper one.
+        graph.add_edge(1, 2, 40);
+        assert_eq!(route(&graph, 0, 3), Some((30, vec![1, 2, 3])));
+        assert_eq!(graph.remove_edge(2, 1), Some(10));
+        graph.add_edge(1, 2, 40);
+        assert_eq!(route(&graph, 0, 3), Some((38, vec![4, 3])));
+
+        assert_eq!(graph.remove_edge(3, 4), Some(19));
+        assert_eq!(graph.neighbors(3).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(route(&graph, 0, 3), Some((41, vec![4, 2, 3])));
+        assert_eq!(graph.remove_edge(3, 4), None);
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt::Display;
+
+use crate::{grid::{movement::{MovementModel, OCTILE_DIAGONAL, OCTILE_STRAIGHT}, region::GoalRegion, state::GridState}, traced::to_goal::HeuristicTo};
+
+pub use crate::traced::to_goal::Heuristic;
+
+// The standard distance functions over cell offsets |dx| and |dy|. Each one is admissible and
+// consistent for the movement models listed with it, on any map (walls only make paths longer).
+
+// FourWay.
+pub fn manhattan(dx: usize, dy: usize) -> usize {
+    dx + dy
+}
+
+// EightWayUniform. Also admissible for FourWay, but weaker than manhattan there.
+pub fn chebyshev(dx: usize, dy: usize) -> usize {
+    dx.max(dy)
+}
+
+// EightWayOctile, in its scaled costs. Exact on a map without walls.
+pub fn octile(dx: usize, dy: usize) -> usize {
+    OCTILE_STRAIGHT * dx.max(dy) + (OCTILE_DIAGONAL - OCTILE_STRAIGHT) * dx.min(dy)
+}
+
+// EightWayOctile, in its scaled costs. The straight line distance is capped by octile() since
+// OCTILE_DIAGONAL rounds sqrt(2) down, so a long diagonal costs slightly less than its length.
+pub fn euclidean(dx: usize, dy: usize) -> usize {
+    let length = (OCTILE_STRAIGHT * OCTILE_STRAIGHT * (dx * dx + dy * dy)).isqrt();
+    length.min(octile(dx, dy))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GridHeuristic {
+    Manhattan,
+    Chebyshev,
+    Octile,
+    Euclidean
+}
+
+impl GridHeuristic {
+    // The tightest heuristic suited to the model.
+    pub fn default_for(movement: MovementModel) -> Self {
+        match movement {
+            MovementModel::FourWay => GridHeuristic::Manhattan,
+            MovementModel::EightWayUniform => GridHeuristic::Chebyshev,
+            MovementModel::EightWayOctile => GridHeuristic::Octile
+        }
+    }
+
+    // Whether the heuristic is admissible under the model and measured in its costs. Manhattan
+    // and Chebyshev are admissible under EightWayOctile too, but count cells instead of scaled
+    // costs, so they are too weak to be of any use there.
+    pub fn is_suited_to(self, movement: MovementModel) -> bool {
+        match self {
+            GridHeuristic::Manhattan => movement == MovementModel::FourWay,
+            GridHeuristic::Chebyshev => movement != MovementModel::EightWayOctile,
+            GridHeuristic::Octile | GridHeuristic::Euclidean => movement == MovementModel::EightWayOctile
+        }
+    }
+
+    pub fn distance(self, dx: usize, dy: usize) -> usize {
+        match self {
+            GridHeuristic::Manhattan => manhattan(dx, dy),
+            GridHeuristic::Chebyshev => chebyshev(dx, dy),
+            GridHeuristic::Octile => octile(dx, dy),
+            GridHeuristic::Euclidean => euclidean(dx, dy)
+        }
+    }
+}
+
+impl Heuristic<GridState<'_>> for GridHeuristic {
+    // Distance to the closest cell of the state's region, 0 without one.
+    fn estimate(&self, state: &GridState<'_>) -> usize {
+        state.region().map_or(0, |region| HeuristicTo::estimate(self, state, &region))
+    }
+}
+
+impl HeuristicTo<GridState<'_>, &GoalRegion<'_>> for GridHeuristic {
+    // Distance to the closest cell of the region given as the goal.
+    fn estimate(&self, state: &GridState<'_>, region: &&GoalRegion<'_>) -> usize {
+        let (x, y) = state.position();
+
+        match self {
+            // Keeps the tighter bound GoalRegion has for discs under this model.
+            GridHeuristic::Manhattan => region.heuristic_to(x, y),
+            _ => {
+                let (dx, dy) = region.offsets_to(x, y);
+                self.distance(dx, dy)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HeuristicMismatch {
+    pub heuristic: GridHeuristic,
+    pub movement: MovementModel
+}
+
+impl Display for HeuristicMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} heuristic is not suited to {:?} movement", self.heuristic, self.movement)
+    }
+}
+
+impl std::error::Error for HeuristicMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, grid::{map::GridMap, region::GoalRegion}, rng::SplitMix64, test_support::random_map, untraced::reachable_set::reachable_set};
+
+    const HEURISTICS: [GridHeuristic; 4] = [GridHeuristic::Manhattan, GridHeuristic::Chebyshev, GridHeuristic::Octile, GridHeuristic::Euclidean];
+    const MOVEMENTS: [MovementModel; 3] = [MovementModel::FourWay, MovementModel::EightWayUniform, MovementModel::EightWayOctile];
+
+    #[test]
+    fn test_suited_heuristics_are_admissible() {
+        let mut rng = SplitMix64::new(7);
+
+        for movement in MOVEMENTS {
+            for _ in 0..20 {
+                let mut map = random_map(&mut rng, 12, 9, 25, movement);
+                let goal = (rng.next_below(map.width()), rng.next_below(map.height()));
+                map.set_blocked(goal.0, goal.1, false);
+                let region = GoalRegion::Cell(goal.0, goal.1);
+
+                // Moves are symmetric, so distances from the goal are distances to it.
+                let distances = reachable_set(GridState::new(&map, goal), usize::MAX);
+
+                for heuristic in HEURISTICS.into_iter().filter(|heuristic| heuristic.is_suited_to(movement)) {
+                    for (&(x, y), &distance) in &distances {
+                        let state = GridState::with_region(&map, (x, y), &region).with_heuristic(heuristic);
+                        assert!(state.h() <= distance, "{:?} under {:?} at {:?}", heuristic, movement, (x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsuited_heuristics_overestimate() {
+        let open_four_way = GridMap::new(5, 5);
+        let open_uniform = GridMap::new(5, 5).with_movement(MovementModel::EightWayUniform);
+        let region = GoalRegion::Cell(4, 4);
+
+        // Manhattan counts 8 where one diagonal walk of 4 moves reaches the goal, and the scaled
+        // octile heuristics are a thousand times off whenever every move costs 1.
+        let uniform_distance = reachable_set(GridState::new(&open_uniform, (4, 4)), usize::MAX)[&(0, 0)];
+        assert_eq!(uniform_distance, 4);
+        for heuristic in [GridHeuristic::Manhattan, GridHeuristic::Octile, GridHeuristic::Euclidean] {
+            assert!(!heuristic.is_suited_to(MovementModel::EightWayUniform));
+            assert!(GridState::with_region(&open_uniform, (0, 0), &region).with_heuristic(heuristic).h() > uniform_distance);
+        }
+
+        for heuristic in [GridHeuristic::Octile, GridHeuristic::Euclidean] {
+            assert!(!heuristic.is_suited_to(MovementModel::FourWay));
+            assert!(GridState::with_region(&open_four_way, (0, 0), &region).with_heuristic(heuristic).h() > 8);
+        }
+    }
+
+    #[test]
+    fn test_defaults_and_distances() {
+        for movement in MOVEMENTS {
+            assert!(GridHeuristic::default_for(movement).is_suited_to(movement));
+        }
+
+        assert_eq!(manhattan(3, 4), 7);
+        assert_eq!(chebyshev(3, 4), 4);
+        assert_eq!(octile(3, 4), 3 * OCTILE_DIAGONAL + OCTILE_STRAIGHT);
+        assert_eq!(euclidean(3, 4), 5 * OCTILE_STRAIGHT);
+        assert_eq!(euclidean(100, 100), octile(100, 100));
+    }
+}
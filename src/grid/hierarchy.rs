@@ -0,0 +1,761 @@
+use std::{cmp::Reverse, collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap}, fmt::Display, iter::once};
+
+use crate::{astar_state::AStarState, grid::{heuristics::GridHeuristic, map::{GridMap, GridResult}, state::{Direction, GridState}}, search_options::SearchOptions, search_stats::SearchStats, traced::{result::TracedResult, state::TracedState, traced_astar::traced_astar_with_options}};
+
+pub use crate::grid::graph::ExplicitGraph;
+
+// Longest stretch of free border cells served by a single transition, placed in its middle.
+//
+// Any path can be rerouted through the transitions by walking at most half an entrance along the
+// border on each side of a crossing, and a diagonal crossing can be replaced by two straight moves.
+// So hpa_star is at most (2 * MAX_ENTRANCE_WIDTH + 1) straight moves worse than the optimum for
+// every move between two clusters on an optimal path.
+pub const MAX_ENTRANCE_WIDTH: usize = 6;
+
+type Cell = (usize, usize);
+
+// The map cut into square clusters of `cluster_size` cells (smaller along the right and bottom
+// edges), with the abstract graph HPA* searches: one node per transition cell, an edge across
+// every transition and an edge between every two transition cells of a cluster that can reach
+// each other without leaving it.
+#[derive(Debug, Clone)]
+pub struct Clusters {
+    cluster_size: usize,
+    width: usize,
+    height: usize,
+    columns: usize,
+    rows: usize,
+    revision: u64,
+    // Transitions of each border, keyed by its two clusters with the lower index first. A
+    // transition is a pair of adjacent free cells, the first one in the lower cluster.
+    transitions: BTreeMap<(usize, usize), Vec<(Cell, Cell)>>,
+    members: Vec<Vec<Cell>>,
+    intra_edges: Vec<Vec<(Cell, Cell, usize)>>,
+    dirty_borders: BTreeSet<(usize, usize)>,
+    dirty_clusters: BTreeSet<usize>,
+    graph: ExplicitGraph,
+    nodes: HashMap<Cell, usize>
+}
+
+pub fn build_clusters(map: &GridMap, cluster_size: usize) -> Clusters {
+    Clusters::build(map, cluster_size)
+}
+
+impl Clusters {
+    pub fn build(map: &GridMap, cluster_size: usize) -> Self {
+        assert!(cluster_size > 0, "clusters need at least one cell");
+
+        let columns = map.width().div_ceil(cluster_size);
+        let rows = map.height().div_ceil(cluster_size);
+        let mut clusters = Clusters {
+            cluster_size,
+            width: map.width(),
+            height: map.height(),
+            columns,
+            rows,
+            revision: map.revision(),
+            transitions: BTreeMap::new(),
+            members: vec![Vec::new(); columns * rows],
+            intra_edges: vec![Vec::new(); columns * rows],
+            dirty_borders: BTreeSet::new(),
+            dirty_clusters: (0..columns * rows).collect(),
+            graph: ExplicitGraph::new(),
+            nodes: HashMap::new()
+        };
+
+        clusters.dirty_borders = clusters.borders().collect();
+        clusters.refresh(map);
+        clusters
+    }
+
+    pub fn cluster_size(&self) -> usize {
+        self.cluster_size
+    }
+
+    pub fn cluster_count(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    pub fn cluster_of(&self, x: usize, y: usize) -> usize {
+        (y / self.cluster_size) * self.columns + x / self.cluster_size
+    }
+
+    pub fn graph(&self) -> &ExplicitGraph {
+        &self.graph
+    }
+
+    pub fn transition_count(&self) -> usize {
+        self.transitions.values().map(Vec::len).sum()
+    }
+
+    // Whether the clusters describe the map as it is now. hpa_star refuses stale clusters.
+    pub fn is_current(&self, map: &GridMap) -> bool {
+        self.revision == map.revision()
+            && (self.width, self.height) == (map.width(), map.height())
+            && self.dirty_clusters.is_empty()
+    }
+
+    // Edits the map and marks the clusters whose transitions or inner paths the cell affects: its
+    // own cluster, plus the one across the border when the cell lies on it.
+    pub fn set_blocked(&mut self, map: &mut GridMap, x: usize, y: usize, blocked: bool) {
+        let in_sync = self.revision == map.revision();
+        map.set_blocked(x, y, blocked);
+        if in_sync {
+            self.revision = map.revision();
+        }
+
+        let cluster = self.cluster_of(x, y);
+        let (column, row) = (x / self.cluster_size, y / self.cluster_size);
+        self.dirty_clusters.insert(cluster);
+
+        let mut dirty_border = |lower: usize, upper: usize| {
+            self.dirty_borders.insert((lower, upper));
+            self.dirty_clusters.insert(lower);
+            self.dirty_clusters.insert(upper);
+        };
+        if x.is_multiple_of(self.cluster_size) && column > 0 {
+            dirty_border(cluster - 1, cluster);
+        }
+        if x % self.cluster_size == self.cluster_size - 1 && column + 1 < self.columns {
+            dirty_border(cluster, cluster + 1);
+        }
+        if y.is_multiple_of(self.cluster_size) && row > 0 {
+            dirty_border(cluster - self.columns, cluster);
+        }
+        if y % self.cluster_size == self.cluster_size - 1 && row + 1 < self.rows {
+            dirty_border(cluster, cluster + self.columns);
+        }
+    }
+
+    // Rebuilds the marked clusters and returns how many there were. A map edited without going
+    // through set_blocked can't tell which cells changed, so it is rebuilt from scratch.
+    pub fn refresh(&mut self, map: &GridMap) -> usize {
+        if self.revision != map.revision() || (self.width, self.height) != (map.width(), map.height()) {
+            *self = Clusters::build(map, self.cluster_size);
+            return self.cluster_count();
+        }
+
+        for border in std::mem::take(&mut self.dirty_borders) {
+            let transitions = self.find_transitions(map, border);
+            self.transitions.insert(border, transitions);
+        }
+
+        let dirty_clusters = std::mem::take(&mut self.dirty_clusters);
+        for &cluster in &dirty_clusters {
+            self.members[cluster] = self.transition_cells(cluster);
+            self.intra_edges[cluster] = self.find_intra_edges(map, cluster);
+        }
+
+        if !dirty_clusters.is_empty() {
+            self.rebuild_graph(map);
+        }
+
+        dirty_clusters.len()
+    }
+
+    // Every pair of neighboring clusters, lower index first.
+    fn borders(&self) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let (columns, rows) = (self.columns, self.rows);
+
+        (0..columns * rows).flat_map(move |cluster| {
+            let right = (cluster % columns + 1 < columns).then_some((cluster, cluster + 1));
+            let below = (cluster / columns + 1 < rows).then_some((cluster, cluster + columns));
+            right.into_iter().chain(below)
+        })
+    }
+
+    // Inclusive corners of the cluster.
+    fn bounds(&self, cluster: usize) -> (Cell, Cell) {
+        let min = ((cluster % self.columns) * self.cluster_size, (cluster / self.columns) * self.cluster_size);
+        let max = ((min.0 + self.cluster_size).min(self.width) - 1, (min.1 + self.cluster_size).min(self.height) - 1);
+        (min, max)
+    }
+
+    fn find_transitions(&self, map: &GridMap, (lower, upper): (usize, usize)) -> Vec<(Cell, Cell)> {
+        let (min, max) = self.bounds(lower);
+        // With a single column of clusters the one below is the next index too.
+        let vertical = lower % self.columns + 1 < self.columns && upper == lower + 1;
+        let pairs: Vec<(Cell, Cell)> = if vertical {
+            (min.1..=max.1).map(|y| ((max.0, y), (max.0 + 1, y))).collect()
+        }
+        else {
+            (min.0..=max.0).map(|x| ((x, max.1), (x, max.1 + 1))).collect()
+        };
+
+        pairs.split(|&((x, y), (other_x, other_y))| map.is_blocked(x, y) || map.is_blocked(other_x, other_y))
+            .flat_map(|entrance| entrance.chunks(MAX_ENTRANCE_WIDTH))
+            .map(|stretch| stretch[stretch.len() / 2])
+            .collect()
+    }
+
+    fn transition_cells(&self, cluster: usize) -> Vec<Cell> {
+        let mut cells = BTreeSet::new();
+        let neighbors = [
+            (cluster.wrapping_sub(1), cluster),
+            (cluster, cluster + 1),
+            (cluster.wrapping_sub(self.columns), cluster),
+            (cluster, cluster + self.columns)
+        ];
+
+        for border in neighbors {
+            for &(lower_cell, upper_cell) in self.transitions.get(&border).into_iter().flatten() {
+                cells.insert(if border.0 == cluster { lower_cell } else { upper_cell });
+            }
+        }
+
+        cells.into_iter().collect()
+    }
+
+    fn find_intra_edges(&self, map: &GridMap, cluster: usize) -> Vec<(Cell, Cell, usize)> {
+        let bounds = self.bounds(cluster);
+        let members = &self.members[cluster];
+        let mut edges = Vec::new();
+
+        for &from in members {
+            let distances = ClusterDistances::from(map, bounds, from);
+            for &to in members.iter().filter(|&&to| to != from) {
+                if let Some(cost) = distances.get(to) {
+                    edges.push((from, to, cost));
+                }
+            }
+        }
+
+        edges
+    }
+
+    fn rebuild_graph(&mut self, map: &GridMap) {
+        self.graph = ExplicitGraph::new();
+        self.nodes.clear();
+
+        for &cell in self.members.iter().flatten() {
+            self.nodes.insert(cell, self.graph.add_node(cell));
+        }
+
+        let crossing_cost = map.movement().cost(Direction::Right);
+        for &(lower_cell, upper_cell) in self.transitions.values().flatten() {
+            let (lower, upper) = (self.nodes[&lower_cell], self.nodes[&upper_cell]);
+            self.graph.add_edge(lower, upper, crossing_cost);
+            self.graph.add_edge(upper, lower, crossing_cost);
+        }
+
+        for &(from, to, cost) in self.intra_edges.iter().flatten() {
+            self.graph.add_edge(self.nodes[&from], self.nodes[&to], cost);
+        }
+    }
+
+    // (node, cost) of the transition cells of the cluster found in `distances`.
+    fn nodes_within(&self, cluster: usize, distances: &ClusterDistances) -> Vec<(usize, usize)> {
+        self.members[cluster]
+            .iter()
+            .filter_map(|cell| distances.get(*cell).map(|cost| (self.nodes[cell], cost)))
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StaleClusters;
+
+impl Display for StaleClusters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the map changed since the clusters were built, refresh them first")
+    }
+}
+
+impl std::error::Error for StaleClusters {}
+
+pub struct HpaResult<'a> {
+    pub result: GridResult<'a>,
+    // The search over the abstract graph, keyed by node.
+    pub abstract_stats: SearchStats<usize>,
+    // One search per abstract edge refined inside a cluster.
+    pub refinement_stats: Vec<SearchStats<Cell>>,
+    // Cells settled linking the start and the goal to the transitions of their clusters.
+    pub insertion_expansions: usize
+}
+
+impl HpaResult<'_> {
+    pub fn total_expansions(&self) -> usize {
+        self.insertion_expansions + self.abstract_stats.expansions + self.refinement_stats.iter().map(|stats| stats.expansions).sum::<usize>()
+    }
+}
+
+// Searches the abstract graph of the clusters, linked to the start and the goal, then refines
+// every abstract edge into grid moves. The final state carries no goal region, it is at `goal`.
+// See MAX_ENTRANCE_WIDTH for how far from optimal the path can be.
+pub fn hpa_star<'a>(map: &'a GridMap, clusters: &Clusters, start: Cell, goal: Cell) -> Result<Option<HpaResult<'a>>, StaleClusters> {
+    if !clusters.is_current(map) {
+        return Err(StaleClusters);
+    }
+
+    if map.is_blocked(start.0, start.1) || map.is_blocked(goal.0, goal.1) {
+        return Ok(None);
+    }
+
+    let start_cluster = clusters.cluster_of(start.0, start.1);
+    let goal_cluster = clusters.cluster_of(goal.0, goal.1);
+    let from_start = ClusterDistances::from(map, clusters.bounds(start_cluster), start);
+    // Moves are reversible, so distances from the goal are distances to it.
+    let to_goal = ClusterDistances::from(map, clusters.bounds(goal_cluster), goal);
+
+    let query = AbstractQuery {
+        graph: &clusters.graph,
+        start,
+        goal,
+        start_edges: clusters.nodes_within(start_cluster, &from_start),
+        goal_edges: clusters.nodes_within(goal_cluster, &to_goal).into_iter().collect(),
+        direct: if start_cluster == goal_cluster { from_start.get(goal) } else { None },
+        heuristic: GridHeuristic::default_for(map.movement())
+    };
+
+    let outcome = traced_astar_with_options(AbstractState { query: &query, node: query.start_node(), g: 0 }, SearchOptions::new());
+    let Some(abstract_result) = outcome.result else {
+        return Ok(None);
+    };
+
+    let waypoints: Vec<Cell> = once(start)
+        .chain(abstract_result.path.to_vec().into_iter().map(|node| query.position(node)))
+        .collect();
+
+    let mut path = Vec::new();
+    let mut refinement_stats = Vec::new();
+    for pair in waypoints.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let cluster = clusters.cluster_of(from.0, from.1);
+
+        if from == to {
+            continue;
+        }
+
+        if cluster == clusters.cluster_of(to.0, to.1) {
+            let local = traced_astar_with_options(ClusterState::new(map, clusters.bounds(cluster), from, to), SearchOptions::new());
+            let local_result = local.result.expect("edges inside a cluster were measured without leaving it");
+            path.extend(local_result.path.to_vec());
+            refinement_stats.push(local.stats);
+        }
+        else {
+            // Transitions are adjacent cells on either side of a border.
+            let direction = Direction::STRAIGHT.into_iter().find(|direction| direction.step(from.0, from.1) == Some(to));
+            path.push(direction.expect("transitions are adjacent"));
+        }
+    }
+
+    let final_state = path.iter().fold(GridState::new(map, start), |state, &direction| {
+        let (x, y) = state.position();
+        state.moved_to(direction.step(x, y).expect("refined moves stay on the map"), direction)
+    });
+
+    let insertion_expansions = from_start.settled() + to_goal.settled();
+    let iterations = insertion_expansions + outcome.stats.expansions + refinement_stats.iter().map(|stats| stats.expansions).sum::<usize>();
+
+    Ok(Some(HpaResult {
+        result: TracedResult::new(path, iterations, final_state),
+        abstract_stats: outcome.stats,
+        refinement_stats,
+        insertion_expansions
+    }))
+}
+
+// Cheapest costs from one cell to every cell of a cluster, moving only inside it. Dense, since
+// building the clusters runs one of these per transition cell.
+struct ClusterDistances {
+    bounds: (Cell, Cell),
+    costs: Vec<Option<usize>>
+}
+
+impl ClusterDistances {
+    fn from(map: &GridMap, bounds: (Cell, Cell), source: Cell) -> Self {
+        let mut distances = ClusterDistances {
+            bounds,
+            costs: vec![None; (bounds.1.0 - bounds.0.0 + 1) * (bounds.1.1 - bounds.0.1 + 1)]
+        };
+        let mut frontier = BinaryHeap::new();
+
+        let source_index = distances.index(source);
+        distances.costs[source_index] = Some(0);
+        frontier.push(Reverse((0, source)));
+
+        while let Some(Reverse((cost, (x, y)))) = frontier.pop() {
+            if distances.get((x, y)).is_some_and(|best| best < cost) {
+                continue;
+            }
+
+            for (target, direction) in map.moves(x, y) {
+                let target_cost = cost + map.movement().cost(direction);
+                if distances.contains(target) && distances.get(target).is_none_or(|best| best > target_cost) {
+                    let index = distances.index(target);
+                    distances.costs[index] = Some(target_cost);
+                    frontier.push(Reverse((target_cost, target)));
+                }
+            }
+        }
+
+        distances
+    }
+
+    fn contains(&self, (x, y): Cell) -> bool {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds;
+        (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)
+    }
+
+    fn index(&self, (x, y): Cell) -> usize {
+        let ((min_x, min_y), (max_x, _)) = self.bounds;
+        (y - min_y) * (max_x - min_x + 1) + x - min_x
+    }
+
+    fn get(&self, cell: Cell) -> Option<usize> {
+        if self.contains(cell) {
+            self.costs[self.index(cell)]
+        }
+        else {
+            None
+        }
+    }
+
+    // Number of cells reached.
+    fn settled(&self) -> usize {
+        self.costs.iter().flatten().count()
+    }
+}
+
+// Grid position that never leaves the inclusive bounds of a cluster, heading to `target`.
+#[derive(Clone)]
+struct ClusterState<'a> {
+    map: &'a GridMap,
+    bounds: (Cell, Cell),
+    target: Cell,
+    heuristic: GridHeuristic,
+    position: Cell,
+    g: usize
+}
+
+impl<'a> ClusterState<'a> {
+    fn new(map: &'a GridMap, bounds: (Cell, Cell), position: Cell, target: Cell) -> Self {
+        ClusterState {
+            map,
+            bounds,
+            target,
+            heuristic: GridHeuristic::default_for(map.movement()),
+            position,
+            g: 0
+        }
+    }
+
+    fn moves(&self) -> Vec<(Self, Direction)> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds;
+
+        self.map.moves(self.position.0, self.position.1)
+            .into_iter()
+            .filter(|&((x, y), _)| (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y))
+            .map(|(position, direction)| {
+                let successor = ClusterState {
+                    position,
+                    g: self.g + self.map.movement().cost(direction),
+                    ..self.clone()
+                };
+                (successor, direction)
+            })
+            .collect()
+    }
+}
+
+impl AStarState<Cell> for ClusterState<'_> {
+    fn key(&self) -> Cell {
+        self.position
+    }
+
+    fn h(&self) -> usize {
+        let (x, y) = self.target;
+        self.heuristic.distance(x.abs_diff(self.position.0), y.abs_diff(self.position.1))
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.target == self.position
+    }
+}
+
+impl TracedState<Cell, Direction> for ClusterState<'_> {
+    fn generate_traced_successors(&self) -> Vec<(Self, Direction)> {
+        self.moves()
+    }
+}
+
+// The abstract graph with the start and the goal linked in as two extra nodes after the others.
+struct AbstractQuery<'a> {
+    graph: &'a ExplicitGraph,
+    start: Cell,
+    goal: Cell,
+    start_edges: Vec<(usize, usize)>,
+    goal_edges: HashMap<usize, usize>,
+    // Cost of the path from the start to the goal inside their cluster, when they share one.
+    direct: Option<usize>,
+    heuristic: GridHeuristic
+}
+
+impl AbstractQuery<'_> {
+    fn start_node(&self) -> usize {
+        self.graph.len()
+    }
+
+    fn goal_node(&self) -> usize {
+        self.graph.len() + 1
+    }
+
+    fn position(&self, node: usize) -> Cell {
+        if node == self.start_node() {
+            self.start
+        }
+        else if node == self.goal_node() {
+            self.goal
+        }
+        else {
+            self.graph.position(node)
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AbstractState<'a> {
+    query: &'a AbstractQuery<'a>,
+    node: usize,
+    g: usize
+}
+
+impl AStarState<usize> for AbstractState<'_> {
+    fn key(&self) -> usize {
+        self.node
+    }
+
+    // Abstract edges cost as much as real paths, so the grid heuristic stays admissible.
+    fn h(&self) -> usize {
+        let (x, y) = self.query.position(self.node);
+        self.query.heuristic.distance(x.abs_diff(self.query.goal.0), y.abs_diff(self.query.goal.1))
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.node == self.query.goal_node()
+    }
+}
+
+impl TracedState<usize, usize> for AbstractState<'_> {
+    fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+        let query = self.query;
+        let goal_node = query.goal_node();
+
+        let edges: Vec<(usize, usize)> = if self.node == query.start_node() {
+            query.start_edges.iter().copied().chain(query.direct.map(|cost| (goal_node, cost))).collect()
+        }
+        else if self.node == goal_node {
+            Vec::new()
+        }
+        else {
+            query.graph.edges(self.node).iter().copied().chain(query.goal_edges.get(&self.node).map(|&cost| (goal_node, cost))).collect()
+        };
+
+        edges.into_iter()
+            .map(|(node, cost)| (AbstractState { query, node, g: self.g + cost }, node))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid::{movement::MovementModel, region::GoalRegion}, rng::SplitMix64, test_support::{random_free_cell, random_map}};
+
+    const MOVEMENTS: [MovementModel; 3] = [MovementModel::FourWay, MovementModel::EightWayUniform, MovementModel::EightWayOctile];
+
+    // Cost, moves and expansions of flat A*.
+    fn flat_astar(map: &GridMap, start: Cell, goal: Cell) -> Option<(usize, Vec<Direction>, usize)> {
+        let region = GoalRegion::Cell(goal.0, goal.1);
+        let outcome = traced_astar_with_options(GridState::with_region(map, start, &region), SearchOptions::new());
+        outcome.result.map(|result| (result.final_state.g(), result.path.to_vec(), outcome.stats.expansions))
+    }
+
+    // Checks every move against the map and returns the cell reached and the cost paid.
+    fn walk(map: &GridMap, start: Cell, path: &[Direction]) -> (Cell, usize) {
+        path.iter().fold((start, 0), |((x, y), cost), &direction| {
+            let (target, _) = map.moves(x, y).into_iter().find(|&(_, legal)| legal == direction).expect("illegal move");
+            (target, cost + map.movement().cost(direction))
+        })
+    }
+
+    fn crossings(clusters: &Clusters, start: Cell, path: &[Direction]) -> usize {
+        let mut position = start;
+        let mut count = 0;
+
+        for direction in path {
+            let next = direction.step(position.0, position.1).unwrap();
+            if clusters.cluster_of(position.0, position.1) != clusters.cluster_of(next.0, next.1) {
+                count += 1;
+            }
+            position = next;
+        }
+
+        count
+    }
+
+    #[test]
+    fn test_costs_stay_within_the_bound() {
+        let mut rng = SplitMix64::new(17);
+
+        for movement in MOVEMENTS {
+            // Neither side is a multiple of the cluster size, so the last clusters are smaller.
+            let map = random_map(&mut rng, 64, 45, 25, movement);
+            let clusters = build_clusters(&map, 10);
+            let detour = (2 * MAX_ENTRANCE_WIDTH + 1) * movement.cost(Direction::Right);
+
+            for _ in 0..40 {
+                let start = random_free_cell(&mut rng, &map);
+                let goal = random_free_cell(&mut rng, &map);
+                let flat = flat_astar(&map, start, goal);
+                let hierarchical = hpa_star(&map, &clusters, start, goal).unwrap();
+                assert_eq!(flat.is_some(), hierarchical.is_some(), "{:?} to {:?} under {:?}", start, goal, movement);
+
+                if let (Some((optimum, optimal_path, _)), Some(hierarchical)) = (flat, hierarchical) {
+                    let path = hierarchical.result.path.to_vec();
+                    let cost = hierarchical.result.final_state.g();
+
+                    assert_eq!(walk(&map, start, &path), (goal, cost));
+                    assert_eq!(hierarchical.result.final_state.position(), goal);
+                    assert!(cost >= optimum);
+                    assert!(cost <= optimum + crossings(&clusters, start, &optimal_path) * detour, "{} against {} under {:?}", cost, optimum, movement);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_expansion_savings_on_a_large_map() {
+        let mut rng = SplitMix64::new(5);
+        let mut map = random_map(&mut rng, 512, 512, 20, MovementModel::FourWay);
+        let (start, goal) = ((0, 0), (511, 511));
+        map.set_blocked(start.0, start.1, false);
+        map.set_blocked(goal.0, goal.1, false);
+
+        // Walls with a gap at alternating ends turn the map into a serpentine, which flat A* can
+        // only get through by expanding most of it.
+        for wall in 1..8 {
+            let gap = if wall % 2 == 1 { 504..512 } else { 0..8 };
+            for x in (0..512).filter(|x| !gap.contains(x)) {
+                map.set_blocked(x, wall * 64, true);
+            }
+        }
+
+        let clusters = build_clusters(&map, 16);
+        let (optimum, _, flat_expansions) = flat_astar(&map, start, goal).unwrap();
+        let hierarchical = hpa_star(&map, &clusters, start, goal).unwrap().unwrap();
+
+        assert!(hierarchical.result.final_state.g() <= optimum + optimum / 10);
+        assert!(hierarchical.total_expansions() * 5 < flat_expansions, "{} against {}", hierarchical.total_expansions(), flat_expansions);
+    }
+
+    #[test]
+    fn test_same_cluster_and_unreachable_goals() {
+        let map = GridMap::from_ascii("
+            ....#...
+            ....#...
+            ....#...
+            ....#...
+        ");
+        let clusters = build_clusters(&map, 4);
+
+        let result = hpa_star(&map, &clusters, (0, 0), (3, 3)).unwrap().unwrap();
+        assert_eq!(result.result.final_state.g(), 6);
+        assert_eq!(walk(&map, (0, 0), &result.result.path.to_vec()), ((3, 3), 6));
+
+        let result = hpa_star(&map, &clusters, (2, 2), (2, 2)).unwrap().unwrap();
+        assert!(result.result.path.is_empty());
+
+        assert!(hpa_star(&map, &clusters, (0, 0), (7, 3)).unwrap().is_none());
+        assert!(hpa_star(&map, &clusters, (0, 0), (4, 0)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_single_column_and_row_of_clusters() {
+        for movement in MOVEMENTS {
+            let detour = (2 * MAX_ENTRANCE_WIDTH + 1) * movement.cost(Direction::Right);
+            for (width, height, goal) in [(2, 4, (0, 2)), (4, 2, (2, 0))] {
+                let map = GridMap::new(width, height).with_movement(movement);
+                let clusters = build_clusters(&map, 2);
+                let (optimum, _, _) = flat_astar(&map, (0, 0), goal).unwrap();
+                let result = hpa_star(&map, &clusters, (0, 0), goal).unwrap().unwrap().result;
+                let cost = result.final_state.g();
+                assert_eq!(walk(&map, (0, 0), &result.path.to_vec()), (goal, cost));
+                assert!(cost <= optimum + detour, "{} against {} under {:?}", cost, optimum, movement);
+            }
+        }
+    }
+
+    // hpa_star finds a path whenever flat A* does, whatever the layout of the clusters.
+    #[test]
+    fn test_finds_every_reachable_goal() {
+        let mut rng = SplitMix64::new(23);
+        for movement in MOVEMENTS {
+            for (width, height, cluster_size) in [(3, 17, 4), (17, 3, 4), (4, 4, 4), (9, 13, 3), (13, 9, 5)] {
+                let map = random_map(&mut rng, width, height, 20, movement);
+                let clusters = build_clusters(&map, cluster_size);
+                for _ in 0..20 {
+                    let start = random_free_cell(&mut rng, &map);
+                    let goal = random_free_cell(&mut rng, &map);
+                    let region = GoalRegion::Cell(goal.0, goal.1);
+                    let flat = map.astar_to_region(start, &region).is_some();
+                    let hierarchical = hpa_star(&map, &clusters, start, goal).unwrap().is_some();
+                    assert_eq!(hierarchical, flat, "{:?} to {:?} on {}x{} by {} under {:?}", start, goal, width, height, cluster_size, movement);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_blocked_invalidates_affected_clusters() {
+        let mut map = GridMap::new(40, 40);
+        let mut clusters = build_clusters(&map, 10);
+        assert_eq!(clusters.cluster_count(), 16);
+
+        // Inside a cluster only that cluster changes, on a border the one across it does too.
+        clusters.set_blocked(&mut map, 5, 5, true);
+        assert_eq!(hpa_star(&map, &clusters, (0, 0), (39, 39)).err(), Some(StaleClusters));
+        assert_eq!(clusters.refresh(&map), 1);
+        clusters.set_blocked(&mut map, 9, 15, true);
+        assert_eq!(clusters.refresh(&map), 2);
+        clusters.set_blocked(&mut map, 19, 19, true);
+        assert_eq!(clusters.refresh(&map), 3);
+
+        // A wall with a single gap, the clusters must route every path through it.
+        for y in 0..40 {
+            clusters.set_blocked(&mut map, 20, y, y != 37);
+        }
+        assert!(clusters.refresh(&map) < clusters.cluster_count());
+        let fresh = build_clusters(&map, 10);
+        assert_eq!(clusters.graph().len(), fresh.graph().len());
+        assert_eq!(clusters.graph().edge_count(), fresh.graph().edge_count());
+
+        let (optimum, _, _) = flat_astar(&map, (0, 0), (39, 0)).unwrap();
+        for clusters in [&clusters, &fresh] {
+            let cost = hpa_star(&map, clusters, (0, 0), (39, 0)).unwrap().unwrap().result.final_state.g();
+            assert!(cost >= optimum && cost <= optimum + 4 * (2 * MAX_ENTRANCE_WIDTH + 1));
+        }
+
+        // Edits that bypass the clusters force a full rebuild.
+        map.set_blocked(20, 37, true);
+        assert!(!clusters.is_current(&map));
+        assert_eq!(clusters.refresh(&map), clusters.cluster_count());
+        assert!(hpa_star(&map, &clusters, (0, 0), (39, 0)).unwrap().is_none());
+    }
+}
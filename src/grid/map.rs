@@ -0,0 +1,406 @@
+use crate::{grid::{heuristics::{GridHeuristic, HeuristicMismatch}, movement::MovementModel, region::GoalRegion, state::{Direction, GridState}}, traced::{result::TracedResult, to_goal::traced_astar_to_goal}, untraced::reachable_set::reachable_set};
+
+pub type GridResult<'a> = TracedResult<GridState<'a>, (usize, usize), Direction>;
+
+// Grid of free and blocked cells, 4-connected with unit move costs unless another movement model
+// is set. Cells are addressed as (x, y) with (0, 0) at the top left.
+#[derive(Clone, Debug)]
+pub struct GridMap {
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    movement: MovementModel,
+    revision: u64
+}
+
+impl GridMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        GridMap {
+            width,
+            height,
+            blocked: vec![false; width * height],
+            movement: MovementModel::FourWay,
+            revision: 0
+        }
+    }
+
+    pub fn with_movement(mut self, movement: MovementModel) -> Self {
+        self.movement = movement;
+        self
+    }
+
+    pub fn movement(&self) -> MovementModel {
+        self.movement
+    }
+
+    // Builds a map from rows of text where '#' is a blocked cell and anything else is free.
+    pub fn from_ascii(text: &str) -> Self {
+        let rows: Vec<&str> = text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let height = rows.len();
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        let mut map = GridMap::new(width, height);
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == '#' {
+                    map.set_blocked(x, y, true);
+                }
+            }
+        }
+
+        map
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    // Out of bounds cells count as blocked.
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        !self.in_bounds(x, y) || self.blocked[self.index(x, y)]
+    }
+
+    pub fn set_blocked(&mut self, x: usize, y: usize, blocked: bool) {
+        let index = self.index(x, y);
+        self.blocked[index] = blocked;
+        self.revision += 1;
+    }
+
+    // Number of set_blocked calls so far, so caches built from the map can tell it changed.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    // Straight moves first, then the diagonal ones if the movement model allows them.
+    pub fn moves(&self, x: usize, y: usize) -> Vec<((usize, usize), Direction)> {
+        let mut moves = Vec::with_capacity(8);
+
+        for direction in Direction::STRAIGHT {
+            if let Some(target) = self.free_step(x, y, direction) {
+                moves.push((target, direction));
+            }
+        }
+
+        if self.movement.allows_diagonals() {
+            for direction in Direction::DIAGONAL {
+                // Both cells beside the diagonal must be free, so moves never cut corners.
+                if let Some((target_x, target_y)) = self.free_step(x, y, direction)
+                    && !self.is_blocked(target_x, y)
+                    && !self.is_blocked(x, target_y)
+                {
+                    moves.push(((target_x, target_y), direction));
+                }
+            }
+        }
+
+        moves
+    }
+
+    fn free_step(&self, x: usize, y: usize, direction: Direction) -> Option<(usize, usize)> {
+        direction.step(x, y).filter(|&(target_x, target_y)| !self.is_blocked(target_x, target_y))
+    }
+
+    pub fn neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        self.moves(x, y)
+            .into_iter()
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    // Row-major mask of the cells reachable from `start` with cost <= bound.
+    pub fn reachable_mask(&self, start: (usize, usize), bound: usize) -> Vec<bool> {
+        let mut mask = vec![false; self.width * self.height];
+
+        if self.is_blocked(start.0, start.1) {
+            return mask;
+        }
+
+        for (x, y) in reachable_set(GridState::new(self, start), bound).into_keys() {
+            mask[self.index(x, y)] = true;
+        }
+
+        mask
+    }
+
+    // Cheapest path from `start` to any cell of the region, guided by the default heuristic of
+    // the movement model.
+    pub fn astar_to_region<'a>(&'a self, start: (usize, usize), region: &'a GoalRegion<'a>) -> Option<GridResult<'a>> {
+        if self.is_blocked(start.0, start.1) {
+            return None;
+        }
+
+        self.search_to_region(start, region, GridHeuristic::default_for(self.movement))
+    }
+
+    // astar_to_region with another heuristic, rejected unless it suits the movement model.
+    pub fn astar_with_heuristic<'a>(&'a self, start: (usize, usize), region: &'a GoalRegion<'a>, heuristic: GridHeuristic) -> Result<Option<GridResult<'a>>, HeuristicMismatch> {
+        if !heuristic.is_suited_to(self.movement) {
+            return Err(HeuristicMismatch {
+                heuristic,
+                movement: self.movement
+            });
+        }
+
+        if self.is_blocked(start.0, start.1) {
+            return Ok(None);
+        }
+
+        Ok(self.search_to_region(start, region, heuristic))
+    }
+
+    // The region is the goal of the search rather than part of every state, so the final state
+    // of the result has no region.
+    fn search_to_region<'a>(&'a self, start: (usize, usize), region: &'a GoalRegion<'a>, heuristic: GridHeuristic) -> Option<GridResult<'a>> {
+        traced_astar_to_goal(
+            GridState::new(self, start).with_heuristic(heuristic),
+            region,
+            heuristic,
+            |state: &GridState, region: &&GoalRegion| {
+                let (x, y) = state.position();
+                region.contains(x, y)
+            }
+        )
+    }
+}
+
+// Maps are equal when their cells and movement are, however they were edited.
+impl PartialEq for GridMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.blocked == other.blocked && self.movement == other.movement
+    }
+}
+
+impl Eq for GridMap {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::{astar_state::AStarState, grid::movement::{OCTILE_DIAGONAL, OCTILE_STRAIGHT}};
+
+    const MAZE: &str = "
+        ..#.....
+        .##.###.
+        ....#...
+        ###.#.#.
+        ....#.#.
+        .####.#.
+        ......#.
+    ";
+
+    fn bfs_distances(map: &GridMap, start: (usize, usize)) -> Vec<Option<usize>> {
+        let mut distances = vec![None; map.width() * map.height()];
+        let mut queue = VecDeque::new();
+
+        distances[map.index(start.0, start.1)] = Some(0);
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            let distance = distances[map.index(x, y)].unwrap();
+            for (nx, ny) in map.neighbors(x, y) {
+                let index = map.index(nx, ny);
+                if distances[index].is_none() {
+                    distances[index] = Some(distance + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        distances
+    }
+
+    // Multi-source BFS from every free cell of the region.
+    fn distances_to_region(map: &GridMap, region: &GoalRegion) -> Vec<Option<usize>> {
+        let mut distances = vec![None; map.width() * map.height()];
+        let mut queue = VecDeque::new();
+
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                if !map.is_blocked(x, y) && region.contains(x, y) {
+                    distances[map.index(x, y)] = Some(0);
+                    queue.push_back((x, y));
+                }
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let distance = distances[map.index(x, y)].unwrap();
+            for (nx, ny) in map.neighbors(x, y) {
+                let index = map.index(nx, ny);
+                if distances[index].is_none() {
+                    distances[index] = Some(distance + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        distances
+    }
+
+    fn test_regions() -> Vec<GoalRegion<'static>> {
+        vec![
+            GoalRegion::Cell(7, 6),
+            GoalRegion::Cell(5, 4),
+            GoalRegion::Rect { min: (5, 2), max: (7, 3) },
+            GoalRegion::Rect { min: (0, 6), max: (2, 6) },
+            GoalRegion::Disc { center: (6, 5), radius: 1 },
+            GoalRegion::Disc { center: (3, 4), radius: 2 },
+            GoalRegion::Predicate(Box::new(|x, _| x == 7))
+        ]
+    }
+
+    #[test]
+    fn test_region_heuristics_are_admissible() {
+        let map = GridMap::from_ascii(MAZE);
+        let open_map = GridMap::new(9, 9);
+
+        for map in [&map, &open_map] {
+            for region in test_regions() {
+                let distances = distances_to_region(map, &region);
+
+                for y in 0..map.height() {
+                    for x in 0..map.width() {
+                        if let Some(distance) = distances[map.index(x, y)] {
+                            assert!(region.heuristic_to(x, y) <= distance);
+                            if region.contains(x, y) {
+                                assert_eq!(region.heuristic_to(x, y), 0);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_astar_to_region_is_optimal() {
+        let map = GridMap::from_ascii(MAZE);
+
+        for region in test_regions() {
+            let distances = distances_to_region(&map, &region);
+
+            for start in [(0, 0), (3, 0), (0, 4), (7, 0)] {
+                let result = map.astar_to_region(start, &region);
+                let expected = distances[map.index(start.0, start.1)];
+
+                assert_eq!(result.as_ref().map(|result| result.final_state.g()), expected);
+                assert_eq!(result.as_ref().map(|result| result.path.len()), expected);
+                if let Some(result) = result {
+                    let (x, y) = result.final_state.position();
+                    assert!(region.contains(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_predicate_borrowing_the_caller() {
+        let map = GridMap::from_ascii(MAZE);
+        let exits = [(7, 6), (0, 6)];
+        let region = GoalRegion::Predicate(Box::new(|x, y| exits.contains(&(x, y))));
+
+        let cost = map.astar_to_region((0, 0), &region).map(|result| result.final_state.g());
+        let closest = exits.iter()
+            .filter_map(|&(x, y)| map.astar_to_region((0, 0), &GoalRegion::Cell(x, y)).map(|result| result.final_state.g()))
+            .min();
+        assert_eq!(cost, closest);
+    }
+
+    #[test]
+    fn test_from_ascii() {
+        let map = GridMap::from_ascii(MAZE);
+
+        assert_eq!(map.width(), 8);
+        assert_eq!(map.height(), 7);
+        assert!(map.is_blocked(2, 0));
+        assert!(!map.is_blocked(0, 0));
+        assert!(map.is_blocked(8, 0));
+        assert_eq!(map.neighbors(0, 0), vec![(0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn test_reachable_set_matches_bfs() {
+        let map = GridMap::from_ascii(MAZE);
+        let start = (0, 0);
+        let distances = bfs_distances(&map, start);
+
+        for bound in [0, 1, 3, 7, 12, 40] {
+            let reached = reachable_set(GridState::new(&map, start), bound);
+
+            for y in 0..map.height() {
+                for x in 0..map.width() {
+                    let expected = distances[map.index(x, y)].filter(|&distance| distance <= bound);
+                    assert_eq!(reached.get(&(x, y)).copied(), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_movement_models() {
+        let room = GridMap::from_ascii("
+            ........
+            ..##....
+            ..#.....
+            .....#..
+            ........
+        ");
+        let region = GoalRegion::Cell(7, 4);
+
+        // On an open map the octile optimum would be 4 diagonals and 3 straight moves, the walls
+        // leave room for only 3 diagonals.
+        for (movement, expected) in [
+            (MovementModel::FourWay, 11),
+            (MovementModel::EightWayUniform, 8),
+            (MovementModel::EightWayOctile, 5 * OCTILE_STRAIGHT + 3 * OCTILE_DIAGONAL)
+        ] {
+            let map = room.clone().with_movement(movement);
+            let distances = reachable_set(GridState::new(&map, (7, 4)), usize::MAX);
+            assert_eq!(distances[&(0, 0)], expected);
+            assert_eq!(map.astar_to_region((0, 0), &region).unwrap().final_state.g(), expected);
+
+            for heuristic in [GridHeuristic::Manhattan, GridHeuristic::Chebyshev, GridHeuristic::Octile, GridHeuristic::Euclidean] {
+                match map.astar_with_heuristic((0, 0), &region, heuristic) {
+                    Ok(result) => assert_eq!(result.unwrap().final_state.g(), expected),
+                    Err(mismatch) => {
+                        assert!(!heuristic.is_suited_to(movement));
+                        assert_eq!(mismatch, HeuristicMismatch { heuristic, movement });
+                    }
+                }
+            }
+        }
+
+        // Up-right and down-left from (3, 2) would cut the corners of the walls at (3, 1) and (2, 2).
+        let uniform = room.with_movement(MovementModel::EightWayUniform);
+        assert_eq!(uniform.neighbors(3, 2), vec![(3, 3), (4, 2), (4, 3)]);
+    }
+
+    #[test]
+    fn test_reachable_mask() {
+        let map = GridMap::from_ascii(MAZE);
+        let distances = bfs_distances(&map, (3, 2));
+        let mask = map.reachable_mask((3, 2), 5);
+
+        for (index, &reachable) in mask.iter().enumerate() {
+            assert_eq!(reachable, distances[index].is_some_and(|distance| distance <= 5));
+        }
+        assert!(map.reachable_mask((2, 0), 5).iter().all(|&reachable| !reachable));
+    }
+}
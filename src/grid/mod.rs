@@ -0,0 +1,7 @@
+pub mod map;
+pub mod state;
+pub mod region;
+pub mod movement;
+pub mod heuristics;
+pub mod graph;
+pub mod hierarchy;
@@ -0,0 +1,33 @@
+use crate::grid::state::Direction;
+
+// Costs of the octile model, where a diagonal move costs sqrt(2) times a straight one. Scaling
+// both by 1000 keeps every cost and heuristic in integers.
+pub const OCTILE_STRAIGHT: usize = 1000;
+pub const OCTILE_DIAGONAL: usize = 1414;
+
+// Which moves a grid map allows and what they cost. Diagonal moves never cut corners: both cells
+// beside the diagonal must be free as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MovementModel {
+    // Up, down, left and right, every move costs 1.
+    FourWay,
+    // Straight and diagonal moves, every move costs 1.
+    EightWayUniform,
+    // Straight and diagonal moves costing OCTILE_STRAIGHT and OCTILE_DIAGONAL.
+    EightWayOctile
+}
+
+impl MovementModel {
+    pub fn allows_diagonals(self) -> bool {
+        self != MovementModel::FourWay
+    }
+
+    pub fn cost(self, direction: Direction) -> usize {
+        match (self, direction.is_diagonal()) {
+            (MovementModel::EightWayOctile, false) => OCTILE_STRAIGHT,
+            (MovementModel::EightWayOctile, true) => OCTILE_DIAGONAL,
+            _ => 1
+        }
+    }
+}
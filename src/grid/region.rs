@@ -0,0 +1,73 @@
+// Goal regions for grid searches. `heuristic_to` is a lower bound on the number of 4-connected
+// unit moves needed to reach the closest cell of the region, so it is admissible on any map
+// (walls only make the real distance longer). 'a is that of whatever a predicate borrows.
+#[non_exhaustive]
+pub enum GoalRegion<'a> {
+    Cell(usize, usize),
+    // Inclusive corners (min_x, min_y) and (max_x, max_y).
+    Rect { min: (usize, usize), max: (usize, usize) },
+    // Cells whose euclidean distance to the center is at most `radius`.
+    Disc { center: (usize, usize), radius: usize },
+    // Arbitrary membership test, its heuristic is always 0.
+    Predicate(Box<dyn Fn(usize, usize) -> bool + 'a>)
+}
+
+impl GoalRegion<'_> {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        match self {
+            GoalRegion::Cell(cell_x, cell_y) => (x, y) == (*cell_x, *cell_y),
+            GoalRegion::Rect { min, max } => {
+                (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y)
+            }
+            GoalRegion::Disc { center, radius } => {
+                let dx = x.abs_diff(center.0);
+                let dy = y.abs_diff(center.1);
+                dx * dx + dy * dy <= radius * radius
+            }
+            GoalRegion::Predicate(predicate) => predicate(x, y)
+        }
+    }
+
+    pub fn heuristic_to(&self, x: usize, y: usize) -> usize {
+        match self {
+            GoalRegion::Cell(cell_x, cell_y) => x.abs_diff(*cell_x) + y.abs_diff(*cell_y),
+            GoalRegion::Rect { min, max } => {
+                distance_to_range(x, min.0, max.0) + distance_to_range(y, min.1, max.1)
+            }
+            GoalRegion::Disc { center, radius } => {
+                // Manhattan distance to any cell of the disc is at least the euclidean distance,
+                // which in turn is at least the distance to the center minus the radius.
+                let dx = x.abs_diff(center.0) as f64;
+                let dy = y.abs_diff(center.1) as f64;
+                let outside = (dx * dx + dy * dy).sqrt() - *radius as f64;
+                if outside <= 0.0 {
+                    0
+                }
+                else {
+                    outside.floor() as usize
+                }
+            }
+            GoalRegion::Predicate(_) => 0
+        }
+    }
+
+    // Lower bounds on |dx| and |dy| from (x, y) to the closest cell of the region, for the
+    // heuristics of other movement models. A disc is replaced by its bounding box.
+    pub fn offsets_to(&self, x: usize, y: usize) -> (usize, usize) {
+        match self {
+            GoalRegion::Cell(cell_x, cell_y) => (x.abs_diff(*cell_x), y.abs_diff(*cell_y)),
+            GoalRegion::Rect { min, max } => {
+                (distance_to_range(x, min.0, max.0), distance_to_range(y, min.1, max.1))
+            }
+            GoalRegion::Disc { center, radius } => (
+                distance_to_range(x, center.0.saturating_sub(*radius), center.0 + radius),
+                distance_to_range(y, center.1.saturating_sub(*radius), center.1 + radius)
+            ),
+            GoalRegion::Predicate(_) => (0, 0)
+        }
+    }
+}
+
+fn distance_to_range(value: usize, min: usize, max: usize) -> usize {
+    min.saturating_sub(value) + value.saturating_sub(max)
+}
@@ -0,0 +1,236 @@
+use crate::{astar_state::AStarState, grid::{heuristics::{GridHeuristic, Heuristic}, map::GridMap, region::GoalRegion}, traced::state::TracedState, untraced::state::UntracedState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight
+}
+
+impl Direction {
+    pub const STRAIGHT: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+    pub const DIAGONAL: [Direction; 4] = [Direction::UpLeft, Direction::UpRight, Direction::DownLeft, Direction::DownRight];
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        Direction::DIAGONAL.contains(&self)
+    }
+
+    // (dx, dy) of the move, with y growing downwards.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1)
+        }
+    }
+
+    // Cell reached by moving from (x, y), or None when it would leave the first quadrant.
+    pub fn step(self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (dx, dy) = self.offset();
+        Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?))
+    }
+}
+
+// Position on a grid map. Without a goal region the heuristic is 0 and no state is a goal, which
+// is what exhaustive expansions such as `reachable_set` want. The heuristic defaults to the one
+// suited to the movement model of the map.
+#[derive(Clone)]
+pub struct GridState<'a> {
+    map: &'a GridMap,
+    region: Option<&'a GoalRegion<'a>>,
+    heuristic: GridHeuristic,
+    position: (usize, usize),
+    // Direction of the move that reached the position, None at the start.
+    heading: Option<Direction>,
+    g: usize
+}
+
+impl<'a> GridState<'a> {
+    pub fn new(map: &'a GridMap, start: (usize, usize)) -> Self {
+        GridState {
+            map,
+            region: None,
+            heuristic: GridHeuristic::default_for(map.movement()),
+            position: start,
+            heading: None,
+            g: 0
+        }
+    }
+
+    pub fn with_region(map: &'a GridMap, start: (usize, usize), region: &'a GoalRegion<'a>) -> Self {
+        GridState {
+            region: Some(region),
+            ..GridState::new(map, start)
+        }
+    }
+
+    // Replaces the default heuristic without checking it against the movement model, see
+    // `GridMap::astar_with_heuristic` for the checked version.
+    pub fn with_heuristic(mut self, heuristic: GridHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    pub fn region(&self) -> Option<&'a GoalRegion<'a>> {
+        self.region
+    }
+
+    pub fn heuristic(&self) -> GridHeuristic {
+        self.heuristic
+    }
+
+    pub fn heading(&self) -> Option<Direction> {
+        self.heading
+    }
+
+    pub(crate) fn moved_to(&self, position: (usize, usize), direction: Direction) -> Self {
+        GridState {
+            position,
+            heading: Some(direction),
+            g: self.g + self.map.movement().cost(direction),
+            ..self.clone()
+        }
+    }
+}
+
+impl AStarState<(usize, usize)> for GridState<'_> {
+    fn key(&self) -> (usize, usize) {
+        self.position
+    }
+
+    fn h(&self) -> usize {
+        self.heuristic.estimate(self)
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        let (x, y) = self.position;
+        self.region.is_some_and(|region| region.contains(x, y))
+    }
+}
+
+impl UntracedState<(usize, usize)> for GridState<'_> {
+    fn generate_successors(&self) -> Vec<Self> {
+        let (x, y) = self.position;
+
+        self.map.moves(x, y)
+            .into_iter()
+            .map(|(position, direction)| self.moved_to(position, direction))
+            .collect()
+    }
+}
+
+impl TracedState<(usize, usize), Direction> for GridState<'_> {
+    fn generate_traced_successors(&self) -> Vec<(Self, Direction)> {
+        let (x, y) = self.position;
+
+        self.map.moves(x, y)
+            .into_iter()
+            .map(|(position, direction)| (self.moved_to(position, direction), direction))
+            .collect()
+    }
+
+    // A corridor cell, with one move back the way the state came and one other, forces the
+    // other: going back never shortens a path when every move costs something.
+    fn forced_successor(&self) -> Option<(Self, Direction)> {
+        let back = self.heading?.opposite();
+        let (x, y) = self.position;
+        let moves = self.map.moves(x, y);
+        if moves.len() != 2 || !moves.iter().any(|&(_, direction)| direction == back) {
+            return None;
+        }
+
+        moves.into_iter()
+            .find(|&(_, direction)| direction != back)
+            .map(|(position, direction)| (self.moved_to(position, direction), direction))
+    }
+}
+
+// Moves are reversible, so the predecessors are the neighbors with the move turned around.
+#[cfg(feature = "bidirectional")]
+impl crate::bidirectional::BidirectionalState<(usize, usize), Direction> for GridState<'_> {
+    fn generate_predecessors(&self) -> Vec<(Self, Direction)> {
+        let (x, y) = self.position;
+
+        self.map.moves(x, y)
+            .into_iter()
+            .map(|(position, direction)| (self.moved_to(position, direction), direction.opposite()))
+            .collect()
+    }
+
+    fn reverse_h(&self, start: &Self) -> usize {
+        let dx = self.position.0.abs_diff(start.position.0);
+        let dy = self.position.1.abs_diff(start.position.1);
+        self.heuristic.distance(dx, dy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{search_options::SearchOptions, traced::{replay::replay, traced_astar::{traced_astar, traced_astar_with_options}}};
+
+    const MAZE: &str = include_str!("../fixtures/data/maze_small.txt");
+
+    #[test]
+    fn test_corridors_force_their_way_on() {
+        let map = GridMap::from_ascii(MAZE);
+        let region = GoalRegion::Cell(19, 11);
+        let start = GridState::with_region(&map, (1, 1), &region);
+        assert!(start.forced_successor().is_none());
+
+        // Stepping right from the start enters the corridor along the top row.
+        let (entered, _) = start.generate_traced_successors().pop().unwrap();
+        let (forced, direction) = entered.forced_successor().unwrap();
+        assert_eq!((forced.position(), direction), ((3, 1), Direction::Right));
+
+        let plain = traced_astar_with_options(start.clone(), SearchOptions::new().with_forced_successors(false)).stats;
+        let checked = traced_astar_with_options(start.clone(), SearchOptions::new().with_forced_check(true));
+        assert!(checked.validation_error.is_none());
+        let result = checked.result.as_ref().unwrap();
+        assert_eq!(result.cost, traced_astar(start.clone()).unwrap().cost);
+        assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+
+        // Most of the maze is corridors: the same states are expanded, but only 10 of them
+        // through the generator, and the moves back are never generated.
+        let stats = &checked.stats;
+        assert_eq!((plain.expansions, plain.forced), (55, 0));
+        assert_eq!((stats.expansions, stats.forced), (55, 45));
+        assert!(stats.generated + 40 < plain.generated, "{} vs {}", stats.generated, plain.generated);
+    }
+}
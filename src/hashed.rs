@@ -0,0 +1,229 @@
+use std::{borrow::Borrow, collections::{HashMap, HashSet}, hash::{BuildHasherDefault, Hash, Hasher}};
+
+// A key with its hash computed once, so maps keyed by it never hash the key again. Equal keys
+// must come with equal hashes, i.e. from the same BuildHasher. Equality compares the hashes
+// first and falls back to K: Eq when they collide.
+#[derive(Debug, Clone)]
+pub(crate) struct Hashed<K> {
+    hash: u64,
+    key: K
+}
+
+impl<K> Hashed<K> {
+    pub fn new(hash: u64, key: K) -> Self {
+        Hashed { hash, key }
+    }
+
+    pub fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+// A hash and a key, owned or borrowed, so maps of Hashed keys can be queried without cloning
+// the key into a Hashed.
+pub(crate) trait HashedKey<K> {
+    fn hash_value(&self) -> u64;
+    fn key(&self) -> &K;
+}
+
+impl<K> HashedKey<K> for Hashed<K> {
+    fn hash_value(&self) -> u64 {
+        self.hash
+    }
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K> HashedKey<K> for (u64, &K) {
+    fn hash_value(&self) -> u64 {
+        self.0
+    }
+
+    fn key(&self) -> &K {
+        self.1
+    }
+}
+
+impl<K: Eq> PartialEq for dyn HashedKey<K> + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash_value() == other.hash_value() && self.key() == other.key()
+    }
+}
+
+impl<K: Eq> Eq for dyn HashedKey<K> + '_ {}
+
+impl<K> Hash for dyn HashedKey<K> + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash_value());
+    }
+}
+
+impl<K: Eq> PartialEq for Hashed<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.key == other.key
+    }
+}
+
+impl<K: Eq> Eq for Hashed<K> {}
+
+// Must hash like the dyn HashedKey it borrows as.
+impl<K> Hash for Hashed<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl<'a, K: 'a> Borrow<dyn HashedKey<K> + 'a> for Hashed<K> {
+    fn borrow(&self) -> &(dyn HashedKey<K> + 'a) {
+        self
+    }
+}
+
+// Hasher of maps keyed by Hashed, passing the cached hash through.
+#[derive(Default)]
+pub(crate) struct PassThrough(u64);
+
+impl Hasher for PassThrough {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+
+    // Only reached by a key type that isn't Hashed.
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+pub(crate) type HashedMap<K, V> = HashMap<Hashed<K>, V, BuildHasherDefault<PassThrough>>;
+pub(crate) type HashedSet<K> = HashSet<Hashed<K>, BuildHasherDefault<PassThrough>>;
+
+// A hash and a borrowed key as a query for HashedMap and HashedSet.
+pub(crate) fn query<'q, K>(query: &'q (u64, &'q K)) -> &'q (dyn HashedKey<K> + 'q) {
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{astar_state::AStarState, open_list::OpenList, traced::{state::TracedState, traced_astar::{traced_astar, traced_astar_with_queue}}, untraced::{state::UntracedState, untraced_astar::{untraced_astar, untraced_astar_with_queue}}};
+
+    const SIZE: usize = 8;
+
+    thread_local! {
+        static HASHES: Cell<usize> = const { Cell::new(0) };
+        static GENERATED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // A cell of an open SIZE x SIZE grid that counts how often it is hashed.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Counted(usize, usize);
+
+    impl Hash for Counted {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            HASHES.with(|hashes| hashes.set(hashes.get() + 1));
+            (self.0, self.1).hash(state);
+        }
+    }
+
+    #[derive(Clone)]
+    struct Walker {
+        cell: (usize, usize),
+        g: usize
+    }
+
+    impl AStarState<Counted> for Walker {
+        fn key(&self) -> Counted {
+            Counted(self.cell.0, self.cell.1)
+        }
+
+        fn h(&self) -> usize {
+            (SIZE - 1 - self.cell.0) + (SIZE - 1 - self.cell.1)
+        }
+
+        fn f(&self) -> usize {
+            self.g + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.cell == (SIZE - 1, SIZE - 1)
+        }
+    }
+
+    impl TracedState<Counted, (usize, usize)> for Walker {
+        fn generate_traced_successors(&self) -> Vec<(Self, (usize, usize))> {
+            let (x, y) = self.cell;
+            let cells = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            let successors: Vec<(Self, (usize, usize))> = cells.into_iter()
+                .filter(|&(x, y)| x < SIZE && y < SIZE)
+                .map(|cell| (Walker { cell, g: self.g + 1 }, cell))
+                .collect();
+            GENERATED.with(|generated| generated.set(generated.get() + successors.len()));
+            successors
+        }
+    }
+
+    impl UntracedState<Counted> for Walker {
+        fn generate_successors(&self) -> Vec<Self> {
+            self.generate_traced_successors().into_iter().map(|(walker, _)| walker).collect()
+        }
+    }
+
+    // Hashes and generated states during `search`, the initial state counting as generated.
+    fn counted(search: impl FnOnce()) -> (usize, usize) {
+        HASHES.with(|hashes| hashes.set(0));
+        GENERATED.with(|generated| generated.set(1));
+        search();
+        (HASHES.with(Cell::get), GENERATED.with(Cell::get))
+    }
+
+    #[test]
+    fn test_each_key_is_hashed_once() {
+        let start = || Walker { cell: (0, 0), g: 0 };
+
+        let (hashes, generated) = counted(|| assert_eq!(untraced_astar(start()).unwrap().final_state.g(), 14));
+        assert_eq!(hashes, generated);
+
+        let mut path_len = 0;
+        let (hashes, generated) = counted(|| path_len = traced_astar(start()).unwrap().path.len());
+        assert_eq!(path_len, 14);
+        assert_eq!(hashes, generated + path_len);
+
+        // Every insert, lookup and heap move of the generic searches hashes again.
+        let (hashes, generated) = counted(|| { untraced_astar_with_queue(start(), OpenList::new()); });
+        assert!(hashes > 2 * generated, "{} hashes for {} states", hashes, generated);
+        let (hashes, generated) = counted(|| { traced_astar_with_queue(start(), OpenList::new()); });
+        assert!(hashes > 2 * generated, "{} hashes for {} states", hashes, generated);
+    }
+
+    // Every key with the same hash, so lookups have to compare the keys.
+    #[test]
+    fn test_colliding_hashes_compare_keys() {
+        let mut map: HashedMap<&str, usize> = HashedMap::default();
+        map.insert(Hashed::new(7, "a"), 1);
+        map.insert(Hashed::new(7, "b"), 2);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(query(&(7, &"a"))), Some(&1));
+        assert_eq!(map.get(query(&(7, &"b"))), Some(&2));
+        assert_eq!(map.get(query(&(7, &"c"))), None);
+        assert_eq!(map.get(query(&(8, &"a"))), None);
+    }
+}
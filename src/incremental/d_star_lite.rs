@@ -0,0 +1,387 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash, marker::PhantomData, mem};
+
+use crate::{bidirectional::BidirectionalState, incremental::{queue::{Key, KeyQueue}, Node}, search_id::SearchId, search_stats::SearchStats, traced::{outcome::SearchOutcome, result::TracedResult}};
+
+// D* Lite (Koenig and Likhachev): a search backwards from a fixed goal towards a start that moves,
+// as in ReusableSearch, that also survives edge cost changes. `update_edge_cost` and
+// `invalidate_state` only record a change; the next `replan` recomputes rhs for the states whose
+// outgoing edges changed and expands just the ones whose distance to the goal is affected, by
+// the two-component keys [min(g, rhs) + reverse_h(start) + km, min(g, rhs)]. km adds up the
+// reverse_h between successive starts, so the keys of states queued for an earlier start stay
+// lower bounds without reordering the queue.
+//
+// Costs come from the generators, g of a successor minus g of its parent, unless overridden, and
+// usize::MAX blocks an edge. Only edges that the generators produce can be overridden. Answers
+// are optimal when reverse_h is consistent for every start it is given, and the states learned
+// so far are kept for good: an agent that covers a large map keeps all of it in memory.
+pub struct DStarLite<S, K, C>
+where
+    S: BidirectionalState<K, C>,
+    K: Clone + Eq + Hash
+{
+    nodes: HashMap<K, Node<S>>,
+    queue: KeyQueue<K>,
+    goal: K,
+    km: usize,
+    last_start: Option<S>,
+    costs: HashMap<(K, K), usize>,
+    invalid: HashSet<K>,
+    // States whose outgoing edges changed since the last replan.
+    changed: Vec<K>,
+    search_id: SearchId,
+    _change: PhantomData<C>
+}
+
+impl<S, K, C> DStarLite<S, K, C>
+where
+    S: BidirectionalState<K, C> + Clone,
+    K: Clone + Eq + Hash
+{
+    // `goal` is the root of the backward search. Its g() needn't be 0, only differences of g
+    // are used.
+    pub fn new(goal: S) -> Self {
+        let goal_key = goal.key();
+        let mut nodes = HashMap::new();
+        nodes.insert(goal_key.clone(), Node { state: goal, g: usize::MAX, rhs: 0 });
+
+        DStarLite {
+            nodes,
+            queue: KeyQueue::new(),
+            goal: goal_key,
+            km: 0,
+            last_start: None,
+            costs: HashMap::new(),
+            invalid: HashSet::new(),
+            changed: Vec::new(),
+            search_id: SearchId::next(),
+            _change: PhantomData
+        }
+    }
+
+    // Every answer of this planner is stamped with the same id.
+    pub fn search_id(&self) -> SearchId {
+        self.search_id
+    }
+
+    // States seen so far, whether or not their distance to the goal is known.
+    pub fn known_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    // Sets the cost of the edge from `from` to `to`, usize::MAX to block it. Calling it again
+    // with the generator's cost restores the edge.
+    pub fn update_edge_cost(&mut self, from: K, to: K, cost: usize) {
+        self.costs.insert((from.clone(), to), cost);
+        self.changed.push(from);
+    }
+
+    // Blocks every edge into and out of `key` for good, e.g. for a cell found to be an obstacle.
+    pub fn invalidate_state(&mut self, key: K) {
+        if let Some(node) = self.nodes.get(&key) {
+            self.changed.extend(node.state.generate_predecessors().into_iter().map(|(predecessor, _)| predecessor.key()));
+        }
+        self.changed.push(key.clone());
+        self.invalid.insert(key);
+    }
+
+    // Path from `current_state` to the goal under the costs as changed so far. The stats count
+    // the expansions of this call alone: the first one searches like a backward A*, later ones
+    // only repair what the changes and the move of the start affected. The final state is the
+    // goal reached by following the path from `current_state`, and the result's cost and step
+    // costs are the changed ones, so the final state's g() only counts generator costs.
+    pub fn replan(&mut self, current_state: S) -> SearchOutcome<S, K, C> {
+        let mut stats = SearchStats {
+            search_id: self.search_id,
+            ..SearchStats::default()
+        };
+
+        match self.last_start.replace(current_state.clone()) {
+            None => {
+                let goal_priority = self.priority(&self.goal.clone(), &current_state);
+                self.queue.set(self.goal.clone(), goal_priority);
+            }
+            Some(last_start) => {
+                self.km = self.km.saturating_add(current_state.reverse_h(&last_start));
+            }
+        }
+
+        let start_key = current_state.key();
+        if !self.nodes.contains_key(&start_key) {
+            self.nodes.insert(start_key.clone(), Node { state: current_state.clone(), g: usize::MAX, rhs: usize::MAX });
+            self.changed.push(start_key.clone());
+        }
+
+        for key in mem::take(&mut self.changed) {
+            if self.nodes.contains_key(&key) {
+                self.recompute_rhs(&key);
+                self.update_vertex(&key, &current_state);
+            }
+        }
+
+        self.compute_shortest_path(&current_state, &mut stats);
+
+        let result = self.extract_path(current_state)
+            .map(|(path, step_costs, final_state)| {
+                let cost = step_costs.iter().sum();
+                TracedResult::new(path, stats.expansions, final_state).with_costs(cost, step_costs)
+            });
+
+        SearchOutcome::new(result, stats)
+    }
+
+    // Expands queued states in key order until the start is consistent and no queued key is
+    // below its own.
+    fn compute_shortest_path(&mut self, start: &S, stats: &mut SearchStats<K>) {
+        let start_key = start.key();
+
+        while let Some((top, old_priority)) = self.queue.top() {
+            let top = top.clone();
+            let start_node = &self.nodes[&start_key];
+            if old_priority >= self.priority(&start_key, start) && start_node.rhs <= start_node.g {
+                break;
+            }
+
+            let new_priority = self.priority(&top, start);
+            if old_priority < new_priority {
+                self.queue.set(top, new_priority);
+                continue;
+            }
+
+            let node = &self.nodes[&top];
+            let predecessors = node.state.generate_predecessors();
+            stats.expansions += 1;
+            stats.generated += predecessors.len();
+            stats.trace_fingerprint.expanded(&top, node.g.min(node.rhs), node.state.reverse_h(start));
+
+            let top_state_g = node.state.g();
+            if node.g > node.rhs {
+                // Overconsistent: its distance went down, and so may those of its predecessors.
+                let g = node.rhs;
+                self.queue.remove(&top);
+                self.nodes.get_mut(&top).expect("queued states are known").g = g;
+
+                for (predecessor, _) in predecessors {
+                    let key = predecessor.key();
+                    let cost = self.edge_cost(&key, &top, predecessor.g() - top_state_g);
+                    let node = self.nodes.entry(key.clone()).or_insert(Node { state: predecessor, g: usize::MAX, rhs: usize::MAX });
+                    if key != self.goal {
+                        node.rhs = node.rhs.min(cost.saturating_add(g));
+                    }
+                    self.update_vertex(&key, start);
+                }
+            }
+            else {
+                // Underconsistent: its distance went up. The predecessors that went through it
+                // look for another way, and so does the state itself.
+                let old_g = mem::replace(&mut self.nodes.get_mut(&top).expect("queued states are known").g, usize::MAX);
+
+                for (predecessor, _) in predecessors {
+                    let key = predecessor.key();
+                    let cost = self.edge_cost(&key, &top, predecessor.g() - top_state_g);
+                    let node = self.nodes.entry(key.clone()).or_insert(Node { state: predecessor, g: usize::MAX, rhs: usize::MAX });
+                    if node.rhs != usize::MAX && node.rhs == cost.saturating_add(old_g) {
+                        self.recompute_rhs(&key);
+                    }
+                    self.update_vertex(&key, start);
+                }
+                self.recompute_rhs(&top);
+                self.update_vertex(&top, start);
+            }
+        }
+    }
+
+    // Follows the successor of least cost + g from `current_state` down to the goal. None when
+    // the start can't reach it.
+    fn extract_path(&self, current_state: S) -> Option<(Vec<C>, Vec<usize>, S)> {
+        let mut state = current_state;
+        let mut path = Vec::new();
+        let mut step_costs = Vec::new();
+
+        if self.nodes[&state.key()].rhs == usize::MAX {
+            return None;
+        }
+
+        while state.key() != self.goal {
+            // Every step lowers g by its cost, so a consistent start can't take more steps than
+            // there are states, whatever the costs.
+            if path.len() > self.nodes.len() {
+                return None;
+            }
+
+            let (_, cost, successor, change) = self.best_successor(&state)?;
+            path.push(change);
+            step_costs.push(cost);
+            state = successor;
+        }
+
+        Some((path, step_costs, state))
+    }
+
+    // Successor of least cost + g, with that sum and the cost of the edge to it.
+    fn best_successor(&self, state: &S) -> Option<(usize, usize, S, C)> {
+        let key = state.key();
+
+        state.generate_traced_successors()
+            .into_iter()
+            .map(|(successor, change)| {
+                let successor_key = successor.key();
+                let cost = self.edge_cost(&key, &successor_key, successor.g() - state.g());
+                let g = self.nodes.get(&successor_key).map_or(usize::MAX, |node| node.g);
+                (cost.saturating_add(g), cost, successor, change)
+            })
+            .filter(|&(total, ..)| total != usize::MAX)
+            .min_by_key(|&(total, ..)| total)
+    }
+
+    fn edge_cost(&self, from: &K, to: &K, generated: usize) -> usize {
+        if self.invalid.contains(from) || self.invalid.contains(to) {
+            return usize::MAX;
+        }
+
+        self.costs.get(&(from.clone(), to.clone())).copied().unwrap_or(generated)
+    }
+
+    fn recompute_rhs(&mut self, key: &K) {
+        if *key == self.goal {
+            return;
+        }
+
+        let rhs = self.best_successor(&self.nodes[key].state).map_or(usize::MAX, |(total, ..)| total);
+        self.nodes.get_mut(key).expect("recomputed states are known").rhs = rhs;
+    }
+
+    // Queues an inconsistent state with its current key, and takes a consistent one off the queue.
+    fn update_vertex(&mut self, key: &K, start: &S) {
+        let node = &self.nodes[key];
+        if node.g != node.rhs {
+            let priority = self.priority(key, start);
+            self.queue.set(key.clone(), priority);
+        }
+        else {
+            self.queue.remove(key);
+        }
+    }
+
+    fn priority(&self, key: &K, start: &S) -> Key {
+        let node = &self.nodes[key];
+        let distance = node.g.min(node.rhs);
+        (distance.saturating_add(node.state.reverse_h(start)).saturating_add(self.km), distance)
+    }
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, grid::{map::GridMap, region::GoalRegion, state::GridState}, traced::replay::{replay, replay_states}};
+
+    // A wall down the middle with a gap at the bottom, so that a fresh A* from the left half
+    // floods most of it before heading down. A robot starting next to the wall walks straight
+    // down along it.
+    fn walled_map() -> GridMap {
+        let mut map = GridMap::new(30, 30);
+        for y in 0..29 {
+            map.set_blocked(15, y, true);
+        }
+        map
+    }
+
+    #[test]
+    fn test_repairs_after_obstacles_ahead() {
+        let map = walled_map();
+        let mut observed = map.clone();
+        let goal = (25, 5);
+        let region = GoalRegion::Cell(goal.0, goal.1);
+        let mut planner = DStarLite::new(GridState::with_region(&map, goal, &region));
+
+        let mut robot = GridState::with_region(&map, (14, 3), &region);
+        let first = planner.replan(robot.clone()).result.unwrap();
+        assert_eq!(first.cost, map.astar_to_region((14, 3), &region).unwrap().cost);
+
+        let mut path = first.path.to_vec();
+        let mut cost = first.cost;
+        let mut detours = 0;
+        for _ in 0..3 {
+            // The robot walks a few steps, then sees a wall of three cells across the path ahead,
+            // and every edge into those cells turns out to be blocked.
+            let ahead = replay_states(robot, path.iter().copied()).unwrap();
+            robot = ahead[4].clone();
+            let (x, y) = ahead[7].position();
+            let wall = if ahead[6].position().1 == y {
+                [(x, y - 1), (x, y), (x, y + 1)]
+            }
+            else {
+                [(x - 1, y), (x, y), (x + 1, y)]
+            };
+            for (x, y) in wall {
+                for (neighbor, _) in observed.moves(x, y) {
+                    planner.update_edge_cost(neighbor, (x, y), usize::MAX);
+                }
+                observed.set_blocked(x, y, true);
+            }
+
+            let outcome = planner.replan(robot.clone());
+            let result = outcome.result.unwrap();
+            let fresh = observed.astar_to_region(robot.position(), &region).unwrap();
+            assert_eq!(result.cost, fresh.cost);
+            if result.cost > cost - 4 {
+                detours += 1;
+            }
+            cost = result.cost;
+            assert!(outcome.stats.expansions * 5 < fresh.iterations, "{} vs {}", outcome.stats.expansions, fresh.iterations);
+
+            let on_observed = GridState::with_region(&observed, robot.position(), &region);
+            assert!(replay(&on_observed, result.path.to_vec()).unwrap().is_goal());
+            path = result.path.to_vec();
+        }
+        assert_eq!(detours, 2);
+    }
+
+    #[test]
+    fn test_edge_cost_changes_both_ways() {
+        let map = GridMap::new(5, 3);
+        let region = GoalRegion::Cell(4, 1);
+        let mut planner = DStarLite::new(GridState::with_region(&map, (4, 1), &region));
+        let start = GridState::with_region(&map, (0, 1), &region);
+        assert_eq!(planner.replan(start.clone()).result.unwrap().cost, 4);
+
+        // Dearer, still cheaper than going round.
+        planner.update_edge_cost((1, 1), (2, 1), 2);
+        let result = planner.replan(start.clone()).result.unwrap();
+        assert_eq!(result.cost, 5);
+        assert_eq!(result.final_state.g(), 4);
+
+        planner.update_edge_cost((1, 1), (2, 1), usize::MAX);
+        assert_eq!(planner.replan(start.clone()).result.unwrap().cost, 6);
+
+        // An edge that no shortest path uses changes nothing.
+        planner.update_edge_cost((0, 0), (0, 1), 9);
+        assert_eq!(planner.replan(start.clone()).stats.expansions, 0);
+
+        planner.update_edge_cost((1, 1), (2, 1), 1);
+        let result = planner.replan(start).result.unwrap();
+        assert_eq!((result.cost, result.path.len()), (4, 4));
+    }
+
+    #[test]
+    fn test_cut_off_start() {
+        let map = GridMap::from_ascii("
+            ..#.
+            ..#.
+            ....
+        ");
+        let region = GoalRegion::Cell(3, 0);
+        let mut planner = DStarLite::new(GridState::with_region(&map, (3, 0), &region));
+        let start = GridState::with_region(&map, (0, 0), &region);
+        assert_eq!(planner.replan(start.clone()).result.unwrap().cost, 7);
+
+        planner.invalidate_state((3, 2));
+        let outcome = planner.replan(start);
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.search_id(), planner.search_id());
+        assert_eq!(planner.queue_len(), 0);
+    }
+}
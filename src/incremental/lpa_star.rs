@@ -0,0 +1,373 @@
+use std::{collections::HashMap, hash::Hash, marker::PhantomData, mem};
+
+use crate::{bidirectional::BidirectionalState, incremental::{queue::{Key, KeyQueue}, Node}, search_id::SearchId, search_stats::SearchStats};
+
+// Lifelong Planning A* (Koenig, Likhachev and Furcy): A* between a fixed start and goal that
+// repairs its answer when edges change, for a domain that changes while the agent stays put.
+// The first `compute_shortest_path` searches like A*. After that, `notify_changed(key)` tells the
+// planner that edges into `key` changed, and the next call only expands the states whose
+// distance from the start the changes affect, by the two-component keys
+// [min(g, rhs) + h, min(g, rhs)].
+//
+// Edges change either through `update_edge_cost`, overrides kept by the planner like those of
+// DStarLite, or in the domain itself, for states that see it through shared, mutable data: the
+// states are generated again at every expansion, so the generators must agree on the edges
+// with `generate_predecessors`. Either way, every key with an incoming edge that changed must be
+// notified. Answers are optimal for a consistent h.
+pub struct LpaStar<S, K, C>
+where
+    S: BidirectionalState<K, C>,
+    K: Clone + Eq + Hash
+{
+    nodes: HashMap<K, Node<S>>,
+    queue: KeyQueue<K>,
+    start: K,
+    goal: K,
+    costs: HashMap<(K, K), usize>,
+    changed: Vec<K>,
+    search_id: SearchId,
+    _change: PhantomData<C>
+}
+
+impl<S, K, C> LpaStar<S, K, C>
+where
+    S: BidirectionalState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // The goal is the state of key `goal`, whatever is_goal() says of the others.
+    pub fn new(start: S, goal: K) -> Self {
+        let start_key = start.key();
+        let priority = (start.h(), 0);
+        let mut nodes = HashMap::new();
+        nodes.insert(start_key.clone(), Node { state: start, g: usize::MAX, rhs: 0 });
+        let mut queue = KeyQueue::new();
+        queue.set(start_key.clone(), priority);
+
+        LpaStar {
+            nodes,
+            queue,
+            start: start_key,
+            goal,
+            costs: HashMap::new(),
+            changed: Vec::new(),
+            search_id: SearchId::next(),
+            _change: PhantomData
+        }
+    }
+
+    pub fn search_id(&self) -> SearchId {
+        self.search_id
+    }
+
+    // States seen so far, whether or not their distance from the start is known.
+    pub fn known_len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Sets the cost of the edge from `from` to `to`, usize::MAX to block it, and notifies `to`.
+    // Calling it again with the generator's cost restores the edge.
+    pub fn update_edge_cost(&mut self, from: K, to: K, cost: usize) {
+        self.costs.insert((from, to.clone()), cost);
+        self.notify_changed(to);
+    }
+
+    // Marks the edges into `key` as changed, to be repaired by the next compute_shortest_path.
+    pub fn notify_changed(&mut self, key: K) {
+        self.changed.push(key);
+    }
+
+    // Cost of the cheapest path to the goal as of the last compute_shortest_path, None when the
+    // goal can't be reached.
+    pub fn cost(&self) -> Option<usize> {
+        self.nodes.get(&self.goal).map(|node| node.g).filter(|&g| g != usize::MAX)
+    }
+
+    // Changes of that path, from the start. Follows the predecessors of least g + cost back from
+    // the goal.
+    pub fn path(&self) -> Option<Vec<C>> {
+        self.cost()?;
+
+        let mut path = Vec::new();
+        let mut key = self.goal.clone();
+        while key != self.start {
+            // Every step back lowers g by its cost, so a consistent goal can't take more steps
+            // than there are states.
+            if path.len() > self.nodes.len() {
+                return None;
+            }
+
+            let (_, predecessor, change) = self.best_predecessor(&self.nodes[&key].state)?;
+            path.push(change);
+            key = predecessor;
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    // Brings g up to date for the goal and every state that could lead to it more cheaply: the
+    // changes notified since the last call are applied, then the inconsistent states are expanded
+    // in key order. The stats count this call alone.
+    pub fn compute_shortest_path(&mut self) -> SearchStats<K> {
+        let mut stats = SearchStats {
+            search_id: self.search_id,
+            ..SearchStats::default()
+        };
+
+        for key in mem::take(&mut self.changed) {
+            if self.nodes.contains_key(&key) {
+                self.recompute_rhs(&key);
+                self.update_vertex(&key);
+            }
+        }
+
+        while let Some((top, old_priority)) = self.queue.top() {
+            let top = top.clone();
+            let (goal_priority, goal_consistent) = match self.nodes.get(&self.goal) {
+                Some(goal) => (self.priority(goal), goal.g == goal.rhs),
+                None => ((usize::MAX, usize::MAX), true)
+            };
+            if old_priority >= goal_priority && goal_consistent {
+                break;
+            }
+
+            let node = &self.nodes[&top];
+            let successors = node.state.generate_traced_successors();
+            stats.expansions += 1;
+            stats.generated += successors.len();
+            stats.trace_fingerprint.expanded(&top, node.g.min(node.rhs), node.state.h());
+            let (top_state_g, g, rhs) = (node.state.g(), node.g, node.rhs);
+            self.queue.remove(&top);
+
+            if g > rhs {
+                // Overconsistent: its distance went down, and so may those of its successors.
+                let g = rhs;
+                self.nodes.get_mut(&top).expect("queued states are known").g = g;
+
+                for (successor, _) in successors {
+                    let key = successor.key();
+                    let cost = self.edge_cost(&top, &key, successor.g() - top_state_g);
+                    let node = self.nodes.entry(key.clone()).or_insert(Node { state: successor, g: usize::MAX, rhs: usize::MAX });
+                    if key != self.start {
+                        node.rhs = node.rhs.min(g.saturating_add(cost));
+                    }
+                    self.update_vertex(&key);
+                }
+            }
+            else {
+                // Underconsistent: its distance went up. The successors that came through it look
+                // for another way, and so does the state itself.
+                let old_g = mem::replace(&mut self.nodes.get_mut(&top).expect("queued states are known").g, usize::MAX);
+
+                for (successor, _) in successors {
+                    let key = successor.key();
+                    let cost = self.edge_cost(&top, &key, successor.g() - top_state_g);
+                    let node = self.nodes.entry(key.clone()).or_insert(Node { state: successor, g: usize::MAX, rhs: usize::MAX });
+                    if node.rhs != usize::MAX && node.rhs == old_g.saturating_add(cost) {
+                        self.recompute_rhs(&key);
+                    }
+                    self.update_vertex(&key);
+                }
+                self.recompute_rhs(&top);
+                self.update_vertex(&top);
+            }
+        }
+
+        stats
+    }
+
+    // Predecessor of least g + cost, with that sum.
+    fn best_predecessor(&self, state: &S) -> Option<(usize, K, C)> {
+        let key = state.key();
+
+        state.generate_predecessors()
+            .into_iter()
+            .map(|(predecessor, change)| {
+                let predecessor_key = predecessor.key();
+                let cost = self.edge_cost(&predecessor_key, &key, predecessor.g() - state.g());
+                let g = self.nodes.get(&predecessor_key).map_or(usize::MAX, |node| node.g);
+                (g.saturating_add(cost), predecessor_key, change)
+            })
+            .filter(|&(total, ..)| total != usize::MAX)
+            .min_by_key(|&(total, ..)| total)
+    }
+
+    fn edge_cost(&self, from: &K, to: &K, generated: usize) -> usize {
+        self.costs.get(&(from.clone(), to.clone())).copied().unwrap_or(generated)
+    }
+
+    fn recompute_rhs(&mut self, key: &K) {
+        if *key == self.start {
+            return;
+        }
+
+        let rhs = self.best_predecessor(&self.nodes[key].state).map_or(usize::MAX, |(total, ..)| total);
+        self.nodes.get_mut(key).expect("recomputed states are known").rhs = rhs;
+    }
+
+    // Queues an inconsistent state with its current key, and takes a consistent one off the queue.
+    fn update_vertex(&mut self, key: &K) {
+        let node = &self.nodes[key];
+        if node.g != node.rhs {
+            let priority = self.priority(node);
+            self.queue.set(key.clone(), priority);
+        }
+        else {
+            self.queue.remove(key);
+        }
+    }
+
+    fn priority(&self, node: &Node<S>) -> Key {
+        let distance = node.g.min(node.rhs);
+        (distance.saturating_add(node.state.h()), distance)
+    }
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{astar_state::AStarState, grid::{map::GridMap, region::GoalRegion, state::GridState}, traced::{replay::replay, state::TracedState}};
+
+    // A wall near the goal's side with a door in it, and a long way round at the bottom.
+    fn door_map() -> GridMap {
+        let mut map = GridMap::new(20, 12);
+        for y in (0..11).filter(|&y| y != 2) {
+            map.set_blocked(16, y, true);
+        }
+        map
+    }
+
+    #[test]
+    fn test_path_flips_with_the_door() {
+        let map = door_map();
+        let mut closed = map.clone();
+        closed.set_blocked(16, 2, true);
+        let region = GoalRegion::Cell(19, 0);
+        let mut planner = LpaStar::new(GridState::with_region(&map, (0, 0), &region), (19, 0));
+
+        let first = planner.compute_shortest_path();
+        assert_eq!(planner.cost(), Some(23));
+
+        for round in 0..3 {
+            // Closing the door the first time searches the way round. After that, the states on
+            // it keep their distances, and only those past the door are repaired.
+            planner.update_edge_cost((15, 2), (16, 2), usize::MAX);
+            let stats = planner.compute_shortest_path();
+            let fresh = closed.astar_to_region((0, 0), &region).unwrap();
+            assert_eq!(planner.cost(), Some(fresh.cost));
+            let on_closed = GridState::with_region(&closed, (0, 0), &region);
+            assert_eq!(replay(&on_closed, planner.path().unwrap()).unwrap().g(), fresh.cost);
+            if round == 0 {
+                assert!(stats.expansions < fresh.iterations);
+            }
+            else {
+                assert!(stats.expansions * 5 < fresh.iterations, "{} vs {}", stats.expansions, fresh.iterations);
+            }
+
+            planner.update_edge_cost((15, 2), (16, 2), 1);
+            let stats = planner.compute_shortest_path();
+            assert_eq!(planner.cost(), Some(23));
+            let on_open = GridState::with_region(&map, (0, 0), &region);
+            assert_eq!(replay(&on_open, planner.path().unwrap()).unwrap().g(), 23);
+            assert!(stats.expansions * 5 < first.expansions, "{} vs {}", stats.expansions, first.expansions);
+        }
+    }
+
+    // Rooms 0 -> 1 -> 2 -> 3 in a row, with a door from 0 straight into 3 when it's open. Every
+    // move costs 1, and the door is shared by all the states, as a domain changing under the
+    // planner would be.
+    #[derive(Clone)]
+    struct Room<'a> {
+        room: usize,
+        g: usize,
+        door: &'a Cell<bool>
+    }
+
+    impl Room<'_> {
+        fn to(&self, room: usize, g: usize) -> Self {
+            Room { room, g, ..self.clone() }
+        }
+    }
+
+    impl AStarState<usize> for Room<'_> {
+        fn key(&self) -> usize {
+            self.room
+        }
+
+        fn h(&self) -> usize {
+            usize::from(self.room != 3)
+        }
+
+        fn f(&self) -> usize {
+            self.g + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.room == 3
+        }
+    }
+
+    impl TracedState<usize, usize> for Room<'_> {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            let mut rooms: Vec<usize> = (self.room < 3).then_some(self.room + 1).into_iter().collect();
+            if self.room == 0 && self.door.get() {
+                rooms.push(3);
+            }
+            rooms.into_iter().map(|room| (self.to(room, self.g + 1), room)).collect()
+        }
+    }
+
+    impl BidirectionalState<usize, usize> for Room<'_> {
+        fn generate_predecessors(&self) -> Vec<(Self, usize)> {
+            let mut rooms: Vec<usize> = self.room.checked_sub(1).into_iter().collect();
+            if self.room == 3 && self.door.get() {
+                rooms.push(0);
+            }
+            rooms.into_iter().map(|room| (self.to(room, self.g + 1), self.room)).collect()
+        }
+
+        fn reverse_h(&self, _start: &Self) -> usize {
+            0
+        }
+    }
+
+    #[test]
+    fn test_domain_changes_are_notified() {
+        let door = Cell::new(true);
+        let mut planner = LpaStar::new(Room { room: 0, g: 0, door: &door }, 3);
+        planner.compute_shortest_path();
+        assert_eq!((planner.cost(), planner.path()), (Some(1), Some(vec![3])));
+
+        door.set(false);
+        planner.notify_changed(3);
+        planner.compute_shortest_path();
+        assert_eq!((planner.cost(), planner.path()), (Some(3), Some(vec![1, 2, 3])));
+
+        // Nothing notified, nothing repaired: the answer is as of the last change notified.
+        door.set(true);
+        assert_eq!(planner.compute_shortest_path().expansions, 0);
+        assert_eq!(planner.cost(), Some(3));
+
+        planner.notify_changed(3);
+        let stats = planner.compute_shortest_path();
+        assert_eq!((planner.cost(), planner.path()), (Some(1), Some(vec![3])));
+        assert_eq!(stats.expansions, 1);
+        assert_eq!(stats.search_id, planner.search_id());
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let door = Cell::new(false);
+        let mut planner = LpaStar::new(Room { room: 0, g: 0, door: &door }, 3);
+        planner.update_edge_cost(1, 2, usize::MAX);
+        planner.compute_shortest_path();
+        assert_eq!((planner.cost(), planner.path()), (None, None));
+        assert_eq!(planner.known_len(), 3);
+    }
+}
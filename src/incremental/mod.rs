@@ -0,0 +1,15 @@
+// Searches that repair their previous answer when edge costs change, instead of searching again.
+pub mod d_star_lite;
+pub mod lpa_star;
+
+mod queue;
+
+// A state seen by an incremental search. g is its distance as last expanded, from the start for
+// LPA* and to the goal for D* Lite, and rhs the one-step lookahead of it: the min over the edges
+// towards the start (or goal) of cost + g. The state is consistent when they are equal, and queued
+// otherwise.
+struct Node<S> {
+    state: S,
+    g: usize,
+    rhs: usize
+}
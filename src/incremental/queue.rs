@@ -0,0 +1,133 @@
+use std::{collections::HashMap, hash::Hash};
+
+// Two-component key of the incremental searches, compared lexicographically.
+pub(crate) type Key = (usize, usize);
+
+// Indexed binary heap of keys by their Key, like BTreeOpenList with a hash map for the index.
+// Unlike the open lists, the Key of an entry can go up as well as down, and any entry can be
+// removed: the incremental searches move states in and out of the queue as their values change.
+pub(crate) struct KeyQueue<K> {
+    heap: Vec<(K, Key)>,
+    index: HashMap<K, usize>
+}
+
+impl<K> KeyQueue<K>
+where
+    K: Clone + Eq + Hash
+{
+    pub(crate) fn new() -> Self {
+        KeyQueue { heap: Vec::new(), index: HashMap::new() }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub(crate) fn top(&self) -> Option<(&K, Key)> {
+        self.heap.first().map(|(key, priority)| (key, *priority))
+    }
+
+    // Adds `key`, or moves it to `priority` if it is queued already.
+    pub(crate) fn set(&mut self, key: K, priority: Key) {
+        if let Some(&index) = self.index.get(&key) {
+            let previous = self.heap[index].1;
+            self.heap[index].1 = priority;
+            if priority < previous {
+                self.bubble_up(index);
+            }
+            else {
+                self.bubble_down(index);
+            }
+        }
+        else {
+            let index = self.heap.len();
+            self.index.insert(key.clone(), index);
+            self.heap.push((key, priority));
+            self.bubble_up(index);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) -> Option<Key> {
+        let index = self.index.remove(key)?;
+        let last_index = self.heap.len() - 1;
+        self.heap.swap(index, last_index);
+        let (_, priority) = self.heap.pop()?;
+
+        if index < self.heap.len() {
+            self.index.insert(self.heap[index].0.clone(), index);
+            self.bubble_up(index);
+            self.bubble_down(index);
+        }
+
+        Some(priority)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.heap.swap(i, j);
+
+        for index in [i, j] {
+            if let Some(slot) = self.index.get_mut(&self.heap[index].0) {
+                *slot = index;
+            }
+        }
+    }
+
+    fn bubble_up(&mut self, mut current: usize) {
+        while current > 0 {
+            let parent = (current - 1) / 2;
+            if self.heap[current].1 >= self.heap[parent].1 {
+                break;
+            }
+
+            self.swap(current, parent);
+            current = parent;
+        }
+    }
+
+    fn bubble_down(&mut self, mut current: usize) {
+        let len = self.heap.len();
+
+        loop {
+            let mut smallest = current;
+            for child in [2 * current + 1, 2 * current + 2] {
+                if child < len && self.heap[child].1 < self.heap[smallest].1 {
+                    smallest = child;
+                }
+            }
+
+            if smallest == current {
+                break;
+            }
+
+            self.swap(current, smallest);
+            current = smallest;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_move_both_ways() {
+        let mut queue = KeyQueue::new();
+        for (key, priority) in [('a', (5, 1)), ('b', (3, 2)), ('c', (5, 0)), ('d', (7, 7))] {
+            queue.set(key, priority);
+        }
+        assert_eq!(queue.top(), Some((&'b', (3, 2))));
+
+        queue.set('b', (9, 0));
+        queue.set('d', (1, 0));
+        assert_eq!(queue.remove(&'c'), Some((5, 0)));
+        assert_eq!(queue.remove(&'c'), None);
+
+        let mut order = Vec::new();
+        while let Some((&key, _)) = queue.top() {
+            order.push(key);
+            queue.remove(&key);
+        }
+        assert_eq!(order, vec!['d', 'a', 'b']);
+        assert_eq!(queue.len(), 0);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display, hash::Hash};
+
+use crate::{search_stats::{SearchWarning, Thrasher}, validation::Anomaly};
+
+pub(crate) type KeyLabeler<'a, K> = Box<dyn Fn(&K) -> String + 'a>;
+
+// A key's entry in a LabelTable: the order in which the search first mentioned it in a
+// diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LabelId(u32);
+
+impl LabelId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// Labels of the keys a search's diagnostics mention, so they can be printed without `K: Debug`.
+// Each key is labeled once, by `SearchOptions::with_key_labeler` when the search had one and as
+// `#` and its LabelId otherwise. Keys that appear in no diagnostic are never labeled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelTable<K>
+where
+    K: Eq + Hash
+{
+    ids: HashMap<K, LabelId>,
+    // Empty without a labeler.
+    labels: Vec<String>
+}
+
+impl<K> Default for LabelTable<K>
+where
+    K: Eq + Hash
+{
+    fn default() -> Self {
+        LabelTable { ids: HashMap::new(), labels: Vec::new() }
+    }
+}
+
+impl<K> LabelTable<K>
+where
+    K: Clone + Eq + Hash
+{
+    pub fn new() -> Self {
+        LabelTable::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn id(&self, key: &K) -> Option<LabelId> {
+        self.ids.get(key).copied()
+    }
+
+    // Panics on an id from another table.
+    pub fn label(&self, id: LabelId) -> Cow<'_, str> {
+        if self.labels.is_empty() {
+            assert!(id.index() < self.ids.len(), "label id from another table");
+            Cow::Owned(format!("#{}", id.0))
+        }
+        else {
+            Cow::Borrowed(&self.labels[id.index()])
+        }
+    }
+
+    pub fn label_of(&self, key: &K) -> Option<Cow<'_, str>> {
+        self.id(key).map(|id| self.label(id))
+    }
+
+    // The item displayed with the labels of its keys, e.g. `outcome.labels.labeled(&warning)`.
+    // Keys the table doesn't know are shown as `?`.
+    pub fn labeled<'t, T>(&'t self, item: &'t T) -> Labeled<'t, T, K> {
+        Labeled { item, labels: self }
+    }
+
+    pub(crate) fn intern(&mut self, key: &K, labeler: Option<&dyn Fn(&K) -> String>) -> LabelId {
+        if let Some(&id) = self.ids.get(key) {
+            return id;
+        }
+
+        let id = LabelId(self.ids.len() as u32);
+        if let Some(labeler) = labeler {
+            self.labels.push(labeler(key));
+        }
+        self.ids.insert(key.clone(), id);
+        id
+    }
+
+    fn show(&self, key: &K) -> String {
+        self.label_of(key).map_or_else(|| "?".to_string(), Cow::into_owned)
+    }
+}
+
+pub struct Labeled<'t, T, K>
+where
+    K: Eq + Hash
+{
+    item: &'t T,
+    labels: &'t LabelTable<K>
+}
+
+impl<K> Display for Labeled<'_, Anomaly<K>, K>
+where
+    K: Clone + Eq + Hash
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.item.write_with(f, |key| self.labels.show(key))
+    }
+}
+
+impl<K> Display for Labeled<'_, SearchWarning<K>, K>
+where
+    K: Clone + Eq + Hash
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.item.write_with(f, |key| self.labels.show(key))
+    }
+}
+
+impl<K> Display for Labeled<'_, Thrasher<K>, K>
+where
+    K: Clone + Eq + Hash
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.item.write_with(f, |key| self.labels.show(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{astar_state::AStarState, search_options::SearchOptions, traced::{state::TracedState, traced_astar::traced_astar_with_options}, validation::ValidationLevel};
+
+    // A key with no Debug.
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct Milestone(usize);
+
+    // A walk from 0 to 8 whose g drops at 4 and again at 5.
+    #[derive(Clone)]
+    struct Walk(usize);
+
+    impl AStarState<Milestone> for Walk {
+        fn key(&self) -> Milestone {
+            Milestone(self.0)
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.g()
+        }
+
+        fn g(&self) -> usize {
+            match self.0 {
+                4 => 2,
+                5 => 1,
+                position => position
+            }
+        }
+
+        fn is_goal(&self) -> bool {
+            self.0 == 8
+        }
+    }
+
+    impl TracedState<Milestone, ()> for Walk {
+        fn generate_traced_successors(&self) -> Vec<(Self, ())> {
+            vec![(Walk(self.0 + 1), ())]
+        }
+    }
+
+    #[test]
+    fn test_warnings_read_with_and_without_a_labeler() {
+        let calls = Cell::new(0);
+        let labeler = |key: &Milestone| {
+            calls.set(calls.get() + 1);
+            format!("mile {}", key.0)
+        };
+        let options = SearchOptions::new().with_validation(ValidationLevel::Warn).with_key_labeler(labeler);
+        let outcome = traced_astar_with_options(Walk(0), options);
+
+        let lines: Vec<String> = outcome.stats.warnings.iter().map(|warning| outcome.labels.labeled(warning).to_string()).collect();
+        assert_eq!(lines, vec!["g drops from 3 at mile 3 to 2 at its successor mile 4", "g drops from 2 at mile 4 to 1 at its successor mile 5"]);
+        // 4 is in both warnings but labeled once, and no other key is labeled.
+        assert_eq!((calls.get(), outcome.labels.len()), (3, 3));
+
+        let outcome = traced_astar_with_options(Walk(0), SearchOptions::new().with_validation(ValidationLevel::Warn));
+        let first = outcome.labels.labeled(&outcome.stats.warnings[0]).to_string();
+        assert_eq!(first, "g drops from 3 at #0 to 2 at its successor #1");
+        assert_eq!(outcome.labels.label_of(&Milestone(5)).as_deref(), Some("#2"));
+        assert_eq!(outcome.labels.label_of(&Milestone(6)), None);
+    }
+
+    #[test]
+    fn test_unknown_keys_show_as_a_question_mark() {
+        let mut labels = LabelTable::new();
+        let id = labels.intern(&1, None);
+        assert_eq!(labels.intern(&1, None), id);
+
+        let anomaly = Anomaly::ForcedNotGenerated { parent: 1, child: 2 };
+        assert_eq!(labels.labeled(&anomaly).to_string(), "forced successor ? of #0 is not among its generated successors");
+    }
+}
@@ -0,0 +1,319 @@
+use std::{cmp::Ordering, collections::{BinaryHeap, HashMap}, hash::Hash};
+
+use crate::{astar_state::AStarState, priority_queue::{Insertion, PriorityQueue}};
+
+const DEFAULT_COMPACTION_FACTOR: usize = 4;
+
+// Open list backed by a std BinaryHeap. Improving a key pushes a new entry instead of moving the
+// old one, and the outdated entry is skipped when it reaches the top (lazy deletion). Stale
+// entries are purged by `compact`, which runs automatically once they outnumber the live ones by
+// the compaction factor. The value of a stale entry is dropped along with it: `insert` can't hand
+// it back, so an improvement reports `Insertion::Improved(None)` and superseded values never reach
+// a `Recycler`. Use `OpenList` where they should.
+pub struct LazyOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    heap: BinaryHeap<LazyEntry<K, V>>,
+    live: HashMap<K, LiveEntry>,
+    next_sequence: u64,
+    compaction_factor: usize,
+    compactions: usize
+}
+
+struct LazyEntry<K, V> {
+    f: usize,
+    sequence: u64,
+    key: K,
+    value: V
+}
+
+#[derive(Clone, Copy)]
+struct LiveEntry {
+    f: usize,
+    sequence: u64
+}
+
+impl<K, V> LazyOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    pub fn new() -> Self {
+        LazyOpenList {
+            heap: BinaryHeap::new(),
+            live: HashMap::new(),
+            next_sequence: 0,
+            compaction_factor: DEFAULT_COMPACTION_FACTOR,
+            compactions: 0
+        }
+    }
+
+    // Compaction is triggered when `stale_entries() > live_entries() * factor`.
+    // `usize::MAX` effectively disables automatic compaction.
+    pub fn with_compaction_factor(mut self, factor: usize) -> Self {
+        self.compaction_factor = factor;
+        self
+    }
+
+    pub fn compaction_factor(&self) -> usize {
+        self.compaction_factor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn live_entries(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn stale_entries(&self) -> usize {
+        self.heap.len() - self.live.len()
+    }
+
+    pub fn total_entries(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn compactions(&self) -> usize {
+        self.compactions
+    }
+
+    // The value it improves on stays behind as a stale entry, see above.
+    pub fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        let value_f = value.f();
+
+        let previous = self.live.get(&key).copied();
+        if previous.is_some_and(|live_entry| value_f >= live_entry.f) {
+            return Insertion::Rejected(value);
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        self.live.insert(key.clone(), LiveEntry { f: value_f, sequence });
+        self.heap.push(LazyEntry {
+            f: value_f,
+            sequence,
+            key,
+            value
+        });
+
+        if self.stale_entries() > self.live_entries().saturating_mul(self.compaction_factor) {
+            self.compact();
+        }
+
+        match previous {
+            Some(_) => Insertion::Improved(None),
+            None => Insertion::Added
+        }
+    }
+
+    pub fn min(&mut self) -> Option<&V> {
+        self.discard_stale_top();
+        self.heap.peek().map(|entry| &entry.value)
+    }
+
+    pub fn extract_min(&mut self) -> Option<V> {
+        self.discard_stale_top();
+
+        let entry = self.heap.pop()?;
+        self.live.remove(&entry.key);
+        Some(entry.value)
+    }
+
+    // Rebuilds the heap keeping only the live entry of every key.
+    pub fn compact(&mut self) {
+        let live = &self.live;
+        let entries: Vec<LazyEntry<K, V>> = std::mem::take(&mut self.heap)
+            .into_vec()
+            .into_iter()
+            .filter(|entry| Self::is_live(live, entry))
+            .collect();
+
+        self.heap = BinaryHeap::from(entries);
+        self.compactions += 1;
+    }
+
+    fn discard_stale_top(&mut self) {
+        while let Some(entry) = self.heap.peek() {
+            if Self::is_live(&self.live, entry) {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+
+    fn is_live(live: &HashMap<K, LiveEntry>, entry: &LazyEntry<K, V>) -> bool {
+        live.get(&entry.key)
+            .is_some_and(|live_entry| live_entry.sequence == entry.sequence)
+    }
+}
+
+impl<K, V> Default for LazyOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> PriorityQueue<K, V> for LazyOpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        LazyOpenList::insert(self, key, value)
+    }
+
+    fn extract_min(&mut self) -> Option<V> {
+        LazyOpenList::extract_min(self)
+    }
+
+    fn len(&self) -> usize {
+        LazyOpenList::len(self)
+    }
+}
+
+// BinaryHeap is a max-heap, so the ordering is reversed: lower f first, then older entries first.
+impl<K, V> Ord for LazyEntry<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.cmp(&self.f)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl<K, V> PartialOrd for LazyEntry<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K, V> PartialEq for LazyEntry<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f && self.sequence == other.sequence
+    }
+}
+
+impl<K, V> Eq for LazyEntry<K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, TestState, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::{traced_astar, traced_astar_with_queue}, untraced::untraced_astar::untraced_astar_with_queue};
+
+    #[test]
+    fn test_insert_and_extract_in_f_order() {
+        let mut open_list = LazyOpenList::new();
+
+        let state1 = TestState { id: 1, g_cost: 10, h_cost: 5 }; // f = 15
+        let state2 = TestState { id: 2, g_cost: 5, h_cost: 5 };  // f = 10
+        let state3 = TestState { id: 3, g_cost: 20, h_cost: 0 }; // f = 20
+
+        open_list.insert(state1.key(), state1.clone());
+        open_list.insert(state2.key(), state2.clone());
+        open_list.insert(state3.key(), state3.clone());
+
+        assert_eq!(open_list.len(), 3);
+        assert_eq!(open_list.min(), Some(&state2));
+        assert_eq!(open_list.extract_min(), Some(state2));
+        assert_eq!(open_list.extract_min(), Some(state1));
+        assert_eq!(open_list.extract_min(), Some(state3));
+        assert_eq!(open_list.extract_min(), None);
+        assert!(open_list.is_empty());
+    }
+
+    #[test]
+    fn test_improve_leaves_stale_entry_behind() {
+        let mut open_list = LazyOpenList::new().with_compaction_factor(usize::MAX);
+        let original_state = TestState { id: 1, g_cost: 10, h_cost: 10 }; // f = 20
+        let updated_state = TestState { id: 1, g_cost: 5, h_cost: 5 };   // f = 10
+        let worse_state = TestState { id: 1, g_cost: 30, h_cost: 0 };    // f = 30
+
+        assert_eq!(open_list.insert(original_state.key(), original_state), Insertion::Added);
+        // The original stays in the heap, so there's nothing to hand back.
+        assert_eq!(open_list.insert(updated_state.key(), updated_state.clone()), Insertion::Improved(None));
+        assert_eq!(open_list.insert(worse_state.key(), worse_state.clone()), Insertion::Rejected(worse_state));
+
+        assert_eq!(open_list.live_entries(), 1);
+        assert_eq!(open_list.stale_entries(), 1);
+
+        assert_eq!(open_list.extract_min(), Some(updated_state));
+        assert_eq!(open_list.extract_min(), None);
+        assert_eq!(open_list.total_entries(), 0);
+    }
+
+    #[test]
+    fn test_compact_keeps_only_live_entries() {
+        let mut open_list = LazyOpenList::new().with_compaction_factor(usize::MAX);
+
+        for id in 0..10 {
+            for g_cost in (0..5).rev() {
+                open_list.insert(id, TestState { id, g_cost, h_cost: 1 });
+            }
+        }
+
+        assert_eq!(open_list.live_entries(), 10);
+        assert_eq!(open_list.stale_entries(), 40);
+
+        open_list.compact();
+
+        assert_eq!(open_list.compactions(), 1);
+        assert_eq!(open_list.stale_entries(), 0);
+        assert_eq!(open_list.total_entries(), 10);
+
+        while let Some(state) = open_list.extract_min() {
+            assert_eq!(state.g_cost, 0);
+        }
+    }
+
+    #[test]
+    fn test_entry_count_stays_bounded_under_decrease_key() {
+        let factor = 2;
+        let mut open_list = LazyOpenList::new().with_compaction_factor(factor);
+        let live_keys = 50;
+
+        for round in (0..200).rev() {
+            for id in 0..live_keys {
+                open_list.insert(id, TestState { id, g_cost: round, h_cost: 1 });
+                assert!(open_list.total_entries() <= (open_list.live_entries() * (factor + 1)).max(1) + 1);
+            }
+        }
+
+        assert!(open_list.compactions() > 0);
+        assert_eq!(open_list.live_entries(), live_keys as usize);
+
+        let mut extracted = 0;
+        while let Some(state) = open_list.extract_min() {
+            assert_eq!(state.g_cost, 0);
+            extracted += 1;
+        }
+        assert_eq!(extracted, live_keys);
+    }
+
+    #[test]
+    fn test_as_search_backend() {
+        let untraced = untraced_astar_with_queue(
+            GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7),
+            LazyOpenList::new()
+        ).unwrap();
+        assert_eq!(untraced.final_state.g(), 9);
+
+        let traced = traced_astar_with_queue(
+            GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7),
+            LazyOpenList::new()
+        ).unwrap();
+        let reference = traced_astar(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)).unwrap();
+        assert_eq!(traced.final_state.g(), reference.final_state.g());
+        assert_eq!(traced.path.len(), reference.path.len());
+    }
+}
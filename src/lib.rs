@@ -1,4 +1,85 @@
-mod open_list;
+// Core: states, queues, the A* searches and their options. Nothing here depends on an
+// optional module.
+pub mod open_list;
+pub mod queue_observer;
+pub mod priority_queue;
+pub mod lazy_open_list;
+pub mod banded_open_list;
+pub mod btree_open_list;
+pub mod closed_store;
 pub mod astar_state;
 pub mod untraced;
 pub mod traced;
+pub mod search_options;
+pub mod search_stats;
+pub mod search_id;
+pub mod fingerprint;
+pub mod edge_costs;
+pub mod validation;
+pub mod plateau;
+pub mod state_pool;
+pub mod successor_cache;
+pub mod recording;
+pub mod weight_schedule;
+pub mod unreachable;
+pub mod goal_set;
+pub mod start_set;
+pub mod labels;
+pub mod expansion_limit;
+
+// Optional modules, one cargo feature each.
+#[cfg(feature = "ida")]
+pub mod transposition;
+#[cfg(feature = "micro")]
+pub mod micro;
+#[cfg(feature = "estimate")]
+pub mod estimate;
+#[cfg(feature = "grid")]
+pub mod grid;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "bidirectional")]
+pub mod bidirectional;
+#[cfg(feature = "bidirectional")]
+pub mod reusable_search;
+#[cfg(feature = "incremental")]
+pub mod incremental;
+#[cfg(feature = "moving-target")]
+pub mod moving_target;
+#[cfg(feature = "operator-stats")]
+pub mod operator_stats;
+#[cfg(feature = "quantized")]
+pub mod quantized;
+#[cfg(feature = "abstraction")]
+pub mod abstraction;
+#[cfg(feature = "pdb")]
+pub mod pdb;
+#[cfg(feature = "auto")]
+pub mod auto;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+#[cfg(feature = "bench-report")]
+pub mod bench_report;
+#[cfg(feature = "puzzles")]
+pub mod puzzles;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(any(test, feature = "testing"))]
+pub mod conformance;
+#[cfg(any(test, feature = "mirror"))]
+pub mod mirrored_queue;
+#[cfg(any(test, feature = "opstats"))]
+pub mod open_list_stats;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+
+mod prioritized;
+mod hashed;
+mod capacity;
+mod quantile;
+mod rng;
+
+// Fixtures for the tests of every module, so which of them get used depends on the features.
+#[cfg(test)]
+#[allow(dead_code)]
+mod test_support;
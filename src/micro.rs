@@ -0,0 +1,166 @@
+use std::{fmt::Display, hash::Hash};
+
+use crate::untraced::{result::UntracedResult, state::UntracedState};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum MicroError {
+    CapacityExceeded { max_nodes: usize }
+}
+
+impl Display for MicroError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MicroError::CapacityExceeded { max_nodes } => {
+                write!(f, "search needed more than {} nodes", max_nodes)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MicroError {}
+
+struct MicroNode<K, S> {
+    key: K,
+    f: usize,
+    state: S,
+    open: bool
+}
+
+// A* for tiny problems. Every node lives in a single arena of `max_nodes` entries and both the
+// open and closed lists are linear scans over it, so no hashing or map allocation takes place.
+// Returns an error if the search needs to store more than `max_nodes` distinct keys. The scans
+// make it quadratic in the nodes stored: it beats untraced_astar up to about 120 of them and falls
+// behind past that, see tests/micro_bench.rs.
+pub fn astar_small<S, K>(initial_state: S, max_nodes: usize) -> Result<Option<UntracedResult<S, K>>, MicroError>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    if max_nodes == 0 {
+        return Err(MicroError::CapacityExceeded { max_nodes });
+    }
+
+    let mut nodes: Vec<MicroNode<K, S>> = Vec::with_capacity(max_nodes);
+    let mut closed_count = 0;
+
+    nodes.push(MicroNode {
+        key: initial_state.key(),
+        f: initial_state.f(),
+        state: initial_state,
+        open: true
+    });
+
+    while let Some(current) = min_open_node(&nodes) {
+        if nodes[current].state.is_goal() {
+            let final_state = nodes.swap_remove(current).state;
+            let iterations = closed_count;
+
+            return Ok(Some(
+                UntracedResult::new(
+                    iterations,
+                    final_state
+                )
+            ));
+        }
+
+        nodes[current].open = false;
+        closed_count += 1;
+
+        let successors = nodes[current].state.generate_successors();
+        for successor in successors {
+            let successor_key = successor.key();
+            let successor_f = successor.f();
+
+            if let Some(node) = nodes.iter_mut().find(|node| node.key == successor_key) {
+                if node.open && successor_f < node.f {
+                    node.f = successor_f;
+                    node.state = successor;
+                }
+                continue;
+            }
+
+            if nodes.len() == max_nodes {
+                return Err(MicroError::CapacityExceeded { max_nodes });
+            }
+
+            nodes.push(MicroNode {
+                key: successor_key,
+                f: successor_f,
+                state: successor,
+                open: true
+            });
+        }
+    }
+
+    Ok(None)
+}
+
+fn min_open_node<K, S>(nodes: &[MicroNode<K, S>]) -> Option<usize> {
+    let mut best: Option<usize> = None;
+
+    for (index, node) in nodes.iter().enumerate() {
+        if !node.open {
+            continue;
+        }
+
+        match best {
+            Some(best_index) if nodes[best_index].f <= node.f => {}
+            _ => best = Some(index)
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, untraced::untraced_astar::untraced_astar};
+
+    fn graph_start(goal: usize) -> GraphState {
+        // The heuristic is only admissible towards node 7, other goals use a zero heuristic.
+        let h_values = if goal == 7 { EIGHT_NODES_H } else { ZERO_H };
+        GraphState::new(EIGHT_NODES, h_values, 0, goal)
+    }
+
+    #[test]
+    fn test_same_cost_as_untraced_astar() {
+        for goal in 0..EIGHT_NODES.len() {
+            let micro = astar_small(graph_start(goal), 8).unwrap();
+            let general = untraced_astar(graph_start(goal));
+
+            assert_eq!(
+                micro.map(|result| result.final_state.g()),
+                general.map(|result| result.final_state.g())
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_optimal_cost() {
+        let result = astar_small(graph_start(7), 8).unwrap().unwrap();
+        assert_eq!(result.final_state.g(), 9);
+        assert!(result.final_state.is_goal());
+    }
+
+    #[test]
+    fn test_capacity_exceeded() {
+        assert_eq!(
+            astar_small(graph_start(7), 3).err(),
+            Some(MicroError::CapacityExceeded { max_nodes: 3 })
+        );
+        assert_eq!(
+            astar_small(graph_start(7), 0).err(),
+            Some(MicroError::CapacityExceeded { max_nodes: 0 })
+        );
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        let start = GraphState::new(SPLIT, &[0, 0, 0], 0, 2);
+
+        assert!(astar_small(start, 3).unwrap().is_none());
+    }
+}
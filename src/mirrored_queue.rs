@@ -0,0 +1,385 @@
+use std::{cell::RefCell, collections::HashMap, fmt::{Debug, Display}, hash::Hash, marker::PhantomData};
+
+use crate::{astar_state::AStarState, priority_queue::{Insertion, PriorityQueue}};
+
+// One operation forwarded by a MirroredQueue, with the outcome the primary backend reported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueueOperation<K> {
+    Insert { key: K, f: usize, outcome: InsertionKind },
+    // Key and f of the extracted value, None if the queue was empty.
+    ExtractMin(Option<(K, usize)>),
+    Len(usize)
+}
+
+// Insertion without the values it carries, so outcomes of the two backends can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InsertionKind {
+    Added,
+    Improved,
+    Rejected
+}
+
+impl<V> From<&Insertion<V>> for InsertionKind {
+    fn from(insertion: &Insertion<V>) -> Self {
+        match insertion {
+            Insertion::Added => InsertionKind::Added,
+            Insertion::Improved(_) => InsertionKind::Improved,
+            Insertion::Rejected(_) => InsertionKind::Rejected
+        }
+    }
+}
+
+// Every operation forwarded so far, numbered from 0 in the order they happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationLog<K> {
+    operations: Vec<QueueOperation<K>>
+}
+
+impl<K> OperationLog<K> {
+    pub fn operations(&self) -> &[QueueOperation<K>] {
+        &self.operations
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+impl<K> Display for OperationLog<K>
+where
+    K: Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, operation) in self.operations.iter().enumerate() {
+            writeln!(f, "{:>6}: {:?}", index, operation)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Primary,
+    Secondary
+}
+
+impl Backend {
+    fn other(self) -> Self {
+        match self {
+            Backend::Primary => Backend::Secondary,
+            Backend::Secondary => Backend::Primary
+        }
+    }
+}
+
+// Debugging adapter that runs two queue backends in lock-step. Every operation goes to both, the
+// primary's answers are returned, and the first time the backends disagree it panics with the
+// index of the operation and the whole log.
+//
+// Backends may break ties between equal f values differently, so extractions only have to agree
+// on f unless strict mode asks for equal keys too. When a tie is broken differently, each key is
+// remembered as unmatched until the other backend extracts it as well, and in the meantime
+// inserts of that key aren't compared (the caller only knows about the primary's extraction, so
+// a key only the secondary extracted goes to the primary alone, as if it had been consumed by
+// both).
+pub struct MirroredQueue<A, B, K, V> {
+    primary: A,
+    secondary: B,
+    strict: bool,
+    // Keys extracted from one backend only, with the backend that still holds them.
+    unmatched: HashMap<K, Backend>,
+    // len() takes &self but is logged like every other operation.
+    log: RefCell<OperationLog<K>>,
+    values: PhantomData<V>
+}
+
+impl<A, B, K, V> MirroredQueue<A, B, K, V>
+where
+    A: PriorityQueue<K, V>,
+    B: PriorityQueue<K, V>,
+    K: Clone + Eq + Hash + Debug,
+    V: AStarState<K> + Clone
+{
+    pub fn new(primary: A, secondary: B) -> Self {
+        MirroredQueue {
+            primary,
+            secondary,
+            strict: false,
+            unmatched: HashMap::new(),
+            log: RefCell::new(OperationLog { operations: Vec::new() }),
+            values: PhantomData
+        }
+    }
+
+    // Extractions must also return the same key from both backends.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    // Copy of the log as it is now.
+    pub fn snapshot(&self) -> OperationLog<K> {
+        self.log.borrow().clone()
+    }
+
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.primary, self.secondary)
+    }
+
+    // `key` was extracted from `backend`, either catching up with the other one or running ahead.
+    fn settle(&mut self, key: K, backend: Backend) {
+        if self.unmatched.get(&key) == Some(&backend) {
+            self.unmatched.remove(&key);
+        }
+        else {
+            self.unmatched.insert(key, backend.other());
+        }
+    }
+
+    fn record(&self, operation: QueueOperation<K>, divergence: Option<String>) {
+        let mut log = self.log.borrow_mut();
+        let index = log.len();
+        log.operations.push(operation);
+
+        if let Some(divergence) = divergence {
+            panic!("queues diverged at operation {}: {}\n{}", index, divergence, log);
+        }
+    }
+}
+
+impl<A, B, K, V> PriorityQueue<K, V> for MirroredQueue<A, B, K, V>
+where
+    A: PriorityQueue<K, V>,
+    B: PriorityQueue<K, V>,
+    K: Clone + Eq + Hash + Debug,
+    V: AStarState<K> + Clone
+{
+    fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        let f = value.f();
+        let unmatched = self.unmatched.get(&key).copied();
+
+        let secondary = match unmatched {
+            Some(Backend::Primary) => None,
+            _ => Some(InsertionKind::from(&self.secondary.insert(key.clone(), value.clone())))
+        };
+        let primary_insertion = self.primary.insert(key.clone(), value);
+        let primary = InsertionKind::from(&primary_insertion);
+
+        let divergence = (unmatched.is_none() && secondary != Some(primary))
+            .then(|| format!("insert returned {:?} and {:?}", primary, secondary));
+        self.record(QueueOperation::Insert { key, f, outcome: primary }, divergence);
+
+        primary_insertion
+    }
+
+    fn extract_min(&mut self) -> Option<V> {
+        let primary_value = self.primary.extract_min();
+        let primary = primary_value.as_ref().map(|value| (value.key(), value.f()));
+        let secondary = self.secondary.extract_min().map(|value| (value.key(), value.f()));
+
+        let agrees = match (&primary, &secondary) {
+            (Some((primary_key, primary_f)), Some((secondary_key, secondary_f))) => {
+                let same_key = primary_key == secondary_key;
+                if !same_key && !self.strict {
+                    self.settle(primary_key.clone(), Backend::Primary);
+                    self.settle(secondary_key.clone(), Backend::Secondary);
+                }
+                primary_f == secondary_f && (same_key || !self.strict)
+            }
+            // One backend is empty, so the other one may only be catching up.
+            (Some((key, _)), None) => self.unmatched.remove(key).is_some_and(|holder| holder == Backend::Primary),
+            (None, Some((key, _))) => self.unmatched.remove(key).is_some_and(|holder| holder == Backend::Secondary),
+            (None, None) => true
+        };
+        let divergence = (!agrees)
+            .then(|| format!("extract_min returned {:?} and {:?}", primary, secondary));
+        self.record(QueueOperation::ExtractMin(primary), divergence);
+
+        primary_value
+    }
+
+    fn len(&self) -> usize {
+        let primary = self.primary.len();
+        let secondary = self.secondary.len();
+        let held_by_primary = self.unmatched.values().filter(|&&holder| holder == Backend::Primary).count();
+        let held_by_secondary = self.unmatched.len() - held_by_primary;
+
+        let divergence = (primary + held_by_secondary != secondary + held_by_primary)
+            .then(|| format!("len returned {} and {}", primary, secondary));
+        self.record(QueueOperation::Len(primary), divergence);
+
+        primary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::*;
+    use crate::{lazy_open_list::LazyOpenList, open_list::OpenList};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Entry {
+        key: usize,
+        f: usize
+    }
+
+    impl AStarState<usize> for Entry {
+        fn key(&self) -> usize {
+            self.key
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.f
+        }
+
+        fn g(&self) -> usize {
+            self.f
+        }
+
+        fn is_goal(&self) -> bool {
+            false
+        }
+    }
+
+    // Linear scan queue that reports improvements but, with the bug on, keeps the old value.
+    struct ToyQueue {
+        entries: Vec<Entry>,
+        forgets_improvements: bool
+    }
+
+    impl PriorityQueue<usize, Entry> for ToyQueue {
+        fn insert(&mut self, key: usize, value: Entry) -> Insertion<Entry> {
+            match self.entries.iter_mut().find(|entry| entry.key == key) {
+                Some(entry) if value.f < entry.f => {
+                    if !self.forgets_improvements {
+                        *entry = value;
+                    }
+                    Insertion::Improved(None)
+                }
+                Some(_) => Insertion::Rejected(value),
+                None => {
+                    self.entries.push(value);
+                    Insertion::Added
+                }
+            }
+        }
+
+        fn extract_min(&mut self) -> Option<Entry> {
+            let (index, _) = self.entries.iter().enumerate().min_by_key(|(_, entry)| entry.f)?;
+            Some(self.entries.remove(index))
+        }
+
+        fn len(&self) -> usize {
+            self.entries.len()
+        }
+    }
+
+    fn toy(forgets_improvements: bool) -> ToyQueue {
+        ToyQueue {
+            entries: Vec::new(),
+            forgets_improvements
+        }
+    }
+
+    fn divergence_message(run: impl FnOnce()) -> String {
+        let payload = catch_unwind(AssertUnwindSafe(run)).expect_err("the mirror should have panicked");
+        payload.downcast::<String>().map(|message| *message).unwrap()
+    }
+
+    #[test]
+    fn test_catches_the_bug_at_its_operation() {
+        let operations = |queue: &mut MirroredQueue<ToyQueue, ToyQueue, usize, Entry>| {
+            queue.insert(1, Entry { key: 1, f: 5 });
+            queue.insert(2, Entry { key: 2, f: 7 });
+            queue.insert(3, Entry { key: 3, f: 6 });
+            queue.insert(2, Entry { key: 2, f: 4 });
+            assert_eq!(queue.len(), 3);
+            queue.extract_min();
+            queue.extract_min();
+        };
+
+        let mut healthy = MirroredQueue::new(toy(false), toy(false)).with_strict(true);
+        operations(&mut healthy);
+        assert_eq!(healthy.snapshot().len(), 7);
+        assert_eq!(healthy.snapshot().operations()[5], QueueOperation::ExtractMin(Some((2, 4))));
+
+        // The improvement of key 2 is accepted by both, but only shows up at the first extraction.
+        let message = divergence_message(|| operations(&mut MirroredQueue::new(toy(false), toy(true))));
+        assert!(message.starts_with("queues diverged at operation 5: extract_min returned Some((2, 4)) and Some((1, 5))"), "{}", message);
+        assert!(message.contains("     3: Insert { key: 2, f: 4, outcome: Improved }"), "{}", message);
+    }
+
+    #[test]
+    fn test_tie_breaking_is_tolerated_unless_strict() {
+        let operations = |queue: &mut MirroredQueue<OpenList<usize, Entry>, LazyOpenList<usize, Entry>, usize, Entry>| {
+            for key in 0..3 {
+                queue.insert(key, Entry { key, f: 1 });
+            }
+            let extracted: Vec<usize> = std::iter::from_fn(|| queue.extract_min()).map(|entry| entry.key).collect();
+            assert!(queue.is_empty());
+            extracted
+        };
+
+        // The indexed heap returns 0, 2, 1 and the lazy one 0, 1, 2.
+        let mut relaxed = MirroredQueue::new(OpenList::new(), LazyOpenList::new());
+        assert_eq!(operations(&mut relaxed), vec![0, 2, 1]);
+
+        let message = divergence_message(|| {
+            operations(&mut MirroredQueue::new(OpenList::new(), LazyOpenList::new()).with_strict(true));
+        });
+        assert!(message.starts_with("queues diverged at operation 4: extract_min returned Some((2, 1)) and Some((1, 1))"), "{}", message);
+    }
+
+    #[test]
+    #[cfg(feature = "grid")]
+    fn test_heap_backends_agree_over_a_search() {
+        use crate::{grid::{map::GridMap, region::GoalRegion, state::GridState}, untraced::untraced_astar::{untraced_astar, untraced_astar_with_queue}};
+
+        let map = GridMap::from_ascii("
+            ..........
+            .####.###.
+            .#......#.
+            .#.####.#.
+            ...#......
+        ");
+        let region = GoalRegion::Cell(9, 4);
+        let start = GridState::with_region(&map, (0, 0), &region);
+
+        let queue = MirroredQueue::new(OpenList::new(), LazyOpenList::new());
+        let mirrored = untraced_astar_with_queue(start.clone(), queue).unwrap();
+        let plain = untraced_astar(start.clone()).unwrap();
+
+        assert_eq!(mirrored.final_state.g(), plain.final_state.g());
+        assert_eq!(mirrored.iterations, plain.iterations);
+
+        // The two heaps do order the ties of this search differently.
+        let message = divergence_message(|| {
+            untraced_astar_with_queue(start.clone(), MirroredQueue::new(OpenList::new(), LazyOpenList::new()).with_strict(true));
+        });
+        assert!(message.contains("extract_min returned"), "{}", message);
+    }
+}
@@ -1,14 +1,44 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{collections::hash_map::RandomState, fmt::Display, hash::{BuildHasher, DefaultHasher, Hash, Hasher}};
+
+use crate::{astar_state::AStarState, hashed::{query, Hashed, HashedMap}, priority_queue::{Insertion, PriorityQueue}, queue_observer::{NoObserver, QueueObserver}};
+#[cfg(any(test, feature = "opstats"))]
+use crate::open_list_stats::OpenListStats;
+
+// The heap and the index map of an OpenList disagree, as found by the fallible operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptionError {
+    pub operation: &'static str,
+    // Hash of the key involved, since keys need not be Debug.
+    pub key_hash: u64,
+    // Heap index the map holds for the key.
+    pub index: Option<usize>,
+    pub heap_len: usize,
+    pub map_len: usize
+}
 
-use crate::astar_state::AStarState;
+impl Display for CorruptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "open list corrupted in {} (key hash {:016x}, index {:?}, heap len {}, map len {})", self.operation, self.key_hash, self.index, self.heap_len, self.map_len)
+    }
+}
 
-pub struct OpenList<K, V>
+impl std::error::Error for CorruptionError {}
+
+// Keys are hashed once, when they are inserted: the heap keeps the hash with the key, and moving
+// an entry only updates its index in the map. `O` is told of every change to the heap, and is a
+// no-op unless set_observer gives the list another one.
+pub struct OpenList<K, V, O = NoObserver>
 where
     K: Clone + Eq + Hash,
-    V: AStarState<K>
+    V: AStarState<K>,
+    O: QueueObserver<K>
 {
-    heap: Vec<(K, V)>,
-    map: HashMap<K, usize>
+    heap: Vec<(Hashed<K>, V)>,
+    map: HashedMap<K, usize>,
+    hasher: RandomState,
+    observer: O,
+    #[cfg(any(test, feature = "opstats"))]
+    stats: OpenListStats
 }
 
 impl<K, V> OpenList<K, V>
@@ -19,65 +49,277 @@ where
     pub fn new() -> Self {
         OpenList {
             heap: Vec::new(),
-            map: HashMap::new()
+            map: HashedMap::default(),
+            hasher: RandomState::new(),
+            observer: NoObserver,
+            #[cfg(any(test, feature = "opstats"))]
+            stats: OpenListStats::default()
+        }
+    }
+}
+
+impl<K, V, O> OpenList<K, V, O>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>,
+    O: QueueObserver<K>
+{
+    // The same list, watched by `observer` from now on. Entries already queued aren't announced.
+    pub fn set_observer<P>(self, observer: P) -> OpenList<K, V, P>
+    where
+        P: QueueObserver<K>
+    {
+        OpenList {
+            heap: self.heap,
+            map: self.map,
+            hasher: self.hasher,
+            observer,
+            #[cfg(any(test, feature = "opstats"))]
+            stats: self.stats
         }
     }
 
-    #[allow(dead_code)]
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
     pub fn is_empty(&self) -> bool {
         self.heap.is_empty()
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
+    pub fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        let key = self.hashed(key);
+        self.insert_hashed(key, value)
+    }
+
+    // `key` with its hash under this list's hasher, for searches that keep their closed list by
+    // the same hashes so keys are hashed only once.
+    pub(crate) fn hashed(&self, key: K) -> Hashed<K> {
+        Hashed::new(self.hash_of(&key), key)
+    }
+
+    pub(crate) fn hash_of(&self, key: &K) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    // insert with a key from `hashed`.
+    pub(crate) fn insert_hashed(&mut self, key: Hashed<K>, value: V) -> Insertion<V> {
         let value_f = value.f();
 
         if let Some(&index) = self.map.get(&key) {
+            debug_assert!(self.entry_matches(key.hash_value(), key.key(), index), "open list index map out of sync for a key");
             if value_f < self.heap[index].1.f() {
-                self.heap[index] = (key.clone(), value);
-                self.bubble_up(index);
+                self.observer.on_improve(key.key(), self.heap[index].1.f(), value_f);
+                let previous = std::mem::replace(&mut self.heap[index].1, value);
+                let _swaps = self.bubble_up(index);
+                #[cfg(any(test, feature = "opstats"))]
+                self.stats.record_improved(_swaps);
+                Insertion::Improved(Some(previous))
+            }
+            else {
+                #[cfg(any(test, feature = "opstats"))]
+                self.stats.record_rejected();
+                Insertion::Rejected(value)
             }
         }
         else {
             let index = self.heap.len();
+            self.observer.on_insert(key.key(), index);
             self.heap.push((key.clone(), value));
             self.map.insert(key, index);
-            self.bubble_up(index);
+            let _swaps = self.bubble_up(index);
+            #[cfg(any(test, feature = "opstats"))]
+            self.stats.record_added(_swaps);
+            Insertion::Added
         }
     }
 
-    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    // Room for at least `additional` more entries without reallocating.
+    pub fn reserve(&mut self, additional: usize) {
+        self.heap.reserve(additional);
+        self.map.reserve(additional);
+    }
+
     pub fn min(&self) -> Option<&V> {
         self.heap.first().map(|(_, value)| value)
     }
 
+    // Drops every entry, keeping the memory for the next search. The observer is told of each
+    // one as extracted.
+    pub fn clear(&mut self) {
+        for (key, _) in &self.heap {
+            self.observer.on_extract(key.key());
+        }
+        self.heap.clear();
+        self.map.clear();
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index_of(key).is_some()
+    }
+
+    // What the inserts so far did, to choose a backend with open_list_stats::recommend_backend.
+    #[cfg(any(test, feature = "opstats"))]
+    pub fn stats(&self) -> &OpenListStats {
+        &self.stats
+    }
+
+    // Lets `update` change the f of every entry in place, then restores the heap order at once,
+    // in linear time. `update` must leave the keys alone.
+    pub fn reprioritize(&mut self, mut update: impl FnMut(&mut V)) {
+        for (_, value) in &mut self.heap {
+            update(value);
+        }
+        for index in (0..self.heap.len() / 2).rev() {
+            self.buble_down(index);
+        }
+    }
+
+    // Key of the entry at `index` in heap order, for picking an arbitrary entry.
+    pub(crate) fn key_at(&self, index: usize) -> Option<&K> {
+        self.heap.get(index).map(|(key, _)| key.key())
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.index_of(key)?;
+        self.heap.get(index).map(|(_, value)| value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let hash = self.hash_of(key);
+        let index = *self.map.get(query(&(hash, key)))?;
+        debug_assert!(self.entry_matches(hash, key, index), "open list index map out of sync for a key");
+
+        let last_index = self.heap.len() - 1;
+        self.swap(index, last_index);
+        let value = self.pop().map(|(_, value)| value);
+
+        if index < self.heap.len() {
+            self.buble_down(index);
+            self.bubble_up(index);
+        }
+
+        value
+    }
+
     pub fn extract_min(&mut self) -> Option<V> {
+        self.extract_min_hashed().map(|(_, value)| value)
+    }
+
+    // extract_min with the key the entry was stored under.
+    pub(crate) fn extract_min_hashed(&mut self) -> Option<(Hashed<K>, V)> {
+        debug_assert_eq!(self.map.len(), self.heap.len(), "open list index map out of sync");
         if self.heap.is_empty() {
             return None;
         }
 
         let last_index = self.heap.len() - 1;
         self.swap(0, last_index);
-        let min_value = self.pop();
+        let min_entry = self.pop();
 
         if !self.heap.is_empty() {
             self.buble_down(0);
         }
 
-        min_value
+        min_entry
+    }
+
+    // Fallible insert, extract_min and remove. The entries they start from are checked against
+    // the index map first, and a mismatch is reported instead of panicking or going on with the
+    // list out of sync. The infallible versions only check in debug builds.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Insertion<V>, CorruptionError> {
+        let key = self.hashed(key);
+        match self.map.get(&key) {
+            Some(&index) if !self.entry_matches(key.hash_value(), key.key(), index) => return Err(self.corruption("insert", key.key(), Some(index))),
+            None if self.map.len() != self.heap.len() => return Err(self.corruption("insert", key.key(), None)),
+            _ => {}
+        }
+
+        Ok(self.insert_hashed(key, value))
+    }
+
+    pub fn try_extract_min(&mut self) -> Result<Option<V>, CorruptionError> {
+        let Some((key, _)) = self.heap.first() else {
+            return match self.map.keys().next() {
+                Some(key) => Err(self.corruption("extract_min", key.key(), self.map.get(key).copied())),
+                None => Ok(None)
+            };
+        };
+
+        if !self.entry_matches(key.hash_value(), key.key(), 0) {
+            return Err(self.corruption("extract_min", key.key(), self.map.get(key).copied()));
+        }
+
+        Ok(self.extract_min())
+    }
+
+    pub fn try_remove(&mut self, key: &K) -> Result<Option<V>, CorruptionError> {
+        let hash = self.hash_of(key);
+        match self.map.get(query(&(hash, key))) {
+            Some(&index) if !self.entry_matches(hash, key, index) => Err(self.corruption("remove", key, Some(index))),
+            Some(_) => Ok(self.remove(key)),
+            None => Ok(None)
+        }
+    }
+
+    fn index_of(&self, key: &K) -> Option<usize> {
+        self.map.get(query(&(self.hash_of(key), key))).copied()
+    }
+
+    // The map points `key` (with hash `hash`) at `index`, the heap holds `key` there, and both
+    // have the same size.
+    fn entry_matches(&self, hash: u64, key: &K, index: usize) -> bool {
+        self.map.len() == self.heap.len()
+            && self.map.get(query(&(hash, key))) == Some(&index)
+            && self.heap.get(index).is_some_and(|(stored, _)| stored.key() == key)
+    }
+
+    fn corruption(&self, operation: &'static str, key: &K, index: Option<usize>) -> CorruptionError {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        CorruptionError {
+            operation,
+            key_hash: hasher.finish(),
+            index,
+            heap_len: self.heap.len(),
+            map_len: self.map.len()
+        }
+    }
+
+    // Points `key` at another heap index, so tests can check what the fallible operations make of it.
+    #[cfg(test)]
+    pub(crate) fn corrupt_index(&mut self, key: &K, index: usize) {
+        let key = self.hashed(key.clone());
+        self.map.insert(key, index);
     }
 
     fn swap(&mut self, i: usize, j: usize) {
         self.heap.swap(i, j);
 
-        let (key_i, _) = &self.heap[i];
-        let (key_j, _) = &self.heap[j];
+        for index in [i, j] {
+            if let Some(slot) = self.map.get_mut(&self.heap[index].0) {
+                *slot = index;
+            }
+        }
 
-        self.map.insert(key_i.clone(), i);
-        self.map.insert(key_j.clone(), j);
+        if i != j {
+            self.observer.on_swap(self.heap[j].0.key(), self.heap[i].0.key(), i, j);
+        }
     }
 
-    fn bubble_up(&mut self, index: usize) {
+    // Returns the number of swaps it took.
+    fn bubble_up(&mut self, index: usize) -> usize {
         let mut current = index;
+        let mut swaps = 0;
         while current > 0 {
             let parent = (current - 1) / 2;
 
@@ -90,7 +332,9 @@ where
 
             self.swap(current, parent);
             current = parent;
+            swaps += 1;
         }
+        swaps
     }
     
     fn buble_down(&mut self, index: usize) {
@@ -118,51 +362,49 @@ where
         }
     }
 
-    fn pop(&mut self) -> Option<V> {
-        if self.heap.is_empty() {
-            return None;
-        }
+    // Unindexes the stored key rather than value.key(), which may be another key if the value was
+    // inserted under one it doesn't report.
+    fn pop(&mut self) -> Option<(Hashed<K>, V)> {
+        let (key, value) = self.heap.pop()?;
+        self.map.remove(&key);
+        self.observer.on_extract(key.key());
+        Some((key, value))
+    }
+}
 
-        let last_index = self.heap.len() - 1;
-        let (_, value) = self.heap.remove(last_index);
-        self.map.remove(&value.key());
-        Some(value)
+impl<K, V> Default for OpenList<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<K, V, O> PriorityQueue<K, V> for OpenList<K, V, O>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>,
+    O: QueueObserver<K>
+{
+    fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        OpenList::insert(self, key, value)
+    }
 
-    // A more flexible TestState struct for fine-grained control over costs.
-    #[derive(Debug, PartialEq, Clone)]
-    struct TestState {
-        id: i32,
-        g_cost: usize,
-        h_cost: usize,
+    fn extract_min(&mut self) -> Option<V> {
+        OpenList::extract_min(self)
     }
 
-    impl AStarState<i32> for TestState {
-        fn key(&self) -> i32 {
-            self.id
-        }
-    
-        fn h(&self) -> usize {
-            self.h_cost
-        }
-    
-        fn f(&self) -> usize {
-            self.g() + self.h()
-        }
-    
-        fn g(&self) -> usize {
-            self.g_cost
-        }
-    
-        fn is_goal(&self) -> bool {
-            self.h_cost == 0
-        }
+    fn len(&self) -> usize {
+        OpenList::len(self)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{queue_observer::{QueueEvent, RecordingQueueObserver}, test_support::TestState};
 
     #[test]
     fn test_new_and_is_empty() {
@@ -256,4 +498,110 @@ mod tests {
         assert_eq!(open_list.min(), Some(&original_state));
         assert_eq!(open_list.min().unwrap().f(), 10);
     }
+
+    #[test]
+    fn test_get_and_remove() {
+        let mut open_list = OpenList::new();
+        for (id, g_cost) in [(1, 7), (2, 3), (3, 9), (4, 1), (5, 5), (6, 8)] {
+            open_list.insert(id, TestState { id, g_cost, h_cost: 0 });
+        }
+
+        assert_eq!(open_list.get(&3).map(|state| state.g_cost), Some(9));
+        assert!(open_list.contains_key(&5));
+        assert_eq!(open_list.get(&10), None);
+
+        assert_eq!(open_list.remove(&2).map(|state| state.id), Some(2));
+        assert_eq!(open_list.remove(&2), None);
+        assert_eq!(open_list.remove(&4).map(|state| state.id), Some(4));
+        assert_eq!(open_list.len(), 4);
+
+        let order: Vec<i32> = std::iter::from_fn(|| open_list.extract_min()).map(|state| state.id).collect();
+        assert_eq!(order, vec![5, 1, 6, 3]);
+    }
+
+    #[test]
+    fn test_fallible_operations_report_corruption() {
+        let mut open_list = OpenList::new();
+        for (id, g_cost) in [(1, 4), (2, 2), (3, 6)] {
+            assert_eq!(open_list.try_insert(id, TestState { id, g_cost, h_cost: 0 }), Ok(Insertion::Added));
+        }
+        assert_eq!(open_list.try_remove(&3).map(|state| state.map(|state| state.id)), Ok(Some(3)));
+        assert_eq!(open_list.try_remove(&3), Ok(None));
+
+        // The minimum now points past the end of the heap.
+        open_list.corrupt_index(&2, 7);
+        let error = open_list.try_extract_min().unwrap_err();
+        assert_eq!((error.operation, error.index, error.heap_len, error.map_len), ("extract_min", Some(7), 2, 2));
+        assert!(open_list.try_remove(&2).is_err());
+        assert!(open_list.try_insert(2, TestState { id: 2, g_cost: 1, h_cost: 0 }).is_err());
+
+        // Nothing was changed by the failed operations.
+        assert_eq!(open_list.len(), 2);
+        assert!(error.to_string().starts_with("open list corrupted in extract_min"));
+    }
+
+    #[test]
+    fn test_extract_unindexes_the_stored_key() {
+        // A value whose key() differs from the key it was queued under.
+        let mut open_list = OpenList::new();
+        open_list.insert(1, TestState { id: 2, g_cost: 1, h_cost: 0 });
+        open_list.insert(3, TestState { id: 3, g_cost: 2, h_cost: 0 });
+
+        assert_eq!(open_list.extract_min().map(|state| state.id), Some(2));
+        assert!(!open_list.contains_key(&1));
+        assert_eq!(open_list.try_extract_min().map(|state| state.map(|state| state.id)), Ok(Some(3)));
+        assert!(open_list.is_empty());
+    }
+
+    #[test]
+    fn test_reprioritize_restores_the_heap_order() {
+        let mut open_list = OpenList::new();
+        for (id, g_cost) in [(1, 7), (2, 3), (3, 9), (4, 1), (5, 5)] {
+            open_list.insert(id, TestState { id, g_cost, h_cost: 0 });
+        }
+
+        // Reverses the order.
+        open_list.reprioritize(|state| state.g_cost = 10 - state.g_cost);
+        assert_eq!(open_list.get(&4).map(|state| state.g_cost), Some(9));
+
+        let order: Vec<i32> = std::iter::from_fn(|| open_list.extract_min()).map(|state| state.id).collect();
+        assert_eq!(order, vec![3, 1, 5, 2, 4]);
+    }
+
+    // Replays the recorded events on a plain vector of keys, which must end up as the heap.
+    #[test]
+    fn test_recorded_events_rebuild_the_heap() {
+        let mut open_list = OpenList::new().set_observer(RecordingQueueObserver::new());
+        for (id, g_cost) in [(1, 9), (2, 7), (3, 8), (4, 3), (5, 6), (6, 2), (7, 5)] {
+            open_list.insert(id, TestState { id, g_cost, h_cost: 0 });
+        }
+        open_list.insert(3, TestState { id: 3, g_cost: 1, h_cost: 0 });
+        open_list.insert(5, TestState { id: 5, g_cost: 8, h_cost: 0 });
+        open_list.extract_min();
+        open_list.remove(&2);
+        open_list.insert(8, TestState { id: 8, g_cost: 4, h_cost: 0 });
+        open_list.extract_min();
+
+        let mut keys: Vec<i32> = Vec::new();
+        let mut improvements = Vec::new();
+        for event in &open_list.observer().events {
+            match event.clone() {
+                QueueEvent::Insert { key, index } => {
+                    assert_eq!(index, keys.len());
+                    keys.push(key);
+                }
+                QueueEvent::Swap { a_key, b_key, a_index, b_index } => {
+                    assert_eq!((keys[a_index], keys[b_index]), (a_key, b_key));
+                    keys.swap(a_index, b_index);
+                }
+                QueueEvent::Extract { key } => assert_eq!(keys.pop(), Some(key)),
+                QueueEvent::Improve { key, old_f, new_f } => improvements.push((key, old_f, new_f))
+            }
+        }
+
+        let heap: Vec<i32> = (0..open_list.len()).map(|index| *open_list.key_at(index).unwrap()).collect();
+        assert_eq!(keys, heap);
+        // The worse insert for 5 was rejected, so only 3 was improved.
+        assert_eq!(improvements, vec![(3, 8, 1)]);
+    }
 }
@@ -0,0 +1,204 @@
+use std::fmt::Write;
+
+// Share of the added entries that improvements may leave stale in a LazyOpenList before the
+// indexed heap is recommended instead.
+pub const LAZY_MAX_STALE_SHARE: f64 = 0.1;
+
+// What the inserts into an OpenList did, counted from its creation. An improvement is a
+// decrease-key, and the swaps are those of the bubble up that follows an insert.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenListStats {
+    pub added: usize,
+    pub improved: usize,
+    pub rejected: usize,
+    pub added_swaps: usize,
+    pub improved_swaps: usize,
+    // Most swaps a single improvement took.
+    pub max_improved_swaps: usize
+}
+
+impl OpenListStats {
+    pub fn inserts(&self) -> usize {
+        self.added + self.improved + self.rejected
+    }
+
+    // Share of the inserts for a key already queued.
+    pub fn duplicate_rate(&self) -> f64 {
+        ratio(self.improved + self.rejected, self.inserts())
+    }
+
+    // Share of the duplicate inserts that lowered f.
+    pub fn decrease_key_rate(&self) -> f64 {
+        ratio(self.improved, self.improved + self.rejected)
+    }
+
+    // Swaps per insert that changed the heap, added or improved.
+    pub fn average_bubble_distance(&self) -> f64 {
+        ratio(self.added_swaps + self.improved_swaps, self.added + self.improved)
+    }
+
+    // Swaps per improvement, how far up a decrease-key moves its entry.
+    pub fn average_reinsert_depth(&self) -> f64 {
+        ratio(self.improved_swaps, self.improved)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        let _ = writeln!(summary, "inserts: {} ({} added, {} improved, {} rejected)", self.inserts(), self.added, self.improved, self.rejected);
+        let _ = writeln!(summary, "duplicate rate: {:.1}%, decrease-key rate: {:.1}%", self.duplicate_rate() * 100.0, self.decrease_key_rate() * 100.0);
+        let _ = writeln!(summary, "bubble distance: {:.2} on average", self.average_bubble_distance());
+        let _ = write!(summary, "reinsert depth: {:.2} on average, {} at worst", self.average_reinsert_depth(), self.max_improved_swaps);
+        summary
+    }
+
+    pub(crate) fn record_added(&mut self, swaps: usize) {
+        self.added += 1;
+        self.added_swaps += swaps;
+    }
+
+    pub(crate) fn record_improved(&mut self, swaps: usize) {
+        self.improved += 1;
+        self.improved_swaps += swaps;
+        self.max_improved_swaps = self.max_improved_swaps.max(swaps);
+    }
+
+    pub(crate) fn record_rejected(&mut self) {
+        self.rejected += 1;
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    }
+    else {
+        part as f64 / whole as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BackendHint {
+    // OpenList, whose index map makes a decrease-key an in-place update.
+    IndexedHeap,
+    // LazyOpenList, which skips the index map and pushes a new entry per improvement.
+    LazyDeletion
+}
+
+// Picks the open list for a workload like the one `stats` counted. Every improvement leaves a
+// stale entry in a LazyOpenList, so it is recommended while improvements stay at most
+// LAZY_MAX_STALE_SHARE of the added entries: its heap then grows by at most that share, and every
+// swap saves the index map updates. More improvements than that, or no inserts at all, give the
+// indexed heap.
+pub fn recommend_backend(stats: &OpenListStats) -> BackendHint {
+    if stats.added > 0 && ratio(stats.improved, stats.added) <= LAZY_MAX_STALE_SHARE {
+        BackendHint::LazyDeletion
+    }
+    else {
+        BackendHint::IndexedHeap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, open_list::OpenList, test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H}, untraced::untraced_astar::untraced_astar_with_queue};
+
+    #[derive(Debug, Clone)]
+    struct Entry {
+        key: usize,
+        f: usize
+    }
+
+    impl AStarState<usize> for Entry {
+        fn key(&self) -> usize {
+            self.key
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.f
+        }
+
+        fn g(&self) -> usize {
+            self.f
+        }
+
+        fn is_goal(&self) -> bool {
+            false
+        }
+    }
+
+    fn insert(open_list: &mut OpenList<usize, Entry>, key: usize, f: usize) {
+        open_list.insert(key, Entry { key, f });
+    }
+
+    #[test]
+    fn test_counts_and_swaps() {
+        let mut open_list = OpenList::new();
+        insert(&mut open_list, 0, 5);
+        insert(&mut open_list, 1, 3);
+        insert(&mut open_list, 2, 4);
+        insert(&mut open_list, 3, 6);
+        // Key 3 sits below key 0 below key 1, at the root.
+        insert(&mut open_list, 3, 1);
+        insert(&mut open_list, 2, 9);
+
+        let stats = *open_list.stats();
+        assert_eq!(stats, OpenListStats { added: 4, improved: 1, rejected: 1, added_swaps: 1, improved_swaps: 2, max_improved_swaps: 2 });
+        assert_eq!(stats.duplicate_rate(), 2.0 / 6.0);
+        assert_eq!(stats.decrease_key_rate(), 0.5);
+        assert_eq!(stats.average_bubble_distance(), 3.0 / 5.0);
+        assert_eq!(stats.average_reinsert_depth(), 2.0);
+        assert_eq!(stats.summary(), "\
+inserts: 6 (4 added, 1 improved, 1 rejected)
+duplicate rate: 33.3%, decrease-key rate: 50.0%
+bubble distance: 0.60 on average
+reinsert depth: 2.00 on average, 2 at worst");
+    }
+
+    #[test]
+    fn test_frequent_improvements_want_the_indexed_heap() {
+        let mut open_list = OpenList::new();
+        for key in 0..1000 {
+            insert(&mut open_list, key, 2000 + key);
+        }
+        for key in 0..500 {
+            insert(&mut open_list, key, 1000 - key);
+        }
+
+        let stats = open_list.stats();
+        assert_eq!(stats.decrease_key_rate(), 1.0);
+        assert!(stats.average_reinsert_depth() > 1.0);
+        assert_eq!(recommend_backend(stats), BackendHint::IndexedHeap);
+    }
+
+    #[test]
+    fn test_rare_improvements_want_lazy_deletion() {
+        let mut open_list = OpenList::new();
+        for key in 0..1000 {
+            insert(&mut open_list, key, 2000 + key);
+        }
+        // Mostly worse paths to queued keys, few better ones.
+        for key in 0..400 {
+            insert(&mut open_list, key, if key % 10 == 0 { key } else { 5000 });
+        }
+
+        let stats = open_list.stats();
+        assert_eq!(stats.decrease_key_rate(), 0.1);
+        assert_eq!(recommend_backend(stats), BackendHint::LazyDeletion);
+        assert_eq!(recommend_backend(&OpenListStats::default()), BackendHint::IndexedHeap);
+    }
+
+    #[test]
+    fn test_stats_of_a_lent_open_list() {
+        let mut open_list = OpenList::new();
+        let result = untraced_astar_with_queue(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), &mut open_list).unwrap();
+
+        assert_eq!(result.final_state.g(), 9);
+        assert!(open_list.stats().added > result.iterations);
+    }
+}
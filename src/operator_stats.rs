@@ -0,0 +1,267 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::traced::{result::TracedResult, state::TracedState};
+
+// How often each class of change appeared on solution paths. The class comes from a user
+// classifier, e.g. the variant of a move without its payload. Accumulate it over many solved
+// instances and turn it into a successor ordering with `successor_priority`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "C: serde::Serialize",
+    deserialize = "C: serde::Deserialize<'de>"
+)))]
+pub struct OperatorStats<C>
+where
+    C: Eq + Hash
+{
+    appearances: HashMap<C, usize>,
+    solutions: usize
+}
+
+impl<C> OperatorStats<C>
+where
+    C: Eq + Hash
+{
+    pub fn new() -> Self {
+        OperatorStats {
+            appearances: HashMap::new(),
+            solutions: 0
+        }
+    }
+
+    pub fn record_path<X>(&mut self, path: impl IntoIterator<Item = X>, classify: impl Fn(&X) -> C) {
+        for change in path {
+            *self.appearances.entry(classify(&change)).or_insert(0) += 1;
+        }
+        self.solutions += 1;
+    }
+
+    pub fn record_result<S, K, X>(&mut self, result: &TracedResult<S, K, X>, classify: impl Fn(&X) -> C)
+    where
+        S: TracedState<K, X>,
+        K: Clone + Eq + Hash,
+        X: Clone
+    {
+        self.record_path(&result.path, classify);
+    }
+
+    pub fn count(&self, class: &C) -> usize {
+        self.appearances.get(class).copied().unwrap_or(0)
+    }
+
+    pub fn solutions(&self) -> usize {
+        self.solutions
+    }
+
+    pub fn classes(&self) -> usize {
+        self.appearances.len()
+    }
+}
+
+impl<C> Default for OperatorStats<C>
+where
+    C: Eq + Hash
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tie-break hook for `SearchOptions::with_tie_break`. A change gets the dense rank of its class by
+// appearances: 0 for the most frequent classes, 1 for the next ones and so on, with classes that
+// never appeared last. The statistics are copied, so later recordings don't affect the hook.
+pub fn successor_priority<X, C, F>(stats: &OperatorStats<C>, classify: F) -> impl Fn(&X) -> usize + use<X, C, F>
+where
+    C: Clone + Eq + Hash,
+    F: Fn(&X) -> C
+{
+    let mut counts: Vec<usize> = stats.appearances.values().copied().collect();
+    counts.push(0);
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts.dedup();
+
+    let ranks: HashMap<C, usize> = stats.appearances.iter()
+        .map(|(class, count)| (class.clone(), counts.iter().position(|other| other == count).unwrap()))
+        .collect();
+    let unseen_rank = counts.len() - 1;
+
+    move |change| ranks.get(&classify(change)).copied().unwrap_or(unseen_rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, rng::SplitMix64, search_options::SearchOptions, traced::traced_astar::{traced_astar, traced_astar_with_options}};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Step {
+        Advance,
+        Detour(u8)
+    }
+
+    fn classify(step: &Step) -> u8 {
+        match step {
+            Step::Advance => 0,
+            Step::Detour(_) => 1
+        }
+    }
+
+    // A corridor of `length` cells over a flat heuristic. Every cell also offers two detours into
+    // dead-end branches, generated in an order that depends on the instance seed, so a greedy
+    // search only walks straight to the goal if it knows to prefer `Advance`.
+    #[derive(Clone)]
+    struct Corridor {
+        position: usize,
+        branch: u8,
+        depth: usize,
+        length: usize,
+        seed: u64,
+        g: usize
+    }
+
+    impl Corridor {
+        fn new(length: usize, seed: u64) -> Self {
+            Corridor {
+                position: 0,
+                branch: 0,
+                depth: 0,
+                length,
+                seed,
+                g: 0
+            }
+        }
+
+        fn with(&self, position: usize, branch: u8, depth: usize) -> Self {
+            Corridor {
+                position,
+                branch,
+                depth,
+                g: self.g + 1,
+                ..self.clone()
+            }
+        }
+    }
+
+    impl AStarState<(usize, u8, usize)> for Corridor {
+        fn key(&self) -> (usize, u8, usize) {
+            (self.position, self.branch, self.depth)
+        }
+
+        fn h(&self) -> usize {
+            if self.is_goal() { 0 } else { 1 }
+        }
+
+        fn f(&self) -> usize {
+            self.g() + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.branch == 0 && self.position == self.length
+        }
+    }
+
+    impl TracedState<(usize, u8, usize), Step> for Corridor {
+        fn generate_traced_successors(&self) -> Vec<(Self, Step)> {
+            if self.branch != 0 {
+                if self.depth < 3 {
+                    return vec![(self.with(self.position, self.branch, self.depth + 1), Step::Detour(self.branch))];
+                }
+                return Vec::new();
+            }
+            if self.position == self.length {
+                return Vec::new();
+            }
+
+            let mut successors = vec![
+                (self.with(self.position + 1, 0, 0), Step::Advance),
+                (self.with(self.position, 1, 1), Step::Detour(1)),
+                (self.with(self.position, 2, 1), Step::Detour(2))
+            ];
+            let mut rng = SplitMix64::new(self.seed ^ self.position as u64);
+            for index in (1..successors.len()).rev() {
+                successors.swap(index, rng.next_below(index + 1));
+            }
+            successors
+        }
+    }
+
+    fn trained(instances: u64) -> OperatorStats<u8> {
+        let mut stats = OperatorStats::new();
+        for seed in 0..instances {
+            let result = traced_astar(Corridor::new(10 + seed as usize % 7, seed)).unwrap();
+            stats.record_result(&result, classify);
+        }
+        stats
+    }
+
+    #[test]
+    fn test_counts_solution_changes() {
+        let mut stats = OperatorStats::new();
+        stats.record_path(vec![Step::Advance, Step::Detour(1), Step::Advance], classify);
+        stats.record_path(vec![Step::Detour(2)], classify);
+
+        assert_eq!(stats.solutions(), 2);
+        assert_eq!(stats.classes(), 2);
+        assert_eq!(stats.count(&0), 2);
+        assert_eq!(stats.count(&1), 2);
+        assert_eq!(stats.count(&7), 0);
+    }
+
+    #[test]
+    fn test_priority_ranks_by_frequency() {
+        let mut stats = OperatorStats::new();
+        stats.record_path(vec!["push", "push", "pull", "rotate", "rotate", "rotate"], |step| *step);
+        stats.record_path(vec!["pull", "push"], |step| *step);
+
+        let priority = successor_priority(&stats, |step: &&str| *step);
+        assert_eq!(priority(&"push"), 0);
+        assert_eq!(priority(&"rotate"), 0);
+        assert_eq!(priority(&"pull"), 1);
+        assert_eq!(priority(&"jump"), 2);
+    }
+
+    #[test]
+    fn test_learned_ordering_saves_greedy_expansions() {
+        let stats = trained(20);
+        assert_eq!(stats.count(&1), 0);
+
+        let mut neutral_expansions = 0;
+        let mut learned_expansions = 0;
+
+        for seed in 100..110 {
+            let start = Corridor::new(25, seed);
+
+            let neutral = traced_astar_with_options(start.clone(), SearchOptions::new().with_greedy(true));
+            let learned = traced_astar_with_options(
+                start,
+                SearchOptions::new()
+                    .with_greedy(true)
+                    .with_tie_break(successor_priority(&stats, classify))
+            );
+
+            assert!(neutral.result.is_some());
+            assert_eq!(learned.result.unwrap().path.len(), 25);
+            assert_eq!(learned.stats.expansions, 25);
+
+            neutral_expansions += neutral.stats.expansions;
+            learned_expansions += learned.stats.expansions;
+        }
+
+        assert!(learned_expansions < neutral_expansions);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let stats = trained(5);
+        let json = serde_json::to_string(&stats).unwrap();
+        let restored: OperatorStats<u8> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored, stats);
+    }
+}
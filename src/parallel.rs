@@ -0,0 +1,331 @@
+use std::{any::Any, collections::{HashMap, VecDeque}, hash::Hash, panic::{self, AssertUnwindSafe}, sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver, SyncSender}, Condvar, Mutex}, thread};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::build_result}};
+
+// Expansions each worker may have queued or running at a time.
+const EXPANSIONS_PER_WORKER: usize = 2;
+
+type Task<S, K, C> = TracedStateWrapper<S, K, C>;
+type Evaluated<S, K, C> = (K, usize, TracedStateWrapper<S, K, C>);
+// What a worker panicked with, to be resumed on the calling thread.
+type Panic = Box<dyn Any + Send>;
+
+enum Message<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // An expanded state with the key and f of each of its successors.
+    Expanded(TracedStateWrapper<S, K, C>, Vec<Evaluated<S, K, C>>),
+    Panicked(Panic)
+}
+
+// Traced A* where a fixed pool of worker threads expands states: it generates the successors and
+// computes key() and f() of each. Several expansions are in flight at once, taken from the top of
+// the open list, and their successors are merged as they come back. Workers pull tasks from their
+// own deque and steal from the others when it runs dry. A goal is only returned once it is the
+// best open state with nothing in flight, and states reached again more cheaply are reopened, so
+// the cost is the one `traced_astar` finds. Among paths of that cost the one returned, and the
+// number of expansions, depend on the order results arrive in. Worth it when successors or h
+// are expensive. A panic in a worker stops the search and is resumed on the calling thread.
+pub fn parallel_traced_astar<S, K, C>(initial_state: S, workers: usize) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C> + Send,
+    K: Clone + Eq + Hash + Send,
+    C: Send
+{
+    let workers = workers.max(1);
+    let pool = WorkerPool::new(workers);
+    let (sender, receiver) = mpsc::sync_channel(workers * EXPANSIONS_PER_WORKER);
+
+    thread::scope(|scope| {
+        for worker in 0..workers {
+            let pool = &pool;
+            let sender = sender.clone();
+            scope.spawn(move || work(pool, worker, sender));
+        }
+        drop(sender);
+
+        // Stops the workers however the search ends, a panic of its own included, or the scope
+        // would wait on them forever.
+        let _shut_down = ShutDown(&pool);
+        match search(initial_state, &pool, &receiver, workers * EXPANSIONS_PER_WORKER) {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload)
+        }
+    })
+}
+
+fn search<S, K, C>(initial_state: S, pool: &WorkerPool<Task<S, K, C>>, receiver: &Receiver<Message<S, K, C>>, window: usize) -> Result<Option<TracedResult<S, K, C>>, Panic>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut open_list: OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>> = OpenList::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    // Best g each key was sent out for expansion with, in flight or back.
+    let mut expanded_g: HashMap<K, usize> = HashMap::new();
+    let mut in_flight = 0;
+    let mut next_task = 0;
+
+    let initial_f = initial_state.f();
+    open_list.insert(initial_state.key(), Prioritized::new(TracedStateWrapper::new(initial_state), initial_f));
+
+    loop {
+        while in_flight < window {
+            let Some(current_state) = open_list.extract_min()
+            else {
+                break;
+            };
+            let current_state = current_state.state;
+
+            if current_state.is_goal() {
+                if in_flight == 0 {
+                    return Ok(Some(build_result(current_state, closed_list)));
+                }
+
+                // A state still in flight may lead to a cheaper goal.
+                let current_f = current_state.f();
+                open_list.insert(current_state.key(), Prioritized::new(current_state, current_f));
+                break;
+            }
+
+            expanded_g.insert(current_state.key(), current_state.g());
+            pool.push(next_task, current_state);
+            next_task += 1;
+            in_flight += 1;
+        }
+
+        if in_flight == 0 {
+            return Ok(None);
+        }
+
+        let (parent, successors) = match receiver.recv().expect("every worker stopped during the search") {
+            Message::Expanded(parent, successors) => (parent, successors),
+            Message::Panicked(payload) => return Err(payload)
+        };
+        in_flight -= 1;
+
+        let parent_key = parent.key();
+        if closed_list.get(&parent_key).is_none_or(|closed| parent.g() < closed.g()) {
+            closed_list.insert(parent_key, parent);
+        }
+
+        for (successor_key, successor_f, successor) in successors {
+            if expanded_g.get(&successor_key).is_some_and(|&g| g <= successor.g()) {
+                continue;
+            }
+
+            open_list.insert(successor_key, Prioritized::new(successor, successor_f));
+        }
+    }
+}
+
+struct WorkerPool<T> {
+    queues: Vec<Mutex<VecDeque<T>>>,
+    sleeping: Mutex<()>,
+    wake_up: Condvar,
+    stopped: AtomicBool
+}
+
+impl<T> WorkerPool<T> {
+    fn new(workers: usize) -> Self {
+        WorkerPool {
+            queues: (0..workers).map(|_| Mutex::new(VecDeque::new())).collect(),
+            sleeping: Mutex::new(()),
+            wake_up: Condvar::new(),
+            stopped: AtomicBool::new(false)
+        }
+    }
+
+    fn push(&self, index: usize, task: T) {
+        let queue = index % self.queues.len();
+        self.queues[queue].lock().unwrap().push_back(task);
+
+        let _guard = self.sleeping.lock().unwrap();
+        self.wake_up.notify_one();
+    }
+
+    // Own deque first (front), then steal from the back of the others.
+    fn pop(&self, worker: usize) -> Option<T> {
+        if let Some(task) = self.queues[worker].lock().unwrap().pop_front() {
+            return Some(task);
+        }
+
+        let workers = self.queues.len();
+        (1..workers)
+            .map(|offset| (worker + offset) % workers)
+            .find_map(|victim| self.queues[victim].lock().unwrap().pop_back())
+    }
+
+    fn has_tasks(&self) -> bool {
+        self.queues.iter().any(|queue| !queue.lock().unwrap().is_empty())
+    }
+
+    fn shut_down(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        let _guard = self.sleeping.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.wake_up.notify_all();
+    }
+}
+
+struct ShutDown<'a, T>(&'a WorkerPool<T>);
+
+impl<T> Drop for ShutDown<'_, T> {
+    fn drop(&mut self) {
+        self.0.shut_down();
+    }
+}
+
+fn work<S, K, C>(pool: &WorkerPool<Task<S, K, C>>, worker: usize, sender: SyncSender<Message<S, K, C>>)
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    loop {
+        if let Some(state) = pool.pop(worker) {
+            let expanded = panic::catch_unwind(AssertUnwindSafe(|| {
+                let successors = state.generate_states()
+                    .into_iter()
+                    .map(|successor| (successor.key(), successor.f(), successor))
+                    .collect();
+                (state, successors)
+            }));
+
+            let (message, panicked) = match expanded {
+                Ok((state, successors)) => (Message::Expanded(state, successors), false),
+                Err(payload) => (Message::Panicked(payload), true)
+            };
+            if sender.send(message).is_err() || panicked {
+                return;
+            }
+            continue;
+        }
+
+        let guard = pool.sleeping.lock().unwrap();
+        if pool.stopped.load(Ordering::SeqCst) {
+            return;
+        }
+        if pool.has_tasks() {
+            continue;
+        }
+        drop(pool.wake_up.wait(guard).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    // Wraps the test graph with an artificially slow heuristic, which panics on `panics_at`.
+    #[derive(Clone)]
+    struct SlowState {
+        inner: GraphState,
+        delay: Duration,
+        panics_at: Option<usize>
+    }
+
+    impl AStarState<usize> for SlowState {
+        fn key(&self) -> usize {
+            self.inner.key()
+        }
+
+        fn h(&self) -> usize {
+            thread::sleep(self.delay);
+            if self.panics_at == Some(self.inner.node) {
+                panic!("no h for node {}", self.inner.node);
+            }
+            self.inner.h()
+        }
+
+        fn f(&self) -> usize {
+            self.g() + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.inner.g()
+        }
+
+        fn is_goal(&self) -> bool {
+            self.inner.is_goal()
+        }
+    }
+
+    impl TracedState<usize, usize> for SlowState {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            self.inner.generate_traced_successors()
+                .into_iter()
+                .map(|(inner, change)| (SlowState { inner, ..*self }, change))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_same_cost_as_serial() {
+        for goal in 0..EIGHT_NODES.len() {
+            let h_values = if goal == 7 { EIGHT_NODES_H } else { ZERO_H };
+            let start = GraphState::new(EIGHT_NODES, h_values, 0, goal);
+
+            for workers in [1, 2, 4] {
+                let parallel = parallel_traced_astar(start.clone(), workers).unwrap();
+                let serial = traced_astar(start.clone()).unwrap();
+
+                assert_eq!(parallel.final_state.g(), serial.final_state.g());
+                assert_eq!(replay(&start, &parallel.path).unwrap().g(), serial.final_state.g());
+            }
+        }
+    }
+
+    #[test]
+    fn test_sliding_puzzles_stay_optimal() {
+        // Plenty of ties and several expansions in flight, so states do get reached again more
+        // cheaply than when they were sent out.
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let serial = traced_astar(start.clone()).unwrap();
+
+            for workers in [1, 3, 8] {
+                let parallel = parallel_traced_astar(start.clone(), workers).unwrap();
+                assert_eq!(parallel.final_state.g(), serial.final_state.g(), "seed {} on {} workers", seed, workers);
+                assert_eq!(replay(&start, &parallel.path).unwrap().g(), serial.final_state.g());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no h for node 4")]
+    fn test_worker_panic_reaches_the_caller() {
+        let start = SlowState {
+            inner: GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7),
+            delay: Duration::ZERO,
+            panics_at: Some(4)
+        };
+
+        parallel_traced_astar(start, 3);
+    }
+
+    #[test]
+    fn test_slow_heuristic() {
+        let start = SlowState {
+            inner: GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7),
+            delay: Duration::from_millis(2),
+            panics_at: None
+        };
+
+        let parallel = parallel_traced_astar(start.clone(), 3).unwrap();
+        assert_eq!(parallel.final_state.g(), 9);
+        assert_eq!(replay(&start, &parallel.path).unwrap().g(), 9);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        let start = GraphState::new(SPLIT, &[0, 0, 0], 0, 2);
+
+        assert!(parallel_traced_astar(start, 2).is_none());
+    }
+}
@@ -0,0 +1,413 @@
+use std::{collections::HashSet, fmt::Display, fs::File, hash::Hash, io::{self, BufReader, Read, Write}, path::Path};
+
+use crate::{open_list::OpenList, prioritized::Prioritized, traced::to_goal::Heuristic, untraced::state::UntracedState};
+
+// Pattern database files: a 16 byte header (magic, format version, byte order mark and number of
+// entries) followed by the dense table, one byte per entry.
+const MAGIC: [u8; 4] = *b"APDB";
+pub const FORMAT_VERSION: u16 = 1;
+const BYTE_ORDER_MARK: u16 = 0xFEFF;
+const HEADER_LEN: usize = 16;
+
+// Entry of abstract states no goal can be reached from. Distances above it are stored as
+// UNREACHABLE - 1, still a lower bound.
+pub const UNREACHABLE: u8 = u8::MAX;
+
+// Estimate of the states whose entry is UNREACHABLE. Above any cost a search gets to, yet a g up
+// to half of usize::MAX can be added to it without overflowing.
+pub const DEAD_END: usize = usize::MAX / 2;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PdbError {
+    Io(io::Error),
+    // The file doesn't start with the magic bytes, so it was never written by `write`.
+    NotAPatternDatabase,
+    // The byte order mark is neither the little nor the big endian one.
+    UnknownByteOrder { mark: [u8; 2] },
+    UnsupportedVersion { found: u16, supported: u16 },
+    // The file ends before the table the header announces.
+    Truncated { entries: u64, found: u64 }
+}
+
+impl Display for PdbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdbError::Io(error) => write!(f, "pattern database i/o error: {}", error),
+            PdbError::NotAPatternDatabase => write!(f, "not a pattern database file, the magic bytes are missing"),
+            PdbError::UnknownByteOrder { mark } => {
+                write!(f, "unknown byte order mark {:02x}{:02x}, expected fffe (little endian) or feff (big endian)", mark[0], mark[1])
+            }
+            PdbError::UnsupportedVersion { found, supported } => {
+                write!(f, "pattern database format version {} is not supported, this build reads version {}", found, supported)
+            }
+            PdbError::Truncated { entries, found } => {
+                write!(f, "pattern database truncated: the header announces {} entries, the file holds {}", entries, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PdbError {}
+
+impl From<io::Error> for PdbError {
+    fn from(error: io::Error) -> Self {
+        PdbError::Io(error)
+    }
+}
+
+// Number of entries the header announces. Writers use little endian; a header in big endian, say
+// from a port of the writer, is read as well, since the table itself has no byte order.
+fn parse_header(header: &[u8; HEADER_LEN]) -> Result<u64, PdbError> {
+    if header[..4] != MAGIC {
+        return Err(PdbError::NotAPatternDatabase);
+    }
+
+    let mark = [header[6], header[7]];
+    let big_endian = if mark == BYTE_ORDER_MARK.to_le_bytes() {
+        false
+    }
+    else if mark == BYTE_ORDER_MARK.to_be_bytes() {
+        true
+    }
+    else {
+        return Err(PdbError::UnknownByteOrder { mark });
+    };
+
+    let version = [header[4], header[5]];
+    let version = if big_endian { u16::from_be_bytes(version) } else { u16::from_le_bytes(version) };
+    if version != FORMAT_VERSION {
+        return Err(PdbError::UnsupportedVersion { found: version, supported: FORMAT_VERSION });
+    }
+
+    let entries: [u8; 8] = header[8..].try_into().expect("the header holds 8 bytes of entries");
+    Ok(if big_endian { u64::from_be_bytes(entries) } else { u64::from_le_bytes(entries) })
+}
+
+enum Table {
+    Owned(Vec<u8>),
+    // The whole file, header included.
+    #[cfg(feature = "mmap")]
+    Mapped { map: memmap2::Mmap, entries: usize }
+}
+
+impl Table {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Table::Owned(table) => table,
+            #[cfg(feature = "mmap")]
+            Table::Mapped { map, entries } => &map[HEADER_LEN..HEADER_LEN + entries]
+        }
+    }
+}
+
+// Heuristic from a table of exact abstract distances to a goal: `index` turns a concrete state
+// into the index of its abstract state, the entry there is the estimate. Admissible as long as
+// the abstraction never makes a path more expensive, e.g. a sliding tile puzzle seen through a
+// few of its tiles.
+pub struct PatternDatabase<F> {
+    table: Table,
+    index: F
+}
+
+impl<F> PatternDatabase<F> {
+    pub fn new(table: Vec<u8>, index: F) -> Self {
+        PatternDatabase { table: Table::Owned(table), index }
+    }
+
+    // Fills a table of `entries` entries by a backward Dijkstra from the abstract `goals`, with
+    // `abstract_index` giving each abstract state its entry. Successors are taken as predecessors,
+    // so moves have to be reversible at the same cost.
+    pub fn build<A, AK>(goals: impl IntoIterator<Item = A>, entries: usize, abstract_index: impl Fn(&A) -> usize, index: F) -> Self
+    where
+        A: UntracedState<AK>,
+        AK: Clone + Eq + Hash
+    {
+        let mut table = vec![UNREACHABLE; entries];
+        let mut open_list = OpenList::new();
+        let mut closed_list = HashSet::new();
+
+        for goal in goals {
+            open_list.insert(goal.key(), Prioritized::by_g(goal));
+        }
+
+        while let Some(Prioritized { state: current, .. }) = open_list.extract_min() {
+            table[abstract_index(&current)] = current.g().min(usize::from(UNREACHABLE - 1)) as u8;
+            closed_list.insert(current.key());
+
+            for successor in current.generate_successors() {
+                let successor_key = successor.key();
+                if !closed_list.contains(&successor_key) {
+                    open_list.insert(successor_key, Prioritized::by_g(successor));
+                }
+            }
+        }
+
+        PatternDatabase::new(table, index)
+    }
+
+    pub fn entries(&self) -> &[u8] {
+        self.table.as_slice()
+    }
+
+    // Writes the table in the file format `open` and `open_mmap` read.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let entries = self.entries();
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&BYTE_ORDER_MARK.to_le_bytes())?;
+        writer.write_all(&(entries.len() as u64).to_le_bytes())?;
+        writer.write_all(entries)?;
+        writer.flush()
+    }
+
+    // Reads a table written by `write` into memory.
+    pub fn open(path: impl AsRef<Path>, index: F) -> Result<Self, PdbError> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0; HEADER_LEN];
+        reader.read_exact(&mut header).map_err(|_| PdbError::NotAPatternDatabase)?;
+        let entries = checked_entries(parse_header(&header)?, file_len)?;
+
+        let mut table = vec![0; entries];
+        reader.read_exact(&mut table)?;
+        Ok(PatternDatabase::new(table, index))
+    }
+
+    // Maps a table written by `write` instead of reading it: processes opening the same file share
+    // its pages, and only the entries looked up are ever loaded. The file must not change while
+    // mapped. Without the `mmap` feature this is `open`.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap(path: impl AsRef<Path>, index: F) -> Result<Self, PdbError> {
+        // SAFETY: the mapping is only read, and the caller keeps the file from changing while it
+        // is mapped, as documented above.
+        let map = unsafe { memmap2::Mmap::map(&File::open(path)?)? };
+        let file = &map[..];
+
+        let header: &[u8; HEADER_LEN] = file.get(..HEADER_LEN)
+            .and_then(|header| header.try_into().ok())
+            .ok_or(PdbError::NotAPatternDatabase)?;
+        let entries = checked_entries(parse_header(header)?, file.len() as u64)?;
+
+        Ok(PatternDatabase { table: Table::Mapped { map, entries }, index })
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    pub fn open_mmap(path: impl AsRef<Path>, index: F) -> Result<Self, PdbError> {
+        Self::open(path, index)
+    }
+}
+
+// The announced entries, if a file of `file_len` bytes holds them.
+fn checked_entries(entries: u64, file_len: u64) -> Result<usize, PdbError> {
+    let found = file_len.saturating_sub(HEADER_LEN as u64);
+    if found < entries {
+        return Err(PdbError::Truncated { entries, found });
+    }
+    usize::try_from(entries).map_err(|_| PdbError::Truncated { entries, found: usize::MAX as u64 })
+}
+
+// An index past the end of the table, e.g. from an index function meant for a bigger table,
+// estimates 0, which is admissible, rather than panicking in the middle of a search.
+impl<S, F> Heuristic<S> for PatternDatabase<F>
+where
+    F: Fn(&S) -> usize
+{
+    fn estimate(&self, state: &S) -> usize {
+        match self.entries().get((self.index)(state)) {
+            Some(&UNREACHABLE) => DEAD_END,
+            Some(&distance) => usize::from(distance),
+            None => 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle}, traced::traced_astar::traced_astar};
+
+    // Cells of the blank and of tiles 1, 2 and 3, every other tile looking the same.
+    const PATTERN: [u8; 4] = [0, 1, 2, 3];
+    const ENTRIES: usize = 9 * 9 * 9 * 9;
+
+    #[derive(Clone)]
+    struct Pattern {
+        cells: [u8; 4],
+        g: usize
+    }
+
+    fn pattern_index(cells: &[u8; 4]) -> usize {
+        cells.iter().fold(0, |index, &cell| index * 9 + usize::from(cell))
+    }
+
+    fn project(puzzle: &SlidingPuzzle) -> usize {
+        let cells = PATTERN.map(|tile| puzzle.tiles.iter().position(|&other| other == tile).unwrap() as u8);
+        pattern_index(&cells)
+    }
+
+    impl AStarState<[u8; 4]> for Pattern {
+        fn key(&self) -> [u8; 4] {
+            self.cells
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.g
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            false
+        }
+    }
+
+    // Every move of the blank costs 1, whether it moves a pattern tile or not.
+    impl UntracedState<[u8; 4]> for Pattern {
+        fn generate_successors(&self) -> Vec<Self> {
+            let blank = usize::from(self.cells[0]);
+            let (x, y) = (blank % 3, blank / 3);
+
+            [(y > 0, blank.wrapping_sub(3)), (y < 2, blank + 3), (x > 0, blank.wrapping_sub(1)), (x < 2, blank + 1)]
+                .into_iter()
+                .filter(|&(possible, _)| possible)
+                .map(|(_, target)| {
+                    let mut cells = self.cells;
+                    if let Some(tile) = cells.iter().position(|&cell| usize::from(cell) == target) {
+                        cells[tile] = blank as u8;
+                    }
+                    cells[0] = target as u8;
+                    Pattern { cells, g: self.g + 1 }
+                })
+                .collect()
+        }
+    }
+
+    fn database() -> PatternDatabase<fn(&SlidingPuzzle) -> usize> {
+        let goal = Pattern { cells: PATTERN.map(|tile| SlidingPuzzle::SOLVED.iter().position(|&other| other == tile).unwrap() as u8), g: 0 };
+        PatternDatabase::build([goal], ENTRIES, |pattern: &Pattern| pattern_index(&pattern.cells), project)
+    }
+
+    // A file of its own per test, removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("astar-helper-{}-{}.pdb", std::process::id(), name));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn written<F>(database: &PatternDatabase<F>) -> Vec<u8> {
+        let mut file = Vec::new();
+        database.write(&mut file).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_estimates_are_admissible() {
+        let database = database();
+        assert_eq!(database.estimate(&SlidingPuzzle::scrambled(0, 0)), 0);
+
+        for seed in 0..5 {
+            let puzzle = SlidingPuzzle::scrambled(seed, 30);
+            let optimal = traced_astar(puzzle.clone()).unwrap().final_state.g();
+            assert!(database.estimate(&puzzle) <= optimal);
+        }
+    }
+
+    #[test]
+    fn test_reopened_database_matches_the_table() {
+        let database = database();
+        let file = TempFile::new("reopen", &written(&database));
+
+        let read = PatternDatabase::open(&file.0, project as fn(&SlidingPuzzle) -> usize).unwrap();
+        let mapped = PatternDatabase::open_mmap(&file.0, project as fn(&SlidingPuzzle) -> usize).unwrap();
+        assert_eq!(read.entries(), database.entries());
+        assert_eq!(mapped.entries(), database.entries());
+
+        for seed in 0..20 {
+            let puzzle = SlidingPuzzle::scrambled(seed, 25);
+            assert_eq!(mapped.estimate(&puzzle), database.estimate(&puzzle));
+        }
+    }
+
+    fn identity(index: &usize) -> usize {
+        *index
+    }
+
+    #[test]
+    fn test_dead_ends_can_be_added_to() {
+        // Node 2 is a dead end on the way from 0 to 3. The test graph's f is a plain g + h.
+        const GRAPH: &[&[(usize, usize)]] = &[&[(2, 1), (1, 1)], &[(3, 1)], &[], &[]];
+        let database = PatternDatabase::new(vec![2, 1, UNREACHABLE, 0], identity);
+        let h_values: &'static [usize] = Vec::leak((0..4).map(|node| database.estimate(&node)).collect());
+        assert_eq!(h_values, [2, 1, DEAD_END, 0]);
+
+        let result = traced_astar(GraphState::new(GRAPH, h_values, 0, 3)).unwrap();
+        assert_eq!(result.path.to_vec(), vec![1, 3]);
+        assert!(DEAD_END.checked_add(usize::MAX / 2).is_some());
+    }
+
+    #[test]
+    fn test_index_out_of_range_estimates_zero() {
+        let database = PatternDatabase::new(vec![3, 1], identity);
+        assert_eq!([1, 2, usize::MAX].map(|index| database.estimate(&index)), [1, 0, 0]);
+    }
+
+    #[test]
+    fn test_big_endian_header_is_read() {
+        let database = PatternDatabase::new(vec![3, 1, UNREACHABLE], identity);
+        let mut file = written(&database);
+        file[4..6].copy_from_slice(&FORMAT_VERSION.to_be_bytes());
+        file[6..8].copy_from_slice(&BYTE_ORDER_MARK.to_be_bytes());
+        file[8..16].copy_from_slice(&3u64.to_be_bytes());
+        let file = TempFile::new("big-endian", &file);
+
+        let mapped = PatternDatabase::open_mmap(&file.0, identity).unwrap();
+        assert_eq!([0, 1, 2].map(|index| mapped.estimate(&index)), [3, 1, DEAD_END]);
+    }
+
+    #[test]
+    fn test_malformed_files_are_rejected() {
+        let database = PatternDatabase::new(vec![0; 8], identity);
+        let valid = written(&database);
+        let open = |name: &str, contents: &[u8]| {
+            let file = TempFile::new(name, contents);
+            [PatternDatabase::open(&file.0, identity), PatternDatabase::open_mmap(&file.0, identity)]
+                .map(|opened| opened.err().unwrap().to_string())
+        };
+
+        let mut wrong_magic = valid.clone();
+        wrong_magic[0] = b'X';
+        assert!(open("magic", &wrong_magic).iter().all(|error| error.starts_with("not a pattern database")));
+
+        let mut wrong_version = valid.clone();
+        wrong_version[4] = 7;
+        assert!(open("version", &wrong_version).iter().all(|error| error == "pattern database format version 7 is not supported, this build reads version 1"));
+
+        let mut wrong_mark = valid.clone();
+        wrong_mark[6] = 0;
+        assert!(open("mark", &wrong_mark).iter().all(|error| error.starts_with("unknown byte order mark 00fe")));
+
+        let truncated = &valid[..valid.len() - 3];
+        assert!(open("truncated", truncated).iter().all(|error| error == "pattern database truncated: the header announces 8 entries, the file holds 5"));
+    }
+}
@@ -0,0 +1,268 @@
+use std::{collections::VecDeque, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, rng::SplitMix64, search_stats::SearchStats};
+
+// Seed of the random restarts, fixed so that a search is reproducible.
+const RESTART_SEED: u64 = 0x514A_7EA0_5EED_0F17;
+
+// What a greedy search does once it has spent the threshold of expansions without improving h.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PlateauPolicy {
+    // Only report the plateau, the search goes on as before.
+    Continue,
+    // Expands the given number of open states in the order they were inserted, oldest first,
+    // whatever their h. Ends early when one of them improves h.
+    BreadthFirstBurst(usize),
+    // Expands one open state picked at random, then goes on greedily from the open list.
+    RandomRestart
+}
+
+// Reported once per plateau, when it reaches the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlateauDetected {
+    // Best h found so far, the level of the plateau.
+    pub h: usize,
+    pub expansions_on_plateau: usize
+}
+
+pub(crate) struct PlateauTracker<K> {
+    threshold: usize,
+    policy: PlateauPolicy,
+    best_h: usize,
+    // Expansions since h last improved.
+    run: usize,
+    burst_left: usize,
+    restart_pending: bool,
+    // Keys in the order they were inserted into the open list, only kept for bursts. Keys that
+    // left the open list are dropped when popped.
+    insertions: VecDeque<K>,
+    rng: SplitMix64
+}
+
+impl<K> PlateauTracker<K>
+where
+    K: Clone + Eq + Hash
+{
+    pub(crate) fn new(threshold: usize, policy: PlateauPolicy) -> Self {
+        PlateauTracker {
+            threshold: threshold.max(1),
+            policy,
+            best_h: usize::MAX,
+            run: 0,
+            burst_left: 0,
+            restart_pending: false,
+            insertions: VecDeque::new(),
+            rng: SplitMix64::new(RESTART_SEED)
+        }
+    }
+
+    pub(crate) fn tracks_insertions(&self) -> bool {
+        matches!(self.policy, PlateauPolicy::BreadthFirstBurst(_))
+    }
+
+    pub(crate) fn record_insert(&mut self, key: &K) {
+        if self.tracks_insertions() {
+            self.insertions.push_back(key.clone());
+        }
+    }
+
+    // Called for every expanded state. Returns the plateau when this expansion brings it to the
+    // threshold.
+    pub(crate) fn expanded(&mut self, h: usize, stats: &mut SearchStats<K>) -> Option<PlateauDetected> {
+        if h < self.best_h {
+            self.best_h = h;
+            self.run = 0;
+            self.burst_left = 0;
+            return None;
+        }
+
+        self.run += 1;
+        stats.largest_plateau = stats.largest_plateau.max(self.run);
+        if self.run != self.threshold {
+            return None;
+        }
+
+        stats.plateaus += 1;
+        let detected = PlateauDetected { h: self.best_h, expansions_on_plateau: self.run };
+
+        // Escaping starts a new count, so a plateau the escape didn't leave is reported again.
+        match self.policy {
+            PlateauPolicy::Continue => {}
+            PlateauPolicy::BreadthFirstBurst(expansions) => {
+                self.burst_left = expansions;
+                self.run = 0;
+            }
+            PlateauPolicy::RandomRestart => {
+                self.restart_pending = true;
+                self.run = 0;
+            }
+        }
+
+        Some(detected)
+    }
+
+    // Key of the open state the policy expands next, or None to take the minimum as usual.
+    pub(crate) fn next_key<V>(&mut self, open_list: &OpenList<K, V>) -> Option<K>
+    where
+        V: AStarState<K>
+    {
+        if self.restart_pending {
+            self.restart_pending = false;
+            if open_list.is_empty() {
+                return None;
+            }
+            return open_list.key_at(self.rng.next_below(open_list.len())).cloned();
+        }
+
+        while self.burst_left > 0 {
+            let key = self.insertions.pop_front()?;
+            if open_list.contains_key(&key) {
+                self.burst_left -= 1;
+                return Some(key);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{search_options::SearchOptions, search_stats::SearchStats, traced::{outcome::SearchOutcome, state::TracedState, traced_astar::traced_astar_with_options}};
+
+    const CORRIDOR: usize = 5000;
+    const BUDGET: usize = 500;
+
+    // From the start, a corridor of CORRIDOR states at h = 5 leading nowhere, and a three step
+    // detour whose first state looks worse (h = 6) but leads to the goal.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Node {
+        Start,
+        Corridor(usize),
+        Detour(usize)
+    }
+
+    #[derive(Clone)]
+    struct Trap {
+        node: Node,
+        g: usize
+    }
+
+    impl AStarState<Node> for Trap {
+        fn key(&self) -> Node {
+            self.node
+        }
+
+        fn h(&self) -> usize {
+            match self.node {
+                Node::Start => 7,
+                Node::Corridor(_) => 5,
+                Node::Detour(step) => 6 - 2 * step
+            }
+        }
+
+        fn f(&self) -> usize {
+            self.g + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.node == Node::Detour(3)
+        }
+    }
+
+    impl TracedState<Node, Node> for Trap {
+        fn generate_traced_successors(&self) -> Vec<(Self, Node)> {
+            let next = match self.node {
+                Node::Start => vec![Node::Corridor(0), Node::Detour(0)],
+                Node::Corridor(index) if index + 1 < CORRIDOR => vec![Node::Corridor(index + 1)],
+                Node::Corridor(_) => vec![],
+                Node::Detour(step) => vec![Node::Detour(step + 1)]
+            };
+
+            next.into_iter()
+                .map(|node| (Trap { node, g: self.g + 1 }, node))
+                .collect()
+        }
+    }
+
+    fn search(options: SearchOptions<Trap, Node, Node>) -> SearchOutcome<Trap, Node, Node> {
+        traced_astar_with_options(Trap { node: Node::Start, g: 0 }, options.with_greedy(true))
+    }
+
+    #[test]
+    fn test_greedy_is_stuck_in_the_corridor() {
+        let outcome = search(SearchOptions::new());
+        assert!(outcome.result.is_some());
+        assert!(outcome.stats.expansions > BUDGET);
+
+        // Without detection nothing is tracked.
+        assert_eq!((outcome.stats.plateaus, outcome.stats.largest_plateau), (0, 0));
+    }
+
+    #[test]
+    fn test_detection_reports_the_plateau() {
+        let events = RefCell::new(Vec::new());
+        let options = SearchOptions::new()
+            .with_plateau_detection(100, PlateauPolicy::Continue)
+            .with_plateau_observer(|event| events.borrow_mut().push(*event));
+        let outcome = search(options);
+
+        assert_eq!(events.into_inner(), vec![PlateauDetected { h: 5, expansions_on_plateau: 100 }]);
+        assert_eq!(outcome.stats.plateaus, 1);
+        // The corridor, then the start of the detour, which is still worse than 5.
+        assert_eq!(outcome.stats.largest_plateau, CORRIDOR);
+    }
+
+    #[test]
+    fn test_breadth_first_burst_escapes() {
+        let options = SearchOptions::new().with_plateau_detection(50, PlateauPolicy::BreadthFirstBurst(4));
+        let outcome = search(options);
+
+        let result = outcome.result.unwrap();
+        assert_eq!(result.path, vec![Node::Detour(0), Node::Detour(1), Node::Detour(2), Node::Detour(3)]);
+        assert!(outcome.stats.expansions < BUDGET, "{}", outcome.stats.expansions);
+        assert_eq!(outcome.stats.plateaus, 1);
+        assert_eq!(outcome.stats.largest_plateau, 50);
+    }
+
+    #[test]
+    fn test_random_restart_escapes() {
+        let options = SearchOptions::new().with_plateau_detection(20, PlateauPolicy::RandomRestart);
+        let outcome = search(options);
+
+        assert!(outcome.result.is_some());
+        assert!(outcome.stats.expansions < BUDGET, "{}", outcome.stats.expansions);
+        assert!(outcome.stats.plateaus >= 1);
+    }
+
+    #[test]
+    fn test_burst_skips_keys_that_left_the_open_list() {
+        let mut tracker = PlateauTracker::new(1, PlateauPolicy::BreadthFirstBurst(2));
+        let mut open_list = OpenList::new();
+        for index in 0..4 {
+            let node = Node::Corridor(index);
+            open_list.insert(node, Trap { node, g: 0 });
+            tracker.record_insert(&node);
+        }
+        open_list.remove(&Node::Corridor(0));
+        open_list.remove(&Node::Corridor(2));
+
+        let mut stats = SearchStats::default();
+        assert_eq!(tracker.next_key(&open_list), None);
+        assert_eq!(tracker.expanded(5, &mut stats), None);
+        assert!(tracker.expanded(5, &mut stats).is_some());
+
+        assert_eq!(tracker.next_key(&open_list), Some(Node::Corridor(1)));
+        assert_eq!(tracker.next_key(&open_list), Some(Node::Corridor(3)));
+        assert_eq!(tracker.next_key(&open_list), None);
+    }
+}
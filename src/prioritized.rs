@@ -0,0 +1,58 @@
+use std::hash::Hash;
+
+use crate::astar_state::AStarState;
+
+// Wraps a state so the open list orders it by a precomputed priority instead of its own f().
+// Everything else is forwarded to the wrapped state.
+pub(crate) struct Prioritized<S> {
+    pub state: S,
+    priority: usize
+}
+
+impl<S> Prioritized<S> {
+    pub fn new(state: S, priority: usize) -> Self {
+        Prioritized {
+            state,
+            priority
+        }
+    }
+
+    pub fn set_priority(&mut self, priority: usize) {
+        self.priority = priority;
+    }
+
+    pub fn by_g<K>(state: S) -> Self
+    where
+        S: AStarState<K>,
+        K: Clone + Eq + Hash
+    {
+        let priority = state.g();
+        Prioritized::new(state, priority)
+    }
+}
+
+impl<S, K> AStarState<K> for Prioritized<S>
+where
+    S: AStarState<K>,
+    K: Clone + Eq + Hash
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.state.h()
+    }
+
+    fn f(&self) -> usize {
+        self.priority
+    }
+
+    fn g(&self) -> usize {
+        self.state.g()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.state.is_goal()
+    }
+}
@@ -0,0 +1,50 @@
+use std::hash::Hash;
+
+use crate::astar_state::AStarState;
+
+// What happened to a value handed to `PriorityQueue::insert`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Insertion<V> {
+    // The key was not in the queue.
+    Added,
+    // The key was already queued with a higher f. The previous value is returned when the queue
+    // drops it right away, backends that keep it around as a stale entry return None.
+    Improved(Option<V>),
+    // The key was already queued with the same or lower f, the new value is returned.
+    Rejected(V)
+}
+
+pub trait PriorityQueue<K, V>
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>
+{
+    // Inserts the value, or replaces the stored one for the same key if the new one has a lower f.
+    fn insert(&mut self, key: K, value: V) -> Insertion<V>;
+    fn extract_min(&mut self) -> Option<V>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Lends a queue to a search, so it can be inspected once the search is done.
+impl<K, V, Q> PriorityQueue<K, V> for &mut Q
+where
+    K: Clone + Eq + Hash,
+    V: AStarState<K>,
+    Q: PriorityQueue<K, V> + ?Sized
+{
+    fn insert(&mut self, key: K, value: V) -> Insertion<V> {
+        (**self).insert(key, value)
+    }
+
+    fn extract_min(&mut self) -> Option<V> {
+        (**self).extract_min()
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
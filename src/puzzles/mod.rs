@@ -0,0 +1,2 @@
+// Ready-made states of puzzles, to solve as they are or to build domains on.
+pub mod sliding_block;
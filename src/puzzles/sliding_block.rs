@@ -0,0 +1,493 @@
+use std::{fmt::Display, hash::{Hash, Hasher}, sync::Arc};
+
+use crate::{astar_state::AStarState, grid::state::Direction, traced::{commutative::CommutativeChanges, state::TracedState, to_goal::HeuristicTo}, untraced::state::UntracedState};
+
+const BLANK: u8 = u8::MAX;
+
+// A rectangular piece, labelled by any character but '.', which marks blank cells in text
+// boards. `position` is its top left cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Piece {
+    pub label: char,
+    pub size: (usize, usize),
+    pub position: (usize, usize)
+}
+
+// The piece that has to reach a position, given as the top left cell it must end on. That is
+// the goal of the board's own `AStarState`, searches towards other positions pass them as the
+// goal parameter, see `TargetDistance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub piece: char,
+    pub position: (usize, usize)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BoardError {
+    // Rows of a text board with different lengths.
+    RaggedRows,
+    // The cells of a label in a text board don't form a filled rectangle.
+    NotARectangle(char),
+    BlankLabel,
+    DuplicateLabel(char),
+    EmptyPiece(char),
+    OutOfBounds(char),
+    // The piece covers a cell of an earlier one.
+    Overlap(char),
+    TooManyPieces,
+    NoTarget(char),
+    // Pieces of a shape are interchangeable, so the target must be the only one of its shape.
+    TargetShapeNotUnique(char)
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::RaggedRows => write!(f, "the rows of the board have different lengths"),
+            BoardError::NotARectangle(label) => write!(f, "the cells of piece '{}' don't form a filled rectangle", label),
+            BoardError::BlankLabel => write!(f, "'.' marks blank cells and can't label a piece"),
+            BoardError::DuplicateLabel(label) => write!(f, "more than one piece is labelled '{}'", label),
+            BoardError::EmptyPiece(label) => write!(f, "piece '{}' has no cells", label),
+            BoardError::OutOfBounds(label) => write!(f, "piece '{}' doesn't fit on the board", label),
+            BoardError::Overlap(label) => write!(f, "piece '{}' overlaps another piece", label),
+            BoardError::TooManyPieces => write!(f, "a board holds at most {} pieces", BLANK),
+            BoardError::NoTarget(label) => write!(f, "there is no target piece '{}'", label),
+            BoardError::TargetShapeNotUnique(label) => write!(f, "another piece has the shape of target piece '{}'", label)
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+// A move of one cell by a piece, numbered by its place in the piece list. `from` and `size` are
+// those of the piece before the move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockMove {
+    pub piece: u8,
+    pub from: (usize, usize),
+    pub size: (usize, usize),
+    pub direction: Direction
+}
+
+impl BlockMove {
+    // Cells covered by the piece before or after the move, as the corners (x0, y0) inclusive and
+    // (x1, y1) exclusive.
+    fn swept_cells(&self) -> ((usize, usize), (usize, usize)) {
+        let ((x, y), (width, height)) = (self.from, self.size);
+
+        match self.direction {
+            Direction::Up => ((x, y - 1), (x + width, y + height)),
+            Direction::Down => ((x, y), (x + width, y + height + 1)),
+            Direction::Left => ((x - 1, y), (x + width, y + height)),
+            _ => ((x, y), (x + width + 1, y + height))
+        }
+    }
+}
+
+impl Display for BlockMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            _ => "right"
+        };
+        write!(f, "piece {} {} from ({}, {})", self.piece, direction, self.from.0, self.from.1)
+    }
+}
+
+// Moves of different pieces that sweep disjoint cells can't affect each other, so they commute.
+// They are canonically ordered by piece.
+impl CommutativeChanges for BlockMove {
+    fn independent(a: &Self, b: &Self) -> bool {
+        let ((ax0, ay0), (ax1, ay1)) = a.swept_cells();
+        let ((bx0, by0), (bx1, by1)) = b.swept_cells();
+        a.piece != b.piece && (ax1 <= bx0 || bx1 <= ax0 || ay1 <= by0 || by1 <= ay0)
+    }
+
+    fn canonical_before(a: &Self, b: &Self) -> bool {
+        a.piece < b.piece
+    }
+}
+
+#[derive(Debug)]
+struct Layout {
+    width: usize,
+    height: usize,
+    labels: Vec<char>,
+    sizes: Vec<(usize, usize)>,
+    target_piece: usize,
+    target_position: (usize, usize)
+}
+
+// Rectangular pieces on a board of any size, each moving one free cell at a time at a cost of
+// 1. The goal is the target piece on its target position, and h is the Manhattan distance
+// between the two. Pieces of the same size are interchangeable: two boards are the same state
+// when they look the same, whichever pieces sit where, which cuts the states to search by
+// orders of magnitude on boards with many alike pieces.
+#[derive(Clone, Debug)]
+pub struct SlidingBlocks {
+    layout: Arc<Layout>,
+    // Piece in every cell, row by row.
+    board: Vec<u8>,
+    positions: Vec<(usize, usize)>,
+    g: usize
+}
+
+impl SlidingBlocks {
+    // Pieces are numbered in the order given.
+    pub fn new(width: usize, height: usize, pieces: &[Piece], target: Target) -> Result<Self, BoardError> {
+        if pieces.len() >= BLANK as usize {
+            return Err(BoardError::TooManyPieces);
+        }
+
+        let mut board = vec![BLANK; width * height];
+        for (index, piece) in pieces.iter().enumerate() {
+            let ((x, y), (piece_width, piece_height)) = (piece.position, piece.size);
+            if piece.label == '.' {
+                return Err(BoardError::BlankLabel);
+            }
+            if pieces[..index].iter().any(|other| other.label == piece.label) {
+                return Err(BoardError::DuplicateLabel(piece.label));
+            }
+            if piece_width == 0 || piece_height == 0 {
+                return Err(BoardError::EmptyPiece(piece.label));
+            }
+            if x + piece_width > width || y + piece_height > height {
+                return Err(BoardError::OutOfBounds(piece.label));
+            }
+
+            for cell_y in y..y + piece_height {
+                for cell_x in x..x + piece_width {
+                    let cell = &mut board[cell_y * width + cell_x];
+                    if *cell != BLANK {
+                        return Err(BoardError::Overlap(piece.label));
+                    }
+                    *cell = index as u8;
+                }
+            }
+        }
+
+        let target_piece = pieces.iter()
+            .position(|piece| piece.label == target.piece)
+            .ok_or(BoardError::NoTarget(target.piece))?;
+        let target_size = pieces[target_piece].size;
+        if pieces.iter().filter(|piece| piece.size == target_size).count() > 1 {
+            return Err(BoardError::TargetShapeNotUnique(target.piece));
+        }
+
+        Ok(SlidingBlocks {
+            layout: Arc::new(Layout {
+                width,
+                height,
+                labels: pieces.iter().map(|piece| piece.label).collect(),
+                sizes: pieces.iter().map(|piece| piece.size).collect(),
+                target_piece,
+                target_position: target.position
+            }),
+            board,
+            positions: pieces.iter().map(|piece| piece.position).collect(),
+            g: 0
+        })
+    }
+
+    // Builds a board from rows of text where '.' is a blank cell and every other character marks
+    // the cells of one piece. Pieces are numbered in the order of their labels.
+    pub fn from_ascii(text: &str, target: Target) -> Result<Self, BoardError> {
+        let rows: Vec<Vec<char>> = text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().collect())
+            .collect();
+
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(BoardError::RaggedRows);
+        }
+
+        let mut labels: Vec<char> = rows.iter().flatten().copied().filter(|&label| label != '.').collect();
+        labels.sort_unstable();
+        labels.dedup();
+
+        let mut pieces = Vec::with_capacity(labels.len());
+        for label in labels {
+            let cells: Vec<(usize, usize)> = rows.iter()
+                .enumerate()
+                .flat_map(|(y, row)| row.iter().enumerate().filter(|&(_, &cell)| cell == label).map(move |(x, _)| (x, y)))
+                .collect();
+
+            // Cells come in row order, so the first is the top left one and the last the bottom right.
+            let ((left, top), (right, bottom)) = (cells[0], cells[cells.len() - 1]);
+            let inside = cells.iter().all(|&(x, y)| (left..=right).contains(&x) && (top..=bottom).contains(&y));
+            if !inside || cells.len() != (right + 1 - left) * (bottom + 1 - top) {
+                return Err(BoardError::NotARectangle(label));
+            }
+
+            pieces.push(Piece { label, size: (right + 1 - left, bottom + 1 - top), position: (left, top) });
+        }
+
+        SlidingBlocks::new(width, height, &pieces, target)
+    }
+
+    pub fn width(&self) -> usize {
+        self.layout.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.layout.height
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn label(&self, piece: u8) -> char {
+        self.layout.labels[piece as usize]
+    }
+
+    // Top left cell of a piece.
+    pub fn position(&self, piece: u8) -> (usize, usize) {
+        self.positions[piece as usize]
+    }
+
+    pub fn target_position(&self) -> (usize, usize) {
+        self.layout.target_position
+    }
+
+    // Whether a piece can move one cell that way: the cells it would enter are on the board and
+    // blank.
+    pub fn can_move(&self, piece: u8, direction: Direction) -> bool {
+        let piece = piece as usize;
+        let (x, y) = self.positions[piece];
+        let (piece_width, piece_height) = self.layout.sizes[piece];
+
+        let mut entered: Box<dyn Iterator<Item = (usize, usize)>> = match direction {
+            Direction::Up if y > 0 => Box::new((x..x + piece_width).map(move |cell_x| (cell_x, y - 1))),
+            Direction::Down if y + piece_height < self.layout.height => {
+                Box::new((x..x + piece_width).map(move |cell_x| (cell_x, y + piece_height)))
+            }
+            Direction::Left if x > 0 => Box::new((y..y + piece_height).map(move |cell_y| (x - 1, cell_y))),
+            Direction::Right if x + piece_width < self.layout.width => {
+                Box::new((y..y + piece_height).map(move |cell_y| (x + piece_width, cell_y)))
+            }
+            _ => return false
+        };
+
+        entered.all(|(cell_x, cell_y)| self.board[cell_y * self.layout.width + cell_x] == BLANK)
+    }
+
+    // The board after a move `can_move` allows, one step further from the initial board.
+    pub fn moved(&self, piece: u8, direction: Direction) -> Self {
+        let index = piece as usize;
+        let (x, y) = self.positions[index];
+        let (piece_width, piece_height) = self.layout.sizes[index];
+        let (new_x, new_y) = direction.step(x, y).unwrap();
+        let width = self.layout.width;
+
+        let mut board = self.board.clone();
+        for cell_y in y..y + piece_height {
+            for cell_x in x..x + piece_width {
+                board[cell_y * width + cell_x] = BLANK;
+            }
+        }
+        for cell_y in new_y..new_y + piece_height {
+            for cell_x in new_x..new_x + piece_width {
+                board[cell_y * width + cell_x] = piece;
+            }
+        }
+
+        let mut positions = self.positions.clone();
+        positions[index] = (new_x, new_y);
+
+        SlidingBlocks {
+            layout: self.layout.clone(),
+            board,
+            positions,
+            g: self.g + 1
+        }
+    }
+}
+
+// One row of labels per line, '.' for blank cells.
+impl Display for SlidingBlocks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.board.chunks(self.layout.width) {
+            let line: String = row.iter()
+                .map(|&piece| if piece == BLANK { '.' } else { self.layout.labels[piece as usize] })
+                .collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// Top left cell and size of every piece, in the order their top left cells come on the board.
+// Equal for boards that look the same.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BoardKey(Vec<((u8, u8), (u8, u8))>);
+
+// Entries are hashed one after another without the length, which every board of a layout
+// shares.
+impl Hash for BoardKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for (position, size) in &self.0 {
+            position.hash(state);
+            size.hash(state);
+        }
+    }
+}
+
+impl AStarState<BoardKey> for SlidingBlocks {
+    fn key(&self) -> BoardKey {
+        let mut entries = Vec::with_capacity(self.positions.len());
+        let mut seen = vec![false; self.positions.len()];
+
+        for (index, &piece) in self.board.iter().enumerate() {
+            if piece == BLANK || seen[piece as usize] {
+                continue;
+            }
+            seen[piece as usize] = true;
+
+            let (width, height) = self.layout.sizes[piece as usize];
+            let (x, y) = (index % self.layout.width, index / self.layout.width);
+            entries.push(((x as u8, y as u8), (width as u8, height as u8)));
+        }
+
+        BoardKey(entries)
+    }
+
+    fn h(&self) -> usize {
+        TargetDistance.estimate(self, &self.layout.target_position)
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        target_reached(self, &self.layout.target_position)
+    }
+}
+
+// Manhattan distance from the target piece to a position given as the goal.
+pub struct TargetDistance;
+
+impl HeuristicTo<SlidingBlocks, (usize, usize)> for TargetDistance {
+    fn estimate(&self, puzzle: &SlidingBlocks, target: &(usize, usize)) -> usize {
+        let (x, y) = puzzle.positions[puzzle.layout.target_piece];
+        x.abs_diff(target.0) + y.abs_diff(target.1)
+    }
+}
+
+pub fn target_reached(puzzle: &SlidingBlocks, target: &(usize, usize)) -> bool {
+    puzzle.positions[puzzle.layout.target_piece] == *target
+}
+
+impl TracedState<BoardKey, BlockMove> for SlidingBlocks {
+    fn generate_traced_successors(&self) -> Vec<(Self, BlockMove)> {
+        let mut successors = Vec::new();
+
+        for piece in 0..self.positions.len() as u8 {
+            for direction in Direction::STRAIGHT {
+                if self.can_move(piece, direction) {
+                    let movement = BlockMove {
+                        piece,
+                        from: self.positions[piece as usize],
+                        size: self.layout.sizes[piece as usize],
+                        direction
+                    };
+                    successors.push((self.moved(piece, direction), movement));
+                }
+            }
+        }
+
+        successors
+    }
+}
+
+impl UntracedState<BoardKey> for SlidingBlocks {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.generate_traced_successors()
+            .into_iter()
+            .map(|(successor, _)| successor)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = "AAB.\nAAC.\nDEFF\nDGHH";
+
+    fn target() -> Target {
+        Target { piece: 'A', position: (2, 2) }
+    }
+
+    #[test]
+    fn test_text_and_piece_list_agree() {
+        let from_text = SlidingBlocks::from_ascii(BOARD, target()).unwrap();
+        let pieces = [
+            Piece { label: 'A', size: (2, 2), position: (0, 0) },
+            Piece { label: 'B', size: (1, 1), position: (2, 0) },
+            Piece { label: 'C', size: (1, 1), position: (2, 1) },
+            Piece { label: 'D', size: (1, 2), position: (0, 2) },
+            Piece { label: 'E', size: (1, 1), position: (1, 2) },
+            Piece { label: 'F', size: (2, 1), position: (2, 2) },
+            Piece { label: 'G', size: (1, 1), position: (1, 3) },
+            Piece { label: 'H', size: (2, 1), position: (2, 3) }
+        ];
+        let from_list = SlidingBlocks::new(4, 4, &pieces, target()).unwrap();
+
+        assert_eq!(from_text.key(), from_list.key());
+        assert_eq!(from_text.to_string(), format!("{}\n", BOARD));
+        assert_eq!(from_list.to_string(), from_text.to_string());
+        assert_eq!((from_list.width(), from_list.height(), from_list.piece_count()), (4, 4, 8));
+    }
+
+    #[test]
+    fn test_moves_into_blank_cells() {
+        let board = SlidingBlocks::from_ascii(BOARD, target()).unwrap();
+        let moves: Vec<BlockMove> = board.generate_traced_successors().into_iter().map(|(_, movement)| movement).collect();
+        assert_eq!(moves, vec![
+            BlockMove { piece: 1, from: (2, 0), size: (1, 1), direction: Direction::Right },
+            BlockMove { piece: 2, from: (2, 1), size: (1, 1), direction: Direction::Right }
+        ]);
+
+        let moved = board.moved(1, Direction::Right);
+        assert_eq!(moved.to_string(), "AA.B\nAAC.\nDEFF\nDGHH\n");
+        assert_eq!(moved.g(), 1);
+        assert_eq!(moves[0].to_string(), "piece 1 right from (2, 0)");
+    }
+
+    #[test]
+    fn test_alike_pieces_share_a_key() {
+        let board = SlidingBlocks::from_ascii(BOARD, target()).unwrap();
+        let swapped = SlidingBlocks::from_ascii("AAC.\nAAB.\nDGFF\nDEHH", target()).unwrap();
+        assert_eq!(board.key(), swapped.key());
+
+        let moved = SlidingBlocks::from_ascii("AA.B\nAAC.\nDEFF\nDGHH", target()).unwrap();
+        assert_ne!(board.key(), moved.key());
+    }
+
+    #[test]
+    fn test_invalid_boards() {
+        let error = |text: &str, piece: char| SlidingBlocks::from_ascii(text, Target { piece, position: (0, 0) }).unwrap_err();
+        assert_eq!(error("AA.\nA..", 'A'), BoardError::NotARectangle('A'));
+        assert_eq!(error("AB\nA", 'A'), BoardError::RaggedRows);
+        assert_eq!(error("AB\n..", 'C'), BoardError::NoTarget('C'));
+        assert_eq!(error("AB\n..", 'A'), BoardError::TargetShapeNotUnique('A'));
+
+        let piece = |label, position| Piece { label, size: (2, 1), position };
+        let new = |pieces: &[Piece]| SlidingBlocks::new(3, 2, pieces, target()).unwrap_err();
+        assert_eq!(new(&[piece('A', (0, 0)), piece('B', (1, 0))]), BoardError::Overlap('B'));
+        assert_eq!(new(&[piece('A', (2, 0))]), BoardError::OutOfBounds('A'));
+        assert_eq!(new(&[piece('A', (0, 0)), piece('A', (0, 1))]), BoardError::DuplicateLabel('A'));
+    }
+}
@@ -0,0 +1,178 @@
+// Streaming estimate of the p-quantile of the values added so far, by the P² algorithm of Jain
+// and Chlamtac: five markers hold the minimum, the p/2, p and (1 + p)/2 quantiles and the maximum,
+// and move towards their desired ranks with a piecewise parabolic fit after each value. Constant
+// memory and time per value, however many are added.
+#[derive(Debug, Clone)]
+pub(crate) struct P2Quantile {
+    p: f64,
+    heights: [f64; 5],
+    // Ranks of the markers, from 1.
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+    count: usize
+}
+
+impl P2Quantile {
+    // `p` is clamped to 0..=1.
+    pub fn new(p: f64) -> Self {
+        let p = if p.is_nan() { 0.5 } else { p.clamp(0.0, 1.0) };
+
+        P2Quantile {
+            p,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+            count: 0
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Cell of the value, widening the extremes if it lies outside them.
+        let cell = if value < self.heights[0] {
+            self.heights[0] = value;
+            0
+        }
+        else if value >= self.heights[4] {
+            self.heights[4] = value;
+            3
+        }
+        else {
+            (1..5).find(|&index| value < self.heights[index]).unwrap() - 1
+        };
+
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        for index in 1..4 {
+            let offset = self.desired[index] - self.positions[index];
+            if (offset >= 1.0 && self.positions[index + 1] - self.positions[index] > 1.0)
+                || (offset <= -1.0 && self.positions[index - 1] - self.positions[index] < -1.0)
+            {
+                let step = offset.signum();
+                let parabolic = self.parabolic(index, step);
+                self.heights[index] = if self.heights[index - 1] < parabolic && parabolic < self.heights[index + 1] {
+                    parabolic
+                }
+                else {
+                    self.linear(index, step)
+                };
+                self.positions[index] += step;
+            }
+        }
+    }
+
+    // None before the first value. Exact up to five values.
+    pub fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            1..5 => {
+                let mut values = self.heights[..self.count].to_vec();
+                values.sort_by(f64::total_cmp);
+                Some(values[((self.count - 1) as f64 * self.p).round() as usize])
+            }
+            _ => Some(self.heights[2])
+        }
+    }
+
+    fn parabolic(&self, index: usize, step: f64) -> f64 {
+        let (n, q) = (&self.positions, &self.heights);
+        q[index] + step / (n[index + 1] - n[index - 1])
+            * ((n[index] - n[index - 1] + step) * (q[index + 1] - q[index]) / (n[index + 1] - n[index])
+                + (n[index + 1] - n[index] - step) * (q[index] - q[index - 1]) / (n[index] - n[index - 1]))
+    }
+
+    fn linear(&self, index: usize, step: f64) -> f64 {
+        let other = if step > 0.0 { index + 1 } else { index - 1 };
+        self.heights[index] + step * (self.heights[other] - self.heights[index]) / (self.positions[other] - self.positions[index])
+    }
+}
+
+// Admission to an open list over a soft limit, see `SearchOptions::with_soft_open_limit`.
+pub(crate) struct SoftOpenLimit {
+    limit: usize,
+    sketch: P2Quantile
+}
+
+impl SoftOpenLimit {
+    pub fn new(limit: usize, percentile: f64) -> Self {
+        SoftOpenLimit { limit, sketch: P2Quantile::new(percentile) }
+    }
+
+    // Whether a successor with this f, generated by a parent with `parent_f`, may go in an open list
+    // of `open_len` entries. Over the limit, a new key needs an f above its parent's by no more than
+    // the percentile of those of the successors offered so far. The open list is ordered from
+    // parent f up, so that is the percentile of the open f values, and unlike the f values
+    // themselves it doesn't drift as the search goes deeper. Keys already open are always let
+    // through to compete with their entry.
+    pub fn admits(&mut self, open_len: usize, already_open: bool, f: usize, parent_f: usize) -> bool {
+        let excess = f.saturating_sub(parent_f) as f64;
+        let percentile = self.sketch.estimate();
+        self.sketch.add(excess);
+
+        already_open || open_len < self.limit || percentile.is_none_or(|percentile| excess <= percentile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    fn shuffled(len: usize, seed: u64) -> Vec<f64> {
+        let mut rng = SplitMix64::new(seed);
+        let mut values: Vec<f64> = (1..=len).map(|value| value as f64).collect();
+        for index in (1..len).rev() {
+            values.swap(index, rng.next_below(index + 1));
+        }
+        values
+    }
+
+    #[test]
+    fn test_quantiles_of_a_shuffled_range() {
+        for (p, expected) in [(0.5, 5000.0), (0.9, 9000.0), (0.1, 1000.0)] {
+            let mut sketch = P2Quantile::new(p);
+            for value in shuffled(10_000, 7) {
+                sketch.add(value);
+            }
+
+            let estimate = sketch.estimate().unwrap();
+            assert!((estimate - expected).abs() < 200.0, "p {}: {}", p, estimate);
+        }
+    }
+
+    #[test]
+    fn test_few_values_are_exact() {
+        let mut sketch = P2Quantile::new(0.5);
+        assert_eq!(sketch.estimate(), None);
+
+        for value in [9.0, 1.0, 5.0] {
+            sketch.add(value);
+        }
+        assert_eq!(sketch.estimate(), Some(5.0));
+    }
+
+    #[test]
+    fn test_constant_values() {
+        let mut sketch = P2Quantile::new(0.75);
+        for _ in 0..1000 {
+            sketch.add(4.0);
+        }
+        assert_eq!(sketch.estimate(), Some(4.0));
+    }
+}
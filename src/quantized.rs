@@ -0,0 +1,306 @@
+use std::hash::Hash;
+
+use crate::{astar_state::AStarState, traced::state::TracedState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rounding {
+    Down,
+    Nearest,
+    Up
+}
+
+// Converts float costs to integer multiples of `resolution`, so float-cost domains can run on the
+// integer searches and queues of the crate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantizer {
+    resolution: f64,
+    edge_rounding: Rounding
+}
+
+impl Quantizer {
+    // Edge costs are rounded to the nearest multiple unless another rounding is set.
+    pub fn new(resolution: f64) -> Self {
+        assert!(resolution.is_finite() && resolution > 0.0, "the resolution must be positive, got {}", resolution);
+
+        Quantizer {
+            resolution,
+            edge_rounding: Rounding::Nearest
+        }
+    }
+
+    // Rounding::Up keeps the quantized heuristic admissible (and consistent, if the float one is)
+    // at the price of a bias towards paths with fewer edges.
+    pub fn with_edge_rounding(mut self, rounding: Rounding) -> Self {
+        self.edge_rounding = rounding;
+        self
+    }
+
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    pub fn edge_rounding(&self) -> Rounding {
+        self.edge_rounding
+    }
+
+    // Number of `resolution` steps in `cost`, which must be finite and non-negative.
+    pub fn quantize(&self, cost: f64, rounding: Rounding) -> usize {
+        assert!(cost.is_finite() && cost >= 0.0, "costs must be finite and non-negative, got {}", cost);
+
+        let steps = cost / self.resolution;
+        let rounded = match rounding {
+            Rounding::Down => steps.floor(),
+            Rounding::Nearest => steps.round(),
+            Rounding::Up => steps.ceil()
+        };
+        rounded as usize
+    }
+
+    pub fn to_cost(&self, steps: usize) -> f64 {
+        steps as f64 * self.resolution
+    }
+
+    // Largest difference between the exact cost of a path with `path_len` edges and its quantized
+    // cost, whatever the rounding of the edges.
+    pub fn max_cost_error(&self, path_len: usize) -> f64 {
+        path_len as f64 * self.resolution
+    }
+}
+
+// A domain whose costs are floats. h() must be admissible against the float costs.
+pub trait FloatCostState<K>
+where
+    K: Clone + Eq + Hash,
+    Self: Sized
+{
+    // What the traced searches record for each edge of the path.
+    type Change;
+
+    fn key(&self) -> K;
+    fn h(&self) -> f64;
+    fn is_goal(&self) -> bool;
+    // Successors with the change that produced them and the cost of the edge.
+    fn generate_float_successors(&self) -> Vec<(Self, Self::Change, f64)>;
+}
+
+// Integer view of a float-cost state for any of the traced searches. g() adds up the edge costs
+// quantized with the edge rounding of the quantizer, and h() is always rounded down.
+//
+// The rounded-down h() never exceeds the float h(), so it is admissible against the float costs.
+// With Rounding::Up edges it is also admissible against the quantized costs, and the returned path
+// costs at most one `resolution` per edge of the optimal path more than the optimum. With
+// Rounding::Nearest (or Down) the quantized rest of a path can fall below h() by up to one step per
+// remaining edge, so the returned path may be slightly worse than that.
+#[derive(Debug, Clone)]
+pub struct QuantizedState<S> {
+    state: S,
+    quantizer: Quantizer,
+    g: usize,
+    exact_g: f64
+}
+
+impl<S> QuantizedState<S> {
+    pub fn new(state: S, quantizer: Quantizer) -> Self {
+        QuantizedState {
+            state,
+            quantizer,
+            g: 0,
+            exact_g: 0.0
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn into_state(self) -> S {
+        self.state
+    }
+
+    // Sum of the float edge costs from the initial state.
+    pub fn exact_cost(&self) -> f64 {
+        self.exact_g
+    }
+
+    // g() converted back to a cost, within `max_cost_error(path length)` of exact_cost().
+    pub fn quantized_cost(&self) -> f64 {
+        self.quantizer.to_cost(self.g)
+    }
+}
+
+impl<S, K> AStarState<K> for QuantizedState<S>
+where
+    S: FloatCostState<K>,
+    K: Clone + Eq + Hash
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.quantizer.quantize(self.state.h(), Rounding::Down)
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.state.is_goal()
+    }
+}
+
+impl<S, K> TracedState<K, S::Change> for QuantizedState<S>
+where
+    S: FloatCostState<K>,
+    K: Clone + Eq + Hash
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, S::Change)> {
+        self.state.generate_float_successors()
+            .into_iter()
+            .map(|(successor, change, cost)| {
+                let successor = QuantizedState {
+                    state: successor,
+                    quantizer: self.quantizer,
+                    g: self.g + self.quantizer.quantize(cost, self.quantizer.edge_rounding),
+                    exact_g: self.exact_g + cost
+                };
+                (successor, change)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng::SplitMix64, traced::traced_astar::traced_astar};
+
+    const SIZE: usize = 10;
+    const MIN_COST: f64 = 1.0;
+
+    // 4-connected grid where entering a cell costs its weight, between MIN_COST and 3.
+    #[derive(Clone)]
+    struct WeightedCell<'a> {
+        weights: &'a [f64],
+        position: (usize, usize)
+    }
+
+    impl FloatCostState<(usize, usize)> for WeightedCell<'_> {
+        type Change = (usize, usize);
+
+        fn key(&self) -> (usize, usize) {
+            self.position
+        }
+
+        fn h(&self) -> f64 {
+            let (x, y) = self.position;
+            ((SIZE - 1 - x) + (SIZE - 1 - y)) as f64 * MIN_COST
+        }
+
+        fn is_goal(&self) -> bool {
+            self.position == (SIZE - 1, SIZE - 1)
+        }
+
+        fn generate_float_successors(&self) -> Vec<(Self, (usize, usize), f64)> {
+            neighbors(self.position)
+                .into_iter()
+                .map(|position| {
+                    let cell = WeightedCell { weights: self.weights, position };
+                    (cell, position, self.weights[position.1 * SIZE + position.0])
+                })
+                .collect()
+        }
+    }
+
+    fn neighbors((x, y): (usize, usize)) -> Vec<(usize, usize)> {
+        [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            .into_iter()
+            .filter(|&(x, y)| x < SIZE && y < SIZE)
+            .collect()
+    }
+
+    // Exact float Dijkstra from the top left corner: cost and edge count of an optimal path.
+    fn exact_optimum(weights: &[f64]) -> (f64, usize) {
+        let mut best = vec![(f64::INFINITY, 0); SIZE * SIZE];
+        let mut done = [false; SIZE * SIZE];
+        best[0] = (0.0, 0);
+
+        while let Some(index) = (0..SIZE * SIZE).filter(|&index| !done[index]).min_by(|&a, &b| best[a].0.total_cmp(&best[b].0)) {
+            done[index] = true;
+            let (cost, edges) = best[index];
+
+            for (x, y) in neighbors((index % SIZE, index / SIZE)) {
+                let next = y * SIZE + x;
+                if cost + weights[next] < best[next].0 {
+                    best[next] = (cost + weights[next], edges + 1);
+                }
+            }
+        }
+
+        best[SIZE * SIZE - 1]
+    }
+
+    fn random_weights(rng: &mut SplitMix64) -> Vec<f64> {
+        (0..SIZE * SIZE).map(|_| MIN_COST + rng.next_below(2000) as f64 / 1000.0).collect()
+    }
+
+    #[test]
+    fn test_quantize() {
+        let quantizer = Quantizer::new(0.25);
+
+        assert_eq!(quantizer.quantize(1.1, Rounding::Down), 4);
+        assert_eq!(quantizer.quantize(1.1, Rounding::Nearest), 4);
+        assert_eq!(quantizer.quantize(1.1, Rounding::Up), 5);
+        assert_eq!(quantizer.quantize(1.2, Rounding::Nearest), 5);
+        assert_eq!(quantizer.to_cost(5), 1.25);
+        assert_eq!(quantizer.max_cost_error(8), 2.0);
+    }
+
+    #[test]
+    fn test_error_bounds_on_random_weighted_grids() {
+        let mut rng = SplitMix64::new(3);
+
+        for resolution in [0.05, 0.3, 1.0] {
+            for rounding in [Rounding::Down, Rounding::Nearest, Rounding::Up] {
+                for _ in 0..5 {
+                    let weights = random_weights(&mut rng);
+                    let (optimum, optimal_len) = exact_optimum(&weights);
+
+                    let quantizer = Quantizer::new(resolution).with_edge_rounding(rounding);
+                    let start = QuantizedState::new(WeightedCell { weights: &weights, position: (0, 0) }, quantizer);
+                    let result = traced_astar(start).unwrap();
+                    let solution = &result.final_state;
+                    let path_len = result.path.len();
+
+                    // Epsilons absorb the float sums being added up in a different order.
+                    assert!((solution.exact_cost() - solution.quantized_cost()).abs() <= quantizer.max_cost_error(path_len) + 1e-9);
+                    assert!(solution.exact_cost() >= optimum - 1e-9);
+
+                    let allowed = match rounding {
+                        Rounding::Up => quantizer.max_cost_error(optimal_len),
+                        _ => quantizer.max_cost_error(path_len + 2 * optimal_len)
+                    };
+                    assert!(solution.exact_cost() - optimum <= allowed + 1e-9, "{} vs {} at {} {:?}", solution.exact_cost(), optimum, resolution, rounding);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fine_resolution_finds_the_optimum() {
+        let mut rng = SplitMix64::new(11);
+        let weights = random_weights(&mut rng);
+        let (optimum, _) = exact_optimum(&weights);
+
+        // Weights are multiples of 0.001, so that resolution is exact.
+        let start = QuantizedState::new(WeightedCell { weights: &weights, position: (0, 0) }, Quantizer::new(0.001));
+        let result = traced_astar(start).unwrap();
+        assert!((result.final_state.exact_cost() - optimum).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,71 @@
+// Notifications of how entries move in an OpenList, for tools that draw the heap. Indexes are
+// heap positions: the root is 0 and the children of i are 2i + 1 and 2i + 2. Every method does
+// nothing by default.
+pub trait QueueObserver<K> {
+    // A new entry was pushed at `index`, the end of the heap, before it bubbles up.
+    fn on_insert(&mut self, _key: &K, _index: usize) {}
+
+    // `a_key` moved from `a_index` to `b_index`, and `b_key` the other way.
+    fn on_swap(&mut self, _a_key: &K, _b_key: &K, _a_index: usize, _b_index: usize) {}
+
+    // The entry for `key` left the heap from its last position, where extract_min and remove
+    // swap it first.
+    fn on_extract(&mut self, _key: &K) {}
+
+    // The f of an entry already queued went down, before it bubbles up.
+    fn on_improve(&mut self, _key: &K, _old_f: usize, _new_f: usize) {}
+}
+
+// The observer of an OpenList nobody watches. Its methods are empty and inlined away.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl<K> QueueObserver<K> for NoObserver {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueueEvent<K> {
+    Insert { key: K, index: usize },
+    Swap { a_key: K, b_key: K, a_index: usize, b_index: usize },
+    Extract { key: K },
+    Improve { key: K, old_f: usize, new_f: usize }
+}
+
+// Keeps every notification, in order.
+#[derive(Debug, Clone)]
+pub struct RecordingQueueObserver<K> {
+    pub events: Vec<QueueEvent<K>>
+}
+
+impl<K> RecordingQueueObserver<K> {
+    pub fn new() -> Self {
+        RecordingQueueObserver { events: Vec::new() }
+    }
+}
+
+impl<K> Default for RecordingQueueObserver<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> QueueObserver<K> for RecordingQueueObserver<K>
+where
+    K: Clone
+{
+    fn on_insert(&mut self, key: &K, index: usize) {
+        self.events.push(QueueEvent::Insert { key: key.clone(), index });
+    }
+
+    fn on_swap(&mut self, a_key: &K, b_key: &K, a_index: usize, b_index: usize) {
+        self.events.push(QueueEvent::Swap { a_key: a_key.clone(), b_key: b_key.clone(), a_index, b_index });
+    }
+
+    fn on_extract(&mut self, key: &K) {
+        self.events.push(QueueEvent::Extract { key: key.clone() });
+    }
+
+    fn on_improve(&mut self, key: &K, old_f: usize, new_f: usize) {
+        self.events.push(QueueEvent::Improve { key: key.clone(), old_f, new_f });
+    }
+}
@@ -0,0 +1,449 @@
+use std::{collections::HashMap, fmt::{Debug, Write}, hash::Hash, time::Duration};
+
+use crate::{plateau::{PlateauPolicy, PlateauTracker}, search_id::SearchId, search_stats::{SearchStats, SearchWarning, SkipReason}, validation::Anomaly, weight_schedule::WeightChange};
+
+// A state that reached the open list. Nodes are numbered in insertion order, so a key reached
+// through several paths has a node for each of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedNode<K> {
+    pub key: K,
+    // Node expanded to generate this one, None for the initial state.
+    pub parent: Option<usize>,
+    pub g: usize,
+    pub h: usize,
+    pub depth: usize
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SearchEvent {
+    // The node entered the open list, replacing an open entry of its key when `improved` or moving
+    // it out of the closed list when `reopened`.
+    Inserted { node: usize, improved: bool, reopened: bool },
+    // The node was extracted and expanded into `successors` states, rebuilt from the successor
+    // cache when `cache_hit`, or into its forced successor alone when `forced`.
+    Expanded { node: usize, successors: usize, cache_hit: bool, forced: bool },
+    // Generating the successors of the node ran out of time, see `with_max_generation_time`.
+    GenerationTimedOut { node: usize, elapsed: Duration },
+    // Zero cost edges among the successors of the last expansion, see `with_edge_cost_bounds`.
+    ZeroCostEdges(usize),
+    // An anomaly found by validation, by its index in `Recording::anomalies`.
+    Anomaly(usize),
+    // A successor (or, for DeadEnd, the expanded node) went nowhere.
+    Pruned { reason: SkipReason, depth: usize },
+    // The weight schedule set a weight, see `with_weight_schedule`.
+    WeightChanged(WeightChange),
+    // The node was extracted and is a goal, the last event of a solved search.
+    Goal(usize),
+    // The node was extracted, is a goal reached from a seed and was closed to wait for a path from
+    // the initial state, see `with_seed_nodes`.
+    SeededGoal(usize)
+}
+
+// Event log of one run of `traced_astar_with_options`, filled through
+// `SearchOptions::with_recording`. It holds keys and numbers only, so it can be stored or sent
+// elsewhere and re-simulated without the domain: `simulate` rebuilds the stats, the expansion trace
+// and the exports from the events alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "K: serde::Serialize",
+    deserialize = "K: serde::Deserialize<'de> + Eq + Hash"
+)))]
+pub struct Recording<K>
+where
+    K: Eq + Hash
+{
+    search_id: u64,
+    // Settings the derived stats depend on.
+    pruning_by_depth: bool,
+    visit_counts: bool,
+    plateau_detection: Option<(usize, PlateauPolicy)>,
+    nodes: Vec<RecordedNode<K>>,
+    events: Vec<SearchEvent>,
+    // Anomalies of the edges validated at ValidationLevel::Warn, whose successors need not have a
+    // node.
+    anomalies: Vec<Anomaly<K>>,
+    // Latest node of every key and whether it was expanded, to find the parent of new nodes and
+    // tell improvements from reopenings. Only needed while recording.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    node_of_key: HashMap<K, (usize, bool)>
+}
+
+impl<K> Recording<K>
+where
+    K: Clone + Eq + Hash
+{
+    pub fn new() -> Self {
+        Recording {
+            search_id: 0,
+            pruning_by_depth: false,
+            visit_counts: false,
+            plateau_detection: None,
+            nodes: Vec::new(),
+            events: Vec::new(),
+            anomalies: Vec::new(),
+            node_of_key: HashMap::new()
+        }
+    }
+
+    pub fn nodes(&self) -> &[RecordedNode<K>] {
+        &self.nodes
+    }
+
+    pub fn events(&self) -> &[SearchEvent] {
+        &self.events
+    }
+
+    pub fn anomalies(&self) -> &[Anomaly<K>] {
+        &self.anomalies
+    }
+
+    // Replays the events to rebuild everything the live run derived from them.
+    pub fn simulate(&self) -> Simulation<'_, K> {
+        let mut stats = SearchStats::new(self.pruning_by_depth, self.visit_counts);
+        stats.search_id = SearchId::from_raw(self.search_id);
+        let mut plateau = self.plateau_detection.map(|(threshold, policy)| PlateauTracker::new(threshold, policy));
+        let mut trace = Vec::new();
+        let mut goal = None;
+        let mut open = 0;
+
+        for event in &self.events {
+            match *event {
+                SearchEvent::Inserted { node, improved, reopened } => {
+                    let RecordedNode { key, g, parent, .. } = &self.nodes[node];
+                    if !improved {
+                        open += 1;
+                        stats.peak_open = stats.peak_open.max(open);
+                    }
+                    if reopened {
+                        stats.reopened += 1;
+                        stats.record_reopening(key, *g);
+                    }
+                    if improved {
+                        stats.improved += 1;
+                    }
+                    // The initial state is inserted before anything is counted.
+                    if parent.is_some() && self.visit_counts {
+                        stats.record_insert(key, *g, improved);
+                    }
+                }
+                SearchEvent::Expanded { node, successors, cache_hit, forced } => {
+                    let RecordedNode { key, g, h, .. } = &self.nodes[node];
+                    if let Some(plateau) = &mut plateau {
+                        plateau.expanded(*h, &mut stats);
+                    }
+                    open -= 1;
+                    stats.expansions += 1;
+                    stats.trace_fingerprint.expanded(key, *g, *h);
+                    stats.generated += successors;
+                    stats.cache_hits += usize::from(cache_hit);
+                    stats.forced += usize::from(forced);
+                    if self.visit_counts {
+                        stats.record_expansion(key, *g);
+                    }
+                    trace.push(node);
+                }
+                SearchEvent::GenerationTimedOut { node, elapsed } => {
+                    stats.warnings.push(SearchWarning::GenerationTimedOut { key: self.nodes[node].key.clone(), elapsed });
+                }
+                SearchEvent::ZeroCostEdges(count) => stats.zero_cost_edges += count,
+                SearchEvent::Anomaly(index) => stats.warnings.push(SearchWarning::Anomaly(self.anomalies[index].clone())),
+                SearchEvent::Pruned { reason, depth } => stats.record_pruned(reason, depth),
+                SearchEvent::WeightChanged(change) => {
+                    stats.weight_changes.push(change);
+                    if change.clamped {
+                        stats.warnings.push(SearchWarning::WeightClamped { expansion: change.expansion });
+                    }
+                }
+                SearchEvent::SeededGoal(_) => open -= 1,
+                SearchEvent::Goal(node) => {
+                    stats.trace_fingerprint.goal(&self.nodes[node].key);
+                    goal = Some(node);
+                }
+            }
+        }
+
+        Simulation { recording: self, stats, trace, goal }
+    }
+
+    pub(crate) fn begin(&mut self, search_id: SearchId, pruning_by_depth: bool, visit_counts: bool, plateau_detection: Option<(usize, PlateauPolicy)>) {
+        *self = Recording {
+            search_id: search_id.get(),
+            pruning_by_depth,
+            visit_counts,
+            plateau_detection,
+            ..Recording::new()
+        };
+    }
+
+    // Only states that made it into the open list are recorded. A key with an open node is being
+    // improved, one whose latest node was expanded is being reopened.
+    pub(crate) fn inserted(&mut self, key: &K, parent: Option<&K>, g: usize, h: usize, depth: usize) {
+        let node = self.nodes.len();
+        let parent = parent.and_then(|parent| self.node_of_key.get(parent)).map(|&(parent, _)| parent);
+        let previous = self.node_of_key.insert(key.clone(), (node, false));
+        self.nodes.push(RecordedNode { key: key.clone(), parent, g, h, depth });
+        self.events.push(SearchEvent::Inserted {
+            node,
+            improved: previous.is_some_and(|(_, expanded)| !expanded),
+            reopened: previous.is_some_and(|(_, expanded)| expanded)
+        });
+    }
+
+    pub(crate) fn expanded(&mut self, key: &K, successors: usize, cache_hit: bool, forced: bool) {
+        let entry = self.node_of_key.get_mut(key).expect("expanded keys were inserted");
+        entry.1 = true;
+        self.events.push(SearchEvent::Expanded { node: entry.0, successors, cache_hit, forced });
+    }
+
+    pub(crate) fn generation_timed_out(&mut self, key: &K, elapsed: Duration) {
+        let (node, _) = self.node_of_key[key];
+        self.events.push(SearchEvent::GenerationTimedOut { node, elapsed });
+    }
+
+    pub(crate) fn anomaly(&mut self, anomaly: Anomaly<K>) {
+        self.events.push(SearchEvent::Anomaly(self.anomalies.len()));
+        self.anomalies.push(anomaly);
+    }
+
+    pub(crate) fn zero_cost_edges(&mut self, count: usize) {
+        if count > 0 {
+            self.events.push(SearchEvent::ZeroCostEdges(count));
+        }
+    }
+
+    pub(crate) fn pruned(&mut self, reason: SkipReason, depth: usize) {
+        self.events.push(SearchEvent::Pruned { reason, depth });
+    }
+
+    pub(crate) fn weight_changed(&mut self, change: WeightChange) {
+        self.events.push(SearchEvent::WeightChanged(change));
+    }
+
+    pub(crate) fn seeded_goal(&mut self, key: &K) {
+        let entry = self.node_of_key.get_mut(key).expect("seeded goals were inserted");
+        entry.1 = true;
+        self.events.push(SearchEvent::SeededGoal(entry.0));
+    }
+
+    pub(crate) fn goal(&mut self, key: &K) {
+        let (node, _) = self.node_of_key[key];
+        self.events.push(SearchEvent::Goal(node));
+    }
+}
+
+impl<K> Default for Recording<K>
+where
+    K: Clone + Eq + Hash
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// What `Recording::simulate` rebuilt. The stats are those of the live run, search id included.
+pub struct Simulation<'r, K>
+where
+    K: Eq + Hash
+{
+    recording: &'r Recording<K>,
+    pub stats: SearchStats<K>,
+    // Expanded nodes in expansion order.
+    pub trace: Vec<usize>,
+    // Goal node, None when the search found no goal.
+    pub goal: Option<usize>
+}
+
+impl<K> Simulation<'_, K>
+where
+    K: Clone + Eq + Hash + Debug
+{
+    pub fn node(&self, node: usize) -> &RecordedNode<K> {
+        &self.recording.nodes[node]
+    }
+
+    // Nodes from the initial state to the goal, empty when no goal was found.
+    pub fn goal_path(&self) -> Vec<usize> {
+        let mut path: Vec<usize> = std::iter::successors(self.goal, |&node| self.node(node).parent).collect();
+        path.reverse();
+        path
+    }
+
+    // One line per expansion: order, key, g, h and depth, tab separated.
+    pub fn trace_to_tsv(&self) -> String {
+        let mut tsv = String::from("order\tkey\tg\th\tdepth\n");
+        for (order, &node) in self.trace.iter().enumerate() {
+            let RecordedNode { key, g, h, depth, .. } = self.node(node);
+            writeln!(tsv, "{}\t{:?}\t{}\t{}\t{}", order, key, g, h, depth).unwrap();
+        }
+        tsv
+    }
+
+    // The search tree in Graphviz DOT: a node per recorded node, an edge to each from its parent.
+    // Expanded nodes are labelled with their expansion order, the goal path is drawn in bold.
+    pub fn to_dot(&self) -> String {
+        let (order, on_path) = self.node_marks();
+        let mut dot = String::from("digraph search {\n");
+
+        for (index, node) in self.recording.nodes.iter().enumerate() {
+            let label = format!("{:?}\\ng={} h={}", node.key, node.g, node.h).replace('"', "\\\"");
+            let expanded = order[index].map_or_else(String::new, |order| format!(" #{}", order));
+            let style = if on_path[index] { ", style=bold" } else { "" };
+            writeln!(dot, "    n{} [label=\"{}{}\"{}];", index, label, expanded, style).unwrap();
+        }
+        for (index, node) in self.recording.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                let style = if on_path[index] { " [style=bold]" } else { "" };
+                writeln!(dot, "    n{} -> n{}{};", parent, index, style).unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    // The same tree in GraphML, with the numbers as node data.
+    pub fn to_graphml(&self) -> String {
+        let (order, on_path) = self.node_marks();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, kind) in [("key", "string"), ("g", "int"), ("h", "int"), ("depth", "int"), ("expansion", "int"), ("goal_path", "boolean")] {
+            writeln!(xml, "  <key id=\"{}\" for=\"node\" attr.name=\"{}\" attr.type=\"{}\"/>", id, id, kind).unwrap();
+        }
+        xml.push_str("  <graph id=\"search\" edgedefault=\"directed\">\n");
+
+        for (index, node) in self.recording.nodes.iter().enumerate() {
+            writeln!(xml, "    <node id=\"n{}\">", index).unwrap();
+            writeln!(xml, "      <data key=\"key\">{}</data>", escape_xml(&format!("{:?}", node.key))).unwrap();
+            for (id, value) in [("g", node.g), ("h", node.h), ("depth", node.depth)] {
+                writeln!(xml, "      <data key=\"{}\">{}</data>", id, value).unwrap();
+            }
+            if let Some(order) = order[index] {
+                writeln!(xml, "      <data key=\"expansion\">{}</data>", order).unwrap();
+            }
+            writeln!(xml, "      <data key=\"goal_path\">{}</data>", on_path[index]).unwrap();
+            xml.push_str("    </node>\n");
+        }
+        for (index, node) in self.recording.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                writeln!(xml, "    <edge source=\"n{}\" target=\"n{}\"/>", parent, index).unwrap();
+            }
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    // Expansion order and goal path membership of every node.
+    fn node_marks(&self) -> (Vec<Option<usize>>, Vec<bool>) {
+        let mut order = vec![None; self.recording.nodes.len()];
+        for (position, &node) in self.trace.iter().enumerate() {
+            order[node] = Some(position);
+        }
+
+        let mut on_path = vec![false; self.recording.nodes.len()];
+        for node in self.goal_path() {
+            on_path[node] = true;
+        }
+
+        (order, on_path)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, plateau::PlateauPolicy, search_options::SearchOptions, test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::traced_astar_with_options};
+
+    #[test]
+    fn test_simulation_rebuilds_the_live_stats() {
+        for seed in 0..3 {
+            let mut recording = Recording::new();
+            let options = SearchOptions::new()
+                .with_pruning_by_depth(true)
+                .with_visit_counts(true)
+                .with_reopening(true)
+                .with_f_bound(40)
+                .with_recording(&mut recording);
+            let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(seed, 30), options);
+            let (live_stats, result) = (outcome.stats, outcome.result.unwrap());
+            let (cost, moves) = (result.final_state.g(), result.path.len());
+            drop(result);
+
+            let simulation = recording.simulate();
+            assert_eq!(simulation.stats, live_stats);
+            assert_eq!(simulation.trace.len(), live_stats.expansions);
+
+            let path = simulation.goal_path();
+            assert_eq!(path.len(), moves + 1);
+            assert_eq!(simulation.node(*path.last().unwrap()).g, cost);
+            assert!(simulation.node(path[0]).parent.is_none());
+        }
+    }
+
+    #[test]
+    fn test_simulation_rebuilds_greedy_plateaus() {
+        let mut recording = Recording::new();
+        let options = SearchOptions::new()
+            .with_greedy(true)
+            .with_plateau_detection(3, PlateauPolicy::Continue)
+            .with_recording(&mut recording);
+        let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(5, 60), options);
+
+        assert!(outcome.stats.plateaus > 0);
+        assert_eq!(recording.simulate().stats, outcome.stats);
+    }
+
+    #[test]
+    fn test_exports_of_the_search_tree() {
+        let mut recording = Recording::new();
+        let outcome = traced_astar_with_options(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), SearchOptions::new().with_recording(&mut recording));
+        let goal_cost = outcome.result.unwrap().final_state.g();
+
+        let simulation = recording.simulate();
+        let path = simulation.goal_path();
+        assert_eq!(simulation.node(path[0]).key, 0);
+        assert_eq!(simulation.node(*path.last().unwrap()).key, 7);
+        assert_eq!(simulation.node(*path.last().unwrap()).g, goal_cost);
+
+        let dot = simulation.to_dot();
+        assert!(dot.starts_with("digraph search {\n    n0 [label=\"0\\ng=0 h=6 #0\", style=bold];\n"));
+        assert_eq!(dot.matches(" -> ").count(), recording.nodes().len() - 1);
+        assert_eq!(dot.matches("style=bold").count(), 2 * path.len() - 1);
+
+        let graphml = simulation.to_graphml();
+        assert_eq!(graphml.matches("<node ").count(), recording.nodes().len());
+        assert_eq!(graphml.matches("<data key=\"expansion\">").count(), simulation.trace.len());
+
+        let tsv = simulation.trace_to_tsv();
+        assert_eq!(tsv.lines().count(), simulation.trace.len() + 1);
+        assert!(tsv.lines().nth(1).unwrap().starts_with("0\t0\t0\t6\t0"));
+    }
+
+    // Everything derived from a recording that went through JSON matches what the original gives.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut recording = Recording::new();
+        let options = SearchOptions::new().with_pruning_by_depth(true).with_visit_counts(true).with_recording(&mut recording);
+        let outcome = traced_astar_with_options(SlidingPuzzle::scrambled(2, 30), options);
+
+        let json = serde_json::to_string(&recording).unwrap();
+        drop(outcome);
+        let restored: Recording<[u8; 9]> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.nodes(), recording.nodes());
+        assert_eq!(restored.events(), recording.events());
+
+        let (live, offline) = (recording.simulate(), restored.simulate());
+        assert_eq!(offline.stats, live.stats);
+        assert_eq!(offline.trace_to_tsv(), live.trace_to_tsv());
+        assert_eq!(offline.to_dot(), live.to_dot());
+        assert_eq!(offline.to_graphml(), live.to_graphml());
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, bidirectional::BidirectionalState, open_list::{CorruptionError, OpenList}, prioritized::Prioritized, priority_queue::Insertion, search_id::SearchId, search_stats::{SearchStats, SkipReason}, traced::{outcome::SearchOutcome, replay::replay, result::TracedResult, state::TracedStateWrapper}};
+
+// Repeated queries towards one fixed goal from starts that move a little between queries, e.g.
+// an agent replanning every tick. The search runs backwards from the goal with reverse_h()
+// towards the current start, and its tree is kept between queries:
+//
+//   * a start already in the closed set is answered at once by following the parent pointers,
+//     which lead to the goal, without any expansion;
+//   * otherwise the open list is re-prioritized with reverse_h() towards the new start and the
+//     search continues from the previous frontier.
+//
+// Every answer is optimal when edge costs are non-negative, the domain doesn't change between
+// queries and reverse_h() is consistent for every start it is given (e.g. Manhattan distance on
+// a 4-connected grid). Closed g values are exact distances to the goal whatever start they were
+// found for, so switching starts never invalidates them. With an inconsistent reverse_h, answers
+// can be suboptimal, both for new queries and for starts found in the closed set.
+pub struct ReusableSearch<S, K, C>
+where
+    S: BidirectionalState<K, C>,
+    K: Clone + Eq + Hash
+{
+    open_list: OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>>,
+    closed_list: HashMap<K, TracedStateWrapper<S, K, C>>,
+    search_id: SearchId
+}
+
+impl<S, K, C> ReusableSearch<S, K, C>
+where
+    S: BidirectionalState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq
+{
+    // `goal` is the root of the backward search, so its g() must be 0.
+    pub fn new(goal: S) -> Self {
+        let mut open_list = OpenList::new();
+        open_list.insert(goal.key(), Prioritized::new(TracedStateWrapper::new(goal), 0));
+
+        ReusableSearch {
+            open_list,
+            closed_list: HashMap::new(),
+            search_id: SearchId::next()
+        }
+    }
+
+    // States whose exact distance to the goal is known.
+    pub fn closed_len(&self) -> usize {
+        self.closed_list.len()
+    }
+
+    pub fn open_len(&self) -> usize {
+        self.open_list.len()
+    }
+
+    // Every query answered from this tree is stamped with the same id.
+    pub fn search_id(&self) -> SearchId {
+        self.search_id
+    }
+
+    // Path from `new_start` to the goal. The final state is the goal reached by replaying the
+    // path from `new_start`, so its g() is the path cost, and the stats only count this query.
+    pub fn requery(&mut self, new_start: S) -> SearchOutcome<S, K, C> {
+        let start_key = new_start.key();
+        let mut stats = SearchStats {
+            search_id: self.search_id,
+            ..SearchStats::default()
+        };
+
+        if !self.closed_list.contains_key(&start_key) {
+            match self.search(&new_start, &mut stats) {
+                Ok(true) => {}
+                Ok(false) => return SearchOutcome::new(None, stats),
+                Err(error) => return SearchOutcome { error: Some(error.into()), ..SearchOutcome::new(None, stats) }
+            }
+        }
+
+        let path = self.path_to_goal(&start_key);
+        let final_state = replay(&new_start, path.iter().cloned())
+            .expect("generate_predecessors must mirror generate_traced_successors");
+
+        SearchOutcome::new(Some(TracedResult::new(path, stats.expansions, final_state)), stats)
+    }
+
+    // Continues the backward search until `start` is closed. Returns false if it can't be reached,
+    // or the error if the open list turns out to be corrupted.
+    fn search(&mut self, start: &S, stats: &mut SearchStats<K>) -> Result<bool, CorruptionError> {
+        let start_key = start.key();
+        self.reprioritize(start)?;
+
+        while let Some(current_state) = self.open_list.try_extract_min()? {
+            let current_state = current_state.state;
+            let current_key = current_state.key();
+
+            let predecessors = current_state.state.generate_predecessors();
+            stats.expansions += 1;
+            stats.trace_fingerprint.expanded(&current_key, current_state.g(), current_state.h());
+            stats.generated += predecessors.len();
+
+            for (predecessor, change) in predecessors {
+                let predecessor_key = predecessor.key();
+                if self.closed_list.contains_key(&predecessor_key) {
+                    stats.pruned[SkipReason::Closed] += 1;
+                    continue;
+                }
+
+                let priority = predecessor.g() + predecessor.reverse_h(start);
+                let wrapper = TracedStateWrapper {
+                    state: predecessor,
+                    prev_key: Some(current_key.clone()),
+                    change: Some(change),
+                    depth: current_state.depth + 1,
+                    g_override: None
+                };
+
+                if let Insertion::Improved(_) = self.open_list.try_insert(predecessor_key, Prioritized::new(wrapper, priority))? {
+                    stats.improved += 1;
+                }
+            }
+
+            // The start is expanded like any other state before returning: a closed state whose
+            // predecessors never reached the open list would cut later queries off from them.
+            self.closed_list.insert(current_key.clone(), current_state);
+            if current_key == start_key {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    // Rebuilds the open list with priorities towards the new start. The g values stay valid.
+    fn reprioritize(&mut self, start: &S) -> Result<(), CorruptionError> {
+        let mut open_list = OpenList::new();
+
+        while let Some(entry) = self.open_list.try_extract_min()? {
+            let priority = entry.state.state.g() + entry.state.state.reverse_h(start);
+            open_list.insert(entry.state.key(), Prioritized::new(entry.state, priority));
+        }
+
+        self.open_list = open_list;
+        Ok(())
+    }
+
+    // The parents of the backward tree point towards the goal, and every change is the forward
+    // move from a state to its parent, so the changes come out in start-to-goal order.
+    fn path_to_goal(&self, start_key: &K) -> Vec<C> {
+        let mut path = Vec::new();
+        let mut current = self.closed_list.get(start_key);
+
+        while let Some(node) = current {
+            if let Some(change) = &node.change {
+                path.push(change.clone());
+            }
+            current = node.prev_key.as_ref().and_then(|parent| self.closed_list.get(parent));
+        }
+
+        path
+    }
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use super::*;
+    use crate::{grid::{map::GridMap, region::GoalRegion, state::GridState}, traced::outcome::SearchError};
+
+    const MAZE: &str = "
+        ..#.......
+        .##.####..
+        ....#.....
+        ###.#.###.
+        ....#...#.
+        .####.#.#.
+        ......#...
+    ";
+
+    #[test]
+    fn test_adjacent_starts_reuse_the_tree() {
+        let map = GridMap::from_ascii(MAZE);
+        let goal = (9, 0);
+        let region = GoalRegion::Cell(goal.0, goal.1);
+        let mut search = ReusableSearch::new(GridState::with_region(&map, goal, &region));
+
+        // The agent walks through the maze, drifting off the planned path now and then.
+        let starts = [(0, 6), (0, 5), (0, 4), (1, 4), (1, 6), (2, 6), (3, 4), (5, 6), (3, 3), (3, 2)];
+        let mut expansions = Vec::new();
+
+        for start in starts {
+            let outcome = search.requery(GridState::with_region(&map, start, &region));
+            let result = outcome.result.unwrap();
+
+            let fresh = map.astar_to_region(start, &region).unwrap();
+            assert_eq!(result.final_state.g(), fresh.final_state.g());
+            assert_eq!(result.final_state.position(), goal);
+            assert_eq!(result.path.len(), result.final_state.g());
+
+            expansions.push(outcome.stats.expansions);
+        }
+
+        assert!(expansions[0] > 10);
+        assert!(expansions[1..].iter().all(|&count| count * 5 <= expansions[0]), "{:?}", expansions);
+        assert!(expansions[1..].iter().filter(|&&count| count == 0).count() * 2 >= starts.len(), "{:?}", expansions);
+    }
+
+    #[test]
+    fn test_new_region_of_the_map() {
+        let map = GridMap::from_ascii(MAZE);
+        let region = GoalRegion::Cell(9, 6);
+        let mut search = ReusableSearch::new(GridState::with_region(&map, (9, 6), &region));
+
+        for start in [(9, 5), (0, 0), (5, 2), (0, 6), (7, 6)] {
+            let result = search.requery(GridState::with_region(&map, start, &region)).result.unwrap();
+            let fresh = map.astar_to_region(start, &region).unwrap();
+            assert_eq!(result.final_state.g(), fresh.final_state.g());
+        }
+    }
+
+    #[test]
+    fn test_unreachable_start() {
+        let map = GridMap::from_ascii("
+            ..#.
+            ..#.
+        ");
+        let region = GoalRegion::Cell(0, 0);
+        let mut search = ReusableSearch::new(GridState::with_region(&map, (0, 0), &region));
+
+        assert!(search.requery(GridState::with_region(&map, (3, 1), &region)).result.is_none());
+        assert_eq!(search.closed_len(), 4);
+
+        let outcome = search.requery(GridState::with_region(&map, (1, 1), &region));
+        assert_eq!(outcome.search_id(), search.search_id());
+        assert_eq!(outcome.result.unwrap().final_state.g(), 2);
+        assert_eq!(outcome.stats.expansions, 0);
+
+        let mut other = ReusableSearch::new(GridState::with_region(&map, (0, 0), &region));
+        assert_ne!(other.requery(GridState::with_region(&map, (1, 1), &region)).search_id(), search.search_id());
+    }
+
+    #[test]
+    fn test_corrupted_open_list_is_reported() {
+        let map = GridMap::from_ascii(MAZE);
+        let region = GoalRegion::Cell(9, 0);
+        let mut search = ReusableSearch::new(GridState::with_region(&map, (9, 0), &region));
+        search.open_list.corrupt_index(&(9, 0), 3);
+
+        let outcome = search.requery(GridState::with_region(&map, (0, 6), &region));
+        assert!(outcome.result.is_none());
+        assert!(matches!(outcome.error, Some(SearchError::Internal(_))));
+    }
+}
@@ -0,0 +1,25 @@
+// SplitMix64, small and deterministic per seed. Used where the crate needs reproducible randomness
+// without pulling in a dependency.
+#[derive(Clone, Debug)]
+pub(crate) struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in 0..bound, bound must be greater than zero.
+    pub fn next_below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+}
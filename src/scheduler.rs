@@ -0,0 +1,241 @@
+use std::hash::Hash;
+
+use crate::traced::{result::TracedResult, state::TracedState, stepper::{StepStatus, TracedSearch}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SearchHandle(usize);
+
+// A search that ended during `run_for`, taken out of the scheduler. The result is None when it
+// was exhausted.
+pub struct Completed<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub handle: SearchHandle,
+    pub expansions: usize,
+    pub result: Option<TracedResult<S, K, C>>
+}
+
+struct Scheduled<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    handle: SearchHandle,
+    weight: usize,
+    search: TracedSearch<S, K, C>
+}
+
+// Suspended searches sharing one thread. `run_for` splits a number of expansions between them in
+// proportion to their weights, so every search makes progress however many there are. Searches
+// can be added, removed and given another weight between calls. A weight of 0 pauses a search.
+pub struct Scheduler<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    searches: Vec<Scheduled<S, K, C>>,
+    next_handle: usize
+}
+
+impl<S, K, C> Default for Scheduler<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, K, C> Scheduler<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub fn new() -> Self {
+        Scheduler { searches: Vec::new(), next_handle: 0 }
+    }
+
+    pub fn add(&mut self, search: TracedSearch<S, K, C>, weight: usize) -> SearchHandle {
+        let handle = SearchHandle(self.next_handle);
+        self.next_handle += 1;
+        self.searches.push(Scheduled { handle, weight, search });
+        handle
+    }
+
+    pub fn remove(&mut self, handle: SearchHandle) -> Option<TracedSearch<S, K, C>> {
+        let index = self.searches.iter().position(|scheduled| scheduled.handle == handle)?;
+        Some(self.searches.remove(index).search)
+    }
+
+    // False when the handle isn't scheduled (any more).
+    pub fn set_weight(&mut self, handle: SearchHandle, weight: usize) -> bool {
+        match self.searches.iter_mut().find(|scheduled| scheduled.handle == handle) {
+            Some(scheduled) => {
+                scheduled.weight = weight;
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn weight(&self, handle: SearchHandle) -> Option<usize> {
+        self.searches.iter().find(|scheduled| scheduled.handle == handle).map(|scheduled| scheduled.weight)
+    }
+
+    pub fn get(&self, handle: SearchHandle) -> Option<&TracedSearch<S, K, C>> {
+        self.searches.iter().find(|scheduled| scheduled.handle == handle).map(|scheduled| &scheduled.search)
+    }
+
+    pub fn len(&self) -> usize {
+        self.searches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.searches.is_empty()
+    }
+
+    // Spends up to `total_expansions` expansions on the searches with a weight, each getting its
+    // share of them rounded up or down by at most one. What a finishing search leaves of its share
+    // goes to the others. Returns the searches that ended, in the order they are scheduled.
+    pub fn run_for(&mut self, total_expansions: usize) -> Vec<Completed<S, K, C>> {
+        let mut budget = total_expansions;
+        let mut completed = Vec::new();
+
+        loop {
+            self.take_completed(&mut completed);
+
+            let weights: Vec<usize> = self.searches.iter().map(|scheduled| scheduled.weight).collect();
+            if budget == 0 || weights.iter().all(|&weight| weight == 0) {
+                break;
+            }
+
+            for (scheduled, share) in self.searches.iter_mut().zip(shares(budget, &weights)) {
+                if share == 0 {
+                    continue;
+                }
+                let before = scheduled.search.expansions();
+                scheduled.search.step(share);
+                budget -= scheduled.search.expansions() - before;
+            }
+        }
+
+        completed
+    }
+
+    fn take_completed(&mut self, completed: &mut Vec<Completed<S, K, C>>) {
+        let mut index = 0;
+        while index < self.searches.len() {
+            if self.searches[index].search.status() == StepStatus::Running {
+                index += 1;
+                continue;
+            }
+
+            let Scheduled { handle, search, .. } = self.searches.remove(index);
+            completed.push(Completed { handle, expansions: search.expansions(), result: search.into_result() });
+        }
+    }
+}
+
+// `total` split in proportion to `weights` by largest remainder: every share is the exact one
+// rounded down or up, and the shares add up to `total`. Some weight must be positive.
+fn shares(total: usize, weights: &[usize]) -> Vec<usize> {
+    let weight_sum: u128 = weights.iter().map(|&weight| weight as u128).sum();
+    let mut shares = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+
+    for (index, &weight) in weights.iter().enumerate() {
+        let exact = total as u128 * weight as u128;
+        shares.push((exact / weight_sum) as usize);
+        remainders.push((exact % weight_sum, index));
+    }
+
+    let left = total - shares.iter().sum::<usize>();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for &(_, index) in remainders.iter().take(left) {
+        shares[index] += 1;
+    }
+
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{Direction, SlidingPuzzle}, traced::traced_astar::traced_astar};
+
+    // Two tiles swapped: an odd permutation, so the goal is unreachable and the search runs for
+    // all 181440 reachable boards.
+    fn endless() -> TracedSearch<SlidingPuzzle, [u8; 9], Direction> {
+        TracedSearch::new(SlidingPuzzle { tiles: [2, 1, 3, 4, 5, 6, 7, 8, 0], g_cost: 0 })
+    }
+
+    #[test]
+    fn test_shares_round_by_at_most_one() {
+        assert_eq!(shares(60, &[1, 2, 3]), vec![10, 20, 30]);
+        assert_eq!(shares(7, &[1, 2, 3]), vec![1, 2, 4]);
+        assert_eq!(shares(2, &[1, 1, 1]), vec![1, 1, 0]);
+        assert_eq!(shares(5, &[0, 3]), vec![0, 5]);
+    }
+
+    #[test]
+    fn test_expansions_follow_the_weights() {
+        let mut scheduler = Scheduler::new();
+        let weights = [1, 2, 3, 0];
+        let handles: Vec<SearchHandle> = weights.iter().map(|&weight| scheduler.add(endless(), weight)).collect();
+
+        for slice in [60, 7, 1, 100, 13] {
+            let before: Vec<usize> = handles.iter().map(|&handle| scheduler.get(handle).unwrap().expansions()).collect();
+            assert!(scheduler.run_for(slice).is_empty());
+
+            for (index, &handle) in handles.iter().enumerate() {
+                let spent = scheduler.get(handle).unwrap().expansions() - before[index];
+                let exact = slice as f64 * weights[index] as f64 / 6.0;
+                assert!((spent as f64 - exact).abs() < 1.0, "{} spent {} of {}", index, spent, slice);
+            }
+        }
+
+        // Reprioritized between slices.
+        assert!(scheduler.set_weight(handles[0], 5));
+        assert!(scheduler.set_weight(handles[3], 1));
+        let before = scheduler.get(handles[0]).unwrap().expansions();
+        scheduler.run_for(110);
+        assert_eq!(scheduler.get(handles[0]).unwrap().expansions() - before, 50);
+        assert_eq!(scheduler.get(handles[3]).unwrap().expansions(), 10);
+
+        assert!(scheduler.remove(handles[1]).is_some());
+        assert!(!scheduler.set_weight(handles[1], 1));
+        assert_eq!(scheduler.len(), 3);
+    }
+
+    #[test]
+    fn test_completed_searches_match_solo_runs() {
+        let starts: Vec<SlidingPuzzle> = (0..6).map(|seed| SlidingPuzzle::scrambled(seed, 20 + seed as usize)).collect();
+        let mut scheduler = Scheduler::new();
+        let handles: Vec<SearchHandle> = starts.iter()
+            .enumerate()
+            .map(|(index, start)| scheduler.add(TracedSearch::new(start.clone()), index + 1))
+            .collect();
+        let endless = scheduler.add(endless(), 1);
+
+        let mut completed = Vec::new();
+        while completed.len() < starts.len() {
+            completed.extend(scheduler.run_for(25));
+        }
+        assert_eq!(scheduler.len(), 1);
+        assert!(scheduler.get(endless).is_some());
+
+        for done in completed {
+            let index = handles.iter().position(|&handle| handle == done.handle).unwrap();
+            let expected = traced_astar(starts[index].clone()).unwrap();
+            let result = done.result.unwrap();
+
+            assert_eq!(done.expansions, expected.iterations);
+            assert_eq!(result.path.to_vec(), expected.path.to_vec());
+            assert_eq!(result.iterations, expected.iterations);
+            assert_eq!(result.cost, expected.cost);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::{fmt::Display, sync::atomic::{AtomicU64, Ordering}};
+
+static NEXT_SEARCH_ID: AtomicU64 = AtomicU64::new(1);
+
+// Identifies one search run, so stats and outcomes kept around can be told apart. Ids come from
+// a process wide counter: they are unique within the process and increase with every search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SearchId(u64);
+
+impl SearchId {
+    pub fn next() -> Self {
+        SearchId(NEXT_SEARCH_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    // The id of a recorded search, see `Recording::simulate`.
+    pub(crate) fn from_raw(id: u64) -> Self {
+        SearchId(id)
+    }
+}
+
+impl Display for SearchId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "search #{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_increase() {
+        let first = SearchId::next();
+        let second = SearchId::next();
+
+        assert!(second > first);
+        assert_eq!(first.to_string(), format!("search #{}", first.get()));
+    }
+}
@@ -0,0 +1,842 @@
+use std::{hash::Hash, rc::Rc, time::Duration};
+
+use crate::{edge_costs::EdgeCostBounds, expansion_limit::{LimitSpec, NodeEstimator, ResolvedLimit, SharedBudget}, labels::KeyLabeler, plateau::{PlateauDetected, PlateauPolicy}, recording::Recording, state_pool::Recycler, successor_cache::CachedEdge, traced::{commutative::{self, CommutativeChanges}, path::ChangeCodec}, unreachable::{GoalTagMismatch, UnreachabilitySet}, validation::ValidationLevel, weight_schedule::{self, ScheduleContext}};
+
+type KeyPredicate<'a, K> = Box<dyn Fn(&K) -> bool + 'a>;
+pub const TIE_BREAK_SLOTS: usize = 1024;
+pub const PRIORITY_HINT_SLOTS: usize = 256;
+pub const DEFAULT_MIN_COVERAGE: f64 = 0.5;
+
+type ChangePriority<'a, C> = Box<dyn Fn(&C) -> usize + 'a>;
+type PlateauObserver<'a> = Box<dyn FnMut(&PlateauDetected) + 'a>;
+type WeightSchedule<'a> = Box<dyn FnMut(&ScheduleContext) -> f64 + 'a>;
+type Reconstructor<'a, S, K, C> = Box<dyn Fn(&S, &CachedEdge<K, C>) -> S + 'a>;
+type EdgeCostOverride<'a, S, C> = Box<dyn Fn(&S, &C, usize) -> usize + 'a>;
+type PriorityHint<'a, K> = Box<dyn Fn(&K) -> i32 + 'a>;
+
+pub struct SearchOptions<'a, S, K, C = ()>
+where
+    K: Clone + Eq + Hash
+{
+    f_bound: Option<usize>,
+    forbidden: Option<KeyPredicate<'a, K>>,
+    pruning_by_depth: bool,
+    recycler: Option<&'a mut dyn Recycler<S>>,
+    change_codec: Option<Rc<dyn ChangeCodec<C>>>,
+    greedy: bool,
+    tie_break: Option<ChangePriority<'a, C>>,
+    priority_hint: Option<PriorityHint<'a, K>>,
+    reopening: bool,
+    successor_cache: Option<(usize, Reconstructor<'a, S, K, C>)>,
+    visit_counts: bool,
+    auto_reserve: bool,
+    edge_cost_bounds: Option<EdgeCostBounds>,
+    plateau_detection: Option<(usize, PlateauPolicy)>,
+    plateau_observer: Option<PlateauObserver<'a>>,
+    redundant_order: Option<fn(&C, &C) -> bool>,
+    recording: Option<&'a mut Recording<K>>,
+    weight_schedule: Option<(usize, WeightSchedule<'a>)>,
+    max_generation_time: Option<Duration>,
+    edge_cost_override: Option<EdgeCostOverride<'a, S, C>>,
+    validation: ValidationLevel,
+    consistent_heuristic: bool,
+    expansion_limit: Option<LimitSpec>,
+    node_estimator: Option<NodeEstimator<'a, S>>,
+    shared_budget: Option<&'a SharedBudget>,
+    seed_nodes: Vec<(S, usize)>,
+    soft_open_limit: Option<(usize, f64)>,
+    forced_successors: bool,
+    forced_check: bool,
+    state_space_bound: Option<usize>,
+    min_coverage: f64,
+    auto_incumbent: bool,
+    known_unreachable: Option<&'a UnreachabilitySet<K>>,
+    key_labeler: Option<KeyLabeler<'a, K>>,
+    cost_verification: bool,
+    #[cfg(test)]
+    corrupt_open_list_after: Option<usize>,
+    #[cfg(test)]
+    miscounted_step: Option<usize>
+}
+
+impl<'a, S, K, C> SearchOptions<'a, S, K, C>
+where
+    K: Clone + Eq + Hash
+{
+    // Plain A*: every option off or unset, except forced successors, which are taken when a state
+    // has one, and a minimum coverage of DEFAULT_MIN_COVERAGE, which only matters with a state
+    // space bound. Validation is ValidationLevel::Off and cost verification is on in debug builds
+    // only. Default is the same.
+    pub fn new() -> Self {
+        SearchOptions {
+            f_bound: None,
+            forbidden: None,
+            pruning_by_depth: false,
+            recycler: None,
+            change_codec: None,
+            greedy: false,
+            tie_break: None,
+            priority_hint: None,
+            reopening: false,
+            successor_cache: None,
+            visit_counts: false,
+            auto_reserve: false,
+            edge_cost_bounds: None,
+            plateau_detection: None,
+            plateau_observer: None,
+            redundant_order: None,
+            recording: None,
+            weight_schedule: None,
+            max_generation_time: None,
+            edge_cost_override: None,
+            validation: ValidationLevel::Off,
+            consistent_heuristic: false,
+            expansion_limit: None,
+            node_estimator: None,
+            shared_budget: None,
+            seed_nodes: Vec::new(),
+            soft_open_limit: None,
+            forced_successors: true,
+            forced_check: false,
+            state_space_bound: None,
+            min_coverage: DEFAULT_MIN_COVERAGE,
+            auto_incumbent: false,
+            known_unreachable: None,
+            key_labeler: None,
+            cost_verification: false,
+            #[cfg(test)]
+            corrupt_open_list_after: None,
+            #[cfg(test)]
+            miscounted_step: None
+        }
+    }
+
+    // Successors with f above the bound are never inserted.
+    pub fn with_f_bound(mut self, bound: usize) -> Self {
+        self.f_bound = Some(bound);
+        self
+    }
+
+    // Successors whose key matches the predicate are never inserted.
+    pub fn with_forbidden(mut self, forbidden: impl Fn(&K) -> bool + 'a) -> Self {
+        self.forbidden = Some(Box::new(forbidden));
+        self
+    }
+
+    // Records the skipped successors per depth and reason in `SearchStats::pruned_by_depth`.
+    pub fn with_pruning_by_depth(mut self, enabled: bool) -> Self {
+        self.pruning_by_depth = enabled;
+        self
+    }
+
+    // Discarded states are handed to the recycler instead of being dropped.
+    pub fn with_recycler(mut self, recycler: &'a mut dyn Recycler<S>) -> Self {
+        self.recycler = Some(recycler);
+        self
+    }
+
+    // The path of the result is stored compressed with this codec.
+    pub fn with_change_codec(mut self, codec: Rc<dyn ChangeCodec<C>>) -> Self {
+        self.change_codec = Some(codec);
+        self
+    }
+
+    // Greedy best-first search: the open list is ordered by h() alone. Paths are no longer optimal.
+    pub fn with_greedy(mut self, enabled: bool) -> Self {
+        self.greedy = enabled;
+        self
+    }
+
+    // Among successors with the same f (or h when greedy), lower values of the hook on the change
+    // that produced them are expanded first. Values from TIE_BREAK_SLOTS up are treated as equal.
+    pub fn with_tie_break(mut self, tie_break: impl Fn(&C) -> usize + 'a) -> Self {
+        self.tie_break = Some(Box::new(tie_break));
+        self
+    }
+
+    // Among successors with the same f (or h when greedy), keys with a higher hint are expanded
+    // first, before the tie break of `with_tie_break` is looked at. Costs and optimality are left
+    // alone. Hints outside -PRIORITY_HINT_SLOTS / 2..PRIORITY_HINT_SLOTS / 2 are treated as the
+    // nearest end of the range.
+    pub fn with_priority_hint(mut self, hint: impl Fn(&K) -> i32 + 'a) -> Self {
+        self.priority_hint = Some(Box::new(hint));
+        self
+    }
+
+    // Closed states reached again through a cheaper path are moved back to the open list. Only
+    // needed with inconsistent heuristics, where it keeps A* optimal.
+    pub fn with_reopening(mut self, enabled: bool) -> Self {
+        self.reopening = enabled;
+        self
+    }
+
+    // Remembers the successors of up to `capacity` expanded states (least recently used evicted
+    // first). When a state is expanded again, its successors are rebuilt from the parent and each
+    // cached edge by `regenerate_from_cache`, typically by applying the change, instead of
+    // calling generate_traced_successors.
+    pub fn with_successor_cache(mut self, capacity: usize, regenerate_from_cache: impl Fn(&S, &CachedEdge<K, C>) -> S + 'a) -> Self {
+        self.successor_cache = Some((capacity, Box::new(regenerate_from_cache)));
+        self
+    }
+
+    // Counts reinserts, reopenings and expansions per key in `SearchStats::visits`, to find the
+    // keys a search keeps coming back to.
+    pub fn with_visit_counts(mut self, enabled: bool) -> Self {
+        self.visit_counts = enabled;
+        self
+    }
+
+    // Reserves the open and closed lists once, after the first few expansions, for the number of
+    // nodes projected from their growth so far. Saves the rehashes of growing them step by step.
+    pub fn with_auto_reserve(mut self, enabled: bool) -> Self {
+        self.auto_reserve = enabled;
+        self
+    }
+
+    // Checks every edge (the g of a successor minus the g of its parent) against the bounds and
+    // stops with `SearchOutcome::edge_cost_error` at the first one outside them, at a successor
+    // with a lower g than its parent, or when no edge cost anything during the first
+    // FORGOTTEN_COSTS_EXPANSIONS expansions. Zero cost edges within the bounds are counted in
+    // `SearchStats::zero_cost_edges`.
+    pub fn with_edge_cost_bounds(mut self, min: usize, max: usize) -> Self {
+        self.edge_cost_bounds = Some(EdgeCostBounds { min, max });
+        self
+    }
+
+    // Greedy searches count the expansions since h last improved. When `threshold` of them go by,
+    // the plateau is counted in `SearchStats::plateaus`, handed to the plateau observer and the
+    // policy decides how to leave it. A* searches ignore it.
+    pub fn with_plateau_detection(mut self, threshold: usize, policy: PlateauPolicy) -> Self {
+        self.plateau_detection = Some((threshold, policy));
+        self
+    }
+
+    // Called with every plateau reported by `with_plateau_detection`.
+    pub fn with_plateau_observer(mut self, observer: impl FnMut(&PlateauDetected) + 'a) -> Self {
+        self.plateau_observer = Some(Box::new(observer));
+        self
+    }
+
+    // Skips the successors whose change is canonically before the independent change that led to
+    // their parent, see CommutativeChanges for what keeps that optimal.
+    pub fn with_commutative_pruning(mut self) -> Self
+    where
+        C: CommutativeChanges
+    {
+        self.redundant_order = Some(commutative::is_redundant_order::<C>);
+        self
+    }
+
+    // Logs every insertion, expansion and pruned successor of the search into `recording`,
+    // replacing what it held, see Recording. Only traced_astar_with_options records.
+    pub fn with_recording(mut self, recording: &'a mut Recording<K>) -> Self {
+        self.recording = Some(recording);
+        self
+    }
+
+    // Orders the open list by g + weight * h, with the weight returned by `schedule`. It is asked
+    // for the initial weight and then after every `every` expansions; when the weight changes the
+    // open list is reprioritized with the new one. Weights below 1 are raised to 1 and flagged in
+    // `SearchStats::weight_changes`. A schedule that ends at 1 only gives optimal paths with
+    // `with_reopening(true)`, states closed under a higher weight may have been reached too dearly.
+    // Greedy searches ignore the weight.
+    pub fn with_weight_schedule(mut self, every: usize, schedule: impl FnMut(&ScheduleContext) -> f64 + 'a) -> Self {
+        self.weight_schedule = Some((every.max(1), Box::new(schedule)));
+        self
+    }
+
+    // Weighted A*: the open list is ordered by g + weight * h for the whole search. Paths cost at
+    // most `weight` times the optimal one, and only the ordering changes, the states keep their g.
+    // A weight of 1 is the plain search. Replaces any weight schedule.
+    pub fn with_weight(self, weight: f64) -> Self {
+        self.with_weight_schedule(usize::MAX, move |_| weight)
+    }
+
+    // Budget for generating the successors of one state. Generators see it through the GenContext
+    // of `generate_traced_successors_with`; when one is still running at the deadline, what it
+    // returns is dropped, the state counts as a dead end and a `SearchWarning::GenerationTimedOut`
+    // is added to the stats. Generators without the context variant are never cut short.
+    pub fn with_max_generation_time(mut self, budget: Duration) -> Self {
+        self.max_generation_time = Some(budget);
+        self
+    }
+
+    // Cost of every edge as `cost_override(parent, change, cost)`, where cost is the g difference
+    // the generator gave the edge. The search keeps its own g from the new costs for ordering,
+    // pruning and the result's `cost` and `step_costs`. The states' own g() is left as it is.
+    pub fn with_edge_cost_override(mut self, cost_override: impl Fn(&S, &C, usize) -> usize + 'a) -> Self {
+        self.edge_cost_override = Some(Box::new(cost_override));
+        self
+    }
+
+    // Checks every edge before its successor is looked at: a g below the parent's and an f below
+    // g, plus an h that drops by more than the edge cost when `with_consistent_heuristic` is set.
+    // Warn lists them in `SearchStats::warnings`, Strict stops at the first one with it in
+    // `SearchOutcome::validation_error`. Off, the default, costs a branch per successor.
+    pub fn with_validation(mut self, level: ValidationLevel) -> Self {
+        self.validation = level;
+        self
+    }
+
+    // Declares h consistent, for validation to check it. Changes nothing else.
+    pub fn with_consistent_heuristic(mut self, consistent: bool) -> Self {
+        self.consistent_heuristic = consistent;
+        self
+    }
+
+    // Stops the search when the next state would be expansion number `limit + 1`, without a
+    // result and with `SearchOutcome::budget_exhausted` set. A goal at the top of the open list is
+    // still returned, since extracting it expands nothing.
+    pub fn with_max_expansions(self, limit: usize) -> Self {
+        self.with_expansion_limit(limit)
+    }
+
+    // Like with_max_expansions, with the limit given as an absolute count or relative to the
+    // estimate of `with_node_estimator`. The limit is resolved when the search starts, and
+    // `SearchStats::expansion_limit` records it with the estimate. A plain count or an
+    // `auto::Budget` converts to an absolute limit.
+    pub fn with_expansion_limit(mut self, limit: impl Into<LimitSpec>) -> Self {
+        self.expansion_limit = Some(limit.into());
+        self
+    }
+
+    // Estimates the nodes a search from the given initial state expands, for `LimitSpec::Relative`.
+    // `auto::predicted_nodes` runs the probe of `estimate::probe`, and `move |_| nodes` supplies an
+    // estimate of your own. Without an estimator, or with a NaN estimate, a relative limit is
+    // unresolved: the search runs without it and adds a `SearchWarning::UnresolvedLimit`.
+    pub fn with_node_estimator(mut self, estimator: impl Fn(&S) -> f64 + 'a) -> Self {
+        self.node_estimator = Some(Box::new(estimator));
+        self
+    }
+
+    // Caps the expansion limit at what is left of the budget when the search starts, and takes
+    // the search's expansions out of it at the end. Searches of a batch given the same budget
+    // share it, however their own limits were set. A search without a limit of its own may take
+    // all that is left.
+    pub fn with_shared_budget(mut self, budget: &'a SharedBudget) -> Self {
+        self.shared_budget = Some(budget);
+        self
+    }
+
+    // States put in the open list next to the initial one with an estimated g, for states known to
+    // lie on good paths. States reached from a seed carry its estimate on, and a path from the
+    // initial state always replaces them, so optimistic estimates get corrected g. A goal reached
+    // from a seed waits until a path from the initial state meets one of the states on its way,
+    // which gives the result. It is stamped `Optimality::Kept` when nothing left open could lead to
+    // a cheaper goal, `Optimality::Unknown` otherwise. Estimates that are upper bounds of the real
+    // g keep seeds from being expanded ahead of their real paths, and a tight one lets the search
+    // stop as soon as it meets the seed's way to the goal. Lower ones make it expand the seeds'
+    // surroundings first and give up the proof. Seeds with the key of the initial state or of an
+    // earlier seed are ignored.
+    pub fn with_seed_nodes(mut self, seeds: Vec<(S, usize)>) -> Self {
+        self.seed_nodes = seeds;
+        self
+    }
+
+    // Once the open list holds `limit` entries, a new key is only admitted when its f is above its
+    // parent's by no more than the `percentile` (0 to 1) of that difference over the successors so
+    // far, estimated by a streaming quantile sketch. The others are counted as
+    // `SkipReason::SoftRejected`. Softer than a hard cap: the list stays near the limit while
+    // keeping the promising states. Rejecting any state gives up the proof of optimality, and the
+    // outcome is stamped `Optimality::Unknown`.
+    pub fn with_soft_open_limit(mut self, limit: usize, percentile: f64) -> Self {
+        self.soft_open_limit = Some((limit, percentile));
+        self
+    }
+
+    // Whether expansions take the forced successor of states that have one, see
+    // TracedState::forced_successor. On by default.
+    pub fn with_forced_successors(mut self, enabled: bool) -> Self {
+        self.forced_successors = enabled;
+        self
+    }
+
+    // Debug mode for TracedState::forced_successor: every forced expansion also runs the full
+    // generator, and the forced successor must be one of the successors of lowest f that aren't
+    // closed. The search still goes on with the forced one alone. The first that isn't stops the
+    // search with `Anomaly::ForcedNotGenerated` or `Anomaly::ForcedNotBest` in
+    // `SearchOutcome::validation_error`, whatever the validation level.
+    pub fn with_forced_check(mut self, enabled: bool) -> Self {
+        self.forced_check = enabled;
+        self
+    }
+
+    // Number of states reachable from the initial one, or an upper bound of it. A search that runs
+    // out of states without a goal reports the share it closed in `SearchOutcome::coverage`, and
+    // adds a `SearchWarning::SuspiciousExhaustion` when that is below `with_min_coverage`. An
+    // exhausted search with an exact bound and no pruning covers everything.
+    pub fn with_state_space_bound(mut self, bound: usize) -> Self {
+        self.state_space_bound = Some(bound);
+        self
+    }
+
+    // Coverage below which an exhausted search warns, DEFAULT_MIN_COVERAGE unless set. Looser
+    // bounds need lower values.
+    pub fn with_min_coverage(mut self, ratio: f64) -> Self {
+        self.min_coverage = ratio;
+        self
+    }
+
+    // Runs a greedy best-first pass before the search, over the same open and closed lists, and
+    // takes the cost of the path it finds as the f bound (or the bound set, if lower). A path
+    // costs at least the optimum, so with an admissible h no state on an optimal path has f over
+    // it: the bound only keeps hopeless states out of the open list, and the result is the one
+    // the search finds without it. The bound prunes f strictly above it, which a goal reached at
+    // exactly the bound passes. Without a path from the greedy pass the search runs unbounded.
+    // The pass honors `with_forbidden` and `with_edge_cost_override`, the pruning that changes
+    // what paths exist or cost, and skips `with_known_unreachable` keys. It ignores the rest.
+    pub fn with_auto_incumbent(mut self, enabled: bool) -> Self {
+        self.auto_incumbent = enabled;
+        self
+    }
+
+    // Keys proven unable to reach the goal by an earlier search, see
+    // `SearchOutcome::into_unreachable_set`. They are dead ends: never inserted, counted as
+    // `SkipReason::DeadEnd`, and an initial state among them ends the search at once. The set
+    // is refused unless `goal_tag` is the one it was proven for.
+    pub fn with_known_unreachable(mut self, set: &'a UnreachabilitySet<K>, goal_tag: &str) -> Result<Self, GoalTagMismatch> {
+        if set.goal_tag() != goal_tag {
+            return Err(GoalTagMismatch {
+                proven_for: set.goal_tag().to_string(),
+                offered_for: goal_tag.to_string()
+            });
+        }
+
+        self.known_unreachable = Some(set);
+        Ok(self)
+    }
+
+    // Names the keys the diagnostics of the outcome mention, in `SearchOutcome::labels`. Each
+    // of them is labeled once, when the search is over.
+    pub fn with_key_labeler(mut self, labeler: impl Fn(&K) -> String + 'a) -> Self {
+        self.key_labeler = Some(Box::new(labeler));
+        self
+    }
+
+    // Checks the returned path before handing it out: its step costs must add up to the cost the
+    // result reports, and that cost must be the goal's g. A mismatch is a bug of the search, which
+    // comes back as `SearchError::Internal` with no result. Debug builds always check, this turns
+    // the check on in release builds too. It walks the path once.
+    pub fn with_cost_verification(mut self, enabled: bool) -> Self {
+        self.cost_verification = enabled;
+        self
+    }
+
+    pub fn f_bound(&self) -> Option<usize> {
+        self.f_bound
+    }
+
+    pub fn is_forbidden(&self, key: &K) -> bool {
+        self.forbidden.as_ref().is_some_and(|forbidden| forbidden(key))
+    }
+
+    pub fn has_forbidden(&self) -> bool {
+        self.forbidden.is_some()
+    }
+
+    pub fn is_known_unreachable(&self, key: &K) -> bool {
+        self.known_unreachable.is_some_and(|set| set.contains(key))
+    }
+
+    pub fn has_known_unreachable(&self) -> bool {
+        self.known_unreachable.is_some()
+    }
+
+    pub fn pruning_by_depth(&self) -> bool {
+        self.pruning_by_depth
+    }
+
+    pub fn has_recycler(&self) -> bool {
+        self.recycler.is_some()
+    }
+
+    pub fn change_codec(&self) -> Option<&Rc<dyn ChangeCodec<C>>> {
+        self.change_codec.as_ref()
+    }
+
+    pub fn greedy(&self) -> bool {
+        self.greedy
+    }
+
+    pub fn has_tie_break(&self) -> bool {
+        self.tie_break.is_some()
+    }
+
+    pub fn has_priority_hint(&self) -> bool {
+        self.priority_hint.is_some()
+    }
+
+    pub fn reopening(&self) -> bool {
+        self.reopening
+    }
+
+    pub fn visit_counts(&self) -> bool {
+        self.visit_counts
+    }
+
+    pub fn auto_reserve(&self) -> bool {
+        self.auto_reserve
+    }
+
+    pub fn edge_cost_bounds(&self) -> Option<EdgeCostBounds> {
+        self.edge_cost_bounds
+    }
+
+    pub fn commutative_pruning(&self) -> bool {
+        self.redundant_order.is_some()
+    }
+
+    pub fn plateau_detection(&self) -> Option<(usize, PlateauPolicy)> {
+        self.plateau_detection
+    }
+
+    pub fn has_plateau_observer(&self) -> bool {
+        self.plateau_observer.is_some()
+    }
+
+    pub fn weight_schedule_interval(&self) -> Option<usize> {
+        self.weight_schedule.as_ref().map(|(every, _)| *every)
+    }
+
+    pub fn max_generation_time(&self) -> Option<Duration> {
+        self.max_generation_time
+    }
+
+    pub fn has_edge_cost_override(&self) -> bool {
+        self.edge_cost_override.is_some()
+    }
+
+    pub fn validation(&self) -> ValidationLevel {
+        self.validation
+    }
+
+    pub fn consistent_heuristic(&self) -> bool {
+        self.consistent_heuristic
+    }
+
+    // The limit set by with_max_expansions or an absolute LimitSpec. Relative limits are only
+    // known once the search starts.
+    pub fn max_expansions(&self) -> Option<usize> {
+        match self.expansion_limit {
+            Some(LimitSpec::Absolute(limit)) => Some(limit),
+            _ => None
+        }
+    }
+
+    pub fn expansion_limit(&self) -> Option<LimitSpec> {
+        self.expansion_limit
+    }
+
+    pub fn has_node_estimator(&self) -> bool {
+        self.node_estimator.is_some()
+    }
+
+    pub fn shared_budget(&self) -> Option<&'a SharedBudget> {
+        self.shared_budget
+    }
+
+    // The limit a search from `initial_state` runs with, and the estimate when a relative limit
+    // needed one. NaN estimates are dropped.
+    pub(crate) fn resolve_expansion_limit(&self, initial_state: &S) -> Option<ResolvedLimit> {
+        let estimate = match (self.expansion_limit, &self.node_estimator) {
+            (Some(LimitSpec::Relative { .. }), Some(estimator)) => Some(estimator(initial_state)).filter(|estimate| !estimate.is_nan()),
+            _ => None
+        };
+        let own = self.expansion_limit.and_then(|limit| limit.resolve(estimate));
+        let pooled = self.shared_budget.map(SharedBudget::remaining);
+        own.into_iter().chain(pooled).min().map(|limit| ResolvedLimit { limit, estimate })
+    }
+
+    // Whether the limit is relative and `resolved` came without the estimate it needs.
+    pub(crate) fn limit_unresolved(&self, resolved: Option<&ResolvedLimit>) -> bool {
+        matches!(self.expansion_limit, Some(LimitSpec::Relative { .. })) && resolved.is_none_or(|resolved| resolved.estimate.is_none())
+    }
+
+    pub fn soft_open_limit(&self) -> Option<(usize, f64)> {
+        self.soft_open_limit
+    }
+
+    pub fn forced_successors(&self) -> bool {
+        self.forced_successors
+    }
+
+    pub fn forced_check(&self) -> bool {
+        self.forced_check
+    }
+
+    pub fn state_space_bound(&self) -> Option<usize> {
+        self.state_space_bound
+    }
+
+    pub fn min_coverage(&self) -> f64 {
+        self.min_coverage
+    }
+
+    pub fn auto_incumbent(&self) -> bool {
+        self.auto_incumbent
+    }
+
+    pub fn has_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn successor_cache_capacity(&self) -> Option<usize> {
+        self.successor_cache.as_ref().map(|(capacity, _)| *capacity)
+    }
+
+    pub fn has_seed_nodes(&self) -> bool {
+        !self.seed_nodes.is_empty()
+    }
+
+    pub fn has_key_labeler(&self) -> bool {
+        self.key_labeler.is_some()
+    }
+
+    // Whether the search checks its result, see `with_cost_verification`.
+    pub fn cost_verification(&self) -> bool {
+        self.cost_verification || cfg!(debug_assertions)
+    }
+
+    pub(crate) fn take_key_labeler(&mut self) -> Option<KeyLabeler<'a, K>> {
+        self.key_labeler.take()
+    }
+
+    pub(crate) fn regenerate(&self, parent: &S, edge: &CachedEdge<K, C>) -> Option<S> {
+        self.successor_cache.as_ref().map(|(_, regenerate)| regenerate(parent, edge))
+    }
+
+    pub(crate) fn take_seed_nodes(&mut self) -> Vec<(S, usize)> {
+        std::mem::take(&mut self.seed_nodes)
+    }
+
+    pub(crate) fn overridden_edge_cost(&self, parent: &S, change: &C, cost: usize) -> Option<usize> {
+        self.edge_cost_override.as_ref().map(|cost_override| cost_override(parent, change, cost))
+    }
+
+    // Open list priority of the state of `key` reached through `change`, under the current weight.
+    pub(crate) fn priority(&self, key: &K, f: usize, h: usize, weight: f64, change: Option<&C>) -> usize {
+        let mut primary = if self.greedy { h } else { weight_schedule::inflated_f(f, h, weight) };
+
+        if let Some(hint) = &self.priority_hint {
+            let half = (PRIORITY_HINT_SLOTS / 2) as i64;
+            let slot = (half - 1 - i64::from(hint(key)).clamp(-half, half - 1)) as usize;
+            primary = primary.saturating_mul(PRIORITY_HINT_SLOTS).saturating_add(slot);
+        }
+
+        match &self.tie_break {
+            Some(tie_break) => {
+                let tie = change.map_or(0, |change| tie_break(change).min(TIE_BREAK_SLOTS - 1));
+                primary.saturating_mul(TIE_BREAK_SLOTS).saturating_add(tie)
+            }
+            None => primary
+        }
+    }
+
+    pub(crate) fn is_redundant_order(&self, parent_change: Option<&C>, change: Option<&C>) -> bool {
+        match (self.redundant_order, parent_change, change) {
+            (Some(redundant_order), Some(parent_change), Some(change)) => redundant_order(parent_change, change),
+            _ => false
+        }
+    }
+
+    pub(crate) fn recording(&mut self) -> Option<&mut Recording<K>> {
+        self.recording.as_deref_mut()
+    }
+
+    // Weight the schedule asks for, clamped, and whether it had to be. None without a schedule.
+    pub(crate) fn scheduled_weight(&mut self, context: &ScheduleContext) -> Option<(f64, bool)> {
+        let (_, schedule) = self.weight_schedule.as_mut()?;
+        Some(weight_schedule::clamp(schedule(context)))
+    }
+
+    pub(crate) fn notify_plateau(&mut self, detected: &PlateauDetected) {
+        if let Some(observer) = &mut self.plateau_observer {
+            observer(detected);
+        }
+    }
+
+    // Test hook: after the given expansion the search breaks the index entry of its open list
+    // minimum, to check that the corruption is reported instead of panicking.
+    #[cfg(test)]
+    pub(crate) fn with_corrupt_open_list_after(mut self, expansions: usize) -> Self {
+        self.corrupt_open_list_after = Some(expansions);
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn corrupt_after_expansion<V>(&self, expansions: usize, open_list: &mut crate::open_list::OpenList<K, V>)
+    where
+        V: crate::astar_state::AStarState<K>
+    {
+        if self.corrupt_open_list_after == Some(expansions)
+            && let Some(key) = open_list.key_at(0).cloned()
+        {
+            open_list.corrupt_index(&key, usize::MAX);
+        }
+    }
+
+    // Test hook: adds to the cost of the last step of the result, the kind of accounting bug the
+    // cost verification is there to catch.
+    #[cfg(test)]
+    pub(crate) fn with_miscounted_step(mut self, extra: usize) -> Self {
+        self.miscounted_step = Some(extra);
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn miscount(&self, result: &mut crate::traced::result::TracedResult<S, K, C>)
+    where
+        S: crate::traced::state::TracedState<K, C>
+    {
+        if let (Some(extra), Some(last)) = (self.miscounted_step, result.step_costs.as_mut().and_then(|steps| steps.last_mut())) {
+            *last += extra;
+        }
+    }
+
+    pub(crate) fn discard(&mut self, state: S) {
+        if let Some(recycler) = &mut self.recycler {
+            recycler.recycle(state);
+        }
+    }
+}
+
+impl<S, K, C> Default for SearchOptions<'_, S, K, C>
+where
+    K: Clone + Eq + Hash
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, ptr};
+
+    use super::*;
+    use crate::{search_stats::SkipReason, state_pool::StatePool, test_support::{Lattice, Step}, traced::{path::FieldlessCodec, traced_astar::traced_astar_with_options}};
+
+    type LatticeOptions<'a> = SearchOptions<'a, Lattice, [usize; 3], Step>;
+
+    #[test]
+    fn test_defaults() {
+        let options = LatticeOptions::default();
+
+        assert_eq!((options.f_bound(), options.max_expansions(), options.expansion_limit()), (None, None, None));
+        assert!(options.shared_budget().is_none());
+        assert!(!options.has_forbidden() && !options.has_known_unreachable() && !options.has_recycler() && options.change_codec().is_none());
+        assert!(!options.pruning_by_depth() && !options.greedy() && !options.has_tie_break() && !options.has_priority_hint() && !options.reopening());
+        assert!(!options.visit_counts() && !options.auto_reserve() && !options.commutative_pruning() && !options.has_recording());
+        assert!(options.edge_cost_bounds().is_none() && options.plateau_detection().is_none() && !options.has_plateau_observer());
+        assert!(options.weight_schedule_interval().is_none() && options.max_generation_time().is_none() && !options.has_edge_cost_override());
+        assert_eq!(options.validation(), ValidationLevel::Off);
+        assert!(!options.consistent_heuristic() && !options.has_node_estimator() && !options.has_seed_nodes() && options.soft_open_limit().is_none());
+        assert!(options.forced_successors() && !options.forced_check() && options.state_space_bound().is_none());
+        assert_eq!(options.min_coverage(), DEFAULT_MIN_COVERAGE);
+        assert!(!options.auto_incumbent() && options.successor_cache_capacity().is_none() && !options.has_key_labeler());
+        assert_eq!(options.cost_verification(), cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn test_every_setter_is_read_back() {
+        let mut pool = StatePool::new(4);
+        let mut recording = Recording::new();
+        let budget = SharedBudget::new(100);
+        let unreachable = UnreachabilitySet::new(HashSet::from([[1, 1, 1]]), "corner".to_string());
+
+        let options = LatticeOptions::new()
+            .with_f_bound(9)
+            .with_forbidden(|key| key[0] == 2)
+            .with_pruning_by_depth(true)
+            .with_recycler(&mut pool)
+            .with_change_codec(Rc::new(FieldlessCodec::new(&[Step(0), Step(1), Step(2)])))
+            .with_greedy(true)
+            .with_tie_break(|step| step.0)
+            .with_priority_hint(|key| key[1] as i32)
+            .with_reopening(true)
+            .with_successor_cache(16, |parent, _| parent.clone())
+            .with_visit_counts(true)
+            .with_auto_reserve(true)
+            .with_edge_cost_bounds(1, 2)
+            .with_plateau_detection(10, PlateauPolicy::BreadthFirstBurst(3))
+            .with_plateau_observer(|_| {})
+            .with_commutative_pruning()
+            .with_recording(&mut recording)
+            .with_weight_schedule(50, |_| 1.5)
+            .with_max_generation_time(Duration::from_millis(20))
+            .with_edge_cost_override(|_, _, cost| cost)
+            .with_validation(ValidationLevel::Warn)
+            .with_consistent_heuristic(true)
+            .with_expansion_limit(LimitSpec::Relative { factor: 2.0 })
+            .with_node_estimator(|_| 30.0)
+            .with_shared_budget(&budget)
+            .with_seed_nodes(vec![(Lattice::new(3), 5)])
+            .with_soft_open_limit(100, 0.5)
+            .with_forced_successors(false)
+            .with_forced_check(true)
+            .with_state_space_bound(27)
+            .with_min_coverage(0.9)
+            .with_auto_incumbent(true)
+            .with_known_unreachable(&unreachable, "corner")
+            .unwrap()
+            .with_key_labeler(|key| format!("{:?}", key))
+            .with_cost_verification(true);
+
+        assert_eq!(options.f_bound(), Some(9));
+        assert!(options.has_forbidden() && options.is_forbidden(&[2, 0, 0]) && !options.is_forbidden(&[1, 0, 0]));
+        assert!(options.pruning_by_depth() && options.has_recycler() && options.change_codec().is_some());
+        assert!(options.greedy() && options.has_tie_break() && options.has_priority_hint() && options.reopening());
+        assert_eq!(options.successor_cache_capacity(), Some(16));
+        assert!(options.visit_counts() && options.auto_reserve());
+        assert_eq!(options.edge_cost_bounds(), Some(EdgeCostBounds { min: 1, max: 2 }));
+        assert_eq!(options.plateau_detection(), Some((10, PlateauPolicy::BreadthFirstBurst(3))));
+        assert!(options.has_plateau_observer() && options.commutative_pruning() && options.has_recording());
+        assert_eq!(options.weight_schedule_interval(), Some(50));
+        assert_eq!(options.max_generation_time(), Some(Duration::from_millis(20)));
+        assert!(options.has_edge_cost_override() && options.consistent_heuristic());
+        assert_eq!(options.validation(), ValidationLevel::Warn);
+        assert_eq!((options.expansion_limit(), options.max_expansions()), (Some(LimitSpec::Relative { factor: 2.0 }), None));
+        assert!(options.has_node_estimator() && options.shared_budget().is_some_and(|shared| ptr::eq(shared, &budget)));
+        assert!(options.has_seed_nodes());
+        assert_eq!(options.soft_open_limit(), Some((100, 0.5)));
+        assert!(!options.forced_successors() && options.forced_check());
+        assert_eq!((options.state_space_bound(), options.min_coverage()), (Some(27), 0.9));
+        assert!(options.auto_incumbent() && options.has_known_unreachable() && options.is_known_unreachable(&[1, 1, 1]));
+        assert!(options.has_key_labeler() && options.cost_verification());
+
+        // with_weight is a schedule that is never asked again, and a plain count an absolute limit.
+        let options = LatticeOptions::new().with_weight(2.0).with_expansion_limit(40);
+        assert_eq!(options.weight_schedule_interval(), Some(usize::MAX));
+        assert_eq!((options.expansion_limit(), options.max_expansions()), (Some(LimitSpec::Absolute(40)), Some(40)));
+    }
+
+    #[test]
+    fn test_setters_change_the_search() {
+        // 6 moves to the far corner, by 90 different orders of the same steps.
+        let run = |options: LatticeOptions| traced_astar_with_options(Lattice::new(3), options);
+
+        let plain = run(SearchOptions::new());
+        assert_eq!(plain.result.as_ref().map(|result| result.cost), Some(6));
+        assert!(plain.stats.visits.is_none() && plain.stats.pruned_by_depth.is_none());
+
+        let cut = run(SearchOptions::new().with_max_expansions(5));
+        assert!(cut.result.is_none() && cut.budget_exhausted);
+        assert_eq!(cut.stats.expansion_limit.map(|resolved| resolved.limit), Some(5));
+
+        let bounded = run(SearchOptions::new().with_f_bound(5));
+        assert!(bounded.result.is_none() && bounded.stats.pruned[SkipReason::FBound] > 0);
+
+        let forbidden = run(SearchOptions::new().with_forbidden(|key: &[usize; 3]| key[0] == 1 && key[1] == 0));
+        assert!(forbidden.result.is_some() && forbidden.stats.pruned[SkipReason::Forbidden] > 0);
+
+        let commuted = run(SearchOptions::new().with_commutative_pruning());
+        assert!(commuted.result.is_some() && commuted.stats.pruned[SkipReason::Commuted] > 0);
+
+        let tracked = run(SearchOptions::new().with_visit_counts(true).with_pruning_by_depth(true));
+        assert!(tracked.stats.visits.is_some() && tracked.stats.pruned_by_depth.is_some());
+
+        let budget = SharedBudget::new(1000);
+        let shared = run(SearchOptions::new().with_shared_budget(&budget));
+        assert_eq!(budget.spent(), shared.stats.expansions);
+    }
+}
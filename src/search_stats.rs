@@ -0,0 +1,396 @@
+use std::{collections::HashMap, fmt::{Debug, Display}, hash::Hash, ops::{Index, IndexMut}, time::Duration};
+
+use crate::{expansion_limit::ResolvedLimit, fingerprint::TraceFingerprint, search_id::SearchId, validation::Anomaly, weight_schedule::WeightChange};
+
+// Why a generated successor (or, for DeadEnd, an expanded state) went nowhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SkipReason {
+    // The key was already expanded.
+    Closed,
+    // The key was already in the open list with the same or a lower f.
+    WorseOpen,
+    // f was above the configured f bound.
+    FBound,
+    // The key was rejected by the configured forbidden predicate.
+    Forbidden,
+    // The expanded state had no successors.
+    DeadEnd,
+    // The change is the redundant order of a commuting pair (`SearchOptions::with_commutative_pruning`).
+    Commuted,
+    // The open list was over its soft limit and f wasn't below the admission percentile
+    // (`SearchOptions::with_soft_open_limit`).
+    SoftRejected
+}
+
+impl SkipReason {
+    pub const ALL: [SkipReason; 7] = [
+        SkipReason::Closed,
+        SkipReason::WorseOpen,
+        SkipReason::FBound,
+        SkipReason::Forbidden,
+        SkipReason::DeadEnd,
+        SkipReason::Commuted,
+        SkipReason::SoftRejected
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkipReason::Closed => "closed",
+            SkipReason::WorseOpen => "worse_open",
+            SkipReason::FBound => "f_bound",
+            SkipReason::Forbidden => "forbidden",
+            SkipReason::DeadEnd => "dead_end",
+            SkipReason::Commuted => "commuted",
+            SkipReason::SoftRejected => "soft_rejected"
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneCounts {
+    counts: [usize; SkipReason::ALL.len()]
+}
+
+impl PruneCounts {
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+impl Index<SkipReason> for PruneCounts {
+    type Output = usize;
+
+    fn index(&self, reason: SkipReason) -> &usize {
+        &self.counts[reason.index()]
+    }
+}
+
+impl IndexMut<SkipReason> for PruneCounts {
+    fn index_mut(&mut self, reason: SkipReason) -> &mut usize {
+        &mut self.counts[reason.index()]
+    }
+}
+
+// What happened to one key over the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyVisits {
+    // Open entries of the key replaced by a cheaper path.
+    pub reinserts: u32,
+    // Times the key was moved from the closed list back to the open list.
+    pub reopenings: u32,
+    pub expansions: u32,
+    // Best g the key was reached with.
+    pub g: usize,
+    // Order in which the keys were first seen, for stable reports.
+    first_seen: usize
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thrasher<K> {
+    pub key: K,
+    pub reinserts: u32,
+    pub reopenings: u32,
+    pub final_g: usize
+}
+
+impl<K> Thrasher<K> {
+    pub(crate) fn write_with(&self, f: &mut std::fmt::Formatter<'_>, key: impl Fn(&K) -> String) -> std::fmt::Result {
+        write!(f, "{}: {} reinserts, {} reopenings, final g {}", key(&self.key), self.reinserts, self.reopenings, self.final_g)
+    }
+}
+
+impl<K> Display for Thrasher<K>
+where
+    K: Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_with(f, |key| format!("{key:?}"))
+    }
+}
+
+// Per-key counts filled when `SearchOptions::with_visit_counts` is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitCounts<K>
+where
+    K: Eq + Hash
+{
+    visits: HashMap<K, KeyVisits>
+}
+
+impl<K> VisitCounts<K>
+where
+    K: Clone + Eq + Hash
+{
+    pub fn get(&self, key: &K) -> Option<&KeyVisits> {
+        self.visits.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.visits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.visits.is_empty()
+    }
+
+    // Expansions of keys that had been expanded before. A key is only expanded again after being
+    // reopened, so each of them redoes work an earlier expansion did with a worse g.
+    pub fn wasted_expansions(&self) -> usize {
+        self.visits.values()
+            .map(|visits| visits.expansions.saturating_sub(1) as usize)
+            .sum()
+    }
+
+    // The `n` keys with the most reinserts plus reopenings, worst first. Keys that were never
+    // reinserted nor reopened are left out, ties keep the order in which keys were first seen.
+    pub fn top_thrashers(&self, n: usize) -> Vec<Thrasher<K>> {
+        let mut thrashers: Vec<(&K, &KeyVisits)> = self.visits.iter()
+            .filter(|(_, visits)| visits.reinserts + visits.reopenings > 0)
+            .collect();
+        thrashers.sort_by_key(|(_, visits)| (std::cmp::Reverse(visits.reinserts + visits.reopenings), visits.first_seen));
+
+        thrashers.into_iter()
+            .take(n)
+            .map(|(key, visits)| Thrasher {
+                key: key.clone(),
+                reinserts: visits.reinserts,
+                reopenings: visits.reopenings,
+                final_g: visits.g
+            })
+            .collect()
+    }
+
+    fn entry(&mut self, key: &K, g: usize) -> &mut KeyVisits {
+        let first_seen = self.visits.len();
+        let visits = self.visits.entry(key.clone()).or_insert(KeyVisits {
+            reinserts: 0,
+            reopenings: 0,
+            expansions: 0,
+            g,
+            first_seen
+        });
+        visits.g = visits.g.min(g);
+        visits
+    }
+}
+
+// Something the search worked around instead of stopping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SearchWarning<K> {
+    // Generating the successors of `key` took longer than `SearchOptions::with_max_generation_time`,
+    // so they were dropped and the state counted as a dead end.
+    GenerationTimedOut { key: K, elapsed: Duration },
+    // An edge found by `SearchOptions::with_validation` at ValidationLevel::Warn.
+    Anomaly(Anomaly<K>),
+    // The search ran out of states after closing fewer than `SearchOptions::with_min_coverage` of
+    // the `bound` given by `SearchOptions::with_state_space_bound`. Unless the options pruned them
+    // on purpose, the missing states hint at a generator that drops moves.
+    SuspiciousExhaustion { closed: usize, bound: usize },
+    // `SearchOptions::with_expansion_limit` was relative, but no `with_node_estimator` was given or
+    // it returned NaN. The search ran without a limit of its own.
+    UnresolvedLimit,
+    // The weight schedule asked for a weight below 1, or NaN, after `expansion` expansions, and got
+    // 1 instead. The matching `WeightChange` is flagged `clamped`.
+    WeightClamped { expansion: usize }
+}
+
+impl<K> SearchWarning<K> {
+    // The keys the warning is about.
+    pub fn keys(&self) -> Vec<&K> {
+        match self {
+            SearchWarning::GenerationTimedOut { key, .. } => vec![key],
+            SearchWarning::Anomaly(anomaly) => anomaly.keys().to_vec(),
+            SearchWarning::SuspiciousExhaustion { .. } | SearchWarning::UnresolvedLimit | SearchWarning::WeightClamped { .. } => Vec::new()
+        }
+    }
+
+    pub(crate) fn write_with(&self, f: &mut std::fmt::Formatter<'_>, key: impl Fn(&K) -> String) -> std::fmt::Result {
+        match self {
+            SearchWarning::GenerationTimedOut { key: timed_out, elapsed } => {
+                write!(f, "generating the successors of {} took {:?}, they were dropped", key(timed_out), elapsed)
+            }
+            SearchWarning::Anomaly(anomaly) => anomaly.write_with(f, key),
+            SearchWarning::SuspiciousExhaustion { closed, bound } => {
+                write!(f, "ran out of states after closing {} of about {}", closed, bound)
+            }
+            SearchWarning::UnresolvedLimit => write!(f, "the relative expansion limit had no estimate, so no limit was set"),
+            SearchWarning::WeightClamped { expansion } => {
+                write!(f, "the weight schedule asked for a weight below 1 after {} expansions, 1 was used", expansion)
+            }
+        }
+    }
+}
+
+impl<K> Display for SearchWarning<K>
+where
+    K: Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_with(f, |key| format!("{key:?}"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchStats<K>
+where
+    K: Eq + Hash
+{
+    // The search these stats were collected by.
+    pub search_id: SearchId,
+    pub expansions: usize,
+    pub generated: usize,
+    // Open entries replaced by a cheaper path to the same key.
+    pub improved: usize,
+    // Closed states moved back to the open list (`SearchOptions::with_reopening`).
+    pub reopened: usize,
+    // Expansions served by the successor cache instead of the generator.
+    pub cache_hits: usize,
+    // Expansions that inserted the state's forced successor instead of generating them all, see
+    // TracedState::forced_successor. Counted in `expansions` too.
+    pub forced: usize,
+    // Successors cut off by the bound of a depth-first search, the threshold of ida_star or the
+    // incumbent of dfbb, and how many of them were the first child their parent descended into,
+    // which tells how well the ChildOrder does.
+    pub cutoffs: usize,
+    pub first_child_cutoffs: usize,
+    // Successors that cost nothing to reach. Only counted when `SearchOptions::with_edge_cost_bounds`
+    // is set; a search with many of them may wander plateaus for a long time.
+    pub zero_cost_edges: usize,
+    // Plateaus reported and the most expansions in a row without improving h. Only tracked by
+    // greedy searches with `SearchOptions::with_plateau_detection`.
+    pub plateaus: usize,
+    pub largest_plateau: usize,
+    // Most entries the open list held at once. Only tracked by traced_astar_with_options.
+    pub peak_open: usize,
+    // Cost of the path found by the greedy pass of `SearchOptions::with_auto_incumbent`, None if
+    // it found none or didn't run, and the expansions it took. They aren't counted in
+    // `expansions`.
+    pub incumbent_cost: Option<usize>,
+    pub incumbent_expansions: usize,
+    // See `SearchOptions::with_expansion_limit`. None when the search ran without a limit.
+    pub expansion_limit: Option<ResolvedLimit>,
+    pub pruned: PruneCounts,
+    // Every weight set by `SearchOptions::with_weight_schedule`, in order.
+    pub weight_changes: Vec<WeightChange>,
+    pub warnings: Vec<SearchWarning<K>>,
+    // Same counts split by the depth of the skipped successor (or of the dead end itself).
+    // Only filled when `SearchOptions::with_pruning_by_depth` is enabled.
+    pub pruned_by_depth: Option<Vec<PruneCounts>>,
+    // Only filled when `SearchOptions::with_visit_counts` is enabled.
+    pub visits: Option<VisitCounts<K>>,
+    // Expansion order of the run, see `SearchOutcome::trace_fingerprint`.
+    pub trace_fingerprint: TraceFingerprint
+}
+
+impl<K> Default for SearchStats<K>
+where
+    K: Eq + Hash
+{
+    fn default() -> Self {
+        SearchStats {
+            search_id: SearchId::next(),
+            expansions: 0,
+            generated: 0,
+            improved: 0,
+            reopened: 0,
+            cache_hits: 0,
+            forced: 0,
+            cutoffs: 0,
+            first_child_cutoffs: 0,
+            zero_cost_edges: 0,
+            plateaus: 0,
+            largest_plateau: 0,
+            peak_open: 0,
+            incumbent_cost: None,
+            incumbent_expansions: 0,
+            expansion_limit: None,
+            pruned: PruneCounts::default(),
+            weight_changes: Vec::new(),
+            warnings: Vec::new(),
+            pruned_by_depth: None,
+            visits: None,
+            trace_fingerprint: TraceFingerprint::new()
+        }
+    }
+}
+
+impl<K> SearchStats<K>
+where
+    K: Clone + Eq + Hash
+{
+    pub(crate) fn new(track_pruning_by_depth: bool, track_visits: bool) -> Self {
+        SearchStats {
+            pruned_by_depth: track_pruning_by_depth.then(Vec::new),
+            visits: track_visits.then(|| VisitCounts { visits: HashMap::new() }),
+            ..Default::default()
+        }
+    }
+
+    // See `VisitCounts::top_thrashers`. Empty when visit counts were not tracked.
+    pub fn top_thrashers(&self, n: usize) -> Vec<Thrasher<K>> {
+        self.visits.as_ref().map_or_else(Vec::new, |visits| visits.top_thrashers(n))
+    }
+
+    // See `VisitCounts::wasted_expansions`. 0 when visit counts were not tracked.
+    pub fn wasted_expansions(&self) -> usize {
+        self.visits.as_ref().map_or(0, |visits| visits.wasted_expansions())
+    }
+
+    pub(crate) fn record_insert(&mut self, key: &K, g: usize, reinsert: bool) {
+        if let Some(visits) = &mut self.visits {
+            visits.entry(key, g).reinserts += u32::from(reinsert);
+        }
+    }
+
+    pub(crate) fn record_reopening(&mut self, key: &K, g: usize) {
+        if let Some(visits) = &mut self.visits {
+            visits.entry(key, g).reopenings += 1;
+        }
+    }
+
+    pub(crate) fn record_expansion(&mut self, key: &K, g: usize) {
+        if let Some(visits) = &mut self.visits {
+            visits.entry(key, g).expansions += 1;
+        }
+    }
+
+    pub(crate) fn record_pruned(&mut self, reason: SkipReason, depth: usize) {
+        self.pruned[reason] += 1;
+
+        if let Some(by_depth) = &mut self.pruned_by_depth {
+            if by_depth.len() <= depth {
+                by_depth.resize(depth + 1, PruneCounts::default());
+            }
+            by_depth[depth][reason] += 1;
+        }
+    }
+
+    // One row per depth with a column per skip reason. Empty apart from the header when the
+    // per-depth matrix was not tracked.
+    pub fn pruning_to_csv(&self) -> String {
+        let mut csv = String::from("depth");
+        for reason in SkipReason::ALL {
+            csv.push(',');
+            csv.push_str(reason.name());
+        }
+        csv.push('\n');
+
+        for (depth, counts) in self.pruned_by_depth.iter().flatten().enumerate() {
+            csv.push_str(&depth.to_string());
+            for reason in SkipReason::ALL {
+                csv.push(',');
+                csv.push_str(&counts[reason].to_string());
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
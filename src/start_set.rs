@@ -0,0 +1,164 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, priority_queue::Insertion, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::build_result}, untraced::{result::UntracedResult, state::UntracedState}};
+
+// A search result along with the index of the initial state its path starts from.
+#[derive(Debug, Clone)]
+pub struct StartChosen<R> {
+    pub result: R,
+    pub start: usize
+}
+
+// traced_astar from all of the initial states at once, each with its own g, to the cheapest goal
+// reachable from any of them. Starts with the same key are one start, the one with the lowest f,
+// or the first of them on a tie. None when there are no starts.
+pub fn traced_astar_from_any<S, K, C>(initial_states: Vec<S>) -> Option<StartChosen<TracedResult<S, K, C>>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut open_list = OpenList::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let mut starts = HashMap::new();
+
+    for (index, initial_state) in initial_states.into_iter().enumerate() {
+        let key = initial_state.key();
+        if !matches!(open_list.insert(key.clone(), TracedStateWrapper::new(initial_state)), Insertion::Rejected(_)) {
+            starts.insert(key, index);
+        }
+    }
+
+    while let Some(current_state) = open_list.extract_min() {
+        if current_state.is_goal() {
+            // Every path goes back to a start, the only states without a parent.
+            let mut root = &current_state;
+            while let Some(prev_key) = &root.prev_key {
+                root = &closed_list[prev_key];
+            }
+            let start = starts[&root.key()];
+            return Some(StartChosen { result: build_result(current_state, closed_list), start });
+        }
+
+        let successors = current_state.generate_states();
+
+        closed_list.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+
+            if closed_list.contains_key(&successor_key) {
+                continue;
+            }
+
+            open_list.insert(successor_key, successor);
+        }
+    }
+
+    None
+}
+
+// untraced_astar from all of the initial states at once, with the same rules for starts as
+// traced_astar_from_any. Each state carries the index of the start it descends from.
+pub fn untraced_astar_from_any<S, K>(initial_states: Vec<S>) -> Option<StartChosen<UntracedResult<S, K>>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    let mut open_list = OpenList::new();
+    let mut closed_list = HashSet::new();
+
+    for (start, state) in initial_states.into_iter().enumerate() {
+        open_list.insert(state.key(), FromStart { state, start });
+    }
+
+    while let Some(FromStart { state: current_state, start }) = open_list.extract_min() {
+        if current_state.is_goal() {
+            let iterations = closed_list.len();
+            return Some(StartChosen { result: UntracedResult::new(iterations, current_state), start });
+        }
+
+        closed_list.insert(current_state.key());
+
+        for successor in current_state.generate_successors() {
+            let successor_key = successor.key();
+
+            if closed_list.contains(&successor_key) {
+                continue;
+            }
+
+            open_list.insert(successor_key, FromStart { state: successor, start });
+        }
+    }
+
+    None
+}
+
+struct FromStart<S> {
+    state: S,
+    start: usize
+}
+
+impl<K, S> AStarState<K> for FromStart<S>
+where
+    K: Clone + Eq + Hash,
+    S: AStarState<K>
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.state.h()
+    }
+
+    fn f(&self) -> usize {
+        self.state.f()
+    }
+
+    fn g(&self) -> usize {
+        self.state.g()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.state.is_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H};
+
+    fn at(node: usize, g_cost: usize) -> GraphState {
+        GraphState { node, g_cost, ..GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7) }
+    }
+
+    #[test]
+    fn test_nearer_start_wins() {
+        // 0 is 9 from the goal, 6 only 1.
+        let traced = traced_astar_from_any(vec![at(0, 0), at(6, 0)]).unwrap();
+        assert_eq!((traced.start, traced.result.cost, traced.result.path.to_vec()), (1, 1, vec![7]));
+
+        let untraced = untraced_astar_from_any(vec![at(0, 0), at(6, 0)]).unwrap();
+        assert_eq!((untraced.start, untraced.result.final_state.g()), (1, 1));
+
+        // A start's own g counts: 6 at 10 loses to 0 at 0.
+        let traced = traced_astar_from_any(vec![at(0, 0), at(6, 10)]).unwrap();
+        assert_eq!((traced.start, traced.result.cost), (0, 9));
+        assert!(!traced.result.path.to_vec().contains(&6));
+    }
+
+    #[test]
+    fn test_duplicate_starts_keep_the_cheapest() {
+        let starts = || vec![at(3, 5), at(0, 0), at(3, 0)];
+
+        let traced = traced_astar_from_any(starts()).unwrap();
+        assert_eq!((traced.start, traced.result.cost), (2, 8));
+        assert!(!traced.result.path.to_vec().contains(&0));
+
+        let untraced = untraced_astar_from_any(starts()).unwrap();
+        assert_eq!((untraced.start, untraced.result.final_state.g()), (2, 8));
+
+        assert!(traced_astar_from_any(Vec::<GraphState>::new()).is_none());
+    }
+}
@@ -0,0 +1,75 @@
+use std::{cell::RefCell, rc::Rc};
+
+// Receives the states a search throws away (duplicates, pruned successors, open entries replaced
+// by a better path) instead of dropping them, so their buffers can be reused.
+pub trait Recycler<S> {
+    fn recycle(&mut self, state: S);
+}
+
+// Simple object pool. Successor generators `acquire` a previously discarded state to overwrite
+// instead of allocating a new one. Since the generator and the search both need the pool, it is
+// usually shared as `Rc<RefCell<StatePool<S>>>`, which also implements `Recycler`.
+pub struct StatePool<S> {
+    free: Vec<S>,
+    capacity: usize
+}
+
+impl<S> StatePool<S> {
+    pub fn new(capacity: usize) -> Self {
+        StatePool {
+            free: Vec::new(),
+            capacity
+        }
+    }
+
+    pub fn acquire(&mut self) -> Option<S> {
+        self.free.pop()
+    }
+
+    // Drops the state if the pool is already full.
+    pub fn release(&mut self, state: S) {
+        if self.free.len() < self.capacity {
+            self.free.push(state);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+}
+
+impl<S> Recycler<S> for StatePool<S> {
+    fn recycle(&mut self, state: S) {
+        self.release(state);
+    }
+}
+
+impl<S> Recycler<S> for Rc<RefCell<StatePool<S>>> {
+    fn recycle(&mut self, state: S) {
+        self.borrow_mut().release(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire_release() {
+        let mut pool = StatePool::new(2);
+        assert_eq!(pool.acquire(), None::<Vec<u8>>);
+
+        pool.release(vec![1]);
+        pool.recycle(vec![2]);
+        pool.release(vec![3]);
+
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.acquire(), Some(vec![2]));
+        assert_eq!(pool.acquire(), Some(vec![1]));
+        assert!(pool.is_empty());
+    }
+}
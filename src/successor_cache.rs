@@ -0,0 +1,228 @@
+use std::{collections::{BTreeMap, HashMap}, hash::Hash};
+
+// What is remembered of a generated successor: enough to rebuild it from its parent without
+// calling the generator again, but not the successor state itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedEdge<K, C> {
+    pub child_key: K,
+    pub edge_cost: usize,
+    pub change: C
+}
+
+// Successor lists by parent key, holding at most `capacity` parents and evicting the least
+// recently used one when full.
+pub struct SuccessorCache<K, C>
+where
+    K: Clone + Eq + Hash
+{
+    entries: HashMap<K, (Vec<CachedEdge<K, C>>, u64)>,
+    recency: BTreeMap<u64, K>,
+    next_stamp: u64,
+    capacity: usize,
+    evictions: usize
+}
+
+impl<K, C> SuccessorCache<K, C>
+where
+    K: Clone + Eq + Hash
+{
+    pub fn new(capacity: usize) -> Self {
+        SuccessorCache {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_stamp: 0,
+            capacity,
+            evictions: 0
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    // Marks the entry as the most recently used one.
+    pub fn get(&mut self, key: &K) -> Option<&[CachedEdge<K, C>]> {
+        let stamp = self.next_stamp;
+        let (edges, last_used) = self.entries.get_mut(key)?;
+
+        self.recency.remove(last_used);
+        self.recency.insert(stamp, key.clone());
+        *last_used = stamp;
+        self.next_stamp += 1;
+
+        Some(edges)
+    }
+
+    pub fn insert(&mut self, key: K, edges: Vec<CachedEdge<K, C>>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.recency.remove(&last_used);
+        }
+        else if self.entries.len() == self.capacity && let Some((_, oldest)) = self.recency.pop_first() {
+            self.entries.remove(&oldest);
+            self.evictions += 1;
+        }
+
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+
+        self.recency.insert(stamp, key.clone());
+        self.entries.insert(key, (edges, stamp));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{astar_state::AStarState, search_options::SearchOptions, test_support::GraphState, traced::{state::TracedState, traced_astar::traced_astar_with_options}};
+
+    fn edge(child_key: usize) -> CachedEdge<usize, usize> {
+        CachedEdge {
+            child_key,
+            edge_cost: 1,
+            change: child_key
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = SuccessorCache::new(2);
+        cache.insert(1, vec![edge(10)]);
+        cache.insert(2, vec![edge(20)]);
+
+        assert!(cache.get(&1).is_some());
+        cache.insert(3, vec![edge(30)]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.evictions(), 1);
+        assert!(cache.get(&2).is_none());
+        assert_eq!(cache.get(&1), Some(&[edge(10)][..]));
+        assert_eq!(cache.get(&3), Some(&[edge(30)][..]));
+    }
+
+    #[test]
+    fn test_reinserting_a_key_does_not_evict() {
+        let mut cache = SuccessorCache::new(2);
+        cache.insert(1, vec![edge(10)]);
+        cache.insert(2, vec![edge(20)]);
+        cache.insert(1, vec![edge(11)]);
+
+        assert_eq!(cache.evictions(), 0);
+        assert_eq!(cache.get(&1), Some(&[edge(11)][..]));
+
+        let mut disabled = SuccessorCache::new(0);
+        disabled.insert(1, vec![edge(10)]);
+        assert!(disabled.is_empty());
+    }
+
+    // Generator that logs the node of every state it expands.
+    #[derive(Clone)]
+    struct CountingState {
+        inner: GraphState,
+        expanded: Rc<RefCell<Vec<usize>>>
+    }
+
+    impl AStarState<usize> for CountingState {
+        fn key(&self) -> usize {
+            self.inner.key()
+        }
+
+        fn h(&self) -> usize {
+            self.inner.h()
+        }
+
+        fn f(&self) -> usize {
+            self.inner.f()
+        }
+
+        fn g(&self) -> usize {
+            self.inner.g()
+        }
+
+        fn is_goal(&self) -> bool {
+            self.inner.is_goal()
+        }
+    }
+
+    impl TracedState<usize, usize> for CountingState {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            self.expanded.borrow_mut().push(self.inner.node);
+
+            self.inner.generate_traced_successors()
+                .into_iter()
+                .map(|(inner, change)| (CountingState { inner, expanded: self.expanded.clone() }, change))
+                .collect()
+        }
+    }
+
+    // h(1) = 6 is admissible but inconsistent: 2 is closed through 0 -> 2 before the cheaper
+    // 0 -> 1 -> 2 is found, so 2 is reopened and expanded twice.
+    const REOPENING: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 3)],
+        &[(2, 1)],
+        &[(3, 5)],
+        &[]
+    ];
+    const REOPENING_H: &[usize] = &[0, 6, 0, 0];
+
+    fn rebuild(parent: &CountingState, edge: &CachedEdge<usize, usize>) -> CountingState {
+        CountingState {
+            inner: GraphState {
+                node: edge.change,
+                g_cost: parent.inner.g_cost + edge.edge_cost,
+                ..parent.inner.clone()
+            },
+            expanded: parent.expanded.clone()
+        }
+    }
+
+    fn search(cached: bool) -> (usize, Vec<usize>, usize, usize) {
+        let expanded = Rc::new(RefCell::new(Vec::new()));
+        let start = CountingState {
+            inner: GraphState::new(REOPENING, REOPENING_H, 0, 3),
+            expanded: expanded.clone()
+        };
+
+        let mut options = SearchOptions::new().with_reopening(true);
+        if cached {
+            options = options.with_successor_cache(16, rebuild);
+        }
+        let outcome = traced_astar_with_options(start, options);
+
+        let cost = outcome.result.unwrap().final_state.g();
+        let expanded = expanded.borrow().clone();
+        (cost, expanded, outcome.stats.reopened, outcome.stats.cache_hits)
+    }
+
+    #[test]
+    fn test_reexpansion_skips_the_generator() {
+        let (cost, expanded, reopened, cache_hits) = search(false);
+        assert_eq!(cost, 7);
+        assert_eq!(expanded, vec![0, 2, 1, 2]);
+        assert_eq!(reopened, 1);
+        assert_eq!(cache_hits, 0);
+
+        let (cost, expanded, reopened, cache_hits) = search(true);
+        assert_eq!(cost, 7);
+        assert_eq!(expanded, vec![0, 2, 1]);
+        assert_eq!(reopened, 1);
+        assert_eq!(cache_hits, 1);
+    }
+}
@@ -0,0 +1,388 @@
+use crate::{astar_state::AStarState, rng::SplitMix64, traced::{commutative::CommutativeChanges, state::TracedState}, untraced::state::UntracedState};
+#[cfg(feature = "grid")]
+use crate::{grid::{map::GridMap, movement::MovementModel, region::GoalRegion, state::GridState}, traced::{replay::replay, result::TracedResult, traced_astar::traced_astar}};
+
+// Small weighted graphs given as adjacency lists of (target, cost), with a heuristic value per node.
+// Traced successors report the node they move to as the change.
+#[derive(Clone, Debug)]
+pub struct GraphState {
+    pub node: usize,
+    pub g_cost: usize,
+    pub edges: &'static [&'static [(usize, usize)]],
+    pub h_values: &'static [usize],
+    pub goal: usize
+}
+
+impl GraphState {
+    pub fn new(edges: &'static [&'static [(usize, usize)]], h_values: &'static [usize], start: usize, goal: usize) -> Self {
+        GraphState {
+            node: start,
+            g_cost: 0,
+            edges,
+            h_values,
+            goal
+        }
+    }
+
+    fn successor(&self, target: usize, cost: usize) -> Self {
+        GraphState {
+            node: target,
+            g_cost: self.g_cost + cost,
+            ..self.clone()
+        }
+    }
+}
+
+impl AStarState<usize> for GraphState {
+    fn key(&self) -> usize {
+        self.node
+    }
+
+    fn h(&self) -> usize {
+        self.h_values[self.node]
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g_cost
+    }
+
+    fn is_goal(&self) -> bool {
+        self.node == self.goal
+    }
+}
+
+impl UntracedState<usize> for GraphState {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.edges[self.node]
+            .iter()
+            .map(|&(target, cost)| self.successor(target, cost))
+            .collect()
+    }
+}
+
+impl TracedState<usize, usize> for GraphState {
+    fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+        self.edges[self.node]
+            .iter()
+            .map(|&(target, cost)| (self.successor(target, cost), target))
+            .collect()
+    }
+}
+
+// An open list entry with its costs set directly, for the tests of the queues. A goal once h is 0.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TestState {
+    pub id: i32,
+    pub g_cost: usize,
+    pub h_cost: usize
+}
+
+impl AStarState<i32> for TestState {
+    fn key(&self) -> i32 {
+        self.id
+    }
+
+    fn h(&self) -> usize {
+        self.h_cost
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g_cost
+    }
+
+    fn is_goal(&self) -> bool {
+        self.h_cost == 0
+    }
+}
+
+// Eight nodes, optimal cost from 0 to 7 is 9 (0 -> 3 -> 2 -> 5 -> 7 or 0 -> 1 -> 2 -> 5 -> 7).
+pub const EIGHT_NODES: &[&[(usize, usize)]] = &[
+    &[(1, 2), (2, 5), (3, 1)],
+    &[(4, 4), (2, 1)],
+    &[(5, 2)],
+    &[(2, 2), (6, 7)],
+    &[(7, 3)],
+    &[(7, 4), (4, 1)],
+    &[(7, 1)],
+    &[]
+];
+// Admissible towards node 7 only.
+pub const EIGHT_NODES_H: &[usize] = &[6, 5, 4, 7, 3, 3, 1, 0];
+pub const ZERO_H: &[usize] = &[0; 8];
+
+// 0 -> 1 -> 3 costs 11 and 0 -> 2 -> 3 costs 8, but 2 looks far enough to be left open once h is
+// weighted by 3.
+pub const DETOUR: &[&[(usize, usize)]] = &[&[(1, 1), (2, 6)], &[(3, 10)], &[(3, 2)], &[]];
+pub const DETOUR_H: &[usize] = &[0, 1, 2, 0];
+
+// A graph of `nodes` nodes with `edges` random edges costing 1 to 5, each one both ways when
+// `undirected`. Leaked, as GraphState wants static edges.
+pub fn random_graph(rng: &mut SplitMix64, nodes: usize, edges: usize, undirected: bool) -> &'static [&'static [(usize, usize)]] {
+    let mut lists = vec![Vec::new(); nodes];
+    for _ in 0..edges {
+        let (from, to, cost) = (rng.next_below(nodes), rng.next_below(nodes), rng.next_below(5) + 1);
+        lists[from].push((to, cost));
+        if undirected {
+            lists[to].push((from, cost));
+        }
+    }
+    Vec::leak(lists.into_iter().map(|list| &*Vec::leak(list)).collect())
+}
+
+#[cfg(feature = "grid")]
+pub use crate::grid::state::Direction;
+
+// The moves of the puzzle's blank when the grid module isn't built, so that the tests using the
+// puzzle don't need it.
+#[cfg(not(feature = "grid"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+#[cfg(not(feature = "grid"))]
+impl Direction {
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left
+        }
+    }
+}
+
+// 3x3 sliding tile puzzle, 0 is the blank. Changes say where the blank moves. The goal is
+// 1..8 in order with the blank in the last cell, and h is the Manhattan distance of the tiles.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlidingPuzzle {
+    pub tiles: [u8; 9],
+    pub g_cost: usize
+}
+
+impl SlidingPuzzle {
+    pub const SOLVED: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+
+    // Random walk of `moves` steps back from the solved board that never undoes the previous step,
+    // so the instance is solvable in at most that many moves.
+    pub fn scrambled(seed: u64, moves: usize) -> Self {
+        let mut rng = SplitMix64::new(seed);
+        let mut puzzle = SlidingPuzzle {
+            tiles: Self::SOLVED,
+            g_cost: 0
+        };
+
+        let mut previous = puzzle.tiles;
+        for _ in 0..moves {
+            let successors: Vec<SlidingPuzzle> = puzzle.generate_traced_successors()
+                .into_iter()
+                .map(|(successor, _)| successor)
+                .filter(|successor| successor.tiles != previous)
+                .collect();
+            previous = puzzle.tiles;
+            puzzle = successors[rng.next_below(successors.len())].clone();
+        }
+
+        puzzle.g_cost = 0;
+        puzzle
+    }
+
+    fn blank(&self) -> usize {
+        self.tiles.iter().position(|&tile| tile == 0).unwrap()
+    }
+}
+
+impl AStarState<[u8; 9]> for SlidingPuzzle {
+    fn key(&self) -> [u8; 9] {
+        self.tiles
+    }
+
+    fn h(&self) -> usize {
+        self.tiles.iter()
+            .enumerate()
+            .filter(|&(_, &tile)| tile != 0)
+            .map(|(index, &tile)| {
+                let target = tile as usize - 1;
+                (index % 3).abs_diff(target % 3) + (index / 3).abs_diff(target / 3)
+            })
+            .sum()
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g_cost
+    }
+
+    fn is_goal(&self) -> bool {
+        self.tiles == Self::SOLVED
+    }
+}
+
+impl TracedState<[u8; 9], Direction> for SlidingPuzzle {
+    fn generate_traced_successors(&self) -> Vec<(Self, Direction)> {
+        let blank = self.blank();
+        let (x, y) = (blank % 3, blank / 3);
+        let mut successors = Vec::new();
+
+        for (direction, possible, target) in [
+            (Direction::Up, y > 0, blank.wrapping_sub(3)),
+            (Direction::Down, y < 2, blank + 3),
+            (Direction::Left, x > 0, blank.wrapping_sub(1)),
+            (Direction::Right, x < 2, blank + 1)
+        ] {
+            if possible {
+                let mut tiles = self.tiles;
+                tiles.swap(blank, target);
+                successors.push((SlidingPuzzle { tiles, g_cost: self.g_cost + 1 }, direction));
+            }
+        }
+
+        successors
+    }
+}
+
+// Walks from the origin to the far corner of a SIZE^3 lattice, one unit step along an axis per
+// change. Steps along different axes commute and are canonically ordered by axis. h is 0.
+#[derive(Clone, Debug)]
+pub struct Lattice {
+    pub position: [usize; 3],
+    pub size: usize,
+    pub g_cost: usize
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step(pub usize);
+
+impl CommutativeChanges for Step {
+    fn independent(a: &Self, b: &Self) -> bool {
+        a.0 != b.0
+    }
+
+    fn canonical_before(a: &Self, b: &Self) -> bool {
+        a.0 < b.0
+    }
+}
+
+impl Lattice {
+    pub fn new(size: usize) -> Self {
+        Lattice {
+            position: [0; 3],
+            size,
+            g_cost: 0
+        }
+    }
+}
+
+impl AStarState<[usize; 3]> for Lattice {
+    fn key(&self) -> [usize; 3] {
+        self.position
+    }
+
+    fn h(&self) -> usize {
+        0
+    }
+
+    fn f(&self) -> usize {
+        self.g_cost
+    }
+
+    fn g(&self) -> usize {
+        self.g_cost
+    }
+
+    fn is_goal(&self) -> bool {
+        self.position == [self.size - 1; 3]
+    }
+}
+
+impl TracedState<[usize; 3], Step> for Lattice {
+    fn generate_traced_successors(&self) -> Vec<(Self, Step)> {
+        (0..3)
+            .filter(|&axis| self.position[axis] + 1 < self.size)
+            .map(|axis| {
+                let mut position = self.position;
+                position[axis] += 1;
+                (Lattice { position, size: self.size, g_cost: self.g_cost + 1 }, Step(axis))
+            })
+            .collect()
+    }
+}
+
+// A map with about `blocked_percent` of its cells blocked.
+#[cfg(feature = "grid")]
+pub fn random_map(rng: &mut SplitMix64, width: usize, height: usize, blocked_percent: usize, movement: MovementModel) -> GridMap {
+    let mut map = GridMap::new(width, height).with_movement(movement);
+    for y in 0..height {
+        for x in 0..width {
+            map.set_blocked(x, y, rng.next_below(100) < blocked_percent);
+        }
+    }
+    map
+}
+
+#[cfg(feature = "grid")]
+pub fn random_free_cell(rng: &mut SplitMix64, map: &GridMap) -> (usize, usize) {
+    loop {
+        let cell = (rng.next_below(map.width()), rng.next_below(map.height()));
+        if !map.is_blocked(cell.0, cell.1) {
+            return cell;
+        }
+    }
+}
+
+// A 30x30 random map with a quarter of the cells blocked, corners kept free, for searches from
+// (0, 0) to (29, 29).
+#[cfg(feature = "grid")]
+pub fn corner_to_corner_map(seed: u64, movement: MovementModel) -> GridMap {
+    let mut map = random_map(&mut SplitMix64::new(seed), 30, 30, 25, movement);
+    map.set_blocked(0, 0, false);
+    map.set_blocked(29, 29, false);
+    map
+}
+
+// Runs a bounded-suboptimal search at each of `bounds` across the corner to corner maps of 20
+// seeds and two movement models. It must return a path that replays to its cost, at most the
+// bound times the optimum, or nothing when there is no path. `search` gets the map too, for
+// states that carry more than the grid.
+#[cfg(feature = "grid")]
+pub fn assert_within_bound_on_random_grids<F>(bounds: &[f64], search: F)
+where
+    F: for<'m> Fn(&'m GridMap, GridState<'m>, f64) -> Option<TracedResult<GridState<'m>, (usize, usize), Direction>>
+{
+    let goal = GoalRegion::Cell(29, 29);
+    for seed in 0..20 {
+        for movement in [MovementModel::FourWay, MovementModel::EightWayOctile] {
+            let map = corner_to_corner_map(seed, movement);
+            let start = GridState::with_region(&map, (0, 0), &goal);
+            let optimal = traced_astar(start.clone()).map(|result| result.cost);
+
+            for &bound in bounds {
+                let result = search(&map, start.clone(), bound);
+                let Some(optimal) = optimal else {
+                    assert!(result.is_none(), "seed {} bound {}: a path on a map without one", seed, bound);
+                    continue;
+                };
+
+                let result = result.unwrap_or_else(|| panic!("seed {} bound {}: no path", seed, bound));
+                assert!(result.cost as f64 <= optimal as f64 * bound, "seed {} bound {}: {} vs {}", seed, bound, result.cost, optimal);
+                assert_eq!(replay(&start, result.path.to_vec()).unwrap().g(), result.cost);
+            }
+        }
+    }
+}
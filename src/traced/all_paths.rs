@@ -0,0 +1,372 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{open_list::OpenList, traced::state::TracedState};
+
+// Which paths the enumeration leaves out. Branches are cut as soon as they would break the
+// filter, so the paths left out are never built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathFilter {
+    // No key twice within a path.
+    Simple,
+    // No parent-child key pair twice within a path. A key can come back through another edge.
+    NoLoops,
+    // Every path, up to the maximum length.
+    All
+}
+
+#[derive(Debug, Clone)]
+pub struct PathEnumeration {
+    filter: PathFilter,
+    max_paths: usize,
+    max_length: Option<usize>
+}
+
+impl PathEnumeration {
+    pub fn new(filter: PathFilter) -> Self {
+        PathEnumeration {
+            filter,
+            max_paths: usize::MAX,
+            max_length: None
+        }
+    }
+
+    // Stops after this many paths.
+    pub fn with_max_paths(mut self, paths: usize) -> Self {
+        self.max_paths = paths;
+        self
+    }
+
+    // Changes per path. Without one, Simple and NoLoops paths are unbounded (they are finite
+    // anyway) and All paths get as many changes as the parent graph has edges, the length of the
+    // longest possible NoLoops path.
+    pub fn with_max_length(mut self, changes: usize) -> Self {
+        self.max_length = Some(changes);
+        self
+    }
+
+    pub fn filter(&self) -> PathFilter {
+        self.filter
+    }
+
+    pub fn max_paths(&self) -> usize {
+        self.max_paths
+    }
+
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllPaths<C> {
+    pub cost: usize,
+    pub paths: Vec<Vec<C>>,
+    // Branches cut by the filter. Each one stands for at least one path left out.
+    pub suppressed: usize,
+    // Branches cut by the maximum length, or paths left out by the maximum number of paths.
+    pub truncated: bool,
+    pub expansions: usize
+}
+
+// A key of the parent graph: its best g and every parent reaching it with that g, with the change
+// leading here.
+struct ParentNode<K, C> {
+    g: usize,
+    parents: Vec<(K, C)>
+}
+
+// Every path of optimal cost from the initial state to a goal, as lists of changes. The search
+// keeps all the parents over which a key is reached with its best g, and goes on until the open
+// list holds nothing of f up to the optimal cost. Zero cost edges can close cycles in that parent
+// graph, which is what the filter is for. Improved states are reopened, so the cost is optimal
+// for any admissible h.
+pub fn all_optimal_paths<S, K, C>(initial_state: S, enumeration: &PathEnumeration) -> Option<AllPaths<C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let start = initial_state.key();
+    let mut graph = HashMap::new();
+    graph.insert(start.clone(), ParentNode { g: initial_state.g(), parents: Vec::new() });
+
+    let mut open_list = OpenList::new();
+    open_list.insert(start.clone(), initial_state);
+    let mut goals = Vec::new();
+    let mut cost = None;
+    let mut expansions = 0;
+
+    while let Some(current) = open_list.extract_min() {
+        if cost.is_some_and(|cost| current.f() > cost) {
+            break;
+        }
+
+        let key = current.key();
+        if current.is_goal() {
+            cost = Some(current.g());
+            goals.push(key);
+            continue;
+        }
+
+        expansions += 1;
+        for (successor, change) in current.generate_traced_successors() {
+            let successor_key = successor.key();
+            let g = successor.g();
+
+            match graph.get_mut(&successor_key) {
+                None => {
+                    graph.insert(successor_key.clone(), ParentNode { g, parents: vec![(key.clone(), change)] });
+                }
+                Some(node) if g < node.g => {
+                    node.g = g;
+                    node.parents = vec![(key.clone(), change)];
+                }
+                Some(node) if g == node.g => {
+                    node.parents.push((key.clone(), change));
+                    continue;
+                }
+                Some(_) => continue
+            }
+
+            open_list.insert(successor_key, successor);
+        }
+    }
+
+    let cost = cost?;
+    let mut enumerator = Enumerator::new(&graph, &start, enumeration);
+    for goal in goals {
+        enumerator.enumerate(goal);
+    }
+
+    Some(AllPaths {
+        cost,
+        paths: enumerator.paths,
+        suppressed: enumerator.suppressed,
+        truncated: enumerator.truncated,
+        expansions
+    })
+}
+
+// Depth-first walk of the parent graph from a goal back to the start, without recursion.
+struct Enumerator<'a, K, C> {
+    graph: &'a HashMap<K, ParentNode<K, C>>,
+    start: &'a K,
+    filter: PathFilter,
+    max_paths: usize,
+    max_length: usize,
+    // Key, index of its next parent to try, and the change from that key to the one before it.
+    stack: Vec<(K, usize, Option<C>)>,
+    keys_on_path: HashSet<K>,
+    edges_on_path: HashSet<(K, K)>,
+    paths: Vec<Vec<C>>,
+    suppressed: usize,
+    truncated: bool
+}
+
+impl<'a, K, C> Enumerator<'a, K, C>
+where
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    fn new(graph: &'a HashMap<K, ParentNode<K, C>>, start: &'a K, enumeration: &PathEnumeration) -> Self {
+        let max_length = match (enumeration.max_length, enumeration.filter) {
+            (Some(max_length), _) => max_length,
+            (None, PathFilter::All) => graph.values().map(|node| node.parents.len()).sum(),
+            (None, _) => usize::MAX
+        };
+
+        Enumerator {
+            graph,
+            start,
+            filter: enumeration.filter,
+            max_paths: enumeration.max_paths,
+            max_length,
+            stack: Vec::new(),
+            keys_on_path: HashSet::new(),
+            edges_on_path: HashSet::new(),
+            paths: Vec::new(),
+            suppressed: 0,
+            truncated: false
+        }
+    }
+
+    fn enumerate(&mut self, goal: K) {
+        self.push(goal, None);
+
+        while let Some((key, next, _)) = self.stack.last_mut() {
+            let parents = &self.graph[&*key].parents;
+            let Some((parent, change)) = parents.get(*next) else {
+                self.pop();
+                continue;
+            };
+            *next += 1;
+            let key = key.clone();
+
+            if self.paths.len() == self.max_paths {
+                self.truncated = true;
+                self.stack.clear();
+                self.keys_on_path.clear();
+                self.edges_on_path.clear();
+                return;
+            }
+
+            let repeats = match self.filter {
+                PathFilter::Simple => self.keys_on_path.contains(parent),
+                PathFilter::NoLoops => self.edges_on_path.contains(&(parent.clone(), key.clone())),
+                PathFilter::All => false
+            };
+            if repeats {
+                self.suppressed += 1;
+                continue;
+            }
+            if self.stack.len() > self.max_length {
+                self.truncated = true;
+                continue;
+            }
+
+            self.push(parent.clone(), Some((key, change.clone())));
+        }
+    }
+
+    fn push(&mut self, key: K, from: Option<(K, C)>) {
+        match self.filter {
+            PathFilter::Simple => {
+                self.keys_on_path.insert(key.clone());
+            }
+            PathFilter::NoLoops => {
+                if let Some((child, _)) = &from {
+                    self.edges_on_path.insert((key.clone(), child.clone()));
+                }
+            }
+            PathFilter::All => {}
+        }
+
+        let reached_start = key == *self.start;
+        self.stack.push((key, 0, from.map(|(_, change)| change)));
+        if reached_start {
+            let path = self.stack.iter().rev().filter_map(|(_, _, change)| change.clone()).collect();
+            self.paths.push(path);
+        }
+    }
+
+    fn pop(&mut self) {
+        let Some((key, _, _)) = self.stack.pop() else {
+            return;
+        };
+
+        match self.filter {
+            PathFilter::Simple => {
+                self.keys_on_path.remove(&key);
+            }
+            PathFilter::NoLoops => {
+                if let Some((child, _, _)) = self.stack.last() {
+                    self.edges_on_path.remove(&(key, child.clone()));
+                }
+            }
+            PathFilter::All => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, ZERO_H};
+    #[cfg(feature = "grid")]
+    use crate::grid::{map::GridMap, region::GoalRegion, state::{Direction, GridState}};
+
+    // Two branches 0 -> 1 -> 3 and 0 -> 2 -> 3 of cost 2, joined by a zero cost cycle 1 <-> 2.
+    const CROSSED: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 1)],
+        &[(3, 1), (2, 0)],
+        &[(3, 1), (1, 0)],
+        &[]
+    ];
+
+    fn enumerate(enumeration: PathEnumeration) -> AllPaths<usize> {
+        all_optimal_paths(GraphState::new(CROSSED, ZERO_H, 0, 3), &enumeration).unwrap()
+    }
+
+    fn repeats_a_key(path: &[usize]) -> bool {
+        let mut seen = HashSet::from([0]);
+        !path.iter().all(|node| seen.insert(*node))
+    }
+
+    fn repeats_an_edge(path: &[usize]) -> bool {
+        let nodes: Vec<usize> = std::iter::once(0).chain(path.iter().copied()).collect();
+        let mut seen = HashSet::new();
+        !nodes.windows(2).all(|edge| seen.insert((edge[0], edge[1])))
+    }
+
+    #[test]
+    fn test_simple_paths_have_no_loops() {
+        let simple = enumerate(PathEnumeration::new(PathFilter::Simple));
+        assert_eq!(simple.cost, 2);
+        assert!(!simple.truncated);
+
+        let mut paths = simple.paths.clone();
+        paths.sort();
+        assert_eq!(paths, vec![vec![1, 2, 3], vec![1, 3], vec![2, 1, 3], vec![2, 3]]);
+        assert!(paths.iter().all(|path| !repeats_a_key(path)));
+
+        // Every branch cut by Simple here completes in exactly one way, one change longer.
+        assert_eq!(simple.suppressed, 2);
+        let all = enumerate(PathEnumeration::new(PathFilter::All).with_max_length(4));
+        assert_eq!(all.suppressed, 0);
+        assert_eq!(all.paths.len(), simple.paths.len() + simple.suppressed);
+        assert_eq!(all.paths.iter().filter(|path| repeats_a_key(path)).count(), simple.suppressed);
+    }
+
+    #[test]
+    fn test_no_loops_allows_revisits_through_new_edges() {
+        let no_loops = enumerate(PathEnumeration::new(PathFilter::NoLoops));
+        assert!(!no_loops.truncated);
+        assert!(no_loops.paths.contains(&vec![1, 2, 1, 3]));
+        assert!(no_loops.paths.iter().all(|path| !repeats_an_edge(path)));
+
+        // The default length of All covers every NoLoops path.
+        let all = enumerate(PathEnumeration::new(PathFilter::All));
+        assert!(all.truncated);
+        assert!(no_loops.paths.iter().all(|path| all.paths.contains(path)));
+        assert!(all.paths.len() > no_loops.paths.len());
+    }
+
+    #[test]
+    fn test_max_paths() {
+        let capped = enumerate(PathEnumeration::new(PathFilter::Simple).with_max_paths(3));
+        assert_eq!(capped.paths.len(), 3);
+        assert!(capped.truncated);
+    }
+
+    // Every monotone walk across an open grid is optimal, C(2n, n) of them on an n+1 square.
+    #[cfg(feature = "grid")]
+    fn corner_to_corner(map: &GridMap, filter: PathFilter) -> AllPaths<Direction> {
+        let goal = GoalRegion::Cell(map.width() - 1, map.height() - 1);
+        all_optimal_paths(GridState::with_region(map, (0, 0), &goal), &PathEnumeration::new(filter)).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "grid")]
+    fn test_corner_to_corner_walks() {
+        let two = corner_to_corner(&GridMap::new(2, 2), PathFilter::Simple);
+        assert_eq!((two.cost, two.paths.len()), (2, 2));
+        assert!(two.paths.contains(&vec![Direction::Down, Direction::Right]));
+        assert!(two.paths.contains(&vec![Direction::Right, Direction::Down]));
+
+        let mut map = GridMap::new(3, 3);
+        assert_eq!(corner_to_corner(&map, PathFilter::Simple).paths.len(), 6);
+        // Unit costs leave no zero cost cycle for the filter to cut.
+        assert_eq!(corner_to_corner(&map, PathFilter::All).paths.len(), 6);
+
+        map.set_blocked(1, 1, true);
+        let around = corner_to_corner(&map, PathFilter::Simple);
+        assert_eq!((around.cost, around.paths.len()), (4, 2));
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let enumeration = PathEnumeration::new(PathFilter::Simple);
+        assert!(all_optimal_paths(GraphState::new(CROSSED, ZERO_H, 3, 0), &enumeration).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{ara_star::build_result, result::TracedResult, state::{TracedState, TracedStateWrapper}}, weight_schedule::inflated_f};
+
+// Anytime weighted A*: weighted A* that goes on after each goal instead of stopping. Every call
+// to `next` resumes the search and yields the next goal cheaper than the last one. States whose
+// f can't beat the incumbent are dropped, and a state reached again with a lower g goes back to
+// the open list even when it was expanded, so the open list and the best g of every key are kept
+// between calls. The iterator ends when the open list runs out, which proves the last solution
+// optimal for an admissible h. Weights below 1 are raised to 1.
+pub struct AnytimeSearch<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    weight: f64,
+    open_list: OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>>,
+    // Latest expansion of every key, for the links of the paths.
+    expanded: HashMap<K, TracedStateWrapper<S, K, C>>,
+    best_g: HashMap<K, usize>,
+    incumbent_cost: Option<usize>,
+    expansions: usize
+}
+
+impl<S, K, C> AnytimeSearch<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    pub fn new(initial_state: S, initial_weight: f64) -> Self {
+        let weight = initial_weight.max(1.0);
+        let mut open_list = OpenList::new();
+        let mut best_g = HashMap::new();
+
+        best_g.insert(initial_state.key(), initial_state.g());
+        let priority = inflated_f(initial_state.f(), initial_state.h(), weight);
+        open_list.insert(initial_state.key(), Prioritized::new(TracedStateWrapper::new(initial_state), priority));
+
+        AnytimeSearch {
+            weight,
+            open_list,
+            expanded: HashMap::new(),
+            best_g,
+            incumbent_cost: None,
+            expansions: 0
+        }
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    // Cost of the last solution yielded.
+    pub fn incumbent_cost(&self) -> Option<usize> {
+        self.incumbent_cost
+    }
+
+    // Nothing is left that could beat the incumbent: the last solution is optimal, or there is
+    // none when no solution was yielded.
+    pub fn is_proven(&self) -> bool {
+        self.open_list.is_empty()
+    }
+
+    fn beats_incumbent(&self, f: usize) -> bool {
+        self.incumbent_cost.is_none_or(|cost| f < cost)
+    }
+}
+
+impl<S, K, C> Iterator for AnytimeSearch<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    type Item = TracedResult<S, K, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Prioritized { state: current_state, .. }) = self.open_list.extract_min() {
+            if !self.beats_incumbent(current_state.f()) {
+                continue;
+            }
+
+            if current_state.is_goal() {
+                self.incumbent_cost = Some(current_state.g());
+                let mut result = build_result(&current_state, &self.expanded);
+                result.iterations = self.expansions;
+                return Some(result);
+            }
+
+            let successors = current_state.generate_states();
+            self.expansions += 1;
+            self.expanded.insert(current_state.key(), current_state);
+
+            for successor in successors {
+                let successor_key = successor.key();
+                if !self.beats_incumbent(successor.f()) || self.best_g.get(&successor_key).is_some_and(|&g| g <= successor.g()) {
+                    continue;
+                }
+
+                self.best_g.insert(successor_key.clone(), successor.g());
+                let priority = inflated_f(successor.f(), successor.h(), self.weight);
+                self.open_list.insert(successor_key, Prioritized::new(successor, priority));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, SlidingPuzzle, DETOUR, DETOUR_H, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::traced_astar};
+
+    #[test]
+    fn test_solutions_improve_until_proven() {
+        let mut search = AnytimeSearch::new(GraphState::new(DETOUR, DETOUR_H, 0, 3), 3.0);
+
+        let first = search.next().unwrap();
+        assert_eq!((first.path.to_vec(), first.cost), (vec![1, 3], 11));
+        assert!(!search.is_proven());
+
+        let second = search.next().unwrap();
+        assert_eq!((second.path.to_vec(), second.cost), (vec![2, 3], 8));
+        assert!(search.next().is_none());
+        assert!(search.is_proven());
+        assert_eq!(search.incumbent_cost(), Some(8));
+    }
+
+    #[test]
+    fn test_weight_one_yields_the_optimum_once() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 30);
+            let optimal = traced_astar(start.clone()).unwrap();
+
+            let solutions: Vec<usize> = AnytimeSearch::new(start, 1.0).map(|result| result.cost).collect();
+            assert_eq!(solutions, vec![optimal.cost]);
+        }
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let mut search = AnytimeSearch::new(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0), 2.0);
+        assert!(search.next().is_none());
+        assert!(search.is_proven());
+        assert_eq!(search.incumbent_cost(), None);
+    }
+}
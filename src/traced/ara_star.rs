@@ -0,0 +1,163 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}}, weight_schedule::inflated_f};
+
+// Anytime Repairing A*: weighted A* with each weight of `weights` in turn, best left at 1.0. The
+// first search stops at a goal, each later one reuses the open list and the best g of every key
+// found so far, so it only repairs what the lower weight changes. Keys whose g improved after
+// they were expanded under the current weight wait in an inconsistent list and go back to the
+// open list for the next weight. A search ends once no open state could lead to a cheaper goal
+// under its weight. `on_incumbent` gets the result whenever one of them found a cheaper goal,
+// and the last one is returned. Its cost is at most weight times the optimum for an admissible
+// h, so optimal if the last weight is 1. Weights below 1 are raised to 1.
+pub fn ara_star<S, K, C>(initial_state: S, weights: &[f64], mut on_incumbent: impl FnMut(&TracedResult<S, K, C>)) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    if initial_state.is_goal() {
+        let result = TracedResult::new(Vec::new(), 0, initial_state);
+        on_incumbent(&result);
+        return Some(result);
+    }
+
+    let mut weights = weights.iter().map(|&weight| weight.max(1.0));
+    let mut weight = weights.next().unwrap_or(1.0);
+
+    let mut open_list: OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>> = OpenList::new();
+    // Latest expansion of every key, for the links of the paths.
+    let mut expanded: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let mut best_g: HashMap<K, usize> = HashMap::new();
+    let mut closed: HashSet<K> = HashSet::new();
+    let mut inconsistent: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let mut incumbent: Option<TracedStateWrapper<S, K, C>> = None;
+    let mut result = None;
+
+    best_g.insert(initial_state.key(), initial_state.g());
+    let priority = inflated_f(initial_state.f(), initial_state.h(), weight);
+    open_list.insert(initial_state.key(), Prioritized::new(TracedStateWrapper::new(initial_state), priority));
+
+    loop {
+        let mut improved = false;
+
+        while let Some(min) = open_list.min() {
+            if incumbent.as_ref().is_some_and(|goal| min.f() >= goal.g()) {
+                break;
+            }
+
+            let current_state = open_list.extract_min().unwrap().state;
+            let successors = current_state.generate_states();
+            let current_key = current_state.key();
+            closed.insert(current_key.clone());
+            expanded.insert(current_key, current_state);
+
+            for successor in successors {
+                let successor_key = successor.key();
+                if best_g.get(&successor_key).is_some_and(|&g| g <= successor.g()) {
+                    continue;
+                }
+                best_g.insert(successor_key.clone(), successor.g());
+
+                if successor.is_goal() {
+                    if incumbent.as_ref().is_none_or(|goal| successor.g() < goal.g()) {
+                        incumbent = Some(successor);
+                        improved = true;
+                    }
+                }
+                else if closed.contains(&successor_key) {
+                    inconsistent.insert(successor_key, successor);
+                }
+                else {
+                    let priority = inflated_f(successor.f(), successor.h(), weight);
+                    open_list.insert(successor_key, Prioritized::new(successor, priority));
+                }
+            }
+        }
+
+        if improved && let Some(goal) = &incumbent {
+            let incumbent_result = build_result(goal, &expanded);
+            on_incumbent(&incumbent_result);
+            result = Some(incumbent_result);
+        }
+
+        let Some(next_weight) = weights.next() else {
+            break;
+        };
+        weight = next_weight;
+
+        for (key, state) in inconsistent.drain() {
+            open_list.insert(key, Prioritized::new(state, 0));
+        }
+        open_list.reprioritize(|entry| entry.set_priority(inflated_f(entry.state.f(), entry.state.h(), weight)));
+        closed.clear();
+    }
+
+    result
+}
+
+// Result for `goal`, following the links of the latest expansions back to the initial state.
+pub(crate) fn build_result<S, K, C>(goal: &TracedStateWrapper<S, K, C>, expanded: &HashMap<K, TracedStateWrapper<S, K, C>>) -> TracedResult<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut path: Vec<C> = goal.change.iter().cloned().collect();
+    let mut prev_key = goal.prev_key.as_ref();
+
+    while let Some(state) = prev_key.and_then(|key| expanded.get(key)) {
+        path.extend(state.change.iter().cloned());
+        prev_key = state.prev_key.as_ref();
+    }
+
+    path.reverse();
+    TracedResult::new(path, expanded.len(), goal.state.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, SlidingPuzzle, DETOUR, DETOUR_H, EIGHT_NODES, EIGHT_NODES_H}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    #[test]
+    fn test_incumbents_improve_down_to_the_optimum() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let optimal = traced_astar(start.clone()).unwrap();
+
+            let mut costs = Vec::new();
+            let result = ara_star(start.clone(), &[5.0, 3.0, 2.0, 1.5, 1.0], |incumbent| {
+                costs.push(incumbent.cost);
+                let replayed = replay(&start, incumbent.path.to_vec()).unwrap();
+                assert!(replayed.is_goal());
+                assert!(replayed.g() <= incumbent.cost);
+            }).unwrap();
+
+            assert!(costs.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", costs);
+            assert_eq!(result.cost, optimal.cost);
+            assert_eq!(costs.last(), Some(&optimal.cost));
+        }
+    }
+
+    #[test]
+    fn test_lower_weights_repair_the_incumbent() {
+        let mut costs = Vec::new();
+        let result = ara_star(GraphState::new(DETOUR, DETOUR_H, 0, 3), &[3.0], |incumbent| costs.push(incumbent.cost)).unwrap();
+        assert_eq!(costs, vec![11]);
+        assert_eq!(result.path.to_vec(), vec![1, 3]);
+
+        let mut costs = Vec::new();
+        let result = ara_star(GraphState::new(DETOUR, DETOUR_H, 0, 3), &[3.0, 1.0], |incumbent| costs.push(incumbent.cost)).unwrap();
+        assert_eq!(costs, vec![11, 8]);
+        assert_eq!(result.path.to_vec(), vec![2, 3]);
+        assert_eq!(result.iterations, 3);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let mut calls = 0;
+        assert!(ara_star(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0), &[2.0, 1.0], |_| calls += 1).is_none());
+        assert_eq!(calls, 0);
+    }
+}
@@ -0,0 +1,688 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{astar_state::AStarState, traced::{result::TracedResult, state::TracedState}};
+
+// Beam search: layer by layer, only the `width` successors with the lowest f (then h) of each layer
+// are kept. A state is only kept again when reached with a lower g than the kept copy. The search
+// doesn't stop at the first goal: it goes on until the beam is empty, skipping states whose f is
+// no better than the cheapest goal so far, and returns that goal. When no layer ever had to drop
+// a state, the search was exhaustive and the cost is optimal for an admissible h.
+pub fn beam_search<S, K, C>(initial_state: S, width: usize) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    beam_search_outcome(initial_state, width).result
+}
+
+pub struct BeamOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    pub expansions: usize,
+    // States left out of a layer by the width.
+    pub dropped: usize
+}
+
+impl<S, K, C> BeamOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // Some layer was cut to the width, so the result may be suboptimal, and None doesn't mean
+    // that no goal is reachable.
+    pub fn pruned(&self) -> bool {
+        self.dropped > 0
+    }
+}
+
+// beam_search, also saying whether the width cut anything.
+pub fn beam_search_outcome<S, K, C>(initial_state: S, width: usize) -> BeamOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let run = run_beam(initial_state, width, usize::MAX, &HashSet::new());
+    BeamOutcome { result: run.result, expansions: run.expansions, dropped: run.dropped }
+}
+
+// How the width grows between the attempts of iterative_beam_search. The width grows by at least
+// one per attempt whatever the schedule says.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum WidthGrowth {
+    Double,
+    Add(usize),
+    // Next width from the last one.
+    Custom(fn(usize) -> usize)
+}
+
+impl WidthGrowth {
+    pub fn next(&self, width: usize) -> usize {
+        let next = match self {
+            WidthGrowth::Double => width.saturating_mul(2),
+            WidthGrowth::Add(step) => width.saturating_add(*step),
+            WidthGrowth::Custom(schedule) => schedule(width)
+        };
+        next.max(width.saturating_add(1))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IterativeBeam {
+    initial_width: usize,
+    growth: WidthGrowth,
+    budget: usize,
+    reuse_prefix: bool,
+    until_optimal: bool
+}
+
+impl IterativeBeam {
+    pub fn new(initial_width: usize, growth: WidthGrowth) -> Self {
+        IterativeBeam {
+            initial_width: initial_width.max(1),
+            growth,
+            budget: usize::MAX,
+            reuse_prefix: false,
+            until_optimal: false
+        }
+    }
+
+    // Expansions over all attempts. The attempt that runs out is cut short and gives no result.
+    pub fn with_budget(mut self, expansions: usize) -> Self {
+        self.budget = expansions;
+        self
+    }
+
+    // Each retry keeps the states of the previous attempt's best path in its layers on top of the
+    // width, whatever their rank: the path to the cheapest goal, or else to the expanded state
+    // with the lowest h.
+    pub fn with_prefix_reuse(mut self, enabled: bool) -> Self {
+        self.reuse_prefix = enabled;
+        self
+    }
+
+    // Keeps widening after a solution until an attempt drops no state, which makes its cost
+    // optimal for an admissible h.
+    pub fn with_until_optimal(mut self, enabled: bool) -> Self {
+        self.until_optimal = enabled;
+        self
+    }
+
+    pub fn initial_width(&self) -> usize {
+        self.initial_width
+    }
+
+    pub fn growth(&self) -> WidthGrowth {
+        self.growth
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn prefix_reuse(&self) -> bool {
+        self.reuse_prefix
+    }
+
+    pub fn until_optimal(&self) -> bool {
+        self.until_optimal
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeamAttempt {
+    pub width: usize,
+    pub expansions: usize,
+    // States left out of a layer by the width.
+    pub dropped: usize,
+    // Cost of the goal found, if any.
+    pub cost: Option<usize>
+}
+
+pub struct IterativeBeamOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    // Width of the attempt the result comes from.
+    pub width: Option<usize>,
+    pub attempts: Vec<BeamAttempt>,
+    // The last attempt dropped no state, so the result (or its absence) is exact.
+    pub exhaustive: bool
+}
+
+impl<S, K, C> IterativeBeamOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub fn total_expansions(&self) -> usize {
+        self.attempts.iter().map(|attempt| attempt.expansions).sum()
+    }
+}
+
+// Beam searches of growing width until one finds a goal (or, with until_optimal, is exhaustive),
+// an attempt turns out exhaustive without a goal, or the budget runs out.
+pub fn iterative_beam_search<S, K, C>(initial_state: S, initial_width: usize, growth: WidthGrowth) -> IterativeBeamOutcome<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    iterative_beam_search_with(initial_state, &IterativeBeam::new(initial_width, growth))
+}
+
+pub fn iterative_beam_search_with<S, K, C>(initial_state: S, config: &IterativeBeam) -> IterativeBeamOutcome<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut outcome: IterativeBeamOutcome<S, K, C> = IterativeBeamOutcome {
+        result: None,
+        width: None,
+        attempts: Vec::new(),
+        exhaustive: false
+    };
+    let mut width = config.initial_width;
+    let mut budget = config.budget;
+    let mut prefix = HashSet::new();
+
+    loop {
+        let attempt = run_beam(initial_state.clone(), width, budget, &prefix);
+        budget -= attempt.expansions;
+        outcome.attempts.push(BeamAttempt {
+            width,
+            expansions: attempt.expansions,
+            dropped: attempt.dropped,
+            cost: attempt.result.as_ref().map(|result| result.final_state.g())
+        });
+
+        if let Some(result) = attempt.result
+            && outcome.result.as_ref().is_none_or(|best| result.final_state.g() < best.final_state.g())
+        {
+            outcome.result = Some(result);
+            outcome.width = Some(width);
+        }
+
+        outcome.exhaustive = !attempt.out_of_budget && attempt.dropped == 0;
+        let solved = outcome.result.is_some() && !config.until_optimal;
+        if solved || outcome.exhaustive || attempt.out_of_budget {
+            return outcome;
+        }
+
+        if config.reuse_prefix {
+            prefix = attempt.best_prefix.into_iter().collect();
+        }
+        width = config.growth.next(width);
+    }
+}
+
+struct BeamRun<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    result: Option<TracedResult<S, K, C>>,
+    expansions: usize,
+    dropped: usize,
+    out_of_budget: bool,
+    // Keys from the initial state to the cheapest goal, or to the expanded state with the lowest h.
+    best_prefix: Vec<K>
+}
+
+// Links from the kept states back to the initial one, shared by all the layers.
+struct PathNode<K, C> {
+    parent: Option<usize>,
+    key: K,
+    change: Option<C>
+}
+
+fn run_beam<S, K, C>(initial_state: S, width: usize, budget: usize, prefix: &HashSet<K>) -> BeamRun<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let width = width.max(1);
+    let mut nodes = vec![PathNode { parent: None, key: initial_state.key(), change: None }];
+    let mut kept_g: HashMap<K, usize> = HashMap::from([(initial_state.key(), initial_state.g())]);
+    let mut expansions = 0;
+    let mut dropped = 0;
+    let mut out_of_budget = false;
+
+    // Cheapest goal so far and the node it was reached through.
+    let mut goal: Option<(S, usize)> = None;
+    // (h, g, node) of the expanded state closest to the goal by h.
+    let mut closest: Option<(usize, usize, usize)> = None;
+
+    let mut layer = Vec::new();
+    if initial_state.is_goal() {
+        goal = Some((initial_state, 0));
+    }
+    else {
+        layer.push((initial_state, 0));
+    }
+
+    while !layer.is_empty() {
+        let mut candidates = Vec::new();
+
+        for (state, node) in layer {
+            if goal.as_ref().is_some_and(|(goal, _)| state.f() >= goal.g()) {
+                continue;
+            }
+            if expansions == budget {
+                out_of_budget = true;
+                break;
+            }
+
+            expansions += 1;
+            if closest.is_none_or(|(h, g, _)| (state.h(), state.g()) < (h, g)) {
+                closest = Some((state.h(), state.g(), node));
+            }
+
+            for (successor, change) in state.generate_traced_successors() {
+                let key = successor.key();
+                let g = successor.g();
+                if kept_g.get(&key).is_some_and(|&kept| kept <= g) || goal.as_ref().is_some_and(|(goal, _)| successor.f() >= goal.g()) {
+                    continue;
+                }
+
+                nodes.push(PathNode { parent: Some(node), key, change: Some(change) });
+                if successor.is_goal() {
+                    goal = Some((successor, nodes.len() - 1));
+                }
+                else {
+                    candidates.push((successor, nodes.len() - 1));
+                }
+            }
+        }
+
+        if out_of_budget {
+            break;
+        }
+
+        layer = select(candidates, width, prefix, &nodes, &mut kept_g, &mut dropped);
+    }
+
+    let best_node = goal.as_ref().map(|(_, node)| *node).or(closest.map(|(_, _, node)| node));
+    let best_prefix = best_node.map_or_else(Vec::new, |node| {
+        let mut keys: Vec<K> = path_nodes(&nodes, node).map(|node| node.key.clone()).collect();
+        keys.reverse();
+        keys
+    });
+
+    let result = if out_of_budget {
+        None
+    }
+    else {
+        goal.map(|(final_state, node)| {
+            let mut path: Vec<C> = path_nodes(&nodes, node).filter_map(|node| node.change.clone()).collect();
+            path.reverse();
+            TracedResult::new(path, expansions, final_state)
+        })
+    };
+
+    BeamRun {
+        result,
+        expansions,
+        dropped,
+        out_of_budget,
+        best_prefix
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulbStats {
+    pub expansions: usize,
+    // Times a layer went on with a worse slice than the best one.
+    pub backtracks: usize,
+    // Discrepancies allowed in the probe that found the goal, or in the last one tried.
+    pub discrepancies: usize
+}
+
+pub struct BulbOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    pub stats: BulbStats
+}
+
+// BULB, beam search using limited discrepancy backtracking: the successors of a layer are ranked
+// as for beam_search and cut into slices of `width`. A probe takes the best slice of every layer
+// and backtracks to the next slices when that dead-ends, taking slice i of a layer costs i
+// discrepancies. Probes allow 0, 1, ... up to `discrepancy_limit` discrepancies, the first goal
+// found is returned. Only the layers of the current probe are kept, and states in one of them
+// are not generated again, so memory stays that of a beam of the same width times the depth.
+// With an unbounded limit it finds a goal whenever one is reachable, at no particular cost.
+pub fn bulb<S, K, C>(initial_state: S, width: usize, discrepancy_limit: usize) -> BulbOutcome<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let width = width.max(1);
+    let mut stats = BulbStats { expansions: 0, backtracks: 0, discrepancies: 0 };
+    if initial_state.is_goal() {
+        return BulbOutcome { result: Some(TracedResult::new(Vec::new(), 0, initial_state)), stats };
+    }
+
+    for discrepancies in 0..=discrepancy_limit {
+        stats.discrepancies = discrepancies;
+        let mut probe = BulbProbe {
+            width,
+            nodes: vec![PathNode { parent: None, key: initial_state.key(), change: None }],
+            on_probe: HashSet::from([initial_state.key()]),
+            stats: &mut stats,
+            exhausted: true
+        };
+
+        if let Some((final_state, node)) = probe.descend(vec![(initial_state.clone(), 0)], discrepancies) {
+            let mut path: Vec<C> = path_nodes(&probe.nodes, node).filter_map(|node| node.change.clone()).collect();
+            path.reverse();
+            let iterations = stats.expansions;
+            return BulbOutcome { result: Some(TracedResult::new(path, iterations, final_state)), stats };
+        }
+        // No slice was left out for lack of discrepancies, so more won't reach anything new.
+        if probe.exhausted {
+            break;
+        }
+    }
+
+    BulbOutcome { result: None, stats }
+}
+
+struct BulbProbe<'a, K, C> {
+    width: usize,
+    nodes: Vec<PathNode<K, C>>,
+    // Keys of the layers from the initial state down to the current one.
+    on_probe: HashSet<K>,
+    stats: &'a mut BulbStats,
+    // Every slice of every layer was tried.
+    exhausted: bool
+}
+
+impl<K, C> BulbProbe<'_, K, C>
+where
+    K: Clone + Eq + Hash
+{
+    // Expands `layer` and goes down its slices while the discrepancies last. Gives the goal and
+    // its node.
+    fn descend<S>(&mut self, layer: Vec<(S, usize)>, discrepancies: usize) -> Option<(S, usize)>
+    where
+        S: TracedState<K, C>
+    {
+        let mut candidates = Vec::new();
+        for (state, node) in layer {
+            self.stats.expansions += 1;
+            for (successor, change) in state.generate_traced_successors() {
+                let key = successor.key();
+                if self.on_probe.contains(&key) {
+                    continue;
+                }
+
+                self.nodes.push(PathNode { parent: Some(node), key, change: Some(change) });
+                if successor.is_goal() {
+                    return Some((successor, self.nodes.len() - 1));
+                }
+                candidates.push((successor, self.nodes.len() - 1));
+            }
+        }
+
+        let mut ranked = ranked(candidates, &self.nodes).into_iter().peekable();
+        let mut slice_index = 0;
+        while ranked.peek().is_some() {
+            if slice_index > discrepancies {
+                self.exhausted = false;
+                return None;
+            }
+            if slice_index > 0 {
+                self.stats.backtracks += 1;
+            }
+
+            let slice: Vec<(S, usize)> = ranked.by_ref().take(self.width).collect();
+            let keys: Vec<K> = slice.iter().map(|(_, node)| self.nodes[*node].key.clone()).collect();
+            let nodes = self.nodes.len();
+            self.on_probe.extend(keys.iter().cloned());
+
+            if let Some(goal) = self.descend(slice, discrepancies - slice_index) {
+                return Some(goal);
+            }
+
+            // Nothing below the slice is needed any more.
+            self.nodes.truncate(nodes);
+            for key in &keys {
+                self.on_probe.remove(key);
+            }
+            slice_index += 1;
+        }
+
+        None
+    }
+}
+
+// The best `width` candidates of a layer, one per key, plus those on the reused prefix. The keys
+// of the prefix are matched at any depth, since a retry may reach them sooner.
+fn select<S, K, C>(candidates: Vec<(S, usize)>, width: usize, prefix: &HashSet<K>, nodes: &[PathNode<K, C>], kept_g: &mut HashMap<K, usize>, dropped: &mut usize) -> Vec<(S, usize)>
+where
+    S: AStarState<K>,
+    K: Clone + Eq + Hash
+{
+    let mut layer = Vec::new();
+    let mut forced = Vec::new();
+
+    for (state, node) in ranked(candidates, nodes) {
+        let key = &nodes[node].key;
+        if layer.len() < width {
+            layer.push((state, node));
+        }
+        else if prefix.contains(key) {
+            forced.push((state, node));
+        }
+        else {
+            *dropped += 1;
+        }
+    }
+
+    layer.append(&mut forced);
+    for (state, node) in &layer {
+        kept_g.insert(nodes[*node].key.clone(), state.g());
+    }
+
+    layer
+}
+
+// Candidates by f then h, the cheapest of each key only.
+fn ranked<S, K, C>(mut candidates: Vec<(S, usize)>, nodes: &[PathNode<K, C>]) -> Vec<(S, usize)>
+where
+    S: AStarState<K>,
+    K: Clone + Eq + Hash
+{
+    candidates.sort_by_key(|(state, _)| (state.f(), state.h()));
+
+    let mut seen = HashSet::new();
+    candidates.retain(|(_, node)| seen.insert(nodes[*node].key.clone()));
+    candidates
+}
+
+fn path_nodes<K, C>(nodes: &[PathNode<K, C>], last: usize) -> impl Iterator<Item = &PathNode<K, C>> {
+    std::iter::successors(Some(&nodes[last]), |node| node.parent.map(|parent| &nodes[parent]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    // The cheap looking branch through 1 is a dead end; the way to the goal starts with the
+    // worst f of the first layer.
+    const DECOY: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 1), (3, 3)],
+        &[(4, 1)],
+        &[(4, 1)],
+        &[(5, 1)],
+        &[],
+        &[]
+    ];
+    const DECOY_H: &[usize] = &[2, 1, 1, 1, 1, 0];
+
+    #[test]
+    fn test_narrow_beam_misses_the_goal() {
+        assert!(beam_search(GraphState::new(DECOY, DECOY_H, 0, 5), 2).is_none());
+
+        let result = beam_search(GraphState::new(DECOY, DECOY_H, 0, 5), 3).unwrap();
+        assert_eq!(result.path.to_vec(), vec![3, 5]);
+        assert_eq!(result.final_state.g(), 4);
+    }
+
+    #[test]
+    fn test_outcome_tells_whether_the_width_cut_anything() {
+        let narrow = beam_search_outcome(GraphState::new(DECOY, DECOY_H, 0, 5), 2);
+        assert!(narrow.result.is_none());
+        assert!(narrow.pruned());
+
+        let wide = beam_search_outcome(GraphState::new(DECOY, DECOY_H, 0, 5), 3);
+        assert!(!wide.pruned());
+        assert_eq!(wide.result.unwrap().final_state.g(), 4);
+    }
+
+    // With a width of 1 each layer keeps the successor with the lowest f (then h) that isn't
+    // kept already, so the search walks down like hill-climbing on f.
+    #[test]
+    fn test_width_one_climbs_the_best_successor() {
+        let mut solved = 0;
+        for seed in 0..5 {
+            let start = SlidingPuzzle::scrambled(seed, 12);
+            let outcome = beam_search_outcome(start.clone(), 1);
+
+            let mut climbed = Vec::new();
+            let mut visited = HashSet::from([start.tiles]);
+            let mut state = start.clone();
+            while !state.is_goal() {
+                let Some((next, change)) = state.generate_traced_successors()
+                    .into_iter()
+                    .filter(|(next, _)| !visited.contains(&next.tiles))
+                    .min_by_key(|(next, _)| (next.f(), next.h()))
+                else {
+                    break;
+                };
+                visited.insert(next.tiles);
+                climbed.push(change);
+                state = next;
+            }
+
+            match outcome.result {
+                Some(result) => {
+                    assert!(state.is_goal());
+                    assert_eq!(result.path.to_vec(), climbed);
+                    assert_eq!(outcome.expansions, climbed.len());
+                    solved += 1;
+                }
+                None => assert!(!state.is_goal())
+            }
+        }
+        // One of the climbs ends where every neighbour was visited.
+        assert_eq!(solved, 4);
+    }
+
+    #[test]
+    fn test_bulb_backtracks_past_the_decoy() {
+        let start = || GraphState::new(DECOY, DECOY_H, 0, 5);
+        assert!(beam_search(start(), 2).is_none());
+
+        // The best slice {1, 2} dead-ends at 4, the second one {3} leads to the goal. The probe
+        // without discrepancies expands 0, 1, 2 and 4, the second one also 3.
+        let outcome = bulb(start(), 2, 4);
+        let result = outcome.result.unwrap();
+        assert_eq!(result.path.to_vec(), vec![3, 5]);
+        assert_eq!(outcome.stats, BulbStats { expansions: 9, backtracks: 1, discrepancies: 1 });
+
+        let too_few = bulb(start(), 2, 0);
+        assert!(too_few.result.is_none());
+        assert_eq!(too_few.stats.backtracks, 0);
+    }
+
+    #[test]
+    fn test_bulb_stops_when_nothing_was_left_out() {
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        let outcome = bulb(GraphState::new(SPLIT, &[0, 0, 0], 0, 2), 1, usize::MAX);
+
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.stats.discrepancies, 0);
+    }
+
+    #[test]
+    fn test_widths_grow_until_a_goal_is_found() {
+        let outcome = iterative_beam_search(GraphState::new(DECOY, DECOY_H, 0, 5), 1, WidthGrowth::Add(1));
+
+        assert_eq!(outcome.width, Some(3));
+        assert_eq!(outcome.attempts.iter().map(|attempt| attempt.width).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(outcome.attempts.iter().map(|attempt| attempt.cost).collect::<Vec<_>>(), vec![None, None, Some(4)]);
+        assert_eq!(outcome.total_expansions(), outcome.attempts.iter().map(|attempt| attempt.expansions).sum::<usize>());
+        assert!(outcome.exhaustive);
+
+        assert_eq!(WidthGrowth::Double.next(3), 6);
+        assert_eq!(WidthGrowth::Custom(|width| width).next(3), 4);
+    }
+
+    #[test]
+    fn test_unreachable_goal_stops_when_exhaustive() {
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        let outcome = iterative_beam_search(GraphState::new(SPLIT, &[0, 0, 0], 0, 2), 1, WidthGrowth::Double);
+
+        assert!(outcome.result.is_none());
+        assert!(outcome.exhaustive);
+        assert_eq!(outcome.attempts.len(), 1);
+    }
+
+    #[test]
+    fn test_until_optimal_matches_astar() {
+        let config = IterativeBeam::new(1, WidthGrowth::Double).with_until_optimal(true);
+
+        let outcome = iterative_beam_search_with(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), &config);
+        assert_eq!(outcome.result.unwrap().final_state.g(), 9);
+
+        for seed in 0..3 {
+            let start = SlidingPuzzle::scrambled(seed, 24);
+            let optimal = traced_astar(start.clone()).unwrap().final_state.g();
+
+            let outcome = iterative_beam_search_with(start.clone(), &config);
+            assert!(outcome.exhaustive);
+            let result = outcome.result.unwrap();
+            assert_eq!(result.final_state.g(), optimal);
+            assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+        }
+    }
+
+    #[test]
+    fn test_budget_stops_the_attempts() {
+        let start = SlidingPuzzle::scrambled(3, 60);
+        let config = IterativeBeam::new(1, WidthGrowth::Double).with_until_optimal(true).with_budget(50);
+        let outcome = iterative_beam_search_with(start, &config);
+
+        assert_eq!(outcome.total_expansions(), 50);
+        assert!(!outcome.exhaustive);
+    }
+
+    #[test]
+    fn test_prefix_reuse_keeps_the_previous_solution() {
+        for seed in 0..3 {
+            let start = SlidingPuzzle::scrambled(seed, 24);
+            let config = IterativeBeam::new(2, WidthGrowth::Double).with_until_optimal(true).with_prefix_reuse(true);
+            let outcome = iterative_beam_search_with(start, &config);
+
+            // Every retry follows the last solution, so it finds it again or a cheaper one.
+            let costs: Vec<usize> = outcome.attempts.iter().filter_map(|attempt| attempt.cost).collect();
+            assert!(costs.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", costs);
+            assert_eq!(costs.len(), outcome.attempts.iter().skip_while(|attempt| attempt.cost.is_none()).count());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::build_result}};
+
+// traced_astar with the open list ordered by `priority` instead of f, lowest first. Closed keys are
+// never reopened, so only priorities that grow along paths like g give optimal paths.
+pub fn best_first_search<S, K, C>(initial_state: S, priority: impl Fn(&S) -> usize) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut open_list = OpenList::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+
+    let initial_priority = priority(&initial_state);
+    open_list.insert(initial_state.key(), Prioritized::new(TracedStateWrapper::new(initial_state), initial_priority));
+
+    while let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() {
+        if current_state.is_goal() {
+            return Some(build_result(current_state, closed_list));
+        }
+
+        let successors = current_state.generate_states();
+
+        closed_list.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+
+            if closed_list.contains_key(&successor_key) {
+                continue;
+            }
+
+            let successor_priority = priority(&successor.state);
+            open_list.insert(successor_key, Prioritized::new(successor, successor_priority));
+        }
+    }
+
+    None
+}
+
+// Greedy best-first search: the open list is ordered by h alone, so it heads for the goal without
+// looking at g. Usually far fewer expansions than A*, with no bound on the path cost.
+pub fn greedy_best_first<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    best_first_search(initial_state, S::h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H};
+
+    #[test]
+    fn test_greedy_follows_h() {
+        // h says 2 (h = 4) is closer than 1 (h = 5) and 3 (h = 7), so greedy goes 0 -> 2 -> 5 -> 7.
+        let result = greedy_best_first(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)).unwrap();
+
+        assert_eq!(result.path.to_vec(), vec![2, 5, 7]);
+        assert_eq!(result.final_state.g(), 11);
+        assert_eq!(result.iterations, 3);
+    }
+}
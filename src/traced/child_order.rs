@@ -0,0 +1,62 @@
+use std::{cmp::Ordering, hash::Hash};
+
+use crate::astar_state::AStarState;
+
+// Order in which the depth-first searches descend into the successors of a state. It decides
+// nothing about the result, only how soon a goal or a tight bound turns up: in the last iteration
+// of IDA* and throughout dfbb, descending into the best child first saves most of the work.
+#[non_exhaustive]
+pub enum ChildOrder<S> {
+    // The generator's order.
+    AsGenerated,
+    // Lowest h first.
+    ByH,
+    // Lowest f first.
+    ByF,
+    // Least first by the comparison.
+    Custom(fn(&S, &S) -> Ordering)
+}
+
+impl<S> ChildOrder<S> {
+    // Sorts a batch of successors to be popped off its end: the first to descend into comes last,
+    // and equal ones keep the generator's order. The sort is stable and in place, so the batch
+    // stays in the searches' scratch buffer.
+    pub(crate) fn sort_for_popping<K, C>(&self, batch: &mut [(S, C)])
+    where
+        S: AStarState<K>,
+        K: Clone + Eq + Hash
+    {
+        match self {
+            ChildOrder::AsGenerated => {}
+            ChildOrder::ByH => batch.sort_by_key(|(state, _)| state.h()),
+            ChildOrder::ByF => batch.sort_by_key(|(state, _)| state.f()),
+            ChildOrder::Custom(compare) => batch.sort_by(|(a, _), (b, _)| compare(a, b))
+        }
+        batch.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H};
+
+    fn popped(order: ChildOrder<GraphState>, nodes: &[usize]) -> Vec<usize> {
+        let mut batch: Vec<(GraphState, ())> = nodes.iter()
+            .map(|&node| (GraphState { node, g_cost: 10 - node, ..GraphState::new(EIGHT_NODES, EIGHT_NODES_H, node, 7) }, ()))
+            .collect();
+        order.sort_for_popping(&mut batch);
+        batch.into_iter().rev().map(|(state, _)| state.node).collect()
+    }
+
+    #[test]
+    fn test_orders_pop_least_first() {
+        assert_eq!(EIGHT_NODES_H[1..6], [5, 4, 7, 3, 3]);
+        assert_eq!(popped(ChildOrder::AsGenerated, &[1, 2, 3, 4]), vec![1, 2, 3, 4]);
+        // 5 and 4 tie on h and stay in the generator's order.
+        assert_eq!(popped(ChildOrder::ByH, &[5, 3, 4, 1]), vec![5, 4, 1, 3]);
+        // f is 10 - node + h: 14, 12, 14 and 9.
+        assert_eq!(popped(ChildOrder::ByF, &[1, 2, 3, 4]), vec![4, 2, 1, 3]);
+        assert_eq!(popped(ChildOrder::Custom(|a, b| b.node.cmp(&a.node)), &[2, 4, 1, 3]), vec![4, 3, 2, 1]);
+    }
+}
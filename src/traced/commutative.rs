@@ -0,0 +1,57 @@
+// Partial-order reduction for traced searches: of the two orders of a pair of independent changes,
+// only the canonical one is searched (`SearchOptions::with_commutative_pruning`).
+//
+// A successor reached through `change` from a parent reached through `parent_change` is skipped
+// when the two are independent and `change` is canonically before `parent_change`, since the same
+// state is reached by applying them the other way round. That keeps an optimal path as long as:
+//
+//   * independent changes commute wherever the pair is applicable: from any state where one
+//     order can be applied, the other can too, reaching the same state at the same cost;
+//   * `independent` is symmetric and `canonical_before` is a strict order (irreflexive and
+//     transitive) on the changes it relates.
+//
+// Any path can then be reordered, swapping adjacent independent changes, into one of the same cost
+// where no change is preceded by an independent change it is canonically before.
+pub trait CommutativeChanges {
+    fn independent(a: &Self, b: &Self) -> bool;
+    fn canonical_before(a: &Self, b: &Self) -> bool;
+}
+
+// Whether the successor reached through `change` after `parent_change` is the redundant order.
+pub(crate) fn is_redundant_order<C>(parent_change: &C, change: &C) -> bool
+where
+    C: CommutativeChanges
+{
+    C::independent(parent_change, change) && C::canonical_before(change, parent_change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, search_options::SearchOptions, search_stats::SkipReason, test_support::{Lattice, Step}, traced::traced_astar::traced_astar_with_options};
+
+    #[test]
+    fn test_redundant_order() {
+        assert!(is_redundant_order(&Step(2), &Step(0)));
+        assert!(!is_redundant_order(&Step(0), &Step(2)));
+        assert!(!is_redundant_order(&Step(1), &Step(1)));
+    }
+
+    #[test]
+    fn test_pruning_keeps_one_order_of_each_path() {
+        let plain = traced_astar_with_options(Lattice::new(6), SearchOptions::new());
+        let pruned = traced_astar_with_options(Lattice::new(6), SearchOptions::new().with_commutative_pruning());
+
+        let plain_result = plain.result.unwrap();
+        let pruned_result = pruned.result.unwrap();
+        assert_eq!(pruned_result.final_state.g(), plain_result.final_state.g());
+
+        // Canonical paths are sorted by axis, and every successor that would break the order is
+        // skipped before reaching the closed or open list.
+        let path = pruned_result.path.to_vec();
+        assert!(path.windows(2).all(|pair| pair[0].0 <= pair[1].0), "{:?}", path);
+        assert!(pruned.stats.pruned[SkipReason::Commuted] > 0);
+        assert_eq!(pruned.stats.pruned[SkipReason::Closed] + pruned.stats.pruned[SkipReason::WorseOpen], 0);
+        assert!(plain.stats.pruned[SkipReason::Closed] + plain.stats.pruned[SkipReason::WorseOpen] > 0);
+    }
+}
@@ -0,0 +1,289 @@
+use std::hash::Hash;
+
+use crate::{search_stats::SearchStats, traced::{child_order::ChildOrder, dfs_stack::{DfsFrame, DfsStack}, outcome::SearchOutcome, result::TracedResult, state::TracedState}, transposition::{TranspositionEntry, TranspositionTable}};
+
+// A state on the current path. Its children still to be descended into are in the scratch buffer
+// from `base` up, the next one last.
+struct Frame<K, C> {
+    key: K,
+    g: usize,
+    change: Option<C>,
+    base: usize,
+    descended: bool,
+    // Shallowest depth on the path of the states skipped in the subtree for being on it,
+    // usize::MAX if none was. Skipping the root or a state below it only cuts cycles, skipping
+    // one above it makes the subtree's bound hold for this path only.
+    shallowest_skip: usize
+}
+
+impl<K, C> DfsFrame<K> for Frame<K, C> {
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+struct Context<'t, S, K, C>
+where
+    K: Eq + Hash
+{
+    incumbent: usize,
+    best: Option<(Vec<C>, S)>,
+    table: Option<&'t mut TranspositionTable<K>>,
+    order: ChildOrder<S>,
+    scratch: Vec<(S, C)>,
+    stats: SearchStats<K>
+}
+
+// Depth-first branch and bound: descends into the children of lowest f first and cuts every
+// state whose f is at least the cost of the best goal found so far, the incumbent. Each goal
+// reached below it tightens the incumbent, and once the tree is exhausted the last goal is
+// optimal for an admissible h. Memory is linear in the depth of the path, which keeps cycles out
+// by skipping the states already on it. `initial_upper_bound` starts the incumbent off, so only
+// paths cheaper than it are looked for and None means there is none. The stack is explicit,
+// since the first dive can go far deeper than the solution. `iterations` counts expansions.
+pub fn dfbb<S, K, C>(initial_state: S, initial_upper_bound: Option<usize>) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    search(initial_state, initial_upper_bound, None, ChildOrder::ByF).result
+}
+
+// dfbb with a transposition table. A subtree searched to the end proves that no goal through its
+// root costs less than the incumbent left afterwards, and later visits to the key use that bound
+// in place of h(). A subtree that skipped a state above its root for being on the path proves
+// nothing, since that state may lead to the goal from elsewhere, and isn't stored. Domains with
+// many transpositions need the table, the plain search explores every path to a state again.
+pub fn dfbb_with_table<S, K, C>(initial_state: S, initial_upper_bound: Option<usize>, table: &mut TranspositionTable<K>) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    search(initial_state, initial_upper_bound, Some(table), ChildOrder::ByF).result
+}
+
+// dfbb descending into the children of each state in `order` instead of lowest f first. The
+// stats count the expansions and cutoffs, and the cutoffs on a first child. Only ByF can drop
+// the rest of a state's children at the first one cut, the other orders look at each of them.
+pub fn dfbb_with_order<S, K, C>(initial_state: S, initial_upper_bound: Option<usize>, order: ChildOrder<S>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    search(initial_state, initial_upper_bound, None, order)
+}
+
+fn search<S, K, C>(initial_state: S, initial_upper_bound: Option<usize>, table: Option<&mut TranspositionTable<K>>, order: ChildOrder<S>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let sorted_by_f = matches!(order, ChildOrder::ByF);
+    let mut context = Context {
+        incumbent: initial_upper_bound.unwrap_or(usize::MAX),
+        best: None,
+        table,
+        order,
+        scratch: Vec::new(),
+        stats: SearchStats::default()
+    };
+
+    if initial_state.f() >= context.incumbent {
+        return SearchOutcome::new(None, context.stats);
+    }
+    if initial_state.is_goal() {
+        return SearchOutcome::new(Some(TracedResult::new(Vec::new(), 0, initial_state)), context.stats);
+    }
+
+    let mut stack = DfsStack::new();
+    stack.push(expand(initial_state, None, &mut context));
+
+    while let Some(frame) = stack.top_mut() {
+        if context.scratch.len() == frame.base {
+            let frame = stack.pop().expect("the frame was just looked at");
+            let depth = stack.depth();
+            if let Some(parent) = stack.top_mut() {
+                parent.shallowest_skip = parent.shallowest_skip.min(frame.shallowest_skip);
+            }
+            if let Some(table) = &mut context.table
+                && frame.shallowest_skip >= depth
+            {
+                let bound = context.incumbent.saturating_sub(frame.g);
+                table.store(frame.key, TranspositionEntry { proven_budget: bound, lower_bound: bound });
+            }
+            continue;
+        }
+        let (child, change) = context.scratch.pop().expect("the frame has children left");
+
+        if child.f() >= context.incumbent {
+            context.stats.cutoffs += 1;
+            context.stats.first_child_cutoffs += usize::from(!frame.descended);
+            frame.descended = true;
+            // Sorted by f, none of the rest is any better.
+            if sorted_by_f {
+                context.stats.cutoffs += context.scratch.len() - frame.base;
+                context.scratch.truncate(frame.base);
+            }
+            continue;
+        }
+        let key = child.key();
+        if let Some(depth) = stack.depth_on_path(&key) {
+            let frame = stack.top_mut().expect("the frame is still there");
+            frame.shallowest_skip = frame.shallowest_skip.min(depth);
+            continue;
+        }
+        if below_table_bound(&child, &key, &mut context) {
+            continue;
+        }
+
+        let frame = stack.top_mut().expect("the frame is still there");
+        frame.descended = true;
+        if child.is_goal() {
+            let path = stack.frames().iter().filter_map(|frame| frame.change.clone()).chain([change]).collect();
+            context.incumbent = child.g();
+            context.best = Some((path, child));
+            continue;
+        }
+
+        stack.push(expand(child, Some(change), &mut context));
+    }
+
+    let expansions = context.stats.expansions;
+    SearchOutcome::new(context.best.map(|(path, final_state)| TracedResult::new(path, expansions, final_state)), context.stats)
+}
+
+// Whether the table's bound for the key cuts the state at the current incumbent.
+fn below_table_bound<S, K, C>(state: &S, key: &K, context: &mut Context<S, K, C>) -> bool
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let Some(table) = &mut context.table else {
+        return false;
+    };
+    let Some(entry) = table.get(key) else {
+        return false;
+    };
+
+    let cut = state.g().saturating_add(entry.lower_bound) >= context.incumbent;
+    if cut {
+        table.record_cutoff();
+    }
+    cut
+}
+
+fn expand<S, K, C>(state: S, change: Option<C>, context: &mut Context<S, K, C>) -> Frame<K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let key = state.key();
+    let base = context.scratch.len();
+    context.scratch.extend(state.generate_traced_successors());
+    context.stats.expansions += 1;
+    context.stats.generated += context.scratch.len() - base;
+
+    context.order.sort_for_popping(&mut context.scratch[base..]);
+
+    Frame { key, g: state.g(), change, base, descended: false, shallowest_skip: usize::MAX }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, rng::SplitMix64, test_support::{random_graph, GraphState, Lattice, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::{replay::replay, traced_astar::traced_astar}, transposition::ReplacementPolicy};
+
+    #[test]
+    fn test_graph_optimum() {
+        for h in [EIGHT_NODES_H, ZERO_H] {
+            let result = dfbb(GraphState::new(EIGHT_NODES, h, 0, 7), None).unwrap();
+            assert_eq!(result.cost, 9);
+            assert_eq!(replay(&GraphState::new(EIGHT_NODES, h, 0, 7), result.path.to_vec()).unwrap().g(), 9);
+        }
+
+        assert_eq!(dfbb(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 7), None).unwrap().path.len(), 0);
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        assert!(dfbb(GraphState::new(SPLIT, &[0, 0, 0], 0, 2), None).is_none());
+    }
+
+    #[test]
+    fn test_upper_bound() {
+        // Only paths cheaper than the bound count, so the optimum itself prunes everything.
+        assert!(dfbb(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), Some(9)).is_none());
+        assert_eq!(dfbb(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), Some(10)).unwrap().cost, 9);
+
+        let loose = dfbb(Lattice::new(4), None).unwrap();
+        let tight = dfbb(Lattice::new(4), Some(10)).unwrap();
+        assert_eq!((loose.cost, tight.cost), (9, 9));
+        assert!(tight.iterations <= loose.iterations);
+    }
+
+    #[test]
+    fn test_sliding_puzzle_with_and_without_table() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let optimal = traced_astar(start.clone()).unwrap().cost;
+
+            let mut table = TranspositionTable::new(1 << 16, ReplacementPolicy::DepthPreferred);
+            for result in [dfbb(start.clone(), None).unwrap(), dfbb_with_table(start.clone(), None, &mut table).unwrap()] {
+                assert_eq!(result.cost, optimal);
+                assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+            }
+        }
+    }
+
+    // Reached first through 0-3-4-7, 8 fails only because 7 is on the path, and must not be cut
+    // when 0-3-8 reaches it more cheaply.
+    #[test]
+    fn test_table_keeps_bounds_of_paths_through_the_stack() {
+        const CROSSED: &[&[(usize, usize)]] = &[&[(3, 5)], &[], &[(9, 3)], &[(8, 5), (4, 4)], &[(7, 3)], &[], &[], &[(8, 4), (1, 3), (2, 4)], &[(7, 1), (4, 4)], &[]];
+        let start = || GraphState::new(CROSSED, &[0; 10], 0, 9);
+        assert_eq!(traced_astar(start()).unwrap().cost, 18);
+
+        for policy in [ReplacementPolicy::AlwaysReplace, ReplacementPolicy::DepthPreferred] {
+            let mut table = TranspositionTable::new(64, policy);
+            let result = dfbb_with_table(start(), None, &mut table).unwrap();
+            assert_eq!(result.cost, 18);
+            assert_eq!(replay(&start(), result.path.to_vec()).unwrap().g(), 18);
+        }
+    }
+
+    #[test]
+    fn test_table_keeps_the_optimum_on_random_graphs() {
+        let mut rng = SplitMix64::new(11);
+        for undirected in [false, true] {
+            for _ in 0..200 {
+                let start = GraphState::new(random_graph(&mut rng, 10, 18, undirected), &[0; 10], 0, 9);
+                let optimal = traced_astar(start.clone()).map(|result| result.cost);
+                let mut table = TranspositionTable::new(64, ReplacementPolicy::DepthPreferred);
+                assert_eq!(dfbb_with_table(start, None, &mut table).map(|result| result.cost), optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_orders_and_cutoffs() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let optimal = traced_astar(start.clone()).unwrap().cost;
+
+            let by_f = dfbb_with_order(start.clone(), Some(optimal + 3), ChildOrder::ByF);
+            let by_h = dfbb_with_order(start.clone(), Some(optimal + 3), ChildOrder::ByH);
+            let generated = dfbb_with_order(start.clone(), Some(optimal + 3), ChildOrder::AsGenerated);
+            for outcome in [&by_f, &by_h, &generated] {
+                assert_eq!(outcome.result.as_ref().unwrap().cost, optimal);
+            }
+
+            // g grows by one for every child, so ByH and ByF descend alike. ByF drops the rest of
+            // the children at the first one cut, which counts them without looking at them.
+            assert_eq!(by_f.stats.expansions, by_h.stats.expansions);
+            assert_eq!(by_f.stats.cutoffs, by_h.stats.cutoffs);
+            assert!(by_f.stats.first_child_cutoffs <= by_f.stats.cutoffs);
+            assert_eq!(by_f.stats.expansions, dfbb(start, Some(optimal + 3)).unwrap().iterations);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashMap, hash::Hash, mem};
+
+// A frame of a depth-first search, standing for a state on the current path.
+pub(crate) trait DfsFrame<K> {
+    fn key(&self) -> &K;
+}
+
+// The explicit stack of the depth-first searches, in place of recursion, so that the depth of a
+// path is only bounded by the heap. It keeps the keys of the frames' states and their depths for
+// the cycle check, and for iterative deepening the threshold of the current iteration along with
+// the smallest value seen above it, which the next iteration starts from.
+pub(crate) struct DfsStack<K, F> {
+    frames: Vec<F>,
+    path_keys: HashMap<K, usize>,
+    threshold: usize,
+    next_threshold: usize
+}
+
+impl<K, F> DfsStack<K, F>
+where
+    K: Clone + Eq + Hash,
+    F: DfsFrame<K>
+{
+    pub(crate) fn new() -> Self {
+        Self::deepening(0)
+    }
+
+    // A stack whose first iteration is bounded by `threshold`.
+    pub(crate) fn deepening(threshold: usize) -> Self {
+        DfsStack {
+            frames: Vec::new(),
+            path_keys: HashMap::new(),
+            threshold,
+            next_threshold: usize::MAX
+        }
+    }
+
+    pub(crate) fn push(&mut self, frame: F) {
+        self.path_keys.insert(frame.key().clone(), self.frames.len());
+        self.frames.push(frame);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<F> {
+        let frame = self.frames.pop()?;
+        self.path_keys.remove(frame.key());
+        Some(frame)
+    }
+
+    // Every frame, the root first, leaving the stack empty.
+    pub(crate) fn take_frames(&mut self) -> Vec<F> {
+        self.path_keys.clear();
+        mem::take(&mut self.frames)
+    }
+
+    pub(crate) fn top_mut(&mut self) -> Option<&mut F> {
+        self.frames.last_mut()
+    }
+
+    // The root first. Only rbfs and dfbb look at the frames in place.
+    #[cfg(any(test, feature = "ida"))]
+    pub(crate) fn frames(&self) -> &[F] {
+        &self.frames
+    }
+
+    pub(crate) fn on_path(&self, key: &K) -> bool {
+        self.path_keys.contains_key(key)
+    }
+
+    // Where the key is on the path, 0 for the root.
+    #[cfg(feature = "ida")]
+    pub(crate) fn depth_on_path(&self, key: &K) -> Option<usize> {
+        self.path_keys.get(key).copied()
+    }
+
+    // The number of frames.
+    #[cfg(feature = "ida")]
+    pub(crate) fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub(crate) fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    // Records a value above the threshold, e.g. the f of a state cut off.
+    pub(crate) fn exceeded(&mut self, value: usize) {
+        self.next_threshold = self.next_threshold.min(value);
+    }
+
+    // Empties the stack and moves the threshold to the smallest value recorded above it. None
+    // when there was none, or only usize::MAX: the next iteration would see nothing new.
+    pub(crate) fn next_iteration(&mut self) -> Option<usize> {
+        self.frames.clear();
+        self.path_keys.clear();
+        let next = mem::replace(&mut self.next_threshold, usize::MAX);
+        if next == usize::MAX {
+            return None;
+        }
+
+        self.threshold = next;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl DfsFrame<u32> for u32 {
+        fn key(&self) -> &u32 {
+            self
+        }
+    }
+
+    #[test]
+    fn test_path_keys_follow_the_frames() {
+        let mut stack = DfsStack::new();
+        stack.push(1);
+        stack.push(2);
+        assert!(stack.on_path(&1) && stack.on_path(&2));
+
+        assert_eq!(stack.pop(), Some(2));
+        assert!(!stack.on_path(&2));
+        stack.push(3);
+        assert_eq!(stack.frames(), &[1, 3]);
+        assert_eq!(stack.take_frames(), vec![1, 3]);
+        assert!(!stack.on_path(&1) && stack.pop().is_none());
+    }
+
+    #[test]
+    fn test_iterations_start_from_the_smallest_excess() {
+        let mut stack: DfsStack<u32, u32> = DfsStack::deepening(5);
+        stack.push(1);
+        stack.exceeded(9);
+        stack.exceeded(7);
+        assert_eq!((stack.next_iteration(), stack.threshold()), (Some(7), 7));
+        assert!(!stack.on_path(&1));
+
+        // Nothing above the threshold, or only unreachable values, ends the deepening.
+        assert_eq!(stack.next_iteration(), None);
+        stack.exceeded(usize::MAX);
+        assert_eq!((stack.next_iteration(), stack.threshold()), (None, 7));
+    }
+}
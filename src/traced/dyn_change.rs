@@ -0,0 +1,345 @@
+use std::{any::{Any, TypeId}, collections::HashMap, fmt::{self, Debug, Display, Formatter}, sync::Arc};
+
+type Format = Box<dyn Fn(&dyn Any, &mut Formatter<'_>) -> fmt::Result + Send + Sync>;
+type Erased = Box<dyn Any + Send>;
+
+// What a registered change type can do once erased, each entry downcasting to the type it was
+// made for.
+struct ChangeType {
+    name: &'static str,
+    format: Format,
+    clone: fn(&(dyn Any + Send)) -> Erased,
+    eq: fn(&dyn Any, &dyn Any) -> bool,
+    #[cfg(feature = "serde")]
+    serialize: Option<fn(&dyn Any) -> serde_json::Result<serde_json::Value>>,
+    #[cfg(feature = "serde")]
+    deserialize: Option<fn(serde_json::Value) -> serde_json::Result<Erased>>
+}
+
+// A change of any registered type, for domains whose move families don't share an enum: the
+// generator returns `DynChange`s made by `ChangeRegistry::erase`, and the path formats, compares
+// and (with the `serde` feature) serializes them through what was registered for each type.
+pub struct DynChange {
+    change: Erased,
+    change_type: Arc<ChangeType>
+}
+
+impl DynChange {
+    // Name the type was registered under.
+    pub fn type_name(&self) -> &'static str {
+        self.change_type.name
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.change.downcast_ref()
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.change.is::<T>()
+    }
+}
+
+impl Clone for DynChange {
+    fn clone(&self) -> Self {
+        DynChange {
+            change: (self.change_type.clone)(self.change.as_ref()),
+            change_type: self.change_type.clone()
+        }
+    }
+}
+
+// Changes of different types are never equal.
+impl PartialEq for DynChange {
+    fn eq(&self, other: &Self) -> bool {
+        self.change.as_ref().type_id() == other.change.as_ref().type_id() && (self.change_type.eq)(self.change.as_ref(), other.change.as_ref())
+    }
+}
+
+impl Display for DynChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        (self.change_type.format)(self.change.as_ref(), f)
+    }
+}
+
+impl Debug for DynChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.type_name(), self)
+    }
+}
+
+// Serialized as `{"type": name, "change": value}`, failing for types registered without serde.
+#[cfg(feature = "serde")]
+impl serde::Serialize for DynChange {
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        use serde::ser::{Error, SerializeStruct};
+
+        let Some(serialize) = self.change_type.serialize else {
+            return Err(Ser::Error::custom(format!("change type {} was registered without serde", self.type_name())));
+        };
+        let change = serialize(self.change.as_ref()).map_err(Ser::Error::custom)?;
+
+        let mut entry = serializer.serialize_struct("DynChange", 2)?;
+        entry.serialize_field("type", self.type_name())?;
+        entry.serialize_field("change", &change)?;
+        entry.end()
+    }
+}
+
+// The change types an erased search may see, each with its name and how to format it.
+#[derive(Default)]
+pub struct ChangeRegistry {
+    types: HashMap<TypeId, Arc<ChangeType>>,
+    by_name: HashMap<&'static str, TypeId>
+}
+
+impl ChangeRegistry {
+    pub fn new() -> Self {
+        ChangeRegistry::default()
+    }
+
+    // Registers T under `name`, formatted by `format`. A later registration of the same type or
+    // name replaces the earlier one.
+    pub fn with_type<T>(self, name: &'static str, format: impl Fn(&T, &mut Formatter<'_>) -> fmt::Result + Send + Sync + 'static) -> Self
+    where
+        T: Any + Send + Clone + PartialEq
+    {
+        self.registered(ChangeType {
+            name,
+            format: erased_format(format),
+            clone: clone_erased::<T>,
+            eq: eq_erased::<T>,
+            #[cfg(feature = "serde")]
+            serialize: None,
+            #[cfg(feature = "serde")]
+            deserialize: None
+        }, TypeId::of::<T>())
+    }
+
+    // with_type, also serializing T with serde for JSON export and `decode`.
+    #[cfg(feature = "serde")]
+    pub fn with_serde_type<T>(self, name: &'static str, format: impl Fn(&T, &mut Formatter<'_>) -> fmt::Result + Send + Sync + 'static) -> Self
+    where
+        T: Any + Send + Clone + PartialEq + serde::Serialize + serde::de::DeserializeOwned
+    {
+        self.registered(ChangeType {
+            name,
+            format: erased_format(format),
+            clone: clone_erased::<T>,
+            eq: eq_erased::<T>,
+            serialize: Some(serialize_erased::<T>),
+            deserialize: Some(deserialize_erased::<T>)
+        }, TypeId::of::<T>())
+    }
+
+    fn registered(mut self, change_type: ChangeType, type_id: TypeId) -> Self {
+        self.by_name.insert(change_type.name, type_id);
+        self.types.insert(type_id, Arc::new(change_type));
+        self
+    }
+
+    // Panics for a type that was never registered, as a missing match arm would for an enum.
+    pub fn erase<T: Any + Send>(&self, change: T) -> DynChange {
+        let change_type = self.types.get(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("change type {} is not registered", std::any::type_name::<T>()));
+
+        DynChange {
+            change: Box::new(change),
+            change_type: change_type.clone()
+        }
+    }
+
+    // A change serialized by DynChange, back with the type registered under its name.
+    #[cfg(feature = "serde")]
+    pub fn decode(&self, value: serde_json::Value) -> serde_json::Result<DynChange> {
+        use serde::de::Error;
+
+        let serde_json::Value::Object(mut entry) = value else {
+            return Err(serde_json::Error::custom("a change is an object with a type and a change"));
+        };
+        let name = match entry.remove("type") {
+            Some(serde_json::Value::String(name)) => name,
+            _ => return Err(serde_json::Error::custom("missing change type"))
+        };
+        let change_type = self.by_name.get(name.as_str())
+            .and_then(|type_id| self.types.get(type_id))
+            .ok_or_else(|| serde_json::Error::custom(format!("unknown change type {}", name)))?;
+        let deserialize = change_type.deserialize
+            .ok_or_else(|| serde_json::Error::custom(format!("change type {} was registered without serde", name)))?;
+
+        Ok(DynChange {
+            change: deserialize(entry.remove("change").unwrap_or(serde_json::Value::Null))?,
+            change_type: change_type.clone()
+        })
+    }
+
+    // A path written with serde_json, e.g. `serde_json::to_string(&result.path.to_vec())`.
+    #[cfg(feature = "serde")]
+    pub fn path_from_json(&self, json: &str) -> serde_json::Result<Vec<DynChange>> {
+        let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+        entries.into_iter().map(|entry| self.decode(entry)).collect()
+    }
+}
+
+fn erased_format<T: Any>(format: impl Fn(&T, &mut Formatter<'_>) -> fmt::Result + Send + Sync + 'static) -> Format {
+    Box::new(move |change, f| format(change.downcast_ref().expect("formatted as its own type"), f))
+}
+
+fn clone_erased<T: Any + Send + Clone>(change: &(dyn Any + Send)) -> Erased {
+    Box::new(change.downcast_ref::<T>().expect("cloned as its own type").clone())
+}
+
+fn eq_erased<T: Any + PartialEq>(a: &dyn Any, b: &dyn Any) -> bool {
+    a.downcast_ref::<T>() == b.downcast_ref::<T>()
+}
+
+#[cfg(feature = "serde")]
+fn serialize_erased<T: Any + serde::Serialize>(change: &dyn Any) -> serde_json::Result<serde_json::Value> {
+    serde_json::to_value(change.downcast_ref::<T>().expect("serialized as its own type"))
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_erased<T: Any + Send + serde::de::DeserializeOwned>(value: serde_json::Value) -> serde_json::Result<Erased> {
+    Ok(Box::new(serde_json::from_value::<T>(value)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, traced::{replay::replay, state::TracedState, traced_astar::traced_astar}};
+
+    const LIMIT: usize = 32;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Step(i8);
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Scale(u8);
+
+    #[cfg(not(feature = "serde"))]
+    fn registry() -> ChangeRegistry {
+        ChangeRegistry::new()
+            .with_type::<Step>("step", |step, f| write!(f, "{:+}", step.0))
+            .with_type::<Scale>("scale", |scale, f| write!(f, "x{}", scale.0))
+    }
+
+    #[cfg(feature = "serde")]
+    fn registry() -> ChangeRegistry {
+        ChangeRegistry::new()
+            .with_serde_type::<Step>("step", |step, f| write!(f, "{:+}", step.0))
+            .with_serde_type::<Scale>("scale", |scale, f| write!(f, "x{}", scale.0))
+    }
+
+    // A number on 0..=LIMIT, moved by steps of one and by doubling, each costing 1. The two move
+    // families are unrelated types, erased by the registry.
+    #[derive(Clone)]
+    struct Number<'a> {
+        value: usize,
+        g: usize,
+        goal: usize,
+        registry: &'a ChangeRegistry
+    }
+
+    impl AStarState<usize> for Number<'_> {
+        fn key(&self) -> usize {
+            self.value
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.g
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.value == self.goal
+        }
+    }
+
+    impl TracedState<usize, DynChange> for Number<'_> {
+        fn generate_traced_successors(&self) -> Vec<(Self, DynChange)> {
+            let moved = |value: usize| Number { value, g: self.g + 1, ..self.clone() };
+            let mut successors = Vec::new();
+            for step in [-1, 1] {
+                if let Some(value) = self.value.checked_add_signed(step as isize).filter(|&value| value <= LIMIT) {
+                    successors.push((moved(value), self.registry.erase(Step(step))));
+                }
+            }
+            if self.value * 2 <= LIMIT {
+                successors.push((moved(self.value * 2), self.registry.erase(Scale(2))));
+            }
+            successors
+        }
+    }
+
+    #[test]
+    fn test_search_mixes_change_types() {
+        let registry = registry();
+        let start = Number { value: 3, g: 0, goal: 13, registry: &registry };
+        let result = traced_astar(start.clone()).unwrap();
+
+        // 3 -> 6 -> 12 -> 13 is the only path of cost 3.
+        let path = result.path.to_vec();
+        assert_eq!(path, vec![registry.erase(Scale(2)), registry.erase(Scale(2)), registry.erase(Step(1))]);
+        assert_eq!(path.iter().map(DynChange::type_name).collect::<Vec<_>>(), vec!["scale", "scale", "step"]);
+        assert_eq!(path[2].downcast_ref::<Step>(), Some(&Step(1)));
+        assert!(path[0].is::<Scale>() && path[0].downcast_ref::<Step>().is_none());
+        assert_eq!(replay(&start, path).unwrap().value, 13);
+    }
+
+    #[test]
+    fn test_erased_changes_format_and_compare() {
+        let registry = registry();
+        let path = [registry.erase(Scale(2)), registry.erase(Step(-1)), registry.erase(Step(1))];
+
+        assert_eq!(path.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "), "x2 -1 +1");
+        assert_eq!(format!("{:?}", path[1]), "step(-1)");
+        assert_eq!(path[1].clone(), path[1]);
+        assert_ne!(path[1], path[2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not registered")]
+    fn test_unregistered_type_panics() {
+        registry().erase(0_u32);
+    }
+
+    #[cfg(feature = "operator-stats")]
+    #[test]
+    fn test_operator_stats_count_types() {
+        use crate::operator_stats::OperatorStats;
+
+        let registry = registry();
+        let mut stats = OperatorStats::new();
+        for goal in [13, 25] {
+            let result = traced_astar(Number { value: 3, g: 0, goal, registry: &registry }).unwrap();
+            stats.record_result(&result, |change| change.type_name());
+        }
+
+        // 3 -> 6 -> 12 -> 24 -> 25 for the second goal.
+        assert_eq!(stats.count(&"scale"), 5);
+        assert_eq!(stats.count(&"step"), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let registry = registry();
+        let result = traced_astar(Number { value: 3, g: 0, goal: 13, registry: &registry }).unwrap();
+
+        let json = serde_json::to_string(&result.path.to_vec()).unwrap();
+        assert_eq!(json, r#"[{"type":"scale","change":2},{"type":"scale","change":2},{"type":"step","change":1}]"#);
+        assert_eq!(registry.path_from_json(&json).unwrap(), result.path.to_vec());
+
+        assert!(registry.path_from_json(r#"[{"type":"jump","change":1}]"#).is_err());
+        let unserializable = ChangeRegistry::new().with_type::<Step>("step", |step, f| write!(f, "{}", step.0));
+        assert!(serde_json::to_string(&unserializable.erase(Step(1))).is_err());
+    }
+}
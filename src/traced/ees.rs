@@ -0,0 +1,292 @@
+use std::{collections::{BTreeSet, HashMap}, hash::Hash};
+
+use crate::{astar_state::AStarState, traced::{focal_lists::FocalLists, result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::build_result}};
+
+// The estimates EES works with besides the admissible h(). Both default to h(), so a state can
+// implement the trait without methods and provide only the estimate it has.
+pub trait EesState<K, C>: TracedState<K, C>
+where
+    K: Clone + Eq + Hash
+{
+    // Estimate of the cost to the nearest goal that may overestimate, but closer than h().
+    fn h_hat(&self) -> usize {
+        self.h()
+    }
+
+    // Estimate of the number of changes to the nearest goal.
+    fn d(&self) -> usize {
+        self.h()
+    }
+}
+
+// The queue an expansion of `ees` was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EesQueue {
+    // Least d among the states within the bound of the least f_hat.
+    Focal,
+    // Least f_hat.
+    Open,
+    // Least f.
+    Cleanup
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EesStats {
+    pub expansions: usize,
+    pub from_focal: usize,
+    pub from_open: usize,
+    pub from_cleanup: usize,
+    // Closed states queued again after a cheaper path reached them.
+    pub reopened: usize
+}
+
+impl EesStats {
+    pub fn from_queue(&self, queue: EesQueue) -> usize {
+        match queue {
+            EesQueue::Focal => self.from_focal,
+            EesQueue::Open => self.from_open,
+            EesQueue::Cleanup => self.from_cleanup
+        }
+    }
+
+    fn record(&mut self, queue: EesQueue) {
+        self.expansions += 1;
+        match queue {
+            EesQueue::Focal => self.from_focal += 1,
+            EesQueue::Open => self.from_open += 1,
+            EesQueue::Cleanup => self.from_cleanup += 1
+        }
+    }
+}
+
+pub struct EesOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    pub stats: EesStats
+}
+
+// The lists of EES: OPEN by f_hat and FOCAL, the entries with f_hat within the bound, by d, plus
+// CLEANUP by f over the same entries.
+struct Queues<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    lists: FocalLists<K, TracedStateWrapper<S, K, C>>,
+    cleanup: BTreeSet<(usize, usize)>
+}
+
+impl<S, K, C> Queues<S, K, C>
+where
+    S: EesState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn new() -> Self {
+        Queues { lists: FocalLists::new(), cleanup: BTreeSet::new() }
+    }
+
+    fn g(&self, key: &K) -> Option<usize> {
+        self.lists.get(key).map(|state| state.state.g())
+    }
+
+    // Queues the state, in place of any entry of the same key.
+    fn insert(&mut self, state: TracedStateWrapper<S, K, C>) {
+        let key = state.key();
+        if let Some(id) = self.lists.id(&key) {
+            self.remove(id);
+        }
+
+        let (f, f_hat, d) = (state.state.f(), state.state.g().saturating_add(state.state.h_hat()), state.state.d());
+        let id = self.lists.insert(key, state, f_hat, d);
+        self.cleanup.insert((f, id));
+    }
+
+    fn remove(&mut self, id: usize) -> TracedStateWrapper<S, K, C> {
+        let state = self.lists.remove(id);
+        self.cleanup.remove(&(state.state.f(), id));
+        state
+    }
+
+    // Takes out the state EES expands next, with the queue it was chosen from: the best of FOCAL
+    // if its f is within `weight` times the least f, else the best of OPEN under the same
+    // condition, else the best of CLEANUP.
+    fn extract(&mut self, weight: f64) -> Option<(TracedStateWrapper<S, K, C>, EesQueue)> {
+        let &(best_f, best_f_id) = self.cleanup.first()?;
+        let (best_f_hat, best_f_hat_id) = self.lists.first_open().expect("OPEN and CLEANUP hold the same entries");
+        self.lists.set_bound((best_f_hat as f64 * weight) as usize);
+        let best_d_id = self.lists.first_focal().expect("the entry with the least f_hat is always in FOCAL");
+
+        let within = |id: usize| self.lists.value(id).state.f() as f64 <= best_f as f64 * weight;
+        let (id, queue) = if within(best_d_id) {
+            (best_d_id, EesQueue::Focal)
+        }
+        else if within(best_f_hat_id) {
+            (best_f_hat_id, EesQueue::Open)
+        }
+        else {
+            (best_f_id, EesQueue::Cleanup)
+        };
+
+        Some((self.remove(id), queue))
+    }
+}
+
+// Explicit Estimation Search: bounded-suboptimal like focal_search, for domains where an
+// inadmissible estimate (`EesState::h_hat`) is far closer to the real cost than the admissible
+// h(). FOCAL holds the open states whose f_hat = g + h_hat is within `bound` times the least
+// f_hat, and its state with the least d is expanded when its f is within `bound` times the least
+// f. Otherwise the state with the least f_hat, under the same condition, and failing both the
+// state with the least f, which raises the lower bound. With an admissible h the result costs at
+// most `bound` times the optimum, whatever h_hat and d are. Bounds below 1 count as 1. Closed
+// states reached again more cheaply are reopened.
+pub fn ees<S, K, C>(initial_state: S, bound: f64) -> EesOutcome<S, K, C>
+where
+    S: EesState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let weight = bound.max(1.0);
+    let mut queues = Queues::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let mut stats = EesStats::default();
+
+    queues.insert(TracedStateWrapper::new(initial_state));
+
+    while let Some((current_state, queue)) = queues.extract(weight) {
+        if current_state.is_goal() {
+            return EesOutcome { result: Some(build_result(current_state, closed_list)), stats };
+        }
+
+        stats.record(queue);
+        let successors = current_state.generate_states();
+
+        closed_list.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+            let g = successor.state.g();
+
+            if queues.g(&successor_key).is_some_and(|open| open <= g) {
+                continue;
+            }
+            match closed_list.get(&successor_key) {
+                Some(closed) if closed.state.g() <= g => continue,
+                // The closed entry stays until the state is expanded again, for the paths that
+                // already go through it.
+                Some(_) => stats.reopened += 1,
+                None => {}
+            }
+
+            queues.insert(successor);
+        }
+    }
+
+    EesOutcome { result: None, stats }
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use super::*;
+    use crate::{grid::{map::GridMap, movement::MovementModel, region::GoalRegion, state::{Direction, GridState}}, search_options::SearchOptions, test_support::{assert_within_bound_on_random_grids, corner_to_corner_map}, traced::traced_astar::{traced_astar, traced_astar_with_options}, untraced::multi_target::multi_target_search};
+
+    impl EesState<(usize, usize), Direction> for GridState<'_> {}
+
+    // A grid position whose admissible h is a quarter of the distance estimate of the map, while
+    // h_hat and d are the exact distances to the goal.
+    #[derive(Clone)]
+    struct Weak<'a, 'd> {
+        state: GridState<'a>,
+        distances: &'d HashMap<(usize, usize), usize>
+    }
+
+    impl AStarState<(usize, usize)> for Weak<'_, '_> {
+        fn key(&self) -> (usize, usize) {
+            self.state.key()
+        }
+
+        fn h(&self) -> usize {
+            self.state.h() / 4
+        }
+
+        fn f(&self) -> usize {
+            self.g() + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.state.g()
+        }
+
+        fn is_goal(&self) -> bool {
+            self.state.is_goal()
+        }
+    }
+
+    impl TracedState<(usize, usize), Direction> for Weak<'_, '_> {
+        fn generate_traced_successors(&self) -> Vec<(Self, Direction)> {
+            self.state.generate_traced_successors()
+                .into_iter()
+                .map(|(state, change)| (Weak { state, distances: self.distances }, change))
+                .collect()
+        }
+    }
+
+    impl EesState<(usize, usize), Direction> for Weak<'_, '_> {
+        fn h_hat(&self) -> usize {
+            self.distances.get(&self.key()).copied().unwrap_or(self.state.h())
+        }
+
+        fn d(&self) -> usize {
+            self.h_hat()
+        }
+    }
+
+    // Distances from every cell to `goal`, by a uniform cost search from it, since moves cost the
+    // same both ways.
+    fn distances_to(map: &GridMap, goal: (usize, usize)) -> HashMap<(usize, usize), usize> {
+        let cells: Vec<(usize, usize)> = (0..30).flat_map(|y| (0..30).map(move |x| (x, y))).collect();
+        multi_target_search(GridState::new(map, goal), &cells)
+    }
+
+    #[test]
+    fn test_cost_within_the_bound_on_random_grids() {
+        assert_within_bound_on_random_grids(&[1.0, 1.2, 2.0], |_, start, bound| {
+            let outcome = ees(start, bound);
+            assert_eq!(outcome.stats.from_focal + outcome.stats.from_open + outcome.stats.from_cleanup, outcome.stats.expansions);
+            outcome.result
+        });
+
+        // Weak states with a strong h_hat still keep to the bound of the admissible h.
+        assert_within_bound_on_random_grids(&[1.5], |map, start, bound| {
+            let distances = distances_to(map, (29, 29));
+            ees(Weak { state: start, distances: &distances }, bound).result.map(|result| result.map_state(|weak| weak.state))
+        });
+    }
+
+    #[test]
+    fn test_strong_h_hat_beats_weighted_astar() {
+        let goal = GoalRegion::Cell(29, 29);
+        let (mut ees_expansions, mut weighted_expansions) = (0, 0);
+        for seed in 0..10 {
+            let map = corner_to_corner_map(seed, MovementModel::EightWayOctile);
+            let distances = distances_to(&map, (29, 29));
+            let start = Weak { state: GridState::with_region(&map, (0, 0), &goal), distances: &distances };
+            let Some(optimal) = traced_astar(start.clone()) else {
+                continue;
+            };
+
+            let weighted = traced_astar_with_options(start.clone(), SearchOptions::new().with_weight(2.0));
+            let outcome = ees(start, 2.0);
+            let cost = outcome.result.unwrap().cost;
+            assert!(cost <= 2 * optimal.cost, "seed {}: {} vs {}", seed, cost, optimal.cost);
+            assert!(outcome.stats.expansions < weighted.stats.expansions, "seed {}: {:?} vs {}", seed, outcome.stats, weighted.stats.expansions);
+
+            ees_expansions += outcome.stats.expansions;
+            weighted_expansions += weighted.stats.expansions;
+        }
+        // 1305 against 4963 over the 8 maps with a path.
+        assert!(ees_expansions * 3 < weighted_expansions, "{} vs {}", ees_expansions, weighted_expansions);
+    }
+}
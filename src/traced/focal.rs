@@ -0,0 +1,126 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, traced::{focal_lists::FocalLists, result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::build_result}};
+
+// focal_search's lists: OPEN by f and FOCAL by the secondary heuristic.
+struct Lists<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    lists: FocalLists<K, TracedStateWrapper<S, K, C>>
+}
+
+impl<S, K, C> Lists<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn new() -> Self {
+        Lists { lists: FocalLists::new() }
+    }
+
+    fn g(&self, key: &K) -> Option<usize> {
+        self.lists.get(key).map(|state| state.state.g())
+    }
+
+    // Queues the state, in place of any entry of the same key.
+    fn insert(&mut self, state: TracedStateWrapper<S, K, C>, secondary: usize) {
+        let f = state.state.f();
+        self.lists.insert(state.key(), state, f, secondary);
+    }
+
+    // Moves the bound to `weight` times the lowest f in OPEN, and takes the FOCAL entry with the
+    // lowest secondary value out of both lists.
+    fn extract(&mut self, weight: f64) -> Option<TracedStateWrapper<S, K, C>> {
+        let (f_min, _) = self.lists.first_open()?;
+        self.lists.set_bound((f_min as f64 * weight) as usize);
+        let id = self.lists.first_focal().expect("the entry with the lowest f is always in FOCAL");
+        Some(self.lists.remove(id))
+    }
+}
+
+// Focal search (A*-epsilon): of the open states whose f is within (1 + epsilon) times the lowest
+// f in the open list, expands the one with the lowest `secondary` value, e.g. an estimate of the
+// number of moves to go. With an admissible h the cost of the result is at most (1 + epsilon)
+// times the optimum. Closed states reached again more cheaply are reopened, which the bound
+// needs when h isn't consistent. An epsilon of 0, or a negative one, gives the optimum with ties
+// among the lowest f broken by `secondary`.
+pub fn focal_search<S, K, C>(initial_state: S, epsilon: f64, secondary: impl Fn(&S) -> usize) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let weight = 1.0 + epsilon.max(0.0);
+    let mut lists = Lists::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+
+    let initial_secondary = secondary(&initial_state);
+    lists.insert(TracedStateWrapper::new(initial_state), initial_secondary);
+
+    while let Some(current_state) = lists.extract(weight) {
+        if current_state.is_goal() {
+            return Some(build_result(current_state, closed_list));
+        }
+
+        let successors = current_state.generate_states();
+
+        closed_list.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+            let g = successor.state.g();
+
+            if closed_list.get(&successor_key).is_some_and(|closed| closed.state.g() <= g) || lists.g(&successor_key).is_some_and(|open| open <= g) {
+                continue;
+            }
+
+            // A reopened state keeps its closed entry until it is expanded again, for the paths
+            // that already go through it.
+            let successor_secondary = secondary(&successor.state);
+            lists.insert(successor, successor_secondary);
+        }
+    }
+
+    None
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use super::*;
+    use crate::{grid::{map::GridMap, movement::MovementModel, region::GoalRegion, state::GridState}, test_support::{assert_within_bound_on_random_grids, corner_to_corner_map, GraphState, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::traced_astar};
+
+    #[test]
+    fn test_cost_within_the_bound_on_random_grids() {
+        // Bounds of 1 + epsilon for epsilons of 0.1, 0.5 and 2.
+        assert_within_bound_on_random_grids(&[1.1, 1.5, 3.0], |_, start, bound| focal_search(start, bound - 1.0, |state| state.h()));
+    }
+
+    #[test]
+    fn test_zero_epsilon_is_astar() {
+        let result = focal_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), 0.0, |state| state.h()).unwrap();
+        let astar = traced_astar(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)).unwrap();
+        assert_eq!((result.cost, result.path.to_vec()), (astar.cost, astar.path.to_vec()));
+
+        let goal = GoalRegion::Cell(29, 29);
+        for seed in 0..10 {
+            let map = corner_to_corner_map(seed, MovementModel::EightWayOctile);
+            let start = GridState::with_region(&map, (0, 0), &goal);
+            let costs = (focal_search(start.clone(), 0.0, |state| state.h()).map(|result| result.cost), traced_astar(start).map(|result| result.cost));
+            assert_eq!(costs.0, costs.1, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_wider_focal_expands_less() {
+        // Unit moves leave many states at the lowest f, and the distance to go picks among them.
+        let map = GridMap::new(40, 40);
+        let goal = GoalRegion::Cell(39, 39);
+        let start = GridState::with_region(&map, (0, 0), &goal);
+
+        let astar = traced_astar(start.clone()).unwrap();
+        let focal = focal_search(start, 1.0, |state| state.h()).unwrap();
+        assert!(focal.iterations < astar.iterations, "{} vs {}", focal.iterations, astar.iterations);
+        assert!(focal.cost <= 2 * astar.cost);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::{BTreeSet, HashMap}, hash::Hash};
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    primary: usize,
+    secondary: usize
+}
+
+// The open entries of the focal searches by id, in two orders: OPEN by a primary value and FOCAL,
+// the entries whose primary value is within the bound, by a secondary one. focal_search orders
+// by f and the secondary heuristic, EES by f_hat and d and keeps a third order of its own. An
+// entry is in FOCAL only while it is in OPEN. Ids grow with every insertion, so ties go to the
+// older entry.
+pub(crate) struct FocalLists<K, V> {
+    entries: HashMap<usize, Entry<K, V>>,
+    ids: HashMap<K, usize>,
+    open: BTreeSet<(usize, usize)>,
+    focal: BTreeSet<(usize, usize, usize)>,
+    // Primary value up to which OPEN entries are in FOCAL.
+    bound: usize,
+    next_id: usize
+}
+
+impl<K, V> FocalLists<K, V>
+where
+    K: Clone + Eq + Hash
+{
+    pub(crate) fn new() -> Self {
+        FocalLists { entries: HashMap::new(), ids: HashMap::new(), open: BTreeSet::new(), focal: BTreeSet::new(), bound: 0, next_id: 0 }
+    }
+
+    pub(crate) fn id(&self, key: &K) -> Option<usize> {
+        self.ids.get(key).copied()
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<&V> {
+        self.id(key).map(|id| self.value(id))
+    }
+
+    pub(crate) fn value(&self, id: usize) -> &V {
+        &self.entries[&id].value
+    }
+
+    // Queues the value, in place of any entry of the same key, and returns its id.
+    pub(crate) fn insert(&mut self, key: K, value: V, primary: usize, secondary: usize) -> usize {
+        if let Some(id) = self.id(&key) {
+            self.remove(id);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.open.insert((primary, id));
+        if primary <= self.bound {
+            self.focal.insert((secondary, primary, id));
+        }
+        self.ids.insert(key.clone(), id);
+        self.entries.insert(id, Entry { key, value, primary, secondary });
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) -> V {
+        let Entry { key, value, primary, secondary } = self.entries.remove(&id).expect("a queued id has an entry");
+        self.ids.remove(&key);
+        self.open.remove(&(primary, id));
+        self.focal.remove(&(secondary, primary, id));
+        value
+    }
+
+    // The least primary value in OPEN, with its id.
+    pub(crate) fn first_open(&self) -> Option<(usize, usize)> {
+        self.open.first().copied()
+    }
+
+    // Moves the bound, after which `first_focal` is within it.
+    pub(crate) fn set_bound(&mut self, bound: usize) {
+        if bound > self.bound {
+            // Entries above the old bound that are now under the new one join FOCAL. When the
+            // bound drops, entries left above it are dropped from FOCAL as they come up instead.
+            let joining: Vec<(usize, usize)> = self.open.range((self.bound.saturating_add(1), 0)..=(bound, usize::MAX)).copied().collect();
+            for (primary, id) in joining {
+                self.focal.insert((self.entries[&id].secondary, primary, id));
+            }
+        }
+        self.bound = bound;
+
+        while let Some(&(_, primary, _)) = self.focal.first() {
+            if primary <= self.bound {
+                break;
+            }
+            self.focal.pop_first();
+        }
+    }
+
+    // The id of the FOCAL entry with the least secondary value. Once the bound is at least the
+    // least primary value, FOCAL is only empty when OPEN is.
+    pub(crate) fn first_focal(&self) -> Option<usize> {
+        self.focal.first().map(|&(_, _, id)| id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focal_follows_the_bound() {
+        let mut lists = FocalLists::new();
+        for (key, primary, secondary) in [('a', 10, 5), ('b', 12, 1), ('c', 15, 0)] {
+            lists.insert(key, key, primary, secondary);
+        }
+        assert_eq!(lists.first_open(), Some((10, 0)));
+
+        lists.set_bound(12);
+        assert_eq!(lists.first_focal().map(|id| *lists.value(id)), Some('b'));
+        lists.set_bound(20);
+        assert_eq!(lists.first_focal().map(|id| *lists.value(id)), Some('c'));
+
+        // c stays in OPEN when the bound drops below it.
+        lists.set_bound(11);
+        assert_eq!(lists.first_focal().map(|id| *lists.value(id)), Some('a'));
+        lists.set_bound(15);
+        assert_eq!(lists.first_focal().map(|id| *lists.value(id)), Some('c'));
+    }
+
+    #[test]
+    fn test_insert_replaces_the_entry_of_its_key() {
+        let mut lists = FocalLists::new();
+        let old = lists.insert('a', 1, 10, 0);
+        lists.insert('b', 2, 10, 0);
+        let new = lists.insert('a', 3, 10, 0);
+        assert_ne!(old, new);
+        assert_eq!((lists.get(&'a'), lists.id(&'a')), (Some(&3), Some(new)));
+
+        // Ties on both values go to the older entry, now b.
+        lists.set_bound(10);
+        assert_eq!(lists.remove(lists.first_focal().unwrap()), 2);
+        assert_eq!(lists.remove(lists.first_focal().unwrap()), 3);
+        assert_eq!((lists.first_open(), lists.first_focal(), lists.get(&'a')), (None, None, None));
+    }
+}
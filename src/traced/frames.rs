@@ -0,0 +1,104 @@
+use std::{fmt::Display, hash::Hash};
+
+use crate::traced::{replay::replay_states, result::TracedResult, state::TracedState};
+
+impl<S, K, C> TracedResult<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // One frame per state of the path, `initial` first and the goal last, each rendered by
+    // `render`: path length + 1 frames. Panics when the path doesn't replay from `initial`, which
+    // has to be the state the search started from.
+    pub fn frames(&self, initial: S, render: impl Fn(&S) -> String) -> Vec<String>
+    where
+        C: Clone + PartialEq
+    {
+        self.replayed(initial)
+            .iter()
+            .map(render)
+            .collect()
+    }
+
+    // frames with a first line naming the step and the change that led to the frame.
+    pub fn frames_diff(&self, initial: S, render: impl Fn(&S) -> String) -> Vec<String>
+    where
+        C: Clone + PartialEq + Display
+    {
+        let steps = self.path.len();
+        let changes = std::iter::once(None).chain(self.path.iter().map(Some));
+
+        self.replayed(initial)
+            .iter()
+            .zip(changes)
+            .enumerate()
+            .map(|(step, (state, change))| match change {
+                Some(change) => format!("step {}/{}: {}\n{}", step, steps, change, render(state)),
+                None => format!("step 0/{}: start\n{}", steps, render(state))
+            })
+            .collect()
+    }
+
+    fn replayed(&self, initial: S) -> Vec<S>
+    where
+        C: Clone + PartialEq
+    {
+        replay_states(initial, self.path.iter()).expect("the path replays from the initial state of the search")
+    }
+}
+
+// Shows the frames one after the other in a terminal, clearing the screen before each and
+// waiting `delay` between them.
+#[cfg(feature = "std")]
+pub fn play(frames: &[String], delay: std::time::Duration) {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    for (index, frame) in frames.iter().enumerate() {
+        if index > 0 {
+            std::thread::sleep(delay);
+        }
+        // Clear the screen and move the cursor to the top left.
+        let _ = write!(stdout, "\x1b[2J\x1b[H{}", frame);
+        let _ = stdout.flush();
+    }
+    let _ = writeln!(stdout);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::traced_astar};
+
+    fn render(state: &GraphState) -> String {
+        format!("node {}{}", state.node, if state.is_goal() { " (goal)" } else { "" })
+    }
+
+    #[test]
+    fn test_one_frame_per_state() {
+        let start = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let result = traced_astar(start.clone()).unwrap();
+
+        let frames = result.frames(start.clone(), render);
+        assert_eq!(frames.len(), result.path.len() + 1);
+        assert_eq!(frames[0], "node 0");
+        assert_eq!(frames.last().unwrap(), "node 7 (goal)");
+
+        let diff = result.frames_diff(start, render);
+        assert_eq!(diff.len(), frames.len());
+        assert_eq!(diff[0], "step 0/4: start\nnode 0");
+        assert_eq!(diff[1], format!("step 1/4: {}\n{}", result.path.to_vec()[0], frames[1]));
+    }
+
+    #[test]
+    fn test_last_frame_is_the_goal() {
+        for seed in 0..3 {
+            let start = SlidingPuzzle::scrambled(seed, 30);
+            let result = traced_astar(start.clone()).unwrap();
+            let frames = result.frames(start, |puzzle| format!("{:?} {}", puzzle.tiles, puzzle.is_goal()));
+
+            assert_eq!(frames.len(), result.path.len() + 1);
+            assert!(frames.last().unwrap().ends_with("true"));
+            assert_eq!(frames.iter().filter(|frame| frame.ends_with("true")).count(), 1);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}}};
+
+// A state fringe_search has reached, linked into the fringe while it waits to be expanded.
+struct Entry<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    state: TracedStateWrapper<S, K, C>,
+    previous: Option<usize>,
+    next: Option<usize>,
+    in_fringe: bool
+}
+
+// The fringe: a doubly linked list through the entries, so a state moves next to its new parent
+// in constant time.
+struct Fringe<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    entries: Vec<Entry<S, K, C>>,
+    head: Option<usize>
+}
+
+impl<S, K, C> Fringe<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // Links `id` in after `at`, or first when `at` is None.
+    fn link_after(&mut self, at: Option<usize>, id: usize) {
+        let next = match at {
+            Some(at) => self.entries[at].next.replace(id),
+            None => self.head.replace(id)
+        };
+        if let Some(next) = next {
+            self.entries[next].previous = Some(id);
+        }
+
+        let entry = &mut self.entries[id];
+        entry.previous = at;
+        entry.next = next;
+        entry.in_fringe = true;
+    }
+
+    fn unlink(&mut self, id: usize) {
+        let entry = &mut self.entries[id];
+        let (previous, next) = (entry.previous.take(), entry.next.take());
+        entry.in_fringe = false;
+
+        match previous {
+            Some(previous) => self.entries[previous].next = next,
+            None => self.head = next
+        }
+        if let Some(next) = next {
+            self.entries[next].previous = previous;
+        }
+    }
+}
+
+// Fringe search: IDA*'s thresholds without its repeated work. The states waiting to be expanded
+// stay in a list between iterations, and every state reached is cached with its best g. Each
+// iteration walks the list once, expanding the states within the f threshold and putting their
+// successors right after them, so they are visited in the same pass, and leaving the others for
+// the next iteration, whose threshold is the lowest f left over. A state reached again with a
+// lower g moves next to its new parent. The list is never sorted, so this trades A*'s heap upkeep
+// for visits to states over the threshold, which pays off on domains with few distinct f values
+// like grids. Optimal for an admissible h. `iterations` counts expansions, a state expanded
+// again after its g improved counting twice.
+pub fn fringe_search<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut fringe = Fringe { entries: Vec::new(), head: None };
+    let mut cache: HashMap<K, usize> = HashMap::new();
+    let mut expansions = 0;
+
+    let mut threshold = initial_state.f();
+    cache.insert(initial_state.key(), 0);
+    fringe.entries.push(Entry { state: TracedStateWrapper::new(initial_state), previous: None, next: None, in_fringe: false });
+    fringe.link_after(None, 0);
+
+    while fringe.head.is_some() {
+        let mut lowest_over = usize::MAX;
+        let mut current = fringe.head;
+
+        while let Some(id) = current {
+            let f = fringe.entries[id].state.f();
+            if f > threshold {
+                lowest_over = lowest_over.min(f);
+                current = fringe.entries[id].next;
+                continue;
+            }
+
+            if fringe.entries[id].state.is_goal() {
+                return Some(rebuild(fringe.entries, &cache, id, expansions));
+            }
+
+            expansions += 1;
+            let mut after = id;
+            for successor in fringe.entries[id].state.generate_states() {
+                let key = successor.key();
+                let successor_id = match cache.get(&key) {
+                    Some(&known) if fringe.entries[known].state.g() <= successor.g() => continue,
+                    Some(&known) => {
+                        if fringe.entries[known].in_fringe {
+                            // A key listed twice among the successors, cheaper the second time.
+                            if known == after {
+                                after = fringe.entries[known].previous.expect("successors follow their parent");
+                            }
+                            fringe.unlink(known);
+                        }
+                        fringe.entries[known].state = successor;
+                        known
+                    }
+                    None => {
+                        let successor_id = fringe.entries.len();
+                        cache.insert(key, successor_id);
+                        fringe.entries.push(Entry { state: successor, previous: None, next: None, in_fringe: false });
+                        successor_id
+                    }
+                };
+
+                fringe.link_after(Some(after), successor_id);
+                after = successor_id;
+            }
+
+            current = fringe.entries[id].next;
+            fringe.unlink(id);
+        }
+
+        threshold = lowest_over;
+    }
+
+    None
+}
+
+// Follows the prev_key links of the goal back through the cache.
+fn rebuild<S, K, C>(mut entries: Vec<Entry<S, K, C>>, cache: &HashMap<K, usize>, goal: usize, expansions: usize) -> TracedResult<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut path = Vec::new();
+    let mut current = Some(goal);
+    while let Some(id) = current {
+        let state = &mut entries[id].state;
+        path.extend(state.change.take());
+        current = state.prev_key.as_ref().and_then(|key| cache.get(key).copied());
+    }
+    path.reverse();
+
+    let goal = entries.swap_remove(goal).state;
+    TracedResult::new(path, expansions, goal.state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, Lattice, SlidingPuzzle, DETOUR, DETOUR_H, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    #[test]
+    fn test_graph_optimum() {
+        for h in [EIGHT_NODES_H, ZERO_H] {
+            let result = fringe_search(GraphState::new(EIGHT_NODES, h, 0, 7)).unwrap();
+            assert_eq!(result.cost, 9);
+            assert_eq!(replay(&GraphState::new(EIGHT_NODES, h, 0, 7), result.path.to_vec()).unwrap().g(), 9);
+        }
+
+        assert_eq!(fringe_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 7)).unwrap().path.len(), 0);
+        assert!(fringe_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0)).is_none());
+    }
+
+    #[test]
+    fn test_cheaper_path_found_later_moves_the_state() {
+        // 1 is reached first at g 1, 3 through it at g 11, then again through 2 at g 8.
+        let result = fringe_search(GraphState::new(DETOUR, DETOUR_H, 0, 3)).unwrap();
+        assert_eq!(result.cost, 8);
+        assert_eq!(result.path.to_vec(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_sliding_puzzle_and_lattice() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 60);
+            let result = fringe_search(start.clone()).unwrap();
+            assert_eq!(result.cost, traced_astar(start.clone()).unwrap().cost);
+            assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+        }
+
+        let result = fringe_search(Lattice::new(6)).unwrap();
+        assert_eq!((result.cost, result.path.len()), (15, 15));
+    }
+}
@@ -0,0 +1,339 @@
+use std::{collections::HashMap, hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash}};
+
+use crate::{astar_state::AStarState, open_list::OpenList, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}}};
+
+// An open state of a suspended search, with the key and change it was reached through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrontierEntry<S, K, C> {
+    pub key: K,
+    pub state: S,
+    pub g: usize,
+    pub f: usize,
+    pub prev_key: Option<K>,
+    pub change: Option<C>,
+    pub depth: usize
+}
+
+// A closed key of a suspended search and the key and change it was reached through, enough to
+// rebuild the path of a later goal without the closed states themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClosedLink<K, C> {
+    pub key: K,
+    pub prev_key: Option<K>,
+    pub change: Option<C>
+}
+
+// Open list and closed keys of a search stopped at an expansion budget, to continue it later or
+// elsewhere with traced_astar_from_frontier. `split` hands disjoint parts of the open list to
+// several continuations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frontier<S, K, C> {
+    pub open: Vec<FrontierEntry<S, K, C>>,
+    pub closed: Vec<ClosedLink<K, C>>,
+    // Expansions of every run before this frontier was taken.
+    pub expansions: usize
+}
+
+#[non_exhaustive]
+pub enum Handoff<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    Solved(TracedResult<S, K, C>),
+    // The budget ran out first.
+    Suspended(Frontier<S, K, C>),
+    // The open list ran out, so no goal is reachable from it.
+    Exhausted
+}
+
+impl<S, K, C> Frontier<S, K, C>
+where
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    // Partitions the open entries by key hash into `parts` frontiers (at least one). Every part
+    // gets all the closed keys, so no continuation expands them again. A continuation can still
+    // reach keys of another part through its own successors.
+    pub fn split(self, parts: usize) -> Vec<Frontier<S, K, C>> {
+        let parts = parts.max(1);
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let mut frontiers: Vec<Frontier<S, K, C>> = (0..parts)
+            .map(|_| Frontier { open: Vec::new(), closed: self.closed.clone(), expansions: self.expansions })
+            .collect();
+
+        for entry in self.open {
+            let part = (hasher.hash_one(&entry.key) % parts as u64) as usize;
+            frontiers[part].open.push(entry);
+        }
+
+        frontiers
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S, K, C> Frontier<S, K, C>
+where
+    S: AStarState<K>,
+    K: Clone + Eq + Hash
+{
+    // Writes the frontier as JSON.
+    pub fn export<W: std::io::Write>(&self, writer: W) -> std::io::Result<()>
+    where
+        S: serde::Serialize,
+        K: serde::Serialize,
+        C: serde::Serialize
+    {
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    // Reads a frontier written by `export`. Entries whose state no longer has the exported g and
+    // f, say because the state type changed in between, are rejected as invalid data.
+    pub fn import<R: std::io::Read>(reader: R) -> std::io::Result<Self>
+    where
+        S: serde::de::DeserializeOwned,
+        K: serde::de::DeserializeOwned,
+        C: serde::de::DeserializeOwned
+    {
+        let frontier: Frontier<S, K, C> = serde_json::from_reader(reader)?;
+
+        if frontier.open.iter().any(|entry| entry.state.g() != entry.g || entry.state.f() != entry.f) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frontier entry state does not match its exported g and f"));
+        }
+
+        Ok(frontier)
+    }
+}
+
+// traced_astar that stops after `max_expansions` expansions and hands back its frontier.
+pub fn traced_astar_with_budget<S, K, C>(initial_state: S, max_expansions: usize) -> Handoff<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut open_list = OpenList::new();
+    open_list.insert(initial_state.key(), TracedStateWrapper::new(initial_state));
+
+    run(open_list, HashMap::new(), 0, max_expansions)
+}
+
+// Continues a suspended search from its frontier for up to `max_expansions` more expansions.
+// Closed keys of the frontier are never expanded again, and paths lead back through them to the
+// initial state. The result's iterations only count this run. With a split frontier, the
+// cheapest of the continuations' results is optimal when the heuristic is consistent.
+pub fn traced_astar_from_frontier<S, K, C>(frontier: Frontier<S, K, C>, max_expansions: usize) -> Handoff<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let Frontier { open, closed, expansions } = frontier;
+    let mut open_list = OpenList::new();
+
+    for entry in open {
+        let wrapper = TracedStateWrapper {
+            state: entry.state,
+            prev_key: entry.prev_key,
+            change: entry.change,
+            depth: entry.depth,
+            g_override: None
+        };
+        open_list.insert(entry.key, wrapper);
+    }
+
+    let links = closed.into_iter()
+        .map(|link| (link.key, (link.prev_key, link.change)))
+        .collect();
+
+    run(open_list, links, expansions, max_expansions)
+}
+
+type Links<K, C> = HashMap<K, (Option<K>, Option<C>)>;
+
+fn run<S, K, C>(mut open_list: OpenList<K, TracedStateWrapper<S, K, C>>, mut links: Links<K, C>, previous_expansions: usize, max_expansions: usize) -> Handoff<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+
+    while closed_list.len() < max_expansions {
+        let Some(current_state) = open_list.extract_min() else {
+            return Handoff::Exhausted;
+        };
+
+        if current_state.is_goal() {
+            return Handoff::Solved(build_result(current_state, closed_list, links));
+        }
+
+        let successors = current_state.generate_states();
+
+        closed_list.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+
+            if closed_list.contains_key(&successor_key) || links.contains_key(&successor_key) {
+                continue;
+            }
+
+            open_list.insert(successor_key, successor);
+        }
+    }
+
+    let expansions = previous_expansions + closed_list.len();
+    for (key, closed) in closed_list {
+        links.insert(key, (closed.prev_key, closed.change));
+    }
+
+    let mut open = Vec::with_capacity(open_list.len());
+    while let Some(wrapper) = open_list.extract_min() {
+        open.push(FrontierEntry {
+            key: wrapper.key(),
+            g: wrapper.g(),
+            f: wrapper.f(),
+            state: wrapper.state,
+            prev_key: wrapper.prev_key,
+            change: wrapper.change,
+            depth: wrapper.depth
+        });
+    }
+
+    let closed = links.into_iter()
+        .map(|(key, (prev_key, change))| ClosedLink { key, prev_key, change })
+        .collect();
+
+    Handoff::Suspended(Frontier { open, closed, expansions })
+}
+
+// build_result that carries on through the closed keys of earlier runs once it leaves this run's
+// closed list.
+fn build_result<S, K, C>(goal_state: TracedStateWrapper<S, K, C>, mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>>, mut links: Links<K, C>) -> TracedResult<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let iterations = closed_list.len();
+    let TracedStateWrapper { state, mut prev_key, change, .. } = goal_state;
+    let mut path: Vec<C> = change.into_iter().collect();
+
+    while let Some(key) = prev_key {
+        let (prev, change) = match closed_list.remove(&key) {
+            Some(closed) => (closed.prev_key, closed.change),
+            None => match links.remove(&key) {
+                Some(link) => link,
+                None => break
+            }
+        };
+        path.extend(change);
+        prev_key = prev;
+    }
+
+    path.reverse();
+
+    TracedResult::new(path, iterations, state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{Direction, SlidingPuzzle}, traced::traced_astar::traced_astar};
+
+    fn apply(mut puzzle: SlidingPuzzle, path: &[Direction]) -> SlidingPuzzle {
+        for direction in path {
+            puzzle = puzzle.generate_traced_successors()
+                .into_iter()
+                .find(|(_, change)| change == direction)
+                .expect("the path only makes possible moves")
+                .0;
+        }
+        puzzle
+    }
+
+    fn suspended(puzzle: SlidingPuzzle, max_expansions: usize) -> Frontier<SlidingPuzzle, [u8; 9], Direction> {
+        match traced_astar_with_budget(puzzle, max_expansions) {
+            Handoff::Suspended(frontier) => frontier,
+            _ => panic!("the budget should run out first")
+        }
+    }
+
+    // Best cost over the continuations of every part, checking each full path on the way.
+    fn best_continuation(puzzle: &SlidingPuzzle, parts: Vec<Frontier<SlidingPuzzle, [u8; 9], Direction>>) -> usize {
+        parts.into_iter()
+            .filter_map(|part| match traced_astar_from_frontier(part, usize::MAX) {
+                Handoff::Solved(result) => Some(result),
+                Handoff::Exhausted => None,
+                Handoff::Suspended(_) => unreachable!("the budget is unbounded")
+            })
+            .map(|result| {
+                let path = result.path.to_vec();
+                assert_eq!(path.len(), result.final_state.g());
+                assert!(apply(puzzle.clone(), &path).is_goal());
+                result.final_state.g()
+            })
+            .min()
+            .expect("the part holding the optimal path solves it")
+    }
+
+    #[test]
+    fn test_split_continuations_find_the_optimal_cost() {
+        let puzzle = SlidingPuzzle::scrambled(7, 40);
+        let optimal = traced_astar(puzzle.clone()).unwrap().final_state.g();
+
+        let frontier = suspended(puzzle.clone(), 200);
+        assert_eq!(frontier.expansions, 200);
+        assert_eq!(frontier.closed.len(), 200);
+
+        let parts = frontier.split(2);
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| !part.open.is_empty()));
+
+        assert_eq!(best_continuation(&puzzle, parts), optimal);
+    }
+
+    #[test]
+    fn test_continuation_can_be_suspended_again() {
+        let puzzle = SlidingPuzzle::scrambled(3, 30);
+        let optimal = traced_astar(puzzle.clone()).unwrap().final_state.g();
+
+        let frontier = match traced_astar_from_frontier(suspended(puzzle.clone(), 50), 50) {
+            Handoff::Suspended(frontier) => frontier,
+            _ => panic!("the budget should run out first")
+        };
+        assert_eq!(frontier.expansions, 100);
+
+        assert_eq!(best_continuation(&puzzle, vec![frontier]), optimal);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_export_split_and_continue() {
+        let puzzle = SlidingPuzzle::scrambled(7, 40);
+        let optimal = traced_astar(puzzle.clone()).unwrap().final_state.g();
+
+        let mut file = Vec::new();
+        suspended(puzzle.clone(), 200).export(&mut file).unwrap();
+        let imported: Frontier<SlidingPuzzle, [u8; 9], Direction> = Frontier::import(file.as_slice()).unwrap();
+
+        assert_eq!(best_continuation(&puzzle, imported.split(2)), optimal);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_import_rejects_mismatched_costs() {
+        let mut frontier = suspended(SlidingPuzzle::scrambled(11, 40), 10);
+        frontier.open[0].g += 1;
+
+        let mut file = Vec::new();
+        frontier.export(&mut file).unwrap();
+        let error = Frontier::<SlidingPuzzle, [u8; 9], Direction>::import(file.as_slice()).err().unwrap();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,304 @@
+use std::{collections::{BTreeMap, HashMap}, hash::Hash, rc::Rc};
+
+use crate::{astar_state::AStarState, open_list::OpenList, priority_queue::Insertion, traced::{result::TracedResult, state::TracedState}};
+
+// A bound on how far g moves along an edge: |g(parent) - g(child)| never exceeds `locality()` for
+// any edge, with both g optimal. Undirected graphs of unit costs have a locality of 1.
+pub trait Locality {
+    fn locality(&self) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrontierStats {
+    // Searches run, more than one when the path is rebuilt.
+    pub searches: usize,
+    pub expansions: usize,
+    // Most entries a closed list held at once, over every search.
+    pub peak_closed: usize,
+    // Closed entries dropped behind the frontier.
+    pub dropped: usize
+}
+
+pub struct FrontierOutcome<R> {
+    pub result: Option<R>,
+    pub stats: FrontierStats
+}
+
+// What a search stops at: a goal of the domain, or the state of a key with h taken as 0.
+enum Target<K> {
+    Goal,
+    Key(K)
+}
+
+impl<K> Target<K>
+where
+    K: Clone + Eq + Hash
+{
+    fn reached<S>(&self, state: &S) -> bool
+    where
+        S: AStarState<K>
+    {
+        match self {
+            Target::Goal => state.is_goal(),
+            Target::Key(key) => state.key() == *key
+        }
+    }
+
+    fn h<S>(&self, state: &S) -> usize
+    where
+        S: AStarState<K>
+    {
+        match self {
+            Target::Goal => state.h(),
+            Target::Key(_) => 0
+        }
+    }
+}
+
+// A state on the open list, with the first change of its path and the state its path passed at
+// the split depth, if any.
+struct Node<S, C> {
+    state: S,
+    f: usize,
+    depth: usize,
+    first_change: Option<C>,
+    relay: Option<Rc<S>>
+}
+
+impl<S, K, C> AStarState<K> for Node<S, C>
+where
+    S: AStarState<K>,
+    K: Clone + Eq + Hash
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.f - self.state.g()
+    }
+
+    fn f(&self) -> usize {
+        self.f
+    }
+
+    fn g(&self) -> usize {
+        self.state.g()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.state.is_goal()
+    }
+}
+
+// Frontier A* for the optimal cost alone. The closed list keeps only the states that a state
+// yet to be expanded could still generate: with every future expansion at a g of at least the
+// lowest g on the open list, a closed state more than `locality()` below that is dropped. On
+// domains that widen slowly, like grids and lattices searched breadth-first, the closed list then
+// holds a few layers around the frontier instead of everything expanded. h must be consistent
+// and the locality honest, or dropped states are expanded again.
+pub fn frontier_search_cost<S, K, C>(initial_state: S) -> FrontierOutcome<usize>
+where
+    S: TracedState<K, C> + Locality + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut stats = FrontierStats::default();
+    let locality = initial_state.locality();
+    let result = search(initial_state, &Target::Goal, locality, None, &mut stats).map(|found| found.state.g());
+    FrontierOutcome { result, stats }
+}
+
+// frontier_search_cost with the path, which the dropped parents can't give back. It is rebuilt
+// by divide and conquer instead: a second search records the state halfway along the path, and
+// both halves are solved the same way, the first one towards that state with h taken as 0. Each
+// level of the recursion searches the whole cost again, around log2 of the path length levels
+// in all.
+pub fn frontier_search<S, K, C>(initial_state: S) -> FrontierOutcome<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C> + Locality + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut stats = FrontierStats::default();
+    let locality = initial_state.locality();
+    let result = solve(initial_state, Target::Goal, locality, &mut stats).map(|(path, final_state, expansions)| TracedResult::new(path, expansions, final_state));
+    FrontierOutcome { result, stats }
+}
+
+// The path from `start` to `target` and the state it ends at, with the expansions of the first
+// search.
+fn solve<S, K, C>(start: S, target: Target<K>, locality: usize, stats: &mut FrontierStats) -> Option<(Vec<C>, S, usize)>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let expansions = stats.expansions;
+    let found = search(start.clone(), &target, locality, None, stats)?;
+    let expansions = stats.expansions - expansions;
+    match found.depth {
+        0 => return Some((Vec::new(), found.state, expansions)),
+        1 => return Some((found.first_change.into_iter().collect(), found.state, expansions)),
+        _ => {}
+    }
+
+    // The same search again takes the same path.
+    let depth = found.depth;
+    let found = search(start.clone(), &target, locality, Some(depth / 2), stats).expect("the second search finds the goal again");
+    debug_assert_eq!(found.depth, depth);
+    let relay = found.relay.expect("the path passes the split depth");
+    let relay = Rc::try_unwrap(relay).unwrap_or_else(|relay| (*relay).clone());
+
+    let (mut path, _, _) = solve(start, Target::Key(relay.key()), locality, stats)?;
+    let (rest, final_state, _) = solve(relay, target, locality, stats)?;
+    path.extend(rest);
+    Some((path, final_state, expansions))
+}
+
+fn search<S, K, C>(start: S, target: &Target<K>, locality: usize, split_depth: Option<usize>, stats: &mut FrontierStats) -> Option<Node<S, C>>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    stats.searches += 1;
+    let mut open_list: OpenList<K, Node<S, C>> = OpenList::new();
+    // How many open entries have each g, for the lowest one.
+    let mut open_gs: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut closed: HashMap<K, usize> = HashMap::new();
+    let mut closed_by_g: BTreeMap<usize, Vec<K>> = BTreeMap::new();
+
+    let f = start.g() + target.h(&start);
+    *open_gs.entry(start.g()).or_default() += 1;
+    open_list.insert(start.key(), Node { state: start, f, depth: 0, first_change: None, relay: None });
+
+    while let Some(node) = open_list.extract_min() {
+        let g = node.state.g();
+        leave(&mut open_gs, g);
+        if target.reached(&node.state) {
+            return Some(node);
+        }
+
+        stats.expansions += 1;
+        let key = node.state.key();
+        closed.insert(key.clone(), g);
+        closed_by_g.entry(g).or_default().push(key);
+
+        for (child, change) in node.state.generate_traced_successors() {
+            let child_key = child.key();
+            if closed.contains_key(&child_key) {
+                continue;
+            }
+
+            let depth = node.depth + 1;
+            let relay = if split_depth == Some(depth) { Some(Rc::new(child.clone())) } else { node.relay.clone() };
+            let first_change = match &node.first_change {
+                Some(first_change) => Some(first_change.clone()),
+                None => Some(change)
+            };
+            let (child_g, f) = (child.g(), child.g() + target.h(&child));
+
+            match open_list.insert(child_key, Node { state: child, f, depth, first_change, relay }) {
+                Insertion::Added => *open_gs.entry(child_g).or_default() += 1,
+                Insertion::Improved(previous) => {
+                    if let Some(previous) = previous {
+                        leave(&mut open_gs, previous.state.g());
+                    }
+                    *open_gs.entry(child_g).or_default() += 1;
+                }
+                Insertion::Rejected(_) => {}
+            }
+        }
+
+        stats.peak_closed = stats.peak_closed.max(closed.len());
+        let Some(&lowest_open_g) = open_gs.keys().next() else {
+            continue;
+        };
+        while let Some(entry) = closed_by_g.first_entry() && *entry.key() + locality < lowest_open_g {
+            let (g, keys) = entry.remove_entry();
+            for key in keys {
+                if closed.get(&key) == Some(&g) {
+                    closed.remove(&key);
+                    stats.dropped += 1;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn leave(open_gs: &mut BTreeMap<usize, usize>, g: usize) {
+    if let Some(count) = open_gs.get_mut(&g) {
+        *count -= 1;
+        if *count == 0 {
+            open_gs.remove(&g);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{Lattice, SlidingPuzzle}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    impl Locality for Lattice {
+        fn locality(&self) -> usize {
+            1
+        }
+    }
+
+    impl Locality for SlidingPuzzle {
+        fn locality(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_closed_list_stays_near_the_frontier() {
+        let outcome = frontier_search_cost(Lattice::new(16));
+        assert_eq!(outcome.result, Some(45));
+
+        // A* closes all the 4096 cells but the goal, while the frontier search keeps the layer
+        // it expands and the one behind, diagonals of at most 192 cells.
+        let astar = traced_astar(Lattice::new(16)).unwrap();
+        assert_eq!(astar.iterations, 4095);
+        assert_eq!(outcome.stats.expansions, 4095);
+        assert!(outcome.stats.peak_closed <= 2 * 192, "{}", outcome.stats.peak_closed);
+        assert!(outcome.stats.dropped > 3000);
+    }
+
+    #[test]
+    fn test_rebuilt_path_matches_astar() {
+        let start = Lattice::new(8);
+        let outcome = frontier_search(start.clone());
+        let result = outcome.result.unwrap();
+        assert_eq!(result.final_state.g(), 21);
+        assert_eq!(replay(&start, result.path.to_vec()).unwrap().g(), 21);
+        assert!(outcome.stats.searches > 2);
+
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let optimal = traced_astar(start.clone()).unwrap().cost;
+
+            assert_eq!(frontier_search_cost(start.clone()).result, Some(optimal));
+            let result = frontier_search(start.clone()).result.unwrap();
+            assert_eq!(result.cost, optimal);
+            assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+        }
+    }
+
+    #[test]
+    fn test_short_paths() {
+        let solved = SlidingPuzzle { tiles: SlidingPuzzle::SOLVED, g_cost: 0 };
+        assert_eq!(frontier_search(solved.clone()).result.unwrap().path.len(), 0);
+
+        let mut tiles = SlidingPuzzle::SOLVED;
+        tiles.swap(7, 8);
+        let one_move = SlidingPuzzle { tiles, g_cost: 0 };
+        let result = frontier_search(one_move.clone()).result.unwrap();
+        assert_eq!(result.path.len(), 1);
+        assert!(replay(&one_move, result.path.to_vec()).unwrap().is_goal());
+    }
+}
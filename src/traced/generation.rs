@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+// Handed to `TracedState::generate_traced_successors_with`. Generators that may run long should
+// poll `should_abort` in their loops and return early once it is true: whatever they return is
+// then dropped and the state counted as a dead end (`SearchOptions::with_max_generation_time`).
+#[derive(Debug, Clone, Copy)]
+pub struct GenContext {
+    started: Instant,
+    deadline: Option<Instant>
+}
+
+impl GenContext {
+    // Never aborts.
+    pub fn unbounded() -> Self {
+        GenContext {
+            started: Instant::now(),
+            deadline: None
+        }
+    }
+
+    pub fn with_budget(budget: Duration) -> Self {
+        let started = Instant::now();
+        GenContext {
+            started,
+            deadline: started.checked_add(budget)
+        }
+    }
+
+    pub fn should_abort(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // Time left before the deadline, None when there is none.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadlines() {
+        let unbounded = GenContext::unbounded();
+        assert!(!unbounded.should_abort());
+        assert_eq!(unbounded.remaining(), None);
+
+        let spent = GenContext::with_budget(Duration::ZERO);
+        assert!(spent.should_abort());
+        assert_eq!(spent.remaining(), Some(Duration::ZERO));
+
+        let generous = GenContext::with_budget(Duration::from_secs(3600));
+        assert!(!generous.should_abort());
+        assert!(generous.remaining().unwrap() > Duration::from_secs(3500));
+    }
+}
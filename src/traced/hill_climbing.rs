@@ -0,0 +1,152 @@
+use std::{collections::{HashMap, VecDeque}, hash::Hash};
+
+use crate::traced::{result::TracedResult, state::TracedState};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HillClimbingStats {
+    pub expansions: usize,
+    // States each breadth-first search expanded, one entry per search: 1 when the state committed
+    // to already had a better successor. A failed search's plateau is the last entry.
+    pub plateaus: Vec<usize>
+}
+
+impl HillClimbingStats {
+    pub fn largest_plateau(&self) -> usize {
+        self.plateaus.iter().copied().max().unwrap_or(0)
+    }
+}
+
+pub struct HillClimbingOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    pub stats: HillClimbingStats
+}
+
+// Enforced hill-climbing, as satisficing planners use it: from the current state, a breadth-first
+// search looks for the nearest state with a strictly lower h() and the search commits to it, never
+// backing up past it. The committed segments make up the path. A goal reached on the way ends the
+// search even when its h is no lower, so a plateau that holds the goal is no trap. When a
+// breadth-first search runs out of states without finding one, the plateau is a dead end and the
+// search fails. The path is usually far from optimal, but every state is expanded near the one
+// before it. `iterations` counts expansions over every breadth-first search.
+pub fn enforced_hill_climbing<S, K, C>(initial_state: S) -> HillClimbingOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut stats = HillClimbingStats::default();
+    let mut path = Vec::new();
+    let mut current = initial_state;
+
+    while !current.is_goal() {
+        let Some((segment, better)) = climb(&current, &mut stats) else {
+            return HillClimbingOutcome { result: None, stats };
+        };
+        path.extend(segment);
+        current = better;
+    }
+
+    let result = TracedResult::new(path, stats.expansions, current);
+    HillClimbingOutcome { result: Some(result), stats }
+}
+
+// The breadth-first search from `start` to the nearest goal or state of lower h, with the changes
+// to it.
+fn climb<S, K, C>(start: &S, stats: &mut HillClimbingStats) -> Option<(Vec<C>, S)>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let h = start.h();
+    let start_key = start.key();
+    // The parent and change of every state reached but the start.
+    let mut parents: HashMap<K, (K, C)> = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut expanded = 0;
+
+    let mut expand = |state: &S, key: K, queue: &mut VecDeque<S>, parents: &mut HashMap<K, (K, C)>| {
+        expanded += 1;
+        for (child, change) in state.generate_traced_successors() {
+            let child_key = child.key();
+            if child_key == start_key || parents.contains_key(&child_key) {
+                continue;
+            }
+            parents.insert(child_key.clone(), (key.clone(), change));
+            if child.is_goal() || child.h() < h {
+                return Some((child_key, child));
+            }
+            queue.push_back(child);
+        }
+        None
+    };
+
+    let mut found = expand(start, start_key.clone(), &mut queue, &mut parents);
+    while found.is_none() && let Some(state) = queue.pop_front() {
+        let key = state.key();
+        found = expand(&state, key, &mut queue, &mut parents);
+    }
+
+    stats.expansions += expanded;
+    stats.plateaus.push(expanded);
+    let (mut key, better) = found?;
+
+    let mut segment = Vec::new();
+    while let Some((parent, change)) = parents.remove(&key) {
+        segment.push(change);
+        key = parent;
+    }
+    segment.reverse();
+    Some((segment, better))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H}, traced::replay::replay};
+
+    #[test]
+    fn test_plateau_is_searched_breadth_first() {
+        // 1 and 2 keep the h of 0, 3 is the first state below it, two moves past the plateau.
+        const PLATEAU: &[&[(usize, usize)]] = &[&[(1, 1), (2, 1)], &[(5, 1)], &[(3, 1)], &[(4, 1)], &[], &[]];
+        const PLATEAU_H: &[usize] = &[3, 3, 3, 2, 0, 3];
+
+        let outcome = enforced_hill_climbing(GraphState::new(PLATEAU, PLATEAU_H, 0, 4));
+        let result = outcome.result.unwrap();
+        assert_eq!(result.path.to_vec(), vec![2, 3, 4]);
+        // 0, 1 and 2 before reaching 3, then 3 alone.
+        assert_eq!(outcome.stats.plateaus, vec![3, 1]);
+        assert_eq!((result.iterations, outcome.stats.largest_plateau()), (4, 3));
+    }
+
+    #[test]
+    fn test_goal_on_a_plateau_and_dead_ends() {
+        // The goal 2 has the same h as everything else.
+        const FLAT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(2, 1)], &[]];
+        let outcome = enforced_hill_climbing(GraphState::new(FLAT, &[1, 1, 1], 0, 2));
+        assert_eq!(outcome.result.unwrap().path.to_vec(), vec![1, 2]);
+        assert_eq!(outcome.stats.plateaus, vec![2]);
+
+        // Committing to 1 for its lower h strands the search away from 3.
+        const TRAP: &[&[(usize, usize)]] = &[&[(1, 1), (2, 1)], &[], &[(3, 1)], &[]];
+        let outcome = enforced_hill_climbing(GraphState::new(TRAP, &[2, 1, 2, 0], 0, 3));
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.stats.plateaus, vec![1, 1]);
+
+        let outcome = enforced_hill_climbing(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 7));
+        assert_eq!((outcome.result.unwrap().path.len(), outcome.stats.expansions), (0, 0));
+    }
+
+    #[test]
+    fn test_solves_sliding_puzzles() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 60);
+            let result = enforced_hill_climbing(start.clone()).result.unwrap();
+            let replayed = replay(&start, result.path.to_vec()).unwrap();
+            assert!(replayed.is_goal());
+            assert_eq!(replayed.g(), result.cost);
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use std::{hash::Hash, mem};
+
+use crate::{search_stats::{SearchStats, SkipReason}, traced::{child_order::ChildOrder, commutative::{self, CommutativeChanges}, dfs_stack::{DfsFrame, DfsStack}, outcome::SearchOutcome, result::TracedResult, state::TracedState}, transposition::{TranspositionEntry, TranspositionTable}};
+
+// A state on the current path. Its successors still to be descended into are in the scratch
+// buffer from `base` up, the next one last.
+struct Frame<K, C> {
+    key: K,
+    g: usize,
+    change: Option<C>,
+    // h, or the table's bound for the key when higher.
+    estimate: usize,
+    base: usize,
+    // Smallest f above the threshold seen in the subtree so far, usize::MAX if there was none.
+    next_threshold: usize,
+    // Shallowest depth on the path of the states skipped in the subtree for being on it,
+    // usize::MAX if none was. The subtree's bound only holds for other paths to the key when no
+    // state above it was skipped.
+    shallowest_skip: usize,
+    // Whether a bound of the table cut off a state of the subtree with a finite f. Bounds that
+    // rest on other bounds could climb around a cycle forever when no goal is reachable, so the
+    // subtree's bound isn't kept then.
+    table_cut: bool,
+    // Whether no successor has been descended into yet.
+    first_child: bool
+}
+
+impl<K, C> DfsFrame<K> for Frame<K, C> {
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+// What became of a state the search descended into.
+enum Entered<K, C> {
+    // The goal, with the change that led to it.
+    Goal(Option<C>),
+    // Cut off with this f, and whether by a bound of the table.
+    Cut(usize, bool),
+    Expanded(Frame<K, C>)
+}
+
+struct Context<'t, S, K, C>
+where
+    K: Eq + Hash
+{
+    stack: DfsStack<K, Frame<K, C>>,
+    table: Option<&'t mut TranspositionTable<K>>,
+    redundant_order: Option<fn(&C, &C) -> bool>,
+    order: ChildOrder<S>,
+    // The successors still to be descended into along the path, each state's batch above its
+    // parent's, so that expansions reuse one buffer.
+    scratch: Vec<(S, C)>,
+    stats: SearchStats<K>
+}
+
+// Iterative deepening A*: depth-first searches bounded by an f threshold that grows to the smallest
+// f that exceeded it, until a goal is found. Memory is linear in the solution depth. States already
+// on the current path are skipped, so cycles are never followed. Every iteration starts from the
+// same borrowed initial state, which is only moved into the result when it is the goal.
+pub fn ida_star<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    search(initial_state, None, None, ChildOrder::AsGenerated).result
+}
+
+// ida_star with a transposition table. After a subtree fails, the table keeps the raised lower
+// bound of its root, and later visits to that key use it in place of h(). A subtree that skipped
+// a state above its root for being on the path leaves no bound, since that state may lead to the
+// goal along another path, and neither does one that a bound of the table cut short. One without
+// any f over the threshold is kept as a dead end. The table can be reused for further searches in
+// the same domain.
+pub fn ida_star_with_table<S, K, C>(initial_state: S, table: &mut TranspositionTable<K>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    search(initial_state, Some(table), None, ChildOrder::AsGenerated)
+}
+
+// ida_star skipping the successors whose change is the redundant order of a commuting pair, see
+// CommutativeChanges. Without a closed list to merge them, IDA* otherwise searches every order of
+// the independent changes on a path again.
+pub fn ida_star_with_commutative_pruning<S, K, C>(initial_state: S) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: CommutativeChanges
+{
+    search(initial_state, None, Some(commutative::is_redundant_order::<C>), ChildOrder::AsGenerated)
+}
+
+// ida_star descending into the successors of each state in `order` instead of the generator's.
+// The stats count the cutoffs, and those on a first child.
+pub fn ida_star_with_order<S, K, C>(initial_state: S, order: ChildOrder<S>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    search(initial_state, None, None, order)
+}
+
+fn search<S, K, C>(initial_state: S, table: Option<&mut TranspositionTable<K>>, redundant_order: Option<fn(&C, &C) -> bool>, order: ChildOrder<S>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut context = Context {
+        stack: DfsStack::deepening(initial_state.f()),
+        table,
+        redundant_order,
+        order,
+        scratch: Vec::new(),
+        stats: SearchStats::default()
+    };
+
+    loop {
+        if let Some((path, final_state)) = probe(&initial_state, &mut context) {
+            let final_state = final_state.unwrap_or(initial_state);
+            let iterations = context.stats.expansions;
+            return SearchOutcome::new(Some(TracedResult::new(path, iterations, final_state)), context.stats);
+        }
+        if context.stack.next_iteration().is_none() {
+            return SearchOutcome::new(None, context.stats);
+        }
+    }
+}
+
+// One iteration, depth-first up to the stack's threshold, in the order of the recursive
+// formulation. Returns the path to the goal and the goal, None in its place when the goal is the
+// initial state, or None when the iteration ends without one.
+fn probe<S, K, C>(initial_state: &S, context: &mut Context<S, K, C>) -> Option<(Vec<C>, Option<S>)>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    match enter(initial_state, None, false, context) {
+        Entered::Goal(_) => return Some((Vec::new(), None)),
+        Entered::Cut(..) => return None,
+        Entered::Expanded(frame) => context.stack.push(frame)
+    }
+
+    while let Some(frame) = context.stack.top_mut() {
+        if context.scratch.len() == frame.base {
+            let frame = context.stack.pop().expect("the frame was just looked at");
+            let depth = context.stack.depth();
+            if let Some(parent) = context.stack.top_mut() {
+                parent.next_threshold = parent.next_threshold.min(frame.next_threshold);
+                parent.shallowest_skip = parent.shallowest_skip.min(frame.shallowest_skip);
+                parent.table_cut |= frame.table_cut;
+            }
+            if let Some(table) = &mut context.table
+                && frame.shallowest_skip >= depth
+                && !frame.table_cut
+            {
+                table.store(frame.key, TranspositionEntry {
+                    proven_budget: context.stack.threshold() - frame.g,
+                    lower_bound: raised_bound(frame.next_threshold, frame.g, frame.estimate)
+                });
+            }
+            continue;
+        }
+
+        let (successor, change) = context.scratch.pop().expect("the batch isn't empty");
+        let commuted = context.redundant_order.is_some_and(|redundant_order| frame.change.as_ref().is_some_and(|parent_change| redundant_order(parent_change, &change)));
+        if let Some(depth) = context.stack.depth_on_path(&successor.key()) {
+            let frame = context.stack.top_mut().expect("the frame is still there");
+            frame.shallowest_skip = frame.shallowest_skip.min(depth);
+            continue;
+        }
+        if commuted {
+            context.stats.pruned[SkipReason::Commuted] += 1;
+            continue;
+        }
+
+        let frame = context.stack.top_mut().expect("the frame is still there");
+        let first_child = mem::replace(&mut frame.first_child, false);
+        match enter(&successor, Some(change), first_child, context) {
+            Entered::Goal(change) => {
+                let frames = context.stack.take_frames();
+                let path = frames.into_iter().filter_map(|frame| frame.change).chain(change).collect();
+                context.scratch.clear();
+                return Some((path, Some(successor)));
+            }
+            Entered::Cut(f, by_table) => {
+                let frame = context.stack.top_mut().expect("the frame is still there");
+                frame.next_threshold = frame.next_threshold.min(f);
+                frame.table_cut |= by_table && f != usize::MAX;
+            }
+            Entered::Expanded(frame) => context.stack.push(frame)
+        }
+    }
+
+    None
+}
+
+// Cuts `state` off when its f is above the threshold, tells when it's a goal, and expands it
+// otherwise. `first_child` tells whether the state is the first successor its parent descended
+// into.
+fn enter<S, K, C>(state: &S, change: Option<C>, first_child: bool, context: &mut Context<S, K, C>) -> Entered<K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let key = state.key();
+    let g = state.g();
+    let threshold = context.stack.threshold();
+    let mut estimate = state.h();
+    let mut by_table = false;
+
+    if let Some(table) = &mut context.table
+        && let Some(entry) = table.get(&key)
+        && entry.lower_bound > estimate
+    {
+        if g.saturating_add(estimate) <= threshold && g.saturating_add(entry.lower_bound) > threshold {
+            table.record_cutoff();
+            by_table = true;
+        }
+        estimate = entry.lower_bound;
+    }
+
+    let f = g.saturating_add(estimate);
+    if f > threshold {
+        context.stats.cutoffs += 1;
+        context.stats.first_child_cutoffs += usize::from(first_child);
+        context.stack.exceeded(f);
+        return Entered::Cut(f, by_table);
+    }
+    if state.is_goal() {
+        context.stats.trace_fingerprint.goal(&key);
+        return Entered::Goal(change);
+    }
+
+    let base = context.scratch.len();
+    context.scratch.extend(state.generate_traced_successors());
+    context.stats.expansions += 1;
+    context.stats.trace_fingerprint.expanded(&key, g, estimate);
+    context.stats.generated += context.scratch.len() - base;
+    context.order.sort_for_popping(&mut context.scratch[base..]);
+
+    Entered::Expanded(Frame { key, g, change, estimate, base, next_threshold: usize::MAX, shallowest_skip: usize::MAX, table_cut: false, first_child: true })
+}
+
+// The lower bound a failed subtree proves for its root at `g`: what the next threshold asks of it
+// beyond g, or usize::MAX for a dead end, whose next threshold is usize::MAX at any g.
+fn raised_bound(next_threshold: usize, g: usize, estimate: usize) -> usize {
+    if next_threshold == usize::MAX {
+        return usize::MAX;
+    }
+    next_threshold.saturating_sub(g).max(estimate)
+}
+
+// The recursive formulation the search above replaces, for the tests to check that the two
+// expand the same states in the same order. It overflows the stack on deep solutions.
+#[cfg(test)]
+mod recursive {
+    use std::{collections::HashMap, hash::Hash};
+
+    use crate::{search_stats::{SearchStats, SkipReason}, traced::{child_order::ChildOrder, outcome::SearchOutcome, result::TracedResult, state::TracedState}, transposition::{TranspositionEntry, TranspositionTable}};
+    use super::raised_bound;
+
+    enum Probe<S> {
+        Found(S),
+        // Smallest f above the threshold seen in the subtree, usize::MAX if there was none, the
+        // shallowest depth of a state skipped in it for being on the path, and whether a bound of
+        // the table cut it short.
+        Exceeded(usize, usize, bool)
+    }
+
+    struct Context<'t, S, K, C>
+    where
+        K: Eq + Hash
+    {
+        threshold: usize,
+        path_keys: HashMap<K, usize>,
+        changes: Vec<C>,
+        table: Option<&'t mut TranspositionTable<K>>,
+        redundant_order: Option<fn(&C, &C) -> bool>,
+        order: ChildOrder<S>,
+        scratch: Vec<(S, C)>,
+        stats: SearchStats<K>
+    }
+
+    pub(super) fn search<S, K, C>(initial_state: S, table: Option<&mut TranspositionTable<K>>, redundant_order: Option<fn(&C, &C) -> bool>, order: ChildOrder<S>) -> SearchOutcome<S, K, C>
+    where
+        S: TracedState<K, C> + Clone,
+        K: Clone + Eq + Hash
+    {
+        let mut context = Context {
+            threshold: initial_state.f(),
+            path_keys: HashMap::new(),
+            changes: Vec::new(),
+            table,
+            redundant_order,
+            order,
+            scratch: Vec::new(),
+            stats: SearchStats::default()
+        };
+
+        loop {
+            match probe(initial_state.clone(), false, &mut context) {
+                Probe::Found(final_state) => {
+                    let iterations = context.stats.expansions;
+                    return SearchOutcome::new(Some(TracedResult::new(context.changes, iterations, final_state)), context.stats);
+                }
+                Probe::Exceeded(usize::MAX, ..) => {
+                    return SearchOutcome::new(None, context.stats);
+                }
+                Probe::Exceeded(next_threshold, ..) => {
+                    context.threshold = next_threshold;
+                    context.path_keys.clear();
+                }
+            }
+        }
+    }
+
+    // `first_child` tells whether the state is the first successor its parent descended into.
+    fn probe<S, K, C>(state: S, first_child: bool, context: &mut Context<S, K, C>) -> Probe<S>
+    where
+        S: TracedState<K, C>,
+        K: Clone + Eq + Hash
+    {
+        let key = state.key();
+        let g = state.g();
+        let mut estimate = state.h();
+        let mut by_table = false;
+
+        if let Some(table) = &mut context.table
+            && let Some(entry) = table.get(&key)
+            && entry.lower_bound > estimate
+        {
+            if g.saturating_add(estimate) <= context.threshold && g.saturating_add(entry.lower_bound) > context.threshold {
+                table.record_cutoff();
+                by_table = true;
+            }
+            estimate = entry.lower_bound;
+        }
+
+        let f = g.saturating_add(estimate);
+        if f > context.threshold {
+            context.stats.cutoffs += 1;
+            context.stats.first_child_cutoffs += usize::from(first_child);
+            return Probe::Exceeded(f, usize::MAX, by_table && f != usize::MAX);
+        }
+        if state.is_goal() {
+            context.stats.trace_fingerprint.goal(&key);
+            return Probe::Found(state);
+        }
+
+        let base = context.scratch.len();
+        context.scratch.extend(state.generate_traced_successors());
+        context.stats.expansions += 1;
+        context.stats.trace_fingerprint.expanded(&key, g, estimate);
+        context.stats.generated += context.scratch.len() - base;
+        context.order.sort_for_popping(&mut context.scratch[base..]);
+        let depth = context.path_keys.len();
+        context.path_keys.insert(key.clone(), depth);
+
+        let mut next_threshold = usize::MAX;
+        let mut shallowest_skip = usize::MAX;
+        let mut table_cut = false;
+        let mut first_child = true;
+        while context.scratch.len() > base {
+            let (successor, change) = context.scratch.pop().expect("the batch isn't empty");
+            if let Some(&skipped) = context.path_keys.get(&successor.key()) {
+                shallowest_skip = shallowest_skip.min(skipped);
+                continue;
+            }
+            if let Some(redundant_order) = context.redundant_order
+                && context.changes.last().is_some_and(|parent_change| redundant_order(parent_change, &change))
+            {
+                context.stats.pruned[SkipReason::Commuted] += 1;
+                continue;
+            }
+
+            context.changes.push(change);
+            match probe(successor, first_child, context) {
+                Probe::Found(final_state) => return Probe::Found(final_state),
+                Probe::Exceeded(successor_f, successor_skip, successor_table_cut) => {
+                    next_threshold = next_threshold.min(successor_f);
+                    shallowest_skip = shallowest_skip.min(successor_skip);
+                    table_cut |= successor_table_cut;
+                }
+            }
+            context.changes.pop();
+            first_child = false;
+        }
+
+        context.path_keys.remove(&key);
+
+        if let Some(table) = &mut context.table
+            && shallowest_skip >= depth
+            && !table_cut
+        {
+            table.store(key, TranspositionEntry {
+                proven_budget: context.threshold - g,
+                lower_bound: raised_bound(next_threshold, g, estimate)
+            });
+        }
+
+        Probe::Exceeded(next_threshold, shallowest_skip, table_cut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, rng::SplitMix64, test_support::{random_graph, GraphState, Lattice, SlidingPuzzle, Step, EIGHT_NODES, EIGHT_NODES_H}, traced::{replay::replay, traced_astar::traced_astar}, transposition::ReplacementPolicy};
+
+    #[test]
+    fn test_graph_optimum() {
+        let result = ida_star(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)).unwrap();
+        assert_eq!(result.final_state.g(), 9);
+
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        assert!(ida_star(GraphState::new(SPLIT, &[0, 0, 0], 0, 2)).is_none());
+    }
+
+    #[test]
+    fn test_table_keeps_the_sliding_puzzle_optimum() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 200);
+            let optimal = traced_astar(start.clone()).unwrap().final_state.g();
+
+            let mut table = TranspositionTable::new(1 << 16, ReplacementPolicy::DepthPreferred);
+            let result = ida_star_with_table(start.clone(), &mut table).result.unwrap();
+            assert_eq!(result.final_state.g(), optimal);
+            assert!(replay(&start, &result.path).is_ok());
+        }
+    }
+
+    // Every state of the lattice is reached by many orders of the same steps, and no step leads
+    // back up the path, so every failed subtree leaves its bound.
+    #[test]
+    fn test_table_cuts_transpositions() {
+        let plain = search(Lattice::new(4), None, None, ChildOrder::AsGenerated);
+        let mut table = TranspositionTable::new(1 << 10, ReplacementPolicy::DepthPreferred);
+        let tabled = ida_star_with_table(Lattice::new(4), &mut table);
+
+        assert_eq!(plain.result.unwrap().final_state.g(), 9);
+        assert_eq!(tabled.result.unwrap().final_state.g(), 9);
+        assert!(table.cutoffs() > 0);
+        // Measured 3113 states against 6807.
+        assert!(tabled.stats.expansions * 3 < plain.stats.expansions * 2, "{} vs {}", tabled.stats.expansions, plain.stats.expansions);
+    }
+
+    #[test]
+    fn test_always_replace_small_table() {
+        let mut table = TranspositionTable::new(16, ReplacementPolicy::AlwaysReplace);
+        let outcome = ida_star_with_table(Lattice::new(4), &mut table);
+
+        assert_eq!(outcome.result.unwrap().final_state.g(), 9);
+        assert!(table.replacements() > 0);
+        assert!(table.cutoffs() > 0);
+    }
+
+    // Every iteration fails without a single cutoff, so the dead ends the table keeps must not
+    // turn into a next threshold when they are reached again at a smaller g.
+    #[test]
+    fn test_table_gives_up_on_an_unreachable_goal() {
+        const RING: &[&[(usize, usize)]] = &[&[(1, 1), (6, 1)], &[(2, 1)], &[(3, 1)], &[(4, 1)], &[(5, 1)], &[(6, 1)], &[(7, 1), (3, 1)], &[(8, 1)], &[(9, 1)], &[(10, 1)], &[(11, 1)], &[(0, 1)], &[]];
+        let mut table = TranspositionTable::new(64, ReplacementPolicy::DepthPreferred);
+        let outcome = ida_star_with_table(GraphState::new(RING, &[0; 13], 0, 12), &mut table);
+        assert!(outcome.result.is_none());
+    }
+
+    #[test]
+    fn test_table_keeps_the_optimum_on_random_graphs() {
+        const CROSSED: &[&[(usize, usize)]] = &[&[(3, 5)], &[], &[(9, 3)], &[(8, 5), (4, 4)], &[(7, 3)], &[], &[], &[(8, 4), (1, 3), (2, 4)], &[(7, 1), (4, 4)], &[]];
+        let mut table = TranspositionTable::new(64, ReplacementPolicy::AlwaysReplace);
+        assert_eq!(ida_star_with_table(GraphState::new(CROSSED, &[0; 10], 0, 9), &mut table).result.unwrap().cost, 18);
+
+        let mut rng = SplitMix64::new(23);
+        for undirected in [false, true] {
+            for _ in 0..200 {
+                let start = GraphState::new(random_graph(&mut rng, 10, 18, undirected), &[0; 10], 0, 9);
+                let optimal = traced_astar(start.clone()).map(|result| result.cost);
+                let mut table = TranspositionTable::new(64, ReplacementPolicy::DepthPreferred);
+                assert_eq!(ida_star_with_table(start, &mut table).result.map(|result| result.cost), optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_commutative_pruning_cuts_the_orders() {
+        let plain = search(Lattice::new(4), None, None, ChildOrder::AsGenerated);
+        let pruned = ida_star_with_commutative_pruning(Lattice::new(4));
+
+        let plain_result = plain.result.unwrap();
+        let pruned_result = pruned.result.unwrap();
+        assert_eq!(pruned_result.final_state.g(), 9);
+        assert_eq!(plain_result.final_state.g(), 9);
+        assert!(pruned.stats.pruned[SkipReason::Commuted] > 0);
+
+        // Of the 1680 orders of the three axes' steps only the sorted one is left.
+        assert!(pruned.stats.expansions * 20 < plain.stats.expansions, "{} vs {}", pruned.stats.expansions, plain.stats.expansions);
+    }
+
+    #[test]
+    fn test_orders_keep_the_optimum() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 200);
+            let optimal = traced_astar(start.clone()).unwrap().final_state.g();
+            let plain = ida_star(start.clone()).unwrap();
+
+            for order in [ChildOrder::AsGenerated, ChildOrder::ByH, ChildOrder::ByF, ChildOrder::Custom(|a: &SlidingPuzzle, b| b.h().cmp(&a.h()))] {
+                let outcome = ida_star_with_order(start.clone(), order);
+                let result = outcome.result.unwrap();
+                assert_eq!(result.final_state.g(), optimal);
+                assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+                assert!(outcome.stats.first_child_cutoffs <= outcome.stats.cutoffs);
+            }
+
+            // The generator's order is the plain search.
+            let generated = ida_star_with_order(start.clone(), ChildOrder::AsGenerated);
+            assert_eq!(generated.stats.expansions, plain.iterations);
+        }
+    }
+
+    fn assert_same_search<S, K, C>(iterative: SearchOutcome<S, K, C>, recursive: SearchOutcome<S, K, C>)
+    where
+        S: TracedState<K, C>,
+        K: Clone + Eq + Hash,
+        C: Clone + PartialEq + std::fmt::Debug
+    {
+        assert_eq!(iterative.stats.trace_fingerprint.get(), recursive.stats.trace_fingerprint.get());
+        assert_eq!((iterative.stats.cutoffs, iterative.stats.first_child_cutoffs), (recursive.stats.cutoffs, recursive.stats.first_child_cutoffs));
+        assert_eq!(iterative.stats.pruned[SkipReason::Commuted], recursive.stats.pruned[SkipReason::Commuted]);
+        assert_eq!(iterative.result.map(|result| result.path.to_vec()), recursive.result.map(|result| result.path.to_vec()));
+    }
+
+    #[test]
+    fn test_same_order_as_the_recursive_search() {
+        let orders = || [ChildOrder::AsGenerated, ChildOrder::ByH, ChildOrder::ByF];
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 200);
+            for (order, same_order) in orders().into_iter().zip(orders()) {
+                assert_same_search(search(start.clone(), None, None, order), recursive::search(start.clone(), None, None, same_order));
+            }
+
+            let mut table = TranspositionTable::new(256, ReplacementPolicy::AlwaysReplace);
+            let mut recursive_table = TranspositionTable::new(256, ReplacementPolicy::AlwaysReplace);
+            assert_same_search(search(start.clone(), Some(&mut table), None, ChildOrder::AsGenerated), recursive::search(start, Some(&mut recursive_table), None, ChildOrder::AsGenerated));
+            assert_eq!((table.hits(), table.cutoffs()), (recursive_table.hits(), recursive_table.cutoffs()));
+        }
+
+        let redundant_order = Some(commutative::is_redundant_order::<Step> as fn(&Step, &Step) -> bool);
+        assert_same_search(search(Lattice::new(3), None, redundant_order, ChildOrder::AsGenerated), recursive::search(Lattice::new(3), None, redundant_order, ChildOrder::AsGenerated));
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        assert_same_search(search(GraphState::new(SPLIT, &[0, 0, 0], 0, 2), None, None, ChildOrder::AsGenerated), recursive::search(GraphState::new(SPLIT, &[0, 0, 0], 0, 2), None, None, ChildOrder::AsGenerated));
+    }
+}
@@ -0,0 +1,216 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{open_list::OpenList, traced::{result::TracedResult, state::TracedState}};
+
+// A solution with the state it passes through before each change, the initial one first and the
+// goal last.
+struct Solution<S, K, C> {
+    keys: Vec<K>,
+    states: Vec<S>,
+    changes: Vec<C>,
+    // Expansions over all the searches until the solution was found.
+    iterations: usize
+}
+
+impl<S, K, C> Solution<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn cost(&self) -> usize {
+        self.states.last().expect("a solution ends at a goal").g()
+    }
+
+    fn into_result(self) -> TracedResult<S, K, C> {
+        let step_costs = self.states.windows(2).map(|step| step[1].g() - step[0].g()).collect();
+        let final_state = self.states.into_iter().last().expect("a solution ends at a goal");
+        let cost = final_state.g();
+        TracedResult::new(self.changes, self.iterations, final_state).with_costs(cost, step_costs)
+    }
+}
+
+// The k cheapest solutions, cheapest first, by Yen's algorithm. Solutions are loopless and
+// distinct as sequences of keys: two that visit the same states in another order are both
+// returned, but parallel edges between two keys give a single solution. Each solution after the
+// first branches off one already found at some state of it, its spur. An A* from the spur, kept
+// off the keys before it and off the next keys of every solution found with the same beginning,
+// gives the cheapest way to branch there, and the cheapest of all branches found so far is the
+// next solution. Ties go to the branch found first. The spur searches reopen improved states, so
+// the costs are in order for any admissible h. Fewer than k come back when there are no more.
+// `iterations` of each result counts the expansions of every search until it was found.
+pub fn traced_astar_k<S, K, C>(initial_state: S, k: usize) -> Vec<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut expansions = 0;
+    let start = Solution { keys: Vec::new(), states: Vec::new(), changes: Vec::new(), iterations: 0 };
+    let first = match spur_search(&start, initial_state, &HashSet::new(), &mut expansions) {
+        Some(first) if k > 0 => first,
+        _ => return Vec::new()
+    };
+
+    let mut found = vec![first];
+    let mut candidates: Vec<Solution<S, K, C>> = Vec::new();
+    let mut known: HashSet<Vec<K>> = HashSet::from([found[0].keys.clone()]);
+
+    while found.len() < k {
+        let last = found.last().expect("the first solution is in");
+        for spur in 0..last.changes.len() {
+            let root = &last.keys[..=spur];
+            let taken = found
+                .iter()
+                .filter(|solution| solution.keys.len() > spur + 1 && solution.keys[..=spur] == *root)
+                .map(|solution| solution.keys[spur + 1].clone())
+                .collect();
+
+            let prefix = Solution {
+                keys: last.keys[..spur].to_vec(),
+                states: last.states[..spur].to_vec(),
+                changes: last.changes[..spur].to_vec(),
+                iterations: 0
+            };
+            let Some(candidate) = spur_search(&prefix, last.states[spur].clone(), &taken, &mut expansions) else {
+                continue;
+            };
+            if known.insert(candidate.keys.clone()) {
+                candidates.push(candidate);
+            }
+        }
+
+        let Some(next) = candidates.iter().enumerate().min_by_key(|(_, candidate)| candidate.cost()).map(|(index, _)| index) else {
+            break;
+        };
+        let mut next = candidates.remove(next);
+        next.iterations = expansions;
+        found.push(next);
+    }
+
+    found.into_iter().map(Solution::into_result).collect()
+}
+
+// A* from the spur to the nearest goal, appended to `prefix`, the solution up to the spur. Keys
+// in the prefix are not entered again, and neither are the keys in `taken` right after the spur.
+fn spur_search<S, K, C>(prefix: &Solution<S, K, C>, spur: S, taken: &HashSet<K>, expansions: &mut usize) -> Option<Solution<S, K, C>>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let spur_key = spur.key();
+    let banned: HashSet<&K> = prefix.keys.iter().collect();
+    let mut best_g = HashMap::from([(spur_key.clone(), spur.g())]);
+    let mut parents: HashMap<K, (K, C)> = HashMap::new();
+    // The state each key was last expanded as, which its parent link belongs to.
+    let mut expanded: HashMap<K, S> = HashMap::new();
+    let mut open_list = OpenList::new();
+    open_list.insert(spur_key.clone(), spur);
+
+    while let Some(current) = open_list.extract_min() {
+        let key = current.key();
+        if current.is_goal() {
+            return Some(join(prefix, &spur_key, key, current, &parents, &mut expanded));
+        }
+
+        *expansions += 1;
+        for (successor, change) in current.generate_traced_successors() {
+            let successor_key = successor.key();
+            if banned.contains(&successor_key) || (key == spur_key && taken.contains(&successor_key)) {
+                continue;
+            }
+            if best_g.get(&successor_key).is_some_and(|&g| g <= successor.g()) {
+                continue;
+            }
+
+            best_g.insert(successor_key.clone(), successor.g());
+            parents.insert(successor_key.clone(), (key.clone(), change));
+            open_list.insert(successor_key, successor);
+        }
+        expanded.insert(key, current);
+    }
+
+    None
+}
+
+fn join<S, K, C>(prefix: &Solution<S, K, C>, spur_key: &K, goal_key: K, goal: S, parents: &HashMap<K, (K, C)>, expanded: &mut HashMap<K, S>) -> Solution<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut keys = vec![goal_key.clone()];
+    let mut states = vec![goal];
+    let mut changes = Vec::new();
+    let mut key = goal_key;
+    while key != *spur_key {
+        let (parent, change) = &parents[&key];
+        changes.push(change.clone());
+        states.push(expanded.remove(parent).expect("a parent was expanded"));
+        keys.push(parent.clone());
+        key = parent.clone();
+    }
+
+    Solution {
+        keys: prefix.keys.iter().cloned().chain(keys.into_iter().rev()).collect(),
+        states: prefix.states.iter().cloned().chain(states.into_iter().rev()).collect(),
+        changes: prefix.changes.iter().cloned().chain(changes.into_iter().rev()).collect(),
+        iterations: 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H}, traced::traced_astar::traced_astar};
+
+    // From 0 to 3 through the two sides of a diamond, 1 and 2, which are also joined both ways,
+    // and straight across. The simple paths cost 2 (0 1 3), 3 (0 2 3), 4 (0 1 2 3), 5 (0 3) and
+    // 6 (0 2 1 3).
+    const DIAMOND: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 2), (3, 5)],
+        &[(3, 1), (2, 2)],
+        &[(3, 1), (1, 3)],
+        &[]
+    ];
+    const DIAMOND_H: &[usize] = &[2, 1, 1, 0];
+
+    fn diamond(k: usize) -> Vec<TracedResult<GraphState, usize, usize>> {
+        traced_astar_k(GraphState::new(DIAMOND, DIAMOND_H, 0, 3), k)
+    }
+
+    fn paths(results: &[TracedResult<GraphState, usize, usize>]) -> Vec<(usize, Vec<usize>)> {
+        results.iter().map(|result| (result.cost, result.path.to_vec())).collect()
+    }
+
+    #[test]
+    fn test_three_cheapest_in_order() {
+        let results = diamond(3);
+        assert_eq!(paths(&results), vec![(2, vec![1, 3]), (3, vec![2, 3]), (4, vec![1, 2, 3])]);
+        assert_eq!(results[2].step_costs.as_deref(), Some(&[1, 2, 1][..]));
+        assert!(results.windows(2).all(|pair| pair[0].iterations <= pair[1].iterations));
+    }
+
+    #[test]
+    fn test_same_keys_in_another_order_is_another_solution() {
+        let results = diamond(10);
+        assert_eq!(
+            paths(&results),
+            vec![(2, vec![1, 3]), (3, vec![2, 3]), (4, vec![1, 2, 3]), (5, vec![3]), (6, vec![2, 1, 3])]
+        );
+    }
+
+    #[test]
+    fn test_first_is_the_astar_solution() {
+        let results = traced_astar_k(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), 4);
+        assert_eq!(results[0].cost, traced_astar(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7)).unwrap().cost);
+        assert_eq!(results.len(), 4);
+        assert!(results.windows(2).all(|pair| pair[0].cost <= pair[1].cost && pair[0].path.to_vec() != pair[1].path.to_vec()));
+    }
+
+    #[test]
+    fn test_no_solution_or_none_asked_for() {
+        assert!(traced_astar_k(GraphState::new(DIAMOND, DIAMOND_H, 3, 0), 3).is_empty());
+        assert!(diamond(0).is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+use std::hash::Hash;
+
+use crate::traced::{dfs_stack::{DfsFrame, DfsStack}, result::TracedResult, state::TracedState};
+
+pub struct LdsOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: TracedResult<S, K, C>,
+    // Successors taken along the path that weren't the best ranked child of their parent.
+    pub discrepancies: usize
+}
+
+// A state on the current path, with its children still to be tried, best ranked last.
+struct Frame<S, K, C> {
+    key: K,
+    change: Option<C>,
+    // Discrepancies on the way to the state.
+    discrepancies: usize,
+    children: Vec<(S, C)>,
+    tried: usize
+}
+
+impl<S, K, C> DfsFrame<K> for Frame<S, K, C> {
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+// Limited discrepancy search: trusts h() to rank the successors and only strays from its ranking
+// a bounded number of times. Each state's successors are ordered by h(), ties in generation
+// order, and taking any but the first counts as a discrepancy. The first iteration follows h()
+// alone, and each one after allows one more discrepancy per path, up to `max_discrepancies`. The
+// first goal reached is returned with the discrepancies its path took, so a heuristic that
+// misranks a single move costs one extra iteration. Paths are not optimal. States already on the
+// path are skipped, and the search stops early once an iteration never hit the bound. Expects a
+// finite space. `iterations` counts expansions over every iteration.
+pub fn lds<S, K, C>(initial_state: S, max_discrepancies: usize) -> Option<LdsOutcome<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    if initial_state.is_goal() {
+        return Some(LdsOutcome { result: TracedResult::new(Vec::new(), 0, initial_state), discrepancies: 0 });
+    }
+
+    // The stack's threshold is the discrepancy bound of the iteration, from 0 up.
+    let mut stack = DfsStack::new();
+    let mut expansions = 0;
+    let mut initial_state = initial_state;
+    loop {
+        match probe(initial_state, &mut stack, &mut expansions) {
+            Probe::Found(path, final_state, discrepancies) => {
+                return Some(LdsOutcome { result: TracedResult::new(path, expansions, final_state), discrepancies });
+            }
+            Probe::Exhausted(state) => {
+                if stack.next_iteration().is_none_or(|bound| bound > max_discrepancies) {
+                    return None;
+                }
+                initial_state = state;
+            }
+        }
+    }
+}
+
+enum Probe<S, C> {
+    Found(Vec<C>, S, usize),
+    // The initial state back.
+    Exhausted(S)
+}
+
+// One iteration, with at most the stack's threshold of discrepancies per path. The stack records
+// a discrepancy over it whenever a successor is left out for the bound. The initial state is
+// handed back when nothing is found, for the next iteration to start from.
+fn probe<S, K, C>(initial_state: S, stack: &mut DfsStack<K, Frame<S, K, C>>, expansions: &mut usize) -> Probe<S, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let bound = stack.threshold();
+    stack.push(expand(&initial_state, None, 0, expansions));
+
+    while let Some(frame) = stack.top_mut() {
+        let Some((child, change)) = frame.children.pop() else {
+            stack.pop();
+            continue;
+        };
+
+        let discrepancies = frame.discrepancies + usize::from(frame.tried > 0);
+        frame.tried += 1;
+        if discrepancies > bound {
+            // The rest are discrepancies too.
+            frame.children.clear();
+            stack.exceeded(discrepancies);
+            continue;
+        }
+        if stack.on_path(&child.key()) {
+            continue;
+        }
+
+        if child.is_goal() {
+            let path = stack.take_frames().into_iter().filter_map(|frame| frame.change).chain([change]).collect();
+            return Probe::Found(path, child, discrepancies);
+        }
+
+        stack.push(expand(&child, Some(change), discrepancies, expansions));
+    }
+
+    Probe::Exhausted(initial_state)
+}
+
+fn expand<S, K, C>(state: &S, change: Option<C>, discrepancies: usize, expansions: &mut usize) -> Frame<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let key = state.key();
+    let mut children = state.generate_traced_successors();
+    *expansions += 1;
+
+    // Stable, so that equal h keep the generation order once reversed for popping.
+    children.sort_by_key(|(child, _)| child.h());
+    children.reverse();
+
+    Frame { key, change, discrepancies, children, tried: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle}, traced::replay::replay};
+
+    // From 0, h ranks 1 first, but 1 only leads to the dead end 3. The goal 4 is behind 2.
+    const MISRANKED: &[&[(usize, usize)]] = &[&[(2, 1), (1, 1)], &[(3, 1)], &[(4, 1)], &[], &[]];
+    const MISRANKED_H: &[usize] = &[2, 1, 3, 0, 0];
+
+    #[test]
+    fn test_one_discrepancy_gets_past_the_misranked_move() {
+        assert!(lds(GraphState::new(MISRANKED, MISRANKED_H, 0, 4), 0).is_none());
+
+        let outcome = lds(GraphState::new(MISRANKED, MISRANKED_H, 0, 4), 3).unwrap();
+        assert_eq!(outcome.discrepancies, 1);
+        assert_eq!(outcome.result.path.to_vec(), vec![2, 4]);
+        // 0, 1 and 3 in the greedy iteration, then 0, 1, 3 and 2.
+        assert_eq!(outcome.result.iterations, 7);
+    }
+
+    #[test]
+    fn test_bound_limits_the_discrepancies() {
+        // Both 0 and 2 misrank their successors, so the goal 6 takes two discrepancies.
+        const TWICE: &[&[(usize, usize)]] = &[&[(1, 1), (2, 1)], &[], &[(3, 1), (4, 1)], &[], &[(6, 1)], &[], &[]];
+        const TWICE_H: &[usize] = &[0, 0, 1, 0, 1, 0, 0];
+
+        assert!(lds(GraphState::new(TWICE, TWICE_H, 0, 6), 1).is_none());
+        let outcome = lds(GraphState::new(TWICE, TWICE_H, 0, 6), 2).unwrap();
+        assert_eq!((outcome.discrepancies, outcome.result.path.to_vec()), (2, vec![2, 4, 6]));
+
+        // Unreachable goals end the iterations as soon as nothing is cut by the bound.
+        let outcome = lds(GraphState::new(TWICE, TWICE_H, 0, 5), usize::MAX);
+        assert!(outcome.is_none());
+    }
+
+    #[test]
+    fn test_solves_sliding_puzzles() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 12);
+            let outcome = lds(start.clone(), 4).unwrap();
+            assert!(replay(&start, outcome.result.path.to_vec()).unwrap().is_goal());
+            assert!(outcome.discrepancies <= 4);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::{hash::Hash, marker::PhantomData};
+
+use crate::{astar_state::AStarState, traced::{path::PathRepr, state::TracedState, traced_astar::traced_astar}};
+
+// Bits of the packed g given to the secondary cost. Secondary totals from 2^SECONDARY_BITS - 1 up
+// compare as equal.
+pub const SECONDARY_BITS: u32 = usize::BITS / 2;
+const SECONDARY_MAX: usize = (1 << SECONDARY_BITS) - 1;
+
+// A pair of costs compared primary first. Edges carry one, and the search keeps the sum of them
+// along the path as g.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LexCost {
+    pub primary: usize,
+    pub secondary: usize
+}
+
+impl LexCost {
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        LexCost { primary, secondary }
+    }
+
+    // The same costs the other way round, for the cheapest among shortest turned into the
+    // shortest among cheapest.
+    pub fn transposed(self) -> Self {
+        LexCost { primary: self.secondary, secondary: self.primary }
+    }
+
+    fn add(self, edge: LexCost) -> Self {
+        LexCost {
+            primary: self.primary + edge.primary,
+            secondary: self.secondary.saturating_add(edge.secondary)
+        }
+    }
+
+    fn packed(self) -> usize {
+        (self.primary << SECONDARY_BITS) | self.secondary.min(SECONDARY_MAX)
+    }
+}
+
+// A state whose g is kept by the search from the two costs of each edge. h estimates the
+// remaining primary cost only, and has to be admissible for it.
+pub trait LexicographicState<K, C>: Sized
+where
+    K: Clone + Eq + Hash
+{
+    fn key(&self) -> K;
+    fn h(&self) -> usize;
+    fn is_goal(&self) -> bool;
+    fn generate_lexicographic_successors(&self) -> Vec<(Self, C, LexCost)>;
+}
+
+pub struct LexicographicResult<S, C> {
+    pub path: PathRepr<C>,
+    pub iterations: usize,
+    pub final_state: S,
+    pub cost: LexCost
+}
+
+// A state with the g the search reached it with. g and f are packed into one usize, primary in
+// the high bits, so that the plain A* orders them lexicographically.
+struct Lexicographic<S, C> {
+    state: S,
+    g: LexCost,
+    _marker: PhantomData<C>
+}
+
+impl<K, C, S> AStarState<K> for Lexicographic<S, C>
+where
+    K: Clone + Eq + Hash,
+    S: LexicographicState<K, C>
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.state.h() << SECONDARY_BITS
+    }
+
+    fn f(&self) -> usize {
+        self.g() + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g.packed()
+    }
+
+    fn is_goal(&self) -> bool {
+        self.state.is_goal()
+    }
+}
+
+impl<K, C, S> TracedState<K, C> for Lexicographic<S, C>
+where
+    K: Clone + Eq + Hash,
+    S: LexicographicState<K, C>
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, C)> {
+        self.state.generate_lexicographic_successors()
+            .into_iter()
+            .map(|(state, change, cost)| (Lexicographic { state, g: self.g.add(cost), _marker: PhantomData }, change))
+            .collect()
+    }
+}
+
+// traced_astar minimizing the primary cost of the path and, among the paths of least primary
+// cost, the secondary one. Much cheaper than a full multi-objective search, since both costs fold
+// into one g. Primary totals have to fit in the bits above SECONDARY_BITS.
+pub fn lexicographic_astar<S, K, C>(initial_state: S) -> Option<LexicographicResult<S, C>>
+where
+    S: LexicographicState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let result = traced_astar(Lexicographic { state: initial_state, g: LexCost::default(), _marker: PhantomData })?;
+    let Lexicographic { state, g, .. } = result.final_state;
+
+    Some(LexicographicResult {
+        path: result.path,
+        iterations: result.iterations,
+        final_state: state,
+        cost: g
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three ways from 0 to 3, as (from, to, moves, toll):
+    // 0 -> 1 -> 3 in 2 moves and 20 toll, 0 -> 2 -> 3 in 2 moves and 6 toll,
+    // 0 -> 4 -> 5 -> 3 in 3 moves and no toll.
+    const ROADS: [(u8, u8, usize, usize); 7] = [
+        (0, 1, 1, 10), (1, 3, 1, 10),
+        (0, 2, 1, 1), (2, 3, 1, 5),
+        (0, 4, 1, 0), (4, 5, 1, 0), (5, 3, 1, 0)
+    ];
+
+    struct Town {
+        node: u8,
+        // Moves are the primary cost, toll the secondary one, or the other way round.
+        moves_first: bool
+    }
+
+    impl LexicographicState<u8, u8> for Town {
+        fn key(&self) -> u8 {
+            self.node
+        }
+
+        fn h(&self) -> usize {
+            if self.moves_first && self.node != 3 { 1 } else { 0 }
+        }
+
+        fn is_goal(&self) -> bool {
+            self.node == 3
+        }
+
+        fn generate_lexicographic_successors(&self) -> Vec<(Self, u8, LexCost)> {
+            ROADS.iter()
+                .filter(|(from, ..)| *from == self.node)
+                .map(|&(_, to, moves, toll)| {
+                    let cost = LexCost::new(moves, toll);
+                    let cost = if self.moves_first { cost } else { cost.transposed() };
+                    (Town { node: to, moves_first: self.moves_first }, to, cost)
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_cheapest_among_shortest() {
+        let result = lexicographic_astar(Town { node: 0, moves_first: true }).unwrap();
+
+        assert_eq!(result.path.to_vec(), vec![2, 3]);
+        assert_eq!(result.cost, LexCost::new(2, 6));
+        assert_eq!(result.final_state.node, 3);
+    }
+
+    #[test]
+    fn test_shortest_among_cheapest() {
+        let result = lexicographic_astar(Town { node: 0, moves_first: false }).unwrap();
+
+        assert_eq!(result.path.to_vec(), vec![4, 5, 3]);
+        assert_eq!(result.cost, LexCost::new(0, 3));
+    }
+
+    #[test]
+    fn test_packed_order_is_lexicographic() {
+        let costs = [LexCost::new(1, 0), LexCost::new(0, SECONDARY_MAX), LexCost::new(1, 1), LexCost::new(0, 0)];
+
+        for a in costs {
+            for b in costs {
+                assert_eq!(a.packed().cmp(&b.packed()), a.cmp(&b));
+            }
+        }
+    }
+}
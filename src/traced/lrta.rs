@@ -0,0 +1,202 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}, hash::Hash, marker::PhantomData};
+
+use crate::traced::state::TracedState;
+
+// A state reached by the lookahead of one step.
+struct Node<S, C> {
+    state: S,
+    change: Option<C>,
+    // The node of the move that starts the path to it, None for the successors of the agent's
+    // state.
+    first: Option<usize>
+}
+
+// The A* of one step, with costs from the agent's state.
+struct Lookahead<S, K, C> {
+    root_g: usize,
+    nodes: Vec<Node<S, C>>,
+    best_g: HashMap<K, usize>,
+    // By f, then g, then node.
+    open: BinaryHeap<Reverse<(usize, usize, usize)>>
+}
+
+impl<S, K, C> Lookahead<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // Adds `state` unless its key was reached as cheaply already.
+    fn push(&mut self, state: S, change: C, first: Option<usize>, h: usize) {
+        let g = state.g() - self.root_g;
+        let key = state.key();
+        if self.best_g.get(&key).is_some_and(|&best| best <= g) {
+            return;
+        }
+
+        self.best_g.insert(key, g);
+        self.open.push(Reverse((g.saturating_add(h), g, self.nodes.len())));
+        self.nodes.push(Node { state, change: Some(change), first });
+    }
+}
+
+// Real-time search in the manner of LRTA*: an agent that commits to one move per `step()`, after
+// a lookahead of at most `lookahead` expansions around its state. The h of the states it expands
+// is learned in a table that persists across steps and can be carried over to later trials, so an
+// agent that keeps coming back to a region raises its h until it is left. On domains where the
+// goal stays reachable from every state, every trial reaches the goal, and the trials of an
+// agent that reuses the table converge on optimal paths for an admissible h.
+pub struct LrtaAgent<S, K, C>
+where
+    K: Clone + Eq + Hash
+{
+    current: S,
+    lookahead: usize,
+    learned: HashMap<K, usize>,
+    moves: usize,
+    _change: PhantomData<C>
+}
+
+impl<S, K, C> LrtaAgent<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // A lookahead of 0 is taken as 1, the successors of the agent's state alone.
+    pub fn new(initial_state: S, lookahead: usize) -> Self {
+        Self::with_learned(initial_state, lookahead, HashMap::new())
+    }
+
+    // An agent that starts from the h learned by an earlier one, see `into_learned`.
+    pub fn with_learned(initial_state: S, lookahead: usize, learned: HashMap<K, usize>) -> Self {
+        LrtaAgent { current: initial_state, lookahead: lookahead.max(1), learned, moves: 0, _change: PhantomData }
+    }
+
+    pub fn current(&self) -> &S {
+        &self.current
+    }
+
+    pub fn is_at_goal(&self) -> bool {
+        self.current.is_goal()
+    }
+
+    // Moves made so far.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    // h learned for the keys the agent expanded. Keys missing from it have their own h().
+    pub fn learned(&self) -> &HashMap<K, usize> {
+        &self.learned
+    }
+
+    pub fn into_learned(self) -> HashMap<K, usize> {
+        self.learned
+    }
+
+    // h of a state as learned so far.
+    pub fn h(&self, state: &S) -> usize {
+        self.learned.get(&state.key()).copied().unwrap_or_else(|| state.h())
+    }
+
+    // Looks ahead from the agent's state with A* over the learned h, until `lookahead` states are
+    // expanded or a goal has the lowest f. The state of lowest f left, the best frontier state,
+    // bounds the cost to a goal through the ones expanded: each of them learns an h of at least
+    // that f minus its own g, as RTAA* does. The agent then makes the first move of the path to
+    // it and returns its change. None at a goal, and at a dead end, whose h is learned as
+    // usize::MAX so that the agent isn't led back to it.
+    pub fn step(&mut self) -> Option<C> {
+        if self.current.is_goal() {
+            return None;
+        }
+
+        let mut lookahead = Lookahead { root_g: self.current.g(), nodes: Vec::new(), best_g: HashMap::new(), open: BinaryHeap::new() };
+        // Keys expanded, with their g and h before this step.
+        let mut expanded: Vec<(K, usize, usize)> = Vec::new();
+
+        let root_key = self.current.key();
+        expanded.push((root_key.clone(), 0, self.h(&self.current)));
+        lookahead.best_g.insert(root_key, 0);
+        for (child, change) in self.current.generate_traced_successors() {
+            let h = self.h(&child);
+            lookahead.push(child, change, None, h);
+        }
+
+        let mut frontier = None;
+        while let Some(Reverse((f, g, id))) = lookahead.open.pop() {
+            let state = &lookahead.nodes[id].state;
+            let key = state.key();
+            if lookahead.best_g.get(&key).is_some_and(|&best| best < g) {
+                continue;
+            }
+            if state.is_goal() || expanded.len() == self.lookahead {
+                frontier = Some((f, id));
+                break;
+            }
+
+            expanded.push((key, g, self.h(state)));
+            let first = lookahead.nodes[id].first.or(Some(id));
+            for (child, change) in lookahead.nodes[id].state.generate_traced_successors() {
+                let h = self.h(&child);
+                lookahead.push(child, change, first, h);
+            }
+        }
+
+        // Nothing left to move to: every state expanded is a dead end.
+        let Some((frontier_f, frontier_id)) = frontier else {
+            for (key, _, _) in expanded {
+                self.learned.insert(key, usize::MAX);
+            }
+            return None;
+        };
+
+        for (key, g, h) in expanded {
+            self.learned.insert(key, h.max(frontier_f.saturating_sub(g)));
+        }
+
+        let first = lookahead.nodes[frontier_id].first.unwrap_or(frontier_id);
+        let node = lookahead.nodes.swap_remove(first);
+        self.current = node.state;
+        self.moves += 1;
+        node.change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, SlidingPuzzle}, traced::replay::replay};
+
+    #[test]
+    fn test_learns_its_way_out_of_a_local_minimum() {
+        // h leads into the dead end 1 first. Backing out of it has to raise its h past that of 2.
+        const POCKET: &[&[(usize, usize)]] = &[&[(1, 1), (2, 1)], &[(0, 1)], &[(0, 1), (3, 1)], &[]];
+        let mut agent = LrtaAgent::new(GraphState::new(POCKET, &[1, 0, 1, 0], 0, 3), 1);
+
+        assert_eq!((agent.step(), agent.step()), (Some(1), Some(0)));
+        assert_eq!(agent.learned()[&1], 2);
+        assert_eq!((agent.step(), agent.step()), (Some(2), Some(3)));
+        assert!(agent.is_at_goal());
+        assert_eq!((agent.step(), agent.moves()), (None, 4));
+    }
+
+    #[test]
+    fn test_dead_end_stops_the_agent() {
+        const STUCK: &[&[(usize, usize)]] = &[&[(1, 1)], &[], &[]];
+        let mut agent = LrtaAgent::new(GraphState::new(STUCK, &[0, 0, 0], 0, 2), 4);
+        assert_eq!(agent.step(), None);
+        assert_eq!(agent.current().node, 0);
+        assert_eq!(agent.learned()[&0], usize::MAX);
+        assert_eq!(agent.h(&GraphState::new(STUCK, &[0, 0, 0], 1, 2)), usize::MAX);
+    }
+
+    #[test]
+    fn test_reaches_the_goal_of_sliding_puzzles() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 20);
+            let mut agent = LrtaAgent::new(start.clone(), 8);
+            let path: Vec<_> = std::iter::from_fn(|| agent.step()).collect();
+            assert!(agent.is_at_goal());
+            assert_eq!(replay(&start, path).unwrap().g(), agent.current().g());
+        }
+    }
+}
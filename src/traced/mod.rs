@@ -1,3 +1,60 @@
 pub mod traced_astar;
+pub mod uniform_cost;
+pub mod multi_target;
+pub mod best_first;
 pub mod state;
 pub mod result;
+pub mod path;
+pub mod outcome;
+pub mod stepper;
+#[cfg(feature = "ida")]
+pub mod child_order;
+#[cfg(any(feature = "ida", feature = "lds"))]
+mod dfs_stack;
+#[cfg(feature = "ida")]
+pub mod ida_star;
+#[cfg(feature = "ida")]
+pub mod rbfs;
+#[cfg(feature = "dfbb")]
+pub mod dfbb;
+#[cfg(feature = "fringe")]
+pub mod fringe;
+#[cfg(feature = "lds")]
+pub mod lds;
+#[cfg(feature = "hill-climbing")]
+pub mod hill_climbing;
+#[cfg(feature = "lrta")]
+pub mod lrta;
+#[cfg(feature = "sma")]
+pub mod sma_star;
+#[cfg(feature = "beam")]
+pub mod beam;
+#[cfg(any(feature = "focal", feature = "ees"))]
+mod focal_lists;
+#[cfg(feature = "focal")]
+pub mod focal;
+#[cfg(feature = "ees")]
+pub mod ees;
+#[cfg(feature = "anytime")]
+pub mod ara_star;
+#[cfg(feature = "anytime")]
+pub mod anytime;
+#[cfg(feature = "anytime")]
+pub mod restarting;
+#[cfg(feature = "all-paths")]
+pub mod all_paths;
+#[cfg(feature = "k-shortest")]
+pub mod k_shortest;
+#[cfg(feature = "repair")]
+pub mod repair;
+pub mod replay;
+pub mod frames;
+pub mod generation;
+pub mod commutative;
+pub mod to_goal;
+pub mod lexicographic;
+pub mod frontier;
+#[cfg(feature = "frontier-search")]
+pub mod frontier_search;
+#[cfg(feature = "dyn-change")]
+pub mod dyn_change;
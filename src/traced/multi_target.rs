@@ -0,0 +1,105 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}}};
+
+// The untraced multi_target_search with a path to each target reached. A result's `iterations`
+// counts the states closed before its target was.
+pub fn multi_target_search<S, K, C>(initial_state: S, targets: &[K]) -> HashMap<K, TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut remaining: HashSet<&K> = targets.iter().collect();
+    // Each target reached, with the states closed before it.
+    let mut reached = Vec::new();
+    let mut open_list = OpenList::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+
+    let initial_g = initial_state.g();
+    open_list.insert(initial_state.key(), Prioritized::new(TracedStateWrapper::new(initial_state), initial_g));
+
+    while !remaining.is_empty() {
+        let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() else {
+            break;
+        };
+
+        let key = current_state.key();
+        if remaining.remove(&key) {
+            reached.push((key.clone(), closed_list.len()));
+        }
+
+        let successors = current_state.generate_states();
+        closed_list.insert(key, current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+
+            if closed_list.contains_key(&successor_key) {
+                continue;
+            }
+
+            let successor_g = successor.state.g();
+            open_list.insert(successor_key, Prioritized::new(successor, successor_g));
+        }
+    }
+
+    // Targets can lie on each other's paths, so every path is read before a target is taken out.
+    let paths: Vec<Vec<C>> = reached.iter().map(|(key, _)| path_to(key, &closed_list)).collect();
+    reached.into_iter()
+        .zip(paths)
+        .map(|((key, iterations), path)| {
+            let target = closed_list.remove(&key).expect("a reached target is closed");
+            (key, TracedResult::new(path, iterations, target.state))
+        })
+        .collect()
+}
+
+fn path_to<S, K, C>(key: &K, closed_list: &HashMap<K, TracedStateWrapper<S, K, C>>) -> Vec<C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut path = Vec::new();
+    let mut current = closed_list.get(key);
+    while let Some(state) = current {
+        path.extend(state.change.iter().cloned());
+        current = state.prev_key.as_ref().and_then(|prev_key| closed_list.get(prev_key));
+    }
+
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::replay::replay_states, untraced::untraced_astar::untraced_astar};
+
+    #[test]
+    fn test_paths_match_a_search_per_target() {
+        let start = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let results = multi_target_search(start.clone(), &[7, 4, 2]);
+
+        assert_eq!(results.len(), 3);
+        for (target, result) in &results {
+            let single = untraced_astar(GraphState::new(EIGHT_NODES, ZERO_H, 0, *target)).unwrap();
+            assert_eq!(result.cost, single.final_state.g(), "cost to {}", target);
+            assert_eq!(result.final_state.node, *target);
+            let replayed = replay_states(start.clone(), result.path.to_vec()).unwrap().pop().unwrap();
+            assert_eq!((replayed.node, replayed.g()), (*target, result.cost));
+        }
+
+        // 2 is closed before 4, and 4 before 7.
+        assert!(results[&2].iterations < results[&4].iterations && results[&4].iterations < results[&7].iterations);
+        assert_eq!(results[&2].path.to_vec(), vec![3, 2]);
+    }
+
+    #[test]
+    fn test_the_start_can_be_a_target() {
+        let results = multi_target_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0), &[7, 0]);
+        assert_eq!(results.keys().collect::<Vec<_>>(), vec![&7]);
+        assert!(results[&7].path.to_vec().is_empty());
+    }
+}
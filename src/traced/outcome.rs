@@ -0,0 +1,152 @@
+use std::{collections::HashSet, fmt::Display, hash::Hash};
+
+use crate::{edge_costs::EdgeCostError, labels::LabelTable, open_list::CorruptionError, search_id::SearchId, search_stats::SearchStats, traced::{result::TracedResult, state::TracedState}, unreachable::UnreachabilitySet, validation::Anomaly};
+
+pub struct SearchOutcome<S, K, C>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    pub stats: SearchStats<K>,
+    // Why the search stopped early, see `SearchOptions::with_edge_cost_bounds`.
+    pub edge_cost_error: Option<EdgeCostError<K, C>>,
+    // Why the search stopped early, see `SearchOptions::with_validation`.
+    pub validation_error: Option<Anomaly<K>>,
+    // The search stopped at `SearchOptions::with_max_expansions` with states left to expand.
+    pub budget_exhausted: bool,
+    // Closed states over `SearchOptions::with_state_space_bound`, set when the search ran out of
+    // states without reaching a goal.
+    pub coverage: Option<f64>,
+    // Every key closed by a search that ran out of states, when nothing it pruned could have led
+    // to a goal. See `into_unreachable_set`.
+    pub exhausted_keys: Option<HashSet<K>>,
+    // A failure of the search itself rather than of the domain.
+    pub error: Option<SearchError>,
+    pub optimality: Optimality,
+    // Labels of the keys in the warnings, errors and thrashers above, for printing them with
+    // `labels.labeled(&warning)`. See `SearchOptions::with_key_labeler`.
+    pub labels: LabelTable<K>
+}
+
+// Whether the result is as cheap as the search makes it without seeds: optimal for an admissible
+// h, unless the weight, greedy ordering or pruning options give that up themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Optimality {
+    Kept,
+    // Reached through a seed before a cheaper path could be ruled out, see
+    // `SearchOptions::with_seed_nodes`, or found after `SearchOptions::with_soft_open_limit`
+    // turned states away.
+    Unknown
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SearchError {
+    Internal(InternalError)
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::Internal(error) => write!(f, "internal search error: {}", error)
+        }
+    }
+}
+
+// A bug of the crate's own bookkeeping, found before it could return a wrong answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InternalError {
+    // The open list was found out of sync, the search stopped there.
+    OpenList(CorruptionError),
+    // The step costs of the returned path, added to the g of the initial state, come to `steps`
+    // instead of the `cost` the result reports. A g that drops along the path shows up here too,
+    // since a step never costs less than 0.
+    StepCosts { steps: usize, cost: usize },
+    // The result reports `cost`, but its goal state has `goal_g`.
+    GoalCost { cost: usize, goal_g: usize }
+}
+
+impl Display for InternalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InternalError::OpenList(error) => write!(f, "{}", error),
+            InternalError::StepCosts { steps, cost } => write!(f, "the path's steps cost {} but the result reports {}", steps, cost),
+            InternalError::GoalCost { cost, goal_g } => write!(f, "the result reports a cost of {} but its goal has g {}", cost, goal_g)
+        }
+    }
+}
+
+impl From<CorruptionError> for SearchError {
+    fn from(error: CorruptionError) -> Self {
+        SearchError::Internal(InternalError::OpenList(error))
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl<S, K, C> SearchOutcome<S, K, C>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>
+{
+    // An outcome with nothing to report beyond the result and stats. Searches that have more set
+    // it over this with struct update syntax.
+    pub(crate) fn new(result: Option<TracedResult<S, K, C>>, stats: SearchStats<K>) -> Self {
+        SearchOutcome {
+            result,
+            stats,
+            edge_cost_error: None,
+            validation_error: None,
+            budget_exhausted: false,
+            coverage: None,
+            exhausted_keys: None,
+            error: None,
+            optimality: Optimality::Kept,
+            labels: LabelTable::new()
+        }
+    }
+
+    pub fn search_id(&self) -> SearchId {
+        self.stats.search_id
+    }
+
+    // Stable 64-bit hash of the expansion order, the same for every run of the same search on any
+    // platform. Pin it in a regression test to catch changes of ordering, as tests/golden.rs does.
+    pub fn trace_fingerprint(&self) -> u64 {
+        self.stats.trace_fingerprint.get()
+    }
+
+    // The exhausted keys as a proof that none of them reaches a goal, for later searches towards
+    // the same goal under the same movement rules, named by `goal_tag`. None unless the search
+    // ran out of states without pruning anything that could have led to a goal.
+    pub fn into_unreachable_set(self, goal_tag: impl Into<String>) -> Option<UnreachabilitySet<K>> {
+        self.exhausted_keys.map(|keys| UnreachabilitySet::new(keys, goal_tag.into()))
+    }
+
+    // Labels every key the diagnostics mention, in the order they appear: warnings, then the
+    // errors, then the keys of the visit counts that were reinserted or reopened.
+    pub(crate) fn label_diagnostics(&mut self, labeler: Option<&dyn Fn(&K) -> String>) {
+        let labels = &mut self.labels;
+        for warning in &self.stats.warnings {
+            for key in warning.keys() {
+                labels.intern(key, labeler);
+            }
+        }
+        if let Some(anomaly) = &self.validation_error {
+            for key in anomaly.keys() {
+                labels.intern(key, labeler);
+            }
+        }
+        if let Some(EdgeCostError::OutOfBounds { parent, .. } | EdgeCostError::Negative { parent, .. }) = &self.edge_cost_error {
+            labels.intern(parent, labeler);
+        }
+        if let Some(visits) = &self.stats.visits {
+            for thrasher in visits.top_thrashers(usize::MAX) {
+                labels.intern(&thrasher.key, labeler);
+            }
+        }
+    }
+}
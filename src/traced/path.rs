@@ -0,0 +1,364 @@
+use std::{fmt::Debug, mem, rc::Rc};
+
+// Turns changes into bytes and back. `decode` reads one change from the front of `reader` and
+// advances it past the bytes it consumed, returning None if they don't form a valid change.
+pub trait ChangeCodec<C> {
+    fn encode(&self, change: &C, writer: &mut Vec<u8>);
+    fn decode(&self, reader: &mut &[u8]) -> Option<C>;
+}
+
+// The changes of a traced result, either as a plain vector or encoded by a `ChangeCodec`.
+// Compressed paths are decoded one change at a time by `iter`, never all at once.
+#[non_exhaustive]
+pub enum PathRepr<C> {
+    Plain(Vec<C>),
+    Compressed(CompressedPath<C>)
+}
+
+pub struct CompressedPath<C> {
+    bytes: Vec<u8>,
+    len: usize,
+    codec: Rc<dyn ChangeCodec<C>>
+}
+
+impl<C> CompressedPath<C> {
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<C> PathRepr<C> {
+    pub fn compress(path: Vec<C>, codec: Rc<dyn ChangeCodec<C>>) -> Self {
+        let mut bytes = Vec::new();
+        for change in path.iter() {
+            codec.encode(change, &mut bytes);
+        }
+        bytes.shrink_to_fit();
+
+        PathRepr::Compressed(CompressedPath {
+            bytes,
+            len: path.len(),
+            codec
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            PathRepr::Plain(path) => path.len(),
+            PathRepr::Compressed(path) => path.len
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, PathRepr::Compressed(_))
+    }
+
+    // Heap memory held by the changes (not counting anything the changes themselves own).
+    pub fn heap_bytes(&self) -> usize {
+        match self {
+            PathRepr::Plain(path) => path.capacity() * mem::size_of::<C>(),
+            PathRepr::Compressed(path) => path.bytes.capacity()
+        }
+    }
+}
+
+impl<C> PathRepr<C>
+where
+    C: Clone
+{
+    pub fn iter(&self) -> PathIter<'_, C> {
+        match self {
+            PathRepr::Plain(path) => PathIter::Plain(path.iter()),
+            PathRepr::Compressed(path) => PathIter::Compressed {
+                reader: &path.bytes,
+                remaining: path.len,
+                codec: path.codec.as_ref()
+            }
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<C> {
+        self.iter().collect()
+    }
+}
+
+impl<C> From<Vec<C>> for PathRepr<C> {
+    fn from(path: Vec<C>) -> Self {
+        PathRepr::Plain(path)
+    }
+}
+
+// Yields the changes by value: cloned from a plain path, decoded from a compressed one.
+#[non_exhaustive]
+pub enum PathIter<'a, C> {
+    Plain(std::slice::Iter<'a, C>),
+    Compressed {
+        reader: &'a [u8],
+        remaining: usize,
+        codec: &'a dyn ChangeCodec<C>
+    }
+}
+
+impl<C> Iterator for PathIter<'_, C>
+where
+    C: Clone
+{
+    type Item = C;
+
+    fn next(&mut self) -> Option<C> {
+        match self {
+            PathIter::Plain(iter) => iter.next().cloned(),
+            PathIter::Compressed { reader, remaining, codec } => {
+                if *remaining == 0 {
+                    return None;
+                }
+                *remaining -= 1;
+
+                let change = codec.decode(reader);
+                assert!(change.is_some(), "compressed path holds bytes its codec can't decode");
+                change
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            PathIter::Plain(iter) => iter.size_hint(),
+            PathIter::Compressed { remaining, .. } => (*remaining, Some(*remaining))
+        }
+    }
+}
+
+impl<'a, C> IntoIterator for &'a PathRepr<C>
+where
+    C: Clone
+{
+    type Item = C;
+    type IntoIter = PathIter<'a, C>;
+
+    fn into_iter(self) -> PathIter<'a, C> {
+        self.iter()
+    }
+}
+
+impl<C> PartialEq for PathRepr<C>
+where
+    C: Clone + PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<C> PartialEq<Vec<C>> for PathRepr<C>
+where
+    C: Clone + PartialEq
+{
+    fn eq(&self, other: &Vec<C>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter().cloned())
+    }
+}
+
+impl<C> Debug for PathRepr<C>
+where
+    C: Clone + Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Single byte codec for enums without fields, given every variant in any fixed order. A change
+// is encoded as its index in that list.
+pub struct FieldlessCodec<C> {
+    variants: Vec<C>
+}
+
+impl<C> FieldlessCodec<C>
+where
+    C: Clone + PartialEq
+{
+    pub fn new(variants: &[C]) -> Self {
+        assert!(variants.len() <= 256, "a single byte codec holds at most 256 variants");
+
+        FieldlessCodec {
+            variants: variants.to_vec()
+        }
+    }
+}
+
+impl<C> ChangeCodec<C> for FieldlessCodec<C>
+where
+    C: Clone + PartialEq
+{
+    fn encode(&self, change: &C, writer: &mut Vec<u8>) {
+        let index = self.variants.iter()
+            .position(|variant| variant == change)
+            .expect("change is not one of the codec's variants");
+        writer.push(index as u8);
+    }
+
+    fn decode(&self, reader: &mut &[u8]) -> Option<C> {
+        let (&index, rest) = reader.split_first()?;
+        let change = self.variants.get(index as usize)?.clone();
+        *reader = rest;
+        Some(change)
+    }
+}
+
+// Two byte codec for changes shaped like (variant, u8 payload), e.g. `Movement::Up(2)`. `split`
+// returns the variant tag and the payload, `join` builds the change back or returns None for an
+// unknown tag.
+pub struct PayloadCodec<C> {
+    split: fn(&C) -> (u8, u8),
+    join: fn(u8, u8) -> Option<C>
+}
+
+impl<C> PayloadCodec<C> {
+    pub fn new(split: fn(&C) -> (u8, u8), join: fn(u8, u8) -> Option<C>) -> Self {
+        PayloadCodec {
+            split,
+            join
+        }
+    }
+}
+
+impl<C> ChangeCodec<C> for PayloadCodec<C> {
+    fn encode(&self, change: &C, writer: &mut Vec<u8>) {
+        let (tag, payload) = (self.split)(change);
+        writer.push(tag);
+        writer.push(payload);
+    }
+
+    fn decode(&self, reader: &mut &[u8]) -> Option<C> {
+        let [tag, payload, rest @ ..] = *reader else {
+            return None;
+        };
+        let change = (self.join)(*tag, *payload)?;
+        *reader = rest;
+        Some(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng::SplitMix64, test_support::Direction};
+
+    const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Movement {
+        Up(u8),
+        Down(u8),
+        Left(u8),
+        Right(u8)
+    }
+
+    // A chunkier change: which piece moved and where to.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Push {
+        piece: usize,
+        direction: Direction
+    }
+
+    fn movement_codec() -> PayloadCodec<Movement> {
+        PayloadCodec::new(
+            |movement| match *movement {
+                Movement::Up(steps) => (0, steps),
+                Movement::Down(steps) => (1, steps),
+                Movement::Left(steps) => (2, steps),
+                Movement::Right(steps) => (3, steps)
+            },
+            |tag, steps| match tag {
+                0 => Some(Movement::Up(steps)),
+                1 => Some(Movement::Down(steps)),
+                2 => Some(Movement::Left(steps)),
+                3 => Some(Movement::Right(steps)),
+                _ => None
+            }
+        )
+    }
+
+    fn push_codec() -> PayloadCodec<Push> {
+        PayloadCodec::new(
+            |push| (DIRECTIONS.iter().position(|&direction| direction == push.direction).unwrap() as u8, push.piece as u8),
+            |tag, piece| DIRECTIONS.get(tag as usize).map(|&direction| Push { piece: piece as usize, direction })
+        )
+    }
+
+    fn random_movement(rng: &mut SplitMix64) -> Movement {
+        let steps = rng.next_below(256) as u8;
+        match rng.next_below(4) {
+            0 => Movement::Up(steps),
+            1 => Movement::Down(steps),
+            2 => Movement::Left(steps),
+            _ => Movement::Right(steps)
+        }
+    }
+
+    #[test]
+    fn test_fieldless_round_trip() {
+        let mut rng = SplitMix64::new(7);
+        let codec: Rc<dyn ChangeCodec<Direction>> = Rc::new(FieldlessCodec::new(&DIRECTIONS));
+
+        for len in [0, 1, 2, 17, 500] {
+            let path: Vec<Direction> = (0..len).map(|_| DIRECTIONS[rng.next_below(4)]).collect();
+            let compressed = PathRepr::compress(path.clone(), codec.clone());
+
+            assert!(compressed.is_compressed());
+            assert_eq!(compressed.len(), len);
+            assert_eq!(compressed.heap_bytes(), len);
+            assert_eq!(compressed.to_vec(), path);
+            assert_eq!(compressed, PathRepr::from(path));
+        }
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let mut rng = SplitMix64::new(11);
+        let codec: Rc<dyn ChangeCodec<Movement>> = Rc::new(movement_codec());
+
+        for len in [0, 1, 3, 64, 1000] {
+            let path: Vec<Movement> = (0..len).map(|_| random_movement(&mut rng)).collect();
+            let compressed = PathRepr::compress(path.clone(), codec.clone());
+
+            assert_eq!(compressed.iter().size_hint(), (len, Some(len)));
+            assert!(compressed.iter().eq(path.iter().copied()));
+            assert_eq!(compressed, path);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_bytes() {
+        let movement = movement_codec();
+        let mut truncated: &[u8] = &[1];
+        let mut unknown_tag: &[u8] = &[9, 1];
+        assert_eq!(movement.decode(&mut truncated), None);
+        assert_eq!(movement.decode(&mut unknown_tag), None);
+
+        let fieldless = FieldlessCodec::new(&DIRECTIONS);
+        let mut out_of_range: &[u8] = &[4];
+        assert_eq!(fieldless.decode(&mut out_of_range), None);
+    }
+
+    #[test]
+    fn test_memory_of_long_solution() {
+        let mut rng = SplitMix64::new(100);
+        let path: Vec<Push> = (0..100_000)
+            .map(|_| Push { piece: rng.next_below(12), direction: DIRECTIONS[rng.next_below(4)] })
+            .collect();
+
+        let plain = PathRepr::from(path.clone());
+        let compressed = PathRepr::compress(path, Rc::new(push_codec()));
+
+        assert_eq!(compressed.heap_bytes(), 200_000);
+        assert_eq!(plain.heap_bytes(), 100_000 * mem::size_of::<Push>());
+        assert!(compressed.heap_bytes() * 4 <= plain.heap_bytes());
+        assert_eq!(plain, compressed);
+    }
+}
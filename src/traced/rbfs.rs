@@ -0,0 +1,296 @@
+use std::hash::Hash;
+
+use crate::traced::{dfs_stack::{DfsFrame, DfsStack}, result::TracedResult, state::TracedState};
+
+// A state on the current path, with its children and their backed-up f.
+struct Frame<S, K, C> {
+    key: K,
+    children: Vec<(S, C, usize)>,
+    // The f of the best alternative anywhere above.
+    bound: usize,
+    // The child searched last, the one the frame above stands for while there is one.
+    searched: usize
+}
+
+impl<S, K, C> DfsFrame<K> for Frame<S, K, C> {
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+// Recursive best-first search: a depth-first recursion into the child of lowest backed-up f,
+// bounded by the f of the best alternative anywhere above it. A subtree that exceeds its bound
+// backs its smallest exceeding f up to its root and is forgotten, so memory is linear in the
+// depth times the branching factor. Unlike ida_star, each subtree resumes from the f it was left
+// at, so most iterations don't restart from the initial state. Optimal for an admissible h, and
+// states already on the current path are skipped. The recursion runs on an explicit stack, so
+// the depth of the solution isn't limited by the thread's stack.
+pub fn rbfs<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    if initial_state.is_goal() {
+        return Some(TracedResult::new(Vec::new(), 0, initial_state));
+    }
+
+    let mut stack = DfsStack::new();
+    let mut expansions = 0;
+    let f = initial_state.f();
+    let root = expand(&initial_state, f, usize::MAX, &stack, &mut expansions);
+    stack.push(root);
+
+    while let Some(frame) = stack.top_mut() {
+        // Ties go to the first child, the others become the alternative with the same f.
+        let best = (0..frame.children.len()).min_by_key(|&index| frame.children[index].2);
+        let best_f = best.map_or(usize::MAX, |best| frame.children[best].2);
+
+        // The subtree backs its f up to the child of its parent it stands for.
+        if best_f > frame.bound || best_f == usize::MAX {
+            stack.pop();
+            match stack.top_mut() {
+                Some(parent) => {
+                    let searched = parent.searched;
+                    parent.children[searched].2 = best_f;
+                    continue;
+                }
+                None => return None
+            }
+        }
+
+        let best = best.expect("a child has an f below usize::MAX");
+        if frame.children[best].0.is_goal() {
+            let (goal, change, _) = frame.children.swap_remove(best);
+            let mut path: Vec<C> = stack.take_frames()
+                .into_iter()
+                .rev()
+                .skip(1)
+                .map(|mut frame| frame.children.swap_remove(frame.searched).1)
+                .collect();
+            path.reverse();
+            path.push(change);
+            return Some(TracedResult::new(path, expansions, goal));
+        }
+
+        let alternative = frame.children.iter()
+            .enumerate()
+            .filter(|&(index, _)| index != best)
+            .map(|(_, (_, _, f))| *f)
+            .min()
+            .unwrap_or(usize::MAX);
+
+        frame.searched = best;
+        let bound = frame.bound.min(alternative);
+        let frame = stack.frames().last().expect("the frame is still there");
+        let child = expand(&frame.children[best].0, best_f, bound, &stack, &mut expansions);
+        stack.push(child);
+    }
+
+    None
+}
+
+// Frame of `state`, whose backed-up f is `backed_up_f`, to be searched while its best child stays
+// within `bound`. Goals are tested when their parent picks them, so a goal is only returned once
+// no child has a lower f.
+fn expand<S, K, C>(state: &S, backed_up_f: usize, bound: usize, stack: &DfsStack<K, Frame<S, K, C>>, expansions: &mut usize) -> Frame<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let key = state.key();
+    let successors = state.generate_traced_successors();
+    *expansions += 1;
+
+    // A state whose backed-up f is above its own was explored before, and its children have
+    // already shown they can't do better than that.
+    let explored = state.f() < backed_up_f;
+    let children = successors.into_iter()
+        .filter(|(child, _)| !stack.on_path(&child.key()))
+        .map(|(child, change)| {
+            let f = if explored { child.f().max(backed_up_f) } else { child.f() };
+            (child, change, f)
+        })
+        .collect();
+
+    Frame { key, children, bound, searched: 0 }
+}
+
+// rbfs as it was written before the explicit stack, for the tests to compare expansion counts
+// and paths with. A deep solution overflows the thread's stack in it.
+#[cfg(test)]
+mod recursive {
+    use std::{collections::HashSet, hash::Hash};
+
+    use crate::traced::{result::TracedResult, state::TracedState};
+
+    enum Probe<S> {
+        Found(S),
+        // Backed-up f of the subtree: the smallest f above the bound in it, usize::MAX for dead ends.
+        Exceeded(usize)
+    }
+
+    struct Context<K, C> {
+        path_keys: HashSet<K>,
+        // Changes of the path to the goal, pushed from the goal back up to the root.
+        reversed_changes: Vec<C>,
+        expansions: usize
+    }
+
+    pub(super) fn rbfs<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
+    where
+        S: TracedState<K, C>,
+        K: Clone + Eq + Hash
+    {
+        if initial_state.is_goal() {
+            return Some(TracedResult::new(Vec::new(), 0, initial_state));
+        }
+
+        let mut context = Context {
+            path_keys: HashSet::new(),
+            reversed_changes: Vec::new(),
+            expansions: 0
+        };
+
+        let f = initial_state.f();
+        match search(&initial_state, f, usize::MAX, &mut context) {
+            Probe::Found(final_state) => {
+                let mut path = context.reversed_changes;
+                path.reverse();
+                Some(TracedResult::new(path, context.expansions, final_state))
+            }
+            Probe::Exceeded(_) => None
+        }
+    }
+
+    // Expands `state`, whose backed-up f is `backed_up_f`, while its best child stays within
+    // `bound`. Goals are tested when their parent picks them, so a goal is only returned once no
+    // child has a lower f.
+    fn search<S, K, C>(state: &S, backed_up_f: usize, bound: usize, context: &mut Context<K, C>) -> Probe<S>
+    where
+        S: TracedState<K, C>,
+        K: Clone + Eq + Hash
+    {
+        let key = state.key();
+        let successors = state.generate_traced_successors();
+        context.expansions += 1;
+
+        // A state whose backed-up f is above its own was explored before, and its children have
+        // already shown they can't do better than that.
+        let explored = state.f() < backed_up_f;
+        let mut children: Vec<(S, C, usize)> = successors.into_iter()
+            .filter(|(child, _)| !context.path_keys.contains(&child.key()))
+            .map(|(child, change)| {
+                let f = if explored { child.f().max(backed_up_f) } else { child.f() };
+                (child, change, f)
+            })
+            .collect();
+
+        context.path_keys.insert(key.clone());
+
+        let probe = loop {
+            // Ties go to the first child, the others become the alternative with the same f.
+            let Some(best) = (0..children.len()).min_by_key(|&index| children[index].2) else {
+                break Probe::Exceeded(usize::MAX);
+            };
+
+            let best_f = children[best].2;
+            if best_f > bound || best_f == usize::MAX {
+                break Probe::Exceeded(best_f);
+            }
+
+            if children[best].0.is_goal() {
+                let (goal, change, _) = children.swap_remove(best);
+                context.reversed_changes.push(change);
+                break Probe::Found(goal);
+            }
+
+            let alternative = children.iter()
+                .enumerate()
+                .filter(|&(index, _)| index != best)
+                .map(|(_, (_, _, f))| *f)
+                .min()
+                .unwrap_or(usize::MAX);
+
+            match search(&children[best].0, best_f, bound.min(alternative), context) {
+                Probe::Found(goal) => {
+                    let (_, change, _) = children.swap_remove(best);
+                    context.reversed_changes.push(change);
+                    break Probe::Found(goal);
+                }
+                Probe::Exceeded(f) => children[best].2 = f
+            }
+        };
+
+        context.path_keys.remove(&key);
+        probe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, Lattice, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    #[test]
+    fn test_graph_optimum() {
+        for h in [EIGHT_NODES_H, ZERO_H] {
+            let result = rbfs(GraphState::new(EIGHT_NODES, h, 0, 7)).unwrap();
+            let optimal = traced_astar(GraphState::new(EIGHT_NODES, h, 0, 7)).unwrap();
+
+            assert_eq!(result.final_state.g(), optimal.final_state.g());
+            let end = replay(&GraphState::new(EIGHT_NODES, h, 0, 7), result.path.to_vec()).unwrap();
+            assert!(end.is_goal());
+            assert_eq!(end.g(), 9);
+        }
+
+        assert_eq!(rbfs(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 7)).unwrap().iterations, 0);
+    }
+
+    #[test]
+    fn test_sliding_puzzle_optimum() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 200);
+            let optimal = traced_astar(start.clone()).unwrap().final_state.g();
+            let result = rbfs(start.clone()).unwrap();
+
+            assert_eq!(result.final_state.g(), optimal);
+            assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+        }
+    }
+
+    #[test]
+    fn test_tied_siblings() {
+        // h is 0 on the lattice, so every child of a state ties with its siblings.
+        let result = rbfs(Lattice::new(3)).unwrap();
+        assert_eq!(result.final_state.g(), 6);
+        assert_eq!(result.path.len(), 6);
+    }
+
+    #[test]
+    fn test_dead_ends_and_huge_f() {
+        // 1 is a dead end that looks best, 4 only leads back with h close to usize::MAX, 2 leads
+        // on to the goal 3.
+        const DEAD_ENDS: &[&[(usize, usize)]] = &[&[(1, 1), (4, 1), (2, 1)], &[], &[(3, 5)], &[], &[(0, 1)]];
+        const DEAD_ENDS_H: &[usize] = &[0, 0, 0, 0, usize::MAX - 100];
+        let result = rbfs(GraphState::new(DEAD_ENDS, DEAD_ENDS_H, 0, 3)).unwrap();
+        assert_eq!(result.final_state.g(), 6);
+        assert_eq!(result.path.to_vec(), vec![2, 3]);
+
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        assert!(rbfs(GraphState::new(SPLIT, &[0, 0, 0], 0, 2)).is_none());
+    }
+
+    #[test]
+    fn test_same_order_as_the_recursive_search() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 200);
+            let iterative = rbfs(start.clone()).unwrap();
+            let recursive = recursive::rbfs(start).unwrap();
+            assert_eq!((iterative.iterations, iterative.path.to_vec()), (recursive.iterations, recursive.path.to_vec()));
+        }
+
+        let iterative = rbfs(Lattice::new(3)).unwrap();
+        let recursive = recursive::rbfs(Lattice::new(3)).unwrap();
+        assert_eq!((iterative.iterations, iterative.path.to_vec()), (recursive.iterations, recursive.path.to_vec()));
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{replay::replay_states, result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::traced_astar}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RepairOptions {
+    detour_budget: Option<usize>
+}
+
+impl RepairOptions {
+    // Any detour is accepted.
+    pub fn new() -> Self {
+        RepairOptions::default()
+    }
+
+    // The repaired path may cost at most `extra` more than the original one, or repair_path falls
+    // back to a full search.
+    pub fn with_detour_budget(mut self, extra: usize) -> Self {
+        self.detour_budget = Some(extra);
+        self
+    }
+
+    pub fn detour_budget(&self) -> Option<usize> {
+        self.detour_budget
+    }
+}
+
+// Repairs a path after a change near one of its steps: `original_start` is the start the result
+// was found from, `start` the same start after the change, and the first `invalid_from` changes
+// of the path must still be valid from it. From the state they lead to, a uniform cost search
+// looks for the nearest key of the original path after that state, the earliest of them on a tie,
+// and the rest of the original path is replayed from there. The result's `iterations` are the
+// expansions of that search.
+//
+// When the original path was optimal and the change only removed moves or raised costs, no path
+// is cheaper than the original any more, so a repaired path costs at most the detour budget more
+// than the best one. Without a way back to the original path within the budget, or when the rest
+// of the path is broken too, the result is a plain traced_astar from `start`.
+pub fn repair_path<S, K, C>(result: &TracedResult<S, K, C>, original_start: &S, start: S, invalid_from: usize, options: &RepairOptions) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq
+{
+    spliced(result, original_start, &start, invalid_from, options).or_else(|| traced_astar(start))
+}
+
+fn spliced<S, K, C>(result: &TracedResult<S, K, C>, original_start: &S, start: &S, invalid_from: usize, options: &RepairOptions) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone + PartialEq
+{
+    let changes = result.path.to_vec();
+    let invalid_from = invalid_from.min(changes.len());
+    let original = replay_states(original_start.clone(), changes.iter().cloned()).ok()?;
+    let limit = options.detour_budget().map(|extra| result.cost.saturating_add(extra));
+
+    let broken = replay_states(start.clone(), changes[..invalid_from].iter().cloned()).ok()?.pop()?;
+    // Position on the original path of each key after the broken state, the first if a key
+    // comes back.
+    let mut later = HashMap::new();
+    for (index, state) in original.iter().enumerate().skip(invalid_from + 1) {
+        later.entry(state.key()).or_insert(index);
+    }
+
+    let (detour, reconnection, index, expansions) = detour(broken, &later, limit)?;
+    let rest = replay_states(reconnection, changes[index..].iter().cloned()).ok()?;
+    let goal = rest.into_iter().last().filter(|goal| goal.is_goal())?;
+    if limit.is_some_and(|limit| goal.g() > limit) {
+        return None;
+    }
+
+    let path = changes[..invalid_from].iter().cloned().chain(detour).chain(changes[index..].iter().cloned()).collect();
+    Some(TracedResult::new(path, expansions, goal))
+}
+
+// Uniform cost search from `broken` to the nearest key of `later`, leaving out states over the
+// cost limit. Returns the changes of the detour, the state it ends at, that state's position on
+// the original path and the expansions.
+fn detour<S, K, C>(broken: S, later: &HashMap<K, usize>, limit: Option<usize>) -> Option<(Vec<C>, S, usize, usize)>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut open_list = OpenList::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let broken_g = broken.g();
+    open_list.insert(broken.key(), Prioritized::new(TracedStateWrapper::new(broken), broken_g));
+
+    // The first reconnection found, then any other at the same g with an earlier position.
+    let mut found: Option<(TracedStateWrapper<S, K, C>, usize)> = None;
+    while let Some(Prioritized { state: current, .. }) = open_list.extract_min() {
+        if let Some((reconnection, _)) = &found
+            && current.state.g() > reconnection.state.g()
+        {
+            break;
+        }
+        if let Some(&index) = later.get(&current.key()) {
+            if found.as_ref().is_none_or(|(_, earliest)| index < *earliest) {
+                found = Some((current, index));
+            }
+            continue;
+        }
+        if found.is_some() {
+            continue;
+        }
+
+        let successors = current.generate_states();
+        closed_list.insert(current.key(), current);
+        for successor in successors {
+            let successor_key = successor.key();
+            if closed_list.contains_key(&successor_key) || limit.is_some_and(|limit| successor.state.g() > limit) {
+                continue;
+            }
+
+            let successor_g = successor.state.g();
+            open_list.insert(successor_key, Prioritized::new(successor, successor_g));
+        }
+    }
+
+    let (reconnection, index) = found?;
+    let expansions = closed_list.len();
+    let mut path: Vec<C> = reconnection.change.iter().cloned().collect();
+    let mut prev_key = reconnection.prev_key.clone();
+    while let Some(state) = prev_key.and_then(|key| closed_list.remove(&key)) {
+        path.extend(state.change);
+        prev_key = state.prev_key;
+    }
+    path.reverse();
+
+    Some((path, reconnection.state, index, expansions))
+}
+
+#[cfg(all(test, feature = "grid"))]
+mod tests {
+    use super::*;
+    use crate::grid::{map::GridMap, region::GoalRegion, state::{Direction, GridState}};
+
+    // Straight along the middle row, with a way around above it.
+    const BYPASS: &str = "
+        #.....#
+        .......
+        #######
+    ";
+
+    // Straight along the top row, with the only other way round the far side of the wall.
+    const LOOP: &str = "
+        .........
+        .#######.
+        .........
+    ";
+
+    #[test]
+    fn test_reconnects_right_after_the_break() {
+        use Direction::*;
+
+        let map = GridMap::from_ascii(BYPASS);
+        let goal = GoalRegion::Cell(6, 1);
+        let original_start = GridState::with_region(&map, (0, 1), &goal);
+        let original = traced_astar(original_start.clone()).unwrap();
+        assert_eq!(original.path.to_vec(), vec![Right; 6]);
+
+        let mut closed = map.clone();
+        closed.set_blocked(3, 1, true);
+        let start = GridState::with_region(&closed, (0, 1), &goal);
+        let repaired = repair_path(&original, &original_start, start.clone(), 2, &RepairOptions::new()).unwrap();
+        assert_eq!(repaired.path.to_vec(), vec![Right, Right, Up, Right, Right, Down, Right, Right]);
+        let replayed = replay_states(start.clone(), repaired.path.to_vec()).unwrap().pop().unwrap();
+        assert!(replayed.is_goal());
+        assert_eq!(repaired.cost, replayed.g());
+        assert_eq!(repaired.cost, traced_astar(start).unwrap().cost);
+    }
+
+    #[test]
+    fn test_costly_detours_fall_back_to_a_full_search() {
+        let map = GridMap::from_ascii(LOOP);
+        let goal = GoalRegion::Cell(8, 0);
+        let original_start = GridState::with_region(&map, (0, 0), &goal);
+        let original = traced_astar(original_start.clone()).unwrap();
+        assert_eq!(original.cost, 8);
+
+        let mut closed = map.clone();
+        closed.set_blocked(5, 0, true);
+        let start = GridState::with_region(&closed, (0, 0), &goal);
+        let optimum = traced_astar(start.clone()).unwrap().cost;
+        assert_eq!(optimum, 12);
+
+        // Back 4 cells and round the wall to the goal, the nearest key of the rest of the path.
+        let repaired = repair_path(&original, &original_start, start.clone(), 4, &RepairOptions::new().with_detour_budget(12)).unwrap();
+        assert_eq!(repaired.cost, 20);
+        assert!(repaired.cost <= optimum + 12);
+        assert_eq!(repaired.path.to_vec()[..4], [Direction::Right; 4]);
+
+        let fallback = repair_path(&original, &original_start, start, 4, &RepairOptions::new().with_detour_budget(11)).unwrap();
+        assert_eq!(fallback.cost, optimum);
+    }
+}
@@ -0,0 +1,60 @@
+use std::hash::Hash;
+
+use crate::traced::state::TracedState;
+
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplayError {
+    // No successor of the state reached after `step` changes was produced by the next change.
+    InvalidChange { step: usize },
+    NotAGoal
+}
+
+// Applies the path to `initial` by picking, at every step, the successor generated with that
+// change. Returns the state reached at the end, which must be a goal.
+pub fn replay<S, K, C>(initial: &S, path: impl IntoIterator<Item = C>) -> Result<S, ReplayError>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: PartialEq
+{
+    let mut current = initial.clone();
+
+    for (step, change) in path.into_iter().enumerate() {
+        current = current.generate_traced_successors()
+            .into_iter()
+            .find(|(_, successor_change)| *successor_change == change)
+            .map(|(successor, _)| successor)
+            .ok_or(ReplayError::InvalidChange { step })?;
+    }
+
+    if current.is_goal() {
+        Ok(current)
+    }
+    else {
+        Err(ReplayError::NotAGoal)
+    }
+}
+
+// Every state the path passes through, `initial` first, picked like replay does. The last one
+// doesn't have to be a goal.
+pub fn replay_states<S, K, C>(initial: S, path: impl IntoIterator<Item = C>) -> Result<Vec<S>, ReplayError>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: PartialEq
+{
+    let mut states = vec![initial];
+
+    for (step, change) in path.into_iter().enumerate() {
+        let current = states.last().expect("the initial state is always there");
+        let next = current.generate_traced_successors()
+            .into_iter()
+            .find(|(_, successor_change)| *successor_change == change)
+            .map(|(successor, _)| successor)
+            .ok_or(ReplayError::InvalidChange { step })?;
+        states.push(next);
+    }
+
+    Ok(states)
+}
@@ -0,0 +1,174 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{astar_state::AStarState, open_list::OpenList, prioritized::Prioritized, traced::{ara_star::build_result, result::TracedResult, state::{TracedState, TracedStateWrapper}}, weight_schedule::inflated_f};
+
+// One search of `restarting_weighted_astar`. `cost` is that of the goal it found, None when
+// nothing under the bound was left to find.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestartPhase {
+    pub weight: f64,
+    pub expansions: usize,
+    pub cost: Option<usize>
+}
+
+pub struct RestartingOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub result: Option<TracedResult<S, K, C>>,
+    pub phases: Vec<RestartPhase>,
+    // Calls to `h()`, once per key over all the phases.
+    pub h_evaluations: usize
+}
+
+impl<S, K, C> RestartingOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub fn total_expansions(&self) -> usize {
+        self.phases.iter().map(|phase| phase.expansions).sum()
+    }
+}
+
+// Restarting weighted A*: weighted A* with each weight of `weights` in turn, every one a new
+// search from the initial state that stops at its first goal. What carries over is the cost of
+// the best goal so far, which prunes every state whose f can't beat it, and the h of every key
+// seen, so no state is estimated twice. Unlike ARA*, a restart doesn't keep the open list, which
+// drops the poor choices the higher weight made near the start. The last result found is
+// returned. With an admissible and consistent h it is optimal once a weight of 1 has run, and
+// within weight times the optimum after any other. Weights below 1 are raised to 1.
+pub fn restarting_weighted_astar<S, K, C>(initial_state: S, weights: &[f64]) -> RestartingOutcome<S, K, C>
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut h_cache: HashMap<K, usize> = HashMap::new();
+    let mut result: Option<TracedResult<S, K, C>> = None;
+    let mut phases = Vec::with_capacity(weights.len());
+
+    for weight in weights.iter().map(|&weight| weight.max(1.0)) {
+        let bound = result.as_ref().map(|incumbent| incumbent.cost);
+        let (found, expansions) = search(&initial_state, weight, bound, &mut h_cache);
+
+        phases.push(RestartPhase { weight, expansions, cost: found.as_ref().map(|goal| goal.cost) });
+        if found.is_some() {
+            result = found;
+        }
+    }
+
+    RestartingOutcome { result, phases, h_evaluations: h_cache.len() }
+}
+
+// One weighted A* up to its first goal cheaper than `bound`, with the expansions it took.
+fn search<S, K, C>(initial_state: &S, weight: f64, bound: Option<usize>, h_cache: &mut HashMap<K, usize>) -> (Option<TracedResult<S, K, C>>, usize)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut open_list: OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>> = OpenList::new();
+    let mut closed: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let beats_bound = |f: usize| bound.is_none_or(|bound| f < bound);
+
+    let initial = TracedStateWrapper::new(initial_state.clone());
+    let h = *h_cache.entry(initial.key()).or_insert_with(|| initial.h());
+    if beats_bound(initial.g() + h) {
+        let priority = inflated_f(initial.g() + h, h, weight);
+        open_list.insert(initial.key(), Prioritized::new(initial, priority));
+    }
+
+    while let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() {
+        if current_state.is_goal() {
+            let mut found = build_result(&current_state, &closed);
+            found.iterations = closed.len();
+            return (Some(found), closed.len());
+        }
+
+        let successors = current_state.generate_states();
+        closed.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+            if closed.contains_key(&successor_key) {
+                continue;
+            }
+
+            let h = *h_cache.entry(successor_key.clone()).or_insert_with(|| successor.h());
+            let f = successor.g() + h;
+            if beats_bound(f) {
+                open_list.insert(successor_key, Prioritized::new(successor, inflated_f(f, h, weight)));
+            }
+        }
+    }
+
+    (None, closed.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, SlidingPuzzle, DETOUR, DETOUR_H, EIGHT_NODES, EIGHT_NODES_H}, traced::{replay::replay, traced_astar::traced_astar}};
+
+    #[test]
+    fn test_last_weight_of_one_is_optimal() {
+        for seed in 0..6 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let optimal = traced_astar(start.clone()).unwrap();
+
+            let outcome = restarting_weighted_astar(start.clone(), &[5.0, 2.0, 1.0]);
+            let result = outcome.result.unwrap();
+            assert_eq!(result.cost, optimal.cost);
+            assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+
+            let costs: Vec<usize> = outcome.phases.iter().filter_map(|phase| phase.cost).collect();
+            assert!(costs.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", costs);
+        }
+    }
+
+    // Once the incumbent is optimal, the last phase expands only states with f below the
+    // optimum, while a search without the bound also expands some with f equal to it, at least
+    // the parent of the goal.
+    #[test]
+    fn test_bound_prunes_the_last_phase() {
+        let mut optimal_incumbents = 0;
+        for seed in 0..8 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let optimal = traced_astar(start.clone()).unwrap();
+            let fresh = restarting_weighted_astar(start.clone(), &[1.0]).phases[0];
+
+            let outcome = restarting_weighted_astar(start, &[1.5, 1.0]);
+            if outcome.phases[0].cost == Some(optimal.cost) {
+                optimal_incumbents += 1;
+                assert_eq!(outcome.phases[1].cost, None);
+                assert!(outcome.phases[1].expansions < fresh.expansions, "seed {}: {:?} vs {:?}", seed, outcome.phases[1], fresh);
+                assert!(outcome.phases[1].expansions < optimal.iterations);
+            }
+        }
+        assert!(optimal_incumbents > 0);
+    }
+
+    #[test]
+    fn test_phases_on_a_detour() {
+        let outcome = restarting_weighted_astar(GraphState::new(DETOUR, DETOUR_H, 0, 3), &[3.0, 1.0]);
+        assert_eq!(outcome.phases.iter().map(|phase| phase.cost).collect::<Vec<_>>(), vec![Some(11), Some(8)]);
+        assert_eq!(outcome.result.unwrap().path.to_vec(), vec![2, 3]);
+        // The four nodes, each estimated once over both phases.
+        assert_eq!(outcome.h_evaluations, 4);
+
+        // Nothing beats the optimum once it's the bound.
+        let outcome = restarting_weighted_astar(GraphState::new(DETOUR, DETOUR_H, 0, 3), &[1.0, 1.0]);
+        assert_eq!(outcome.phases[1], RestartPhase { weight: 1.0, expansions: 2, cost: None });
+        assert_eq!(outcome.result.unwrap().cost, 8);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let outcome = restarting_weighted_astar(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0), &[2.0, 1.0]);
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.phases.len(), 2);
+        assert!(outcome.phases.iter().all(|phase| phase.cost.is_none()));
+    }
+}
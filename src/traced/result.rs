@@ -1,15 +1,20 @@
-use std::hash::Hash;
+use std::{hash::Hash, rc::Rc};
 
-use crate::traced::state::TracedState;
+use crate::traced::{path::{ChangeCodec, PathRepr}, state::TracedState};
 
 pub struct TracedResult<S, K, C>
 where
     K: Clone + Eq + Hash,
     S: TracedState<K, C>
 {
-    pub path: Vec<C>,
+    pub path: PathRepr<C>,
     pub iterations: usize,
     pub final_state: S,
+    // g of the goal as the search saw it, which differs from final_state.g() under an edge cost
+    // override.
+    pub cost: usize,
+    // Cost of each change of the path, from the searches that keep every parent on the path.
+    pub step_costs: Option<Vec<usize>>,
     _marker: std::marker::PhantomData<K>
 }
 
@@ -20,10 +25,41 @@ where
 {
     pub fn new(path: Vec<C>, iterations: usize, final_state: S) -> Self {
         Self {
-            path,
+            path: PathRepr::Plain(path),
             iterations,
+            cost: final_state.g(),
+            step_costs: None,
             final_state,
             _marker: std::marker::PhantomData,
         }
     }
+
+    pub(crate) fn with_costs(mut self, cost: usize, step_costs: Vec<usize>) -> Self {
+        self.cost = cost;
+        self.step_costs = Some(step_costs);
+        self
+    }
+
+    // The same result with the final state converted, for searches run over a wrapper of it.
+    pub(crate) fn map_state<T>(self, unwrap: impl FnOnce(S) -> T) -> TracedResult<T, K, C>
+    where
+        T: TracedState<K, C>
+    {
+        TracedResult {
+            path: self.path,
+            iterations: self.iterations,
+            final_state: unwrap(self.final_state),
+            cost: self.cost,
+            step_costs: self.step_costs,
+            _marker: std::marker::PhantomData
+        }
+    }
+
+    // Re-encodes a plain path with the codec. Already compressed paths are left as they are.
+    pub fn compressed(mut self, codec: Rc<dyn ChangeCodec<C>>) -> Self {
+        if let PathRepr::Plain(path) = self.path {
+            self.path = PathRepr::compress(path, codec);
+        }
+        self
+    }
 }
@@ -0,0 +1,335 @@
+use std::{cmp::Reverse, collections::{BTreeSet, HashMap, HashSet}, hash::Hash};
+
+use crate::traced::{result::TracedResult, state::TracedState};
+
+pub struct SmaOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    // `iterations` counts the nodes generated, regenerated ones included.
+    pub result: Option<TracedResult<S, K, C>>,
+    // Leaves forgotten to make room for a new node.
+    pub evictions: usize,
+    // Most nodes held at once, never more than the budget.
+    pub peak_nodes: usize
+}
+
+struct Node<S, K, C> {
+    state: S,
+    parent: Option<usize>,
+    change: Option<C>,
+    depth: usize,
+    // f of the state, raised to the f of its parent and to its backed-up f when it's generated
+    // again.
+    own_f: usize,
+    // Backed-up f: the lowest f left under the node, in memory or forgotten.
+    f: usize,
+    // Lowest f of the successors not in memory, usize::MAX once there are none to generate.
+    pending_f: usize,
+    children: Vec<usize>,
+    // Backed-up f of the children that were evicted, until they're generated again.
+    forgotten: Vec<(K, usize)>
+}
+
+// The search tree, with its nodes in an arena and two orders over them: nodes with successors
+// left to generate by lowest pending f and then deepest first, and leaves by highest f and then
+// shallowest first.
+struct Tree<S, K, C> {
+    nodes: Vec<Option<Node<S, K, C>>>,
+    free: Vec<usize>,
+    len: usize,
+    open: BTreeSet<(usize, Reverse<usize>, usize)>,
+    leaves: BTreeSet<(usize, Reverse<usize>, usize)>
+}
+
+impl<S, K, C> Tree<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    fn new() -> Self {
+        Tree { nodes: Vec::new(), free: Vec::new(), len: 0, open: BTreeSet::new(), leaves: BTreeSet::new() }
+    }
+
+    fn node(&self, id: usize) -> &Node<S, K, C> {
+        self.nodes[id].as_ref().expect("live node")
+    }
+
+    fn add(&mut self, node: Node<S, K, C>) -> usize {
+        let id = self.free.pop().unwrap_or(self.nodes.len());
+        if let Some(parent_id) = node.parent {
+            let parent = self.nodes[parent_id].as_mut().expect("live node");
+            if parent.children.is_empty() {
+                self.leaves.remove(&(parent.f, Reverse(parent.depth), parent_id));
+            }
+            parent.children.push(id);
+        }
+
+        if node.pending_f != usize::MAX {
+            self.open.insert((node.pending_f, Reverse(node.depth), id));
+        }
+        self.leaves.insert((node.f, Reverse(node.depth), id));
+        if id == self.nodes.len() {
+            self.nodes.push(Some(node));
+        }
+        else {
+            self.nodes[id] = Some(node);
+        }
+        self.len += 1;
+        id
+    }
+
+    // Changes the f and pending f of `id`, keeping both orders in step.
+    fn update(&mut self, id: usize, f: usize, pending_f: usize) {
+        let node = self.nodes[id].as_mut().expect("live node");
+        let (depth, leaf) = (Reverse(node.depth), node.children.is_empty());
+
+        if node.pending_f != usize::MAX {
+            self.open.remove(&(node.pending_f, depth, id));
+        }
+        if pending_f != usize::MAX {
+            self.open.insert((pending_f, depth, id));
+        }
+        if leaf {
+            self.leaves.remove(&(node.f, depth, id));
+            self.leaves.insert((f, depth, id));
+        }
+
+        node.f = f;
+        node.pending_f = pending_f;
+    }
+
+    // Recomputes the backed-up f of `id` and of its ancestors, up to the first that keeps its f.
+    fn back_up(&mut self, mut id: usize) {
+        loop {
+            let node = self.node(id);
+            let lowest_child = node.children.iter().map(|&child| self.node(child).f).min().unwrap_or(usize::MAX);
+            let f = node.own_f.max(node.pending_f.min(lowest_child));
+            if f == node.f {
+                return;
+            }
+
+            let (pending_f, parent) = (node.pending_f, node.parent);
+            self.update(id, f, pending_f);
+            match parent {
+                Some(parent) => id = parent,
+                None => return
+            }
+        }
+    }
+
+    // Forgets the leaf of highest f, the shallowest of those, other than the root and `keep`.
+    // Its parent keeps its f for when it's generated again. Returns false when there is no such
+    // leaf.
+    fn evict(&mut self, root: usize, keep: usize) -> bool {
+        let Some(&(_, _, id)) = self.leaves.iter().rev().find(|&&(_, _, id)| id != root && id != keep) else {
+            return false;
+        };
+
+        let node = self.nodes[id].take().expect("live node");
+        self.leaves.remove(&(node.f, Reverse(node.depth), id));
+        if node.pending_f != usize::MAX {
+            self.open.remove(&(node.pending_f, Reverse(node.depth), id));
+        }
+        self.free.push(id);
+        self.len -= 1;
+
+        let parent_id = node.parent.expect("the root is never evicted");
+        let parent = self.nodes[parent_id].as_mut().expect("live node");
+        parent.children.retain(|&child| child != id);
+        parent.forgotten.push((node.state.key(), node.f));
+        if parent.children.is_empty() {
+            self.leaves.insert((parent.f, Reverse(parent.depth), parent_id));
+        }
+
+        let (parent_f, pending_f) = (parent.f, parent.pending_f.min(node.f));
+        self.update(parent_id, parent_f, pending_f);
+        self.back_up(parent_id);
+        true
+    }
+}
+
+// Simplified memory-bounded A*: a tree search that holds at most `max_nodes` nodes. The node of
+// lowest f (the deepest of those) generates one successor at a time, its best one not in memory.
+// When the tree is full the leaf of highest f is forgotten first. Its parent keeps its backed-up
+// f, so the subtree is only regenerated once nothing in memory beats it.
+// Returns the optimal path when it fits, that is when it has fewer than `max_nodes` changes and
+// h is admissible. A state already on the path to a node is never generated under it, so memory
+// isn't spent on cycles, but the same state can be held under several paths. Of several moves
+// from a node to the same state only the cheapest is followed. A non-goal node at the deepest
+// level the budget allows is a dead end.
+pub fn sma_star<S, K, C>(initial_state: S, max_nodes: usize) -> SmaOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut tree = Tree::new();
+    let (mut generated, mut evictions) = (0, 0);
+    // A node at `depth` can have a child as long as the path to it still fits.
+    let expandable = |depth: usize| depth + 2 <= max_nodes;
+
+    let f = initial_state.f();
+    let pending_f = if initial_state.is_goal() || expandable(0) { f } else { usize::MAX };
+    let root = tree.add(Node { state: initial_state, parent: None, change: None, depth: 0, own_f: f, f, pending_f, children: Vec::new(), forgotten: Vec::new() });
+    tree.back_up(root);
+    let mut peak_nodes = tree.len;
+
+    let goal = loop {
+        let Some(&(_, _, id)) = tree.open.first() else {
+            break None;
+        };
+        if tree.node(id).state.is_goal() {
+            break Some(id);
+        }
+
+        while tree.len >= max_nodes {
+            if !tree.evict(root, id) {
+                break;
+            }
+            evictions += 1;
+        }
+
+        let node = tree.node(id);
+        let mut excluded: HashSet<K> = node.children.iter().map(|&child| tree.node(child).state.key()).collect();
+        let mut ancestor = Some(id);
+        while let Some(current) = ancestor {
+            excluded.insert(tree.node(current).state.key());
+            ancestor = tree.node(current).parent;
+        }
+
+        // Of the successors with the same key only the cheapest is ever generated, so that the
+        // key alone tells which one a forgotten f belongs to.
+        let mut cheapest: Vec<(S, C)> = Vec::new();
+        let mut slots: HashMap<K, usize> = HashMap::new();
+        for (successor, change) in node.state.generate_traced_successors() {
+            let key = successor.key();
+            if excluded.contains(&key) {
+                continue;
+            }
+            match slots.get(&key) {
+                Some(&slot) if successor.g() < cheapest[slot].0.g() => cheapest[slot] = (successor, change),
+                Some(_) => {}
+                None => {
+                    slots.insert(key, cheapest.len());
+                    cheapest.push((successor, change));
+                }
+            }
+        }
+
+        // Successors not in memory, each with its backed-up f if it was forgotten, and never
+        // below the f of the node.
+        let mut successors: Vec<(S, C, usize)> = cheapest
+            .into_iter()
+            .map(|(successor, change)| {
+                let key = successor.key();
+                let f = node.forgotten.iter().find(|(forgotten, _)| *forgotten == key).map_or(successor.f(), |&(_, f)| f);
+                (successor, change, f.max(node.own_f))
+            })
+            .collect();
+        let best = (0..successors.len()).min_by_key(|&index| successors[index].2);
+        let Some(best) = best.filter(|&best| successors[best].2 != usize::MAX) else {
+            let f = node.f;
+            tree.update(id, f, usize::MAX);
+            tree.back_up(id);
+            continue;
+        };
+
+        let (state, change, own_f) = successors.swap_remove(best);
+        let next_f = successors.iter().map(|&(_, _, f)| f).min().unwrap_or(usize::MAX);
+        let depth = node.depth + 1;
+        let (f, child_pending_f) = if state.is_goal() || expandable(depth) { (own_f, own_f) } else { (usize::MAX, usize::MAX) };
+        generated += 1;
+
+        let key = state.key();
+        let node = tree.nodes[id].as_mut().expect("live node");
+        node.forgotten.retain(|(forgotten, _)| *forgotten != key);
+        let node_f = node.f;
+        tree.update(id, node_f, next_f);
+        tree.add(Node { state, parent: Some(id), change: Some(change), depth, own_f, f, pending_f: child_pending_f, children: Vec::new(), forgotten: Vec::new() });
+        tree.back_up(id);
+        peak_nodes = peak_nodes.max(tree.len);
+    };
+
+    let result = goal.map(|mut id| {
+        let mut path = Vec::new();
+        let final_state = tree.nodes[id].take().expect("live node");
+        let mut parent = final_state.parent;
+        path.extend(final_state.change);
+        while let Some(current) = parent {
+            let node = tree.nodes[current].as_mut().expect("live node");
+            path.extend(node.change.take());
+            parent = node.parent;
+            id = current;
+        }
+        debug_assert_eq!(id, root);
+        path.reverse();
+        TracedResult::new(path, generated, final_state.state)
+    });
+
+    SmaOutcome { result, evictions, peak_nodes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, search_options::SearchOptions, test_support::{GraphState, SlidingPuzzle, DETOUR, DETOUR_H, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::{replay::replay, traced_astar::{traced_astar, traced_astar_with_options}}};
+
+    #[test]
+    fn test_budget_below_the_astar_frontier() {
+        for seed in 0..4 {
+            let start = SlidingPuzzle::scrambled(seed, 40);
+            let outcome = traced_astar_with_options(start.clone(), SearchOptions::new());
+            let optimal = outcome.result.unwrap().cost;
+            let max_nodes = outcome.stats.peak_open / 4;
+
+            let sma = sma_star(start.clone(), max_nodes);
+            assert!(sma.evictions > 0);
+            assert!(sma.peak_nodes <= max_nodes);
+            let result = sma.result.unwrap();
+            assert_eq!(result.cost, optimal, "seed {}", seed);
+            assert!(replay(&start, result.path.to_vec()).unwrap().is_goal());
+        }
+    }
+
+    #[test]
+    fn test_graph_optimum() {
+        for h in [EIGHT_NODES_H, ZERO_H] {
+            for max_nodes in [6, 8, 100] {
+                let result = sma_star(GraphState::new(EIGHT_NODES, h, 0, 7), max_nodes).result.unwrap();
+                assert_eq!(result.cost, traced_astar(GraphState::new(EIGHT_NODES, h, 0, 7)).unwrap().cost);
+                assert_eq!(replay(&GraphState::new(EIGHT_NODES, h, 0, 7), result.path.to_vec()).unwrap().g(), result.cost);
+            }
+        }
+    }
+
+    #[test]
+    fn test_path_longer_than_the_budget() {
+        // The optimal path 0 -> 2 -> 3 needs three nodes, with two the direct edge is all that fits.
+        const SHORTCUT: &[&[(usize, usize)]] = &[&[(1, 1), (2, 6), (3, 20)], &[(3, 10)], &[(3, 2)], &[]];
+        assert_eq!(sma_star(GraphState::new(SHORTCUT, DETOUR_H, 0, 3), 3).result.unwrap().path.to_vec(), vec![2, 3]);
+        assert_eq!(sma_star(GraphState::new(SHORTCUT, DETOUR_H, 0, 3), 2).result.unwrap().path.to_vec(), vec![3]);
+
+        assert!(sma_star(GraphState::new(DETOUR, DETOUR_H, 0, 3), 2).result.is_none());
+        assert!(sma_star(GraphState::new(DETOUR, DETOUR_H, 0, 3), 1).result.is_none());
+        assert_eq!(sma_star(GraphState::new(DETOUR, DETOUR_H, 3, 3), 1).result.unwrap().path.len(), 0);
+    }
+
+    // Two edges lead from 0 to 1, and only the cheap one is on the optimal path. The changes name
+    // the target alone, so the path can't tell which edge it took, but the cost does.
+    #[test]
+    fn test_parallel_edges_keep_the_cheapest() {
+        const PARALLEL: &[&[(usize, usize)]] = &[&[(1, 8), (2, 6), (1, 4)], &[(3, 1)], &[(3, 4)], &[]];
+        for max_nodes in [3, 4, 100] {
+            let result = sma_star(GraphState::new(PARALLEL, &[0; 4], 0, 3), max_nodes).result.unwrap();
+            assert_eq!(result.cost, 5, "{} nodes", max_nodes);
+            assert_eq!(result.path.to_vec(), vec![1, 3]);
+        }
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        let outcome = sma_star(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0), 4);
+        assert!(outcome.result.is_none());
+    }
+}
@@ -1,23 +1,117 @@
-use std::hash::Hash;
+use std::{hash::Hash, rc::Rc, sync::Arc};
 
-use crate::astar_state::AStarState;
+use crate::{astar_state::AStarState, traced::generation::GenContext};
 
 pub trait TracedState<K, C>: AStarState<K>
 where
     K: Clone + Eq + Hash,
 {
     fn generate_traced_successors(&self) -> Vec<(Self, C)>;
+
+    // Variant given the generation context, only used by traced_astar_with_options. Generators
+    // that can hang override it and poll `ctx.should_abort()`, see GenContext.
+    fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, C)> {
+        let _ = ctx;
+        self.generate_traced_successors()
+    }
+
+    // The one successor worth generating, when the state can tell without generating the others:
+    // the only legal move, or one that provably starts a cheapest way on. The search then inserts
+    // just that successor and counts the expansion in `SearchStats::forced`. It must lie on an
+    // optimal path through the state, or paths stop being optimal;
+    // `SearchOptions::with_forced_check` verifies that against the full generator. Only used by
+    // traced_astar_with_options.
+    fn forced_successor(&self) -> Option<(Self, C)> {
+        None
+    }
 }
 
+// Forwarded for owning pointers as UntracedState is, see there.
+
+impl<K, C, T> TracedState<K, C> for Box<T>
+where
+    K: Clone + Eq + Hash,
+    T: TracedState<K, C>
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, C)> {
+        (**self).generate_traced_successors()
+            .into_iter()
+            .map(|(successor, change)| (Box::new(successor), change))
+            .collect()
+    }
+
+    fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, C)> {
+        (**self).generate_traced_successors_with(ctx)
+            .into_iter()
+            .map(|(successor, change)| (Box::new(successor), change))
+            .collect()
+    }
+
+    fn forced_successor(&self) -> Option<(Self, C)> {
+        (**self).forced_successor().map(|(successor, change)| (Box::new(successor), change))
+    }
+}
 
-pub(super) struct TracedStateWrapper<T, K, C>
+impl<K, C, T> TracedState<K, C> for Rc<T>
+where
+    K: Clone + Eq + Hash,
+    T: TracedState<K, C>
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, C)> {
+        (**self).generate_traced_successors()
+            .into_iter()
+            .map(|(successor, change)| (Rc::new(successor), change))
+            .collect()
+    }
+
+    fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, C)> {
+        (**self).generate_traced_successors_with(ctx)
+            .into_iter()
+            .map(|(successor, change)| (Rc::new(successor), change))
+            .collect()
+    }
+
+    fn forced_successor(&self) -> Option<(Self, C)> {
+        (**self).forced_successor().map(|(successor, change)| (Rc::new(successor), change))
+    }
+}
+
+impl<K, C, T> TracedState<K, C> for Arc<T>
+where
+    K: Clone + Eq + Hash,
+    T: TracedState<K, C>
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, C)> {
+        (**self).generate_traced_successors()
+            .into_iter()
+            .map(|(successor, change)| (Arc::new(successor), change))
+            .collect()
+    }
+
+    fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, C)> {
+        (**self).generate_traced_successors_with(ctx)
+            .into_iter()
+            .map(|(successor, change)| (Arc::new(successor), change))
+            .collect()
+    }
+
+    fn forced_successor(&self) -> Option<(Self, C)> {
+        (**self).forced_successor().map(|(successor, change)| (Arc::new(successor), change))
+    }
+}
+
+
+pub struct TracedStateWrapper<T, K, C>
 where
     T: TracedState<K, C>,
     K: Clone + Eq + Hash
 {
     pub state: T,
     pub prev_key: Option<K>,
-    pub change: Option<C>
+    pub change: Option<C>,
+    pub depth: usize,
+    // g given by `SearchOptions::with_edge_cost_override`, in place of the state's own.
+    pub g_override: Option<usize>
 }
 
 impl<T, K, C> TracedStateWrapper<T, K, C>
@@ -29,18 +123,33 @@ where
         TracedStateWrapper {
             state,
             prev_key: None,
-            change: None
+            change: None,
+            depth: 0,
+            g_override: None
         }
     }
 
     pub fn generate_states(&self) -> Vec<Self> {
-        TracedState::generate_traced_successors(&self.state)
-            .into_iter()
+        self.wrap_successors(self.state.generate_traced_successors())
+    }
+
+    pub fn generate_states_with(&self, ctx: &GenContext) -> Vec<Self> {
+        self.wrap_successors(self.state.generate_traced_successors_with(ctx))
+    }
+
+    pub fn forced_state(&self) -> Option<Self> {
+        self.state.forced_successor().and_then(|forced| self.wrap_successors(vec![forced]).pop())
+    }
+
+    fn wrap_successors(&self, successors: Vec<(T, C)>) -> Vec<Self> {
+        successors.into_iter()
             .map(|(successor, change)| {
                 TracedStateWrapper {
                     state: successor,
                     prev_key: Some(self.key()),
-                    change: Some(change)
+                    change: Some(change),
+                    depth: self.depth + 1,
+                    g_override: None
                 }
             })
             .collect()
@@ -60,12 +169,16 @@ where
         self.state.h()
     }
 
+    // An overridden g moves f by as much, whatever the state makes of h.
     fn f(&self) -> usize {
-        self.state.f()
+        match self.g_override {
+            Some(g) => self.state.f().saturating_sub(self.state.g()) + g,
+            None => self.state.f()
+        }
     }
 
     fn g(&self) -> usize {
-        self.state.g()
+        self.g_override.unwrap_or_else(|| self.state.g())
     }
 
     fn is_goal(&self) -> bool {
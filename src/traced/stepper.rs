@@ -0,0 +1,141 @@
+use std::{collections::HashMap, hash::Hash, mem};
+
+use crate::{astar_state::AStarState, open_list::OpenList, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}, traced_astar::build_result}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StepStatus {
+    Running,
+    Solved,
+    // The open list ran out, so no goal is reachable.
+    Exhausted
+}
+
+// traced_astar suspended between calls to `step`, which keep its open and closed lists. However
+// the expansions are sliced, it expands the same states in the same order as traced_astar and
+// ends with the same result.
+pub struct TracedSearch<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    open_list: OpenList<K, TracedStateWrapper<S, K, C>>,
+    closed_list: HashMap<K, TracedStateWrapper<S, K, C>>,
+    status: StepStatus,
+    expansions: usize,
+    result: Option<TracedResult<S, K, C>>
+}
+
+impl<S, K, C> TracedSearch<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    pub fn new(initial_state: S) -> Self {
+        let mut open_list = OpenList::new();
+        open_list.insert(initial_state.key(), TracedStateWrapper::new(initial_state));
+
+        TracedSearch {
+            open_list,
+            closed_list: HashMap::new(),
+            status: StepStatus::Running,
+            expansions: 0,
+            result: None
+        }
+    }
+
+    // Expands at most `max_expansions` states and returns the status afterwards. Finding the goal
+    // on top of the open list isn't an expansion, so it may take a step of 0 to notice it.
+    pub fn step(&mut self, max_expansions: usize) -> StepStatus {
+        let mut budget = max_expansions;
+
+        while self.status == StepStatus::Running {
+            let Some(current_state) = self.open_list.min() else {
+                self.status = StepStatus::Exhausted;
+                break;
+            };
+            if !current_state.is_goal() && budget == 0 {
+                break;
+            }
+
+            let current_state = self.open_list.extract_min().unwrap();
+            if current_state.is_goal() {
+                self.result = Some(build_result(current_state, mem::take(&mut self.closed_list)));
+                self.status = StepStatus::Solved;
+                break;
+            }
+
+            let successors = current_state.generate_states();
+
+            self.closed_list.insert(current_state.key(), current_state);
+            self.expansions += 1;
+            budget -= 1;
+
+            for successor in successors {
+                let successor_key = successor.key();
+
+                if self.closed_list.contains_key(&successor_key) {
+                    continue;
+                }
+
+                self.open_list.insert(successor_key, successor);
+            }
+        }
+
+        self.status
+    }
+
+    pub fn status(&self) -> StepStatus {
+        self.status
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    // The result once solved, None while running or when exhausted.
+    pub fn into_result(self) -> Option<TracedResult<S, K, C>> {
+        self.result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::{GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::traced_astar::traced_astar};
+
+    #[test]
+    fn test_single_expansions_match_traced_astar() {
+        for seed in 0..8 {
+            let start = SlidingPuzzle::scrambled(seed, 24);
+            let expected = traced_astar(start.clone()).unwrap();
+
+            let mut search = TracedSearch::new(start);
+            while search.step(1) == StepStatus::Running {}
+
+            assert_eq!(search.expansions(), expected.iterations);
+            let result = search.into_result().unwrap();
+            assert_eq!(result.path.to_vec(), expected.path.to_vec());
+            assert_eq!(result.iterations, expected.iterations);
+            assert_eq!(result.cost, expected.cost);
+        }
+    }
+
+    #[test]
+    fn test_goal_on_top_needs_no_expansion() {
+        let mut search = TracedSearch::new(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 7));
+        assert_eq!(search.step(0), StepStatus::Solved);
+        assert_eq!(search.expansions(), 0);
+        assert!(search.into_result().unwrap().path.to_vec().is_empty());
+    }
+
+    #[test]
+    fn test_exhausted() {
+        // Node 4 only leads to 7, so nothing reaches 3 from it.
+        let mut search = TracedSearch::new(GraphState::new(EIGHT_NODES, ZERO_H, 4, 3));
+        assert_eq!(search.step(1), StepStatus::Running);
+        assert_eq!(search.step(10), StepStatus::Exhausted);
+        assert_eq!(search.expansions(), 2);
+        assert!(search.into_result().is_none());
+    }
+}
@@ -0,0 +1,174 @@
+use std::hash::Hash;
+
+use crate::{astar_state::AStarState, traced::{generation::GenContext, result::TracedResult, state::TracedState, traced_astar::traced_astar}};
+
+// Estimate of the remaining cost of a state, towards the goal the state carries.
+pub trait Heuristic<S> {
+    fn estimate(&self, state: &S) -> usize;
+}
+
+// Estimate of the remaining cost from a state to a goal given at search time, for heuristics
+// that need a goal description the state doesn't carry.
+pub trait HeuristicTo<S, G> {
+    fn estimate(&self, state: &S, goal: &G) -> usize;
+}
+
+impl<S, G, F> HeuristicTo<S, G> for F
+where
+    F: Fn(&S, &G) -> usize
+{
+    fn estimate(&self, state: &S, goal: &G) -> usize {
+        self(state, goal)
+    }
+}
+
+// Goal, heuristic and goal test of one search, owned by the entry point and shared by every node.
+struct GoalContext<G, H, F> {
+    goal: G,
+    heuristic: H,
+    is_goal: F
+}
+
+// A state of the search bound to the context. The goal is reached through the reference, so the
+// only cost per node is a pointer.
+struct GoalBound<'g, S, G, H, F> {
+    state: S,
+    context: &'g GoalContext<G, H, F>
+}
+
+impl<K, S, G, H, F> AStarState<K> for GoalBound<'_, S, G, H, F>
+where
+    K: Clone + Eq + Hash,
+    S: AStarState<K>,
+    H: HeuristicTo<S, G>,
+    F: Fn(&S, &G) -> bool
+{
+    fn key(&self) -> K {
+        self.state.key()
+    }
+
+    fn h(&self) -> usize {
+        self.context.heuristic.estimate(&self.state, &self.context.goal)
+    }
+
+    // Saturating, so a heuristic can return usize::MAX for states that can't reach the goal.
+    fn f(&self) -> usize {
+        self.g().saturating_add(self.h())
+    }
+
+    fn g(&self) -> usize {
+        self.state.g()
+    }
+
+    fn is_goal(&self) -> bool {
+        (self.context.is_goal)(&self.state, &self.context.goal)
+    }
+}
+
+impl<K, C, S, G, H, F> TracedState<K, C> for GoalBound<'_, S, G, H, F>
+where
+    K: Clone + Eq + Hash,
+    S: TracedState<K, C>,
+    H: HeuristicTo<S, G>,
+    F: Fn(&S, &G) -> bool
+{
+    fn generate_traced_successors(&self) -> Vec<(Self, C)> {
+        self.state.generate_traced_successors()
+            .into_iter()
+            .map(|(state, change)| (GoalBound { state, context: self.context }, change))
+            .collect()
+    }
+
+    fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, C)> {
+        self.state.generate_traced_successors_with(ctx)
+            .into_iter()
+            .map(|(state, change)| (GoalBound { state, context: self.context }, change))
+            .collect()
+    }
+}
+
+// traced_astar towards `goal`: the heuristic and the goal test are given the goal with every
+// state, and the state's own h() and is_goal() are never called. The same state type can be
+// searched towards any goal without carrying it.
+pub fn traced_astar_to_goal<S, K, C, G, H, F>(initial_state: S, goal: G, heuristic: H, is_goal: F) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    H: HeuristicTo<S, G>,
+    F: Fn(&S, &G) -> bool
+{
+    let context = GoalContext { goal, heuristic, is_goal };
+    let result = traced_astar(GoalBound { state: initial_state, context: &context })?;
+    Some(result.map_state(|bound| bound.state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Position on an unbounded line, with no goal of its own.
+    #[derive(Clone)]
+    struct Walker {
+        position: i64,
+        g: usize
+    }
+
+    impl AStarState<i64> for Walker {
+        fn key(&self) -> i64 {
+            self.position
+        }
+
+        fn h(&self) -> usize {
+            unreachable!("the goal-aware heuristic replaces h()")
+        }
+
+        fn f(&self) -> usize {
+            self.g
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            unreachable!("the goal test replaces is_goal()")
+        }
+    }
+
+    impl TracedState<i64, i64> for Walker {
+        fn generate_traced_successors(&self) -> Vec<(Self, i64)> {
+            [-1, 1, 3].into_iter()
+                .map(|step| (Walker { position: self.position + step, g: self.g + 1 }, step))
+                .collect()
+        }
+    }
+
+    // Not Clone, so the search can only hand it out by reference.
+    struct Target(i64);
+
+    fn steps_to(walker: &Walker, target: &Target) -> usize {
+        let distance = target.0 - walker.position;
+        if distance >= 0 {
+            (distance as usize).div_ceil(3)
+        }
+        else {
+            distance.unsigned_abs() as usize
+        }
+    }
+
+    #[test]
+    fn test_same_state_type_to_two_goals() {
+        for (target, cost) in [(7, 3), (-2, 2)] {
+            let result = traced_astar_to_goal(
+                Walker { position: 0, g: 0 },
+                Target(target),
+                steps_to,
+                |walker: &Walker, target: &Target| walker.position == target.0
+            ).unwrap();
+
+            assert_eq!(result.final_state.position, target);
+            assert_eq!(result.final_state.g(), cost);
+            assert_eq!(result.path.to_vec().iter().sum::<i64>(), target);
+        }
+    }
+}
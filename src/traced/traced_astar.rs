@@ -1,71 +1,1543 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{collections::{HashMap, HashSet}, hash::Hash, time::{Duration, Instant}};
 
-use crate::{astar_state::AStarState, open_list::OpenList, traced::{result::TracedResult, state::{TracedState, TracedStateWrapper}}};
+use crate::{astar_state::AStarState, btree_open_list::BTreeOpenList, capacity, closed_store::{BTreeClosedStore, ClosedStore, HashClosedStore, MapImpl}, hashed::{query, HashedMap}, edge_costs::EdgeCostValidator, open_list::{CorruptionError, OpenList}, plateau::PlateauTracker, prioritized::Prioritized, quantile::SoftOpenLimit, priority_queue::{Insertion, PriorityQueue}, search_options::SearchOptions, search_stats::{SearchStats, SearchWarning, SkipReason}, successor_cache::{CachedEdge, SuccessorCache}, traced::{generation::GenContext, outcome::{InternalError, Optimality, SearchError, SearchOutcome}, result::TracedResult, state::{TracedState, TracedStateWrapper}}, validation::{self, Anomaly, EdgeCosts, ValidationLevel}, weight_schedule::{ScheduleContext, WeightChange}};
 
+// traced_astar_with_queue on an OpenList, with the closed list keyed by the open list's hashes so
+// each generated key is hashed once. Rebuilding the path hashes the keys on it once more.
 pub fn traced_astar<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
 where
     S: TracedState<K, C>,
     K: Clone + Eq + Hash
 {
-    let mut open_list: OpenList<K, TracedStateWrapper<S, K, C>> = OpenList::new();
-    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let mut open_list = OpenList::new();
+    let mut closed_list: HashedMap<K, TracedStateWrapper<S, K, C>> = HashedMap::default();
+
+    let initial_key = open_list.hashed(initial_state.key());
+    open_list.insert_hashed(initial_key, TracedStateWrapper::new(initial_state));
+
+    while let Some((current_key, current_state)) = open_list.extract_min_hashed() {
+        if current_state.is_goal() {
+            let iterations = closed_list.len();
+            return Some(rebuild_result(current_state, iterations, |key| closed_list.remove(query(&(open_list.hash_of(key), key)))));
+        }
+
+        let successors = current_state.generate_states();
 
+        closed_list.insert(current_key, current_state);
+
+        for successor in successors {
+            let successor_key = open_list.hashed(successor.key());
+
+            if closed_list.contains_key(&successor_key) {
+                continue;
+            }
+
+            open_list.insert_hashed(successor_key, successor);
+        }
+    }
+
+    None
+}
+
+pub fn traced_astar_with_queue<S, K, C, Q>(initial_state: S, open_list: Q) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    Q: PriorityQueue<K, TracedStateWrapper<S, K, C>>
+{
+    traced_astar_with_stores(initial_state, open_list, HashClosedStore::new())
+}
+
+// traced_astar_with_queue with the closed list in `closed_list` too.
+pub fn traced_astar_with_stores<S, K, C, Q, CS>(initial_state: S, mut open_list: Q, mut closed_list: CS) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    Q: PriorityQueue<K, TracedStateWrapper<S, K, C>>,
+    CS: ClosedStore<K, TracedStateWrapper<S, K, C>>
+{
     open_list.insert(initial_state.key(), TracedStateWrapper::new(initial_state));
 
     while let Some(current_state) = open_list.extract_min() {
         if current_state.is_goal() {
-            let TracedStateWrapper { state, prev_key, change } = current_state;
-            
-            let final_state= state;
             let iterations = closed_list.len();
-            let mut path = Vec::new();
+            return Some(rebuild_result(current_state, iterations, |key| closed_list.remove(key)));
+        }
 
-            if let Some(change) = change {
-                path.push(change);
+        let successors = current_state.generate_states();
+
+        closed_list.insert(current_state.key(), current_state);
+
+        for successor in successors {
+            let successor_key = successor.key();
+
+            if closed_list.contains_key(&successor_key) {
+                continue;
             }
 
-            if let Some(prev_key) = prev_key {
-                let mut curr_key = prev_key;
+            open_list.insert(successor_key, successor);
+        }
+    }
+
+    None
+}
 
-                while let Some(prev_state) = closed_list.remove(&curr_key) {
-                    if let Some(change) = prev_state.change {
-                        path.push(change);
+// traced_astar over the open and closed lists of `map_impl`: OpenList and a hash map, or
+// BTreeOpenList and BTreeClosedStore.
+pub fn traced_astar_with_map_impl<S, K, C>(initial_state: S, map_impl: MapImpl) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Ord + Hash
+{
+    match map_impl {
+        MapImpl::Hash => traced_astar(initial_state),
+        MapImpl::BTree => traced_astar_with_stores(initial_state, BTreeOpenList::new(), BTreeClosedStore::new())
+    }
+}
+
+// traced_astar with the extra pruning rules of the options, reporting search statistics.
+// Successors are checked in this order: commuted, closed (unless reopened), forbidden, known
+// unreachable, f bound, soft open limit, worse than the open entry.
+pub fn traced_astar_with_options<S, K, C>(initial_state: S, mut options: SearchOptions<S, K, C>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let labeler = options.take_key_labeler();
+    let verification = options.cost_verification().then(|| (initial_state.g(), options.has_edge_cost_override() || options.has_seed_nodes()));
+    let expansion_limit = options.resolve_expansion_limit(&initial_state);
+    let limit_unresolved = options.limit_unresolved(expansion_limit.as_ref());
+    let shared_budget = options.shared_budget();
+    let mut outcome = search_with_options(initial_state, options, expansion_limit.map(|resolved| resolved.limit));
+    outcome.stats.expansion_limit = expansion_limit;
+    if limit_unresolved {
+        outcome.stats.warnings.push(SearchWarning::UnresolvedLimit);
+    }
+    if let Some(budget) = shared_budget {
+        budget.charge(outcome.stats.expansions);
+    }
+    if let Some((initial_g, moved_g)) = verification
+        && let Some(result) = &outcome.result
+        && let Err(error) = verify_costs(result, initial_g, moved_g)
+    {
+        outcome.result = None;
+        outcome.error = Some(SearchError::Internal(error));
+    }
+    outcome.label_diagnostics(labeler.as_deref());
+    outcome
+}
+
+// Adds up the step costs of the result from the initial g and compares them with its cost, then
+// the cost with the goal's own g. An edge cost override or seeds move the g the search sees away
+// from the states' own, and `moved_g` skips the second check.
+fn verify_costs<S, K, C>(result: &TracedResult<S, K, C>, initial_g: usize, moved_g: bool) -> Result<(), InternalError>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let Some(step_costs) = &result.step_costs else {
+        return Ok(());
+    };
+
+    let steps = step_costs.iter().fold(initial_g, |g, cost| g.saturating_add(*cost));
+    if steps != result.cost {
+        return Err(InternalError::StepCosts { steps, cost: result.cost });
+    }
+    if !moved_g && result.final_state.g() != result.cost {
+        return Err(InternalError::GoalCost { cost: result.cost, goal_g: result.final_state.g() });
+    }
+    Ok(())
+}
+
+fn search_with_options<S, K, C>(initial_state: S, mut options: SearchOptions<S, K, C>, expansion_limit: Option<usize>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let mut open_list: OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>> = OpenList::new();
+    let mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>> = HashMap::new();
+    let mut successor_cache = options.successor_cache_capacity().map(SuccessorCache::new);
+    let mut stats = SearchStats::new(options.pruning_by_depth(), options.visit_counts());
+    let mut validator = options.edge_cost_bounds().map(EdgeCostValidator::new);
+    let mut plateau = options.plateau_detection()
+        .filter(|_| options.greedy())
+        .map(|(threshold, policy)| PlateauTracker::new(threshold, policy));
+    let mut soft_limit = options.soft_open_limit().map(|(limit, percentile)| SoftOpenLimit::new(limit, percentile));
+
+    let initial_unreachable = options.is_known_unreachable(&initial_state.key());
+    let mut f_bound = options.f_bound();
+    let initial_state = if options.auto_incumbent() && !initial_unreachable {
+        let (initial_state, incumbent) = incumbent_pass(initial_state, &options, &mut open_list, &mut closed_list, &mut stats);
+        f_bound = f_bound.into_iter().chain(incumbent).min();
+        initial_state
+    }
+    else {
+        initial_state
+    };
+
+    let recorded_plateau = options.plateau_detection().filter(|_| options.greedy());
+    if let Some(recording) = options.recording() {
+        recording.begin(stats.search_id, stats.pruned_by_depth.is_some(), stats.visits.is_some(), recorded_plateau);
+    }
+
+    if initial_unreachable {
+        return unreachable_start(initial_state.key(), &mut options, stats);
+    }
+
+    let started = Instant::now();
+    let mut weight = 1.0;
+    consult_schedule(&mut options, &mut open_list, &mut stats, &mut weight, started);
+
+    let initial_priority = options.priority(&initial_state.key(), initial_state.f(), initial_state.h(), weight, None);
+    if let Some(plateau) = &mut plateau {
+        plateau.record_insert(&initial_state.key());
+    }
+    if let Some(recording) = options.recording() {
+        recording.inserted(&initial_state.key(), None, initial_state.g(), initial_state.h(), 0);
+    }
+    open_list.insert(initial_state.key(), Prioritized::new(TracedStateWrapper::new(initial_state), initial_priority));
+
+    let mut seeded = Seeded::new();
+    for (seed, estimate) in options.take_seed_nodes() {
+        let key = seed.key();
+        if open_list.contains_key(&key) {
+            continue;
+        }
+        let seed = TracedStateWrapper { g_override: Some(estimate), ..TracedStateWrapper::new(seed) };
+        let priority = options.priority(&key, seed.f(), seed.h(), weight, None);
+        if let Some(plateau) = &mut plateau {
+            plateau.record_insert(&key);
+        }
+        if let Some(recording) = options.recording() {
+            recording.inserted(&key, None, seed.g(), seed.h(), 0);
+        }
+        seeded.keys.insert(key.clone());
+        open_list.insert(key, Prioritized::new(seed, priority));
+    }
+    stats.peak_open = open_list.len();
+    let seeding = !seeded.keys.is_empty();
+
+    loop {
+        // A plateau policy may pick the next state instead of the open list order.
+        let picked = match plateau.as_mut().and_then(|plateau| plateau.next_key(&open_list)) {
+            Some(key) => open_list.try_remove(&key),
+            None => Ok(None)
+        };
+        let next = match picked {
+            Ok(None) => open_list.try_extract_min(),
+            picked => picked
+        };
+        let current_state = match next {
+            Ok(Some(current_state)) => current_state.state,
+            Ok(None) => break,
+            Err(error) => return internal_error(stats, error)
+        };
+
+        let current_seeded = seeding && seeded.keys.contains(&current_state.key());
+        if current_seeded && current_state.is_goal() {
+            seeded.goal.get_or_insert_with(|| current_state.key());
+            if let Some(recording) = options.recording() {
+                recording.seeded_goal(&current_state.key());
+            }
+            closed_list.insert(current_state.key(), current_state);
+            continue;
+        }
+
+        if current_state.is_goal() {
+            let goal = current_state.key();
+            let result = build_result(current_state, closed_list);
+            let optimality = admission_optimality(&stats);
+            return goal_reached(&goal, result, optimality, &mut options, stats);
+        }
+
+        if expansion_limit.is_some_and(|limit| stats.expansions >= limit) {
+            return SearchOutcome { budget_exhausted: true, ..SearchOutcome::new(None, stats) };
+        }
+
+        if let Some(detected) = plateau.as_mut().and_then(|plateau| plateau.expanded(current_state.h(), &mut stats)) {
+            options.notify_plateau(&detected);
+        }
+
+        let (cache_hits, forced_expansions) = (stats.cache_hits, stats.forced);
+        let forced = options.forced_successors().then(|| current_state.forced_state()).flatten();
+        if let Some(forced) = &forced
+            && options.forced_check()
+            && let Some(anomaly) = check_forced(&current_state, forced, &closed_list)
+        {
+            return SearchOutcome { validation_error: Some(anomaly), ..SearchOutcome::new(None, stats) };
+        }
+        let mut successors = match forced {
+            Some(forced) => {
+                stats.forced += 1;
+                vec![forced]
+            }
+            None => match expand(&current_state, &options, successor_cache.as_mut(), &mut stats) {
+                Ok(successors) => successors,
+                Err(elapsed) => {
+                    stats.warnings.push(SearchWarning::GenerationTimedOut { key: current_state.key(), elapsed });
+                    if let Some(recording) = options.recording() {
+                        recording.generation_timed_out(&current_state.key(), elapsed);
+                    }
+                    Vec::new()
+                }
+            }
+        };
+        if options.has_edge_cost_override() {
+            override_edge_costs(&current_state, &mut successors, &options);
+        }
+        if current_seeded {
+            carry_estimate(&current_state, &mut successors);
+        }
+        stats.expansions += 1;
+        stats.trace_fingerprint.expanded(&current_state.key(), current_state.g(), current_state.h());
+        #[cfg(test)]
+        options.corrupt_after_expansion(stats.expansions, &mut open_list);
+        if options.visit_counts() {
+            stats.record_expansion(&current_state.key(), current_state.g());
+        }
+        stats.generated += successors.len();
+        if let Some(recording) = options.recording() {
+            recording.expanded(&current_state.key(), successors.len(), stats.cache_hits > cache_hits, stats.forced > forced_expansions);
+        }
+
+        if let Some(validator) = &mut validator {
+            let edges = successors.iter().filter_map(|successor| Some((successor.g(), successor.change.as_ref()?)));
+            match validator.check(&current_state.key(), current_state.g(), edges, stats.expansions) {
+                Ok(zero_cost_edges) => {
+                    stats.zero_cost_edges += zero_cost_edges;
+                    if let Some(recording) = options.recording() {
+                        recording.zero_cost_edges(zero_cost_edges);
                     }
+                }
+                Err(error) => return SearchOutcome { edge_cost_error: Some(error), ..SearchOutcome::new(None, stats) }
+            }
+        }
+
+        if successors.is_empty() {
+            stats.record_pruned(SkipReason::DeadEnd, current_state.depth);
+            if let Some(recording) = options.recording() {
+                recording.pruned(SkipReason::DeadEnd, current_state.depth);
+            }
+        }
+
+        if options.auto_reserve() && stats.expansions == capacity::SAMPLE_EXPANSIONS {
+            let projection = capacity::project(stats.expansions, stats.generated, current_state.depth, current_state.g(), current_state.h());
+            closed_list.reserve(projection.expansions.saturating_sub(closed_list.len()));
+            open_list.reserve(projection.frontier().saturating_sub(open_list.len()));
+        }
+
+        let parent_change = current_state.change.clone();
+        let parent_f = current_state.f();
+        let parent_key = options.has_recording().then(|| current_state.key());
+        let validated_parent = (options.validation() != ValidationLevel::Off).then(|| (current_state.key(), current_state.g(), current_state.h()));
+        closed_list.insert(current_state.key(), current_state);
+        let successors_min_f = seeding.then(|| successors.iter().map(AStarState::f).min()).flatten();
+
+        for successor in successors {
+            let successor_key = successor.key();
+            let depth = successor.depth;
 
-                    if let Some(prev_key) = prev_state.prev_key {
-                        curr_key = prev_key;
+            if seeding && seeded.keys.contains(&successor_key) != current_seeded {
+                if current_seeded {
+                    // Seeded states never replace states reached from the initial one.
+                    let reason = if closed_list.contains_key(&successor_key) {
+                        Some(SkipReason::Closed)
                     }
                     else {
-                        break;
+                        open_list.contains_key(&successor_key).then_some(SkipReason::WorseOpen)
+                    };
+                    if let Some(reason) = reason {
+                        stats.record_pruned(reason, depth);
+                        if let Some(recording) = options.recording() {
+                            recording.pruned(reason, depth);
+                        }
+                        options.discard(successor.state);
+                        continue;
                     }
                 }
+                else {
+                    if let Some(goal) = &seeded.goal
+                        && let Some(seeded_g) = seeded_g_on_path(goal, &successor_key, &closed_list)
+                    {
+                        let frontier_f = open_list.min().map(|entry| entry.state.f()).into_iter().chain(successors_min_f).min();
+                        return seeded_goal_reached(successor, seeded_g, goal.clone(), frontier_f, closed_list, &mut options, stats);
+                    }
+
+                    // A path from the initial state takes the place of the seeded state whatever
+                    // its g, correcting an optimistic estimate.
+                    if let Some(replaced) = closed_list.remove(&successor_key) {
+                        options.discard(replaced.state);
+                    }
+                    match open_list.try_remove(&successor_key) {
+                        Ok(replaced) => if let Some(replaced) = replaced {
+                            options.discard(replaced.state.state);
+                        }
+                        Err(error) => return internal_error(stats, error)
+                    }
+                    seeded.keys.remove(&successor_key);
+                }
             }
 
-            path.reverse();
+            if let Some((parent, parent_g, parent_h)) = &validated_parent {
+                let costs = EdgeCosts { parent_g: *parent_g, parent_h: *parent_h, child_g: successor.g(), child_h: successor.h(), child_f: successor.f() };
+                if let Some(anomaly) = validation::check(parent, &successor_key, costs, options.consistent_heuristic()) {
+                    if options.validation() == ValidationLevel::Strict {
+                        return SearchOutcome { validation_error: Some(anomaly), ..SearchOutcome::new(None, stats) };
+                    }
+                    if let Some(recording) = options.recording() {
+                        recording.anomaly(anomaly.clone());
+                    }
+                    stats.warnings.push(SearchWarning::Anomaly(anomaly));
+                }
+            }
 
-            return Some(
-                TracedResult::new(
-                    path,
-                    iterations,
-                    final_state
-                )
-            );
+            let skip_reason = if options.is_redundant_order(parent_change.as_ref(), successor.change.as_ref()) {
+                Some(SkipReason::Commuted)
+            }
+            else if closed_list.get(&successor_key).is_some_and(|closed| !options.reopening() || closed.g() <= successor.g()) {
+                Some(SkipReason::Closed)
+            }
+            else if options.is_forbidden(&successor_key) {
+                Some(SkipReason::Forbidden)
+            }
+            else if options.is_known_unreachable(&successor_key) {
+                Some(SkipReason::DeadEnd)
+            }
+            else if f_bound.is_some_and(|bound| successor.f() > bound) {
+                Some(SkipReason::FBound)
+            }
+            else if let Some(limit) = &mut soft_limit
+                && !limit.admits(open_list.len(), open_list.contains_key(&successor_key), successor.f(), parent_f)
+            {
+                Some(SkipReason::SoftRejected)
+            }
+            else {
+                None
+            };
+
+            if let Some(reason) = skip_reason {
+                stats.record_pruned(reason, depth);
+                if let Some(recording) = options.recording() {
+                    recording.pruned(reason, depth);
+                }
+                options.discard(successor.state);
+                continue;
+            }
+
+            let (g, h) = (successor.g(), successor.h());
+            if let Some(reopened) = closed_list.remove(&successor_key) {
+                stats.reopened += 1;
+                stats.record_reopening(&successor_key, g);
+                options.discard(reopened.state);
+            }
+
+            // Keys are only cloned when visit counts, a plateau burst or the recording need them after
+            // the insertion.
+            let tracks_insertions = plateau.as_ref().is_some_and(PlateauTracker::tracks_insertions);
+            let tracked_key = (options.visit_counts() || tracks_insertions || options.has_recording()).then(|| successor_key.clone());
+            let priority = options.priority(&successor_key, successor.f(), successor.h(), weight, successor.change.as_ref());
+            let seeded_key = current_seeded.then(|| successor_key.clone());
+            let insertion = match open_list.try_insert(successor_key, Prioritized::new(successor, priority)) {
+                Ok(insertion) => insertion,
+                Err(error) => return internal_error(stats, error)
+            };
+
+            if let Some(key) = &tracked_key
+                && !matches!(insertion, Insertion::Rejected(_))
+            {
+                if options.visit_counts() {
+                    stats.record_insert(key, g, matches!(insertion, Insertion::Improved(_)));
+                }
+                if let Some(plateau) = &mut plateau {
+                    plateau.record_insert(key);
+                }
+                if let Some(recording) = options.recording() {
+                    recording.inserted(key, parent_key.as_ref(), g, h, depth);
+                }
+            }
+
+            if let Some(key) = seeded_key
+                && !matches!(insertion, Insertion::Rejected(_))
+            {
+                seeded.keys.insert(key);
+            }
+
+            stats.peak_open = stats.peak_open.max(open_list.len());
+
+            match insertion {
+                Insertion::Added => {}
+                Insertion::Improved(previous) => {
+                    stats.improved += 1;
+                    if let Some(previous) = previous {
+                        options.discard(previous.state.state);
+                    }
+                }
+                Insertion::Rejected(rejected) => {
+                    stats.record_pruned(SkipReason::WorseOpen, depth);
+                    if let Some(recording) = options.recording() {
+                        recording.pruned(SkipReason::WorseOpen, depth);
+                    }
+                    options.discard(rejected.state.state);
+                }
+            }
         }
 
-        let successors = current_state.generate_states();
+        if options.weight_schedule_interval().is_some_and(|every| stats.expansions % every == 0) {
+            consult_schedule(&mut options, &mut open_list, &mut stats, &mut weight, started);
+        }
+    }
+
+    exhausted(closed_list, seeded.goal.is_some(), &options, stats)
+}
+
+// A known dead end as initial state: nothing is searched, and its key is all the proof needed.
+fn unreachable_start<S, K, C>(initial_key: K, options: &mut SearchOptions<S, K, C>, mut stats: SearchStats<K>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    stats.record_pruned(SkipReason::DeadEnd, 0);
+    if let Some(recording) = options.recording() {
+        recording.pruned(SkipReason::DeadEnd, 0);
+    }
+    SearchOutcome { exhausted_keys: Some(HashSet::from([initial_key])), ..SearchOutcome::new(None, stats) }
+}
+
+// Records the goal and returns `result`, compressed when the options have a change codec.
+fn goal_reached<S, K, C>(goal: &K, mut result: TracedResult<S, K, C>, optimality: Optimality, options: &mut SearchOptions<S, K, C>, mut stats: SearchStats<K>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    stats.trace_fingerprint.goal(goal);
+    if let Some(recording) = options.recording() {
+        recording.goal(goal);
+    }
+    #[cfg(test)]
+    options.miscount(&mut result);
+    if let Some(codec) = options.change_codec() {
+        result = result.compressed(codec.clone());
+    }
+    SearchOutcome { optimality, ..SearchOutcome::new(Some(result), stats) }
+}
+
+// The path through `junction`, reached from the initial state, on to the goal a seed already
+// reached. It is only known to be the cheapest when nothing on the frontier, `frontier_f` at
+// best, is cheaper.
+fn seeded_goal_reached<S, K, C>(junction: TracedStateWrapper<S, K, C>, seeded_g: usize, goal: K, frontier_f: Option<usize>, closed_list: HashMap<K, TracedStateWrapper<S, K, C>>, options: &mut SearchOptions<S, K, C>, stats: SearchStats<K>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let optimality = if frontier_f.is_none_or(|f| f >= stitched_cost(&junction, seeded_g, &goal, &closed_list)) {
+        admission_optimality(&stats)
+    }
+    else {
+        Optimality::Unknown
+    };
+    let result = stitch(junction, seeded_g, &goal, closed_list);
+    goal_reached(&goal, result, optimality, options, stats)
+}
+
+// The outcome of a search that ran out of states, with the coverage of the state space bound and
+// the closed keys when they prove the goal unreachable.
+fn exhausted<S, K, C>(closed_list: HashMap<K, TracedStateWrapper<S, K, C>>, seeded_goal: bool, options: &SearchOptions<S, K, C>, mut stats: SearchStats<K>) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let optimality = admission_optimality(&stats);
+    let coverage = options.state_space_bound().map(|bound| check_coverage(closed_list.len(), bound, options.min_coverage(), &mut stats));
+    // A goal reached from a seed is closed too, and is no proof of anything.
+    let exhausted_keys = (!seeded_goal && proves_unreachable(&stats)).then(|| closed_list.into_keys().collect());
+    SearchOutcome { coverage, exhausted_keys, optimality, ..SearchOutcome::new(None, stats) }
+}
+
+// Share of the state space bound an exhausted search closed, with a warning when it's too low.
+fn check_coverage<K>(closed: usize, bound: usize, min_coverage: f64, stats: &mut SearchStats<K>) -> f64
+where
+    K: Eq + Hash
+{
+    let coverage = closed as f64 / bound.max(1) as f64;
+    if coverage < min_coverage {
+        stats.warnings.push(SearchWarning::SuspiciousExhaustion { closed, bound });
+    }
+    coverage
+}
 
+// The greedy pass of `SearchOptions::with_auto_incumbent`: best-first by h over the search's own
+// lists, left empty for it afterwards. Returns the initial state, taken back from the closed list,
+// and the cost of the path found.
+fn incumbent_pass<S, K, C>(initial_state: S, options: &SearchOptions<S, K, C>, open_list: &mut OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>>, closed_list: &mut HashMap<K, TracedStateWrapper<S, K, C>>, stats: &mut SearchStats<K>) -> (S, Option<usize>)
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let initial_key = initial_state.key();
+    let initial_h = initial_state.h();
+    open_list.insert(initial_key.clone(), Prioritized::new(TracedStateWrapper::new(initial_state), initial_h));
+
+    while let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() {
+        if current_state.is_goal() {
+            stats.incumbent_cost = Some(current_state.g());
+            closed_list.insert(current_state.key(), current_state);
+            break;
+        }
+
+        let mut successors = current_state.generate_states();
+        if options.has_edge_cost_override() {
+            override_edge_costs(&current_state, &mut successors, options);
+        }
+        stats.incumbent_expansions += 1;
         closed_list.insert(current_state.key(), current_state);
 
         for successor in successors {
             let successor_key = successor.key();
-
-            if closed_list.contains_key(&successor_key) {
+            if closed_list.contains_key(&successor_key) || options.is_forbidden(&successor_key) || options.is_known_unreachable(&successor_key) {
                 continue;
             }
 
-            open_list.insert(successor_key, successor);
+            let successor_h = successor.h();
+            open_list.insert(successor_key, Prioritized::new(successor, successor_h));
         }
     }
 
-    None
+    let initial_state = closed_list.remove(&initial_key)
+        .expect("the initial state is the first one the pass closes")
+        .state;
+    open_list.clear();
+    closed_list.clear();
+    (initial_state, stats.incumbent_cost)
+}
+
+// Whether an exhausted search closed every state that could lead to a goal: nothing pruned by a
+// bound, a predicate or the soft open limit, and no generator cut short. The other pruning skips
+// states reached some other way, or proven unreachable themselves.
+fn proves_unreachable<K>(stats: &SearchStats<K>) -> bool
+where
+    K: Eq + Hash
+{
+    let pruned = [SkipReason::FBound, SkipReason::Forbidden, SkipReason::SoftRejected].iter().any(|&reason| stats.pruned[reason] > 0);
+    let timed_out = stats.warnings.iter().any(|warning| matches!(warning, SearchWarning::GenerationTimedOut { .. }));
+    !pruned && !timed_out
+}
+
+// States turned away by the soft open limit may have led to a cheaper goal, or to any goal.
+fn admission_optimality<K>(stats: &SearchStats<K>) -> Optimality
+where
+    K: Eq + Hash
+{
+    if stats.pruned[SkipReason::SoftRejected] > 0 {
+        Optimality::Unknown
+    }
+    else {
+        Optimality::Kept
+    }
+}
+
+// Asks the weight schedule for the weight after the expansions done so far. A new or clamped
+// weight is recorded, a clamped one warned about, and a new one reprioritizes the open list.
+fn consult_schedule<S, K, C>(options: &mut SearchOptions<S, K, C>, open_list: &mut OpenList<K, Prioritized<TracedStateWrapper<S, K, C>>>, stats: &mut SearchStats<K>, weight: &mut f64, started: Instant)
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let context = ScheduleContext {
+        expansions: stats.expansions,
+        elapsed: started.elapsed(),
+        min_f: open_list.min().map(|entry| entry.state.f()),
+        weight: *weight
+    };
+    let Some((scheduled, clamped)) = options.scheduled_weight(&context) else {
+        return;
+    };
+    if scheduled == *weight && !clamped {
+        return;
+    }
+
+    let change = WeightChange { expansion: stats.expansions, weight: scheduled, clamped };
+    stats.weight_changes.push(change);
+    if clamped {
+        stats.warnings.push(SearchWarning::WeightClamped { expansion: change.expansion });
+    }
+    if let Some(recording) = options.recording() {
+        recording.weight_changed(change);
+    }
+
+    if scheduled != *weight {
+        *weight = scheduled;
+        open_list.reprioritize(|entry| {
+            let priority = options.priority(&entry.state.key(), entry.state.f(), entry.state.h(), scheduled, entry.state.change.as_ref());
+            entry.set_priority(priority);
+        });
+    }
+}
+
+fn internal_error<S, K, C>(stats: SearchStats<K>, error: CorruptionError) -> SearchOutcome<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    SearchOutcome { error: Some(error.into()), ..SearchOutcome::new(None, stats) }
+}
+
+// Successors of `current`, rebuilt from the cached edges when the cache has seen its key before.
+// Generation past the time budget gives the time it took instead, and nothing is cached.
+fn expand<S, K, C>(current: &TracedStateWrapper<S, K, C>, options: &SearchOptions<S, K, C>, cache: Option<&mut SuccessorCache<K, C>>, stats: &mut SearchStats<K>) -> Result<Vec<TracedStateWrapper<S, K, C>>, Duration>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let generate = || {
+        let ctx = options.max_generation_time().map_or_else(GenContext::unbounded, GenContext::with_budget);
+        let successors = current.generate_states_with(&ctx);
+        if ctx.should_abort() {
+            Err(ctx.elapsed())
+        }
+        else {
+            Ok(successors)
+        }
+    };
+
+    let Some(cache) = cache else {
+        return generate();
+    };
+
+    let key = current.key();
+    if let Some(edges) = cache.get(&key) {
+        stats.cache_hits += 1;
+
+        return Ok(edges.iter()
+            .map(|edge| TracedStateWrapper {
+                state: options.regenerate(&current.state, edge).expect("the successor cache comes with a reconstructor"),
+                prev_key: Some(key.clone()),
+                change: Some(edge.change.clone()),
+                depth: current.depth + 1,
+                g_override: None
+            })
+            .collect());
+    }
+
+    let successors = generate()?;
+    let edges = successors.iter()
+        .filter_map(|successor| {
+            successor.change.clone().map(|change| CachedEdge {
+                child_key: successor.key(),
+                edge_cost: successor.state.g().saturating_sub(current.state.g()),
+                change
+            })
+        })
+        .collect();
+    cache.insert(key, edges);
+
+    Ok(successors)
+}
+
+// Checks the forced successor of `current` against the full generator: it has to be there, with
+// the lowest f among the successors that aren't closed.
+fn check_forced<S, K, C>(current: &TracedStateWrapper<S, K, C>, forced: &TracedStateWrapper<S, K, C>, closed_list: &HashMap<K, TracedStateWrapper<S, K, C>>) -> Option<Anomaly<K>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let forced_key = forced.key();
+    let mut forced_f = None;
+    let mut best_f = usize::MAX;
+    for successor in current.generate_states() {
+        let key = successor.key();
+        if key == forced_key {
+            forced_f = Some(forced_f.map_or(successor.f(), |f: usize| f.min(successor.f())));
+        }
+        if closed_list.get(&key).is_none_or(|closed| closed.g() > successor.g()) {
+            best_f = best_f.min(successor.f());
+        }
+    }
+
+    match forced_f {
+        None => Some(Anomaly::ForcedNotGenerated { parent: current.key(), child: forced_key }),
+        Some(f) if f > best_f => Some(Anomaly::ForcedNotBest { parent: current.key(), child: forced_key, f, best_f }),
+        Some(_) => None
+    }
+}
+
+// Keys whose open or closed state descends from a seed rather than the initial state, and the
+// first goal reached from a seed.
+struct Seeded<K> {
+    keys: HashSet<K>,
+    goal: Option<K>
+}
+
+impl<K> Seeded<K> {
+    fn new() -> Self {
+        Seeded { keys: HashSet::new(), goal: None }
+    }
+}
+
+// Gives the successors of a seeded state the g through its estimate.
+fn carry_estimate<S, K, C>(current: &TracedStateWrapper<S, K, C>, successors: &mut [TracedStateWrapper<S, K, C>])
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    for successor in successors.iter_mut().filter(|successor| successor.g_override.is_none()) {
+        successor.g_override = Some(current.g() + successor.state.g().saturating_sub(current.state.g()));
+    }
+}
+
+// Seeded g of `key` when it is on the path of the seeded goal, which is closed along with every
+// state on its path.
+fn seeded_g_on_path<S, K, C>(goal: &K, key: &K, closed_list: &HashMap<K, TracedStateWrapper<S, K, C>>) -> Option<usize>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let mut current = goal;
+    loop {
+        let state = closed_list.get(current)?;
+        if current == key {
+            return Some(state.g());
+        }
+        current = state.prev_key.as_ref()?;
+    }
+}
+
+// Cost of the seeded goal's path once `junction` takes the place of the state with `seeded_g`.
+fn stitched_cost<S, K, C>(junction: &TracedStateWrapper<S, K, C>, seeded_g: usize, goal: &K, closed_list: &HashMap<K, TracedStateWrapper<S, K, C>>) -> usize
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    (closed_list[goal].g() + junction.g()).saturating_sub(seeded_g)
+}
+
+// The path of the seeded goal from the initial state: the junction's path, then the seeded states
+// after it with their g moved by as much as the junction's.
+fn stitch<S, K, C>(junction: TracedStateWrapper<S, K, C>, seeded_g: usize, goal: &K, mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>>) -> TracedResult<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let iterations = closed_list.len();
+    let junction_key = junction.key();
+    let junction_g = junction.g();
+    let mut junction = Some(junction);
+    let mut take_closed = move |key: &K| {
+        if *key == junction_key {
+            return junction.take();
+        }
+        let mut state = closed_list.remove(key)?;
+        if junction.is_some() {
+            state.g_override = Some((state.g() + junction_g).saturating_sub(seeded_g));
+        }
+        Some(state)
+    };
+
+    let goal_state = take_closed(goal).expect("the seeded goal is closed");
+    rebuild_result(goal_state, iterations, take_closed)
+}
+
+// Gives each successor the g through `current` with the overridden cost of its edge.
+fn override_edge_costs<S, K, C>(current: &TracedStateWrapper<S, K, C>, successors: &mut [TracedStateWrapper<S, K, C>], options: &SearchOptions<S, K, C>)
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    for successor in successors {
+        let Some(change) = &successor.change else {
+            continue;
+        };
+        let cost = successor.state.g().saturating_sub(current.state.g());
+        if let Some(cost) = options.overridden_edge_cost(&current.state, change, cost) {
+            successor.g_override = Some(current.g() + cost);
+        }
+    }
+}
+
+// Follows the prev_key links of the goal back through the closed list to rebuild the path.
+pub(crate) fn build_result<S, K, C>(goal_state: TracedStateWrapper<S, K, C>, mut closed_list: HashMap<K, TracedStateWrapper<S, K, C>>) -> TracedResult<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let iterations = closed_list.len();
+    rebuild_result(goal_state, iterations, |key| closed_list.remove(key))
+}
+
+// build_result over any closed list, `take_closed` removing the closed state of a key.
+fn rebuild_result<S, K, C>(goal_state: TracedStateWrapper<S, K, C>, iterations: usize, mut take_closed: impl FnMut(&K) -> Option<TracedStateWrapper<S, K, C>>) -> TracedResult<S, K, C>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    let cost = goal_state.g();
+    let TracedStateWrapper { state, prev_key, change, .. } = goal_state;
+
+    let final_state= state;
+    let mut path = Vec::new();
+    let mut step_costs = Vec::new();
+    let mut g = cost;
+
+    if let Some(change) = change {
+        path.push(change);
+    }
+
+    if let Some(prev_key) = prev_key {
+        let mut curr_key = prev_key;
+
+        while let Some(prev_state) = take_closed(&curr_key) {
+            step_costs.push(g.saturating_sub(prev_state.g()));
+            g = prev_state.g();
+            if let Some(change) = prev_state.change {
+                path.push(change);
+            }
+
+            if let Some(prev_key) = prev_state.prev_key {
+                curr_key = prev_key;
+            }
+            else {
+                break;
+            }
+        }
+    }
+
+    path.reverse();
+    step_costs.reverse();
+
+    TracedResult::new(
+        path,
+        iterations,
+        final_state
+    ).with_costs(cost, step_costs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{recording::{Recording, SearchEvent}, search_stats::Thrasher, state_pool::Recycler, test_support::{Direction, GraphState, SlidingPuzzle, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, traced::{path::FieldlessCodec, replay::replay}, unreachable::GoalTagMismatch};
+
+    // Expansion order by g: 0, 1, 2, 3, 5, then the goal 7.
+    // depth 2: 1 -> 0 is closed, 2 -> 3 is worse than the open entry, 2 -> 4 is forbidden.
+    // depth 3: 3 -> 6 goes over the f bound, 5 is a dead end.
+    const SCRIPTED: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 2)],
+        &[(0, 1), (3, 5)],
+        &[(3, 5), (4, 1)],
+        &[(5, 1), (6, 20), (7, 2)],
+        &[],
+        &[],
+        &[],
+        &[]
+    ];
+
+    fn scripted_options<'a>() -> SearchOptions<'a, GraphState, usize, usize> {
+        SearchOptions::new()
+            .with_f_bound(10)
+            .with_forbidden(|&key| key == 4)
+    }
+
+    #[test]
+    fn test_pruning_matrix() {
+        let outcome = traced_astar_with_options(
+            GraphState::new(SCRIPTED, ZERO_H, 0, 7),
+            scripted_options().with_pruning_by_depth(true)
+        );
+
+        let result = outcome.result.unwrap();
+        assert_eq!(result.path, vec![1, 3, 7]);
+        assert_eq!(result.final_state.g(), 8);
+
+        let by_depth = outcome.stats.pruned_by_depth.unwrap();
+        assert_eq!(by_depth.len(), 4);
+        assert_eq!(by_depth[0].total(), 0);
+        assert_eq!(by_depth[1].total(), 0);
+        assert_eq!(by_depth[2][SkipReason::Closed], 1);
+        assert_eq!(by_depth[2][SkipReason::WorseOpen], 1);
+        assert_eq!(by_depth[2][SkipReason::Forbidden], 1);
+        assert_eq!(by_depth[2].total(), 3);
+        assert_eq!(by_depth[3][SkipReason::FBound], 1);
+        assert_eq!(by_depth[3][SkipReason::DeadEnd], 1);
+        assert_eq!(by_depth[3].total(), 2);
+
+        assert_eq!(outcome.stats.expansions, 5);
+        assert_eq!(outcome.stats.pruned.total(), 5);
+    }
+
+    #[test]
+    fn test_pruning_csv() {
+        let outcome = traced_astar_with_options(
+            GraphState::new(SCRIPTED, ZERO_H, 0, 7),
+            scripted_options().with_pruning_by_depth(true)
+        );
+
+        assert_eq!(
+            outcome.stats.pruning_to_csv(),
+            "depth,closed,worse_open,f_bound,forbidden,dead_end,commuted,soft_rejected\n\
+             0,0,0,0,0,0,0,0\n\
+             1,0,0,0,0,0,0,0\n\
+             2,1,1,0,1,0,0,0\n\
+             3,0,0,1,0,1,0,0\n"
+        );
+    }
+
+    #[test]
+    fn test_matrix_disabled_by_default() {
+        let outcome = traced_astar_with_options(GraphState::new(SCRIPTED, ZERO_H, 0, 7), scripted_options());
+
+        assert!(outcome.stats.pruned_by_depth.is_none());
+        assert_eq!(outcome.stats.pruned.total(), 5);
+        assert_eq!(outcome.stats.pruning_to_csv(), "depth,closed,worse_open,f_bound,forbidden,dead_end,commuted,soft_rejected\n");
+    }
+
+    #[test]
+    fn test_every_search_gets_its_own_id() {
+        let first = traced_astar_with_options(GraphState::new(SCRIPTED, ZERO_H, 0, 7), SearchOptions::new());
+        let second = traced_astar_with_options(GraphState::new(SCRIPTED, ZERO_H, 0, 7), SearchOptions::new());
+
+        assert_eq!(first.search_id(), first.stats.search_id);
+        assert!(second.search_id() > first.search_id());
+    }
+
+    #[test]
+    fn test_default_options_match_traced_astar() {
+        let outcome = traced_astar_with_options(GraphState::new(SCRIPTED, ZERO_H, 0, 7), SearchOptions::new());
+        let reference = traced_astar(GraphState::new(SCRIPTED, ZERO_H, 0, 7)).unwrap();
+        let result = outcome.result.unwrap();
+
+        assert_eq!(result.path, reference.path);
+        assert_eq!(result.iterations, reference.iterations);
+    }
+
+    #[test]
+    fn test_change_codec_compresses_path() {
+        let codec = Rc::new(FieldlessCodec::new(&[0, 1, 2, 3, 4, 5, 6, 7]));
+        let outcome = traced_astar_with_options(
+            GraphState::new(SCRIPTED, ZERO_H, 0, 7),
+            scripted_options().with_change_codec(codec)
+        );
+
+        let result = outcome.result.unwrap();
+        assert!(result.path.is_compressed());
+        assert_eq!(result.path.heap_bytes(), 3);
+        assert_eq!(result.path, vec![1, 3, 7]);
+    }
+
+    // h = 10 on 1, 2 and 3 is admissible but inconsistent, so 4 is closed with g = 10 and then
+    // reached three more times, each time through a cheaper parent.
+    const THRASHING: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 2), (3, 3), (4, 10), (5, 30)],
+        &[(4, 8)],
+        &[(4, 5)],
+        &[(4, 1)],
+        &[],
+        &[]
+    ];
+    const THRASHING_H: &[usize] = &[0, 10, 10, 10, 0, 0];
+
+    #[test]
+    fn test_visit_counts_find_the_thrashing_key() {
+        let outcome = traced_astar_with_options(
+            GraphState::new(THRASHING, THRASHING_H, 0, 5),
+            SearchOptions::new().with_reopening(true).with_visit_counts(true)
+        );
+
+        assert_eq!(outcome.result.unwrap().final_state.g(), 30);
+        assert_eq!(outcome.stats.reopened, 3);
+        assert_eq!(outcome.stats.top_thrashers(3), vec![Thrasher { key: 4, reinserts: 0, reopenings: 3, final_g: 4 }]);
+        assert_eq!(outcome.stats.wasted_expansions(), 3);
+        assert_eq!(outcome.stats.visits.unwrap().get(&4).unwrap().expansions, 4);
+
+        let untracked = traced_astar_with_options(GraphState::new(THRASHING, THRASHING_H, 0, 5), SearchOptions::new().with_reopening(true));
+        assert!(untracked.stats.visits.is_none());
+        assert!(untracked.stats.top_thrashers(3).is_empty());
+        assert_eq!(untracked.stats.wasted_expansions(), 0);
+    }
+
+    // Three ways of g = 1 from 0, then 1 -> 4 of g = 3 and the goal 5 behind 3.
+    const FAN: &[&[(usize, usize)]] = &[
+        &[(1, 1), (2, 1), (3, 1)],
+        &[(4, 2)],
+        &[],
+        &[(5, 3)],
+        &[],
+        &[]
+    ];
+
+    // Keys of the expanded states in order.
+    fn hinted_order(hint: impl Fn(&usize) -> i32) -> Vec<usize> {
+        let mut recording = Recording::new();
+        traced_astar_with_options(GraphState::new(FAN, &[0; 6], 0, 5), SearchOptions::new().with_priority_hint(hint).with_recording(&mut recording));
+        let simulation = recording.simulate();
+        simulation.trace.iter().map(|&node| simulation.node(node).key).collect()
+    }
+
+    #[test]
+    fn test_priority_hint_orders_equal_f() {
+        assert_eq!(hinted_order(|&key| -(key as i32))[..4], [0, 1, 2, 3]);
+        assert_eq!(hinted_order(|&key| key as i32)[..4], [0, 3, 2, 1]);
+
+        // The hint never overtakes f: 4 (f = 3) still comes before the goal (f = 4).
+        let order = hinted_order(|&key| if key == 5 { 1000 } else { 0 });
+        assert_eq!(order.len(), 5);
+        assert_eq!(order.last(), Some(&4));
+    }
+
+    #[test]
+    fn test_priority_hint_keeps_costs() {
+        for seed in 0..5 {
+            let plain = traced_astar_with_options(SlidingPuzzle::scrambled(seed, 30), SearchOptions::new());
+            let hinted = traced_astar_with_options(
+                SlidingPuzzle::scrambled(seed, 30),
+                SearchOptions::new()
+                    .with_priority_hint(|tiles: &[u8; 9]| i32::from(tiles[0]) * 40 - i32::from(tiles[8]) * 300)
+                    .with_tie_break(|direction: &Direction| *direction as usize)
+            );
+
+            assert_eq!(hinted.result.unwrap().final_state.g(), plain.result.unwrap().final_state.g());
+        }
+    }
+
+    #[test]
+    fn test_max_expansions_stops_the_search() {
+        let puzzle = SlidingPuzzle::scrambled(2, 30);
+        let expansions = traced_astar_with_options(puzzle.clone(), SearchOptions::new()).stats.expansions;
+
+        let cut = traced_astar_with_options(puzzle.clone(), SearchOptions::new().with_max_expansions(expansions - 1));
+        assert!(cut.result.is_none() && cut.budget_exhausted);
+        assert_eq!(cut.stats.expansions, expansions - 1);
+
+        // The goal is extracted after the last expansion, so the exact count is enough.
+        let exact = traced_astar_with_options(puzzle, SearchOptions::new().with_max_expansions(expansions));
+        assert!(exact.result.is_some() && !exact.budget_exhausted);
+    }
+
+    #[test]
+    fn test_recording_tells_reopenings_from_improvements() {
+        let mut recording = Recording::new();
+        let outcome = traced_astar_with_options(
+            GraphState::new(THRASHING, THRASHING_H, 0, 5),
+            SearchOptions::new().with_reopening(true).with_visit_counts(true).with_recording(&mut recording)
+        );
+
+        let reopened = recording.events().iter()
+            .filter(|event| matches!(event, SearchEvent::Inserted { reopened: true, .. }))
+            .count();
+        assert_eq!(reopened, 3);
+        assert_eq!(recording.simulate().stats, outcome.stats);
+    }
+
+    #[test]
+    fn test_visit_counts_rank_open_reinserts() {
+        // 2 is queued with g = 5, improved to g = 3 through 1 and then to g = 2 through 3.
+        const IMPROVING: &[&[(usize, usize)]] = &[
+            &[(1, 1), (2, 5), (3, 1), (4, 9)],
+            &[(2, 2)],
+            &[(4, 1)],
+            &[(2, 1), (1, 3)],
+            &[]
+        ];
+
+        let outcome = traced_astar_with_options(
+            GraphState::new(IMPROVING, &[0; 5], 0, 4),
+            SearchOptions::new().with_visit_counts(true)
+        );
+
+        assert_eq!(outcome.result.unwrap().final_state.g(), 3);
+        assert_eq!(outcome.stats.top_thrashers(1), vec![Thrasher { key: 2, reinserts: 2, reopenings: 0, final_g: 2 }]);
+        assert_eq!(outcome.stats.top_thrashers(5).len(), 2);
+        assert_eq!(outcome.stats.wasted_expansions(), 0);
+    }
+
+    struct CountingRecycler {
+        recycled: Vec<usize>
+    }
+
+    impl Recycler<GraphState> for CountingRecycler {
+        fn recycle(&mut self, state: GraphState) {
+            self.recycled.push(state.node);
+        }
+    }
+
+    #[test]
+    fn test_recycler_sees_every_discard() {
+        // 0 -> 2 is found with g = 4 first and improved to g = 2 through 1.
+        const IMPROVING: &[&[(usize, usize)]] = &[
+            &[(1, 1), (2, 4)],
+            &[(2, 1), (0, 1)],
+            &[(3, 1), (1, 1)],
+            &[]
+        ];
+
+        let mut recycler = CountingRecycler { recycled: Vec::new() };
+        let outcome = traced_astar_with_options(
+            GraphState::new(IMPROVING, &[0; 4], 0, 3),
+            SearchOptions::new().with_recycler(&mut recycler)
+        );
+
+        assert_eq!(outcome.result.unwrap().final_state.g(), 3);
+        assert_eq!(outcome.stats.improved, 1);
+        assert_eq!(outcome.stats.pruned[SkipReason::Closed], 2);
+        assert_eq!(recycler.recycled.len(), outcome.stats.improved + outcome.stats.pruned.total());
+        assert_eq!(recycler.recycled, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn test_recycler_counts_match_pruning_stats() {
+        let mut recycler = CountingRecycler { recycled: Vec::new() };
+        let outcome = traced_astar_with_options(
+            GraphState::new(SCRIPTED, ZERO_H, 0, 7),
+            scripted_options().with_recycler(&mut recycler)
+        );
+
+        let stats = outcome.stats;
+        let discarded = stats.pruned.total() - stats.pruned[SkipReason::DeadEnd] + stats.improved;
+        assert_eq!(recycler.recycled.len(), discarded);
+        assert_eq!(recycler.recycled.len(), 4);
+    }
+
+    #[test]
+    fn test_corrupted_open_list_is_reported() {
+        let outcome = traced_astar_with_options(
+            GraphState::new(SCRIPTED, ZERO_H, 0, 7),
+            scripted_options().with_corrupt_open_list_after(2)
+        );
+
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.stats.expansions, 2);
+        let Some(SearchError::Internal(InternalError::OpenList(error))) = outcome.error else {
+            panic!("expected an internal error, got {:?}", outcome.error);
+        };
+        assert_eq!(error.index, Some(usize::MAX));
+    }
+
+    #[test]
+    fn test_miscounted_step_is_caught() {
+        let start = || GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let outcome = traced_astar_with_options(start(), SearchOptions::new().with_cost_verification(true).with_miscounted_step(2));
+
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.error, Some(SearchError::Internal(InternalError::StepCosts { steps: 11, cost: 9 })));
+        assert_eq!(outcome.error.unwrap().to_string(), "internal search error: the path's steps cost 11 but the result reports 9");
+
+        let outcome = traced_astar_with_options(start(), SearchOptions::new().with_cost_verification(true));
+        assert_eq!((outcome.result.map(|result| result.cost), outcome.error), (Some(9), None));
+    }
+
+    // A chain 0 -> 1 -> ... -> 4 with a shortcut 0 -> 9 -> 4, where generating the successors of 9
+    // never finishes on its own.
+    #[derive(Clone)]
+    struct Stalling {
+        node: usize,
+        g: usize
+    }
+
+    impl AStarState<usize> for Stalling {
+        fn key(&self) -> usize {
+            self.node
+        }
+
+        fn h(&self) -> usize {
+            0
+        }
+
+        fn f(&self) -> usize {
+            self.g
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.node == 4
+        }
+    }
+
+    impl TracedState<usize, usize> for Stalling {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            self.generate_traced_successors_with(&GenContext::unbounded())
+        }
+
+        fn generate_traced_successors_with(&self, ctx: &GenContext) -> Vec<(Self, usize)> {
+            let next: &[usize] = match self.node {
+                0 => &[1, 9],
+                9 => {
+                    while !ctx.should_abort() {
+                        std::hint::spin_loop();
+                    }
+                    &[4]
+                }
+                node => &[node + 1]
+            };
+            next.iter().map(|&node| (Stalling { node, g: self.g + 1 }, node)).collect()
+        }
+    }
+
+    #[test]
+    fn test_generation_timeout_makes_a_dead_end() {
+        let mut recording = Recording::new();
+        let outcome = traced_astar_with_options(
+            Stalling { node: 0, g: 0 },
+            SearchOptions::new().with_max_generation_time(Duration::from_millis(5)).with_recording(&mut recording)
+        );
+
+        // The shortcut is lost, the chain is not.
+        assert_eq!(outcome.result.unwrap().final_state.g(), 4);
+        let [SearchWarning::GenerationTimedOut { key, elapsed }] = outcome.stats.warnings[..] else {
+            panic!("expected one timeout, got {:?}", outcome.stats.warnings);
+        };
+        assert_eq!(key, 9);
+        assert!(elapsed >= Duration::from_millis(5));
+        assert_eq!(outcome.stats.pruned[SkipReason::DeadEnd], 1);
+        assert_eq!(recording.simulate().stats, outcome.stats);
+    }
+
+    #[test]
+    fn test_no_budget_leaves_generation_alone() {
+        let outcome = traced_astar_with_options(GraphState::new(SCRIPTED, ZERO_H, 0, 7), scripted_options());
+        assert!(outcome.stats.warnings.is_empty());
+        assert_eq!(scripted_options().max_generation_time(), None);
+    }
+
+    #[test]
+    fn test_optimistic_seed_is_corrected() {
+        // Node 6 is 8 away from the start and 1 from the goal, the estimate of 0 makes it the way
+        // to a goal at cost 1 until the real path to it comes in at 8.
+        let start = || GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let seed = GraphState { node: 6, ..start() };
+        let mut recording = Recording::new();
+        let outcome = traced_astar_with_options(start(), SearchOptions::new().with_seed_nodes(vec![(seed, 0)]).with_recording(&mut recording));
+
+        let result = outcome.result.unwrap();
+        assert_eq!(result.path.to_vec(), vec![3, 6, 7]);
+        assert_eq!(result.cost, 9);
+        assert_eq!(result.step_costs, Some(vec![1, 7, 1]));
+        assert_eq!(replay(&start(), result.path.to_vec()).unwrap().g(), 9);
+        // 9 is optimal, but 5 was still open at f 8.
+        assert_eq!(outcome.optimality, Optimality::Unknown);
+        assert_eq!(recording.simulate().stats, outcome.stats);
+    }
+
+    #[test]
+    fn test_upper_bound_seed_speeds_the_search_up() {
+        let puzzle = SlidingPuzzle::scrambled(2, 60);
+        let plain = traced_astar_with_options(puzzle.clone(), SearchOptions::new());
+        let path = plain.result.unwrap().path.to_vec();
+        assert_eq!(path.len(), 28);
+
+        for at in [4, 14, 24] {
+            let seed = path[..at].iter().fold(puzzle.clone(), |state, direction| {
+                state.generate_traced_successors().into_iter().find(|(_, moved)| moved == direction).unwrap().0
+            });
+            let seeded = traced_astar_with_options(puzzle.clone(), SearchOptions::new().with_seed_nodes(vec![(seed, at)]));
+
+            assert!(seeded.stats.expansions < plain.stats.expansions, "seed at {}: {} expansions", at, seeded.stats.expansions);
+            assert_eq!(seeded.result.unwrap().cost, 28);
+            assert_eq!(seeded.optimality, Optimality::Kept);
+        }
+    }
+
+    #[test]
+    fn test_soft_open_limit_bounds_the_open_list() {
+        // A deep instance whose open list grows to about 20 times the limit.
+        let puzzle = SlidingPuzzle::scrambled(2, 60);
+        let plain = traced_astar_with_options(puzzle.clone(), SearchOptions::new());
+        let limited = traced_astar_with_options(puzzle.clone(), SearchOptions::new().with_soft_open_limit(200, 0.5));
+        assert!(plain.stats.peak_open > 3000);
+        assert_eq!(plain.optimality, Optimality::Kept);
+
+        assert!(limited.stats.peak_open <= 250, "{}", limited.stats.peak_open);
+        assert!(limited.stats.pruned[SkipReason::SoftRejected] > 0);
+        assert_eq!(limited.optimality, Optimality::Unknown);
+        let result = limited.result.unwrap();
+        assert!(replay(&puzzle, result.path.to_vec()).unwrap().is_goal());
+        assert!(result.cost >= 28);
+
+        // Under the limit the search is left alone.
+        let roomy = traced_astar_with_options(puzzle, SearchOptions::new().with_soft_open_limit(5000, 0.5));
+        assert_eq!(roomy.trace_fingerprint(), plain.trace_fingerprint());
+        assert_eq!(roomy.optimality, Optimality::Kept);
+    }
+
+    // EIGHT_NODES, with the move from node 0 to `target` forced at a cost of 5.
+    #[derive(Clone)]
+    struct Forcing {
+        graph: GraphState,
+        target: usize
+    }
+
+    impl AStarState<usize> for Forcing {
+        fn key(&self) -> usize {
+            self.graph.key()
+        }
+
+        fn h(&self) -> usize {
+            self.graph.h()
+        }
+
+        fn f(&self) -> usize {
+            self.graph.f()
+        }
+
+        fn g(&self) -> usize {
+            self.graph.g()
+        }
+
+        fn is_goal(&self) -> bool {
+            self.graph.is_goal()
+        }
+    }
+
+    impl TracedState<usize, usize> for Forcing {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            self.graph.generate_traced_successors()
+                .into_iter()
+                .map(|(graph, change)| (Forcing { graph, target: self.target }, change))
+                .collect()
+        }
+
+        fn forced_successor(&self) -> Option<(Self, usize)> {
+            let graph = GraphState { node: self.target, g_cost: self.graph.g_cost + 5, ..self.graph.clone() };
+            (self.graph.node == 0).then_some((Forcing { graph, target: self.target }, self.target))
+        }
+    }
+
+    #[test]
+    fn test_forced_check_catches_a_wrong_forced_move() {
+        let start = |target| Forcing { graph: GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), target };
+
+        // Forcing node 2 costs 2 more than the optimum, which the search can't tell.
+        let outcome = traced_astar_with_options(start(2), SearchOptions::new());
+        assert_eq!(outcome.result.unwrap().cost, 11);
+        assert_eq!(outcome.stats.forced, 1);
+
+        let outcome = traced_astar_with_options(start(2), SearchOptions::new().with_forced_check(true));
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.validation_error, Some(Anomaly::ForcedNotBest { parent: 0, child: 2, f: 9, best_f: 7 }));
+
+        let outcome = traced_astar_with_options(start(6), SearchOptions::new().with_forced_check(true));
+        assert_eq!(outcome.validation_error, Some(Anomaly::ForcedNotGenerated { parent: 0, child: 6 }));
+
+        let outcome = traced_astar_with_options(start(2), SearchOptions::new().with_forced_successors(false).with_forced_check(true));
+        assert_eq!((outcome.result.unwrap().cost, outcome.stats.forced), (9, 0));
+    }
+
+    #[test]
+    fn test_exhaustion_reports_coverage() {
+        // 2 can't be reached from 0, which only leads to 1 and back.
+        const SPLIT: &[&[(usize, usize)]] = &[&[(1, 1)], &[(0, 1)], &[]];
+        let start = || GraphState::new(SPLIT, &[0, 0, 0], 0, 2);
+
+        let outcome = traced_astar_with_options(start(), SearchOptions::new().with_state_space_bound(2));
+        assert_eq!(outcome.coverage, Some(1.0));
+        assert!(outcome.stats.warnings.is_empty());
+
+        // Three states left out of five look like lost moves, unless the bound is known to be loose.
+        let outcome = traced_astar_with_options(start(), SearchOptions::new().with_state_space_bound(5));
+        assert_eq!(outcome.coverage, Some(0.4));
+        assert_eq!(outcome.stats.warnings, vec![SearchWarning::SuspiciousExhaustion { closed: 2, bound: 5 }]);
+        let outcome = traced_astar_with_options(start(), SearchOptions::new().with_state_space_bound(5).with_min_coverage(0.25));
+        assert!(outcome.stats.warnings.is_empty());
+
+        let outcome = traced_astar_with_options(start(), SearchOptions::new());
+        assert_eq!(outcome.coverage, None);
+        let outcome = traced_astar_with_options(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), SearchOptions::new().with_state_space_bound(100));
+        assert!(outcome.result.is_some() && outcome.coverage.is_none());
+    }
+
+    // 0 -> 1 -> 3 for 2, or 0 -> 2 -> 3 for 4.
+    const SHORTCUT: &[&[(usize, usize)]] = &[&[(1, 1), (2, 3)], &[(3, 1)], &[(3, 1)], &[]];
+
+    #[test]
+    fn test_incumbent_at_the_optimum_keeps_the_goal() {
+        // h sends the greedy pass through 1, which is also the optimal way. 2 then has f 4, over
+        // the bound of 2, while the goal has f 2, equal to it.
+        let outcome = traced_astar_with_options(GraphState::new(SHORTCUT, &[2, 0, 1, 0], 0, 3), SearchOptions::new().with_auto_incumbent(true));
+
+        assert_eq!((outcome.stats.incumbent_cost, outcome.stats.incumbent_expansions), (Some(2), 2));
+        assert_eq!(outcome.result.unwrap().cost, 2);
+        assert_eq!(outcome.stats.pruned[SkipReason::FBound], 1);
+        assert_eq!(outcome.stats.expansions, 2);
+    }
+
+    #[test]
+    fn test_suboptimal_incumbent_still_finds_the_optimum() {
+        let start = GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7);
+        let plain = traced_astar_with_options(start.clone(), SearchOptions::new());
+        let bounded = traced_astar_with_options(start.clone(), SearchOptions::new().with_auto_incumbent(true));
+
+        // Greedy goes 0 -> 2 -> 5 -> 7 for 11.
+        assert_eq!(bounded.stats.incumbent_cost, Some(11));
+        assert_eq!(bounded.result.unwrap().cost, plain.result.unwrap().cost);
+        assert_eq!(plain.stats.incumbent_cost, None);
+
+        // A lower bound given explicitly wins.
+        let tighter = traced_astar_with_options(start.clone(), SearchOptions::new().with_auto_incumbent(true).with_f_bound(9));
+        assert_eq!(tighter.result.unwrap().cost, 9);
+        assert!(traced_astar_with_options(start, SearchOptions::new().with_auto_incumbent(true).with_f_bound(8)).result.is_none());
+    }
+
+    #[test]
+    fn test_incumbent_pass_honors_forbidden_keys() {
+        // The greedy pass would go through 1 for 2, but 1 is forbidden: a bound of 2 would cut
+        // the only allowed path, which costs 4.
+        let options = SearchOptions::new().with_auto_incumbent(true).with_forbidden(|&key| key == 1);
+        let outcome = traced_astar_with_options(GraphState::new(SHORTCUT, &[2, 0, 1, 0], 0, 3), options);
+        assert_eq!(outcome.stats.incumbent_cost, Some(4));
+        assert_eq!(outcome.result.unwrap().cost, 4);
+
+        // Nothing reachable: both passes fail, the second one unbounded.
+        const STUCK: &[&[(usize, usize)]] = &[&[(1, 1)], &[], &[]];
+        let outcome = traced_astar_with_options(GraphState::new(STUCK, &[0, 0, 0], 0, 2), SearchOptions::new().with_auto_incumbent(true));
+        assert_eq!((outcome.stats.incumbent_cost, outcome.stats.incumbent_expansions), (None, 2));
+        assert!(outcome.result.is_none());
+        assert_eq!(outcome.stats.expansions, 2);
+    }
+
+    // 0 -> 1 -> 2 leads one way into the loop 2 -> 3 -> 4 -> 2, with no way out to the goal 5,
+    // which 0 also reaches directly for 10.
+    const SEALED: &[&[(usize, usize)]] = &[&[(1, 1), (5, 10)], &[(2, 1)], &[(3, 1)], &[(4, 1)], &[(2, 1)], &[]];
+
+    #[test]
+    fn test_exhausted_region_is_skipped_later() {
+        let sealed = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 2, 5), SearchOptions::new());
+        assert!(sealed.result.is_none());
+        let set = sealed.into_unreachable_set("to 5").unwrap();
+        assert_eq!((set.len(), set.contains(&3), set.contains(&1)), (3, true, false));
+
+        let options = SearchOptions::new().with_known_unreachable(&set, "to 5").unwrap();
+        let inside = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 3, 5), options);
+        assert!(inside.result.is_none());
+        assert_eq!((inside.stats.expansions, inside.stats.pruned[SkipReason::DeadEnd]), (0, 1));
+
+        // From outside, the region is never entered, and the goal is still found.
+        let plain = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 0, 5), SearchOptions::new());
+        let options = SearchOptions::new().with_known_unreachable(&set, "to 5").unwrap();
+        let outside = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 0, 5), options);
+        assert_eq!(outside.result.unwrap().cost, plain.result.unwrap().cost);
+        assert_eq!((plain.stats.expansions, outside.stats.expansions), (5, 2));
+
+        let refused = SearchOptions::<GraphState, usize, usize>::new().with_known_unreachable(&set, "to 4").err();
+        assert_eq!(refused, Some(GoalTagMismatch { proven_for: "to 5".to_string(), offered_for: "to 4".to_string() }));
+    }
+
+    #[test]
+    fn test_pruned_exhaustion_proves_nothing() {
+        // With 5 out of reach of the bound, or forbidden, the search from 0 runs out of states,
+        // but 0 can reach the goal.
+        let bounded = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 0, 5), SearchOptions::new().with_f_bound(5));
+        assert!(bounded.result.is_none() && bounded.exhausted_keys.is_none());
+        let forbidden = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 0, 5), SearchOptions::new().with_forbidden(|&key| key == 5));
+        assert!(forbidden.result.is_none() && forbidden.into_unreachable_set("to 5").is_none());
+
+        // Known unreachable keys are proven, and the proof carries over.
+        let set = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 2, 5), SearchOptions::new()).into_unreachable_set("to 5").unwrap();
+        let options = SearchOptions::new().with_known_unreachable(&set, "to 5").unwrap();
+        let outcome = traced_astar_with_options(GraphState::new(SEALED, &[0; 6], 1, 5), options);
+        assert_eq!(outcome.exhausted_keys, Some(HashSet::from([1])));
+    }
 }
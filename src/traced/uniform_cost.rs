@@ -0,0 +1,39 @@
+use std::hash::Hash;
+
+use crate::{traced::{best_first::best_first_search, result::TracedResult, state::TracedState}};
+
+// Dijkstra's algorithm: traced_astar with the open list ordered by g alone, for domains without
+// an admissible heuristic. h() is never called, so it can return anything.
+pub fn uniform_cost_search<S, K, C>(initial_state: S) -> Option<TracedResult<S, K, C>>
+where
+    S: TracedState<K, C>,
+    K: Clone + Eq + Hash
+{
+    best_first_search(initial_state, S::g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, EIGHT_NODES}, traced::traced_astar::traced_astar};
+
+    // Inadmissible: cheap estimates at 2 and 5 lure A* onto 0 -> 2 -> 5 -> 7, which costs 11.
+    const MISLEADING_H: &[usize] = &[0, 50, 0, 50, 50, 0, 50, 0];
+
+    #[test]
+    fn test_misleading_h_is_ignored() {
+        let misled = traced_astar(GraphState::new(EIGHT_NODES, MISLEADING_H, 0, 7)).unwrap();
+        assert_eq!(misled.final_state.g(), 11);
+
+        let result = uniform_cost_search(GraphState::new(EIGHT_NODES, MISLEADING_H, 0, 7)).unwrap();
+        let path = result.path.to_vec();
+        assert_eq!(result.final_state.g(), 9);
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[1..], [2, 5, 7]);
+    }
+
+    #[test]
+    fn test_unreachable_goal() {
+        assert!(uniform_cost_search(GraphState::new(EIGHT_NODES, MISLEADING_H, 7, 0)).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+// What to do when a new entry maps to a slot that already holds another key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplacementPolicy {
+    AlwaysReplace,
+    // Keep the entry proven with the larger remaining budget, it prunes more.
+    DepthPreferred
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranspositionEntry {
+    // Remaining cost budget (threshold - g) under which the subtree was searched without success.
+    pub proven_budget: usize,
+    // Lower bound on the cost from the state to a goal. Never smaller than h().
+    pub lower_bound: usize
+}
+
+// Fixed size table for the depth-first searches, one entry per slot picked by the key hash.
+// It remembers subtrees already proven fruitless, so a later visit to the same key (in the same
+// threshold iteration or a later one) can be cut off without searching it again.
+pub struct TranspositionTable<K>
+where
+    K: Eq + Hash
+{
+    slots: Vec<Option<(K, TranspositionEntry)>>,
+    policy: ReplacementPolicy,
+    hits: usize,
+    cutoffs: usize,
+    stores: usize,
+    replacements: usize
+}
+
+impl<K> TranspositionTable<K>
+where
+    K: Eq + Hash
+{
+    pub fn new(capacity: usize, policy: ReplacementPolicy) -> Self {
+        TranspositionTable {
+            slots: (0..capacity.max(1)).map(|_| None).collect(),
+            policy,
+            hits: 0,
+            cutoffs: 0,
+            stores: 0,
+            replacements: 0
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn policy(&self) -> ReplacementPolicy {
+        self.policy
+    }
+
+    // Lookups that found an entry for their key.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    // Visits cut off because the stored bound was above the threshold while h() alone wasn't.
+    pub fn cutoffs(&self) -> usize {
+        self.cutoffs
+    }
+
+    pub fn stores(&self) -> usize {
+        self.stores
+    }
+
+    // Stores that overwrote the entry of a different key.
+    pub fn replacements(&self) -> usize {
+        self.replacements
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<TranspositionEntry> {
+        let index = self.slot(key);
+
+        match &self.slots[index] {
+            Some((slot_key, entry)) if slot_key == key => {
+                self.hits += 1;
+                Some(*entry)
+            }
+            _ => None
+        }
+    }
+
+    pub fn store(&mut self, key: K, entry: TranspositionEntry) {
+        let index = self.slot(&key);
+
+        if let Some((slot_key, slot_entry)) = &mut self.slots[index] {
+            if *slot_key == key {
+                slot_entry.lower_bound = slot_entry.lower_bound.max(entry.lower_bound);
+                slot_entry.proven_budget = slot_entry.proven_budget.max(entry.proven_budget);
+                self.stores += 1;
+                return;
+            }
+
+            if self.policy == ReplacementPolicy::DepthPreferred && slot_entry.proven_budget > entry.proven_budget {
+                return;
+            }
+            self.replacements += 1;
+        }
+
+        self.slots[index] = Some((key, entry));
+        self.stores += 1;
+    }
+
+    pub(crate) fn record_cutoff(&mut self) {
+        self.cutoffs += 1;
+    }
+
+    fn slot(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.slots.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(proven_budget: usize, lower_bound: usize) -> TranspositionEntry {
+        TranspositionEntry {
+            proven_budget,
+            lower_bound
+        }
+    }
+
+    #[test]
+    fn test_store_and_get() {
+        let mut table = TranspositionTable::new(64, ReplacementPolicy::AlwaysReplace);
+        assert_eq!(table.get(&1), None);
+
+        table.store(1, entry(3, 5));
+        table.store(1, entry(2, 7));
+
+        assert_eq!(table.get(&1), Some(entry(3, 7)));
+        assert_eq!(table.hits(), 1);
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_replacement_policies() {
+        // With a single slot every key collides.
+        let mut always = TranspositionTable::new(1, ReplacementPolicy::AlwaysReplace);
+        always.store(1, entry(10, 5));
+        always.store(2, entry(1, 5));
+        assert_eq!(always.get(&1), None);
+        assert_eq!(always.get(&2), Some(entry(1, 5)));
+        assert_eq!(always.replacements(), 1);
+
+        let mut depth_preferred = TranspositionTable::new(1, ReplacementPolicy::DepthPreferred);
+        depth_preferred.store(1, entry(10, 5));
+        depth_preferred.store(2, entry(1, 5));
+        assert_eq!(depth_preferred.get(&1), Some(entry(10, 5)));
+        assert_eq!(depth_preferred.get(&2), None);
+
+        depth_preferred.store(3, entry(12, 5));
+        assert_eq!(depth_preferred.get(&3), Some(entry(12, 5)));
+        assert_eq!(depth_preferred.replacements(), 1);
+    }
+}
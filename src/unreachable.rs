@@ -0,0 +1,81 @@
+use std::{collections::HashSet, fmt::Display, hash::Hash};
+
+// Keys proven unable to reach a goal: the closed keys of a search that ran out of states without
+// pruning any successor that might have led to one, see `SearchOutcome::into_unreachable_set`.
+// The proof only holds for the goal and movement rules of that search, which the caller names
+// with `goal_tag`, and `SearchOptions::with_known_unreachable` refuses the set for another tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreachabilitySet<K>
+where
+    K: Eq + Hash
+{
+    keys: HashSet<K>,
+    goal_tag: String
+}
+
+impl<K> UnreachabilitySet<K>
+where
+    K: Eq + Hash
+{
+    pub(crate) fn new(keys: HashSet<K>, goal_tag: String) -> Self {
+        UnreachabilitySet { keys, goal_tag }
+    }
+
+    pub fn goal_tag(&self) -> &str {
+        &self.goal_tag
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.keys.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // Adds the keys of another set proven for the same goal. Returns the other set back if its
+    // tag differs.
+    pub fn merge(&mut self, other: UnreachabilitySet<K>) -> Result<(), UnreachabilitySet<K>> {
+        if other.goal_tag != self.goal_tag {
+            return Err(other);
+        }
+
+        self.keys.extend(other.keys);
+        Ok(())
+    }
+}
+
+// A set offered for a goal other than the one it was proven for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoalTagMismatch {
+    pub proven_for: String,
+    pub offered_for: String
+}
+
+impl Display for GoalTagMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unreachability set proven for goal {:?} offered for goal {:?}", self.proven_for, self.offered_for)
+    }
+}
+
+impl std::error::Error for GoalTagMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_to_one_goal() {
+        let mut set = UnreachabilitySet::new(HashSet::from([1, 2]), "a".to_string());
+        set.merge(UnreachabilitySet::new(HashSet::from([2, 3]), "a".to_string())).unwrap();
+        assert_eq!(set.len(), 3);
+
+        let other = UnreachabilitySet::new(HashSet::from([4]), "b".to_string());
+        assert_eq!(set.merge(other.clone()), Err(other));
+        assert!(!set.contains(&4));
+    }
+}
@@ -0,0 +1,49 @@
+use std::{collections::HashSet, hash::Hash};
+
+use crate::{open_list::OpenList, prioritized::Prioritized, untraced::{result::UntracedResult, state::UntracedState}};
+
+// untraced_astar with the open list ordered by `priority` instead of f, lowest first. Closed keys
+// are never reopened, so only priorities that grow along paths like g give optimal results.
+pub fn best_first_search<S, K>(initial_state: S, priority: impl Fn(&S) -> usize) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    let mut open_list = OpenList::new();
+    let mut closed_list = HashSet::new();
+
+    let initial_priority = priority(&initial_state);
+    open_list.insert(initial_state.key(), Prioritized::new(initial_state, initial_priority));
+
+    while let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() {
+        if current_state.is_goal() {
+            let iterations = closed_list.len();
+            return Some(UntracedResult::new(iterations, current_state));
+        }
+
+        closed_list.insert(current_state.key());
+
+        for successor in current_state.generate_successors() {
+            let successor_key = successor.key();
+
+            if closed_list.contains(&successor_key) {
+                continue;
+            }
+
+            let successor_priority = priority(&successor);
+            open_list.insert(successor_key, Prioritized::new(successor, successor_priority));
+        }
+    }
+
+    None
+}
+
+// Greedy best-first search: the open list is ordered by h alone, so it heads for the goal without
+// looking at g. Usually far fewer expansions than A*, with no bound on the cost of the result.
+pub fn greedy_best_first<S, K>(initial_state: S) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    best_first_search(initial_state, S::h)
+}
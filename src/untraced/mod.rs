@@ -1,3 +1,7 @@
 pub mod state;
 pub mod untraced_astar;
+pub mod uniform_cost;
+pub mod multi_target;
+pub mod best_first;
 pub mod result;
+pub mod reachable_set;
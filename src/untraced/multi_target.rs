@@ -0,0 +1,74 @@
+use std::{collections::{HashMap, HashSet}, hash::Hash};
+
+use crate::{open_list::OpenList, prioritized::Prioritized, untraced::state::UntracedState};
+
+// Optimal costs from the initial state to each of the target keys, by one uniform cost search in
+// place of a search per target. A target's cost is its g when it is closed, and the search stops
+// once every target is closed or the states run out. Targets it never reaches are left out of the
+// map. is_goal() and h() are ignored.
+pub fn multi_target_search<S, K>(initial_state: S, targets: &[K]) -> HashMap<K, usize>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    let mut remaining: HashSet<&K> = targets.iter().collect();
+    let mut costs = HashMap::new();
+    let mut open_list = OpenList::new();
+    let mut closed_list = HashSet::new();
+
+    let initial_g = initial_state.g();
+    open_list.insert(initial_state.key(), Prioritized::new(initial_state, initial_g));
+
+    while !remaining.is_empty() {
+        let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() else {
+            break;
+        };
+
+        let key = current_state.key();
+        if remaining.remove(&key) {
+            costs.insert(key.clone(), current_state.g());
+        }
+        closed_list.insert(key);
+
+        for successor in current_state.generate_successors() {
+            let successor_key = successor.key();
+
+            if closed_list.contains(&successor_key) {
+                continue;
+            }
+
+            let successor_g = successor.g();
+            open_list.insert(successor_key, Prioritized::new(successor, successor_g));
+        }
+    }
+
+    costs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, EIGHT_NODES, EIGHT_NODES_H, ZERO_H}, untraced::untraced_astar::untraced_astar};
+
+    #[test]
+    fn test_costs_match_a_search_per_target() {
+        let targets: Vec<usize> = (0..8).collect();
+        let costs = multi_target_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), &targets);
+
+        assert_eq!(costs.len(), 8);
+        for target in targets {
+            // EIGHT_NODES_H estimates the distance to 7, so it would mislead the searches to the
+            // others.
+            let single = untraced_astar(GraphState::new(EIGHT_NODES, ZERO_H, 0, target)).unwrap();
+            assert_eq!(costs[&target], single.final_state.g(), "cost to {}", target);
+        }
+    }
+
+    #[test]
+    fn test_unreached_targets_are_absent() {
+        // Nothing leaves 7, and 9 is no node at all.
+        let costs = multi_target_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 7, 0), &[7, 0, 9]);
+        assert_eq!(costs, HashMap::from([(7, 0)]));
+        assert!(multi_target_search(GraphState::new(EIGHT_NODES, EIGHT_NODES_H, 0, 7), &[]).is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{open_list::OpenList, prioritized::Prioritized, untraced::state::UntracedState};
+
+// Uniform-cost expansion from the initial state, returning every key reachable with cost <= bound
+// together with its optimal g. `is_goal` is never consulted.
+pub fn reachable_set<S, K>(initial_state: S, bound: usize) -> HashMap<K, usize>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    let mut open_list = OpenList::new();
+    let mut reached = HashMap::new();
+
+    if initial_state.g() > bound {
+        return reached;
+    }
+
+    open_list.insert(initial_state.key(), Prioritized::by_g(initial_state));
+
+    while let Some(current) = open_list.extract_min() {
+        let current_state = current.state;
+        reached.insert(current_state.key(), current_state.g());
+
+        let successors = current_state.generate_successors();
+        for successor in successors {
+            if successor.g() > bound {
+                continue;
+            }
+
+            let successor_key = successor.key();
+
+            if reached.contains_key(&successor_key) {
+                continue;
+            }
+
+            open_list.insert(successor_key, Prioritized::by_g(successor));
+        }
+    }
+
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{GraphState, EIGHT_NODES, ZERO_H};
+
+    #[test]
+    fn test_bounded_reachability_on_weighted_graph() {
+        let reached = reachable_set(GraphState::new(EIGHT_NODES, ZERO_H, 0, 7), 4);
+
+        let expected: HashMap<usize, usize> = [(0, 0), (1, 2), (2, 3), (3, 1)].into_iter().collect();
+        assert_eq!(reached, expected);
+    }
+
+    #[test]
+    fn test_unbounded_reaches_everything() {
+        let reached = reachable_set(GraphState::new(EIGHT_NODES, ZERO_H, 0, 7), usize::MAX);
+
+        assert_eq!(reached.len(), 8);
+        assert_eq!(reached[&7], 9);
+        assert_eq!(reached[&6], 8);
+    }
+}
@@ -1,4 +1,4 @@
-use std::hash::Hash;
+use std::{hash::Hash, rc::Rc, sync::Arc};
 
 use crate::astar_state::AStarState;
 
@@ -8,3 +8,38 @@ where
 {
     fn generate_successors(&self) -> Vec<Self>;
 }
+
+// Owning pointers wrap every successor in a new allocation of their own kind, which mostly pays
+// off with Rc and Arc, where the searches then clone pointers instead of states. References can't
+// be forwarded like this. A state owned by an arena can implement UntracedState for `&'a State`
+// itself, returning references to successors it placed in the arena.
+
+impl<K, T> UntracedState<K> for Box<T>
+where
+    K: Clone + Eq + Hash,
+    T: UntracedState<K>
+{
+    fn generate_successors(&self) -> Vec<Self> {
+        (**self).generate_successors().into_iter().map(Box::new).collect()
+    }
+}
+
+impl<K, T> UntracedState<K> for Rc<T>
+where
+    K: Clone + Eq + Hash,
+    T: UntracedState<K>
+{
+    fn generate_successors(&self) -> Vec<Self> {
+        (**self).generate_successors().into_iter().map(Rc::new).collect()
+    }
+}
+
+impl<K, T> UntracedState<K> for Arc<T>
+where
+    K: Clone + Eq + Hash,
+    T: UntracedState<K>
+{
+    fn generate_successors(&self) -> Vec<Self> {
+        (**self).generate_successors().into_iter().map(Arc::new).collect()
+    }
+}
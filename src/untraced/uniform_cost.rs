@@ -0,0 +1,32 @@
+use std::hash::Hash;
+
+use crate::untraced::{best_first::best_first_search, result::UntracedResult, state::UntracedState};
+
+// Dijkstra's algorithm: untraced_astar with the open list ordered by g alone, for domains without
+// an admissible heuristic. h() is never called, so it can return anything.
+pub fn uniform_cost_search<S, K>(initial_state: S) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    best_first_search(initial_state, S::g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, test_support::{GraphState, EIGHT_NODES}, untraced::untraced_astar::untraced_astar};
+
+    // Inadmissible: cheap estimates at 2 and 5 lure A* onto 0 -> 2 -> 5 -> 7, which costs 11.
+    const MISLEADING_H: &[usize] = &[0, 50, 0, 50, 50, 0, 50, 0];
+
+    #[test]
+    fn test_misleading_h_is_ignored() {
+        let misled = untraced_astar(GraphState::new(EIGHT_NODES, MISLEADING_H, 0, 7)).unwrap();
+        assert_eq!(misled.final_state.g(), 11);
+
+        let result = uniform_cost_search(GraphState::new(EIGHT_NODES, MISLEADING_H, 0, 7)).unwrap();
+        assert_eq!(result.final_state.g(), 9);
+        assert!(result.final_state.is_goal());
+    }
+}
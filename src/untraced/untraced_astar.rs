@@ -1,15 +1,99 @@
 use std::{collections::HashSet, hash::Hash};
 
-use crate::{open_list::OpenList, untraced::{result::UntracedResult, state::UntracedState}};
+use crate::{btree_open_list::BTreeOpenList, closed_store::{BTreeClosedStore, ClosedStore, HashClosedStore, MapImpl}, hashed::HashedSet, open_list::OpenList, prioritized::Prioritized, priority_queue::PriorityQueue, untraced::{result::UntracedResult, state::UntracedState}, weight_schedule};
 
+// untraced_astar_with_queue on an OpenList, with the closed set keyed by the open list's hashes so
+// each generated key is hashed once.
 pub fn untraced_astar<S, K>(initial_state: S) -> Option<UntracedResult<S, K>>
 where
     S: UntracedState<K>,
     K: Clone + Eq + Hash
 {
+    let mut open_list = OpenList::new();
+    let mut closed_list = HashedSet::default();
+
+    let initial_key = open_list.hashed(initial_state.key());
+    open_list.insert_hashed(initial_key, initial_state);
+
+    while let Some((current_key, current_state)) = open_list.extract_min_hashed() {
+        if current_state.is_goal() {
+            let iterations = closed_list.len();
+            return Some(UntracedResult::new(iterations, current_state));
+        }
+
+        closed_list.insert(current_key);
+
+        for successor in current_state.generate_successors() {
+            let successor_key = open_list.hashed(successor.key());
+
+            if closed_list.contains(&successor_key) {
+                continue;
+            }
+
+            open_list.insert_hashed(successor_key, successor);
+        }
+    }
+
+    None
+}
+
+// untraced_astar ordering the open list by g + weight * h. Weights below 1 count as 1, which is
+// the plain search.
+pub fn untraced_astar_weighted<S, K>(initial_state: S, weight: f64) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash
+{
+    let (weight, _) = weight_schedule::clamp(weight);
+    let weighted = |state: S| {
+        let priority = weight_schedule::inflated_f(state.f(), state.h(), weight);
+        Prioritized::new(state, priority)
+    };
+
     let mut open_list = OpenList::new();
     let mut closed_list = HashSet::new();
 
+    open_list.insert(initial_state.key(), weighted(initial_state));
+
+    while let Some(Prioritized { state: current_state, .. }) = open_list.extract_min() {
+        if current_state.is_goal() {
+            let iterations = closed_list.len();
+            return Some(UntracedResult::new(iterations, current_state));
+        }
+
+        closed_list.insert(current_state.key());
+
+        for successor in current_state.generate_successors() {
+            let successor_key = successor.key();
+
+            if closed_list.contains(&successor_key) {
+                continue;
+            }
+
+            open_list.insert(successor_key, weighted(successor));
+        }
+    }
+
+    None
+}
+
+pub fn untraced_astar_with_queue<S, K, Q>(initial_state: S, open_list: Q) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash,
+    Q: PriorityQueue<K, S>
+{
+    untraced_astar_with_stores(initial_state, open_list, HashClosedStore::new())
+}
+
+// untraced_astar_with_queue with the closed set in `closed_list` too, keys mapped to nothing.
+pub fn untraced_astar_with_stores<S, K, Q, CS>(initial_state: S, mut open_list: Q, mut closed_list: CS) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Eq + Hash,
+    Q: PriorityQueue<K, S>,
+    CS: ClosedStore<K, ()>
+{
     open_list.insert(initial_state.key(), initial_state);
 
     while let Some(current_state) = open_list.extract_min() {
@@ -25,13 +109,13 @@ where
             );
         }
 
-        closed_list.insert(current_state.key());
+        closed_list.insert(current_state.key(), ());
 
         let successors = current_state.generate_successors();
         for successor in successors {
             let successor_key = successor.key();
 
-            if closed_list.contains(&successor_key) {
+            if closed_list.contains_key(&successor_key) {
                 continue;
             }
 
@@ -41,3 +125,15 @@ where
 
     None
 }
+
+// untraced_astar over the open and closed lists of `map_impl`, see traced_astar_with_map_impl.
+pub fn untraced_astar_with_map_impl<S, K>(initial_state: S, map_impl: MapImpl) -> Option<UntracedResult<S, K>>
+where
+    S: UntracedState<K>,
+    K: Clone + Ord + Hash
+{
+    match map_impl {
+        MapImpl::Hash => untraced_astar(initial_state),
+        MapImpl::BTree => untraced_astar_with_stores(initial_state, BTreeOpenList::new(), BTreeClosedStore::new())
+    }
+}
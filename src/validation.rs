@@ -0,0 +1,220 @@
+use std::fmt::{Debug, Display};
+
+// How much traced_astar_with_options checks the g and h user states report, see
+// SearchOptions::with_validation. g and h are unsigned, so a negative or NaN value in the user's
+// own arithmetic shows up here as a g below the parent's or an f below g.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationLevel {
+    // Nothing is checked.
+    #[default]
+    Off,
+    // Anomalies are added to the stats' warnings and the search goes on.
+    Warn,
+    // The first anomaly stops the search, in SearchOutcome::validation_error.
+    Strict
+}
+
+// Costs of an edge that no well-behaved state reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Anomaly<K> {
+    // The child has a lower g than its parent.
+    DecreasingG { parent: K, child: K, parent_g: usize, child_g: usize },
+    // h drops by more than the edge cost, with a heuristic declared consistent.
+    InconsistentH { parent: K, child: K, parent_h: usize, child_h: usize, cost: usize },
+    // The child's f is below its g.
+    FBelowG { parent: K, child: K, f: usize, g: usize },
+    // The forced successor is not among the states the generator gives
+    // (`SearchOptions::with_forced_check`).
+    ForcedNotGenerated { parent: K, child: K },
+    // The forced successor has a higher f than another successor that isn't closed.
+    ForcedNotBest { parent: K, child: K, f: usize, best_f: usize }
+}
+
+impl<K> Anomaly<K> {
+    // The parent and the child of the edge.
+    pub fn keys(&self) -> [&K; 2] {
+        match self {
+            Anomaly::DecreasingG { parent, child, .. }
+            | Anomaly::InconsistentH { parent, child, .. }
+            | Anomaly::FBelowG { parent, child, .. }
+            | Anomaly::ForcedNotGenerated { parent, child }
+            | Anomaly::ForcedNotBest { parent, child, .. } => [parent, child]
+        }
+    }
+
+    // The message of Display, with the keys written by `key`.
+    pub(crate) fn write_with(&self, f: &mut std::fmt::Formatter<'_>, key: impl Fn(&K) -> String) -> std::fmt::Result {
+        match self {
+            Anomaly::DecreasingG { parent, child, parent_g, child_g } => {
+                write!(f, "g drops from {} at {} to {} at its successor {}", parent_g, key(parent), child_g, key(child))
+            }
+            Anomaly::InconsistentH { parent, child, parent_h, child_h, cost } => {
+                write!(f, "h drops from {} at {} to {} at its successor {}, more than the edge cost {}", parent_h, key(parent), child_h, key(child), cost)
+            }
+            Anomaly::FBelowG { parent, child, f: child_f, g } => {
+                write!(f, "f {} is below g {} at {}, successor of {}", child_f, g, key(child), key(parent))
+            }
+            Anomaly::ForcedNotGenerated { parent, child } => {
+                write!(f, "forced successor {} of {} is not among its generated successors", key(child), key(parent))
+            }
+            Anomaly::ForcedNotBest { parent, child, f: child_f, best_f } => {
+                write!(f, "forced successor {} of {} has f {}, above the best f {} of its other successors", key(child), key(parent), child_f, best_f)
+            }
+        }
+    }
+}
+
+impl<K> Display for Anomaly<K>
+where
+    K: Debug
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_with(f, |key| format!("{key:?}"))
+    }
+}
+
+impl<K> std::error::Error for Anomaly<K>
+where
+    K: Debug
+{}
+
+// g and h of an edge's ends as the search sees them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EdgeCosts {
+    pub parent_g: usize,
+    pub parent_h: usize,
+    pub child_g: usize,
+    pub child_h: usize,
+    pub child_f: usize
+}
+
+// The first anomaly of the edge from `parent` to `child`, if any. Keys are only cloned for it.
+pub(crate) fn check<K: Clone>(parent: &K, child: &K, costs: EdgeCosts, consistent: bool) -> Option<Anomaly<K>> {
+    let EdgeCosts { parent_g, parent_h, child_g, child_h, child_f } = costs;
+
+    if child_g < parent_g {
+        return Some(Anomaly::DecreasingG { parent: parent.clone(), child: child.clone(), parent_g, child_g });
+    }
+    if child_f < child_g {
+        return Some(Anomaly::FBelowG { parent: parent.clone(), child: child.clone(), f: child_f, g: child_g });
+    }
+
+    let cost = child_g - parent_g;
+    if consistent && parent_h > child_h.saturating_add(cost) {
+        return Some(Anomaly::InconsistentH { parent: parent.clone(), child: child.clone(), parent_h, child_h, cost });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{astar_state::AStarState, recording::Recording, search_options::SearchOptions, search_stats::SearchWarning, traced::{state::TracedState, traced_astar::traced_astar_with_options}};
+
+    const LENGTH: usize = 10;
+
+    // Walk along a line up to LENGTH, with g, h and f of each position given by `costs`.
+    #[derive(Clone)]
+    struct Walk {
+        position: usize,
+        costs: fn(usize) -> (usize, usize, usize)
+    }
+
+    impl AStarState<usize> for Walk {
+        fn key(&self) -> usize {
+            self.position
+        }
+
+        fn h(&self) -> usize {
+            (self.costs)(self.position).1
+        }
+
+        fn f(&self) -> usize {
+            (self.costs)(self.position).2
+        }
+
+        fn g(&self) -> usize {
+            (self.costs)(self.position).0
+        }
+
+        fn is_goal(&self) -> bool {
+            self.position == LENGTH
+        }
+    }
+
+    impl TracedState<usize, usize> for Walk {
+        fn generate_traced_successors(&self) -> Vec<(Self, usize)> {
+            vec![(Walk { position: self.position + 1, costs: self.costs }, self.position + 1)]
+        }
+    }
+
+    fn decreasing_g(position: usize) -> (usize, usize, usize) {
+        let g = if position == 4 { 2 } else { position };
+        (g, 0, g)
+    }
+
+    // f one below g at 6, as an f computed with a wrapped around h would be.
+    fn f_below_g(position: usize) -> (usize, usize, usize) {
+        (position, 0, if position == 6 { position - 1 } else { position })
+    }
+
+    // h = 5 at 2 and 0 elsewhere: admissible, but it drops by 5 over an edge of cost 1.
+    fn inconsistent_h(position: usize) -> (usize, usize, usize) {
+        let h = if position == 2 { 5 } else { 0 };
+        (position, h, position + h)
+    }
+
+    fn walk(costs: fn(usize) -> (usize, usize, usize), options: SearchOptions<'_, Walk, usize, usize>) -> (bool, Vec<Anomaly<usize>>, Option<Anomaly<usize>>) {
+        let outcome = traced_astar_with_options(Walk { position: 0, costs }, options);
+        let warnings = outcome.stats.warnings.into_iter()
+            .filter_map(|warning| match warning {
+                SearchWarning::Anomaly(anomaly) => Some(anomaly),
+                _ => None
+            })
+            .collect();
+        (outcome.result.is_some(), warnings, outcome.validation_error)
+    }
+
+    #[test]
+    fn test_decreasing_g() {
+        let anomaly = Anomaly::DecreasingG { parent: 3, child: 4, parent_g: 3, child_g: 2 };
+
+        // The path through the drop is found, but its step costs no longer add up to its cost, so
+        // the cost verification turns it down.
+        assert_eq!(walk(decreasing_g, SearchOptions::new().with_cost_verification(true)), (false, vec![], None));
+        assert_eq!(walk(decreasing_g, SearchOptions::new().with_validation(ValidationLevel::Warn).with_cost_verification(true)), (false, vec![anomaly.clone()], None));
+        assert_eq!(walk(decreasing_g, SearchOptions::new().with_validation(ValidationLevel::Strict)), (false, vec![], Some(anomaly.clone())));
+        assert_eq!(anomaly.to_string(), "g drops from 3 at 3 to 2 at its successor 4");
+    }
+
+    #[test]
+    fn test_f_below_g() {
+        let anomaly = Anomaly::FBelowG { parent: 5, child: 6, f: 5, g: 6 };
+
+        assert_eq!(walk(f_below_g, SearchOptions::new()), (true, vec![], None));
+        assert_eq!(walk(f_below_g, SearchOptions::new().with_validation(ValidationLevel::Warn)), (true, vec![anomaly.clone()], None));
+        assert_eq!(walk(f_below_g, SearchOptions::new().with_validation(ValidationLevel::Strict)), (false, vec![], Some(anomaly)));
+    }
+
+    #[test]
+    fn test_inconsistent_h_only_when_declared_consistent() {
+        let anomaly = Anomaly::InconsistentH { parent: 2, child: 3, parent_h: 5, child_h: 0, cost: 1 };
+
+        assert_eq!(walk(inconsistent_h, SearchOptions::new().with_consistent_heuristic(true)), (true, vec![], None));
+        assert_eq!(walk(inconsistent_h, SearchOptions::new().with_validation(ValidationLevel::Strict)), (true, vec![], None));
+        assert_eq!(walk(inconsistent_h, SearchOptions::new().with_validation(ValidationLevel::Warn).with_consistent_heuristic(true)), (true, vec![anomaly.clone()], None));
+        assert_eq!(walk(inconsistent_h, SearchOptions::new().with_validation(ValidationLevel::Strict).with_consistent_heuristic(true)), (false, vec![], Some(anomaly)));
+    }
+
+    #[test]
+    fn test_recording_replays_the_warnings() {
+        let mut recording = Recording::new();
+        let outcome = traced_astar_with_options(Walk { position: 0, costs: decreasing_g }, SearchOptions::new().with_validation(ValidationLevel::Warn).with_recording(&mut recording));
+
+        assert_eq!(recording.simulate().stats, outcome.stats);
+    }
+}
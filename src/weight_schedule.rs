@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+// What a weight schedule is given when it is consulted, see `SearchOptions::with_weight_schedule`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduleContext {
+    pub expansions: usize,
+    pub elapsed: Duration,
+    // f of the state at the top of the open list, None when it is empty. It is the minimum f of the
+    // open list only while the weight is 1.
+    pub min_f: Option<usize>,
+    pub weight: f64
+}
+
+// A weight set by the schedule, in `SearchStats::weight_changes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightChange {
+    // Expansions done when the weight was set, 0 for the initial weight.
+    pub expansion: usize,
+    pub weight: f64,
+    // The schedule asked for a weight below 1 (or NaN), which was raised to 1. Recorded even when
+    // the weight was already 1.
+    pub clamped: bool
+}
+
+// Weights are never NaN, see clamp.
+impl Eq for WeightChange {}
+
+// Weights below 1 would make h count less than g, which no search here is meant for.
+pub(crate) fn clamp(requested: f64) -> (f64, bool) {
+    if requested >= 1.0 {
+        (requested, false)
+    }
+    else {
+        (1.0, true)
+    }
+}
+
+// g + weight * h, rounded down.
+pub(crate) fn inflated_f(f: usize, h: usize, weight: f64) -> usize {
+    if weight == 1.0 {
+        return f;
+    }
+
+    let inflated_h = (h as f64 * weight) as usize;
+    f.saturating_sub(h).saturating_add(inflated_h)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{astar_state::AStarState, recording::Recording, search_options::SearchOptions, search_stats::SearchWarning, traced::{outcome::SearchOutcome, state::TracedState, traced_astar::{traced_astar, traced_astar_with_options}}};
+
+    #[test]
+    fn test_clamp_and_inflate() {
+        assert_eq!(clamp(2.5), (2.5, false));
+        assert_eq!(clamp(1.0), (1.0, false));
+        assert_eq!(clamp(0.3), (1.0, true));
+        assert_eq!(clamp(f64::NAN), (1.0, true));
+
+        assert_eq!(inflated_f(10, 4, 1.0), 10);
+        assert_eq!(inflated_f(10, 4, 2.5), 16);
+        assert_eq!(inflated_f(10, 4, f64::INFINITY), usize::MAX);
+        // A weight this close to 1 is 1 in single precision.
+        assert_eq!(inflated_f(100_000_000, 100_000_000, 1.00000002), 100_000_002);
+    }
+
+    // 100 x 100 open field with a pocket open towards the start that the heuristic pulls into:
+    // a wall at x = 60 from y = 10 to 90 with arms along y = 10 and y = 90 back to x = 20.
+    #[derive(Clone)]
+    struct Field {
+        position: (usize, usize),
+        g: usize
+    }
+
+    const SIZE: usize = 100;
+    const GOAL: (usize, usize) = (99, 50);
+
+    fn is_wall((x, y): (usize, usize)) -> bool {
+        (x == 60 && (10..=90).contains(&y)) || ((y == 10 || y == 90) && (20..=60).contains(&x))
+    }
+
+    impl AStarState<(usize, usize)> for Field {
+        fn key(&self) -> (usize, usize) {
+            self.position
+        }
+
+        fn h(&self) -> usize {
+            self.position.0.abs_diff(GOAL.0) + self.position.1.abs_diff(GOAL.1)
+        }
+
+        fn f(&self) -> usize {
+            self.g + self.h()
+        }
+
+        fn g(&self) -> usize {
+            self.g
+        }
+
+        fn is_goal(&self) -> bool {
+            self.position == GOAL
+        }
+    }
+
+    impl TracedState<(usize, usize), (usize, usize)> for Field {
+        fn generate_traced_successors(&self) -> Vec<(Self, (usize, usize))> {
+            let (x, y) = self.position;
+            [(x + 1, y), (x.wrapping_sub(1), y), (x, y + 1), (x, y.wrapping_sub(1))].into_iter()
+                .filter(|&(x, y)| x < SIZE && y < SIZE && !is_wall((x, y)))
+                .map(|position| (Field { position, g: self.g + 1 }, position))
+                .collect()
+        }
+    }
+
+    const START: Field = Field { position: (0, 50), g: 0 };
+
+    type Cell = (usize, usize);
+
+    // Expanded keys in order, from the recording.
+    fn traced(every: usize, schedule: impl FnMut(&ScheduleContext) -> f64) -> (SearchOutcome<Field, Cell, Cell>, Vec<Cell>) {
+        let mut recording = Recording::new();
+        let options = SearchOptions::new()
+            .with_reopening(true)
+            .with_weight_schedule(every, schedule)
+            .with_recording(&mut recording);
+        let outcome = traced_astar_with_options(START, options);
+        let simulation = recording.simulate();
+        let trace = simulation.trace.iter().map(|&node| simulation.node(node).key).collect();
+        (outcome, trace)
+    }
+
+    #[test]
+    fn test_schedule_ending_at_one_is_optimal() {
+        let optimal = traced_astar(START).unwrap().final_state.g();
+        let (inflated, inflated_trace) = traced(1000, |_| 5.0);
+        let (plain, plain_trace) = traced(1000, |_| 1.0);
+        assert!(inflated.stats.expansions > 1000);
+        assert!(plain.stats.weight_changes.is_empty());
+
+        let (scheduled, trace) = traced(100, |context| if context.expansions < 1000 { 5.0 } else { 1.0 });
+        assert_eq!(scheduled.result.unwrap().final_state.g(), optimal);
+        assert_eq!(scheduled.stats.weight_changes, vec![
+            WeightChange { expansion: 0, weight: 5.0, clamped: false },
+            WeightChange { expansion: 1000, weight: 1.0, clamped: false }
+        ]);
+
+        // Up to the switch the search expanded what weighted A* does, not what A* does.
+        assert_eq!(trace[..1000], inflated_trace[..1000]);
+        assert_ne!(trace[..1000], plain_trace[..1000]);
+    }
+
+    #[test]
+    fn test_weights_below_one_are_clamped() {
+        let contexts = RefCell::new(Vec::new());
+        let (outcome, _) = traced(50, |context| {
+            contexts.borrow_mut().push(*context);
+            if context.expansions == 0 { 2.0 } else { 0.5 }
+        });
+
+        // Every consultation after the first asks for 0.5 and is flagged.
+        let changes = outcome.stats.weight_changes;
+        assert_eq!(changes[..2], [
+            WeightChange { expansion: 0, weight: 2.0, clamped: false },
+            WeightChange { expansion: 50, weight: 1.0, clamped: true }
+        ]);
+        assert_eq!(changes.len(), outcome.stats.expansions / 50 + 1);
+        assert!(changes[1..].iter().all(|change| change.clamped && change.weight == 1.0));
+        let warnings: Vec<_> = changes[1..].iter().map(|change| SearchWarning::WeightClamped { expansion: change.expansion }).collect();
+        assert_eq!(outcome.stats.warnings, warnings);
+
+        let contexts = contexts.into_inner();
+        assert_eq!((contexts[0].expansions, contexts[0].min_f, contexts[0].weight), (0, None, 1.0));
+        assert_eq!((contexts[1].expansions, contexts[1].weight), (50, 2.0));
+        assert!(contexts[1].min_f.is_some());
+    }
+}
@@ -0,0 +1,201 @@
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell};
+
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, grid::{map::GridMap, region::GoalRegion}, open_list::OpenList, traced::traced_astar::traced_astar};
+
+// Counts the allocator calls of the current thread, so tests running side by side don't see each
+// other's.
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTS: Cell<AllocStats> = const { Cell::new(AllocStats { allocations: 0, reallocations: 0, deallocations: 0, bytes: 0 }) };
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AllocStats {
+    allocations: usize,
+    reallocations: usize,
+    deallocations: usize,
+    // Bytes asked for by allocations and by the growth of reallocations.
+    bytes: usize
+}
+
+fn count(update: impl FnOnce(&mut AllocStats)) {
+    // Gone while the thread shuts down, when nothing is measured anyway.
+    let _ = COUNTS.try_with(|counts| {
+        let mut stats = counts.get();
+        update(&mut stats);
+        counts.set(stats);
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(|stats| {
+            stats.allocations += 1;
+            stats.bytes += layout.size();
+        });
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(|stats| {
+            stats.allocations += 1;
+            stats.bytes += layout.size();
+        });
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(|stats| {
+            stats.reallocations += 1;
+            stats.bytes += new_size.saturating_sub(layout.size());
+        });
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(|stats| stats.deallocations += 1);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+// Allocator calls made by `f` on this thread.
+fn measure_allocations(f: impl FnOnce()) -> AllocStats {
+    let before = COUNTS.with(Cell::get);
+    f();
+    let after = COUNTS.with(Cell::get);
+
+    AllocStats {
+        allocations: after.allocations - before.allocations,
+        reallocations: after.reallocations - before.reallocations,
+        deallocations: after.deallocations - before.deallocations,
+        bytes: after.bytes - before.bytes
+    }
+}
+
+// A 64x64 maze carved by a depth-first walk over the cells of even coordinates, every wall
+// between them blocked until the walk opens it. The last row and column stay blocked.
+fn maze_64() -> GridMap {
+    const CELLS: usize = 32;
+    let mut map = GridMap::new(64, 64);
+    for y in 0..64 {
+        for x in 0..64 {
+            map.set_blocked(x, y, x % 2 == 1 || y % 2 == 1);
+        }
+    }
+
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut visited = vec![false; CELLS * CELLS];
+    let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+    visited[0] = true;
+    while let Some(&(x, y)) = stack.last() {
+        let neighbors: Vec<(usize, usize)> = [(x + 1, y), (x, y + 1), (x.wrapping_sub(1), y), (x, y.wrapping_sub(1))]
+            .into_iter()
+            .filter(|&(nx, ny)| nx < CELLS && ny < CELLS && !visited[ny * CELLS + nx])
+            .collect();
+        if neighbors.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let (nx, ny) = neighbors[(seed % neighbors.len() as u64) as usize];
+        map.set_blocked(x + nx, y + ny, false);
+        visited[ny * CELLS + nx] = true;
+        stack.push((nx, ny));
+    }
+
+    map
+}
+
+#[derive(Clone)]
+struct Entry {
+    id: u32,
+    f: usize
+}
+
+impl AStarState<u32> for Entry {
+    fn key(&self) -> u32 {
+        self.id
+    }
+
+    fn h(&self) -> usize {
+        0
+    }
+
+    fn f(&self) -> usize {
+        self.f
+    }
+
+    fn g(&self) -> usize {
+        self.f
+    }
+
+    fn is_goal(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_harness_counts_this_thread() {
+    let stats = measure_allocations(|| {
+        let mut buffer = Vec::<u8>::with_capacity(100);
+        buffer.reserve(200);
+    });
+    assert_eq!(stats, AllocStats { allocations: 1, reallocations: 1, deallocations: 1, bytes: 200 });
+
+    assert_eq!(measure_allocations(|| {}).allocations, 0);
+}
+
+// Each expansion clones the board of every successor and the keys going into both lists.
+// Measured at 56 allocations per expansion.
+#[test]
+fn test_traced_astar_on_the_puzzle() {
+    let mut expansions = 0;
+    let stats = measure_allocations(|| expansions = traced_astar(layton_132().0).unwrap().iterations);
+
+    assert_eq!(expansions, 54584);
+    assert!(stats.allocations <= expansions * 60, "{} allocations for {} expansions", stats.allocations, expansions);
+    assert!(stats.reallocations * 4 <= expansions, "{} reallocations for {} expansions", stats.reallocations, expansions);
+    assert_eq!(stats.deallocations, stats.allocations);
+}
+
+// The moves of a cell and the successors each take a vector. Measured at 4 allocations per
+// expansion.
+#[test]
+fn test_grid_astar_on_a_maze() {
+    let map = maze_64();
+    let region = GoalRegion::Cell(62, 62);
+    let mut expansions = 0;
+    let stats = measure_allocations(|| {
+        let result = map.astar_to_region((0, 0), &region).unwrap();
+        assert_eq!(result.cost, 444);
+        expansions = result.iterations;
+    });
+
+    assert!(stats.allocations <= expansions * 5, "{} allocations for {} expansions", stats.allocations, expansions);
+    assert!(stats.reallocations * 20 <= expansions, "{} reallocations for {} expansions", stats.reallocations, expansions);
+}
+
+// Once the heap and its index have grown, cycles that keep the size reuse their buffers.
+#[test]
+fn test_open_list_cycles_reuse_their_buffers() {
+    let mut open_list = OpenList::new();
+    for id in 0..1000u32 {
+        open_list.insert(id, Entry { id, f: id as usize * 7 % 1000 });
+    }
+
+    let stats = measure_allocations(|| {
+        for cycle in 0..10_000 {
+            let entry = open_list.extract_min().unwrap();
+            open_list.insert(entry.id, Entry { id: entry.id, f: entry.f + 1 + cycle % 13 });
+        }
+    });
+    assert_eq!(stats, AllocStats { allocations: 0, reallocations: 0, deallocations: 0, bytes: 0 });
+    assert_eq!(open_list.len(), 1000);
+}
@@ -0,0 +1,20 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, traced::{anytime::AnytimeSearch, replay::replay, traced_astar::traced_astar}};
+
+#[test]
+fn test_anytime_solutions_on_the_puzzle() {
+    let optimal = traced_astar(layton_132().0).unwrap().final_state.g();
+
+    let mut search = AnytimeSearch::new(layton_132().0, 3.0);
+    let solutions: Vec<_> = search.by_ref().collect();
+    assert!(search.is_proven());
+
+    let costs: Vec<usize> = solutions.iter().map(|result| result.cost).collect();
+    assert!(costs.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", costs);
+    assert_eq!(costs.last(), Some(&optimal));
+
+    for result in &solutions {
+        let replayed = replay(&layton_132().0, result.path.to_vec()).unwrap();
+        assert!(replayed.is_goal());
+        assert!(replayed.g() <= result.cost);
+    }
+}
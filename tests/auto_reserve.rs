@@ -0,0 +1,60 @@
+use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell, sync::atomic::{AtomicUsize, Ordering}};
+
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, search_options::SearchOptions, traced::traced_astar::traced_astar_with_options};
+
+// Counts the large allocations made on threads that opted in. Those are the open and closed
+// lists growing: nothing else in the search allocates more than a page at once.
+struct CountingAllocator;
+
+const LARGE: usize = 4096;
+static LARGE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn count(size: usize) {
+    if size >= LARGE && COUNTING.with(Cell::get) {
+        LARGE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Large allocations and solution cost of one search.
+fn search(auto_reserve: bool) -> (usize, usize) {
+    let options = SearchOptions::new().with_auto_reserve(auto_reserve);
+
+    LARGE_ALLOCATIONS.store(0, Ordering::Relaxed);
+    COUNTING.with(|counting| counting.set(true));
+    let outcome = traced_astar_with_options(layton_132().0, options);
+    COUNTING.with(|counting| counting.set(false));
+
+    (LARGE_ALLOCATIONS.load(Ordering::Relaxed), outcome.result.unwrap().final_state.g())
+}
+
+#[test]
+fn test_auto_reserve_saves_regrowths_on_the_layton_puzzle() {
+    let (growing, cost) = search(false);
+    let (reserved, reserved_cost) = search(true);
+
+    assert_eq!(reserved_cost, cost);
+    assert!(reserved < growing, "{} large allocations with auto reserve against {} without", reserved, growing);
+}
@@ -0,0 +1,27 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, traced::{beam::beam_search_outcome, replay::replay, traced_astar::traced_astar}};
+
+#[test]
+fn test_wide_beam_finds_the_optimal_solution() {
+    let optimal = traced_astar(layton_132().0).unwrap().final_state.g();
+
+    // 2048 wide still drops states on the way but keeps the optimal path; 8192 never drops any.
+    for (width, pruned) in [(2048, true), (8192, false)] {
+        let outcome = beam_search_outcome(layton_132().0, width);
+        assert_eq!(outcome.pruned(), pruned);
+
+        let result = outcome.result.unwrap();
+        assert_eq!(result.final_state.g(), optimal);
+        let replayed = replay(&layton_132().0, result.path.to_vec()).unwrap();
+        assert!(replayed.is_goal());
+        assert_eq!(replayed.g(), optimal);
+    }
+}
+
+#[test]
+fn test_narrow_beam_runs_dry() {
+    // Climbing the best successor alone gets stuck after 16 expansions.
+    let outcome = beam_search_outcome(layton_132().0, 1);
+    assert!(outcome.result.is_none());
+    assert!(outcome.pruned());
+    assert_eq!(outcome.expansions, 16);
+}
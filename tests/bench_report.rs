@@ -0,0 +1,44 @@
+use astar_helper::{bench_report::{run, BenchConfig, CSV_HEADER}, fixtures::{maze_large, maze_small, maze::MazeState}, search_options::SearchOptions, traced::{traced_astar::traced_astar_with_options, uniform_cost::uniform_cost_search}};
+
+fn mazes() -> Vec<(&'static str, MazeState)> {
+    vec![("maze_small", maze_small().0), ("maze_large", maze_large().0)]
+}
+
+fn configs<'a>() -> Vec<BenchConfig<'a, MazeState>> {
+    vec![
+        BenchConfig::new("astar", |state| traced_astar_with_options(state, SearchOptions::new()).into()),
+        BenchConfig::new("dijkstra", |state| uniform_cost_search(state).into())
+    ]
+}
+
+#[test]
+fn test_two_by_two_matrix() {
+    let report = run(&mazes(), &configs());
+    assert_eq!((report.instances().len(), report.configs().len(), report.rows().len()), (2, 2, 4));
+    assert_eq!(report.to_markdown().lines().count(), 2 + 4);
+
+    for (instance, optimal_cost) in [("maze_small", maze_small().1), ("maze_large", maze_large().1)] {
+        let astar = report.get(instance, "astar").unwrap();
+        let dijkstra = report.get(instance, "dijkstra").unwrap();
+        assert_eq!((astar.measurement.cost, dijkstra.measurement.cost), (Some(optimal_cost), Some(optimal_cost)));
+        assert!(dijkstra.measurement.expansions >= astar.measurement.expansions);
+        assert!(astar.min <= astar.median);
+        assert!(astar.measurement.peak_open.is_some() && dijkstra.measurement.peak_open.is_none());
+    }
+}
+
+// Scripts read the CSV by column name, so its header is part of the API.
+#[test]
+fn test_csv_schema_is_stable() {
+    assert_eq!(CSV_HEADER, "instance,config,cost,expansions,peak_open,median_ns,min_ns,repetitions");
+
+    let csv = run(&mazes()[..1], &configs()).to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], CSV_HEADER);
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("maze_small,astar,40,"));
+    assert!(lines[2].starts_with("maze_small,dijkstra,40,"));
+    assert!(lines.iter().all(|line| line.split(',').count() == 8));
+    // Dijkstra reports no peak.
+    assert_eq!(lines[2].split(',').nth(4), Some(""));
+}
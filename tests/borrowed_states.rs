@@ -0,0 +1,150 @@
+// States that borrow their board from the caller instead of owning it. Everything here only has to
+// compile and agree with the owned searches: no search, wrapper or option may ask for a 'static S.
+use std::{cell::{Cell, RefCell}, rc::Rc};
+
+use astar_helper::{astar_state::AStarState, plateau::PlateauPolicy, recording::Recording, search_options::SearchOptions, state_pool::StatePool, traced::{beam::beam_search, best_first::greedy_best_first, ida_star::ida_star, replay::replay, state::TracedState, traced_astar::{traced_astar, traced_astar_with_options}}, untraced::{state::UntracedState, untraced_astar::untraced_astar}};
+
+// Walls as '#', start at the top left and goal at the bottom right.
+struct Board {
+    width: usize,
+    cells: Vec<bool>
+}
+
+impl Board {
+    fn parse(rows: &[&str]) -> Board {
+        Board {
+            width: rows[0].len(),
+            cells: rows.iter().flat_map(|row| row.chars().map(|cell| cell == '#')).collect()
+        }
+    }
+
+    fn goal(&self) -> usize {
+        self.cells.len() - 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Step {
+    Left,
+    Right,
+    Up,
+    Down
+}
+
+#[derive(Clone)]
+struct Walker<'a> {
+    board: &'a Board,
+    cell: usize,
+    g: usize
+}
+
+impl<'a> Walker<'a> {
+    fn new(board: &'a Board) -> Self {
+        Walker { board, cell: 0, g: 0 }
+    }
+
+    fn step(&self, step: Step) -> Option<Walker<'a>> {
+        let width = self.board.width;
+        let (x, y) = (self.cell % width, self.cell / width);
+        let cell = match step {
+            Step::Left if x > 0 => self.cell - 1,
+            Step::Right if x + 1 < width => self.cell + 1,
+            Step::Up if y > 0 => self.cell - width,
+            Step::Down if self.cell + width < self.board.cells.len() => self.cell + width,
+            _ => return None
+        };
+        (!self.board.cells[cell]).then(|| Walker { board: self.board, cell, g: self.g + 1 })
+    }
+}
+
+impl AStarState<usize> for Walker<'_> {
+    fn key(&self) -> usize {
+        self.cell
+    }
+
+    fn h(&self) -> usize {
+        let width = self.board.width;
+        let goal = self.board.goal();
+        (self.cell % width).abs_diff(goal % width) + (self.cell / width).abs_diff(goal / width)
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.cell == self.board.goal()
+    }
+}
+
+impl TracedState<usize, Step> for Walker<'_> {
+    fn generate_traced_successors(&self) -> Vec<(Self, Step)> {
+        [Step::Left, Step::Right, Step::Up, Step::Down]
+            .into_iter()
+            .filter_map(|step| self.step(step).map(|walker| (walker, step)))
+            .collect()
+    }
+}
+
+impl UntracedState<usize> for Walker<'_> {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.generate_traced_successors().into_iter().map(|(walker, _)| walker).collect()
+    }
+}
+
+fn board() -> Board {
+    Board::parse(&[
+        ".....#..",
+        ".###.#.#",
+        "...#...#",
+        "##.###..",
+        "........"
+    ])
+}
+
+#[test]
+fn test_searches_over_a_borrowed_board() {
+    let board = board();
+
+    let traced = traced_astar(Walker::new(&board)).unwrap();
+    assert_eq!(traced.final_state.g(), 11);
+    let end = replay(&Walker::new(&board), traced.path.to_vec()).unwrap();
+    assert!(end.is_goal());
+
+    assert_eq!(untraced_astar(Walker::new(&board)).unwrap().final_state.g(), 11);
+    assert_eq!(ida_star(Walker::new(&board)).unwrap().final_state.g(), 11);
+    assert!(greedy_best_first(Walker::new(&board)).unwrap().final_state.is_goal());
+    assert!(beam_search(Walker::new(&board), 4).unwrap().final_state.is_goal());
+}
+
+#[test]
+fn test_options_capturing_the_caller() {
+    let board = board();
+    let forbidden_cell = 2 * board.width + 6;
+    let plateaus = Cell::new(0);
+    let pool = Rc::new(RefCell::new(StatePool::new(16)));
+    let mut recycler = pool.clone();
+    let mut recording = Recording::new();
+
+    let options = SearchOptions::new()
+        .with_forbidden(|&cell: &usize| cell == forbidden_cell)
+        .with_tie_break(|step: &Step| *step as usize)
+        .with_priority_hint(|&cell: &usize| -((cell % board.width) as i32))
+        .with_successor_cache(8, |parent: &Walker, edge| Walker { board: parent.board, cell: edge.child_key, g: parent.g + edge.edge_cost })
+        .with_edge_cost_override(|_: &Walker, _: &Step, cost| cost)
+        .with_plateau_detection(4, PlateauPolicy::Continue)
+        .with_plateau_observer(|_| plateaus.set(plateaus.get() + 1))
+        .with_weight_schedule(usize::MAX, |_| 1.0)
+        .with_recycler(&mut recycler)
+        .with_recording(&mut recording);
+    let outcome = traced_astar_with_options(Walker::new(&board), options);
+
+    let result = outcome.result.unwrap();
+    assert_eq!(result.final_state.g(), 11);
+    assert!(!result.path.to_vec().is_empty());
+    assert_eq!(recording.simulate().stats, outcome.stats);
+}
@@ -0,0 +1,20 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, search_options::SearchOptions, search_stats::{PruneCounts, SkipReason}, traced::{replay::replay, traced_astar::traced_astar_with_options}};
+
+#[test]
+fn test_commutative_pruning_on_the_puzzle() {
+    let plain = traced_astar_with_options(layton_132().0, SearchOptions::new());
+    let pruned = traced_astar_with_options(layton_132().0, SearchOptions::new().with_commutative_pruning());
+
+    let plain_result = plain.result.unwrap();
+    let pruned_result = pruned.result.unwrap();
+    assert_eq!(pruned_result.final_state.g(), plain_result.final_state.g());
+    assert!(replay(&layton_132().0, pruned_result.path.to_vec()).unwrap().is_goal());
+
+    // The closed list already merges the orders of commuting moves, so expansions stay about the
+    // same; what goes away is most of the duplicates it had to catch. Measured 137533 duplicates
+    // without pruning and 85024 with it, plus 54987 successors skipped as commuted.
+    let duplicates = |pruned: &PruneCounts| pruned[SkipReason::Closed] + pruned[SkipReason::WorseOpen];
+    assert!(pruned.stats.pruned[SkipReason::Commuted] > 0);
+    assert!(duplicates(&pruned.stats.pruned) * 3 < duplicates(&plain.stats.pruned) * 2);
+    assert!(pruned.stats.expansions.abs_diff(plain.stats.expansions) * 20 < plain.stats.expansions);
+}
@@ -0,0 +1,34 @@
+use astar_helper::{conformance::{run_all, run_btree_backends, run_depth_first}, fixtures::{self, Instance}};
+
+// Every fixture through every optimal search, apart from the 15-puzzles too deep for Dijkstra.
+#[test]
+fn test_fixtures() {
+    for fixture in fixtures::all() {
+        match fixture.instance {
+            Instance::SlidingBlocks(state) => run_all(vec![state], vec![fixture.optimal_cost]),
+            Instance::Fifteen(state) if fixture.optimal_cost <= 10 => run_all(vec![state], vec![fixture.optimal_cost]),
+            Instance::Fifteen(_) => {}
+            Instance::Maze(state) => run_all(vec![state], vec![fixture.optimal_cost]),
+            Instance::Graph(state) => run_all(vec![state], vec![fixture.optimal_cost])
+        }
+    }
+}
+
+// The same fixtures over the BTree open and closed lists.
+#[test]
+fn test_fixtures_btree_backends() {
+    for fixture in fixtures::all() {
+        match fixture.instance {
+            Instance::SlidingBlocks(state) => run_btree_backends(vec![state], vec![fixture.optimal_cost]),
+            Instance::Fifteen(state) => run_btree_backends(vec![state], vec![fixture.optimal_cost]),
+            Instance::Maze(state) => run_btree_backends(vec![state], vec![fixture.optimal_cost]),
+            Instance::Graph(state) => run_btree_backends(vec![state], vec![fixture.optimal_cost])
+        }
+    }
+}
+
+#[test]
+fn test_fifteen_puzzles_depth_first() {
+    let (instances, optimal_costs) = fixtures::fifteen_puzzles().into_iter().take(3).unzip();
+    run_depth_first(instances, optimal_costs);
+}
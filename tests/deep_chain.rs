@@ -0,0 +1,73 @@
+use astar_helper::{astar_state::AStarState, traced::{dfbb::dfbb, ida_star::ida_star, lds::lds, rbfs::rbfs, state::TracedState}};
+
+// A corridor whose only solution is 100k steps forward. The recursive formulations of the
+// depth-first searches overflowed a test thread's stack long before the goal; the explicit stack
+// only grows the heap.
+const LENGTH: u32 = 100_000;
+
+#[derive(Clone)]
+struct Chain {
+    position: u32,
+    g: usize
+}
+
+impl AStarState<u32> for Chain {
+    fn key(&self) -> u32 {
+        self.position
+    }
+
+    fn h(&self) -> usize {
+        (LENGTH - self.position) as usize
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.position == LENGTH
+    }
+}
+
+impl TracedState<u32, i8> for Chain {
+    fn generate_traced_successors(&self) -> Vec<(Self, i8)> {
+        let mut successors = vec![(Chain { position: self.position + 1, g: self.g + 1 }, 1)];
+        if self.position > 0 {
+            successors.push((Chain { position: self.position - 1, g: self.g + 1 }, -1));
+        }
+        successors
+    }
+}
+
+fn start() -> Chain {
+    Chain { position: 0, g: 0 }
+}
+
+#[test]
+fn test_ida_star_reaches_the_end_of_the_chain() {
+    let result = ida_star(start()).unwrap();
+    assert_eq!((result.path.len(), result.cost), (LENGTH as usize, LENGTH as usize));
+}
+
+#[test]
+fn test_rbfs_reaches_the_end_of_the_chain() {
+    let result = rbfs(start()).unwrap();
+    assert_eq!(result.path.len(), LENGTH as usize);
+    assert!(result.path.iter().all(|step| step == 1));
+}
+
+#[test]
+fn test_dfbb_reaches_the_end_of_the_chain() {
+    let result = dfbb(start(), None).unwrap();
+    assert_eq!(result.cost, LENGTH as usize);
+}
+
+#[test]
+fn test_lds_reaches_the_end_of_the_chain() {
+    let outcome = lds(start(), 0).unwrap();
+    assert_eq!((outcome.result.path.len(), outcome.discrepancies), (LENGTH as usize, 0));
+}
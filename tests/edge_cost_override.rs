@@ -0,0 +1,58 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, grid::state::Direction, puzzles::sliding_block::{BlockMove, SlidingBlocks}, search_options::SearchOptions, traced::traced_astar::traced_astar_with_options};
+
+fn is_left(movement: &BlockMove) -> bool {
+    movement.direction == Direction::Left
+}
+
+fn lefts_of(path: &[BlockMove], piece: u8) -> usize {
+    path.iter().filter(|movement| is_left(movement) && movement.piece == piece).count()
+}
+
+// Moving piece 4 left costs 10 instead of 1, so the cheapest solution goes around it.
+#[test]
+fn test_reweighted_left_moves_change_the_solution() {
+    let plain = traced_astar_with_options(layton_132().0, SearchOptions::new()).result.unwrap();
+    let plain_path = plain.path.to_vec();
+    assert_eq!(plain.cost, 64);
+    assert_eq!(lefts_of(&plain_path, 4), 2);
+
+    let reweighted = traced_astar_with_options(
+        layton_132().0,
+        SearchOptions::new().with_edge_cost_override(|_: &SlidingBlocks, movement: &BlockMove, cost| {
+            if is_left(movement) && movement.piece == 4 { 10 * cost } else { cost }
+        })
+    ).result.unwrap();
+    let path = reweighted.path.to_vec();
+
+    assert_eq!(lefts_of(&path, 4), 0);
+    assert_eq!(path.len(), 70);
+    assert_eq!(reweighted.cost, 70);
+    // The state itself still counts every movement as 1.
+    assert_eq!(reweighted.final_state.g(), 70);
+
+    // Keeping the plain solution would have cost 2 * 9 more.
+    assert!(reweighted.cost < plain.cost + 2 * 9);
+
+    let step_costs = reweighted.step_costs.unwrap();
+    assert_eq!(step_costs.len(), path.len());
+    assert_eq!(step_costs.iter().sum::<usize>(), reweighted.cost);
+}
+
+// Every step of the path carries its overridden cost.
+#[test]
+fn test_override_is_reflected_in_step_costs() {
+    let result = traced_astar_with_options(
+        layton_132().0,
+        SearchOptions::new().with_edge_cost_override(|_: &SlidingBlocks, movement: &BlockMove, cost| {
+            if is_left(movement) { 2 * cost } else { cost }
+        })
+    ).result.unwrap();
+    let path = result.path.to_vec();
+    let step_costs = result.step_costs.unwrap();
+
+    for (movement, cost) in path.iter().zip(&step_costs) {
+        let expected = if is_left(movement) { 2 } else { 1 };
+        assert_eq!(*cost, expected);
+    }
+    assert_eq!(result.cost, path.len() + path.iter().filter(|movement| is_left(movement)).count());
+}
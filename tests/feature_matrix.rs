@@ -0,0 +1,60 @@
+use std::{path::Path, process::Command};
+
+// Features of Cargo.toml, without the `default` and `full` umbrellas.
+fn features() -> Vec<String> {
+    let manifest = std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).unwrap();
+
+    manifest.lines()
+        .skip_while(|line| line.trim() != "[features]")
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, _)| name.trim().to_string())
+        .filter(|name| name != "default" && name != "full")
+        .collect()
+}
+
+// Checks the library with only the given features, warnings denied, and with `all_targets` its
+// tests, examples and benches too. Runs in its own target directory so it doesn't wait on the lock
+// of the cargo running the tests.
+fn check(features: &[&str], all_targets: bool) -> Result<(), String> {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let output = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args(["check", if all_targets { "--all-targets" } else { "--lib" }, "--quiet", "--no-default-features", "--features", &features.join(",")])
+        .env("CARGO_TARGET_DIR", Path::new(manifest_dir).join("target").join("feature-matrix"))
+        .env("RUSTFLAGS", "-D warnings")
+        .output()
+        .map_err(|error| error.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    }
+    else {
+        Err(format!("{:?}{}:\n{}", features, if all_targets { " (all targets)" } else { "" }, String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+// The core alone, every feature alone and everything together must build with their tests, and
+// every pair of features must build the library. Slow, so it only runs on request, which CI does:
+// `cargo test --test feature_matrix -- --ignored`.
+#[test]
+#[ignore]
+fn test_feature_matrix() {
+    let features = features();
+    assert!(features.iter().any(|feature| feature == "grid"));
+
+    let mut combinations: Vec<(Vec<&str>, bool)> = vec![(vec![], true), (vec!["full"], true)];
+    for (index, first) in features.iter().enumerate() {
+        combinations.push((vec![first], true));
+        for second in &features[index + 1..] {
+            combinations.push((vec![first, second], false));
+        }
+    }
+
+    let failures: Vec<String> = combinations.iter()
+        .filter_map(|(combination, all_targets)| check(combination, *all_targets).err())
+        .collect();
+
+    assert!(failures.is_empty(), "{} of {} builds failed\n{}", failures.len(), combinations.len(), failures.join("\n"));
+}
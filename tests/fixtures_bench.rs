@@ -0,0 +1,43 @@
+use std::{hash::Hash, time::{Duration, Instant}};
+
+use astar_helper::{fixtures::{self, Instance}, traced::{state::TracedState, traced_astar::traced_astar}};
+
+const RUNS: usize = 3;
+
+// Fastest of RUNS solves, with the expansions of one of them.
+fn fastest<S, K, C>(instance: &S, optimal_cost: usize) -> (Duration, usize)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash
+{
+    let mut expansions = 0;
+    let elapsed = (0..RUNS)
+        .map(|_| {
+            let started = Instant::now();
+            let result = traced_astar(instance.clone()).unwrap();
+            let elapsed = started.elapsed();
+            assert_eq!(result.cost, optimal_cost);
+            expansions = result.iterations;
+            elapsed
+        })
+        .min()
+        .unwrap();
+
+    (elapsed, expansions)
+}
+
+// Timing of traced_astar on every fixture, only meaningful in release builds, so it only runs on
+// request: `cargo test --release --test fixtures_bench -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn test_traced_astar_on_the_fixtures() {
+    for fixture in fixtures::all() {
+        let (elapsed, expansions) = match &fixture.instance {
+            Instance::SlidingBlocks(state) => fastest(state, fixture.optimal_cost),
+            Instance::Fifteen(state) => fastest(state, fixture.optimal_cost),
+            Instance::Maze(state) => fastest(state, fixture.optimal_cost),
+            Instance::Graph(state) => fastest(state, fixture.optimal_cost)
+        };
+        println!("{:<16} cost {:>3}, {:>7} expansions in {:?}", fixture.name, fixture.optimal_cost, expansions, elapsed);
+    }
+}
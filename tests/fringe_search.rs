@@ -0,0 +1,39 @@
+use astar_helper::{astar_state::AStarState, fixtures::{maze_large, maze_small}, grid::{map::GridMap, region::GoalRegion, state::GridState}, traced::{fringe::fringe_search, replay::replay, traced_astar::traced_astar}};
+
+#[test]
+fn test_mazes_against_astar() {
+    for (start, optimal) in [maze_small(), maze_large()] {
+        let astar = traced_astar(start.clone()).unwrap();
+        let fringe = fringe_search(start.clone()).unwrap();
+        assert_eq!((fringe.cost, astar.cost), (optimal, optimal));
+        assert_eq!(replay(&start, fringe.path.to_vec()).unwrap().g(), optimal);
+
+        // Grids have few distinct f values, so the thresholds admit about what A* expands.
+        assert!(fringe.iterations <= astar.iterations, "fringe {} vs A* {}", fringe.iterations, astar.iterations);
+    }
+}
+
+#[test]
+fn test_open_grid_with_a_wall() {
+    let mut map = GridMap::new(80, 80);
+    for x in 0..80 {
+        for y in 0..80 {
+            if (x * 7 + y * 13) % 11 == 0 && (x, y) != (0, 0) && (x, y) != (79, 79) {
+                map.set_blocked(x, y, true);
+            }
+        }
+    }
+    for x in 10..70 {
+        map.set_blocked(x, 40, true);
+    }
+
+    let region = GoalRegion::Cell(79, 79);
+    let start = GridState::with_region(&map, (0, 0), &region);
+    let astar = traced_astar(start.clone()).unwrap();
+    let fringe = fringe_search(start.clone()).unwrap();
+    assert_eq!(fringe.cost, astar.cost);
+    let replayed = replay(&start, fringe.path.to_vec()).unwrap();
+    assert!(replayed.is_goal());
+    assert_eq!(replayed.g(), astar.cost);
+    assert!(fringe.iterations <= astar.iterations, "fringe {} vs A* {}", fringe.iterations, astar.iterations);
+}
@@ -0,0 +1,15 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, puzzles::sliding_block::{target_reached, TargetDistance}, traced::{to_goal::traced_astar_to_goal, traced_astar::traced_astar}};
+
+// The same puzzle type solved towards two target positions given at run time.
+#[test]
+fn test_layton_puzzle_to_two_targets() {
+    for (target, cost) in [(layton_132().0.target_position(), 64), ((1, 2), 20)] {
+        let result = traced_astar_to_goal(layton_132().0, target, TargetDistance, target_reached).unwrap();
+        assert_eq!(result.final_state.g(), cost);
+        assert_eq!(result.path.to_vec().len(), cost);
+        assert!(target_reached(&result.final_state, &target));
+    }
+
+    // The default goal of the state type agrees with the goal parameter.
+    assert_eq!(traced_astar(layton_132().0).unwrap().final_state.g(), 64);
+}
@@ -0,0 +1,141 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, search_options::SearchOptions, traced::{commutative::CommutativeChanges, ida_star::ida_star_with_commutative_pruning, outcome::SearchOutcome, state::TracedState, traced_astar::traced_astar_with_options}};
+
+// Expansion order fingerprints of fixed instances and configurations. The crate guarantees them
+// across versions: a change that alters the order of expansions on purpose has to update them
+// here, and say so in its changelog.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Blank {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+// 3x3 sliding tile puzzle, 0 is the blank, h is the Manhattan distance of the tiles.
+#[derive(Clone)]
+struct Tiles {
+    tiles: [u8; 9],
+    g: usize
+}
+
+// Moves of the blank never commute, so IDA* with commutative pruning searches every path. It is
+// used here for the SearchOutcome the plain ida_star doesn't return.
+impl CommutativeChanges for Blank {
+    fn independent(_: &Self, _: &Self) -> bool {
+        false
+    }
+
+    fn canonical_before(a: &Self, b: &Self) -> bool {
+        (*a as usize) < (*b as usize)
+    }
+}
+
+const SOLVED: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 0];
+
+// Boards 17, 28 and 14 moves from SOLVED.
+const BOARDS: [[u8; 9]; 3] = [
+    [4, 1, 2, 0, 8, 7, 6, 3, 5],
+    [0, 8, 7, 6, 5, 4, 3, 2, 1],
+    [8, 1, 3, 4, 0, 2, 7, 6, 5]
+];
+
+impl AStarState<[u8; 9]> for Tiles {
+    fn key(&self) -> [u8; 9] {
+        self.tiles
+    }
+
+    fn h(&self) -> usize {
+        self.tiles.iter()
+            .enumerate()
+            .filter(|&(_, &tile)| tile != 0)
+            .map(|(index, &tile)| {
+                let target = tile as usize - 1;
+                (index % 3).abs_diff(target % 3) + (index / 3).abs_diff(target / 3)
+            })
+            .sum()
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.tiles == SOLVED
+    }
+}
+
+impl TracedState<[u8; 9], Blank> for Tiles {
+    fn generate_traced_successors(&self) -> Vec<(Self, Blank)> {
+        let blank = self.tiles.iter().position(|&tile| tile == 0).unwrap();
+        let (x, y) = (blank % 3, blank / 3);
+
+        [(Blank::Up, y > 0, blank.wrapping_sub(3)), (Blank::Down, y < 2, blank + 3), (Blank::Left, x > 0, blank.wrapping_sub(1)), (Blank::Right, x < 2, blank + 1)]
+            .into_iter()
+            .filter(|&(_, possible, _)| possible)
+            .map(|(change, _, target)| {
+                let mut tiles = self.tiles;
+                tiles.swap(blank, target);
+                (Tiles { tiles, g: self.g + 1 }, change)
+            })
+            .collect()
+    }
+}
+
+fn fingerprints(search: impl Fn(Tiles) -> SearchOutcome<Tiles, [u8; 9], Blank>) -> [u64; 3] {
+    BOARDS.map(|tiles| {
+        let outcome = search(Tiles { tiles, g: 0 });
+        assert!(outcome.result.is_some());
+        outcome.trace_fingerprint()
+    })
+}
+
+#[test]
+fn test_golden_default() {
+    let golden = fingerprints(|puzzle| traced_astar_with_options(puzzle, SearchOptions::new()));
+    assert_eq!(golden, [18330752541588360913, 10587397795451220463, 1409589369346559125]);
+}
+
+#[test]
+fn test_golden_tie_break() {
+    let golden = fingerprints(|puzzle| traced_astar_with_options(puzzle, SearchOptions::new().with_tie_break(|change: &Blank| *change as usize)));
+    assert_eq!(golden, [10746520594449817783, 11128049226520002175, 17388243044976091379]);
+}
+
+#[test]
+fn test_golden_greedy() {
+    let golden = fingerprints(|puzzle| traced_astar_with_options(puzzle, SearchOptions::new().with_greedy(true)));
+    assert_eq!(golden, [17015547498673078953, 6021445709431535551, 16689002323236729064]);
+}
+
+#[test]
+fn test_golden_weighted_with_reopening() {
+    let golden = fingerprints(|puzzle| traced_astar_with_options(puzzle, SearchOptions::new().with_reopening(true).with_weight_schedule(1, |_| 2.0)));
+    assert_eq!(golden, [16584974771858725909, 2747684321411346790, 6694077315475067431]);
+}
+
+#[test]
+fn test_golden_ida_star() {
+    let golden = fingerprints(ida_star_with_commutative_pruning);
+    assert_eq!(golden, [1349135990734750225, 11504728000361251379, 14049124902993589573]);
+}
+
+#[test]
+fn test_golden_layton() {
+    let plain = traced_astar_with_options(layton_132().0, SearchOptions::new());
+    let pruned = traced_astar_with_options(layton_132().0, SearchOptions::new().with_commutative_pruning());
+
+    assert_eq!([plain.trace_fingerprint(), pruned.trace_fingerprint()], [7496782218095180330, 13571152833238918157]);
+}
+
+// Runs are reproducible within a process too, whatever the hash seeds of the search's maps.
+#[test]
+fn test_fingerprint_is_reproducible() {
+    let first = fingerprints(|puzzle| traced_astar_with_options(puzzle, SearchOptions::new()));
+    let second = fingerprints(|puzzle| traced_astar_with_options(puzzle, SearchOptions::new()));
+    assert_eq!(first, second);
+}
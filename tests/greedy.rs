@@ -0,0 +1,72 @@
+use std::hash::Hash;
+
+use astar_helper::{astar_state::AStarState, fixtures::{fifteen_puzzle, layton_132, maze_large, weighted_graph}, search_options::SearchOptions, traced::{best_first, replay::replay, state::TracedState, traced_astar::{traced_astar, traced_astar_with_options}}, untraced::{self, untraced_astar::untraced_astar}};
+
+#[test]
+fn test_greedy_traced_search_on_the_puzzle() {
+    let optimal = traced_astar(layton_132().0).unwrap();
+    let greedy = best_first::greedy_best_first(layton_132().0).unwrap();
+
+    assert!(greedy.iterations * 4 < optimal.iterations, "{} vs {}", greedy.iterations, optimal.iterations);
+
+    // Every move is legal from where the previous one left off.
+    let path = greedy.path.to_vec();
+    let replayed = replay(&layton_132().0, path).unwrap();
+    assert!(replayed.is_goal());
+    assert_eq!(replayed.g(), greedy.final_state.g());
+    assert!(greedy.final_state.g() >= optimal.final_state.g());
+}
+
+#[test]
+fn test_greedy_untraced_search_on_the_puzzle() {
+    let optimal = untraced_astar(layton_132().0).unwrap();
+    let greedy = untraced::best_first::greedy_best_first(layton_132().0).unwrap();
+
+    assert!(greedy.iterations * 4 < optimal.iterations, "{} vs {}", greedy.iterations, optimal.iterations);
+    assert!(greedy.final_state.is_goal());
+    assert!(greedy.final_state.g() >= optimal.final_state.g());
+}
+
+// Plain and incumbent-bounded runs of traced_astar_with_options: (cost, expansions, peak open)
+// of each, and the incumbent cost.
+fn with_and_without_incumbent<S, K, C>(start: S) -> ((usize, usize, usize), (usize, usize, usize), usize)
+where
+    S: TracedState<K, C> + Clone,
+    K: Clone + Eq + Hash,
+    C: Clone
+{
+    let plain = traced_astar_with_options(start.clone(), SearchOptions::new());
+    let bounded = traced_astar_with_options(start, SearchOptions::new().with_auto_incumbent(true));
+    assert_eq!(plain.stats.incumbent_expansions, 0);
+    assert!(bounded.stats.incumbent_expansions > 0);
+
+    (
+        (plain.result.unwrap().cost, plain.stats.expansions, plain.stats.peak_open),
+        (bounded.result.unwrap().cost, bounded.stats.expansions, bounded.stats.peak_open),
+        bounded.stats.incumbent_cost.unwrap()
+    )
+}
+
+#[test]
+fn test_auto_incumbent_never_changes_the_result() {
+    // The greedy pass finds the optimum of the shallowest 15-puzzle board. The bound prunes the
+    // open list down to the states of f 9, and the goal, at f 9, is still found.
+    let (plain, bounded, incumbent) = with_and_without_incumbent(fifteen_puzzle(0).0);
+    assert_eq!((plain.0, bounded.0, incumbent), (9, 9, 9));
+    assert!(bounded.1 <= plain.1);
+    assert!(bounded.2 * 2 < plain.2, "{:?} vs {:?}", bounded, plain);
+
+    let (plain, bounded, incumbent) = with_and_without_incumbent(weighted_graph().0);
+    assert_eq!((plain.0, bounded.0), (54, 54));
+    assert!(incumbent > 54);
+    assert!(bounded.2 < plain.2);
+
+    // Greedy paths far over the optimum prune nothing, and cost the greedy pass on top.
+    let (plain, bounded, incumbent) = with_and_without_incumbent(maze_large().0);
+    assert!(incumbent > 134);
+    assert_eq!(plain, bounded);
+    for index in 1..4 {
+        let (plain, bounded, _) = with_and_without_incumbent(fifteen_puzzle(index).0);
+        assert_eq!(plain, bounded);
+    }
+}
@@ -0,0 +1,18 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, traced::{hill_climbing::enforced_hill_climbing, replay::replay, traced_astar::traced_astar}};
+
+// The puzzle's h rarely drops, so the plateaus are large, but the search still ends before A*
+// with a legal, longer path.
+#[test]
+fn test_enforced_hill_climbing_solves_the_puzzle() {
+    let astar = traced_astar(layton_132().0).unwrap();
+    let outcome = enforced_hill_climbing(layton_132().0);
+    let result = outcome.result.unwrap();
+
+    let replayed = replay(&layton_132().0, result.path.to_vec()).unwrap();
+    assert!(replayed.is_goal());
+    assert_eq!(replayed.g(), result.cost);
+    assert!(result.cost >= astar.cost);
+    assert!(result.iterations < astar.iterations, "{} {}", result.iterations, astar.iterations);
+    assert_eq!(outcome.stats.plateaus.iter().sum::<usize>(), result.iterations);
+    assert!(outcome.stats.largest_plateau() > 1000);
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use astar_helper::{astar_state::AStarState, fixtures::maze_small, traced::{lrta::LrtaAgent, replay::replay}};
+
+// Moves of each of `count` trials on the small maze, every agent starting from the table the
+// one before learned.
+fn trial_lengths(lookahead: usize, count: usize) -> Vec<usize> {
+    let (start, _) = maze_small();
+    let mut learned = HashMap::new();
+    let mut lengths = Vec::new();
+
+    for _ in 0..count {
+        let mut agent = LrtaAgent::with_learned(start.clone(), lookahead, learned);
+        let mut path = Vec::new();
+        while let Some(change) = agent.step() {
+            path.push(change);
+        }
+        assert!(agent.is_at_goal());
+        assert_eq!(agent.moves(), path.len());
+        assert!(replay(&start, path.clone()).unwrap().is_goal());
+        lengths.push(path.len());
+        learned = agent.into_learned();
+    }
+    lengths
+}
+
+// LRTA* lengths wander while h is learned, so only the trend is checked: from paths well over the
+// optimum to the optimum on every trial after convergence. A deeper lookahead learns faster.
+#[test]
+fn test_trials_get_shorter_as_h_is_learned() {
+    let (_, optimal_cost) = maze_small();
+    let first_trials: Vec<usize> = [1, 4, 16].into_iter()
+        .map(|lookahead| {
+            let lengths = trial_lengths(lookahead, 30);
+            assert!(lengths[0] > optimal_cost);
+            assert!(lengths[..5].iter().sum::<usize>() > lengths[25..].iter().sum::<usize>());
+            assert!(lengths[15..].iter().all(|&length| length == optimal_cost), "{:?}", lengths);
+            lengths[0]
+        })
+        .collect();
+    assert!(first_trials[2] < first_trials[0]);
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+use astar_helper::{astar_state::AStarState, micro::astar_small, untraced::{state::UntracedState, untraced_astar::untraced_astar}};
+
+const RUNS: usize = 5;
+// Searches per timed run, so that the smallest grids take long enough to measure.
+const REPEATS: usize = 200;
+
+// A cell of an open side x side grid, walking from one corner to the other.
+#[derive(Clone)]
+struct Walker {
+    side: usize,
+    cell: (usize, usize),
+    g: usize
+}
+
+impl Walker {
+    fn neighbours(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (x, y) = self.cell;
+        [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            .into_iter()
+            .filter(|&(x, y)| x < self.side && y < self.side)
+    }
+}
+
+impl AStarState<(usize, usize)> for Walker {
+    fn key(&self) -> (usize, usize) {
+        self.cell
+    }
+
+    // Half the distance, so the search spreads over most of the grid.
+    fn h(&self) -> usize {
+        ((self.side - 1 - self.cell.0) + (self.side - 1 - self.cell.1)) / 2
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.cell == (self.side - 1, self.side - 1)
+    }
+}
+
+impl UntracedState<(usize, usize)> for Walker {
+    fn generate_successors(&self) -> Vec<Self> {
+        self.neighbours().map(|cell| Walker { side: self.side, cell, g: self.g + 1 }).collect()
+    }
+}
+
+fn fastest(side: usize, search: impl Fn(Walker) -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let started = Instant::now();
+            for _ in 0..REPEATS {
+                assert_eq!(search(Walker { side, cell: (0, 0), g: 0 }), 2 * (side - 1));
+            }
+            started.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// Prints the timings next to those of untraced_astar, the hashing search over the same states.
+// Wall-clock times vary too much between machines and runs to assert on, and only mean something
+// in release builds, so it runs on request:
+// `cargo test --release --test micro_bench -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn test_astar_small_against_untraced_astar() {
+    for side in [3, 4, 6, 8, 10, 12, 14, 16, 24] {
+        let small = fastest(side, |start| astar_small(start, side * side).unwrap().unwrap().final_state.g());
+        let hashed = fastest(side, |start| untraced_astar(start).unwrap().final_state.g());
+        println!("{:>3} cells: astar_small {:?}, untraced_astar {:?}", side * side, small / REPEATS as u32, hashed / REPEATS as u32);
+    }
+}
@@ -0,0 +1,84 @@
+use std::{hint::black_box, thread, time::{Duration, Instant}};
+
+use astar_helper::{astar_state::AStarState, parallel::parallel_traced_astar, traced::{state::TracedState, traced_astar::traced_astar}};
+
+const SIZE: usize = 40;
+const RUNS: usize = 3;
+const WORKERS: usize = 4;
+// Rounds of mixing per h, some tens of microseconds in release builds.
+const H_ROUNDS: u64 = 20_000;
+
+// A grid cell whose heuristic is as slow as a pattern database lookup that misses the cache, or
+// a small search of its own.
+#[derive(Clone)]
+struct Walker {
+    cell: (usize, usize),
+    g: usize
+}
+
+impl AStarState<(usize, usize)> for Walker {
+    fn key(&self) -> (usize, usize) {
+        self.cell
+    }
+
+    // Half the distance, so the search spreads out instead of heading straight for the corner.
+    fn h(&self) -> usize {
+        let mut mixed = (self.cell.0 * SIZE + self.cell.1) as u64;
+        for round in 0..black_box(H_ROUNDS) {
+            mixed = (mixed ^ round).wrapping_mul(0x9e3779b97f4a7c15).rotate_left(17);
+        }
+        black_box(mixed);
+        ((SIZE - 1 - self.cell.0) + (SIZE - 1 - self.cell.1)) / 2
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.cell == (SIZE - 1, SIZE - 1)
+    }
+}
+
+impl TracedState<(usize, usize), (usize, usize)> for Walker {
+    fn generate_traced_successors(&self) -> Vec<(Self, (usize, usize))> {
+        let (x, y) = self.cell;
+        [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            .into_iter()
+            .filter(|&(x, y)| x < SIZE && y < SIZE)
+            .map(|cell| (Walker { cell, g: self.g + 1 }, cell))
+            .collect()
+    }
+}
+
+fn fastest(search: impl Fn() -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let started = Instant::now();
+            assert_eq!(search(), 2 * (SIZE - 1));
+            started.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// Timing benchmark, only meaningful in release builds, so it only runs on request:
+// `cargo test --release --features parallel --test parallel_bench -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn test_workers_pay_off_on_a_slow_heuristic() {
+    let start = || Walker { cell: (0, 0), g: 0 };
+    let serial = fastest(|| traced_astar(start()).unwrap().final_state.g());
+    let parallel = fastest(|| parallel_traced_astar(start(), WORKERS).unwrap().final_state.g());
+    let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+    println!("{} runs on {} cores: traced_astar {:?}, parallel_traced_astar on {} workers {:?}", RUNS, cores, serial, WORKERS, parallel);
+
+    // Only comparable with a core for each worker.
+    if cores >= WORKERS {
+        assert!(parallel.mul_f64(1.5) < serial, "parallel {:?} vs serial {:?}", parallel, serial);
+    }
+}
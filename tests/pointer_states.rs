@@ -0,0 +1,25 @@
+use std::{rc::Rc, sync::Arc};
+
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, traced::traced_astar::traced_astar, untraced::untraced_astar::untraced_astar};
+
+#[test]
+fn test_untraced_search_over_rc_states() {
+    let by_value = untraced_astar(layton_132().0).unwrap();
+    let by_rc = untraced_astar(Rc::new(layton_132().0)).unwrap();
+
+    assert_eq!(by_rc.final_state.g(), by_value.final_state.g());
+    assert_eq!(by_rc.final_state.key(), by_value.final_state.key());
+    assert_eq!(by_rc.iterations, by_value.iterations);
+}
+
+#[test]
+fn test_traced_search_over_arc_and_box_states() {
+    let by_value = traced_astar(layton_132().0).unwrap();
+    let by_arc = traced_astar(Arc::new(layton_132().0)).unwrap();
+    let by_box = traced_astar(Box::new(layton_132().0)).unwrap();
+
+    for (g, path) in [(by_arc.final_state.g(), by_arc.path.to_vec()), (by_box.final_state.g(), by_box.path.to_vec())] {
+        assert_eq!(g, by_value.final_state.g());
+        assert_eq!(path, by_value.path.to_vec());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::{HashSet, VecDeque};
+
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, puzzles::sliding_block::{SlidingBlocks, Target}, search_options::SearchOptions, traced::{replay::replay, state::TracedState, traced_astar::{traced_astar, traced_astar_with_options}}, untraced::untraced_astar::untraced_astar};
+
+// Fewest moves that solve puzzle 132 as stated.
+const LAYTON_OPTIMAL_COST: usize = 64;
+
+#[test]
+fn test_layton_puzzle_traced() {
+    let (puzzle, optimal_cost) = layton_132();
+    assert_eq!(optimal_cost, LAYTON_OPTIMAL_COST);
+
+    let result = traced_astar(puzzle.clone()).unwrap();
+    assert_eq!(result.cost, LAYTON_OPTIMAL_COST);
+    assert_eq!(result.path.len(), LAYTON_OPTIMAL_COST);
+
+    let replayed = replay(&puzzle, result.path.to_vec()).unwrap();
+    assert!(replayed.is_goal());
+    assert_eq!(replayed.to_string(), result.final_state.to_string());
+}
+
+#[test]
+fn test_layton_puzzle_untraced() {
+    let result = untraced_astar(layton_132().0).unwrap();
+    assert_eq!(result.final_state.g(), LAYTON_OPTIMAL_COST);
+    assert!(result.final_state.is_goal());
+}
+
+// Moving along the path one move at a time reaches the goal too, as the example does.
+#[test]
+fn test_layton_path_moves_the_board() {
+    let (puzzle, _) = layton_132();
+    let result = traced_astar(puzzle.clone()).unwrap();
+
+    let board = result.path.iter().fold(puzzle, |board: SlidingBlocks, movement| {
+        assert!(board.can_move(movement.piece, movement.direction), "{}", movement);
+        assert_eq!(board.position(movement.piece), movement.from);
+        board.moved(movement.piece, movement.direction)
+    });
+    assert!(board.is_goal());
+    assert_eq!(board.position(1), board.target_position());
+    assert_eq!(board.label(1), 'B');
+}
+
+// States reachable from `board`, counted by a plain breadth-first search.
+fn reachable_states(board: SlidingBlocks) -> usize {
+    let mut seen = HashSet::from([board.key()]);
+    let mut queue = VecDeque::from([board]);
+    while let Some(board) = queue.pop_front() {
+        for (next, _) in board.generate_traced_successors() {
+            if seen.insert(next.key()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+// With a wall piece in one of the two blanks, the 2x2 block can never move. The search has to
+// close every reachable board before giving up.
+#[test]
+fn test_walled_in_puzzle_is_covered_before_giving_up() {
+    let walled = SlidingBlocks::from_ascii("AADHJ\nBBEHW\nBBFI.\nCCGIK", Target { piece: 'B', position: (3, 1) }).unwrap();
+    let reachable = reachable_states(walled.clone());
+
+    let outcome = traced_astar_with_options(walled, SearchOptions::new().with_state_space_bound(reachable));
+    assert!(outcome.result.is_none());
+    assert_eq!(outcome.coverage, Some(1.0));
+    assert_eq!(outcome.stats.expansions, reachable);
+    assert!(outcome.stats.warnings.is_empty());
+}
